@@ -1,21 +1,47 @@
 //! Semantic static and dynamic analysis of the source code.
 
+pub mod bib;
+pub use bib::*;
 pub mod call;
 pub use call::*;
 pub mod color_exprs;
 pub use color_exprs::*;
 pub mod def_use;
 pub use def_use::*;
+pub mod deprecation;
+pub use deprecation::*;
+pub mod docs;
+pub use docs::*;
+pub mod export;
+pub use export::*;
+pub mod font;
+pub use font::*;
 pub mod import;
 pub use import::*;
+pub mod label;
+pub use label::*;
 pub mod linked_def;
 pub use linked_def::*;
+pub mod package;
+pub use package::*;
+pub mod path_completion;
+pub use path_completion::*;
+pub mod preview;
+pub use preview::*;
+pub mod provider;
+pub use provider::*;
 pub mod signature;
 pub use signature::*;
+pub mod snippet;
+pub use snippet::*;
+pub mod spelling;
+pub use spelling::*;
 mod ty;
 pub(crate) use ty::*;
 pub mod track_values;
 pub use track_values::*;
+pub mod warm_up;
+pub use warm_up::*;
 mod prelude;
 
 mod global;
@@ -235,6 +261,22 @@ mod document_tests {
     }
 }
 
+#[cfg(test)]
+mod module_docs_tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("module_docs", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+            let symbols = module_docs(ctx, &source).unwrap();
+            let names = symbols.iter().map(|symbol| symbol.name.as_str());
+            assert_snapshot!(names.collect::<Vec<_>>().join("\n"));
+        });
+    }
+}
+
 #[cfg(test)]
 mod lexical_hierarchy_tests {
     use std::collections::HashMap;
@@ -483,3 +525,33 @@ mod call_info_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod warm_up_tests {
+    use reflexo::path::unix_slash;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("warm_up", &|ctx, _| {
+            assert_eq!(warm_up_files(ctx, 1).len(), 1);
+            assert!(warm_up_library(ctx) > 0);
+
+            let files = warm_up_files(ctx, usize::MAX);
+            let mut result = files
+                .into_iter()
+                .map(|id| {
+                    let path = unix_slash(id.vpath().as_rooted_path());
+                    (path, warm_up_file(ctx, id))
+                })
+                .collect::<Vec<_>>();
+
+            result.sort();
+            // remove /main.typ
+            result.retain(|(p, _)| p != "/main.typ");
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
@@ -0,0 +1,283 @@
+//! Parsing of the bibliography files, i.e. the BibLaTeX `.bib` files and the
+//! Hayagriva `.yml` files, into the keys of their entries and their syntax
+//! errors.
+//!
+//! The parsers are lenient: they only find the entry keys, leaving the fields
+//! to the compiler, and recover from the errors to find the keys after them.
+
+use std::ops::Range;
+use std::path::Path;
+
+/// The kind of a bibliography file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BibKind {
+    /// A BibLaTeX file.
+    BibLaTeX,
+    /// A Hayagriva YAML file.
+    Hayagriva,
+}
+
+impl BibKind {
+    /// Gets the kind of a bibliography file by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "bib" => Some(Self::BibLaTeX),
+            "yml" | "yaml" => Some(Self::Hayagriva),
+            _ => None,
+        }
+    }
+}
+
+/// The key of an entry in a bibliography file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BibKey {
+    /// The name of the key.
+    pub name: String,
+    /// The range of the key in the file.
+    pub range: Range<usize>,
+}
+
+/// A syntax error in a bibliography file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BibError {
+    /// The range of the erroneous text in the file.
+    pub range: Range<usize>,
+    /// The message of the error.
+    pub message: String,
+}
+
+/// The keys and the syntax errors of a bibliography file.
+#[derive(Debug, Clone, Default)]
+pub struct BibFile {
+    /// The keys of the entries, in the order of their definitions.
+    pub keys: Vec<BibKey>,
+    /// The syntax errors.
+    pub errors: Vec<BibError>,
+}
+
+impl BibFile {
+    /// Parses a bibliography file of a kind.
+    pub fn parse(kind: BibKind, text: &str) -> Self {
+        match kind {
+            BibKind::BibLaTeX => parse_biblatex(text),
+            BibKind::Hayagriva => parse_hayagriva(text),
+        }
+    }
+
+    /// Gets the key at a byte offset.
+    pub fn key_at(&self, offset: usize) -> Option<&BibKey> {
+        let mut keys = self.keys.iter();
+        keys.find(|key| key.range.start <= offset && offset <= key.range.end)
+    }
+
+    /// Gets the keys defined more than once, paired with their first
+    /// definitions.
+    pub fn duplicate_keys(&self) -> Vec<(&BibKey, &BibKey)> {
+        let mut duplicates = vec![];
+        for (idx, key) in self.keys.iter().enumerate() {
+            let first = self.keys[..idx].iter().find(|first| first.name == key.name);
+            if let Some(first) = first {
+                duplicates.push((key, first));
+            }
+        }
+        duplicates
+    }
+}
+
+/// Parses a BibLaTeX file, e.g. `@article{key, title = {..}}`.
+///
+/// The text out of the entries is a comment, so an `@` is only the start of
+/// an entry if it is followed by an entry type and a delimiter, and is not in
+/// a word, e.g. an email address, or in a `%` comment.
+fn parse_biblatex(text: &str) -> BibFile {
+    let mut file = BibFile::default();
+    let mut pos = 0;
+    while let Some(at) = text[pos..].find('@').map(|idx| pos + idx) {
+        let line_start = text[..at].rfind('\n').map_or(0, |idx| idx + 1);
+        let in_comment = text[line_start..at].trim_start().starts_with('%');
+        let in_word = text[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric());
+        if in_comment || in_word {
+            pos = at + 1;
+            continue;
+        }
+
+        let ty_start = at + 1;
+        let ty_len = text[ty_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(text.len() - ty_start);
+        let ty_end = ty_start + ty_len;
+        let open = ty_end + (text[ty_end..].len() - text[ty_end..].trim_start().len());
+        let close = match text[open..].chars().next() {
+            Some('{') => Some('}'),
+            Some('(') => Some(')'),
+            _ => None,
+        };
+        let Some(close) = close.filter(|_| ty_len > 0) else {
+            pos = ty_end.max(at + 1);
+            continue;
+        };
+
+        let Some(end) = find_closing(text, open + 1, close) else {
+            file.errors.push(BibError {
+                range: at..open + 1,
+                message: "unclosed entry".to_owned(),
+            });
+            break;
+        };
+        pos = end + 1;
+
+        // The special entries have no keys.
+        let ty = text[ty_start..ty_end].to_ascii_lowercase();
+        if matches!(ty.as_str(), "comment" | "string" | "preamble") {
+            continue;
+        }
+
+        let body = &text[open + 1..end];
+        let key = &body[..body.find(',').unwrap_or(body.len())];
+        let key_start = open + 1 + (key.len() - key.trim_start().len());
+        let key = key.trim();
+        if key.is_empty() {
+            file.errors.push(BibError {
+                range: at..ty_end,
+                message: "missing entry key".to_owned(),
+            });
+            continue;
+        }
+        file.keys.push(BibKey {
+            name: key.to_owned(),
+            range: key_start..key_start + key.len(),
+        });
+    }
+
+    file
+}
+
+/// Finds the closing delimiter of an entry, skipping the nested braces.
+fn find_closing(text: &str, start: usize, close: char) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, c) in text[start..].char_indices() {
+        match c {
+            c if c == close && depth == 0 => return Some(start + idx),
+            '{' => depth += 1,
+            '}' => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses a Hayagriva file, whose entries are keyed at the top level, e.g.
+/// `key:` followed by the indented fields.
+fn parse_hayagriva(text: &str) -> BibFile {
+    let mut file = BibFile::default();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let content = line.trim_start_matches([' ', '\t']);
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('\t') {
+            file.errors.push(BibError {
+                range: start..start + (line.len() - content.len()),
+                message: "tabs are not allowed in indentation".to_owned(),
+            });
+            continue;
+        }
+        // The fields and the document markers are skipped.
+        if line.starts_with(' ') || line == "---" || line == "..." {
+            continue;
+        }
+
+        let Some(colon) = line.find(':') else {
+            file.errors.push(BibError {
+                range: start..start + line.len(),
+                message: "expected an entry key followed by `:`".to_owned(),
+            });
+            continue;
+        };
+        let key = line[..colon].trim_end();
+        let quoted = key.len() >= 2
+            && ((key.starts_with('"') && key.ends_with('"'))
+                || (key.starts_with('\'') && key.ends_with('\'')));
+        let (key, key_start) = if quoted {
+            (&key[1..key.len() - 1], start + 1)
+        } else {
+            (key, start)
+        };
+        if key.is_empty() {
+            file.errors.push(BibError {
+                range: start..start + colon + 1,
+                message: "missing entry key".to_owned(),
+            });
+            continue;
+        }
+        file.keys.push(BibKey {
+            name: key.to_owned(),
+            range: key_start..key_start + key.len(),
+        });
+    }
+
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(file: &BibFile) -> Vec<&str> {
+        file.keys.iter().map(|key| key.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_biblatex() {
+        let text = "% comment by me@example.com, see @article{not, a key}\n\
+                    Contact: @ the desk\n\
+                    @article{ netwok, title = {A {Nested} Title} }\n\
+                    @comment{ not a key, }\n\
+                    @book(knuth, author = \"Knuth\")\n\
+                    @misc{, title = {No key}}\n\
+                    @article{netwok, year = 2020}\n\
+                    @online{unclosed, title = {Oops}";
+        let file = BibFile::parse(BibKind::BibLaTeX, text);
+        assert_eq!(keys(&file), ["netwok", "knuth", "netwok"]);
+        assert_eq!(&text[file.keys[0].range.clone()], "netwok");
+
+        let errors = file.errors.iter().map(|error| error.message.as_str());
+        assert_eq!(
+            errors.collect::<Vec<_>>(),
+            ["missing entry key", "unclosed entry"]
+        );
+
+        let duplicates = file.duplicate_keys();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].1, &file.keys[0]);
+
+        let offset = file.keys[1].range.start + 1;
+        assert_eq!(
+            file.key_at(offset).map(|key| key.name.as_str()),
+            Some("knuth")
+        );
+    }
+
+    #[test]
+    fn test_hayagriva() {
+        let text = "# references\n\
+                    harry:\n    type: Book\n    title: Harry Potter\n\
+                    \"quoted\":\n    type: Web\n\
+                    not a key\n\
+                    harry:\n    type: Book\n";
+        let file = BibFile::parse(BibKind::Hayagriva, text);
+        assert_eq!(keys(&file), ["harry", "quoted", "harry"]);
+        assert_eq!(&text[file.keys[1].range.clone()], "quoted");
+        assert_eq!(file.errors.len(), 1);
+        assert_eq!(&text[file.errors[0].range.clone()], "not a key");
+        assert_eq!(file.duplicate_keys().len(), 1);
+    }
+}
@@ -1,8 +1,9 @@
 //! Analyze color expressions in a source file.
-use std::{ops::Range, str::FromStr};
+use std::{ops::Range, str::FromStr, sync::Arc};
 
 use lsp_types::ColorInformation;
 use typst::{
+    foundations::Value,
     syntax::{
         ast::{self, AstNode},
         LinkedNode, Source, SyntaxKind,
@@ -10,13 +11,52 @@ use typst::{
     visualize::Color,
 };
 
+use super::{resolve_global_value, DefUseInfo};
+use crate::syntax::IdentRef;
 use crate::AnalysisContext;
 
 /// Get color expressions from a source.
 pub fn get_color_exprs(ctx: &mut AnalysisContext, src: &Source) -> Option<Vec<ColorInformation>> {
+    let colors = collect_color_exprs(ctx, src)?;
+    Some(
+        colors
+            .into_iter()
+            .map(|(range, color)| {
+                let [r, g, b, a] = color.to_rgb().to_vec4();
+                ColorInformation {
+                    range: ctx.to_lsp_range(range, src),
+                    color: lsp_types::Color {
+                        red: r,
+                        green: g,
+                        blue: b,
+                        alpha: a,
+                    },
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Find the innermost color expression at the cursor.
+pub fn find_color_expr_at(
+    ctx: &mut AnalysisContext,
+    src: &Source,
+    cursor: usize,
+) -> Option<(Range<usize>, Color)> {
+    collect_color_exprs(ctx, src)?
+        .into_iter()
+        .filter(|(range, _)| range.start <= cursor && cursor <= range.end)
+        .min_by_key(|(range, _)| range.len())
+}
+
+fn collect_color_exprs(
+    ctx: &mut AnalysisContext,
+    src: &Source,
+) -> Option<Vec<(Range<usize>, Color)>> {
+    let def_use = ctx.def_use(src.clone());
     let mut worker = ColorExprWorker {
         ctx,
-        source: src.clone(),
+        def_use,
         colors: vec![],
     };
     let root = LinkedNode::new(src.root());
@@ -26,8 +66,8 @@ pub fn get_color_exprs(ctx: &mut AnalysisContext, src: &Source) -> Option<Vec<Co
 
 struct ColorExprWorker<'a, 'w> {
     ctx: &'a mut AnalysisContext<'w>,
-    source: Source,
-    colors: Vec<ColorInformation>,
+    def_use: Option<Arc<DefUseInfo>>,
+    colors: Vec<(Range<usize>, Color)>,
 }
 
 impl<'a, 'w> ColorExprWorker<'a, 'w> {
@@ -39,6 +79,10 @@ impl<'a, 'w> ColorExprWorker<'a, 'w> {
                     return Some(());
                 }
             }
+            SyntaxKind::Ident => {
+                self.analyze_named_color(node);
+                return Some(());
+            }
             SyntaxKind::Named => {}
             k if k.is_trivia() || k.is_keyword() || k.is_error() => return Some(()),
             _ => {}
@@ -62,7 +106,9 @@ impl<'a, 'w> ColorExprWorker<'a, 'w> {
                         return None;
                     };
                     if ident.get().as_str() != "color" {
-                        return None;
+                        // Methods of named colors, e.g. `red.lighten(20%)`.
+                        self.named_color(&node.find(ident.span())?)?;
+                        return self.analyze_general(&node, call);
                     }
                     callee = ast::Expr::Ident(fa.field());
                     continue 'check_color_fn;
@@ -109,20 +155,44 @@ impl<'a, 'w> ColorExprWorker<'a, 'w> {
         Some(())
     }
 
-    fn push_color(&mut self, range: Range<usize>, color: Color) -> Option<()> {
-        let rng = self.ctx.to_lsp_range(range, &self.source);
-        let [r, g, b, a] = color.to_rgb().to_vec4();
-
-        self.colors.push(ColorInformation {
-            range: rng,
-            color: lsp_types::Color {
-                red: r,
-                green: g,
-                blue: b,
-                alpha: a,
-            },
-        });
+    fn analyze_named_color(&mut self, node: LinkedNode) -> Option<()> {
+        let color = self.named_color(&node)?;
+        self.push_color(node.range(), color)
+    }
+
+    /// Resolves an identifier to a color of the global scope, e.g. `red`,
+    /// unless it is shadowed or is not an expression.
+    fn named_color(&mut self, node: &LinkedNode) -> Option<Color> {
+        if let Some(parent) = node.parent() {
+            let is_name = match parent.kind() {
+                SyntaxKind::Named => node.index() == 0,
+                SyntaxKind::FieldAccess => node.index() > 0,
+                _ => false,
+            };
+            if is_name {
+                return None;
+            }
+        }
+
+        let ident = node.cast::<ast::Ident>()?;
+        let ident_ref = IdentRef {
+            name: ident.get().to_string(),
+            range: node.range(),
+        };
+        let def_use = self.def_use.as_ref()?;
+        let fid = node.span().id()?;
+        if def_use.get_ref(&ident_ref).is_some() || def_use.get_def(fid, &ident_ref).is_some() {
+            return None;
+        }
 
+        match resolve_global_value(self.ctx, node.clone(), false)? {
+            Value::Color(color) => Some(color),
+            _ => None,
+        }
+    }
+
+    fn push_color(&mut self, range: Range<usize>, color: Color) -> Option<()> {
+        self.colors.push((range, color));
         Some(())
     }
 }
@@ -1,7 +1,7 @@
 //! Static analysis for def-use relations.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::{Deref, Range},
     sync::Arc,
 };
@@ -97,6 +97,56 @@ impl DefUseInfo {
     pub fn is_exported(&self, id: DefId) -> bool {
         self.exports_refs.contains(&id)
     }
+
+    /// Get the definition id of a symbol exported by its name.
+    pub fn get_export(&self, name: &str) -> Option<DefId> {
+        self.exports_defs.get(name).copied()
+    }
+
+    /// Get the files imported as modules, e.g. `a.typ` in `import "a.typ"`.
+    pub fn module_imports(&self) -> impl Iterator<Item = TypstFileId> + '_ {
+        self.external_refs
+            .iter()
+            .filter(|((_, name), refs)| name.is_none() && !refs.is_empty())
+            .map(|((fid, _), _)| *fid)
+    }
+}
+
+/// The definitions imported from the source files in the workspace by the
+/// other files.
+#[derive(Default)]
+pub struct ImportIndex {
+    /// The files imported as modules, any exported definition of which can be
+    /// used.
+    modules: HashSet<TypstFileId>,
+    /// The definitions imported by their names.
+    items: HashSet<(TypstFileId, IdentRef)>,
+}
+
+impl ImportIndex {
+    /// Collects the imports of the source files in the workspace.
+    pub fn build(ctx: &mut AnalysisContext) -> Self {
+        let mut index = Self::default();
+        for fid in ctx.source_files().clone() {
+            let Some(def_use) = ctx.source_by_id(fid).ok().and_then(|s| ctx.def_use(s)) else {
+                continue;
+            };
+            let modules = def_use.module_imports().filter(|module| *module != fid);
+            index.modules.extend(modules);
+            let items = def_use
+                .ident_defs
+                .keys()
+                .filter(|(def_fid, _)| *def_fid != fid);
+            index.items.extend(items.cloned());
+        }
+        index
+    }
+
+    /// Checks whether a definition in a file may be used by the other files,
+    /// i.e. the file is imported as a module, or the definition is imported.
+    pub fn is_imported(&self, fid: TypstFileId, ident: &IdentRef) -> bool {
+        self.modules.contains(&fid) || self.items.contains(&(fid, ident.clone()))
+    }
 }
 
 pub(super) fn get_def_use_inner(
@@ -187,7 +237,7 @@ impl<'a, 'w> DefUseCollector<'a, 'w> {
     fn scan(&mut self, e: &'a [LexicalHierarchy]) -> Option<()> {
         for e in e {
             match &e.info.kind {
-                LexicalKind::Heading(..) => unreachable!(),
+                LexicalKind::Heading(..) | LexicalKind::Figure => unreachable!(),
                 LexicalKind::Mod(LexicalModKind::PathInclude) => {}
                 LexicalKind::Var(LexicalVarKind::Label) => {
                     self.insert(Ns::Label, e);
@@ -0,0 +1,138 @@
+//! Items deprecated or renamed across Typst versions.
+
+use ecow::{eco_format, EcoString};
+use typst::foundations::Value;
+use typst::syntax::{FileId as TypstFileId, VirtualPath};
+use typst::World;
+
+/// The version of the bundled compiler, used if it cannot be read from the
+/// standard library.
+const BUNDLED_VERSION: (u32, u32) = (0, 11);
+
+/// An item of the global scope deprecated or renamed in a Typst version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The name of the item.
+    pub name: &'static str,
+    /// The Typst version deprecating the item, as `(major, minor)`.
+    pub since: (u32, u32),
+    /// The replacement of the item.
+    pub replacement: &'static str,
+}
+
+impl Deprecation {
+    /// A short description pointing to the replacement.
+    pub fn detail(&self) -> EcoString {
+        let (major, minor) = self.since;
+        eco_format!(
+            "Deprecated since Typst {major}.{minor}, use `{}` instead.",
+            self.replacement
+        )
+    }
+}
+
+/// The items deprecated or renamed across Typst versions, up to the version of
+/// the bundled compiler.
+pub const DEPRECATIONS: &[Deprecation] = &[
+    Deprecation {
+        name: "style",
+        since: (0, 11),
+        replacement: "context",
+    },
+    Deprecation {
+        name: "locate",
+        since: (0, 11),
+        replacement: "context",
+    },
+];
+
+/// Finds the deprecation of an item of the global scope, if the item is
+/// deprecated in the given Typst version.
+pub fn find_deprecation(name: &str, version: (u32, u32)) -> Option<&'static Deprecation> {
+    DEPRECATIONS
+        .iter()
+        .find(|d| d.name == name && d.since <= version)
+}
+
+/// The `typst.toml` at the root of the workspace.
+pub fn workspace_manifest_id() -> TypstFileId {
+    TypstFileId::new(None, VirtualPath::new("typst.toml"))
+}
+
+/// Detects the Typst version targeted by the workspace, as `(major, minor)`.
+///
+/// It is the compiler version required by the `typst.toml` at the root of
+/// the workspace if any, otherwise the version of the bundled compiler. The
+/// version is cached by [`crate::AnalysisContext::typst_version`].
+pub fn workspace_typst_version(world: &dyn World) -> (u32, u32) {
+    let manifest = world.file(workspace_manifest_id()).ok();
+    let version = manifest.and_then(|bytes| manifest_version(&bytes));
+    version.unwrap_or_else(|| compiler_version(world))
+}
+
+/// Reads the `package.compiler` field of a `typst.toml`.
+fn manifest_version(bytes: &[u8]) -> Option<(u32, u32)> {
+    let manifest: toml::Table = toml::from_str(std::str::from_utf8(bytes).ok()?).ok()?;
+    let compiler = manifest.get("package")?.get("compiler")?.as_str()?;
+    parse_version(compiler)
+}
+
+/// Reads `sys.version` of the standard library.
+fn compiler_version(world: &dyn World) -> (u32, u32) {
+    let version = world
+        .library()
+        .global
+        .scope()
+        .get("sys")
+        .and_then(|sys| match sys {
+            Value::Module(sys) => sys.scope().get("version").cloned(),
+            _ => None,
+        });
+    match version {
+        Some(Value::Version(version)) => match version.values() {
+            [major, minor, ..] => (*major, *minor),
+            _ => BUNDLED_VERSION,
+        },
+        _ => BUNDLED_VERSION,
+    }
+}
+
+/// Parses the major and minor components of a version like `0.11.0`.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut components = version.trim().split('.');
+    let major = components.next()?.parse().ok()?;
+    let minor = components.next().map_or(Some(0), |c| c.parse().ok())?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_deprecation() {
+        assert_eq!(find_deprecation("style", (0, 10)), None);
+        let style = find_deprecation("style", (0, 11)).unwrap();
+        assert_eq!(style.replacement, "context");
+        assert_eq!(
+            style.detail(),
+            "Deprecated since Typst 0.11, use `context` instead."
+        );
+        assert!(find_deprecation("locate", (0, 12)).is_some());
+        assert_eq!(find_deprecation("text", (1, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.11.0"), Some((0, 11)));
+        assert_eq!(parse_version("1"), Some((1, 0)));
+        assert_eq!(parse_version("x.1"), None);
+    }
+
+    #[test]
+    fn test_manifest_version() {
+        let manifest = b"[package]\nname = \"example\"\ncompiler = \"0.10.0\"\n";
+        assert_eq!(manifest_version(manifest), Some((0, 10)));
+        assert_eq!(manifest_version(b"[package]\nname = \"example\"\n"), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Documentation of the symbols exported by a module, e.g. the entry of a
+//! package, combining their doc comments, signatures, and examples.
+
+use typst::diag::StrResult;
+use typst::foundations::Value;
+
+use super::prelude::*;
+use crate::analysis::{analyze_dyn_signature, evaluate_module, DefinitionLink};
+use crate::hover::{def_type, DocTooltip, ParamTooltip};
+
+/// The kind of an exported symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocSymbolKind {
+    /// A function.
+    Function,
+    /// A module.
+    Module,
+    /// Any other value.
+    Variable,
+}
+
+/// The documentation of an exported symbol.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolDocs {
+    /// The name of the symbol.
+    pub name: String,
+    /// The kind of the symbol.
+    pub kind: DocSymbolKind,
+    /// The signature of the symbol, e.g. `f(x, y: 1)` of a function or
+    /// `x: int` of a variable whose type is inferred.
+    pub signature: Option<String>,
+    /// The doc comment of the symbol, or the docs of a builtin function,
+    /// including the examples.
+    pub docs: Option<String>,
+}
+
+/// Collects the documentation of the symbols exported by a module, in the
+/// order of their definitions.
+///
+/// The module is evaluated to find the exported symbols, including the ones
+/// imported from the other modules.
+pub fn module_docs(ctx: &mut AnalysisContext, source: &Source) -> StrResult<Vec<SymbolDocs>> {
+    let module = evaluate_module(ctx.world(), source)?;
+    let def_use = ctx.def_use(source.clone());
+
+    let mut symbols = vec![];
+    for (name, value) in module.scope().iter() {
+        // The symbols starting with `_` are private by convention.
+        if name.starts_with('_') {
+            continue;
+        }
+        let def_at = def_use.as_ref().and_then(|def_use| {
+            let (fid, def) = def_use.get_def_by_id(def_use.get_export(name)?)?;
+            Some((fid, def.range.clone()))
+        });
+        let kind = match value {
+            Value::Func(..) => DocSymbolKind::Function,
+            Value::Module(..) => DocSymbolKind::Module,
+            _ => DocSymbolKind::Variable,
+        };
+        let lnk = DefinitionLink {
+            kind: match kind {
+                DocSymbolKind::Function => LexicalKind::Var(LexicalVarKind::Function),
+                _ => LexicalKind::Var(LexicalVarKind::Variable),
+            },
+            value: Some(value.clone()),
+            name: name.to_string(),
+            name_range: def_at.as_ref().map(|(_, range)| range.clone()),
+            def_at,
+        };
+
+        let signature = match value {
+            Value::Func(func) => {
+                let sig = analyze_dyn_signature(ctx, func.clone());
+                Some(format!("{name}({})", ParamTooltip(Some(sig))))
+            }
+            Value::Module(..) => None,
+            _ => match def_type(ctx, &lnk) {
+                Some(ty) => Some(format!("{name}: {ty}")),
+                None => Some(name.to_string()),
+            },
+        };
+        let docs = DocTooltip::get_inner(ctx, &lnk);
+
+        symbols.push(SymbolDocs {
+            name: name.to_string(),
+            kind,
+            signature,
+            docs,
+        });
+    }
+
+    Ok(symbols)
+}
@@ -0,0 +1,67 @@
+//! Collect the definitions exported by the source files, e.g. to complete them
+//! with an import of their files.
+
+use std::sync::Arc;
+
+use ecow::EcoString;
+use typst::syntax::{ast, package::PackageSpec, FileId as TypstFileId, Source};
+
+/// A definition exported at the top level of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    /// The name of the definition.
+    pub name: EcoString,
+    /// Whether the definition is a function, i.e. bound to a closure.
+    pub is_func: bool,
+}
+
+/// The definitions exported by the source files in the workspace.
+#[derive(Debug, Default)]
+pub struct ExportIndex {
+    /// The exports of the source files, by their files.
+    pub files: Vec<(TypstFileId, Arc<Vec<Export>>)>,
+}
+
+/// The definitions exported by the entrypoints of the packages available
+/// locally, where only the latest version of each package is indexed.
+#[derive(Debug, Default)]
+pub struct PackageExportIndex {
+    /// The exports of the entrypoints, by their packages.
+    pub packages: Vec<(PackageSpec, Arc<Vec<Export>>)>,
+}
+
+/// Collects the definitions at the top level of a source, including the items
+/// imported into it, which are exported along with the definitions.
+pub fn top_level_exports(source: &Source) -> Vec<Export> {
+    let Some(markup) = source.root().cast::<ast::Markup>() else {
+        return vec![];
+    };
+
+    let mut exports = vec![];
+    for expr in markup.exprs() {
+        match expr {
+            ast::Expr::Let(binding) => {
+                let is_func = matches!(binding.kind(), ast::LetBindingKind::Closure(..));
+                for ident in binding.kind().bindings() {
+                    let name = ident.get().clone();
+                    exports.push(Export { name, is_func });
+                }
+            }
+            ast::Expr::Import(import) => {
+                let Some(ast::Imports::Items(items)) = import.imports() else {
+                    continue;
+                };
+                for item in items.iter() {
+                    let name = item.bound_name().get().clone();
+                    exports.push(Export {
+                        name,
+                        is_func: false,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    exports
+}
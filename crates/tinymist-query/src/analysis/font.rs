@@ -0,0 +1,157 @@
+//! Analyze the font families passed to the `text` function, e.g. whether they
+//! are available and which fonts the missing glyphs fall back to.
+
+use std::ops::Range;
+
+use typst::{
+    syntax::{
+        ast::{self, AstNode},
+        LinkedNode, Source, SyntaxKind,
+    },
+    text::{FontBook, FontVariant},
+};
+
+/// Gets the font family of a string in the `font` argument of the `text`
+/// function, e.g. `text(font: "Arial")` or `set text(font: ("Arial",))`.
+pub fn font_family_at(leaf: &LinkedNode) -> Option<String> {
+    let string = leaf.cast::<ast::Str>()?;
+    font_named(leaf)?;
+    Some(string.get().to_string())
+}
+
+/// Collects the font families in the `font` arguments of the `text` function,
+/// with the ranges of their strings including the quotes.
+pub fn collect_font_families(source: &Source) -> Vec<(Range<usize>, String)> {
+    fn collect(node: &LinkedNode, families: &mut Vec<(Range<usize>, String)>) {
+        if node.kind() == SyntaxKind::Str {
+            if let Some(family) = font_family_at(node) {
+                families.push((node.range(), family));
+            }
+            return;
+        }
+        for child in node.children() {
+            collect(&child, families);
+        }
+    }
+
+    let mut families = vec![];
+    collect(&LinkedNode::new(source.root()), &mut families);
+    families
+}
+
+/// Gets the language of the text around a font family string, i.e. the `lang`
+/// argument of the same `text` call or of the first `set text(lang: ..)` rule
+/// in the source.
+pub fn text_lang(source: &Source, leaf: &LinkedNode) -> Option<String> {
+    fn lang_arg(args: ast::Args) -> Option<String> {
+        args.items().find_map(|arg| match arg {
+            ast::Arg::Named(named) if named.name().as_str() == "lang" => match named.expr() {
+                ast::Expr::Str(lang) => Some(lang.get().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    fn set_lang(node: &LinkedNode) -> Option<String> {
+        if let Some(set) = node.cast::<ast::SetRule>() {
+            if is_text_func(set.target()) {
+                if let Some(lang) = lang_arg(set.args()) {
+                    return Some(lang);
+                }
+            }
+        }
+        node.children().find_map(|child| set_lang(&child))
+    }
+
+    let named = font_named(leaf)?;
+    let args = named.parent()?.cast::<ast::Args>()?;
+    lang_arg(args).or_else(|| set_lang(&LinkedNode::new(source.root())))
+}
+
+/// Gets a sample text of a language, whose glyphs are looked up in the fonts.
+pub fn lang_sample(lang: &str) -> &'static str {
+    match lang {
+        "zh" => "中文",
+        "ja" => "日本語",
+        "ko" => "한국어",
+        "ar" | "fa" | "ur" => "العربية",
+        "he" => "עברית",
+        "ru" | "uk" | "bg" | "sr" | "be" => "Русский",
+        "el" => "Ελληνικά",
+        "hi" | "mr" | "ne" => "हिन्दी",
+        "th" => "ไทย",
+        _ => "Abc",
+    }
+}
+
+/// Selects the font that the glyphs of a text missing in a font family fall
+/// back to, or `None` if the family covers all of them.
+pub fn select_fallback(book: &FontBook, family: &str, text: &str) -> Option<usize> {
+    let fonts = book
+        .select_family(&family.to_lowercase())
+        .collect::<Vec<_>>();
+    let covered = |c: char| {
+        let mut infos = fonts.iter().filter_map(|&idx| book.info(idx));
+        infos.any(|info| info.coverage.contains(c as u32))
+    };
+    if !fonts.is_empty() && text.chars().all(covered) {
+        return None;
+    }
+
+    let like = fonts.first().and_then(|&idx| book.info(idx));
+    book.select_fallback(like, FontVariant::default(), text)
+}
+
+/// Gets the `font` argument of the `text` function containing a string.
+fn font_named<'a>(leaf: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut parent = leaf.parent()?.clone();
+    // The fonts may be listed in an array.
+    if parent.kind() == SyntaxKind::Array {
+        parent = parent.parent()?.clone();
+    }
+    let named = parent.cast::<ast::Named>()?;
+    if named.name().as_str() != "font" {
+        return None;
+    }
+
+    let call = parent.parent()?.parent()?;
+    let target = match call.cast::<ast::Expr>()? {
+        ast::Expr::FuncCall(call) => call.callee(),
+        ast::Expr::Set(set) => set.target(),
+        _ => return None,
+    };
+    is_text_func(target).then_some(parent)
+}
+
+/// Checks whether an expression is the `text` function.
+fn is_text_func(expr: ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Ident(ident) if ident.as_str() == "text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_families() {
+        let source = Source::detached(
+            "#set text(lang: \"zh\")\n\
+             #set text(font: (\"Arial\", \"Noto Serif\"))\n\
+             #text(font: \"Inter\", lang: \"ja\")[x]\n\
+             #box(font: \"Ignored\")",
+        );
+        let families = collect_font_families(&source);
+        let names = families.iter().map(|(_, family)| family.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["Arial", "Noto Serif", "Inter"]);
+        assert_eq!(&source.text()[families[0].0.clone()], "\"Arial\"");
+
+        let lang_at = |range: &Range<usize>| {
+            let root = LinkedNode::new(source.root());
+            let leaf = root.leaf_at(range.start + 1)?;
+            text_lang(&source, &leaf)
+        };
+        assert_eq!(lang_at(&families[0].0).as_deref(), Some("zh"));
+        assert_eq!(lang_at(&families[2].0).as_deref(), Some("ja"));
+    }
+}
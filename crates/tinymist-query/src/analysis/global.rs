@@ -11,9 +11,10 @@ use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
 use reflexo::hash::hash128;
 use reflexo::{cow_mut::CowMut, debug_loc::DataSource, ImmutPath};
+use serde::{Deserialize, Serialize};
 use typst::eval::Eval;
 use typst::foundations;
-use typst::syntax::{LinkedNode, SyntaxNode};
+use typst::syntax::{LinkedNode, SyntaxKind, SyntaxNode};
 use typst::{
     diag::{eco_format, FileError, FileResult, PackageError},
     syntax::{package::PackageSpec, Source, Span, VirtualPath},
@@ -23,16 +24,20 @@ use typst::{foundations::Value, syntax::ast, text::Font};
 use typst::{layout::Position, syntax::FileId as TypstFileId};
 
 use super::{
-    literal_type_check, DefUseInfo, FlowType, ImportInfo, PathPreference, Signature,
-    SignatureTarget, TypeCheckInfo,
+    literal_type_check, top_level_exports, workspace_manifest_id, workspace_typst_version,
+    AnalysisProviders, DefUseInfo, Export, ExportIndex, FlowType, ImportIndex, ImportInfo,
+    LabelIndex, PackageExportIndex, PathCompletionConfig, PathPreference, Signature,
+    SignatureTarget, SpellChecker, TypeCheckInfo, UserSnippets,
 };
-use crate::syntax::get_check_target;
+use crate::syntax::{get_check_target, CheckTarget};
 use crate::{
     lsp_to_typst,
     syntax::{
-        construct_module_dependencies, scan_workspace_files, LexicalHierarchy, ModuleDependency,
+        construct_module_dependencies, find_source_by_import_path, scan_workspace_files,
+        scan_workspace_files_with, LexicalHierarchy, ModuleDependency,
     },
-    typst_to_lsp, LspPosition, LspRange, PositionEncoding, TypstRange, VersionedDocument,
+    typst_to_lsp, CompletionFeat, LintConfig, LspPosition, LspRange, PositionEncoding, TypstRange,
+    VersionedDocument,
 };
 
 /// A cache for module-level analysis results of a module.
@@ -90,6 +95,29 @@ pub struct Analysis {
     pub position_encoding: PositionEncoding,
     /// The position encoding for the workspace.
     pub enable_periscope: bool,
+    /// Whether to show rendered previews of equations, symbols, and examples
+    /// in hover.
+    pub enable_hover_preview: bool,
+    /// Whether to show inlay hints for the inferred types of variables.
+    pub enable_type_inlay_hints: bool,
+    /// The minimum number of lines of a block to show an inlay hint after it,
+    /// indicating what it closes. The hints are disabled if `None`.
+    pub closing_hint_min_lines: Option<usize>,
+    /// The user-defined completion snippets.
+    pub snippets: UserSnippets,
+    /// The third-party completion and hover providers.
+    pub providers: AnalysisProviders,
+    /// The user preferences of path completion.
+    pub path_completion: PathCompletionConfig,
+    /// The user configuration of the lints.
+    pub lint: LintConfig,
+    /// The spell checking configuration and dictionaries.
+    pub spell_check: SpellChecker,
+    /// The completion features supported by the client.
+    pub completion_feat: CompletionFeat,
+    /// The cap of the estimated memory usage of the analysis caches in bytes,
+    /// above which the least recently used modules are evicted.
+    pub memory_budget: Option<usize>,
     /// The global caches for analysis.
     pub caches: AnalysisGlobalCaches,
 }
@@ -97,25 +125,16 @@ pub struct Analysis {
 impl Analysis {
     /// Get estimated memory usage of the analysis data.
     pub fn estimated_memory(&self) -> usize {
-        self.caches.modules.capacity() * 32
-            + self
-                .caches
-                .modules
-                .values()
-                .map(|v| {
-                    v.def_use_lexical_hierarchy
-                        .output
-                        .read()
-                        .as_ref()
-                        .map_or(0, |e| e.iter().map(|e| e.estimated_memory()).sum())
-                })
-                .sum::<usize>()
+        self.caches.estimated_memory()
     }
 
     fn gc(&mut self) {
         self.caches
             .signatures
             .retain(|_, (l, _, _)| (self.caches.lifetime - *l) < 30);
+        if let Some(budget) = self.memory_budget {
+            self.caches.evict(budget);
+        }
     }
 }
 
@@ -221,6 +240,24 @@ pub struct ModuleAnalysisGlobalCache {
     import: Arc<ComputingNode<EcoVec<LexicalHierarchy>, Arc<ImportInfo>>>,
     signature_source: Option<Source>,
     signatures: HashMap<usize, Signature>,
+    /// The lifetime of the caches when the module is used at last.
+    last_used: u64,
+}
+
+impl ModuleAnalysisGlobalCache {
+    /// Get estimated memory usage of the module caches.
+    fn estimated_memory(&self) -> usize {
+        let lexical_hierarchy = self.def_use_lexical_hierarchy.output.read();
+        let lexical_hierarchy = lexical_hierarchy.as_ref();
+        let def_use = self.def_use.output.read();
+        let def_use = def_use.as_ref();
+        let source = self.signature_source.as_ref();
+
+        32 + lexical_hierarchy.map_or(0, |e| e.iter().map(|e| e.estimated_memory()).sum())
+            + def_use.map_or(0, |e| e.estimated_memory())
+            + source.map_or(0, |s| s.text().len())
+            + self.signatures.len() * std::mem::size_of::<Signature>()
+    }
 }
 
 impl Default for ModuleAnalysisGlobalCache {
@@ -233,10 +270,23 @@ impl Default for ModuleAnalysisGlobalCache {
 
             signature_source: None,
             signatures: Default::default(),
+            last_used: 0,
         }
     }
 }
 
+/// The sizes of the global caches of analysis.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisCacheStats {
+    /// The number of the modules whose results are cached.
+    pub modules: usize,
+    /// The estimated memory usage of the module caches in bytes.
+    pub modules_memory: usize,
+    /// The number of the cached signatures of the runtime functions.
+    pub signatures: usize,
+}
+
 /// A global (compiler server spanned) cache for all level of analysis results
 /// of a module.
 #[derive(Default)]
@@ -244,9 +294,71 @@ pub struct AnalysisGlobalCaches {
     lifetime: u64,
     modules: HashMap<TypstFileId, ModuleAnalysisGlobalCache>,
     signatures: HashMap<u128, (u64, foundations::Func, Signature)>,
+    label_index: Option<LabelIndexCache>,
+    exports: HashMap<TypstFileId, (u128, Arc<Vec<Export>>)>,
+    /// The exports of the packages available locally, with the hash of the
+    /// packages they are collected from.
+    package_exports: Option<(u128, Arc<PackageExportIndex>)>,
+    /// The Typst version targeted by the workspace, with the hash of the
+    /// `typst.toml` it is detected from.
+    typst_version: Option<(Option<u128>, (u32, u32))>,
+}
+
+/// The label index of the workspace, with the revisions of the source files and
+/// the bibliography files it is built from.
+struct LabelIndexCache {
+    sources: u128,
+    bib_files: u128,
+    index: Arc<LabelIndex>,
 }
 
 impl AnalysisGlobalCaches {
+    /// Get estimated memory usage of the caches.
+    pub fn estimated_memory(&self) -> usize {
+        let modules = self.modules.values().map(|v| v.estimated_memory());
+        let signatures = self.signatures.len() * std::mem::size_of::<Signature>();
+        modules.sum::<usize>() + signatures
+    }
+
+    /// Get the sizes of the caches.
+    pub fn stats(&self) -> AnalysisCacheStats {
+        let modules = self.modules.values().map(|v| v.estimated_memory());
+        AnalysisCacheStats {
+            modules: self.modules.len(),
+            modules_memory: modules.sum(),
+            signatures: self.signatures.len(),
+        }
+    }
+
+    /// Evict the caches of the least recently used modules until the estimated
+    /// memory usage is within the budget in bytes. The signatures of the
+    /// runtime functions are evicted as well if it is still exceeded.
+    pub fn evict(&mut self, budget: usize) {
+        let mut usage = self.estimated_memory();
+        if usage <= budget {
+            return;
+        }
+
+        let mut modules = self
+            .modules
+            .iter()
+            .map(|(fid, v)| (v.last_used, *fid, v.estimated_memory()))
+            .collect::<Vec<_>>();
+        modules.sort_by_key(|(last_used, ..)| *last_used);
+        for (_, fid, memory) in modules {
+            if usage <= budget {
+                return;
+            }
+            self.modules.remove(&fid);
+            self.exports.remove(&fid);
+            usage -= memory;
+        }
+
+        if usage > budget {
+            self.signatures.clear();
+        }
+    }
+
     /// Get the signature of a function.
     pub fn signature(&self, source: Option<Source>, func: &SignatureTarget) -> Option<Signature> {
         match func {
@@ -281,6 +393,7 @@ impl AnalysisGlobalCaches {
         match func {
             SignatureTarget::Syntax(node) => {
                 let cache = self.modules.entry(node.span().id().unwrap()).or_default();
+                cache.last_used = self.lifetime;
                 // todo: check performance on peeking signature source frequently
                 if cache
                     .signature_source
@@ -312,8 +425,12 @@ impl AnalysisGlobalCaches {
 pub struct AnalysisCaches {
     modules: HashMap<TypstFileId, ModuleAnalysisCache>,
     completion_files: OnceCell<Vec<PathBuf>>,
+    configured_completion_files: OnceCell<Vec<PathBuf>>,
     root_files: OnceCell<Vec<TypstFileId>>,
     module_deps: OnceCell<HashMap<TypstFileId, ModuleDependency>>,
+    label_index: OnceCell<Arc<LabelIndex>>,
+    import_index: OnceCell<Arc<ImportIndex>>,
+    export_index: OnceCell<Arc<ExportIndex>>,
 }
 
 /// The resources for analysis.
@@ -327,6 +444,18 @@ pub trait AnalysisResources {
     /// Get all the files in the workspace.
     fn iter_dependencies(&self, f: &mut dyn FnMut(&ImmutPath, std::time::SystemTime));
 
+    /// Get the packages available locally, i.e. without downloading.
+    fn local_packages(&self) -> EcoVec<PackageSpec> {
+        super::local_packages()
+    }
+
+    /// Get the packages in the registry index, which may be downloaded from
+    /// the network. The index should be fetched without blocking, i.e. no
+    /// package is returned until it is fetched.
+    fn registry_packages(&self) -> EcoVec<PackageSpec> {
+        EcoVec::new()
+    }
+
     /// Resolve extra font information.
     fn font_info(&self, _font: Font) -> Option<Arc<DataSource>> {
         None
@@ -341,6 +470,11 @@ pub trait AnalysisResources {
     ) -> Option<String> {
         None
     }
+
+    /// Render the first page of a preview document into a markdown image.
+    fn preview_image(&self, _ctx: &mut AnalysisContext, _doc: VersionedDocument) -> Option<String> {
+        None
+    }
 }
 
 /// The context for analyzers.
@@ -384,35 +518,82 @@ impl<'w> AnalysisContext<'w> {
         self.caches.completion_files.get_or_init(f);
     }
 
+    #[cfg(test)]
+    pub fn test_configured_completion_files(&mut self, f: impl FnOnce() -> Vec<PathBuf>) {
+        self.caches.configured_completion_files.get_or_init(f);
+    }
+
     #[cfg(test)]
     pub fn test_files(&mut self, f: impl FnOnce() -> Vec<TypstFileId>) {
         self.caches.root_files.get_or_init(f);
     }
 
-    /// Get all the source files in the workspace.
+    /// Get all the files in the workspace that may be referenced by paths.
+    pub(crate) fn workspace_files(&self) -> &Vec<PathBuf> {
+        self.caches.completion_files.get_or_init(|| {
+            scan_workspace_files(
+                &self.analysis.root,
+                PathPreference::Special.ext_matcher(),
+                |relative_path| relative_path.to_owned(),
+            )
+        })
+    }
+
+    /// Get all the files in the workspace offered in path completion.
     pub(crate) fn completion_files(&self, pref: &PathPreference) -> impl Iterator<Item = &PathBuf> {
-        let r = pref.ext_matcher();
-        self.caches
-            .completion_files
-            .get_or_init(|| {
-                scan_workspace_files(
+        let config = &self.analysis.path_completion;
+        let files = if config.is_default_scan() {
+            self.workspace_files()
+        } else {
+            self.caches.configured_completion_files.get_or_init(|| {
+                let ext = if config.extensions.is_empty() {
+                    PathPreference::Special.ext_matcher()
+                } else {
+                    PathPreference::None.ext_matcher()
+                };
+                scan_workspace_files_with(
                     &self.analysis.root,
-                    PathPreference::Special.ext_matcher(),
+                    ext,
+                    config.show_hidden,
+                    config.max_entries,
                     |relative_path| relative_path.to_owned(),
                 )
             })
-            .iter()
-            .filter(move |p| {
-                p.extension()
-                    .and_then(|p| p.to_str())
-                    .is_some_and(|e| r.is_match(e))
-            })
+        };
+
+        let filter = self.completion_filter(pref);
+        files.iter().filter(move |p| filter(p.as_path()))
+    }
+
+    /// Get a filter of the files offered in path completion.
+    pub(crate) fn completion_filter(&self, pref: &PathPreference) -> impl Fn(&Path) -> bool + '_ {
+        let r = pref.ext_matcher();
+        let exts = self
+            .analysis
+            .path_completion
+            .extensions_for(pref.config_key());
+        move |p| {
+            let Some(e) = p.extension().and_then(|p| p.to_str()) else {
+                return false;
+            };
+            match exts {
+                Some(exts) => exts.iter().any(|ext| ext.eq_ignore_ascii_case(e)),
+                None => r.is_match(e),
+            }
+        }
     }
 
     /// Get all the source files in the workspace.
     pub fn source_files(&self) -> &Vec<TypstFileId> {
         self.caches.root_files.get_or_init(|| {
-            self.completion_files(&PathPreference::Source)
+            let r = PathPreference::Source.ext_matcher();
+            self.workspace_files()
+                .iter()
+                .filter(|p| {
+                    p.extension()
+                        .and_then(|p| p.to_str())
+                        .is_some_and(|e| r.is_match(e))
+                })
                 .map(|p| TypstFileId::new(None, VirtualPath::new(p.as_path())))
                 .collect()
         })
@@ -430,6 +611,146 @@ impl<'w> AnalysisContext<'w> {
         }
     }
 
+    /// Get the labels, the references, and the bibliography keys of the
+    /// workspace, which are collected once per analysis.
+    pub fn label_index(&mut self) -> Arc<LabelIndex> {
+        if let Some(index) = self.caches.label_index.get() {
+            return index.clone();
+        }
+        let index = self.global_label_index();
+        self.caches.label_index.get_or_init(|| index).clone()
+    }
+
+    /// Get the label index kept across analyses, which is built again only if
+    /// the source files or the bibliography files are changed.
+    fn global_label_index(&mut self) -> Arc<LabelIndex> {
+        let sources = self.source_files().clone();
+        let sources = sources.into_iter().map(|id| {
+            let source = self.source_by_id(id).ok();
+            source.map(|source| hash128(&source))
+        });
+        let sources = hash128(&sources.collect::<Vec<_>>());
+        let bib_files = |ctx: &Self, files: &[TypstFileId]| {
+            let files = files.iter().map(|&id| {
+                let bytes = ctx.world().file(id).ok();
+                bytes.map(|bytes| hash128(&bytes))
+            });
+            hash128(&files.collect::<Vec<_>>())
+        };
+
+        if let Some(cache) = &self.analysis.caches.label_index {
+            if cache.sources == sources
+                && cache.bib_files == bib_files(self, &cache.index.bib_files)
+            {
+                return cache.index.clone();
+            }
+        }
+
+        let index = Arc::new(LabelIndex::build(self));
+        let bib_files = bib_files(self, &index.bib_files);
+        self.analysis.caches.label_index = Some(LabelIndexCache {
+            sources,
+            bib_files,
+            index: index.clone(),
+        });
+        index
+    }
+
+    /// Get the Typst version targeted by the workspace, which is detected
+    /// again only if the `typst.toml` at the root is changed.
+    pub fn typst_version(&mut self) -> (u32, u32) {
+        let manifest = self.world().file(workspace_manifest_id()).ok();
+        let manifest = manifest.map(|bytes| hash128(&bytes));
+        if let Some((cached, version)) = self.analysis.caches.typst_version {
+            if cached == manifest {
+                return version;
+            }
+        }
+
+        let version = workspace_typst_version(self.world());
+        self.analysis.caches.typst_version = Some((manifest, version));
+        version
+    }
+
+    /// Get the imports of the source files in the workspace, which are
+    /// collected once per analysis.
+    pub fn import_index(&mut self) -> Arc<ImportIndex> {
+        if let Some(index) = self.caches.import_index.get() {
+            return index.clone();
+        }
+        let index = Arc::new(ImportIndex::build(self));
+        self.caches.import_index.get_or_init(|| index).clone()
+    }
+
+    /// Get the definitions exported by the source files in the workspace,
+    /// which are collected once per analysis.
+    pub fn export_index(&mut self) -> Arc<ExportIndex> {
+        if let Some(index) = self.caches.export_index.get() {
+            return index.clone();
+        }
+        let mut files = vec![];
+        for id in self.source_files().clone() {
+            let Ok(source) = self.source_by_id(id) else {
+                continue;
+            };
+            files.push((id, self.exports(&source)));
+        }
+        let index = Arc::new(ExportIndex { files });
+        self.caches.export_index.get_or_init(|| index).clone()
+    }
+
+    /// Get the definitions exported by the latest versions of the packages
+    /// available locally, which are collected again only if the packages are
+    /// changed.
+    pub fn package_export_index(&mut self) -> Arc<PackageExportIndex> {
+        let specs = self.resources.local_packages();
+        let revision = hash128(&specs);
+        if let Some((cached, index)) = &self.analysis.caches.package_exports {
+            if *cached == revision {
+                return index.clone();
+            }
+        }
+
+        let mut specs = specs.to_vec();
+        specs.sort_by(|a, b| {
+            (&a.namespace, &a.name)
+                .cmp(&(&b.namespace, &b.name))
+                .then_with(|| b.version.cmp(&a.version))
+        });
+        specs.dedup_by(|a, b| a.namespace == b.namespace && a.name == b.name);
+
+        // The entrypoints of packages are resolved regardless of the current
+        // file.
+        let main = self.world().main();
+        let mut packages = vec![];
+        for spec in specs {
+            let Some(source) = find_source_by_import_path(self.world(), main, &spec.to_string())
+            else {
+                continue;
+            };
+            packages.push((spec, self.exports(&source)));
+        }
+        let index = Arc::new(PackageExportIndex { packages });
+        self.analysis.caches.package_exports = Some((revision, index.clone()));
+        index
+    }
+
+    /// Get the definitions exported by a source file, which are kept across
+    /// analyses until the source is changed.
+    pub fn exports(&mut self, source: &Source) -> Arc<Vec<Export>> {
+        let revision = hash128(source);
+        let exports = &mut self.analysis.caches.exports;
+        if let Some((cached, exports)) = exports.get(&source.id()) {
+            if *cached == revision {
+                return exports.clone();
+            }
+        }
+
+        let res = Arc::new(top_level_exports(source));
+        exports.insert(source.id(), (revision, res.clone()));
+        res
+    }
+
     /// Resolve the real path for a file id.
     pub fn path_for_id(&self, id: TypstFileId) -> Result<PathBuf, FileError> {
         if id.vpath().as_rootless_path() == Path::new("-") {
@@ -515,6 +836,7 @@ impl<'w> AnalysisContext<'w> {
     /// Get the type check information of a source file.
     pub(crate) fn type_check(&mut self, source: Source) -> Option<Arc<TypeCheckInfo>> {
         let fid = source.id();
+        let _scope = crate::profile::scope_with(|| format!("type check {:?}", fid.vpath()));
 
         if let Some(res) = self.caches.modules.entry(fid).or_default().type_check() {
             return Some(res);
@@ -543,6 +865,7 @@ impl<'w> AnalysisContext<'w> {
     /// Get the def-use information of a source file.
     pub fn def_use(&mut self, source: Source) -> Option<Arc<DefUseInfo>> {
         let fid = source.id();
+        let _scope = crate::profile::scope_with(|| format!("def-use {:?}", fid.vpath()));
 
         if let Some(res) = self.caches.modules.entry(fid).or_default().def_use() {
             return Some(res);
@@ -587,7 +910,10 @@ impl<'w> AnalysisContext<'w> {
     }
 
     fn at_module(&mut self, fid: TypstFileId) -> &mut ModuleAnalysisGlobalCache {
-        self.analysis.caches.modules.entry(fid).or_default()
+        let caches = &mut self.analysis.caches;
+        let cache = caches.modules.entry(fid).or_default();
+        cache.last_used = caches.lifetime;
+        cache
     }
 
     pub(crate) fn with_vm<T>(&self, f: impl FnOnce(&mut typst::eval::Vm) -> T) -> T {
@@ -661,7 +987,12 @@ impl<'w> AnalysisContext<'w> {
         let source = self.source_by_id(id).ok()?;
         let ty_chk = self.type_check(source.clone())?;
 
-        let check_target = get_check_target(k.clone())?;
+        // A field of a dict literal is not an expression, but its type can be
+        // projected from the enclosing dict.
+        let check_target = match k.kind() {
+            SyntaxKind::Named => CheckTarget::Normal(k.clone()),
+            _ => get_check_target(k.clone())?,
+        };
 
         literal_type_check(self, &ty_chk, check_target.clone())
             .or_else(|| ty_chk.mapping.get(&k.span()).cloned())
@@ -698,3 +1029,29 @@ impl SearchCtx<'_, '_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let mut caches = AnalysisGlobalCaches::default();
+        let fid = |path: &str| TypstFileId::new(None, VirtualPath::new(path));
+        for (lifetime, path) in [(3, "a.typ"), (1, "b.typ"), (2, "c.typ")] {
+            let cache = caches.modules.entry(fid(path)).or_default();
+            cache.last_used = lifetime;
+        }
+        let module_memory = ModuleAnalysisGlobalCache::default().estimated_memory();
+
+        caches.evict(module_memory * 3);
+        assert_eq!(caches.modules.len(), 3);
+
+        caches.evict(module_memory * 2);
+        assert!(!caches.modules.contains_key(&fid("b.typ")));
+        assert_eq!(caches.modules.len(), 2);
+
+        caches.evict(0);
+        assert!(caches.modules.is_empty());
+    }
+}
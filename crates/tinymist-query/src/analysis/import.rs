@@ -52,7 +52,7 @@ impl<'a, 'w> ImportCollector<'a, 'w> {
     fn scan(&mut self, e: &'a [LexicalHierarchy]) {
         for e in e {
             match &e.info.kind {
-                LexicalKind::Heading(..) => unreachable!(),
+                LexicalKind::Heading(..) | LexicalKind::Figure => unreachable!(),
                 LexicalKind::Var(..) => {}
                 LexicalKind::Block => {
                     if let Some(e) = &e.children {
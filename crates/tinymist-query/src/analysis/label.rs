@@ -0,0 +1,299 @@
+//! Analysis of labels, references, and bibliography keys across the workspace.
+
+use std::collections::HashSet;
+
+use typst::{
+    syntax::{
+        ast::{self, AstNode},
+        LinkedNode, SyntaxKind,
+    },
+    World,
+};
+
+use super::prelude::*;
+use super::{BibFile, BibKind};
+use crate::syntax::resolve_id_by_path;
+
+/// Gets the name of a label or reference node, and the range of the name in
+/// the source, i.e. the text without the `<`, `>`, or `@` markers.
+pub fn label_name_at(node: &LinkedNode) -> Option<(String, Range<usize>)> {
+    match node.kind() {
+        SyntaxKind::Label => {
+            let label = node.cast::<ast::Label>()?;
+            let range = node.range();
+            Some((label.get().to_string(), range.start + 1..range.end - 1))
+        }
+        SyntaxKind::Ref => {
+            let reference = node.cast::<ast::Ref>()?;
+            let marker = node.children().next()?;
+            let range = marker.range();
+            Some((reference.target().to_string(), range.start + 1..range.end))
+        }
+        SyntaxKind::RefMarker => label_name_at(node.parent()?),
+        _ => None,
+    }
+}
+
+/// Finds the names of the labels and the references to them in the
+/// workspace, including the citations by keys, e.g. `<intro>`, `@intro`, and
+/// `#cite(<intro>)`.
+pub fn find_label_occurrences(
+    ctx: &mut AnalysisContext,
+    name: &str,
+) -> Vec<(TypstFileId, Range<usize>)> {
+    let mut occurrences = vec![];
+    for id in ctx.source_files().clone() {
+        let Ok(source) = ctx.source_by_id(id) else {
+            continue;
+        };
+
+        let mut ranges = vec![];
+        collect_label_occurrences(&LinkedNode::new(source.root()), name, &mut ranges);
+        occurrences.extend(ranges.into_iter().map(|range| (id, range)));
+    }
+
+    occurrences
+}
+
+fn collect_label_occurrences(node: &LinkedNode, name: &str, ranges: &mut Vec<Range<usize>>) {
+    if matches!(node.kind(), SyntaxKind::Label | SyntaxKind::Ref) {
+        if let Some((label, range)) = label_name_at(node) {
+            if label == name {
+                ranges.push(range);
+            }
+        }
+        return;
+    }
+
+    for child in node.children() {
+        collect_label_occurrences(&child, name, ranges);
+    }
+}
+
+/// Finds the block equation labelled by a name in the workspace, returning
+/// its markup, e.g. `$ E = m c^2 $` for `<eq:mass>`.
+pub fn find_labelled_equation(ctx: &mut AnalysisContext, name: &str) -> Option<String> {
+    find_label_occurrences(ctx, name)
+        .into_iter()
+        .find_map(|(id, range)| {
+            let source = ctx.source_by_id(id).ok()?;
+            let label = LinkedNode::new(source.root()).leaf_at(range.start)?;
+            if label.kind() != SyntaxKind::Label {
+                return None;
+            }
+            let equation = label.prev_sibling()?;
+            let block = equation.cast::<ast::Equation>()?.block();
+            block.then(|| equation.get().clone().into_text().to_string())
+        })
+}
+
+/// An occurrence of a label in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelOccurrence {
+    /// A label attached to the markup, e.g. `= Intro <intro>`, or created by
+    /// `label("intro")`.
+    Definition(String, Range<usize>),
+    /// A reference to a label, e.g. `@intro`.
+    Reference(String, Range<usize>),
+    /// A label used in code, e.g. `ref(<intro>)` or `show <intro>: ..`.
+    Usage(String),
+    /// A label created from a non-literal string, e.g. `label("sec-" + i)`.
+    Dynamic,
+}
+
+/// Collects the labels defined, referenced, and used in a source file.
+pub fn collect_labels(source: &Source) -> Vec<LabelOccurrence> {
+    fn collect(node: &LinkedNode, labels: &mut Vec<LabelOccurrence>) {
+        match node.kind() {
+            SyntaxKind::Label => {
+                let in_markup = node
+                    .parent()
+                    .is_some_and(|parent| parent.kind() == SyntaxKind::Markup);
+                if let Some((name, range)) = label_name_at(node) {
+                    labels.push(match in_markup {
+                        true => LabelOccurrence::Definition(name, range),
+                        false => LabelOccurrence::Usage(name),
+                    });
+                }
+                return;
+            }
+            SyntaxKind::Ref => {
+                if let Some((name, range)) = label_name_at(node) {
+                    labels.push(LabelOccurrence::Reference(name, range));
+                }
+                return;
+            }
+            SyntaxKind::FuncCall => {
+                if let Some(label) = label_call(node) {
+                    labels.push(label);
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children() {
+            collect(&child, labels);
+        }
+    }
+
+    let mut labels = vec![];
+    collect(&LinkedNode::new(source.root()), &mut labels);
+    labels
+}
+
+/// Gets the label created by a `label(..)` call.
+fn label_call(node: &LinkedNode) -> Option<LabelOccurrence> {
+    let call = node.cast::<ast::FuncCall>()?;
+    if !matches!(call.callee(), ast::Expr::Ident(callee) if callee.get() == "label") {
+        return None;
+    }
+    let arg = call.args().items().find_map(|arg| match arg {
+        ast::Arg::Pos(arg) => Some(arg),
+        _ => None,
+    })?;
+    let ast::Expr::Str(name) = arg else {
+        return Some(LabelOccurrence::Dynamic);
+    };
+    let range = node.find(name.span())?.range();
+    let range = range.start + 1..range.end - 1;
+    Some(LabelOccurrence::Definition(name.get().to_string(), range))
+}
+
+/// The labels, the references, and the bibliography keys of the workspace.
+#[derive(Debug, Default)]
+pub struct LabelIndex {
+    /// The names of the defined labels.
+    pub defined: HashSet<String>,
+    /// The names referenced by `@name` or used in code.
+    pub referenced: HashSet<String>,
+    /// The keys of the bibliography files.
+    pub bib_keys: HashSet<String>,
+    /// The bibliography files loaded by the source files.
+    pub bib_files: Vec<TypstFileId>,
+    /// Whether a label is created from a non-literal string, so that the
+    /// defined labels are not known statically.
+    pub dynamic: bool,
+}
+
+impl LabelIndex {
+    /// Collects the labels of the source files and the keys of the
+    /// bibliography files in the workspace.
+    pub fn build(ctx: &mut AnalysisContext) -> Self {
+        let mut index = Self::default();
+        for id in ctx.source_files().clone() {
+            let Ok(source) = ctx.source_by_id(id) else {
+                continue;
+            };
+            for label in collect_labels(&source) {
+                match label {
+                    LabelOccurrence::Definition(name, _) => {
+                        index.defined.insert(name);
+                    }
+                    LabelOccurrence::Reference(name, _) | LabelOccurrence::Usage(name) => {
+                        index.referenced.insert(name);
+                    }
+                    LabelOccurrence::Dynamic => index.dynamic = true,
+                }
+            }
+        }
+        index.bib_files = find_bibliography_files(ctx);
+        for &id in &index.bib_files {
+            let Some((kind, source)) = bibliography_source(ctx, id) else {
+                continue;
+            };
+            let file = BibFile::parse(kind, source.text());
+            index
+                .bib_keys
+                .extend(file.keys.into_iter().map(|key| key.name));
+        }
+
+        index
+    }
+
+    /// Whether a name is a defined label or a bibliography key.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defined.contains(name) || self.bib_keys.contains(name)
+    }
+}
+
+/// Finds the bibliography files loaded by `#bibliography` in the workspace.
+pub fn find_bibliography_files(ctx: &mut AnalysisContext) -> Vec<TypstFileId> {
+    let mut files = vec![];
+    for id in ctx.source_files().clone() {
+        let Ok(source) = ctx.source_by_id(id) else {
+            continue;
+        };
+        for file in source_bibliography_files(ctx, &source) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    files
+}
+
+/// Finds the bibliography files loaded by `#bibliography` in a source file.
+pub fn source_bibliography_files(ctx: &AnalysisContext, source: &Source) -> Vec<TypstFileId> {
+    let mut paths = vec![];
+    collect_bibliography_paths(&LinkedNode::new(source.root()), &mut paths);
+    let files = paths
+        .iter()
+        .filter_map(|path| resolve_id_by_path(ctx.world(), source.id(), path));
+    files.collect()
+}
+
+fn collect_bibliography_paths(node: &LinkedNode, paths: &mut Vec<String>) {
+    if let Some(call) = node.cast::<ast::FuncCall>() {
+        let is_bibliography = matches!(
+            call.callee(),
+            ast::Expr::Ident(callee) if callee.get() == "bibliography"
+        );
+        let first_arg = call.args().items().find_map(|arg| match arg {
+            ast::Arg::Pos(arg) => Some(arg),
+            _ => None,
+        });
+        match first_arg.filter(|_| is_bibliography) {
+            Some(ast::Expr::Str(path)) => paths.push(path.get().to_string()),
+            Some(ast::Expr::Array(array)) => {
+                paths.extend(array.items().filter_map(|item| match item {
+                    ast::ArrayItem::Pos(ast::Expr::Str(path)) => Some(path.get().to_string()),
+                    _ => None,
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    for child in node.children() {
+        collect_bibliography_paths(&child, paths);
+    }
+}
+
+/// Reads a bibliography file as a source to convert positions, along with its
+/// kind.
+pub fn bibliography_source(ctx: &AnalysisContext, id: TypstFileId) -> Option<(BibKind, Source)> {
+    let kind = BibKind::from_path(id.vpath().as_rootless_path())?;
+    let bytes = ctx.world().file(id).ok()?;
+    let text = std::str::from_utf8(&bytes).ok()?;
+    Some((kind, Source::new(id, text.to_owned())))
+}
+
+/// Finds the definitions of a key in the bibliography files, i.e. the entry
+/// keys of BibLaTeX files and the top-level keys of Hayagriva files.
+pub fn find_bibliography_keys(
+    ctx: &mut AnalysisContext,
+    key: &str,
+) -> Vec<(TypstFileId, Source, Range<usize>)> {
+    let mut keys = vec![];
+    for id in find_bibliography_files(ctx) {
+        let Some((kind, source)) = bibliography_source(ctx, id) else {
+            continue;
+        };
+        let file = BibFile::parse(kind, source.text());
+        let ranges = file.keys.into_iter().filter(|k| k.name == key);
+        keys.extend(ranges.map(|k| (id, source.clone(), k.range)));
+    }
+
+    keys
+}
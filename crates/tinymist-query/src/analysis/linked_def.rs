@@ -12,8 +12,8 @@ use super::prelude::*;
 use crate::{
     prelude::*,
     syntax::{
-        find_source_by_expr, get_deref_target, DerefTarget, IdentRef, LexicalKind, LexicalModKind,
-        LexicalVarKind,
+        find_source_by_expr, get_deref_target, DerefTarget, IdentDef, IdentRef, LexicalKind,
+        LexicalModKind, LexicalVarKind,
     },
 };
 
@@ -76,6 +76,17 @@ pub fn find_definition(
         }
     };
 
+    // The field of an access, e.g. `add` in `pkg.add`, is resolved along with
+    // its target.
+    let use_site = match use_site.parent() {
+        Some(access)
+            if access.kind() == SyntaxKind::FieldAccess && use_site.prev_sibling().is_some() =>
+        {
+            access.clone()
+        }
+        _ => use_site,
+    };
+
     // Lexical reference
     let ident_ref = match use_site.cast::<ast::Expr>()? {
         ast::Expr::Ident(e) => Some(IdentRef {
@@ -89,6 +100,9 @@ pub fn find_definition(
         ast::Expr::FieldAccess(..) => {
             debug!("find field access");
 
+            if let Some(def) = find_field_definition(ctx, &source, &use_site) {
+                return Some(def);
+            }
             None
         }
         _ => {
@@ -121,8 +135,17 @@ pub fn find_definition(
         });
     };
 
+    def_link(ctx, def_fid, def)
+}
+
+/// Links a lexical definition to its possible instance.
+fn def_link(
+    ctx: &mut AnalysisContext<'_>,
+    def_fid: TypstFileId,
+    def: &IdentDef,
+) -> Option<DefinitionLink> {
     match def.kind {
-        LexicalKind::Heading(..) | LexicalKind::Block => unreachable!(),
+        LexicalKind::Heading(..) | LexicalKind::Block | LexicalKind::Figure => unreachable!(),
         LexicalKind::Var(
             LexicalVarKind::Variable
             | LexicalVarKind::ValRef
@@ -162,12 +185,106 @@ pub fn find_definition(
             })
         }
         LexicalKind::Mod(LexicalModKind::Star) => {
-            log::info!("unimplemented star import {:?}", ident_ref);
+            log::info!("unimplemented star import {:?}", def.name);
             None
         }
     }
 }
 
+/// Finds the definition of a field of an imported module, e.g. `add` in
+/// `pkg.add` after `#import "@preview/pkg:0.1.0"`.
+fn find_field_definition(
+    ctx: &mut AnalysisContext<'_>,
+    source: &Source,
+    access_node: &LinkedNode,
+) -> Option<DefinitionLink> {
+    let access = access_node.cast::<ast::FieldAccess>()?;
+    let target = access_node.find(access.target().span())?;
+    let module = find_module_source(ctx, source, &target)?;
+
+    let def_use = ctx.def_use(module)?;
+    let def_id = def_use.get_export(access.field().as_str())?;
+    let (def_fid, def) = def_use.get_def_by_id(def_id)?;
+    def_link(ctx, def_fid, def)
+}
+
+/// Finds the source of the module an expression refers to, e.g. `pkg` after
+/// `#import "@preview/pkg:0.1.0"`, or `pkg.utils` if `utils` is a module
+/// imported by the package.
+fn find_module_source(
+    ctx: &mut AnalysisContext<'_>,
+    source: &Source,
+    node: &LinkedNode,
+) -> Option<Source> {
+    let (def_fid, def) = match node.cast::<ast::Expr>()? {
+        ast::Expr::Ident(ident) => {
+            let def_use = ctx.def_use(source.clone())?;
+            let ident_ref = IdentRef {
+                name: ident.get().to_string(),
+                range: node.range(),
+            };
+            let (def_fid, def) = def_use.get_def_by_id(def_use.get_ref(&ident_ref)?)?;
+            (def_fid, def.clone())
+        }
+        ast::Expr::FieldAccess(access) => {
+            let target = node.find(access.target().span())?;
+            let module = find_module_source(ctx, source, &target)?;
+            let def_use = ctx.def_use(module)?;
+            let def_id = def_use.get_export(access.field().as_str())?;
+            let (def_fid, def) = def_use.get_def_by_id(def_id)?;
+            (def_fid, def.clone())
+        }
+        _ => return None,
+    };
+    if !matches!(
+        def.kind,
+        LexicalKind::Mod(LexicalModKind::PathVar | LexicalModKind::ModuleAlias)
+    ) {
+        return None;
+    }
+
+    // The module is bound by an import, e.g. `#import "@preview/pkg:0.1.0" as pkg`.
+    let def_source = ctx.source_by_id(def_fid).ok()?;
+    let mut import_node = LinkedNode::new(def_source.root()).leaf_at(def.range.start + 1)?;
+    while import_node.kind() != SyntaxKind::ModuleImport {
+        import_node = import_node.parent()?.clone();
+    }
+    let import = import_node.cast::<ast::ModuleImport>()?;
+    find_source_by_expr(ctx.world(), def_fid, import.source())
+}
+
+/// Follows a renamed import item to the definition it renames, e.g. from `g`
+/// in `#import "lib.typ": f as g` to `f` in `lib.typ`, through any number of
+/// re-exports.
+pub fn resolve_import_alias(ctx: &mut AnalysisContext, def: DefinitionLink) -> DefinitionLink {
+    /// Bounds the chain of renames in case of cyclic imports.
+    const MAX_ALIAS_DEPTH: usize = 32;
+
+    let mut def = def;
+    for _ in 0..MAX_ALIAS_DEPTH {
+        match resolve_import_alias_once(ctx, &def) {
+            Some(target) => def = target,
+            None => break,
+        }
+    }
+    def
+}
+
+fn resolve_import_alias_once(
+    ctx: &mut AnalysisContext,
+    def: &DefinitionLink,
+) -> Option<DefinitionLink> {
+    let LexicalKind::Mod(LexicalModKind::Alias { target }) = &def.kind else {
+        return None;
+    };
+    let (fid, _) = def.def_at.as_ref()?;
+
+    let source = ctx.source_by_id(*fid).ok()?;
+    let def_use = ctx.def_use(source)?;
+    let (def_fid, target_def) = def_use.get_def_by_id(def_use.get_ref(target)?)?;
+    def_link(ctx, def_fid, target_def)
+}
+
 /// The target of a dynamic call.
 #[derive(Debug, Clone)]
 pub struct DynCallTarget {
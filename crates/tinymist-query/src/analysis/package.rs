@@ -0,0 +1,239 @@
+//! Discovery and management of the packages available locally.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use ecow::EcoVec;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reflexo::hash::hash128;
+use serde::Serialize;
+use typst::syntax::package::{PackageSpec, PackageVersion};
+
+/// A package available locally.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocalPackage {
+    /// The namespace of the package, e.g. `preview` or `local`.
+    pub namespace: String,
+    /// The name of the package.
+    pub name: String,
+    /// The version of the package.
+    pub version: String,
+    /// The directory of the package.
+    pub path: PathBuf,
+}
+
+/// The directories in which Typst stores packages, i.e. the local packages in
+/// the data directory and the downloaded packages in the cache directory.
+fn package_dirs() -> impl Iterator<Item = PathBuf> {
+    [dirs::data_dir(), dirs::cache_dir()]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("typst").join("packages"))
+}
+
+/// Scans the package directories for the packages available without
+/// downloading, laid out as `{namespace}/{name}/{version}`.
+pub fn local_packages() -> EcoVec<PackageSpec> {
+    /// The packages scanned at last, with the revision of the directories.
+    static LOCAL_PACKAGES: Lazy<Mutex<Option<(u128, EcoVec<PackageSpec>)>>> =
+        Lazy::new(Default::default);
+
+    let dirs = package_dirs().collect::<Vec<_>>();
+    let revision = hash128(&dirs.iter().map(|dir| dir_revision(dir)).collect::<Vec<_>>());
+    let mut cache = LOCAL_PACKAGES.lock();
+    if let Some((cached, packages)) = cache.as_ref() {
+        if *cached == revision {
+            return packages.clone();
+        }
+    }
+
+    let packages = dirs.iter().flat_map(|dir| scan_packages(dir));
+    let packages: EcoVec<_> = packages.map(|(spec, _)| spec).collect();
+    *cache = Some((revision, packages.clone()));
+    packages
+}
+
+/// The modification times of a package directory and its namespace and name
+/// directories, which change when a package or a version is added or removed.
+/// They are cheaper to check than scanning the versions of all the packages.
+fn dir_revision(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let entries = walkdir::WalkDir::new(dir).max_depth(2);
+    let entries = entries.into_iter().filter_map(Result::ok);
+    let dirs = entries.filter(|entry| entry.file_type().is_dir());
+    dirs.map(|entry| {
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+        (entry.into_path(), modified)
+    })
+    .collect()
+}
+
+/// Lists the packages available without downloading with their directories,
+/// sorted by their namespaces, names, and versions. A package in the data
+/// directory is listed before the same one in the cache directory.
+pub fn list_local_packages() -> Vec<LocalPackage> {
+    let packages = package_dirs().flat_map(|dir| scan_packages(&dir));
+    let mut packages = packages
+        .map(|(spec, path)| LocalPackage {
+            namespace: spec.namespace.to_string(),
+            name: spec.name.to_string(),
+            version: spec.version.to_string(),
+            path,
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable, so the order of the package directories is kept.
+    packages.sort_by(|a, b| {
+        let version = |p: &LocalPackage| p.version.parse::<PackageVersion>().ok();
+        (&a.namespace, &a.name, version(a)).cmp(&(&b.namespace, &b.name, version(b)))
+    });
+    packages
+}
+
+/// Gets the directory of a package available without downloading.
+pub fn local_package_dir(spec: &PackageSpec) -> Option<PathBuf> {
+    let subdir = package_subdir(spec);
+    package_dirs()
+        .map(|dir| dir.join(&subdir))
+        .find(|dir| dir.is_dir())
+}
+
+/// Gets the directory in which a downloaded package is cached, which is
+/// removed to download the package again.
+pub fn cached_package_dir(spec: &PackageSpec) -> Option<PathBuf> {
+    let dir = dirs::cache_dir()?.join("typst").join("packages");
+    Some(dir.join(package_subdir(spec)))
+}
+
+/// Creates a package `@local/{name}:{version}` in the data directory, with a
+/// manifest and an entrypoint, returning the path of the entrypoint.
+pub fn create_local_package(name: &str, version: PackageVersion) -> anyhow::Result<PathBuf> {
+    let dir = dirs::data_dir().ok_or_else(|| anyhow::anyhow!("no data directory"))?;
+    let dir = dir.join("typst").join("packages").join("local");
+    scaffold_package(&dir.join(name).join(version.to_string()), name, version)
+}
+
+/// Writes the manifest and the entrypoint of a new package into a directory,
+/// which must not exist yet.
+fn scaffold_package(dir: &Path, name: &str, version: PackageVersion) -> anyhow::Result<PathBuf> {
+    if !typst::syntax::is_ident(name) {
+        anyhow::bail!("`{name}` is not a valid package name");
+    }
+    if dir.exists() {
+        anyhow::bail!("the package already exists in {}", dir.display());
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let manifest = format!(
+        "[package]\nname = \"{name}\"\nversion = \"{version}\"\nentrypoint = \"lib.typ\"\n"
+    );
+    std::fs::write(dir.join("typst.toml"), manifest)?;
+    let entrypoint = dir.join("lib.typ");
+    std::fs::write(&entrypoint, "")?;
+    Ok(entrypoint)
+}
+
+/// Scans a package directory for the packages laid out as
+/// `{namespace}/{name}/{version}`.
+fn scan_packages(dir: &Path) -> Vec<(PackageSpec, PathBuf)> {
+    let mut packages = vec![];
+    let entries = walkdir::WalkDir::new(dir).min_depth(3).max_depth(3);
+    for entry in entries.into_iter().filter_map(Result::ok) {
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let Ok(path) = entry.path().strip_prefix(dir) else {
+            continue;
+        };
+        let mut parts = path.iter().filter_map(|p| p.to_str());
+        let (Some(namespace), Some(name), Some(version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+
+        if let Ok(spec) = format!("@{namespace}/{name}:{version}").parse() {
+            packages.push((spec, entry.into_path()));
+        }
+    }
+
+    packages
+}
+
+/// The subdirectory of a package in a package directory.
+fn package_subdir(spec: &PackageSpec) -> PathBuf {
+    let version = spec.version.to_string();
+    [
+        spec.namespace.as_str(),
+        spec.name.as_str(),
+        version.as_str(),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Finds the latest version of a package compatible with its version in a
+/// list of packages, if it is newer. The versions are compatible if their
+/// major versions are the same, or their minor versions are the same as well
+/// for the `0.x` versions.
+pub fn newer_compatible_version<'a>(
+    spec: &PackageSpec,
+    packages: impl IntoIterator<Item = &'a PackageSpec>,
+) -> Option<PackageVersion> {
+    let current = spec.version;
+    packages
+        .into_iter()
+        .filter(|p| p.namespace == spec.namespace && p.name == spec.name)
+        .map(|p| p.version)
+        .filter(|v| v.major == current.major && (v.major != 0 || v.minor == current.minor))
+        .filter(|v| *v > current)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_package() {
+        let dir = std::env::temp_dir().join(format!("tinymist-packages-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let version = "0.1.0".parse().unwrap();
+
+        let entry = scaffold_package(&dir.join("local/example/0.1.0"), "example", version);
+        assert_eq!(entry.unwrap(), dir.join("local/example/0.1.0/lib.typ"));
+        let manifest = std::fs::read_to_string(dir.join("local/example/0.1.0/typst.toml"));
+        let manifest: toml::Table = toml::from_str(&manifest.unwrap()).unwrap();
+        assert_eq!(manifest["package"]["name"].as_str(), Some("example"));
+        assert_eq!(manifest["package"]["entrypoint"].as_str(), Some("lib.typ"));
+
+        let exists = scaffold_package(&dir.join("local/example/0.1.0"), "example", version);
+        assert!(exists.is_err());
+        let invalid = scaffold_package(&dir.join("local/a b/0.1.0"), "a b", version);
+        assert!(invalid.is_err());
+
+        let packages = scan_packages(&dir);
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].0.to_string(), "@local/example:0.1.0");
+        assert_eq!(packages[0].1, dir.join("local/example/0.1.0"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_newer_compatible_version() {
+        let packages = ["0.1.0", "0.1.2", "0.2.0", "1.0.0", "1.3.1", "2.0.0"].map(|v| {
+            format!("@preview/example:{v}")
+                .parse::<PackageSpec>()
+                .unwrap()
+        });
+        let newer = |version: &str| {
+            let spec = format!("@preview/example:{version}").parse().unwrap();
+            newer_compatible_version(&spec, &packages).map(|v| v.to_string())
+        };
+
+        assert_eq!(newer("0.1.0").as_deref(), Some("0.1.2"));
+        assert_eq!(newer("0.1.2"), None);
+        assert_eq!(newer("1.0.0").as_deref(), Some("1.3.1"));
+        assert_eq!(newer("2.0.0"), None);
+    }
+}
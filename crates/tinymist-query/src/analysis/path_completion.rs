@@ -0,0 +1,51 @@
+//! User preferences of path completion.
+
+use std::collections::HashMap;
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+
+/// The user preferences of path completion.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathCompletionConfig {
+    /// The extensions offered in a context, replacing the default ones.
+    ///
+    /// The contexts are `source`, `image`, `csv`, `json`, `yaml`, `xml`,
+    /// `toml`, `bibliography`, `rawTheme`, `rawSyntax`, and `any` for the
+    /// paths accepting any file. Extensions are given without the leading dot,
+    /// e.g. `["png", "gif"]`.
+    #[serde(default)]
+    pub extensions: HashMap<EcoString, Vec<EcoString>>,
+    /// Whether hidden files and folders, i.e. those starting with a dot, are
+    /// offered.
+    #[serde(default)]
+    pub show_hidden: bool,
+    /// The maximum number of files scanned in the workspace. Scanning stops at
+    /// the limit, visiting the files in the order of their names.
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+impl PathCompletionConfig {
+    /// Whether the workspace is scanned as for the other analyses, so that the
+    /// scanned files can be shared.
+    pub fn is_default_scan(&self) -> bool {
+        self.extensions.is_empty() && !self.show_hidden && self.max_entries.is_none()
+    }
+
+    /// The extensions offered in the given context, if configured.
+    pub fn extensions_for(&self, context: &str) -> Option<&[EcoString]> {
+        self.extensions.get(context).map(Vec::as_slice)
+    }
+
+    /// Normalizes the configured extensions, e.g. `.PNG` to `png`.
+    pub fn normalized(mut self) -> Self {
+        for exts in self.extensions.values_mut() {
+            for ext in exts.iter_mut() {
+                *ext = ext.trim_start_matches('.').to_lowercase().into();
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,132 @@
+//! Previews of code snippets, rendered in hovers.
+//!
+//! A snippet is compiled as the main file of a world sharing the library,
+//! fonts, and files of the workspace, so that it can import the files of the
+//! workspace.
+
+use std::sync::Arc;
+
+use comemo::Prehashed;
+use typst::diag::{FileResult, SourceResult};
+use typst::eval::Tracer;
+use typst::foundations::{Bytes, Datetime};
+use typst::model::Document;
+use typst::syntax::{FileId as TypstFileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::{Library, World};
+
+/// The path of the main file of a preview.
+const PREVIEW_PATH: &str = "/__tinymist_preview__.typ";
+
+/// The preamble of a preview, fitting the page to the snippet.
+const PREVIEW_PREAMBLE: &str = "#set page(width: auto, height: auto, margin: 0.5em)\n";
+
+/// A world compiling a snippet instead of the main file.
+struct PreviewWorld<'a> {
+    base: &'a dyn World,
+    main: Source,
+}
+
+impl World for PreviewWorld<'_> {
+    fn library(&self) -> &Prehashed<Library> {
+        self.base.library()
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        self.base.book()
+    }
+
+    fn main(&self) -> Source {
+        self.main.clone()
+    }
+
+    fn source(&self, id: TypstFileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            return Ok(self.main.clone());
+        }
+        self.base.source(id)
+    }
+
+    fn file(&self, id: TypstFileId) -> FileResult<Bytes> {
+        self.base.file(id)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.base.font(index)
+    }
+
+    /// The date of the base world, which is fixed by the creation timestamp
+    /// of the document if it is set.
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.base.today(offset)
+    }
+}
+
+/// Compiles a snippet of markup into a document fitting its content.
+pub fn compile_preview(world: &dyn World, code: &str) -> Option<Arc<Document>> {
+    let id = TypstFileId::new(None, VirtualPath::new(PREVIEW_PATH));
+    let main = Source::new(id, format!("{PREVIEW_PREAMBLE}{code}"));
+
+    match compile_main(world, main) {
+        Ok(doc) => Some(Arc::new(doc)),
+        Err(err) => {
+            log::debug!("failed to compile preview {code:?}: {err:?}");
+            None
+        }
+    }
+}
+
+/// Compiles a source as the main file instead of the one of the world, e.g. a
+/// test file of the workspace.
+pub fn compile_main(world: &dyn World, main: Source) -> SourceResult<Document> {
+    let world = PreviewWorld { base: world, main };
+    let mut tracer = Tracer::new();
+    typst::compile(&world, &mut tracer)
+}
+
+/// Extracts the code of the first example in docs, i.e. a code block fenced
+/// with ` ```example `.
+///
+/// As in the docs of Typst, lines starting with `>>>` are only compiled and
+/// lines starting with `<<<` are only shown, so the former are kept and the
+/// latter are dropped.
+pub fn doc_example(docs: &str) -> Option<String> {
+    let mut lines = docs.lines();
+    lines.find(|line| line.trim() == "```example")?;
+
+    let mut code = String::new();
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            return Some(code);
+        }
+        if trimmed.starts_with("<<<") {
+            continue;
+        }
+        let line = match trimmed.strip_prefix(">>>") {
+            Some(line) => line.strip_prefix(' ').unwrap_or(line),
+            None => line,
+        };
+        code.push_str(line);
+        code.push('\n');
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_example() {
+        let docs =
+            "Adds numbers.\n\n```example\n>>> #set text(red)\n<<< #add(1, 2)\n#add(1, 2)\n```\n";
+        assert_eq!(
+            doc_example(docs).as_deref(),
+            Some("#set text(red)\n#add(1, 2)\n")
+        );
+        assert_eq!(doc_example("```typ\n#add(1, 2)\n```"), None);
+        assert_eq!(doc_example("```example\n#add(1, 2)"), None);
+    }
+}
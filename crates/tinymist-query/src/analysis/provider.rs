@@ -0,0 +1,246 @@
+//! Third-party completion and hover providers.
+//!
+//! A provider only sees a [`ProviderQuery`], a serializable snapshot of the
+//! cursor context, rather than the whole [`AnalysisContext`]. This keeps the
+//! interface stable and allows providers to live out of process, e.g. in a
+//! WebAssembly plugin loaded with the `wasm-plugin` feature.
+//!
+//! [`AnalysisContext`]: super::AnalysisContext
+
+use std::sync::Arc;
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+
+/// The syntax mode at the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderMode {
+    /// The cursor is in markup.
+    Markup,
+    /// The cursor is in code.
+    Code,
+    /// The cursor is in math.
+    Math,
+}
+
+/// The restricted view of the analysis passed to providers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderQuery {
+    /// The path of the file relative to the workspace root.
+    pub path: String,
+    /// The byte offset of the cursor.
+    pub cursor: usize,
+    /// The syntax mode at the cursor.
+    pub mode: ProviderMode,
+    /// The kind of the syntax leaf at the cursor, e.g. `ident`.
+    pub leaf_kind: EcoString,
+    /// The text of the syntax leaf at the cursor.
+    pub leaf_text: EcoString,
+    /// The text of the line before the cursor.
+    pub line_before: EcoString,
+    /// The names of the modules imported by the file, e.g.
+    /// `@preview/cetz:0.2.2`.
+    pub imports: Vec<EcoString>,
+}
+
+impl ProviderQuery {
+    /// Creates a query for the cursor in the source.
+    pub fn new(source: &Source, path: String, cursor: usize) -> Option<Self> {
+        let root = LinkedNode::new(source.root());
+        let leaf = root.leaf_at(cursor)?;
+
+        let mut mode = ProviderMode::Markup;
+        let mut node = leaf.parent();
+        while let Some(n) = node {
+            match n.kind() {
+                SyntaxKind::Equation => mode = ProviderMode::Math,
+                SyntaxKind::Markup => mode = ProviderMode::Markup,
+                SyntaxKind::Code | SyntaxKind::CodeBlock | SyntaxKind::Args => {
+                    mode = ProviderMode::Code
+                }
+                _ => {
+                    node = n.parent();
+                    continue;
+                }
+            }
+            break;
+        }
+
+        let text = source.text();
+        let line_start = text[..cursor].rfind('\n').map_or(0, |i| i + 1);
+
+        let imports = source
+            .root()
+            .children()
+            .filter(|node| node.kind() == SyntaxKind::ModuleImport)
+            .filter_map(|node| node.children().find(|n| n.kind() == SyntaxKind::Str))
+            .map(|node| node.text().trim_matches('"').into())
+            .collect();
+
+        Some(Self {
+            path,
+            cursor,
+            mode,
+            leaf_kind: leaf.kind().name().into(),
+            leaf_text: leaf.text().clone(),
+            line_before: text[line_start..cursor].into(),
+            imports,
+        })
+    }
+}
+
+/// A completion offered by a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCompletion {
+    /// The label shown in the completion list.
+    pub label: EcoString,
+    /// The text to insert, in the snippet syntax. Defaults to the label.
+    #[serde(default)]
+    pub apply: Option<EcoString>,
+    /// A short description.
+    #[serde(default)]
+    pub detail: Option<EcoString>,
+}
+
+/// A third-party provider of completions and hovers.
+pub trait AnalysisProvider: Send + Sync {
+    /// The name of the provider, used in logs.
+    fn name(&self) -> &str;
+
+    /// Computes additional completions at the cursor.
+    fn complete(&self, _query: &ProviderQuery) -> Vec<ProviderCompletion> {
+        vec![]
+    }
+
+    /// Computes additional hover contents at the cursor, in markdown.
+    fn hover(&self, _query: &ProviderQuery) -> Option<String> {
+        None
+    }
+}
+
+/// The registered providers.
+#[derive(Default, Clone)]
+pub struct AnalysisProviders {
+    providers: Vec<Arc<dyn AnalysisProvider>>,
+}
+
+impl std::fmt::Debug for AnalysisProviders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.providers.iter().map(|p| p.name()))
+            .finish()
+    }
+}
+
+impl AnalysisProviders {
+    /// Registers a provider.
+    pub fn register(&mut self, provider: Arc<dyn AnalysisProvider>) {
+        self.providers.push(provider);
+    }
+
+    /// Whether no provider is registered.
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Collects the completions of all providers.
+    pub fn complete(&self, query: &ProviderQuery) -> Vec<ProviderCompletion> {
+        self.providers
+            .iter()
+            .flat_map(|p| p.complete(query))
+            .collect()
+    }
+
+    /// Collects the hover contents of all providers.
+    pub fn hover(&self, query: &ProviderQuery) -> Vec<String> {
+        self.providers
+            .iter()
+            .filter_map(|p| p.hover(query))
+            .collect()
+    }
+}
+
+#[cfg(feature = "wasm-plugin")]
+pub use wasm::WasmProvider;
+
+#[cfg(feature = "wasm-plugin")]
+mod wasm {
+    use std::path::Path;
+
+    use typst::foundations::{Bytes, Plugin};
+
+    use super::*;
+
+    /// A provider implemented by a WebAssembly plugin.
+    ///
+    /// The plugin follows the protocol of Typst plugins, and may export the
+    /// functions `complete` and `hover`. Both take the JSON-encoded
+    /// [`ProviderQuery`] as the only argument. `complete` returns a
+    /// JSON-encoded list of [`ProviderCompletion`], and `hover` returns the
+    /// markdown contents, or nothing.
+    pub struct WasmProvider {
+        name: String,
+        plugin: Plugin,
+        has_complete: bool,
+        has_hover: bool,
+    }
+
+    impl WasmProvider {
+        /// Loads a plugin from a WebAssembly file.
+        pub fn load(path: &Path) -> anyhow::Result<Self> {
+            let bytes = std::fs::read(path)?;
+            let plugin = Plugin::new(Bytes::from(bytes)).map_err(|e| anyhow::anyhow!("{e}"))?;
+            let has = |name: &str| plugin.iter().any(|f| f == name);
+
+            Ok(Self {
+                name: path.display().to_string(),
+                has_complete: has("complete"),
+                has_hover: has("hover"),
+                plugin,
+            })
+        }
+
+        fn call(&self, name: &str, query: &ProviderQuery) -> Option<Bytes> {
+            let query = serde_json::to_vec(query).ok()?;
+            match self.plugin.call(name, vec![Bytes::from(query)]) {
+                Ok(res) => Some(res),
+                Err(err) => {
+                    log::error!("plugin {}: failed to call {name}: {err}", self.name);
+                    None
+                }
+            }
+        }
+    }
+
+    impl AnalysisProvider for WasmProvider {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn complete(&self, query: &ProviderQuery) -> Vec<ProviderCompletion> {
+            if !self.has_complete {
+                return vec![];
+            }
+            let Some(res) = self.call("complete", query) else {
+                return vec![];
+            };
+            serde_json::from_slice(res.as_slice()).unwrap_or_else(|err| {
+                log::error!("plugin {}: invalid completions: {err}", self.name);
+                vec![]
+            })
+        }
+
+        fn hover(&self, query: &ProviderQuery) -> Option<String> {
+            if !self.has_hover {
+                return None;
+            }
+            let res = self.call("hover", query)?;
+            let res = std::str::from_utf8(res.as_slice()).ok()?;
+            (!res.is_empty()).then(|| res.to_owned())
+        }
+    }
+}
@@ -16,10 +16,12 @@ use typst::{
 };
 
 use crate::analysis::resolve_callee;
-use crate::syntax::{get_def_target, get_deref_target, DefTarget};
+use crate::syntax::{get_def_target, get_deref_target, get_non_strict_def_target, DefTarget};
 use crate::AnalysisContext;
 
-use super::{find_definition, DefinitionLink, FlowType, FlowVar, LexicalKind, LexicalVarKind};
+use super::{
+    find_definition, DefinitionLink, FlowSignature, FlowType, FlowVar, LexicalKind, LexicalVarKind,
+};
 
 // pub fn analyze_signature
 
@@ -159,6 +161,7 @@ pub enum SignatureTarget<'a> {
 }
 
 pub(crate) fn analyze_dyn_signature(ctx: &mut AnalysisContext, func: Func) -> Signature {
+    let _scope = crate::profile::scope("dynamic signature");
     ctx.analysis
         .caches
         .compute_signature(None, SignatureTarget::Runtime(func.clone()), || {
@@ -241,6 +244,38 @@ pub(crate) fn analyze_signature(
     })))
 }
 
+/// Analyzes the signatures of a closure inferred by type checking.
+///
+/// A closure bound to a union type results in several signatures.
+pub(crate) fn analyze_type_signatures(
+    ctx: &mut AnalysisContext,
+    func: &Func,
+) -> Option<Vec<FlowSignature>> {
+    use typst::foundations::func::Repr;
+    let mut func = func;
+    while let Repr::With(f) = func.inner() {
+        func = &f.0;
+    }
+
+    let def = func.span();
+    let source = ctx.source_by_id(def.id()?).ok()?;
+    let def = get_non_strict_def_target(source.find(def)?)?;
+    let DefTarget::Let(l) = def else {
+        return None;
+    };
+
+    let lb = l.cast::<ast::LetBinding>()?;
+    let ast::LetBindingKind::Closure(c) = lb.kind() else {
+        return None;
+    };
+
+    let fn_ty = ctx.type_of_span(c.span());
+    let info = ctx.type_check(source.clone())?;
+
+    log::info!("function sig by type checking: {:?}", fn_ty);
+    fn_ty.and_then(|ty| ty.signatures(&info, false))
+}
+
 // fn analyze_static_signature(
 //     ctx: &mut AnalysisContext<'_>,
 //     source: Source,
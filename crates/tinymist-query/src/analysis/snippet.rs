@@ -0,0 +1,149 @@
+//! User-defined completion snippets.
+//!
+//! Snippets come from two sources: the `completionSnippets` configuration
+//! item, and a `.tinymist/snippets.json` file located at the workspace root.
+//! The file is re-read whenever its modification time changes.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+
+/// The relative path of the snippet file in a workspace.
+pub const SNIPPET_FILE: &str = ".tinymist/snippets.json";
+
+/// The syntax mode in which a snippet is offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SnippetScope {
+    /// Offered in markup, inserted after a hash.
+    Markup,
+    /// Offered in code.
+    #[default]
+    Code,
+    /// Offered in math.
+    Math,
+}
+
+/// A user-defined completion snippet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSnippet {
+    /// The label shown in the completion list.
+    pub label: EcoString,
+    /// The snippet body, in the LSP snippet syntax.
+    pub body: EcoString,
+    /// The syntax mode in which the snippet is offered.
+    #[serde(default)]
+    pub scope: SnippetScope,
+    /// The description shown along with the snippet.
+    #[serde(default)]
+    pub description: Option<EcoString>,
+}
+
+/// The content of a snippet file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum SnippetFile {
+    /// A list of snippets.
+    List(Vec<UserSnippet>),
+    /// An object holding a list of snippets.
+    Object { snippets: Vec<UserSnippet> },
+}
+
+impl From<SnippetFile> for Vec<UserSnippet> {
+    fn from(file: SnippetFile) -> Self {
+        match file {
+            SnippetFile::List(snippets) | SnippetFile::Object { snippets } => snippets,
+        }
+    }
+}
+
+/// The snippets loaded from a workspace.
+#[derive(Debug, Default, Clone)]
+pub struct UserSnippets {
+    /// The snippets declared in the configuration.
+    configured: Arc<Vec<UserSnippet>>,
+    /// The snippet file and its last seen modification time.
+    file: Option<(PathBuf, Option<SystemTime>)>,
+    /// The snippets declared in the snippet file.
+    from_file: Arc<Vec<UserSnippet>>,
+}
+
+impl UserSnippets {
+    /// Sets the snippets declared in the configuration.
+    pub fn set_configured(&mut self, snippets: Vec<UserSnippet>) {
+        self.configured = Arc::new(snippets);
+    }
+
+    /// Reloads the snippet file under the workspace root if it has changed
+    /// since the last call.
+    pub fn refresh(&mut self, root: &Path) {
+        let path = root.join(SNIPPET_FILE);
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if matches!(&self.file, Some((p, t)) if *p == path && *t == mtime) {
+            return;
+        }
+
+        self.from_file = Arc::new(match mtime {
+            Some(..) => Self::load(&path),
+            None => vec![],
+        });
+        self.file = Some((path, mtime));
+    }
+
+    fn load(path: &Path) -> Vec<UserSnippet> {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::error!("failed to read snippet file {path:?}: {err}");
+                return vec![];
+            }
+        };
+
+        match serde_json::from_slice::<SnippetFile>(&content) {
+            Ok(file) => file.into(),
+            Err(err) => {
+                log::error!("failed to parse snippet file {path:?}: {err}");
+                vec![]
+            }
+        }
+    }
+
+    /// Iterates over the snippets offered in the given scope.
+    pub fn in_scope(&self, scope: SnippetScope) -> impl Iterator<Item = &UserSnippet> {
+        self.configured
+            .iter()
+            .chain(self.from_file.iter())
+            .filter(move |s| s.scope == scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_file_forms() {
+        let list = r##"[{ "label": "fig", "body": "#figure(${body})" }]"##;
+        let object = r#"{
+            "snippets": [{ "label": "fr", "body": "${a}/${b}", "scope": "math" }]
+        }"#;
+
+        let list: Vec<UserSnippet> = serde_json::from_str::<SnippetFile>(list).unwrap().into();
+        assert_eq!(list[0].scope, SnippetScope::Code);
+
+        let object: Vec<UserSnippet> = serde_json::from_str::<SnippetFile>(object).unwrap().into();
+        assert_eq!(object[0].scope, SnippetScope::Math);
+
+        let mut snippets = UserSnippets::default();
+        snippets.set_configured(list.into_iter().chain(object).collect());
+        let math = snippets.in_scope(SnippetScope::Math).collect::<Vec<_>>();
+        assert_eq!(math.len(), 1);
+        assert_eq!(math[0].label, "fr");
+    }
+}
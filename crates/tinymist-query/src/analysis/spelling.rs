@@ -0,0 +1,227 @@
+//! Dictionaries for spell checking of prose.
+//!
+//! Dictionaries come from the `spellCheck` configuration item, which maps the
+//! languages to word lists, and a `.tinymist/dictionary.txt` file located at
+//! the workspace root, which holds the words accepted in all languages. The
+//! files are re-read whenever their modification times change.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+
+/// The relative path of the user dictionary in a workspace.
+pub const DICTIONARY_FILE: &str = ".tinymist/dictionary.txt";
+
+/// The user configuration of spell checking.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpellCheckConfig {
+    /// Whether the prose is spell checked.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The language of the prose if it is not set by `set text(lang: ..)`.
+    #[serde(default = "default_language")]
+    pub language: EcoString,
+    /// The paths to the word lists by languages, e.g. `{ "en": "en_US.dic" }`.
+    /// The lists have a word per line. Hunspell dictionaries are accepted,
+    /// ignoring the affix flags.
+    #[serde(default)]
+    pub dictionaries: HashMap<EcoString, PathBuf>,
+    /// The words accepted in all languages.
+    #[serde(default)]
+    pub words: Vec<EcoString>,
+}
+
+fn default_language() -> EcoString {
+    "en".into()
+}
+
+impl Default for SpellCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            language: default_language(),
+            dictionaries: HashMap::new(),
+            words: vec![],
+        }
+    }
+}
+
+/// A list of the correctly spelled words.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Parses a word list. The words are in lower case to be looked up
+    /// regardless of the capitalization at the start of sentences.
+    pub fn parse(text: &str) -> Self {
+        let mut lines = text.lines().peekable();
+        // The first line of a Hunspell dictionary is the number of words.
+        if lines
+            .peek()
+            .is_some_and(|l| l.trim().parse::<usize>().is_ok())
+        {
+            lines.next();
+        }
+
+        let words = lines
+            .map(|line| line.split('/').next().unwrap_or_default().trim())
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect();
+        Self { words }
+    }
+
+    /// Checks whether a word is in the dictionary.
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    /// Suggests the words within two edits of a misspelled word, the closest
+    /// first.
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let lower = word.to_lowercase().chars().collect::<Vec<_>>();
+        let mut candidates = self
+            .words
+            .iter()
+            .filter(|w| w.chars().count().abs_diff(lower.len()) <= 2)
+            .filter_map(|w| {
+                let distance = edit_distance(&lower, &w.chars().collect::<Vec<_>>());
+                (distance <= 2).then_some((distance, w))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(_, w)| {
+                if capitalized {
+                    capitalize(w)
+                } else {
+                    w.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// The Levenshtein distance between two words.
+pub(crate) fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// A dictionary file and its last seen modification time.
+type LoadedDictionary = (PathBuf, Option<SystemTime>, Arc<Dictionary>);
+
+/// The spell checking configuration and the dictionaries loaded for it.
+#[derive(Debug, Default, Clone)]
+pub struct SpellChecker {
+    config: Arc<SpellCheckConfig>,
+    /// The dictionaries by languages.
+    dictionaries: HashMap<EcoString, LoadedDictionary>,
+    /// The user dictionary in the workspace.
+    user_file: Option<LoadedDictionary>,
+    /// The words accepted in all languages.
+    user_words: Arc<Dictionary>,
+}
+
+impl SpellChecker {
+    /// Sets the configuration of spell checking.
+    pub fn set_config(&mut self, config: SpellCheckConfig) {
+        let words = config.words.join("\n");
+        self.user_words = Arc::new(Dictionary::parse(&words));
+        self.config = Arc::new(config);
+    }
+
+    /// Gets the configuration of spell checking.
+    pub fn config(&self) -> &SpellCheckConfig {
+        &self.config
+    }
+
+    /// Reloads the dictionaries if they have changed since the last call.
+    pub fn refresh(&mut self, root: &Path) {
+        self.dictionaries
+            .retain(|lang, _| self.config.dictionaries.contains_key(lang));
+        for (lang, path) in self.config.dictionaries.iter() {
+            let path = root.join(path);
+            let loaded = self.dictionaries.remove(lang);
+            self.dictionaries
+                .insert(lang.clone(), Self::reload(loaded, path));
+        }
+
+        let path = root.join(DICTIONARY_FILE);
+        self.user_file = Some(Self::reload(self.user_file.take(), path));
+    }
+
+    fn reload(loaded: Option<LoadedDictionary>, path: PathBuf) -> LoadedDictionary {
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        match loaded {
+            Some((p, t, dict)) if p == path && t == mtime => (p, t, dict),
+            _ => {
+                let text = match mtime {
+                    Some(..) => std::fs::read_to_string(&path).unwrap_or_else(|err| {
+                        log::error!("failed to read dictionary {path:?}: {err}");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                (path, mtime, Arc::new(Dictionary::parse(&text)))
+            }
+        }
+    }
+
+    /// Gets the dictionary of a language, if configured.
+    pub fn dictionary(&self, lang: &str) -> Option<Arc<Dictionary>> {
+        let (_, _, dict) = self.dictionaries.get(lang)?;
+        Some(dict.clone())
+    }
+
+    /// Checks whether a word is accepted by the user in all languages.
+    pub fn is_user_word(&self, word: &str) -> bool {
+        self.user_words.contains(word)
+            || (self.user_file.as_ref()).is_some_and(|(_, _, dict)| dict.contains(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary() {
+        let dict = Dictionary::parse("3\nhello/S\nworld\n# comment\nword/MS\n");
+        assert!(dict.contains("Hello"));
+        assert!(!dict.contains("words"));
+        assert!(!dict.contains("comment"));
+
+        assert_eq!(dict.suggest("Wrld", 3), vec!["World", "Word"]);
+        assert_eq!(dict.suggest("xyz", 3), Vec::<String>::new());
+    }
+}
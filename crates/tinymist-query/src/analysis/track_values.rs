@@ -2,12 +2,13 @@
 
 use comemo::Track;
 use ecow::*;
+use typst::diag::StrResult;
 use typst::engine::{Engine, Route};
 use typst::eval::{Tracer, Vm};
-use typst::foundations::{Context, Label, Scopes, Styles, Value};
+use typst::foundations::{Context, Label, Module, Scopes, Styles, Value};
 use typst::introspection::{Introspector, Locator};
 use typst::model::{BibliographyElem, Document};
-use typst::syntax::{ast, LinkedNode, Span, SyntaxKind};
+use typst::syntax::{ast, LinkedNode, Source, Span, SyntaxKind};
 use typst::World;
 
 /// Try to determine a set of possible values for an expression.
@@ -78,6 +79,47 @@ pub fn analyze_import(world: &dyn World, source: &LinkedNode) -> Option<Value> {
         .map(Value::Module)
 }
 
+/// Evaluate a top-level binding of a source file. The functions without
+/// required parameters are called, e.g. `#let f() = ..`.
+pub fn evaluate_binding(world: &dyn World, source: &Source, name: &str) -> StrResult<Value> {
+    let module = evaluate_module(world, source)?;
+    let value = module
+        .scope()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| eco_format!("unknown variable: {name}"))?;
+    let Value::Func(func) = value else {
+        return Ok(value);
+    };
+
+    let mut locator = Locator::default();
+    let introspector = Introspector::default();
+    let mut tracer = Tracer::new();
+    let mut engine = Engine {
+        world: world.track(),
+        route: Route::default(),
+        introspector: introspector.track(),
+        locator: &mut locator,
+        tracer: tracer.track_mut(),
+    };
+    let context = Context::none();
+    func.call(&mut engine, context.track(), Vec::<Value>::new())
+        .map_err(first_error)
+}
+
+/// Evaluate a source file as a module, e.g. to get the symbols exported by it.
+pub fn evaluate_module(world: &dyn World, source: &Source) -> StrResult<Module> {
+    let route = Route::default();
+    let mut tracer = Tracer::new();
+    typst::eval::eval(world.track(), route.track(), tracer.track_mut(), source).map_err(first_error)
+}
+
+/// Gets the message of the first error of an evaluation.
+fn first_error(errors: EcoVec<typst::diag::SourceDiagnostic>) -> EcoString {
+    let message = errors.first().map(|err| err.message.clone());
+    message.unwrap_or_else(|| "failed to evaluate".into())
+}
+
 /// Find all labels and details for them.
 ///
 /// Returns:
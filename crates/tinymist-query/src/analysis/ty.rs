@@ -10,7 +10,7 @@ use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock};
 use reflexo::{hash::hash128, vector::ir::DefId};
 use typst::{
-    foundations::{Func, Value},
+    foundations::{Element, Func, Value},
     syntax::{
         ast::{self, AstNode},
         LinkedNode, Source, Span, SyntaxKind,
@@ -27,6 +27,7 @@ mod builtin;
 pub(crate) use builtin::*;
 mod literal_flow;
 pub(crate) use literal_flow::*;
+mod describe;
 
 /// Type checking at the source unit level.
 pub(crate) fn type_check(ctx: &mut AnalysisContext, source: Source) -> Option<Arc<TypeCheckInfo>> {
@@ -570,16 +571,40 @@ impl<'a, 'w> TypeChecker<'a, 'w> {
     fn check_show(&mut self, root: LinkedNode<'_>) -> Option<FlowType> {
         let show_rule: ast::ShowRule = root.cast()?;
 
-        let _selector = show_rule
+        let selector = show_rule
             .selector()
             .map(|sel| self.check_expr_in(sel.span(), root.clone()));
         let t = show_rule.transform();
-        // todo: infer it type by selector
         let _transform = self.check_expr_in(t.span(), root.clone());
 
+        // The shown content is an element selected by the selector, e.g. a
+        // heading in `show heading: it => ..`.
+        if let (Some(elem), ast::Expr::Closure(closure)) =
+            (selector.as_ref().and_then(selected_element), t)
+        {
+            self.check_shown_param(closure, elem, root);
+        }
+
         Some(FlowType::Any)
     }
 
+    fn check_shown_param(
+        &mut self,
+        closure: ast::Closure,
+        elem: Element,
+        root: LinkedNode<'_>,
+    ) -> Option<()> {
+        let Some(ast::Param::Pos(ast::Pattern::Normal(ast::Expr::Ident(ident)))) =
+            closure.params().children().next()
+        else {
+            return None;
+        };
+
+        let v = self.get_var(ident.span(), to_ident_ref(&root, ident)?)?;
+        v.ever_be(FlowType::Element(elem));
+        Some(())
+    }
+
     // currently we do nothing on contextual
     fn check_contextual(&mut self, root: LinkedNode<'_>) -> Option<FlowType> {
         let contextual: ast::Contextual = root.cast()?;
@@ -1411,6 +1436,18 @@ impl<'a, 'b> TypeSimplifier<'a, 'b> {
     }
 }
 
+/// Gets the element selected by a selector, e.g. `heading`.
+fn selected_element(selector: &FlowType) -> Option<Element> {
+    match selector {
+        FlowType::Element(elem) => Some(*elem),
+        FlowType::Value(v) => match &v.0 {
+            Value::Func(func) => func.element(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn to_ident_ref(root: &LinkedNode, c: ast::Ident) -> Option<IdentRef> {
     Some(IdentRef {
         name: c.get().to_string(),
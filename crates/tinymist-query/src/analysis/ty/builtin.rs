@@ -6,7 +6,7 @@ use typst::{
     syntax::Span,
 };
 
-use super::{FlowRecord, FlowType};
+use super::{FlowRecord, FlowSignature, FlowType};
 
 #[derive(Debug, Clone, Hash)]
 pub(crate) enum PathPreference {
@@ -25,6 +25,24 @@ pub(crate) enum PathPreference {
 }
 
 impl PathPreference {
+    /// The name of the context in the path completion configuration.
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            PathPreference::None => "any",
+            PathPreference::Special => "special",
+            PathPreference::Source => "source",
+            PathPreference::Csv => "csv",
+            PathPreference::Image => "image",
+            PathPreference::Json => "json",
+            PathPreference::Yaml => "yaml",
+            PathPreference::Xml => "xml",
+            PathPreference::Toml => "toml",
+            PathPreference::Bibliography => "bibliography",
+            PathPreference::RawTheme => "rawTheme",
+            PathPreference::RawSyntax => "rawSyntax",
+        }
+    }
+
     pub fn ext_matcher(&self) -> &'static RegexSet {
         static SOURCE_REGSET: Lazy<RegexSet> =
             Lazy::new(|| RegexSet::new([r"^typ$", r"^typc$"]).unwrap());
@@ -89,6 +107,8 @@ pub(crate) enum FlowBuiltinType {
     TextFont,
     TextLang,
     TextRegion,
+    Numbering,
+    DatePattern,
 
     Dir,
     Length,
@@ -192,6 +212,57 @@ pub(in crate::analysis::ty) fn param_mapping(f: &Func, p: &ParamInfo) -> Option<
         }
         ("text", "lang") => Some(literally(TextLang)),
         ("text", "region") => Some(literally(TextRegion)),
+        ("heading" | "figure" | "page" | "equation", "numbering") => {
+            static NUMBERING_TYPE: Lazy<FlowType> = Lazy::new(|| {
+                flow_union!(
+                    literally(Numbering),
+                    FlowType::Value(Box::new((
+                        Value::Type(Type::of::<Func>()),
+                        Span::detached()
+                    ))),
+                    FlowType::None,
+                )
+            });
+            Some(NUMBERING_TYPE.clone())
+        }
+        ("enum" | "footnote" | "numbering" | "display", "numbering") => {
+            static NUMBERING_TYPE: Lazy<FlowType> = Lazy::new(|| {
+                flow_union!(
+                    literally(Numbering),
+                    FlowType::Value(Box::new((
+                        Value::Type(Type::of::<Func>()),
+                        Span::detached()
+                    ))),
+                )
+            });
+            Some(NUMBERING_TYPE.clone())
+        }
+        ("display", "pattern") => Some(flow_union!(literally(DatePattern), FlowType::Auto)),
+        ("map", "mapper")
+        | ("filter" | "any" | "all", "test")
+        | ("find" | "position", "searcher")
+        | ("sorted" | "dedup", "key") => {
+            static CALLBACK_TYPE: Lazy<FlowType> = Lazy::new(|| {
+                FlowType::Func(Box::new(FlowSignature {
+                    pos: vec![FlowType::Any],
+                    named: vec![],
+                    rest: None,
+                    ret: FlowType::Any,
+                }))
+            });
+            Some(CALLBACK_TYPE.clone())
+        }
+        ("fold", "folder") | ("reduce", "reducer") => {
+            static ACCUMULATOR_TYPE: Lazy<FlowType> = Lazy::new(|| {
+                FlowType::Func(Box::new(FlowSignature {
+                    pos: vec![FlowType::Any, FlowType::Any],
+                    named: vec![],
+                    rest: None,
+                    ret: FlowType::Any,
+                }))
+            });
+            Some(ACCUMULATOR_TYPE.clone())
+        }
         ("text" | "stack", "dir") => Some(literally(Dir)),
         (
             // todo: polygon.regular
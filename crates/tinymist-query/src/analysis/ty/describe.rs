@@ -0,0 +1,140 @@
+use typst::foundations::{Repr, Value};
+
+use super::{FlowBuiltinType, FlowSignature, FlowType};
+
+impl FlowType {
+    /// Describes the type in a Typst-like syntax, e.g. `(int | none)`.
+    ///
+    /// Returns `None` if nothing is known about the type.
+    pub(crate) fn describe(&self) -> Option<String> {
+        match self {
+            FlowType::Any | FlowType::Undef | FlowType::Infer | FlowType::Clause => None,
+            ty => Some(describe(ty)),
+        }
+    }
+}
+
+fn describe(ty: &FlowType) -> String {
+    match ty {
+        FlowType::Clause | FlowType::Undef | FlowType::Infer | FlowType::Any => "any".into(),
+        FlowType::None | FlowType::FlowNone => "none".into(),
+        FlowType::Auto => "auto".into(),
+        FlowType::Content => "content".into(),
+        FlowType::Boolean(Some(b)) => b.to_string(),
+        FlowType::Boolean(None) => "bool".into(),
+        FlowType::Builtin(b) => describe_builtin(b).into(),
+        FlowType::Value(v) => describe_value(&v.0),
+        FlowType::ValueDoc(v) => v.0.repr().to_string(),
+        FlowType::Element(_) => "function".into(),
+        FlowType::Var(v) => v.1.to_string(),
+        FlowType::Func(sig) => describe_signature(sig),
+        FlowType::Dict(r) if r.fields.is_empty() => "dictionary".into(),
+        FlowType::Dict(r) => {
+            let fields = r
+                .fields
+                .iter()
+                .map(|(name, ty, _)| format!("{name}: {}", describe(ty)));
+            format!("({})", fields.collect::<Vec<_>>().join(", "))
+        }
+        FlowType::Array(a) => format!("array<{}>", describe(a)),
+        FlowType::Tuple(t) => {
+            let elems = t.iter().map(describe).collect::<Vec<_>>();
+            match elems.as_slice() {
+                [elem] => format!("({elem},)"),
+                elems => format!("({})", elems.join(", ")),
+            }
+        }
+        FlowType::With(w) => describe(&w.0),
+        FlowType::Args(..) => "arguments".into(),
+        FlowType::If(i) => describe_union([&i.then, &i.else_]),
+        FlowType::Union(u) => describe_union(u.iter()),
+        FlowType::Let(v) => describe_union(v.lbs.iter()),
+        FlowType::At(..) | FlowType::Unary(..) | FlowType::Binary(..) => "any".into(),
+    }
+}
+
+fn describe_union<'a>(tys: impl IntoIterator<Item = &'a FlowType>) -> String {
+    let mut members: Vec<String> = vec![];
+    for ty in tys {
+        let ty = describe(ty);
+        if !members.contains(&ty) {
+            members.push(ty);
+        }
+    }
+
+    match members.as_slice() {
+        [] => "any".into(),
+        [ty] => ty.clone(),
+        tys => format!("({})", tys.join(" | ")),
+    }
+}
+
+fn describe_signature(sig: &FlowSignature) -> String {
+    let mut params = sig.pos.iter().map(describe).collect::<Vec<_>>();
+    for (name, ty) in &sig.named {
+        params.push(format!("{name}: {}", describe(ty)));
+    }
+    if let Some(rest) = &sig.rest {
+        params.push(format!("..{}", describe(rest)));
+    }
+
+    format!("({}) => {}", params.join(", "), describe(&sig.ret))
+}
+
+fn describe_value(v: &Value) -> String {
+    match v {
+        Value::Type(ty) => ty.short_name().into(),
+        Value::None => "none".into(),
+        Value::Auto => "auto".into(),
+        v => v.ty().short_name().into(),
+    }
+}
+
+fn describe_builtin(b: &FlowBuiltinType) -> &'static str {
+    match b {
+        FlowBuiltinType::Args => "arguments",
+        FlowBuiltinType::Color => "color",
+        FlowBuiltinType::TextSize | FlowBuiltinType::Length => "length",
+        FlowBuiltinType::TextFont => "(str | array)",
+        FlowBuiltinType::TextLang
+        | FlowBuiltinType::TextRegion
+        | FlowBuiltinType::DatePattern
+        | FlowBuiltinType::Path(..) => "str",
+        FlowBuiltinType::Numbering => "(str | function)",
+        FlowBuiltinType::Dir => "direction",
+        FlowBuiltinType::Float => "float",
+        FlowBuiltinType::Stroke => "stroke",
+        FlowBuiltinType::Margin
+        | FlowBuiltinType::Inset
+        | FlowBuiltinType::Outset
+        | FlowBuiltinType::Radius => "(relative | dictionary)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::Str;
+    use typst::syntax::Span;
+
+    use super::*;
+
+    fn value(v: Value) -> FlowType {
+        FlowType::Value(Box::new((v, Span::detached())))
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(FlowType::Any.describe(), None);
+        assert_eq!(value(Value::Int(1)).describe().as_deref(), Some("int"));
+
+        let ty = FlowType::Union(Box::new(vec![
+            value(Value::Int(1)),
+            value(Value::Int(2)),
+            FlowType::None,
+        ]));
+        assert_eq!(ty.describe().as_deref(), Some("(int | none)"));
+
+        let ty = FlowType::Array(Box::new(value(Value::Str(Str::from("a")))));
+        assert_eq!(ty.describe().as_deref(), Some("array<str>"));
+    }
+}
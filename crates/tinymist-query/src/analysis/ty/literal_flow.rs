@@ -1,5 +1,6 @@
 //! Infer more than the principal type of some expression.
 
+use ecow::EcoString;
 use typst::syntax::{
     ast::{self, AstNode},
     LinkedNode, SyntaxKind,
@@ -7,7 +8,10 @@ use typst::syntax::{
 
 use crate::{syntax::CheckTarget, AnalysisContext};
 
-use super::{FlowType, FlowVarKind, TypeCheckInfo};
+use super::{
+    FlowBuiltinType, FlowRecord, FlowType, FlowVarKind, TypeCheckInfo, FLOW_INSET_DICT,
+    FLOW_MARGIN_DICT, FLOW_OUTSET_DICT, FLOW_RADIUS_DICT, FLOW_STROKE_DICT,
+};
 
 // todo: detect recursive usage
 
@@ -49,10 +53,33 @@ impl<'a, 'w> LiteralTypeCheckWorker<'a, 'w> {
                 let p = parent.cast::<ast::Named>()?;
                 let exp = p.expr();
                 if exp.span() == node.span() {
-                    let ty = self.info.mapping.get(&p.span())?;
-                    return self.ubs(ty);
+                    if let Some(ty) = self.info.mapping.get(&p.span()) {
+                        return self.ubs(ty);
+                    }
+
+                    // The value of a field in a dict literal has the type
+                    // of the field itself.
+                    if parent.parent_kind() == Some(SyntaxKind::Dict) {
+                        return self.check(parent.clone());
+                    }
                 }
             }
+            SyntaxKind::Dict => {
+                // A field of a dict literal, e.g. `start` in
+                // `marks: (start: ..)`, is typed by projecting the expected
+                // type of the enclosing dict.
+                let named = node.cast::<ast::Named>()?;
+                let dict_ty = self.check(parent.clone())?;
+                return self.project_field(&dict_ty, named.name().get());
+            }
+            SyntaxKind::Array => {
+                let index = parent
+                    .children()
+                    .filter(|c| c.is::<ast::Expr>())
+                    .position(|c| c.span() == node.span())?;
+                let array_ty = self.check(parent.clone())?;
+                return self.project_item(&array_ty, index);
+            }
             _ => return None,
         }
 
@@ -80,6 +107,49 @@ impl<'a, 'w> LiteralTypeCheckWorker<'a, 'w> {
         }
     }
 
+    /// Projects the expected type of a dict to the type of one of its fields.
+    fn project_field(&self, ty: &FlowType, key: &EcoString) -> Option<FlowType> {
+        let record: &FlowRecord = match ty {
+            FlowType::Dict(record) => record,
+            FlowType::Builtin(FlowBuiltinType::Stroke) => &FLOW_STROKE_DICT,
+            FlowType::Builtin(FlowBuiltinType::Margin) => &FLOW_MARGIN_DICT,
+            FlowType::Builtin(FlowBuiltinType::Inset) => &FLOW_INSET_DICT,
+            FlowType::Builtin(FlowBuiltinType::Outset) => &FLOW_OUTSET_DICT,
+            FlowType::Builtin(FlowBuiltinType::Radius) => &FLOW_RADIUS_DICT,
+            FlowType::Union(types) => {
+                return self.project_union(types, |ty| self.project_field(ty, key))
+            }
+            FlowType::Let(..) | FlowType::Var(..) => {
+                return self.project_field(&self.ubs(ty)?, key)
+            }
+            _ => return None,
+        };
+
+        let (_, ty, _) = record.fields.iter().find(|(k, _, _)| k == key)?;
+        self.ubs(ty)
+    }
+
+    /// Projects the expected type of an array to the type of its item at the
+    /// given index.
+    fn project_item(&self, ty: &FlowType, index: usize) -> Option<FlowType> {
+        match ty {
+            FlowType::Array(item) => self.ubs(item),
+            FlowType::Tuple(items) => self.ubs(items.get(index)?),
+            FlowType::Union(types) => self.project_union(types, |ty| self.project_item(ty, index)),
+            FlowType::Let(..) | FlowType::Var(..) => self.project_item(&self.ubs(ty)?, index),
+            _ => None,
+        }
+    }
+
+    fn project_union(
+        &self,
+        types: &[FlowType],
+        f: impl Fn(&FlowType) -> Option<FlowType>,
+    ) -> Option<FlowType> {
+        let types = types.iter().filter_map(f).collect::<Vec<_>>();
+        (!types.is_empty()).then(|| FlowType::from_types(types.into_iter()))
+    }
+
     fn ubs(&self, ty: &FlowType) -> Option<FlowType> {
         match ty {
             FlowType::Let(ty) => Some(FlowType::from_types(ty.ubs.iter().cloned())),
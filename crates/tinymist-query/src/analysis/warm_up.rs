@@ -0,0 +1,46 @@
+//! Warming up the analysis caches of a workspace in advance, so that the first
+//! requests on it are not slow.
+
+use typst::foundations::{Func, Value};
+
+use super::prelude::*;
+use crate::analysis::analyze_dyn_signature;
+
+/// Gets the source files of the workspace to warm up, at most `budget` files.
+pub fn warm_up_files(ctx: &AnalysisContext, budget: usize) -> Vec<TypstFileId> {
+    ctx.source_files().iter().take(budget).copied().collect()
+}
+
+/// Warms up the analysis caches of a source file, i.e. parses it and computes
+/// its def-use information and exports. Returns whether the file is analyzed.
+pub fn warm_up_file(ctx: &mut AnalysisContext, id: TypstFileId) -> bool {
+    let Ok(source) = ctx.source_by_id(id) else {
+        return false;
+    };
+    ctx.def_use(source).is_some()
+}
+
+/// Warms up the signatures of the functions in the standard library, including
+/// the ones in its modules, e.g. `calc`. Returns the number of the functions.
+pub fn warm_up_library(ctx: &mut AnalysisContext) -> usize {
+    let mut funcs: Vec<Func> = vec![];
+    for (_, value) in ctx.world().library().global.scope().iter() {
+        match value {
+            Value::Func(func) => funcs.push(func.clone()),
+            Value::Module(module) => {
+                let values = module.scope().iter().map(|(_, value)| value);
+                funcs.extend(values.filter_map(|value| match value {
+                    Value::Func(func) => Some(func.clone()),
+                    _ => None,
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    let count = funcs.len();
+    for func in funcs {
+        analyze_dyn_signature(ctx, func);
+    }
+    count
+}
@@ -0,0 +1,287 @@
+mod equation_numbering;
+mod extract_let;
+mod import_form;
+mod markup_rewrite;
+mod organize_imports;
+mod show_rule;
+mod table_edit;
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, CreateFile, CreateFileOptions,
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    ResourceOp, TextDocumentEdit, TextEdit,
+};
+
+use crate::{diagnostic_fixes, lint_fixes, lint_source, prelude::*, LintFix, SemanticRequest};
+use equation_numbering::number_equation;
+use extract_let::extract_let;
+use import_form::rewrite_import;
+use markup_rewrite::rewrite_markup;
+use organize_imports::organize_imports;
+use show_rule::create_show_rule;
+use table_edit::edit_table;
+
+/// The [`textDocument/codeAction`] request is sent from the client to the
+/// server to compute commands for a given text document and range.
+///
+/// [`textDocument/codeAction`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_codeAction
+///
+/// The code actions are the quick fixes of the lints overlapping the range,
+/// e.g. the suggestions of the misspelled words, and the quick fixes of the
+/// compiler diagnostics sent by the client, e.g. replacing the deprecated
+/// functions.
+///
+/// When an expression is selected, a refactoring is also offered to extract
+/// it into a `let` binding. The imports can be rewritten between importing a
+/// module, importing items explicitly, and importing all items with a
+/// wildcard. The markup can be wrapped in figures or emphasis, and the
+/// headings and lists can be restructured. The rows and the columns of a table
+/// can be inserted, deleted, and aligned. An unnumbered block equation can be
+/// numbered and labelled. A show rule can be created for the element at the
+/// cursor.
+///
+/// The imports at the top level can be organized by the source action, which
+/// can also be run on save by the clients. It is computed only if the client
+/// asks for the source actions explicitly.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 1.0.0.
+#[derive(Debug, Clone)]
+pub struct CodeActionRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The range of the document to get code actions for.
+    pub range: LspRange,
+    /// The diagnostics overlapping the range known to the client.
+    pub diagnostics: Vec<LspDiagnostic>,
+    /// The kinds of the code actions requested by the client, e.g.
+    /// `source.organizeImports` on save.
+    pub only: Option<Vec<CodeActionKind>>,
+    /// Whether the client can run `editor.action.rename` after extracting an
+    /// expression, to rename the introduced binding.
+    pub trigger_rename: bool,
+}
+
+impl SemanticRequest for CodeActionRequest {
+    type Response = Vec<CodeActionOrCommand>;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let range = ctx.to_typst_range(self.range, &source)?;
+        let uri = path_to_url(&self.path).ok()?;
+
+        let mut actions = vec![];
+        for lint in lint_source(ctx, &source) {
+            if lint.range.end < range.start || range.end < lint.range.start {
+                continue;
+            }
+
+            let diagnostic = lint.to_diagnostic(ctx, &source);
+            let fixes = lint_fixes(ctx, &source, &lint);
+            push_fix_actions(ctx, &source, &uri, fixes, diagnostic, &mut actions);
+        }
+
+        for diagnostic in self.diagnostics {
+            let fixes = diagnostic_fixes(ctx, &source, &diagnostic);
+            push_fix_actions(ctx, &source, &uri, fixes, diagnostic, &mut actions);
+        }
+
+        if let Some(edits) = extract_let(&source, range) {
+            let edits = edits.into_iter().map(|(range, new_text)| TextEdit {
+                range: ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+            // The selection is replaced by the name, at which the rename is
+            // triggered.
+            let command = self.trigger_rename.then(|| Command {
+                title: "Rename".to_owned(),
+                command: "editor.action.rename".to_owned(),
+                arguments: None,
+            });
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Extract into `let` binding".to_owned(),
+                kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from_iter([(uri, edits.collect())])),
+                    ..Default::default()
+                }),
+                command,
+                ..Default::default()
+            }));
+        }
+
+        let rewrites = rewrite_import(ctx, &source, range.start).into_iter();
+        let rewrites = rewrites.chain(rewrite_markup(&source, range.clone()));
+        for (title, edits) in rewrites.chain(edit_table(&source, range.start)) {
+            let edits = edits.into_iter().map(|(range, new_text)| TextEdit {
+                range: ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.to_owned(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from_iter([(uri.clone(), edits.collect())])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+
+        let defined = || ctx.label_index().defined.clone();
+        let numbered = number_equation(&source, range.start, defined);
+        let created = create_show_rule(ctx, &source, range.start);
+        for (title, edits) in numbered.into_iter().chain(created) {
+            let edits = edits.into_iter().map(|(range, new_text)| TextEdit {
+                range: ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::REFACTOR),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from_iter([(uri.clone(), edits.collect())])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+
+        let organized = self
+            .is_requested(&CodeActionKind::SOURCE_ORGANIZE_IMPORTS)
+            .then(|| organize_imports(ctx, &source))
+            .unwrap_or_default();
+        if !organized.is_empty() {
+            let edits = organized.into_iter().map(|(range, new_text)| TextEdit {
+                range: ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Organize imports".to_owned(),
+                kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from_iter([(uri.clone(), edits.collect())])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+
+        Some(actions)
+    }
+}
+
+impl CodeActionRequest {
+    /// Checks whether the client asks for a kind of the code actions, i.e. the
+    /// kind or its parent kind, e.g. `source` of `source.organizeImports`, is
+    /// in `only`.
+    fn is_requested(&self, kind: &CodeActionKind) -> bool {
+        let kind = kind.as_str();
+        self.only.iter().flatten().any(|only| {
+            let only = only.as_str();
+            kind == only || kind.strip_prefix(only).is_some_and(|k| k.starts_with('.'))
+        })
+    }
+}
+
+/// Converts the quick fixes of a diagnostic to code actions.
+fn push_fix_actions(
+    ctx: &AnalysisContext,
+    source: &Source,
+    uri: &Url,
+    fixes: Vec<LintFix>,
+    diagnostic: LspDiagnostic,
+    actions: &mut Vec<CodeActionOrCommand>,
+) {
+    for (i, fix) in fixes.into_iter().enumerate() {
+        let edits = fix.edits.iter().map(|(range, new_text)| TextEdit {
+            range: ctx.to_lsp_range(range.clone(), source),
+            new_text: new_text.clone(),
+        });
+        let edits = edits.collect::<Vec<_>>();
+        let edit = match &fix.create {
+            Some(file) => {
+                let Ok(file_uri) = path_to_url(file) else {
+                    continue;
+                };
+                Some(file_edit(file_uri, uri.clone(), edits))
+            }
+            // The fix may only run a command, e.g. downloading a package.
+            None if edits.is_empty() => None,
+            None => Some(WorkspaceEdit {
+                changes: Some(HashMap::from_iter([(uri.clone(), edits)])),
+                ..Default::default()
+            }),
+        };
+
+        // Only the first fix is preferred, e.g. the closest suggestion.
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: fix.title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit,
+            command: fix.command,
+            is_preferred: Some(i == 0),
+            ..Default::default()
+        }));
+    }
+}
+
+/// Creates a file and then applies the edits to the document.
+fn file_edit(file: Url, uri: Url, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    let create = DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+        uri: file,
+        options: Some(CreateFileOptions {
+            overwrite: Some(false),
+            ignore_if_exists: Some(true),
+        }),
+        annotation_id: None,
+    }));
+
+    let mut operations = vec![create];
+    if !edits.is_empty() {
+        operations.push(DocumentChangeOperation::Edit(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+            edits: edits.into_iter().map(OneOf::Left).collect(),
+        }));
+    }
+
+    WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Operations(operations)),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("code_action", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = CodeActionRequest {
+                path: path.clone(),
+                range: ctx.to_lsp_range(find_test_range(&source), &source),
+                diagnostics: vec![],
+                only: Some(vec![CodeActionKind::SOURCE_ORGANIZE_IMPORTS]),
+                trigger_rename: false,
+            };
+
+            let result = request.request(ctx).map(|actions| {
+                actions
+                    .into_iter()
+                    .filter_map(|action| match action {
+                        CodeActionOrCommand::CodeAction(action) => Some(action.title),
+                        CodeActionOrCommand::Command(..) => None,
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
@@ -0,0 +1,148 @@
+//! The numbering of an unnumbered block equation, which wraps the equation in
+//! a content block scoping a `set math.equation(numbering: ..)` rule and
+//! labels it so that it can be referenced.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::markup_rewrite::find_ancestor;
+use super::*;
+
+/// Numbers the block equation at the cursor if no set rule in its scope does,
+/// labelling it with a fresh `eq:` label unless it is already labelled.
+/// Returns the title and the edits.
+///
+/// The defined labels are only collected by `defined` if the equation is
+/// numbered and labelled, since they are collected in the whole workspace.
+pub(super) fn number_equation(
+    source: &Source,
+    cursor: usize,
+    defined: impl FnOnce() -> HashSet<String>,
+) -> Option<(String, Vec<(Range<usize>, String)>)> {
+    let root = LinkedNode::new(source.root());
+    let equation = find_ancestor(&root, cursor, &[SyntaxKind::Equation])?;
+    if !equation.cast::<ast::Equation>()?.block()
+        || equation.parent_kind() != Some(SyntaxKind::Markup)
+        || is_numbered(&equation)
+    {
+        return None;
+    }
+
+    let text = source.text();
+    let start = equation.offset();
+    let (end, label, title) = match attached_label(&equation) {
+        Some(label) => (
+            label.range().end,
+            label.text().to_string(),
+            "Number equation".to_owned(),
+        ),
+        None => {
+            let defined = defined();
+            let name = (1..)
+                .map(|idx| format!("eq:{idx}"))
+                .find(|name| !defined.contains(name))?;
+            let title = format!("Number equation and label it `<{name}>`");
+            (equation.range().end, format!("<{name}>"), title)
+        }
+    };
+
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..start];
+    let indent = if indent.trim().is_empty() { indent } else { "" };
+    let body = text[equation.range()].replace('\n', "\n  ");
+    let numbered = format!(
+        "#[\n{indent}  #set math.equation(numbering: \"(1)\")\n{indent}  {body} {label}\n{indent}]"
+    );
+    Some((title, vec![(start..end, numbered)]))
+}
+
+/// Checks whether a set rule preceding the equation in its scope or in an
+/// enclosing scope numbers the equations.
+fn is_numbered(equation: &LinkedNode) -> bool {
+    let mut node = equation.clone();
+    loop {
+        let mut prev = node.prev_sibling();
+        while let Some(sibling) = prev {
+            if numbers_equations(&sibling) {
+                return true;
+            }
+            prev = sibling.prev_sibling();
+        }
+        match node.parent() {
+            Some(parent) => node = parent.clone(),
+            None => return false,
+        }
+    }
+}
+
+/// Checks whether a node is a set rule numbering the equations, e.g.
+/// `set math.equation(numbering: "(1)")`.
+fn numbers_equations(node: &LinkedNode) -> bool {
+    let Some(rule) = node.cast::<ast::SetRule>() else {
+        return false;
+    };
+    let target = rule.target().to_untyped().clone().into_text();
+    if !matches!(target.as_str(), "math.equation" | "equation") {
+        return false;
+    }
+
+    rule.args().items().any(|arg| match arg {
+        ast::Arg::Named(named) if named.name().get() == "numbering" => {
+            !matches!(named.expr(), ast::Expr::None(_))
+        }
+        _ => false,
+    })
+}
+
+/// Gets the label attached to an equation, e.g. `<eq:mass>` in
+/// `$ E = m c^2 $ <eq:mass>`.
+fn attached_label<'a>(equation: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let next = equation.next_sibling()?;
+    (next.kind() == SyntaxKind::Label).then_some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str, cursor: &str, defined: &[&str]) -> Option<(String, String)> {
+        let source = Source::detached(text);
+        let cursor = text.find(cursor).unwrap();
+        let defined = || defined.iter().map(|name| name.to_string()).collect();
+        let (title, edits) = number_equation(&source, cursor, defined)?;
+
+        let mut text = text.to_owned();
+        for (range, new_text) in edits.into_iter().rev() {
+            text.replace_range(range, &new_text);
+        }
+        Some((title, text))
+    }
+
+    #[test]
+    fn test_number_equation() {
+        let (title, text) = number("Mass:\n$ E = m c^2 $\n", "E =", &["eq:1"]).unwrap();
+        assert_eq!(title, "Number equation and label it `<eq:2>`");
+        assert_eq!(
+            text,
+            "Mass:\n#[\n  #set math.equation(numbering: \"(1)\")\n  $ E = m c^2 $ <eq:2>\n]\n"
+        );
+
+        let (title, text) = number("- $ x $ <eq:x>", "x $", &[]).unwrap();
+        assert_eq!(title, "Number equation");
+        assert_eq!(
+            text,
+            "- #[\n  #set math.equation(numbering: \"(1)\")\n  $ x $ <eq:x>\n]"
+        );
+    }
+
+    #[test]
+    fn test_numbered_equation() {
+        assert_eq!(number("Inline $x$.", "x", &[]), None);
+        let numbered = "#set math.equation(numbering: \"1.\")\n#[\n$ x $\n]";
+        assert_eq!(number(numbered, "x $", &[]), None);
+        let unnumbered = "#set math.equation(numbering: none)\n$ x $";
+        assert!(number(unnumbered, "x $", &[]).is_some());
+        let scoped = "#[#set math.equation(numbering: \"1.\")]\n$ x $";
+        assert!(number(scoped, "x $", &[]).is_some());
+    }
+}
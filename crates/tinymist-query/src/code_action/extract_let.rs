@@ -0,0 +1,243 @@
+//! The refactoring extracting an expression into a `let` binding.
+
+use std::ops::Range;
+
+use super::*;
+
+/// The name of the introduced binding, suffixed with a number if it is
+/// already used in the document.
+const EXTRACTED_NAME: &str = "extracted";
+
+/// Extracts the selected expression into a `let` binding before the statement
+/// containing it, and replaces the selection with the name of the binding.
+///
+/// The binding is introduced in the closest code block or markup, unless the
+/// expression is not evaluated exactly once there, e.g. when it is in the body
+/// of a closure or in a branch of a conditional.
+pub(super) fn extract_let(
+    source: &Source,
+    range: Range<usize>,
+) -> Option<Vec<(Range<usize>, String)>> {
+    let text = source.text();
+    let selected = text.get(range.clone())?;
+    let start = range.start + (selected.len() - selected.trim_start().len());
+    let end = range.end - (selected.len() - selected.trim_end().len());
+    // The hash of an embedded expression may be selected as well.
+    let start = start + usize::from(text[start..end].starts_with('#'));
+    if start >= end {
+        return None;
+    }
+
+    let root = LinkedNode::new(source.root());
+    let node = selected_expr(&root, start..end)?;
+    let (container, statement) = binding_scope(node.clone())?;
+
+    let mut name = EXTRACTED_NAME.to_owned();
+    for i in 1.. {
+        if !text.contains(&name) {
+            break;
+        }
+        name = format!("{EXTRACTED_NAME}{i}");
+    }
+
+    // In markup, the embedded expressions start with a hash, which is kept to
+    // embed the binding in place of the expression.
+    let in_markup = container.kind() == SyntaxKind::Markup;
+    let mut insert_at = statement.range().start;
+    if in_markup && statement.prev_sibling_kind() == Some(SyntaxKind::Hash) {
+        insert_at -= 1;
+    }
+
+    let line_start = text[..insert_at].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..insert_at];
+    let separator = match (indent.trim().is_empty(), in_markup) {
+        (true, _) => format!("\n{indent}"),
+        (false, true) => ";".to_owned(),
+        (false, false) => "; ".to_owned(),
+    };
+    let hash = if in_markup { "#" } else { "" };
+    let binding = format!("{hash}let {name} = {}{separator}", &text[node.range()]);
+
+    Some(vec![(insert_at..insert_at, binding), (node.range(), name)])
+}
+
+/// Finds the outermost expression exactly covering a range.
+fn selected_expr<'a>(root: &LinkedNode<'a>, range: Range<usize>) -> Option<LinkedNode<'a>> {
+    let mut node = root.leaf_at(range.start + 1)?;
+    while node.range() != range {
+        node = node.parent()?.clone();
+        if node.range().start < range.start || node.range().end > range.end {
+            return None;
+        }
+    }
+    while let Some(parent) = node.parent() {
+        let is_block = matches!(parent.kind(), SyntaxKind::Markup | SyntaxKind::Code);
+        if parent.range() != range || is_block {
+            break;
+        }
+        node = parent.clone();
+    }
+
+    // The names, the text in markup, and the expressions in math are not
+    // extracted.
+    let extractable = node.cast::<ast::Expr>().is_some()
+        && !matches!(
+            node.kind(),
+            SyntaxKind::Ident
+                | SyntaxKind::Text
+                | SyntaxKind::Space
+                | SyntaxKind::Linebreak
+                | SyntaxKind::Parbreak
+                | SyntaxKind::Escape
+                | SyntaxKind::Shorthand
+                | SyntaxKind::SmartQuote
+                | SyntaxKind::Equation
+                | SyntaxKind::Math
+        );
+    extractable.then_some(node)
+}
+
+/// Finds the code or markup to introduce a binding of an expression in, and
+/// the statement in it containing the expression.
+fn binding_scope(node: LinkedNode) -> Option<(LinkedNode, LinkedNode)> {
+    let mut child = node;
+    loop {
+        let parent = child.parent()?.clone();
+        match parent.kind() {
+            SyntaxKind::Code => return Some((parent, child)),
+            SyntaxKind::Markup => {
+                // The expressions in markup must be embedded with a hash.
+                let embedded = child.prev_sibling_kind() == Some(SyntaxKind::Hash);
+                let is_element = matches!(child.kind(), SyntaxKind::Strong | SyntaxKind::Emph);
+                if !embedded && !is_element && !is_markup_item(child.kind()) {
+                    return None;
+                }
+                // The bindings are introduced in the markup of documents and
+                // content blocks, instead of the markup in headings or lists.
+                let grand = parent.parent();
+                if grand.map_or(true, |grand| grand.kind() == SyntaxKind::ContentBlock) {
+                    return Some((parent, child));
+                }
+            }
+            _ if !is_evaluated_once(&parent, &child) => return None,
+            _ => {}
+        }
+        child = parent;
+    }
+}
+
+fn is_markup_item(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Heading | SyntaxKind::ListItem | SyntaxKind::EnumItem | SyntaxKind::TermItem
+    )
+}
+
+/// Checks whether a child is evaluated exactly once whenever its parent is,
+/// so that it can be evaluated ahead of the parent.
+fn is_evaluated_once(parent: &LinkedNode, child: &LinkedNode) -> bool {
+    let span = child.span();
+    match parent.kind() {
+        SyntaxKind::Args
+        | SyntaxKind::Array
+        | SyntaxKind::Dict
+        | SyntaxKind::Parenthesized
+        | SyntaxKind::FuncCall
+        | SyntaxKind::Unary
+        | SyntaxKind::Spread
+        | SyntaxKind::SetRule
+        | SyntaxKind::ShowRule
+        | SyntaxKind::ModuleInclude
+        | SyntaxKind::FuncReturn
+        | SyntaxKind::Strong
+        | SyntaxKind::Emph => true,
+        kind if is_markup_item(kind) => true,
+        SyntaxKind::Named => parent
+            .cast::<ast::Named>()
+            .is_some_and(|named| named.expr().span() == span),
+        SyntaxKind::Keyed => parent
+            .cast::<ast::Keyed>()
+            .is_some_and(|keyed| keyed.expr().span() == span),
+        SyntaxKind::FieldAccess => parent
+            .cast::<ast::FieldAccess>()
+            .is_some_and(|access| access.target().span() == span),
+        SyntaxKind::Conditional => parent
+            .cast::<ast::Conditional>()
+            .is_some_and(|cond| cond.condition().span() == span),
+        SyntaxKind::ForLoop => parent
+            .cast::<ast::ForLoop>()
+            .is_some_and(|for_loop| for_loop.iterable().span() == span),
+        SyntaxKind::DestructAssignment => parent
+            .cast::<ast::DestructAssignment>()
+            .is_some_and(|assign| assign.value().span() == span),
+        // The closures defined by `let f(x) = ..` are not extracted.
+        SyntaxKind::LetBinding => parent.cast::<ast::LetBinding>().is_some_and(|binding| {
+            matches!(binding.kind(), ast::LetBindingKind::Normal(..))
+                && binding.init().is_some_and(|init| init.span() == span)
+        }),
+        SyntaxKind::Binary => parent.cast::<ast::Binary>().is_some_and(|binary| {
+            match binary.op() {
+                // The right-hand side is short-circuited.
+                ast::BinOp::And | ast::BinOp::Or => binary.lhs().span() == span,
+                // The left-hand side is assigned to.
+                ast::BinOp::Assign
+                | ast::BinOp::AddAssign
+                | ast::BinOp::SubAssign
+                | ast::BinOp::MulAssign
+                | ast::BinOp::DivAssign => binary.rhs().span() == span,
+                _ => true,
+            }
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(text: &str, selected: &str) -> Option<String> {
+        let source = Source::detached(text);
+        let start = text.find(selected).unwrap();
+        let mut edits = extract_let(&source, start..start + selected.len())?;
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+        let mut text = text.to_owned();
+        for (range, new_text) in edits {
+            text.replace_range(range, &new_text);
+        }
+        Some(text)
+    }
+
+    #[test]
+    fn test_extract_let() {
+        assert_eq!(
+            extract("#let a = (1 + 2) * 3", "1 + 2").as_deref(),
+            Some("#let extracted = 1 + 2\n#let a = (extracted) * 3")
+        );
+        assert_eq!(
+            extract("Sum: #calc.abs(-1) apples", "#calc.abs(-1)").as_deref(),
+            Some("Sum: #let extracted = calc.abs(-1);#extracted apples")
+        );
+        assert_eq!(
+            extract("#{\n  let a = 1\n  f(a + 1)\n}", "a + 1").as_deref(),
+            Some("#{\n  let a = 1\n  let extracted = a + 1\n  f(extracted)\n}")
+        );
+        assert_eq!(
+            extract("#let extracted = 1\n#f(1 + 2)", "1 + 2").as_deref(),
+            Some("#let extracted = 1\n#let extracted1 = 1 + 2\n#f(extracted1)")
+        );
+        assert_eq!(
+            extract("#{ if x { f(1 + 2) } }", "1 + 2").as_deref(),
+            Some("#{ if x { let extracted = 1 + 2; f(extracted) } }")
+        );
+
+        // The expressions not evaluated exactly once in their scope.
+        assert_eq!(extract("#let f(x) = x + 1", "x + 1"), None);
+        assert_eq!(extract("#{ x => x + 1 }", "x + 1"), None);
+        assert_eq!(extract("#{ a and f(b) }", "f(b)"), None);
+        // The text in markup and the expressions in math.
+        assert_eq!(extract("Hello world", "world"), None);
+        assert_eq!(extract("$x + 1$", "x + 1"), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! The refactorings converting between the forms of `#import`, i.e. importing
+//! a module, importing items explicitly, and importing all items with a
+//! wildcard.
+
+use std::{collections::HashSet, ops::Range};
+
+use typst::syntax::is_ident;
+
+use super::*;
+use crate::{
+    analysis::DefUseInfo,
+    syntax::{find_source_by_import_path, IdentRef},
+};
+
+/// Rewrites the import under the cursor to the other forms, returning the
+/// titles and the edits of the rewrites.
+///
+/// The module imports and the wildcard imports are rewritten to import the
+/// used items explicitly, and the item imports and the wildcard imports are
+/// rewritten to import the module, in which case the usages are qualified by
+/// the module name, e.g. `a(..)` becomes `m.a(..)`.
+pub(super) fn rewrite_import(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    cursor: usize,
+) -> Vec<(&'static str, Vec<(Range<usize>, String)>)> {
+    let root = LinkedNode::new(source.root());
+    let Some(node) = find_import(&root, cursor) else {
+        return vec![];
+    };
+    let Some(import) = node.cast::<ast::ModuleImport>() else {
+        return vec![];
+    };
+    let ast::Expr::Str(path) = import.source() else {
+        return vec![];
+    };
+    let Some(path_node) = node.find(path.span()) else {
+        return vec![];
+    };
+    let Some(ext) = find_source_by_import_path(ctx.world(), source.id(), &path.get()) else {
+        return vec![];
+    };
+    let (Some(def_use), Some(ext_def_use)) = (ctx.def_use(source.clone()), ctx.def_use(ext)) else {
+        return vec![];
+    };
+
+    let rewriter = ImportRewriter {
+        source,
+        root: &root,
+        node: &node,
+        path_node: &path_node,
+        def_use: &def_use,
+    };
+    let mut actions = vec![];
+    match import.imports() {
+        None => actions.extend(rewriter.explicit_from_module(import)),
+        Some(ast::Imports::Wildcard) => {
+            let used = rewriter.used_items(&ext_def_use);
+            actions.extend(rewriter.explicit_from_wildcard(&used));
+            actions.extend(rewriter.qualify(import, &used));
+        }
+        Some(ast::Imports::Items(..)) => {
+            let used = rewriter.used_items(&ext_def_use);
+            actions.extend(rewriter.qualify(import, &used));
+        }
+    }
+    actions
+}
+
+/// Finds the import containing the cursor.
+fn find_import<'a>(node: &LinkedNode<'a>, cursor: usize) -> Option<LinkedNode<'a>> {
+    if node.kind() == SyntaxKind::ModuleImport {
+        return Some(node.clone());
+    }
+    node.children()
+        .filter(|child| child.range().start <= cursor && cursor <= child.range().end)
+        .find_map(|child| find_import(&child, cursor))
+}
+
+struct ImportRewriter<'a, 'b> {
+    source: &'a Source,
+    root: &'a LinkedNode<'b>,
+    node: &'a LinkedNode<'b>,
+    path_node: &'a LinkedNode<'b>,
+    def_use: &'a DefUseInfo,
+}
+
+impl ImportRewriter<'_, '_> {
+    /// Rewrites `#import "m.typ"` to `#import "m.typ": a, b`, where the items
+    /// are the fields accessed on the module, e.g. `m.a`.
+    fn explicit_from_module(
+        &self,
+        import: ast::ModuleImport,
+    ) -> Option<(&'static str, Vec<(Range<usize>, String)>)> {
+        // The module is defined at its new name or at the path.
+        let (name, range) = match import.new_name() {
+            Some(name) => (name.get().to_string(), self.node.find(name.span())?.range()),
+            None => (module_name(&import)?, self.path_node.range()),
+        };
+        let (id, _) = self
+            .def_use
+            .get_def(self.source.id(), &IdentRef { name, range })?;
+
+        let mut names = vec![];
+        let mut edits = vec![];
+        for site in self.def_use.get_refs(id) {
+            let ident = self.root.leaf_at(site.range.start + 1)?;
+            let access = ident.parent()?;
+            let field = access.cast::<ast::FieldAccess>()?;
+            if field.target().span() != ident.span() {
+                return None;
+            }
+            let field = field.field().get().to_string();
+            edits.push((access.range(), field.clone()));
+            names.push(field);
+        }
+
+        let items = self.explicit_items(names)?;
+        let import_end = self.node.range().end;
+        edits.push((self.path_node.range().end..import_end, format!(": {items}")));
+        Some(("Import used symbols explicitly", edits))
+    }
+
+    /// Rewrites `#import "m.typ": *` to `#import "m.typ": a, b`, where the
+    /// items are the used ones.
+    fn explicit_from_wildcard(
+        &self,
+        used: &[(Range<usize>, String)],
+    ) -> Option<(&'static str, Vec<(Range<usize>, String)>)> {
+        let star = self
+            .node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::Star)?;
+        let names = used.iter().map(|(_, name)| name.clone()).collect();
+        let items = self.explicit_items(names)?;
+        Some((
+            "Import used symbols explicitly",
+            vec![(star.range(), items)],
+        ))
+    }
+
+    /// Rewrites `#import "m.typ": a` or `#import "m.typ": *` to
+    /// `#import "m.typ"`, qualifying the usages of the items by the module
+    /// name.
+    fn qualify(
+        &self,
+        import: ast::ModuleImport,
+        used: &[(Range<usize>, String)],
+    ) -> Option<(&'static str, Vec<(Range<usize>, String)>)> {
+        let new_name = import.new_name().map(|name| name.get().to_string());
+        let module = match &new_name {
+            Some(name) => name.clone(),
+            None => module_name(&import)?,
+        };
+        if self.defines(&module) {
+            return None;
+        }
+
+        let mut edits = used
+            .iter()
+            .map(|(range, name)| (range.clone(), format!("{module}.{name}")))
+            .collect::<Vec<_>>();
+
+        // The usages of the renamed items are qualified by their original
+        // names.
+        if let Some(ast::Imports::Items(items)) = import.imports() {
+            for item in items.iter() {
+                let ast::ImportItem::Renamed(renamed) = item else {
+                    continue;
+                };
+                let alias = IdentRef {
+                    name: renamed.new_name().get().to_string(),
+                    range: self.node.find(renamed.new_name().span())?.range(),
+                };
+                let (id, _) = self.def_use.get_def(self.source.id(), &alias)?;
+                let original = renamed.original_name().get();
+                let refs = self.def_use.get_refs(id);
+                edits.extend(refs.map(|site| (site.range.clone(), format!("{module}.{original}"))));
+            }
+        }
+
+        let import_end = self.node.range().end;
+        let new_name = new_name.map(|name| format!(" as {name}"));
+        edits.push((
+            self.path_node.range().end..import_end,
+            new_name.unwrap_or_default(),
+        ));
+        Some(("Qualify usages and import module", edits))
+    }
+
+    /// Collects the usages of the items exported by the imported file, outside
+    /// of the import itself.
+    fn used_items(&self, ext_def_use: &DefUseInfo) -> Vec<(Range<usize>, String)> {
+        let exported = ext_def_use
+            .ident_defs
+            .keys()
+            .enumerate()
+            .filter(|(idx, _)| ext_def_use.is_exported(DefId(*idx as u64)))
+            .map(|(_, key)| key)
+            .collect::<HashSet<_>>();
+
+        let import_range = self.node.range();
+        let mut used = vec![];
+        for (site, id) in &self.def_use.ident_refs {
+            if import_range.contains(&site.range.start) {
+                continue;
+            }
+            let Some((key, def)) = self.def_use.ident_defs.get_index(id.0 as usize) else {
+                continue;
+            };
+            if exported.contains(key) {
+                used.push((site.range.clone(), def.name.clone()));
+            }
+        }
+        used.sort_by_key(|(range, _)| range.start);
+        used
+    }
+
+    /// Formats the items to import explicitly, which must not conflict with
+    /// the definitions in the current file.
+    fn explicit_items(&self, mut names: Vec<String>) -> Option<String> {
+        names.sort();
+        names.dedup();
+        if names.is_empty() || names.iter().any(|name| self.defines(name)) {
+            return None;
+        }
+        Some(names.join(", "))
+    }
+
+    /// Checks whether the current file defines a name, excluding the import.
+    fn defines(&self, name: &str) -> bool {
+        let import_range = self.node.range();
+        self.def_use.ident_defs.iter().any(|((fid, ident), _)| {
+            *fid == self.source.id()
+                && ident.name == name
+                && !import_range.contains(&ident.range.start)
+        })
+    }
+}
+
+/// Gets the name bound by importing a module without a new name, i.e. the
+/// file stem of the path or the name of the package.
+fn module_name(import: &ast::ModuleImport) -> Option<String> {
+    let ast::Expr::Str(path) = import.source() else {
+        return None;
+    };
+    let path = path.get();
+    let name = match path.parse::<PackageSpec>() {
+        Ok(spec) => spec.name.to_string(),
+        Err(..) => Path::new(path.as_str())
+            .file_name()?
+            .to_str()?
+            .strip_suffix(".typ")?
+            .to_owned(),
+    };
+    is_ident(&name).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_name() {
+        let name = |text: &str| {
+            let source = Source::detached(text);
+            let root = LinkedNode::new(source.root());
+            let import = find_import(&root, 2)?;
+            module_name(&import.cast::<ast::ModuleImport>()?)
+        };
+
+        assert_eq!(name("#import \"utils/math.typ\""), Some("math".into()));
+        assert_eq!(name("#import \"@preview/cetz:0.2.2\""), Some("cetz".into()));
+        assert_eq!(name("#import \"2d.typ\""), None);
+        assert_eq!(name("#import \"data.json\""), None);
+    }
+}
@@ -0,0 +1,435 @@
+//! The refactorings of markup, e.g. wrapping a selection in strong emphasis,
+//! changing the level of a heading or a whole section, converting a list to an
+//! enumeration, and converting an inline equation to a block equation.
+
+use std::ops::Range;
+
+use super::*;
+
+/// Rewrites the markup at the selection, returning the titles and the edits
+/// of the rewrites. The edits only insert or replace the markers, so that
+/// the selected text and its indentation are preserved.
+pub(super) fn rewrite_markup(
+    source: &Source,
+    range: Range<usize>,
+) -> Vec<(&'static str, Vec<(Range<usize>, String)>)> {
+    let mut actions = vec![];
+    wrap_selection(source, range.clone(), &mut actions);
+
+    let root = LinkedNode::new(source.root());
+    if let Some(heading) = find_ancestor(&root, range.start, &[SyntaxKind::Heading]) {
+        change_heading_level(&heading, &mut actions);
+        change_section_level(&heading, &mut actions);
+    }
+    let items = [SyntaxKind::ListItem, SyntaxKind::EnumItem];
+    if let Some(item) = find_ancestor(&root, range.start, &items) {
+        convert_list(&item, &mut actions);
+    }
+    if let Some(equation) = find_ancestor(&root, range.start, &[SyntaxKind::Equation]) {
+        toggle_equation(source, &equation, &mut actions);
+    }
+    actions
+}
+
+/// Wraps the selected markup in a figure, strong emphasis, or emphasis. The
+/// selection must cover whole elements of the same markup.
+fn wrap_selection(
+    source: &Source,
+    range: Range<usize>,
+    actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+) -> Option<()> {
+    let text = source.text();
+    let selected = text.get(range.clone())?;
+    let start = range.start + (selected.len() - selected.trim_start().len());
+    let end = range.end - (selected.len() - selected.trim_end().len());
+    if start >= end {
+        return None;
+    }
+
+    let root = LinkedNode::new(source.root());
+    let markup = enclosing_markup(&root, &(start..end))?;
+    // The embedded expressions are wrapped with their hashes.
+    let starts = markup.children().any(|child| {
+        child.range().start == start && child.prev_sibling_kind() != Some(SyntaxKind::Hash)
+    });
+    let ends = markup.children().any(|child| child.range().end == end);
+    if !starts || !ends {
+        return None;
+    }
+
+    let parent = markup.parent_kind();
+    let in_block = matches!(
+        parent,
+        None | Some(SyntaxKind::ContentBlock | SyntaxKind::ListItem | SyntaxKind::EnumItem)
+    );
+    if in_block {
+        // The selected lines are moved into the figure with their
+        // indentation.
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i);
+        let indent = &text[line_start..start];
+        let whole_lines = indent.trim().is_empty() && text[end..line_end].trim().is_empty();
+        let (open, close) = if whole_lines && text[start..end].contains('\n') {
+            (
+                format!("#figure([\n{indent}"),
+                format!("\n{indent}], caption: [])"),
+            )
+        } else {
+            ("#figure([".to_owned(), "], caption: [])".to_owned())
+        };
+        actions.push((
+            "Wrap in figure",
+            vec![(start..start, open), (end..end, close)],
+        ));
+    }
+
+    // The strong emphasis and emphasis can neither span paragraphs nor be
+    // nested in themselves.
+    let spans_paragraphs = markup.children().any(|child| {
+        child.kind() == SyntaxKind::Parbreak && start <= child.offset() && child.offset() < end
+    });
+    if spans_paragraphs {
+        return Some(());
+    }
+    if parent != Some(SyntaxKind::Strong) {
+        let edits = vec![(start..start, "*".to_owned()), (end..end, "*".to_owned())];
+        actions.push(("Wrap in strong", edits));
+    }
+    if parent != Some(SyntaxKind::Emph) {
+        let edits = vec![(start..start, "_".to_owned()), (end..end, "_".to_owned())];
+        actions.push(("Wrap in emphasis", edits));
+    }
+    Some(())
+}
+
+/// Raises or lowers the level of a heading by its marker.
+fn change_heading_level(
+    heading: &LinkedNode,
+    actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+) {
+    let Some(marker) = heading
+        .children()
+        .find(|child| child.kind() == SyntaxKind::HeadingMarker)
+    else {
+        return;
+    };
+
+    let start = marker.offset();
+    if marker.len() > 1 {
+        actions.push((
+            "Raise heading level",
+            vec![(start..start + 1, String::new())],
+        ));
+    }
+    actions.push(("Lower heading level", vec![(start..start, "=".to_owned())]));
+}
+
+/// Promotes or demotes a heading along with its subsections, i.e. the
+/// following headings of the same markup that are deeper than it, keeping
+/// their relative nesting. The actions are only offered if the heading has
+/// subsections.
+fn change_section_level(
+    heading: &LinkedNode,
+    actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+) {
+    let depth = |node: &LinkedNode| Some(node.cast::<ast::Heading>()?.depth().get());
+    let marker = |node: &LinkedNode| {
+        let marker = node
+            .children()
+            .find(|c| c.kind() == SyntaxKind::HeadingMarker);
+        marker.map(|marker| marker.offset())
+    };
+    let Some(level) = depth(heading) else {
+        return;
+    };
+
+    let mut markers = vec![];
+    markers.extend(marker(heading));
+    let mut next = heading.next_sibling();
+    while let Some(node) = next {
+        if node.kind() == SyntaxKind::Heading {
+            match depth(&node) {
+                Some(depth) if depth > level => markers.extend(marker(&node)),
+                _ => break,
+            }
+        }
+        next = node.next_sibling();
+    }
+    if markers.len() < 2 {
+        return;
+    }
+
+    if level > 1 {
+        let edits = markers
+            .iter()
+            .map(|&start| (start..start + 1, String::new()));
+        actions.push(("Promote heading and its subsections", edits.collect()));
+    }
+    let edits = markers.iter().map(|&start| (start..start, "=".to_owned()));
+    actions.push(("Demote heading and its subsections", edits.collect()));
+}
+
+/// Converts the items of a list to the items of an enumeration, or vice
+/// versa. The adjacent items at the same level are converted together.
+fn convert_list(item: &LinkedNode, actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>) {
+    let Some(markup) = item.parent() else {
+        return;
+    };
+    let children = markup.children().collect::<Vec<_>>();
+    let Some(index) = children
+        .iter()
+        .position(|child| child.offset() == item.offset())
+    else {
+        return;
+    };
+
+    let kind = item.kind();
+    let in_list = |child: &LinkedNode| {
+        matches!(child.kind(), SyntaxKind::Space | SyntaxKind::Parbreak) || child.kind() == kind
+    };
+    let first = children[..index]
+        .iter()
+        .rposition(|child| !in_list(child))
+        .map_or(0, |i| i + 1);
+    let last = children[index..]
+        .iter()
+        .position(|child| !in_list(child))
+        .map_or(children.len(), |i| index + i);
+
+    let (title, marker) = match kind {
+        SyntaxKind::ListItem => ("Convert to numbered list", "+"),
+        _ => ("Convert to bullet list", "-"),
+    };
+    let edits = children[first..last]
+        .iter()
+        .filter(|child| child.kind() == kind)
+        .filter_map(|child| {
+            let marker_node = child.children().next()?;
+            Some((marker_node.range(), marker.to_owned()))
+        });
+    actions.push((title, edits.collect()));
+}
+
+/// Converts an inline equation to a block equation, e.g. `$x$` to `$ x $`,
+/// or vice versa, and labels an equation for referencing, e.g. `<eq:1>`.
+fn toggle_equation(
+    source: &Source,
+    equation: &LinkedNode,
+    actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+) {
+    let Some(block) = equation.cast::<ast::Equation>().map(|eq| eq.block()) else {
+        return;
+    };
+    let text = source.text();
+    let range = equation.range();
+    if range.len() < 2
+        || !text[range.clone()].starts_with('$')
+        || !text[range.clone()].ends_with('$')
+    {
+        return;
+    }
+    let (open, close) = (range.start + 1, range.end - 1);
+    let body = &text[open..close];
+    if body.trim().is_empty() {
+        return;
+    }
+
+    // The block equations are spaced inside the dollars.
+    let to_block = {
+        let mut edits = vec![];
+        if !body.starts_with(char::is_whitespace) {
+            edits.push((open..open, " ".to_owned()));
+        }
+        if !body.ends_with(char::is_whitespace) {
+            edits.push((close..close, " ".to_owned()));
+        }
+        edits
+    };
+    if block {
+        let leading = body.len() - body.trim_start().len();
+        let trailing = body.len() - body.trim_end().len();
+        let edits = vec![
+            (open..open + leading, String::new()),
+            (close - trailing..close, String::new()),
+        ];
+        actions.push(("Convert to inline equation", edits));
+    } else {
+        actions.push(("Convert to block equation", to_block.clone()));
+    }
+
+    if is_labeled(equation) {
+        return;
+    }
+    let label = (1..)
+        .map(|i| format!("eq:{i}"))
+        .find(|label| !text.contains(&format!("<{label}>")))
+        .unwrap_or_default();
+    let mut edits = to_block;
+    edits.push((range.end..range.end, format!(" <{label}>")));
+    let title = if block {
+        "Label equation for referencing"
+    } else {
+        "Extract into labeled block equation"
+    };
+    actions.push((title, edits));
+}
+
+/// Checks whether a label follows a node, e.g. `$ x $ <eq:1>`.
+fn is_labeled(node: &LinkedNode) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let mut siblings = parent.children().skip(node.index() + 1);
+    siblings
+        .find(|sibling| sibling.kind() != SyntaxKind::Space || sibling.text().contains('\n'))
+        .is_some_and(|sibling| sibling.kind() == SyntaxKind::Label)
+}
+
+/// Finds the innermost markup containing a range.
+fn enclosing_markup<'a>(node: &LinkedNode<'a>, range: &Range<usize>) -> Option<LinkedNode<'a>> {
+    let inner = node
+        .children()
+        .find(|child| child.range().start <= range.start && range.end <= child.range().end)
+        .and_then(|child| enclosing_markup(&child, range));
+    inner.or_else(|| (node.kind() == SyntaxKind::Markup).then(|| node.clone()))
+}
+
+/// Finds the closest ancestor of the kinds at the cursor, which may be at the
+/// end of the line.
+pub(super) fn find_ancestor<'a>(
+    root: &LinkedNode<'a>,
+    cursor: usize,
+    kinds: &[SyntaxKind],
+) -> Option<LinkedNode<'a>> {
+    [cursor + 1, cursor].into_iter().find_map(|cursor| {
+        let mut node = root.leaf_at(cursor)?;
+        while !kinds.contains(&node.kind()) {
+            node = node.parent()?.clone();
+        }
+        Some(node)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(text: &str, selected: &str, title: &str) -> Option<String> {
+        let source = Source::detached(text);
+        let start = text.find(selected).unwrap();
+        let actions = rewrite_markup(&source, start..start + selected.len());
+        let (_, mut edits) = actions.into_iter().find(|(t, _)| *t == title)?;
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+        let mut text = text.to_owned();
+        for (range, new_text) in edits {
+            text.replace_range(range, &new_text);
+        }
+        Some(text)
+    }
+
+    #[test]
+    fn test_wrap_selection() {
+        let text = "Hello world and #emoji.face!";
+        assert_eq!(
+            rewrite(text, "world", "Wrap in strong").as_deref(),
+            Some("Hello *world* and #emoji.face!")
+        );
+        assert_eq!(
+            rewrite(text, "#emoji.face", "Wrap in emphasis").as_deref(),
+            Some("Hello world and _#emoji.face_!")
+        );
+        assert_eq!(rewrite(text, "emoji.face", "Wrap in strong"), None);
+        assert_eq!(rewrite(text, "orld", "Wrap in strong"), None);
+        assert_eq!(rewrite("*Hello world*", "world", "Wrap in strong"), None);
+
+        assert_eq!(
+            rewrite(
+                "- item\n  Hello\n  world\n",
+                "Hello\n  world",
+                "Wrap in figure"
+            )
+            .as_deref(),
+            Some("- item\n  #figure([\n  Hello\n  world\n  ], caption: [])\n")
+        );
+        assert_eq!(
+            rewrite("Hello\n\nworld", "Hello\n\nworld", "Wrap in strong"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_change_heading_level() {
+        let text = "== Introduction";
+        assert_eq!(
+            rewrite(text, "Intro", "Raise heading level").as_deref(),
+            Some("= Introduction")
+        );
+        assert_eq!(
+            rewrite(text, "Intro", "Lower heading level").as_deref(),
+            Some("=== Introduction")
+        );
+        assert_eq!(
+            rewrite("= Introduction", "Intro", "Raise heading level"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_change_section_level() {
+        let text = "== A\n=== B\n==== C\n=== D\n== E\n=== F";
+        assert_eq!(
+            rewrite(text, "A", "Promote heading and its subsections").as_deref(),
+            Some("= A\n== B\n=== C\n== D\n== E\n=== F")
+        );
+        assert_eq!(
+            rewrite(text, "B", "Demote heading and its subsections").as_deref(),
+            Some("== A\n==== B\n===== C\n=== D\n== E\n=== F")
+        );
+        assert_eq!(
+            rewrite(text, "D", "Demote heading and its subsections"),
+            None
+        );
+        assert_eq!(
+            rewrite("= A\n== B", "A", "Promote heading and its subsections"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_convert_list() {
+        let text = "Text\n- a\n  - nested\n- b\n\n- c\n";
+        assert_eq!(
+            rewrite(text, "b", "Convert to numbered list").as_deref(),
+            Some("Text\n+ a\n  - nested\n+ b\n\n+ c\n")
+        );
+        assert_eq!(
+            rewrite("1. a\n+ b", "a", "Convert to bullet list").as_deref(),
+            Some("- a\n- b")
+        );
+    }
+
+    #[test]
+    fn test_toggle_equation() {
+        let text = "where $x^2$ is positive";
+        assert_eq!(
+            rewrite(text, "x^2", "Convert to block equation").as_deref(),
+            Some("where $ x^2 $ is positive")
+        );
+        assert_eq!(
+            rewrite(text, "x^2", "Extract into labeled block equation").as_deref(),
+            Some("where $ x^2 $ <eq:1> is positive")
+        );
+
+        let text = "$\n  x^2\n$ <eq:1>";
+        assert_eq!(
+            rewrite(text, "x^2", "Convert to inline equation").as_deref(),
+            Some("$x^2$ <eq:1>")
+        );
+        assert_eq!(rewrite(text, "x^2", "Label equation for referencing"), None);
+        assert_eq!(
+            rewrite("$ x $ <eq:1>\n$ y $", "y", "Label equation for referencing").as_deref(),
+            Some("$ x $ <eq:1>\n$ y $ <eq:2>")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! The source action organizing the imports at the top level.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use super::*;
+use crate::unused_imports;
+
+/// The imports of a module merged from the import statements.
+#[derive(Default)]
+struct MergedImport {
+    /// The quoted path of the module, as written in the source.
+    quoted: EcoString,
+    /// Whether the module itself is imported.
+    module: bool,
+    /// Whether all items are imported.
+    wildcard: bool,
+    /// The imported items and their new names.
+    items: Vec<(EcoString, Option<EcoString>)>,
+}
+
+/// Organizes the consecutive import statements at the top level of a source,
+/// returning the edits replacing them.
+///
+/// The imports of the same module are merged, the unused items are removed,
+/// and the items are sorted alphabetically. The package imports are placed
+/// before the file imports.
+pub(super) fn organize_imports(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+) -> Vec<(Range<usize>, String)> {
+    let unused = unused_imports(ctx, source);
+    let root = LinkedNode::new(source.root());
+    let text = source.text();
+
+    let mut edits = vec![];
+    for run in import_runs(&root) {
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            continue;
+        };
+        // The range starts at the hash of the first import.
+        let start = first.offset() - 1;
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &text[line_start..start];
+        let indent = if indent.trim().is_empty() { indent } else { "" };
+
+        let Some(organized) = organize_run(&run, &unused, indent) else {
+            continue;
+        };
+        let range = start..last.range().end;
+        if text[range.clone()] != organized {
+            edits.push((range, organized));
+        }
+    }
+    edits
+}
+
+/// Collects the runs of import statements at the top level, which are only
+/// separated by spaces.
+fn import_runs<'a>(root: &LinkedNode<'a>) -> Vec<Vec<LinkedNode<'a>>> {
+    let mut runs = vec![];
+    let mut run = vec![];
+    for child in root.children() {
+        let is_separator = matches!(
+            child.kind(),
+            SyntaxKind::Hash | SyntaxKind::Space | SyntaxKind::Parbreak | SyntaxKind::Semicolon
+        );
+        if child.kind() == SyntaxKind::ModuleImport {
+            run.push(child);
+        } else if !is_separator && !run.is_empty() {
+            runs.push(std::mem::take(&mut run));
+        }
+    }
+    if !run.is_empty() {
+        runs.push(run);
+    }
+    runs
+}
+
+/// Organizes a run of import statements. Returns `None` if an import is not
+/// of a path, e.g. `#import mod`.
+fn organize_run(run: &[LinkedNode], unused: &[Range<usize>], indent: &str) -> Option<String> {
+    // The package imports are sorted before the file imports.
+    let mut merged = BTreeMap::<_, MergedImport>::new();
+    for node in run {
+        let import = node.cast::<ast::ModuleImport>()?;
+        let ast::Expr::Str(path) = import.source() else {
+            return None;
+        };
+        let path_node = node.find(path.span())?;
+        let path = path.get();
+        let new_name = import.new_name();
+        let key = (
+            !path.starts_with('@'),
+            path,
+            new_name.map(|name| name.get().clone()),
+        );
+        let entry = merged.entry(key).or_default();
+        entry.quoted = path_node.text().clone();
+
+        let is_unused = |ident: ast::Ident| {
+            let range = node.find(ident.span()).map(|ident| ident.range());
+            range.is_some_and(|range| unused.contains(&range))
+        };
+        match import.imports() {
+            None => {
+                // The module is defined at its new name or at the path.
+                let unused_module = match new_name {
+                    Some(name) => is_unused(name),
+                    None => unused.contains(&path_node.range()),
+                };
+                entry.module |= !unused_module;
+            }
+            Some(ast::Imports::Wildcard) => entry.wildcard = true,
+            Some(ast::Imports::Items(items)) => {
+                for item in items.iter() {
+                    match item {
+                        ast::ImportItem::Simple(name) if !is_unused(name) => {
+                            entry.items.push((name.get().clone(), None));
+                        }
+                        ast::ImportItem::Renamed(renamed) if !is_unused(renamed.new_name()) => {
+                            let new_name = renamed.new_name().get().clone();
+                            entry
+                                .items
+                                .push((renamed.original_name().get().clone(), Some(new_name)));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    for ((_, _, new_name), mut entry) in merged {
+        let mut head = format!("#import {}", entry.quoted);
+        if let Some(new_name) = new_name {
+            head.push_str(&format!(" as {new_name}"));
+        }
+
+        if entry.module {
+            lines.push(head.clone());
+        }
+        if entry.wildcard {
+            lines.push(format!("{head}: *"));
+            // The wildcard imports the items except the renamed ones.
+            entry.items.retain(|(_, new_name)| new_name.is_some());
+        }
+        entry.items.sort();
+        entry.items.dedup();
+        if !entry.items.is_empty() {
+            let items = entry.items.iter().map(|(name, new_name)| match new_name {
+                Some(new_name) => format!("{name} as {new_name}"),
+                None => name.to_string(),
+            });
+            lines.push(format!("{head}: {}", items.collect::<Vec<_>>().join(", ")));
+        }
+    }
+
+    Some(lines.join(&format!("\n{indent}")))
+}
@@ -0,0 +1,108 @@
+//! The generation of a show rule stub for the element at the cursor.
+
+use std::ops::Range;
+
+use super::*;
+use crate::analysis::resolve_global_value;
+
+/// Creates a show rule for the element at the cursor, which is either a
+/// reference to an element function, e.g. `heading` in `#heading[..]`, or an
+/// element in markup, e.g. `= Heading`. Returns the title and the edits.
+///
+/// The show rule is inserted before the first usage of the element in the
+/// file, at the top level so that it applies to the rest of the file, or at
+/// the top of the file if the element is not used yet.
+pub(super) fn create_show_rule(
+    ctx: &AnalysisContext,
+    source: &Source,
+    cursor: usize,
+) -> Option<(String, Vec<(Range<usize>, String)>)> {
+    let root = LinkedNode::new(source.root());
+    let name = element_at(ctx, &root, cursor)?;
+
+    let text = source.text();
+    let statement = first_usage(ctx, &root, &name).and_then(|first| {
+        root.children()
+            .find(|child| child.range().start <= first && first < child.range().end)
+    });
+    let mut insert_at = statement.as_ref().map_or(0, |statement| statement.offset());
+    if statement.and_then(|statement| statement.prev_sibling_kind()) == Some(SyntaxKind::Hash) {
+        insert_at -= 1;
+    }
+
+    let line_start = text[..insert_at].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..insert_at];
+    let indent = if indent.trim().is_empty() { indent } else { "" };
+    let stub = format!("#show {name}: it => {{ it }}\n{indent}");
+    Some((
+        format!("Create show rule for `{name}`"),
+        vec![(insert_at..insert_at, stub)],
+    ))
+}
+
+/// Gets the name of the element at the cursor.
+fn element_at(ctx: &AnalysisContext, root: &LinkedNode, cursor: usize) -> Option<EcoString> {
+    let mut node = root.leaf_at(cursor + 1)?;
+    loop {
+        if let Some(name) = markup_element(node.kind()) {
+            return Some(name.into());
+        }
+        if let Some(callee) = element_func(ctx, &node) {
+            return Some(callee);
+        }
+        node = node.parent()?.clone();
+    }
+}
+
+/// Gets the name of an element written in markup.
+fn markup_element(kind: SyntaxKind) -> Option<&'static str> {
+    Some(match kind {
+        SyntaxKind::Heading => "heading",
+        SyntaxKind::Strong => "strong",
+        SyntaxKind::Emph => "emph",
+        SyntaxKind::ListItem => "list",
+        SyntaxKind::EnumItem => "enum",
+        SyntaxKind::TermItem => "terms",
+        SyntaxKind::Raw => "raw",
+        SyntaxKind::Link => "link",
+        SyntaxKind::Ref => "ref",
+        SyntaxKind::Equation => "math.equation",
+        _ => return None,
+    })
+}
+
+/// Gets the name of a reference to an element function, e.g. `heading` or
+/// `math.equation`.
+fn element_func(ctx: &AnalysisContext, node: &LinkedNode) -> Option<EcoString> {
+    // The field of an access is resolved with its target.
+    let access = node
+        .parent()
+        .and_then(|parent| parent.cast::<ast::FieldAccess>());
+    if access.is_some_and(|access| access.field().span() == node.span()) {
+        return None;
+    }
+    if !matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::FieldAccess) {
+        return None;
+    }
+    match resolve_global_value(ctx, node.clone(), false)? {
+        Value::Func(func) if func.element().is_some() => {
+            Some(node.get().clone().into_text().trim().into())
+        }
+        _ => None,
+    }
+}
+
+/// Finds the offset of the first usage of an element, either in markup or
+/// by calling its function.
+fn first_usage(ctx: &AnalysisContext, node: &LinkedNode, name: &str) -> Option<usize> {
+    let is_usage = markup_element(node.kind()) == Some(name)
+        || node.cast::<ast::FuncCall>().is_some_and(|call| {
+            let callee = node.find(call.callee().span());
+            callee.is_some_and(|callee| element_func(ctx, &callee).as_deref() == Some(name))
+        });
+    if is_usage {
+        return Some(node.offset());
+    }
+    node.children()
+        .find_map(|child| first_usage(ctx, &child, name))
+}
@@ -0,0 +1,434 @@
+//! The editing of tables and grids, i.e. inserting and deleting the rows and
+//! the columns at the cell under the cursor, and aligning the cells in a grid
+//! with one row per line.
+
+use std::ops::Range;
+
+use super::*;
+
+/// Edits the `table` or `grid` call at the cursor, returning the titles and
+/// the edits of the actions. The rows and the columns are computed from the
+/// `columns` argument, so that the actions are only offered if it is a
+/// literal and the cells span a single row and column each.
+pub(super) fn edit_table(
+    source: &Source,
+    cursor: usize,
+) -> Vec<(&'static str, Vec<(Range<usize>, String)>)> {
+    let mut actions = vec![];
+    let root = LinkedNode::new(source.root());
+    let Some(table) = table_at(&root, cursor) else {
+        return actions;
+    };
+
+    let text = source.text();
+    let full = !table.cells.is_empty() && table.cells.len() % table.cols == 0;
+    let cell = table.cells.iter().position(|&idx| {
+        let range = table.args[idx].range();
+        range.start <= cursor && cursor <= range.end
+    });
+    if let Some(cell) = cell.filter(|_| full) {
+        table.edit_rows(text, cell / table.cols, &mut actions);
+        table.edit_columns(cell % table.cols, &mut actions);
+    }
+    if let Some(edit) = table.align(text) {
+        actions.push(("Align table cells", vec![edit]));
+    }
+    actions
+}
+
+/// A `table` or `grid` call of which the cells are laid out by its columns.
+struct Table<'a> {
+    /// The parentheses of the arguments.
+    parens: (LinkedNode<'a>, LinkedNode<'a>),
+    /// The arguments in the parentheses.
+    args: Vec<LinkedNode<'a>>,
+    /// The indices of the cells in the arguments, i.e. the positional
+    /// arguments except the lines.
+    cells: Vec<usize>,
+    /// The value of the `columns` argument, if it is given.
+    columns: Option<LinkedNode<'a>>,
+    /// The number of the columns.
+    cols: usize,
+    /// Whether there are comments in the arguments, which are lost by
+    /// aligning the cells.
+    commented: bool,
+}
+
+/// Finds the innermost table or grid enclosing the cursor.
+fn table_at<'a>(root: &LinkedNode<'a>, cursor: usize) -> Option<Table<'a>> {
+    let mut node = root.leaf_at(cursor + 1).or_else(|| root.leaf_at(cursor))?;
+    loop {
+        if let Some(table) = parse_table(&node) {
+            return Some(table);
+        }
+        node = node.parent()?.clone();
+    }
+}
+
+fn parse_table<'a>(node: &LinkedNode<'a>) -> Option<Table<'a>> {
+    let call = node.cast::<ast::FuncCall>()?;
+    let ast::Expr::Ident(callee) = call.callee() else {
+        return None;
+    };
+    if !matches!(callee.as_str(), "table" | "grid") {
+        return None;
+    }
+
+    let args = node.children().find(|c| c.kind() == SyntaxKind::Args)?;
+    let mut children = args.children();
+    let left = children
+        .next()
+        .filter(|c| c.kind() == SyntaxKind::LeftParen)?;
+    let mut table = Table {
+        parens: (left.clone(), left),
+        args: vec![],
+        cells: vec![],
+        columns: None,
+        cols: 1,
+        commented: false,
+    };
+    let mut closed = false;
+    for child in children {
+        match child.kind() {
+            SyntaxKind::Comma | SyntaxKind::Space => {}
+            SyntaxKind::LineComment | SyntaxKind::BlockComment => table.commented = true,
+            // The trailing content blocks are not edited.
+            _ if closed => return None,
+            SyntaxKind::RightParen => {
+                table.parens.1 = child;
+                closed = true;
+            }
+            SyntaxKind::Named => {
+                let named = child.cast::<ast::Named>()?;
+                if named.name().as_str() == "columns" {
+                    table.columns = Some(child.find(named.expr().span())?);
+                }
+                table.args.push(child);
+            }
+            SyntaxKind::Spread => return None,
+            _ => {
+                if !is_line(&child)? {
+                    table.cells.push(table.args.len());
+                }
+                table.args.push(child);
+            }
+        }
+    }
+
+    table.cols = match table.columns.as_ref().map(|c| c.cast::<ast::Expr>()) {
+        None => 1,
+        Some(Some(ast::Expr::Int(cols))) => usize::try_from(cols.get()).ok()?,
+        Some(Some(ast::Expr::Array(cols))) => cols.items().count(),
+        Some(_) => return None,
+    };
+    (closed && table.cols > 0).then_some(table)
+}
+
+/// Checks whether a positional argument is a line, e.g. `table.hline()`.
+/// Returns `None` if the cells are not laid out by the columns, e.g. if a
+/// cell spans multiple columns.
+fn is_line(arg: &LinkedNode) -> Option<bool> {
+    let Some(call) = arg.cast::<ast::FuncCall>() else {
+        return Some(false);
+    };
+    let ast::Expr::FieldAccess(access) = call.callee() else {
+        return Some(false);
+    };
+    match access.field().as_str() {
+        "hline" | "vline" => Some(true),
+        "header" | "footer" => None,
+        "cell" => {
+            let spans = call.args().items().any(|arg| {
+                matches!(arg, ast::Arg::Named(named)
+                    if matches!(named.name().as_str(), "colspan" | "rowspan"))
+            });
+            (!spans).then_some(false)
+        }
+        _ => Some(false),
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Gets the node of the cell at an index.
+    fn cell(&self, idx: usize) -> &LinkedNode<'a> {
+        &self.args[self.cells[idx]]
+    }
+
+    /// Gets the range removing the cells from `first` to `last`, along with
+    /// the separators, keeping the line breaks between the rows.
+    fn removal(&self, first: usize, last: usize) -> Range<usize> {
+        let (first, last) = (self.cells[first], self.cells[last]);
+        match self.args.get(last + 1) {
+            Some(next) => self.args[first].offset()..next.offset(),
+            None if first > 0 => self.args[first - 1].range().end..self.args[last].range().end,
+            None => self.args[first].offset()..self.args[last].range().end,
+        }
+    }
+
+    /// Inserts a row above or below the row at the cursor, or deletes it.
+    fn edit_rows(
+        &self,
+        text: &str,
+        row: usize,
+        actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+    ) {
+        let rows = self.cells.len() / self.cols;
+        let new_row = vec!["[]"; self.cols].join(", ");
+        // The new row is put on its own line if the rows are, i.e. if only
+        // the indentation precedes the first cell of a row.
+        let indent = |row: usize| {
+            let at = self.cell(row * self.cols).offset();
+            let line_start = text[..at].rfind('\n').map_or(0, |idx| idx + 1);
+            let indent = &text[line_start..at];
+            indent.trim().is_empty().then_some(indent)
+        };
+        let insert_before = |row: usize| {
+            let at = self.cell(row * self.cols).offset();
+            match indent(row) {
+                Some(indent) => (at..at, format!("{new_row},\n{indent}")),
+                None => (at..at, format!("{new_row}, ")),
+            }
+        };
+
+        actions.push(("Insert table row above", vec![insert_before(row)]));
+        if row + 1 < rows {
+            actions.push(("Insert table row below", vec![insert_before(row + 1)]));
+        } else {
+            let at = self.cell(self.cells.len() - 1).range().end;
+            let inserted = match indent(row) {
+                Some(indent) => format!(",\n{indent}{new_row}"),
+                None => format!(", {new_row}"),
+            };
+            actions.push(("Insert table row below", vec![(at..at, inserted)]));
+        }
+        if rows > 1 {
+            let first = row * self.cols;
+            let range = self.removal(first, first + self.cols - 1);
+            actions.push(("Delete table row", vec![(range, String::new())]));
+        }
+    }
+
+    /// Inserts a column to the left or the right of the column at the
+    /// cursor, or deletes it, along with its track in the `columns` argument.
+    fn edit_columns(
+        &self,
+        col: usize,
+        actions: &mut Vec<(&'static str, Vec<(Range<usize>, String)>)>,
+    ) {
+        let rows = self.cells.len() / self.cols;
+        let cells = (0..rows).map(|row| row * self.cols + col);
+
+        let mut left = self
+            .insert_track(col, false)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut right = self.insert_track(col, true).into_iter().collect::<Vec<_>>();
+        for idx in cells.clone() {
+            let range = self.cell(idx).range();
+            left.push((range.start..range.start, "[], ".to_owned()));
+            right.push((range.end..range.end, ", []".to_owned()));
+        }
+        if left.len() > rows {
+            actions.push(("Insert table column left", left));
+            actions.push(("Insert table column right", right));
+        }
+
+        if self.cols > 1 {
+            let Some(track) = self.delete_track(col) else {
+                return;
+            };
+            let mut edits = vec![track];
+            for idx in cells {
+                // The last cell of a row is removed with the separator before
+                // it, to keep the line break after it.
+                let range = match col + 1 == self.cols {
+                    true => self.cell(idx - 1).range().end..self.cell(idx).range().end,
+                    false => self.cell(idx).offset()..self.cell(idx + 1).offset(),
+                };
+                edits.push((range, String::new()));
+            }
+            actions.push(("Delete table column", edits));
+        }
+    }
+
+    /// Gets the edit of the `columns` argument inserting a track.
+    fn insert_track(&self, col: usize, after: bool) -> Option<(Range<usize>, String)> {
+        let Some(columns) = &self.columns else {
+            let at = self.parens.0.range().end;
+            return Some((at..at, "columns: 2, ".to_owned()));
+        };
+        match columns.kind() {
+            SyntaxKind::Int => Some((columns.range(), (self.cols + 1).to_string())),
+            SyntaxKind::Array => {
+                let track = array_items(columns).nth(col)?.range();
+                Some(match after {
+                    true => (track.end..track.end, ", auto".to_owned()),
+                    false => (track.start..track.start, "auto, ".to_owned()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the edit of the `columns` argument deleting a track.
+    fn delete_track(&self, col: usize) -> Option<(Range<usize>, String)> {
+        let columns = self.columns.as_ref()?;
+        match columns.kind() {
+            SyntaxKind::Int => Some((columns.range(), (self.cols - 1).to_string())),
+            SyntaxKind::Array => {
+                let items = array_items(columns).collect::<Vec<_>>();
+                let range = match items.get(col + 1) {
+                    Some(next) => items[col].offset()..next.offset(),
+                    None => items[col - 1].range().end..items[col].range().end,
+                };
+                Some((range, String::new()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reformats the arguments as a grid with one row per line, in which the
+    /// cells of a column are aligned. Returns `None` if the arguments are
+    /// already aligned or can't be aligned.
+    fn align(&self, text: &str) -> Option<(Range<usize>, String)> {
+        let cell_text = |idx: usize| self.cell(idx).clone().into_text();
+        let cells = (0..self.cells.len()).map(cell_text).collect::<Vec<_>>();
+        if self.commented || cells.is_empty() || cells.iter().any(|cell| cell.contains('\n')) {
+            return None;
+        }
+
+        let mut widths = vec![0; self.cols];
+        for (idx, cell) in cells.iter().enumerate() {
+            let width = &mut widths[idx % self.cols];
+            *width = (*width).max(cell.chars().count());
+        }
+
+        let mut lines = vec![];
+        let mut row = String::new();
+        let mut cell_idx = 0;
+        for (idx, arg) in self.args.iter().enumerate() {
+            if self.cells.get(cell_idx) != Some(&idx) {
+                lines.push(format!("{},", arg.clone().into_text()));
+                continue;
+            }
+            let col = cell_idx % self.cols;
+            let cell = format!("{},", cells[cell_idx]);
+            if col + 1 == self.cols || cell_idx + 1 == cells.len() {
+                row.push_str(&cell);
+                lines.push(std::mem::take(&mut row));
+            } else {
+                row.push_str(&format!("{cell:<width$} ", width = widths[col] + 1));
+            }
+            cell_idx += 1;
+        }
+
+        let start = self.parens.0.offset();
+        let line_start = text[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        let line = &text[line_start..start];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut aligned = "(\n".to_owned();
+        for line in lines {
+            aligned.push_str(&format!("{indent}  {line}\n"));
+        }
+        aligned.push_str(&format!("{indent})"));
+
+        let range = start..self.parens.1.range().end;
+        (text[range.clone()] != aligned).then_some((range, aligned))
+    }
+}
+
+/// Gets the items of an array.
+fn array_items<'a>(array: &LinkedNode<'a>) -> impl Iterator<Item = LinkedNode<'a>> {
+    array
+        .children()
+        .filter(|child| child.cast::<ast::Expr>().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(text: &str, cursor: &str, title: &str) -> Option<String> {
+        let source = Source::detached(text);
+        let cursor = text.find(cursor).unwrap();
+        let actions = edit_table(&source, cursor);
+        let (_, mut edits) = actions.into_iter().find(|(t, _)| *t == title)?;
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+        let mut text = text.to_owned();
+        for (range, new_text) in edits {
+            text.replace_range(range, &new_text);
+        }
+        Some(text)
+    }
+
+    #[test]
+    fn test_edit_rows() {
+        let text = "#table(\n  columns: 2,\n  [a], [b],\n  [c], [d],\n)";
+        assert_eq!(
+            edit(text, "[c]", "Insert table row above").as_deref(),
+            Some("#table(\n  columns: 2,\n  [a], [b],\n  [], [],\n  [c], [d],\n)")
+        );
+        assert_eq!(
+            edit(text, "[d]", "Insert table row below").as_deref(),
+            Some("#table(\n  columns: 2,\n  [a], [b],\n  [c], [d],\n  [], [],\n)")
+        );
+        assert_eq!(
+            edit(text, "[a]", "Delete table row").as_deref(),
+            Some("#table(\n  columns: 2,\n  [c], [d],\n)")
+        );
+        assert_eq!(
+            edit(text, "[c]", "Delete table row").as_deref(),
+            Some("#table(\n  columns: 2,\n  [a], [b],\n)")
+        );
+        assert_eq!(
+            edit(
+                "#grid(columns: 2, [a], [b], [c])",
+                "[a]",
+                "Delete table row"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_edit_columns() {
+        let text = "#table(\n  columns: (auto, 1fr),\n  [a], [b],\n  [c], [d],\n)";
+        assert_eq!(
+            edit(text, "[a]", "Insert table column right").as_deref(),
+            Some("#table(\n  columns: (auto, auto, 1fr),\n  [a], [], [b],\n  [c], [], [d],\n)")
+        );
+        assert_eq!(
+            edit(text, "[b]", "Delete table column").as_deref(),
+            Some("#table(\n  columns: (auto),\n  [a],\n  [c],\n)")
+        );
+        assert_eq!(
+            edit("#grid([a], [b])", "[b]", "Insert table column left").as_deref(),
+            Some("#grid(columns: 2, [], [a], [], [b])")
+        );
+        assert_eq!(
+            edit(
+                "#grid(columns: 3, [a], [b], [c])",
+                "[a]",
+                "Delete table column"
+            )
+            .as_deref(),
+            Some("#grid(columns: 2, [b], [c])")
+        );
+    }
+
+    #[test]
+    fn test_align_cells() {
+        let text = "#table(columns: 2, table.hline(), [a], [bbb], [cc], [d], [e])";
+        assert_eq!(
+            edit(text, "[a]", "Align table cells").as_deref(),
+            Some(
+                "#table(\n  columns: 2,\n  table.hline(),\n  [a],  [bbb],\n  [cc], [d],\n  [e],\n)"
+            )
+        );
+        let aligned = "#grid(\n  columns: 2,\n  [a], [b],\n)";
+        assert_eq!(edit(aligned, "[a]", "Align table cells"), None);
+        let spanned = "#table(columns: 2, table.cell(colspan: 2)[a], [b], [c])";
+        assert_eq!(edit(spanned, "[b]", "Align table cells"), None);
+    }
+}
@@ -1,24 +1,41 @@
+use std::{fmt, ops::Range};
+
 use lsp_types::Command;
+use typst::{
+    layout::{Frame, FrameItem},
+    model::Document,
+    syntax::SyntaxNode,
+};
 
-use crate::{prelude::*, SemanticRequest};
+use crate::{prelude::*, SyntaxRequest};
 
 /// The [`textDocument/codeLens`] request is sent from the client to the server
 /// to compute code lenses for a given text document.
 ///
 /// [`textDocument/codeLens`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_codeLens
+///
+/// The lenses are computed from the syntax of the document, so that they are
+/// never blocked by a compilation. The summary of the compiled document is
+/// provided by the server.
 #[derive(Debug, Clone)]
 pub struct CodeLensRequest {
     /// The path of the document to request for.
     pub path: PathBuf,
+    /// The summary of the last compiled document, if the document is its main
+    /// file.
+    pub summary: Option<String>,
 }
 
-impl SemanticRequest for CodeLensRequest {
+impl SyntaxRequest for CodeLensRequest {
     type Response = Vec<CodeLens>;
 
-    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
-        let source = ctx.source_by_path(&self.path).ok()?;
-
-        let doc_start = ctx.to_lsp_range(0..0, &source);
+    fn request(
+        self,
+        source: &Source,
+        position_encoding: PositionEncoding,
+    ) -> Option<Self::Response> {
+        let to_lsp_range = |range| typst_to_lsp::range(range, source, position_encoding);
+        let doc_start = to_lsp_range(0..0);
 
         let mut res = vec![];
 
@@ -39,7 +56,260 @@ impl SemanticRequest for CodeLensRequest {
         res.push(doc_lens("Preview in ..", vec!["preview-in".into()]));
         res.push(doc_lens("Export PDF", vec!["export-pdf".into()]));
         res.push(doc_lens("Export as ..", vec!["export-as".into()]));
+        res.push(doc_lens("Pin as main", vec!["pin-main".into()]));
+
+        if let Some(summary) = &self.summary {
+            res.push(doc_lens(summary, vec!["word-count".into()]));
+        }
+
+        for (range, count) in section_counts(source) {
+            res.push(CodeLens {
+                range: to_lsp_range(range),
+                command: Some(run_code_lens_cmd(
+                    &count.to_string(),
+                    vec!["word-count".into()],
+                )),
+                data: None,
+            });
+        }
+
+        // The bindings are evaluated by the server, which shows the values.
+        let path = self.path.to_string_lossy();
+        for (range, name) in evaluable_bindings(source) {
+            res.push(CodeLens {
+                range: to_lsp_range(range),
+                command: Some(Command {
+                    title: "Evaluate".to_string(),
+                    command: "tinymist.evaluateBinding".to_string(),
+                    arguments: Some(vec![path.as_ref().into(), name.as_str().into()]),
+                }),
+                data: None,
+            });
+        }
 
         Some(res)
     }
 }
+
+/// Collects the top-level bindings which can be evaluated without arguments,
+/// i.e. the variables and the functions without required parameters.
+fn evaluable_bindings(source: &Source) -> Vec<(Range<usize>, EcoString)> {
+    let root = LinkedNode::new(source.root());
+    let mut bindings = vec![];
+    for child in root.children() {
+        let Some(binding) = child.cast::<ast::LetBinding>() else {
+            continue;
+        };
+        let name = match binding.kind() {
+            ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(ident))) => {
+                ident.get().clone()
+            }
+            ast::LetBindingKind::Closure(ident) => {
+                let Some(ast::Expr::Closure(closure)) = binding.init() else {
+                    continue;
+                };
+                let has_required = closure
+                    .params()
+                    .children()
+                    .any(|param| matches!(param, ast::Param::Pos(..)));
+                if has_required {
+                    continue;
+                }
+                ident.get().clone()
+            }
+            _ => continue,
+        };
+        bindings.push((child.range(), name));
+    }
+    bindings
+}
+
+/// The number of words and characters in a piece of text.
+#[derive(Debug, Default, Clone, Copy)]
+struct TextCount {
+    words: usize,
+    chars: usize,
+    /// Whether the counted text ends in a word, which is continued by the
+    /// next text.
+    in_word: bool,
+}
+
+impl TextCount {
+    /// Counts the words and characters in a text. Each CJK character is
+    /// counted as a word, and the other words are separated by whitespace.
+    fn add(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.in_word = false;
+                continue;
+            }
+            self.chars += 1;
+            if is_cjk(c) {
+                self.words += 1;
+                self.in_word = false;
+            } else if !self.in_word {
+                self.words += 1;
+                self.in_word = true;
+            }
+        }
+    }
+
+    /// Gets the words and characters counted since an earlier count.
+    fn since(&self, start: &TextCount) -> TextCount {
+        TextCount {
+            words: self.words - start.words,
+            chars: self.chars - start.chars,
+            in_word: false,
+        }
+    }
+}
+
+impl fmt::Display for TextCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} word{}, {} character{}",
+            self.words,
+            plural(self.words),
+            self.chars,
+            plural(self.chars)
+        )
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{11FF}'
+            | '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Summarizes a compiled document by its words and pages, which is shown in
+/// the code lenses of its main file.
+pub fn document_summary(doc: &Document) -> String {
+    fn frame_text(frame: &Frame, count: &mut TextCount) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => frame_text(&group.frame, count),
+                FrameItem::Text(text) => {
+                    // The text items are not separated at the line breaks.
+                    count.in_word = false;
+                    count.add(&text.text);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut count = TextCount::default();
+    for page in &doc.pages {
+        frame_text(&page.frame, &mut count);
+    }
+
+    let pages = doc.pages.len();
+    format!(
+        "{} word{}, {pages} page{}",
+        count.words,
+        plural(count.words),
+        plural(pages)
+    )
+}
+
+/// Counts the words and characters in the sections of a source, returning the
+/// ranges of the headings and the counts of their sections.
+///
+/// A section spans from its heading to the next heading of the same or a
+/// higher level, and its text is collected from the markup, excluding the raw
+/// blocks and the equations.
+fn section_counts(source: &Source) -> Vec<(Range<usize>, TextCount)> {
+    let root = LinkedNode::new(source.root());
+
+    // The headings, their depths, and the counts at their starts.
+    let mut sections = vec![];
+    let mut counts = vec![];
+    let mut open = vec![];
+    let mut count = TextCount::default();
+    for child in root.children() {
+        if let Some(heading) = child.cast::<ast::Heading>() {
+            let depth = heading.depth().get();
+            while let Some(&(idx, open_depth, start)) = open.last() {
+                if open_depth < depth {
+                    break;
+                }
+                counts[idx] = count.since(&start);
+                open.pop();
+            }
+            open.push((sections.len(), depth, count));
+            sections.push(child.range());
+            counts.push(TextCount::default());
+        }
+        markup_text(child.get(), &mut count);
+    }
+    for (idx, _, start) in open {
+        counts[idx] = count.since(&start);
+    }
+
+    sections.into_iter().zip(counts).collect()
+}
+
+/// Collects the text in markup into a count.
+fn markup_text(node: &SyntaxNode, count: &mut TextCount) {
+    match node.kind() {
+        SyntaxKind::Text | SyntaxKind::SmartQuote => count.add(node.text()),
+        // The spaces separate the words in the adjacent text nodes.
+        SyntaxKind::Space | SyntaxKind::Linebreak | SyntaxKind::Parbreak => count.in_word = false,
+        SyntaxKind::Raw | SyntaxKind::Equation => {}
+        SyntaxKind::LineComment | SyntaxKind::BlockComment => {}
+        _ => {
+            for child in node.children() {
+                markup_text(child, count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluable_bindings() {
+        let source = Source::detached(
+            "#let a = 1\n#let f() = a\n#let g(x) = x\n#let h(x: 1, ..rest) = x\n#let (b, c) = (1, 2)\n#{ let d = 1 }",
+        );
+        let names = evaluable_bindings(&source)
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "f", "h"]);
+    }
+
+    #[test]
+    fn test_section_counts() {
+        let source = Source::detached(
+            "= Intro\nHello *world*.\n\n== Details\nMore `raw` text $x + y$ here.\n\n= 结论\n你好",
+        );
+        let counts = section_counts(&source)
+            .into_iter()
+            .map(|(range, count)| (&source.text()[range], count.words, count.chars))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![("= Intro", 7, 36), ("== Details", 4, 20), ("= 结论", 4, 4)]
+        );
+    }
+}
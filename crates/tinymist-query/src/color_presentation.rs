@@ -1,8 +1,38 @@
+use lsp_types::TextEdit;
 use typst::foundations::Repr;
+use typst::visualize::Color;
 
 use crate::prelude::*;
 
-/// The
+/// The named colors of Typst, offered if the picked color is one of them.
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("black", Color::BLACK),
+    ("gray", Color::GRAY),
+    ("silver", Color::SILVER),
+    ("white", Color::WHITE),
+    ("navy", Color::NAVY),
+    ("blue", Color::BLUE),
+    ("aqua", Color::AQUA),
+    ("teal", Color::TEAL),
+    ("eastern", Color::EASTERN),
+    ("purple", Color::PURPLE),
+    ("fuchsia", Color::FUCHSIA),
+    ("maroon", Color::MAROON),
+    ("red", Color::RED),
+    ("orange", Color::ORANGE),
+    ("yellow", Color::YELLOW),
+    ("olive", Color::OLIVE),
+    ("green", Color::GREEN),
+    ("lime", Color::LIME),
+];
+
+/// The [`textDocument/colorPresentation`] request is sent from the client to
+/// the server to obtain a list of presentations for a color value at a given
+/// location.
+///
+/// Each presentation rewrites the color expression at the location.
+///
+/// [`textDocument/colorPresentation`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_colorPresentation
 #[derive(Debug, Clone)]
 pub struct ColorPresentationRequest {
     /// The path of the document to request color presentations for.
@@ -16,30 +46,68 @@ pub struct ColorPresentationRequest {
 impl ColorPresentationRequest {
     /// Serve the request.
     pub fn request(self) -> Option<Vec<ColorPresentation>> {
-        let color = typst::visualize::Color::Rgb(typst::visualize::Rgb::new(
+        let color = Color::Rgb(typst::visualize::Rgb::new(
             self.color.red,
             self.color.green,
             self.color.blue,
             self.color.alpha,
         ));
-        Some(vec![
-            simple(format!("{:?}", color.to_hex())),
-            simple(color.to_rgb().repr().to_string()),
-            simple(color.to_luma().repr().to_string()),
-            simple(color.to_oklab().repr().to_string()),
-            simple(color.to_oklch().repr().to_string()),
-            simple(color.to_rgb().repr().to_string()),
-            simple(color.to_linear_rgb().repr().to_string()),
-            simple(color.to_cmyk().repr().to_string()),
-            simple(color.to_hsl().repr().to_string()),
-            simple(color.to_hsv().repr().to_string()),
-        ])
+        let hex = color.to_hex();
+
+        let named = NAMED_COLORS
+            .iter()
+            .filter(|(_, named)| named.to_hex() == hex)
+            .map(|(name, _)| name.to_string());
+        let labels = named.chain([
+            color.to_rgb().repr().to_string(),
+            color.to_luma().repr().to_string(),
+            color.to_oklab().repr().to_string(),
+            color.to_oklch().repr().to_string(),
+            color.to_linear_rgb().repr().to_string(),
+            color.to_cmyk().repr().to_string(),
+            color.to_hsl().repr().to_string(),
+            color.to_hsv().repr().to_string(),
+        ]);
+
+        Some(labels.map(|label| self.presentation(label)).collect())
+    }
+
+    fn presentation(&self, label: String) -> ColorPresentation {
+        ColorPresentation {
+            text_edit: Some(TextEdit {
+                range: self.range,
+                new_text: label.clone(),
+            }),
+            label,
+            ..ColorPresentation::default()
+        }
     }
 }
 
-fn simple(label: String) -> ColorPresentation {
-    ColorPresentation {
-        label,
-        ..ColorPresentation::default()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(color: lsp_types::Color) -> Vec<String> {
+        let request = ColorPresentationRequest {
+            path: PathBuf::new(),
+            color,
+            range: LspRange::default(),
+        };
+        let result = request.request().unwrap();
+        result.into_iter().map(|p| p.label).collect()
+    }
+
+    #[test]
+    fn test_named_color() {
+        let white = lsp_types::Color {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+            alpha: 1.0,
+        };
+        let labels = labels(white);
+        assert_eq!(labels[0], "white");
+        assert_eq!(labels[1], "rgb(\"#ffffff\")");
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use lsp_types::CompletionList;
 
 use crate::{
@@ -10,6 +12,38 @@ use crate::{
 
 use self::typst_to_lsp::completion;
 
+pub(crate) mod fuzzy;
+use fuzzy::rank_completions;
+
+/// The completion features supported by the client, detected from its
+/// capabilities at initialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionFeat {
+    /// Whether the client supports snippets in the inserted text. Otherwise,
+    /// the text is inserted as plain text.
+    pub snippets: bool,
+    /// Whether the client can run `editor.action.triggerSuggest` after
+    /// inserting a completion.
+    pub trigger_suggest: bool,
+}
+
+impl Default for CompletionFeat {
+    fn default() -> Self {
+        Self {
+            snippets: true,
+            trigger_suggest: true,
+        }
+    }
+}
+
+impl CompletionFeat {
+    /// The command to trigger suggestions again, if supported.
+    pub fn trigger_suggest_command(&self) -> Option<&'static str> {
+        self.trigger_suggest
+            .then_some("editor.action.triggerSuggest")
+    }
+}
+
 /// The [`textDocument/completion`] request is sent from the client to the
 /// server to compute completion items at a given cursor position.
 ///
@@ -136,11 +170,14 @@ impl StatefulRequest for CompletionRequest {
         }
 
         let mut completion_items_rest = None;
+        let mut ident_prefix = None;
+        let mut fitting = HashSet::new();
         let is_incomplete = false;
 
         let mut items = completion_result.or_else(|| {
             let cc_ctx = CompletionContext::new(ctx, doc, &source, cursor, explicit)?;
-            let (offset, ic, mut completions, completions_items2) = autocomplete(cc_ctx)?;
+            let (offset, ic, completions, completions_items2, fits) = autocomplete(cc_ctx)?;
+            fitting = fits;
             if !completions_items2.is_empty() {
                 completion_items_rest = Some(completions_items2);
             }
@@ -155,31 +192,17 @@ impl StatefulRequest for CompletionRequest {
                 let rng = match_ident.range();
                 replace_range = ctx.to_lsp_range(match_ident.range(), &source);
 
-                let ident_prefix = source.text()[rng.start..cursor].to_string();
-                completions.retain(|c| {
-                    // c.label
-                    let mut prefix_matcher = c.label.chars();
-                    'ident_matching: for ch in ident_prefix.chars() {
-                        for c in prefix_matcher.by_ref() {
-                            if c == ch {
-                                continue 'ident_matching;
-                            }
-                        }
-
-                        return false;
-                    }
-
-                    true
-                });
+                ident_prefix = Some(source.text()[rng.start..cursor].to_string());
             } else {
                 let lsp_start_position = ctx.to_lsp_pos(offset, &source);
                 replace_range = LspRange::new(lsp_start_position, self.position);
             }
 
+            let feat = ctx.analysis.completion_feat;
             Some(
                 completions
                     .iter()
-                    .map(|typst_completion| completion(typst_completion, replace_range))
+                    .map(|typst_completion| completion(typst_completion, replace_range, feat))
                     .collect_vec(),
             )
         })?;
@@ -188,6 +211,10 @@ impl StatefulRequest for CompletionRequest {
             items.append(items_rest);
         }
 
+        if let Some(ident_prefix) = ident_prefix {
+            rank_completions(&ident_prefix, &fitting, &mut items);
+        }
+
         // To response completions in fine-grained manner, we need to mark result as
         // incomplete. This follows what rust-analyzer does.
         // https://github.com/rust-lang/rust-analyzer/blob/f5a9250147f6569d8d89334dc9cca79c0322729f/crates/rust-analyzer/src/handlers/request.rs#L940C55-L940C75
@@ -204,8 +231,17 @@ mod tests {
     use lsp_types::CompletionItem;
 
     use super::*;
+    use crate::analysis::PathCompletionConfig;
     use crate::tests::*;
 
+    /// Gets the labels given by `// contains: a, b` in the first line of a
+    /// fixture, so that only the completions with the labels are snapshotted.
+    fn contains_labels(source: &Source) -> Option<HashSet<&str>> {
+        let line = source.text().lines().next()?;
+        let labels = line.strip_prefix("// contains:")?;
+        Some(labels.split(',').map(str::trim).collect())
+    }
+
     #[test]
     fn test() {
         snapshot_testing("completion", &|ctx, path| {
@@ -270,6 +306,26 @@ mod tests {
                     }
                 }));
             }
+
+            // The completions are too many to snapshot in the fixtures giving the
+            // labels, where they are snapshotted by the labels instead of the ranks.
+            if let Some(labels) = contains_labels(&source) {
+                for result in results.iter_mut().flatten() {
+                    let CompletionResponse::List(list) = result else {
+                        continue;
+                    };
+                    list.items
+                        .retain(|item| labels.contains(item.label.as_str()));
+                    for item in &mut list.items {
+                        item.sort_text = None;
+                    }
+                    list.items.sort_by_cached_key(|item| {
+                        (item.label.clone(), serde_json::to_string(item).unwrap())
+                    });
+                    list.items.dedup();
+                }
+            }
+
             with_settings!({
                 description => format!("Completion on {text} ({rng:?})"),
             }, {
@@ -277,4 +333,37 @@ mod tests {
             })
         });
     }
+
+    #[test]
+    fn test_path_preferences() {
+        snapshot_testing("completion_path", &|ctx, path| {
+            ctx.analysis.path_completion = PathCompletionConfig {
+                extensions: HashMap::from([("image".into(), vec![".SVG".into(), "gif".into()])]),
+                ..PathCompletionConfig::default()
+            }
+            .normalized();
+            ctx.test_configured_completion_files(|| {
+                ["e.json", "a.png", "b.svg", "d.typ", "c.gif"]
+                    .map(PathBuf::from)
+                    .into()
+            });
+
+            let source = ctx.source_by_path(&path).unwrap();
+            let rng = find_test_range(&source);
+            let request = CompletionRequest {
+                path: path.clone(),
+                position: ctx.to_lsp_pos(rng.start, &source),
+                explicit: false,
+            };
+            let result = request.request(ctx, None).map(|resp| {
+                let CompletionResponse::List(list) = resp else {
+                    unreachable!("path completions are returned as a list");
+                };
+                let mut items = list.items;
+                items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+                items.into_iter().map(|item| item.label).collect::<Vec<_>>()
+            });
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
 }
@@ -0,0 +1,163 @@
+//! Fuzzy matching and ranking of completion items.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use lsp_types::CompletionItem;
+
+use crate::prelude::*;
+
+/// Scores how well the `pattern` matches the `candidate`.
+///
+/// The pattern must be a (case-insensitive) subsequence of the candidate,
+/// otherwise `None` is returned. Matched characters score more at the start of
+/// the candidate, at the start of a word in kebab, snake or camel case, and
+/// when they are consecutive. A candidate starting with the pattern scores the
+/// most.
+pub(crate) fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    let mut pattern_chars = pattern.chars().peekable();
+    let mut score = 0;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+
+    for (i, c) in candidate.chars().enumerate() {
+        let Some(p) = pattern_chars.peek() else {
+            break;
+        };
+
+        let matched = p.to_lowercase().eq(c.to_lowercase());
+        if matched {
+            pattern_chars.next();
+            score += 1;
+            if i == 0 {
+                score += 8;
+            } else if prev.is_some_and(|prev| is_hump(prev, c)) {
+                score += 6;
+            }
+            if prev_matched {
+                score += 4;
+            }
+        }
+
+        prev = Some(c);
+        prev_matched = matched;
+    }
+
+    if pattern_chars.peek().is_some() {
+        return None;
+    }
+    if candidate.starts_with(pattern) {
+        score += 10;
+    }
+
+    Some(score)
+}
+
+/// Whether `c` starts a new word after `prev`.
+fn is_hump(prev: char, c: char) -> bool {
+    matches!(prev, '-' | '_' | '.' | ' ') || (prev.is_lowercase() && c.is_uppercase())
+}
+
+/// Compares the sort texts of two completions, where the completions without
+/// sort texts are sorted last, so that the boosted completions, e.g. with
+/// `000`, are sorted before them.
+pub(crate) fn cmp_sort_text<S: AsRef<str>>(a: &Option<S>, b: &Option<S>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.as_ref().cmp(b.as_ref()),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The score added to the completions whose values fit the expected type, as
+/// much as a prefix match.
+const FITTING_BONUS: i32 = 10;
+
+/// Filters out the items not matching the `pattern`, and sorts the rest by
+/// their fuzzy scores, and then by their sort texts. The items labelled in
+/// `fitting`, i.e. fitting the expected type, are scored higher.
+///
+/// The `sort_text` and `filter_text` of all items are reset, so that clients
+/// keep the order computed here and keep filtering by the labels as the user
+/// types.
+pub(crate) fn rank_completions(
+    pattern: &str,
+    fitting: &HashSet<EcoString>,
+    items: &mut Vec<CompletionItem>,
+) {
+    let score = |item: &CompletionItem| {
+        let score = fuzzy_score(pattern, &item.label)?;
+        let fits = fitting.contains(item.label.as_str());
+        Some(if fits { score + FITTING_BONUS } else { score })
+    };
+    let mut scored = std::mem::take(items)
+        .into_iter()
+        .filter_map(|item| Some((score(&item)?, item)))
+        .collect_vec();
+
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| cmp_sort_text(&a.sort_text, &b.sort_text))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+
+    let digits = scored.len().to_string().len();
+    items.extend(scored.into_iter().enumerate().map(|(i, (_, mut item))| {
+        item.sort_text = Some(format!("{i:0>digits$}"));
+        item.filter_text = Some(item.label.clone());
+        item
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("xyz", "abc"), None);
+        assert_eq!(fuzzy_score("", "abc"), Some(10));
+
+        // prefix matches rank first
+        let prefix = fuzzy_score("pa", "page").unwrap();
+        let humped = fuzzy_score("pa", "pad-array").unwrap();
+        let sparse = fuzzy_score("pa", "ellipse-arc").unwrap();
+        assert!(prefix > sparse);
+        assert!(humped > sparse);
+
+        // kebab and camel humps are preferred over inner matches
+        let kebab = fuzzy_score("ta", "text-align").unwrap();
+        let inner = fuzzy_score("ta", "tomato").unwrap();
+        assert!(kebab > inner);
+        assert!(fuzzy_score("fB", "fooBar").unwrap() > fuzzy_score("fB", "fabB").unwrap());
+    }
+
+    #[test]
+    fn test_rank_boosted() {
+        let item = |label: &str, sort_text: Option<&str>| CompletionItem {
+            label: label.to_owned(),
+            sort_text: sort_text.map(ToOwned::to_owned),
+            ..Default::default()
+        };
+        let mut items = vec![item("here-a", None), item("here-b", Some("000"))];
+        rank_completions("here", &HashSet::new(), &mut items);
+        let labels = items.iter().map(|item| item.label.as_str()).collect_vec();
+        assert_eq!(labels, ["here-b", "here-a"]);
+    }
+
+    #[test]
+    fn test_rank_fitting() {
+        let item = |label: &str| CompletionItem {
+            label: label.to_owned(),
+            ..Default::default()
+        };
+        // `rgb` fits a color, and is ranked before the items matched as well.
+        let mut items = vec![item("red-ish"), item("rgb"), item("ratio")];
+        let fitting = HashSet::from_iter(["rgb".into()]);
+        rank_completions("r", &fitting, &mut items);
+        let labels = items.iter().map(|item| item.label.as_str()).collect_vec();
+        assert_eq!(labels, ["rgb", "ratio", "red-ish"]);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use reflexo::hash::hash128;
+use serde::Serialize;
+
+use crate::{prelude::*, SemanticRequest};
+
+/// The number of tokens in a shingle.
+const SHINGLE_SIZE: usize = 5;
+/// The minimum number of tokens of a block to be considered.
+const MIN_BLOCK_TOKENS: usize = 12;
+/// The minimum Jaccard similarity of two blocks to be reported.
+const MIN_SIMILARITY: f32 = 0.8;
+
+/// How a duplicated block could be shared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentReuseSuggestion {
+    /// Both blocks are in the same file, extract them into a function.
+    ExtractFunction,
+    /// The blocks are in different files, move them into a file and include
+    /// it.
+    ExtractInclude,
+}
+
+/// A pair of near-duplicate markup blocks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentReuse {
+    /// The location of the first block.
+    pub first: LspLocation,
+    /// The location of the second block.
+    pub second: LspLocation,
+    /// The Jaccard similarity of the shingles of both blocks, in `(0, 1]`.
+    pub similarity: f32,
+    /// How the blocks could be shared.
+    pub suggestion: ContentReuseSuggestion,
+}
+
+/// A request to find near-duplicate markup blocks across the workspace.
+///
+/// Markup is split into paragraphs and content blocks, which are normalized
+/// by dropping whitespace, comments, and case, and then hashed into shingles
+/// of consecutive tokens. Two blocks are reported if the Jaccard similarity of
+/// their shingles exceeds a threshold.
+///
+/// This is not part of the LSP protocol.
+#[derive(Debug, Clone)]
+pub struct ContentReuseRequest {
+    /// The path of the document whose workspace is searched.
+    pub path: PathBuf,
+}
+
+struct Block {
+    id: TypstFileId,
+    source: Source,
+    range: Range<usize>,
+    shingles: HashSet<u128>,
+}
+
+impl SemanticRequest for ContentReuseRequest {
+    type Response = Vec<ContentReuse>;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let mut blocks = vec![];
+        for id in ctx.source_files().clone() {
+            let Ok(source) = ctx.source_by_id(id) else {
+                continue;
+            };
+            let mut ranges = vec![];
+            collect_blocks(&LinkedNode::new(source.root()), &mut ranges);
+            for range in ranges {
+                let shingles = shingles(&source, range.clone());
+                if !shingles.is_empty() {
+                    blocks.push(Block {
+                        id,
+                        source: source.clone(),
+                        range,
+                        shingles,
+                    });
+                }
+            }
+        }
+
+        // Counts the shared shingles of candidate pairs with an inverted index.
+        let mut index: HashMap<u128, Vec<usize>> = HashMap::new();
+        for (i, block) in blocks.iter().enumerate() {
+            for shingle in &block.shingles {
+                index.entry(*shingle).or_default().push(i);
+            }
+        }
+        let mut shared: HashMap<(usize, usize), usize> = HashMap::new();
+        for owners in index.values() {
+            for (k, &i) in owners.iter().enumerate() {
+                for &j in &owners[k + 1..] {
+                    *shared.entry((i, j)).or_default() += 1;
+                }
+            }
+        }
+
+        let mut candidates = vec![];
+        for ((i, j), count) in shared.into_iter().sorted() {
+            let (a, b) = (&blocks[i], &blocks[j]);
+            if a.id == b.id && overlaps(&a.range, &b.range) {
+                continue;
+            }
+
+            let union = a.shingles.len() + b.shingles.len() - count;
+            let similarity = count as f32 / union as f32;
+            if similarity >= MIN_SIMILARITY {
+                candidates.push((i, j, similarity));
+            }
+        }
+
+        // The blocks nested in a pair, e.g. the content block of a paragraph,
+        // match each other as well, where only the maximal pair is reported.
+        let size =
+            |(i, j, _): &(usize, usize, f32)| blocks[*i].range.len() + blocks[*j].range.len();
+        candidates.sort_by_key(|pair| std::cmp::Reverse(size(pair)));
+        let contains = |outer: usize, inner: usize| {
+            let (outer, inner) = (&blocks[outer], &blocks[inner]);
+            outer.id == inner.id
+                && outer.range.start <= inner.range.start
+                && inner.range.end <= outer.range.end
+        };
+        let mut reported: Vec<(usize, usize, f32)> = vec![];
+        for (i, j, similarity) in candidates {
+            let nested = reported.iter().any(|&(k, l, _)| {
+                (contains(k, i) && contains(l, j)) || (contains(k, j) && contains(l, i))
+            });
+            if !nested {
+                reported.push((i, j, similarity));
+            }
+        }
+        reported.sort_by_key(|&(i, j, _)| (i, j));
+
+        let mut results = vec![];
+        for (i, j, similarity) in reported {
+            let (a, b) = (&blocks[i], &blocks[j]);
+            let suggestion = if a.id == b.id {
+                ContentReuseSuggestion::ExtractFunction
+            } else {
+                ContentReuseSuggestion::ExtractInclude
+            };
+            let (Some(first), Some(second)) = (block_location(ctx, a), block_location(ctx, b))
+            else {
+                continue;
+            };
+            results.push(ContentReuse {
+                first,
+                second,
+                similarity,
+                suggestion,
+            });
+        }
+
+        Some(results)
+    }
+}
+
+/// Collects paragraphs of markup and the content blocks containing them.
+fn collect_blocks(node: &LinkedNode, ranges: &mut Vec<Range<usize>>) {
+    if node.kind() == SyntaxKind::Markup {
+        let mut para: Option<Range<usize>> = None;
+        for child in node.children() {
+            match child.kind() {
+                SyntaxKind::Parbreak => ranges.extend(para.take()),
+                kind if kind.is_trivia() => {}
+                _ => {
+                    let range = child.range();
+                    para = Some(match para {
+                        Some(para) => para.start..range.end,
+                        None => range,
+                    });
+                }
+            }
+        }
+        ranges.extend(para);
+    }
+
+    for child in node.children() {
+        if child.kind() == SyntaxKind::ContentBlock {
+            ranges.push(child.range());
+        }
+        collect_blocks(&child, ranges);
+    }
+}
+
+/// Computes the shingles of the normalized tokens in the range.
+fn shingles(source: &Source, range: Range<usize>) -> HashSet<u128> {
+    let mut tokens = vec![];
+    let root = LinkedNode::new(source.root());
+    let mut leaf = root.leaf_at(range.start + 1);
+    while let Some(node) = leaf {
+        if node.offset() >= range.end {
+            break;
+        }
+        match node.kind() {
+            SyntaxKind::Text => {
+                let words = node.text().split_whitespace();
+                tokens.extend(words.map(|w| EcoString::from(w.to_lowercase())));
+            }
+            kind if kind.is_trivia() || kind == SyntaxKind::Parbreak => {}
+            _ => tokens.push(node.text().clone()),
+        }
+        leaf = node.next_leaf();
+    }
+
+    if tokens.len() < MIN_BLOCK_TOKENS {
+        return HashSet::new();
+    }
+    tokens.windows(SHINGLE_SIZE).map(|w| hash128(&w)).collect()
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+fn block_location(ctx: &AnalysisContext, block: &Block) -> Option<LspLocation> {
+    let path = ctx.path_for_id(block.id).ok()?;
+    Some(LspLocation {
+        uri: path_to_url(&path).ok()?,
+        range: ctx.to_lsp_range(block.range.clone(), &block.source),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_core::path::unix_slash;
+
+    use super::*;
+    use crate::{tests::*, url_to_path};
+
+    #[test]
+    fn test() {
+        snapshot_testing("content_reuse", &|world, path| {
+            let request = ContentReuseRequest { path };
+
+            let result = request.request(world);
+            // The order of the files in the workspace is not stable.
+            let location = |l: LspLocation| {
+                let fp = unix_slash(&url_to_path(l.uri));
+                let fp = fp.strip_prefix("C:").unwrap_or(&fp).to_owned();
+                format!(
+                    "{fp}@{}:{}:{}:{}",
+                    l.range.start.line,
+                    l.range.start.character,
+                    l.range.end.line,
+                    l.range.end.character
+                )
+            };
+            let result = result.map(|v| {
+                v.into_iter()
+                    .map(|e| {
+                        let mut locations = [location(e.first), location(e.second)];
+                        locations.sort();
+                        let [first, second] = locations;
+                        format!("{first} {second} {:?}", e.suggestion)
+                    })
+                    .sorted()
+                    .collect::<Vec<_>>()
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+
+    #[test]
+    fn test_shingles_normalization() {
+        let text = "The quick brown fox jumps over the lazy dog and runs far away from here.";
+        let a = Source::detached(text);
+        let b = Source::detached(format!("{} // comment", text.to_uppercase()));
+
+        let sa = shingles(&a, 0..a.text().len());
+        let sb = shingles(&b, 0..b.text().len());
+        assert!(!sa.is_empty());
+        assert_eq!(sa, sb);
+    }
+
+    #[test]
+    fn test_collect_paragraphs() {
+        let source = Source::detached("first para\n\nsecond para #[inner]");
+        let mut ranges = vec![];
+        collect_blocks(&LinkedNode::new(source.root()), &mut ranges);
+
+        let texts = ranges
+            .into_iter()
+            .map(|r| &source.text()[r])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            ["first para", "second para #[inner]", "[inner]", "inner"]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Quick fixes of the compiler diagnostics, e.g. the unknown font families,
+//! the deprecated functions, and the unknown variables.
+//!
+//! The fixes are registered by the patterns of the diagnostic messages, in
+//! which each `{}` captures a part of the message.
+
+use std::ops::Range;
+
+use reflexo::path::unix_slash;
+
+use crate::{analysis::edit_distance, prelude::*, syntax::resolve_id_by_path, LintFix};
+
+/// A quick fix of the compiler diagnostics matching a message pattern.
+struct DiagnosticFix {
+    /// The pattern of the messages.
+    pattern: &'static str,
+    /// Computes the fixes from the range of a diagnostic and the captured
+    /// parts of its message.
+    fix: fn(&mut AnalysisContext, &Source, Range<usize>, &[&str]) -> Vec<LintFix>,
+}
+
+/// The registry of the quick fixes of the compiler diagnostics.
+const DIAGNOSTIC_FIXES: &[DiagnosticFix] = &[
+    DiagnosticFix {
+        pattern: "unknown font family: {}",
+        fix: fix_font_family,
+    },
+    DiagnosticFix {
+        pattern: "`{}` is deprecated, use `{}` instead",
+        fix: fix_deprecated,
+    },
+    DiagnosticFix {
+        pattern: "unknown variable: {}",
+        fix: fix_unknown_variable,
+    },
+    DiagnosticFix {
+        pattern: "no text within stars",
+        fix: fix_empty_markup,
+    },
+    DiagnosticFix {
+        pattern: "no text within underscores",
+        fix: fix_empty_markup,
+    },
+];
+
+/// Gets the quick fixes of a diagnostic reported by the compiler.
+pub fn diagnostic_fixes(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    diag: &LspDiagnostic,
+) -> Vec<LintFix> {
+    if diag.source.as_deref() != Some("typst") {
+        return vec![];
+    }
+    let Some(range) = ctx.to_typst_range(diag.range, source) else {
+        return vec![];
+    };
+
+    // The hints are appended to the message after blank lines.
+    let message = diag.message.lines().next().unwrap_or_default();
+    let fixes = DIAGNOSTIC_FIXES.iter().filter_map(|entry| {
+        let captures = match_pattern(entry.pattern, message)?;
+        Some((entry.fix)(ctx, source, range.clone(), &captures))
+    });
+    fixes.flatten().collect()
+}
+
+/// Matches a message against a pattern, returning the captured parts.
+fn match_pattern<'a>(pattern: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut parts = pattern.split("{}");
+    let mut rest = message.strip_prefix(parts.next().unwrap_or_default())?;
+
+    let mut captures = vec![];
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        let end = match parts.peek() {
+            // The last capture extends to the suffix of the pattern.
+            None => rest.strip_suffix(part)?.len(),
+            // The adjacent captures are ambiguous.
+            Some(..) if part.is_empty() => return None,
+            Some(..) => rest.find(part)?,
+        };
+        captures.push(&rest[..end]);
+        rest = &rest[end + part.len()..];
+    }
+
+    rest.is_empty().then_some(captures)
+}
+
+/// Suggests the closest font families to an unknown one.
+pub(crate) fn fix_font_family(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    range: Range<usize>,
+    captures: &[&str],
+) -> Vec<LintFix> {
+    let [family] = captures else {
+        return vec![];
+    };
+    // The font list may be a string or an array, and the family in the
+    // message is in lowercase.
+    let root = LinkedNode::new(source.root());
+    let Some(string) = find_node(&root, &range, &|node| {
+        let string = node.cast::<ast::Str>()?;
+        let range = node.range();
+        (string.get().to_lowercase() == *family).then(|| range.start + 1..range.end - 1)
+    }) else {
+        return vec![];
+    };
+
+    let family = family.chars().collect::<Vec<_>>();
+    let book = ctx.world().book();
+    let mut candidates = book
+        .families()
+        .filter_map(|(name, mut infos)| {
+            let name = name.to_lowercase().chars().collect::<Vec<_>>();
+            let distance = edit_distance(&family, &name);
+            Some((distance, infos.next()?.family.clone()))
+        })
+        .filter(|(distance, _)| *distance <= (family.len() / 3).max(2))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+
+    let fixes = candidates.into_iter().take(3).map(|(_, name)| LintFix {
+        title: format!("Change to `{name}`"),
+        edits: vec![(string.clone(), name)],
+        create: None,
+        command: None,
+    });
+    fixes.collect()
+}
+
+/// Replaces a deprecated function with its replacement.
+fn fix_deprecated(
+    _ctx: &mut AnalysisContext,
+    source: &Source,
+    range: Range<usize>,
+    captures: &[&str],
+) -> Vec<LintFix> {
+    let [deprecated, replacement] = captures else {
+        return vec![];
+    };
+    let root = LinkedNode::new(source.root());
+    let Some(callee) = find_node(&root, &range, &|node| {
+        let is_callee = matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::FieldAccess);
+        (is_callee && source.text()[node.range()] == **deprecated).then(|| node.range())
+    }) else {
+        return vec![];
+    };
+
+    vec![LintFix {
+        title: format!("Replace with `{replacement}`"),
+        edits: vec![(callee, replacement.to_string())],
+        create: None,
+        command: None,
+    }]
+}
+
+/// Imports an unknown variable from a file in the workspace or an installed
+/// package exporting it, or defines it locally.
+fn fix_unknown_variable(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    range: Range<usize>,
+    captures: &[&str],
+) -> Vec<LintFix> {
+    let [name] = captures else {
+        return vec![];
+    };
+    let root = LinkedNode::new(source.root());
+    let import_at = import_position(source, &root, range.start);
+
+    let mut fixes = vec![];
+    for (path, id) in import_candidates(ctx, source.id()) {
+        let Ok(candidate) = ctx.source_by_id(id) else {
+            continue;
+        };
+        let exported = ctx
+            .def_use(candidate)
+            .is_some_and(|def_use| def_use.get_export(name).is_some());
+        if exported {
+            fixes.push(LintFix {
+                title: format!("Import `{name}` from `{path}`"),
+                edits: vec![(import_at.clone(), format!("#import \"{path}\": {name}\n"))],
+                create: None,
+                command: None,
+            });
+        }
+    }
+
+    // The variable is defined before the top-level statement using it.
+    if let Some(statement) = root
+        .children()
+        .find(|child| child.range().start <= range.start && range.start < child.range().end)
+    {
+        let mut insert_at = statement.offset();
+        if statement.prev_sibling_kind() == Some(SyntaxKind::Hash) {
+            insert_at -= 1;
+        }
+        let text = source.text();
+        let line_start = text[..insert_at].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &text[line_start..insert_at];
+        let indent = if indent.trim().is_empty() { indent } else { "" };
+        let binding = format!("#let {name} = none");
+        fixes.push(LintFix {
+            title: format!("Create `{binding}` locally"),
+            edits: vec![(insert_at..insert_at, format!("{binding}\n{indent}"))],
+            create: None,
+            command: None,
+        });
+    }
+    fixes
+}
+
+/// Gets the files in the workspace and the entrypoints of the latest versions
+/// of the installed packages, with their import paths relative to a file.
+fn import_candidates(ctx: &AnalysisContext, current: TypstFileId) -> Vec<(String, TypstFileId)> {
+    let base = current.vpath().as_rootless_path().parent();
+    let base = base.unwrap_or(Path::new(""));
+    let mut candidates = vec![];
+    for id in ctx.source_files() {
+        if *id == current || id.package().is_some() {
+            continue;
+        }
+        let path = id.vpath().as_rootless_path();
+        if let Some(relative) = pathdiff::diff_paths(path, base) {
+            candidates.push((unix_slash(&relative), *id));
+        }
+    }
+
+    let mut packages = ctx.resources.local_packages().to_vec();
+    packages.sort_by(|a, b| {
+        (&a.namespace, &a.name, b.version).cmp(&(&b.namespace, &b.name, a.version))
+    });
+    packages.dedup_by(|a, b| a.namespace == b.namespace && a.name == b.name);
+    for spec in packages {
+        let spec = spec.to_string();
+        if let Some(id) = resolve_id_by_path(ctx.world(), current, &spec) {
+            candidates.push((spec, id));
+        }
+    }
+    candidates
+}
+
+/// Gets the position to insert an import at, i.e. after the imports at the
+/// top level before an offset, or at the start of the file.
+fn import_position(source: &Source, root: &LinkedNode, offset: usize) -> Range<usize> {
+    let last_import = root
+        .children()
+        .take_while(|child| child.range().end <= offset)
+        .filter(|child| child.kind() == SyntaxKind::ModuleImport)
+        .last();
+    // The import must precede the usage, which may be on the same line.
+    let at = last_import.and_then(|import| {
+        let text = source.text();
+        let end = import.range().end;
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+        (line_end <= offset).then_some(line_end)
+    });
+    let at = at.unwrap_or(0);
+    at..at
+}
+
+/// Removes the strong or emphasized markup without text, e.g. `**`.
+fn fix_empty_markup(
+    _ctx: &mut AnalysisContext,
+    source: &Source,
+    range: Range<usize>,
+    _captures: &[&str],
+) -> Vec<LintFix> {
+    let Some(text) = source.text().get(range.clone()) else {
+        return vec![];
+    };
+    if text.is_empty() || !text.chars().all(|c| c == '*' || c == '_') {
+        return vec![];
+    }
+
+    vec![LintFix {
+        title: format!("Remove `{text}`"),
+        edits: vec![(range, String::new())],
+        create: None,
+        command: None,
+    }]
+}
+
+/// Finds the first node in a range of which the function returns a value.
+fn find_node<T>(
+    node: &LinkedNode,
+    range: &Range<usize>,
+    f: &impl Fn(&LinkedNode) -> Option<T>,
+) -> Option<T> {
+    let node_range = node.range();
+    if node_range.end < range.start || range.end < node_range.start {
+        return None;
+    }
+    if range.start <= node_range.start && node_range.end <= range.end {
+        if let Some(found) = f(node) {
+            return Some(found);
+        }
+    }
+
+    node.children()
+        .find_map(|child| find_node(&child, range, f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_pattern() {
+        let pattern = "`{}` is deprecated, use `{}` instead";
+        assert_eq!(
+            match_pattern(pattern, "`path` is deprecated, use `curve` instead"),
+            Some(vec!["path", "curve"])
+        );
+        assert_eq!(match_pattern(pattern, "`path` is deprecated"), None);
+
+        assert_eq!(
+            match_pattern("unknown font family: {}", "unknown font family: arail"),
+            Some(vec!["arail"])
+        );
+        assert_eq!(
+            match_pattern("no text within stars", "no text within stars"),
+            Some(vec![])
+        );
+        assert_eq!(match_pattern("no text within stars", "no text"), None);
+    }
+
+    #[test]
+    fn test_import_position() {
+        let position = |text: &str, usage: &str| {
+            let source = Source::detached(text);
+            let root = LinkedNode::new(source.root());
+            import_position(&source, &root, text.find(usage).unwrap()).start
+        };
+
+        assert_eq!(position("= Title\n#foo", "foo"), 0);
+        assert_eq!(position("#import \"a.typ\": a\n#foo", "foo"), 19);
+        assert_eq!(position("#import \"a.typ\": a; #foo", "foo"), 0);
+    }
+}
@@ -1,3 +1,5 @@
+use lsp_types::DiagnosticTag;
+
 use crate::prelude::*;
 
 /// Stores diagnostics for files.
@@ -19,6 +21,82 @@ pub fn convert_diagnostics<'a>(
         .collect::<Vec<_>>()
         .into_iter()
         .into_group_map()
+        .into_iter()
+        .map(|(uri, diagnostics)| (uri, dedup_diagnostics(diagnostics)))
+        .collect()
+}
+
+/// Merges the diagnostics of analysis, e.g. the lints, into the diagnostics of
+/// the compiler. The diagnostics at the same range are merged into one.
+pub fn merge_diagnostics(diagnostics: &mut DiagnosticsMap, others: DiagnosticsMap) {
+    for (uri, others) in others {
+        let diagnostics = diagnostics.entry(uri).or_default();
+        diagnostics.extend(others);
+        *diagnostics = dedup_diagnostics(std::mem::take(diagnostics));
+    }
+}
+
+/// Merges the diagnostics at the same range, keeping the more severe one and
+/// joining their messages, tags, and related information. The identical
+/// diagnostics are reported only once.
+fn dedup_diagnostics(diagnostics: Vec<LspDiagnostic>) -> Vec<LspDiagnostic> {
+    let mut merged: Vec<LspDiagnostic> = Vec::with_capacity(diagnostics.len());
+    for diag in diagnostics {
+        let Some(prev) = merged.iter_mut().find(|prev| prev.range == diag.range) else {
+            merged.push(diag);
+            continue;
+        };
+        if prev.message == diag.message && prev.severity == diag.severity {
+            continue;
+        }
+
+        if severity_rank(diag.severity) < severity_rank(prev.severity) {
+            prev.severity = diag.severity;
+        }
+        let from_lints = is_lint(prev) && is_lint(&diag);
+        // The messages differing only in case are reported once, e.g. a lint
+        // repeating a compiler warning with the spelling in the source.
+        if !prev.message.eq_ignore_ascii_case(&diag.message) {
+            prev.message = format!("{}\n\n{}", prev.message, diag.message);
+        }
+        prev.code = prev.code.take().or(diag.code);
+        for tag in diag.tags.into_iter().flatten() {
+            let prev_tags = prev.tags.get_or_insert_with(Vec::new);
+            if !prev_tags.contains(&tag) {
+                prev_tags.push(tag);
+            }
+        }
+        // The code is grayed out only if all the diagnostics are lints, e.g. an
+        // error at an unused variable is not grayed out.
+        if !from_lints {
+            if let Some(tags) = &mut prev.tags {
+                tags.retain(|tag| *tag != DiagnosticTag::UNNECESSARY);
+            }
+        }
+        if let Some(related) = diag.related_information {
+            prev.related_information
+                .get_or_insert_with(Vec::new)
+                .extend(related);
+        }
+    }
+
+    merged
+}
+
+/// Whether a diagnostic is reported by the lints instead of the compiler.
+fn is_lint(diag: &LspDiagnostic) -> bool {
+    diag.source.as_deref() == Some("tinymist")
+}
+
+/// The rank of a severity, where the most severe one is the lowest.
+fn severity_rank(severity: Option<LspSeverity>) -> u8 {
+    match severity {
+        Some(LspSeverity::ERROR) => 0,
+        Some(LspSeverity::WARNING) => 1,
+        Some(LspSeverity::INFORMATION) => 2,
+        Some(LspSeverity::HINT) => 3,
+        _ => 4,
+    }
 }
 
 fn convert_diagnostic(
@@ -57,24 +135,21 @@ fn convert_diagnostic(
     Ok((uri, diagnostic))
 }
 
-fn tracepoint_to_relatedinformation(
+fn span_to_location(
     project: &AnalysisContext,
-    tracepoint: &Spanned<Tracepoint>,
+    span: TypstSpan,
     position_encoding: PositionEncoding,
-) -> anyhow::Result<Option<DiagnosticRelatedInformation>> {
-    if let Some(id) = tracepoint.span.id() {
+) -> anyhow::Result<Option<LspLocation>> {
+    if let Some(id) = span.id() {
         let uri = path_to_url(&project.path_for_id(id)?)?;
         let source = project.world().source(id)?;
 
-        if let Some(typst_range) = source.range(tracepoint.span) {
+        if let Some(typst_range) = source.range(span) {
             let lsp_range = typst_to_lsp::range(typst_range, &source, position_encoding);
 
-            return Ok(Some(DiagnosticRelatedInformation {
-                location: LspLocation {
-                    uri,
-                    range: lsp_range,
-                },
-                message: tracepoint.v.to_string(),
+            return Ok(Some(LspLocation {
+                uri,
+                range: lsp_range,
             }));
         }
     }
@@ -89,21 +164,49 @@ fn diagnostic_related_information(
 ) -> anyhow::Result<Vec<DiagnosticRelatedInformation>> {
     let mut tracepoints = vec![];
 
+    // The diagnostics in packages are reported at the call sites in the user's
+    // files, and related to their locations in the packages.
+    let reported_span = diagnostic_span_id(typst_diagnostic).map(|(_, span)| span);
+    let span = typst_diagnostic.span;
+    if let Some(spec) = span.id().and_then(|id| id.package().cloned()) {
+        if reported_span != Some(span) {
+            if let Some(location) = span_to_location(project, span, position_encoding)? {
+                tracepoints.push(DiagnosticRelatedInformation {
+                    location,
+                    message: format!("in package {spec}"),
+                });
+            }
+        }
+    }
+
     for tracepoint in &typst_diagnostic.trace {
-        if let Some(info) =
-            tracepoint_to_relatedinformation(project, tracepoint, position_encoding)?
-        {
-            tracepoints.push(info);
+        // The tracepoints in packages are internal details of the packages.
+        let span = tracepoint.span;
+        if span.id().map_or(true, |id| id.package().is_some()) || reported_span == Some(span) {
+            continue;
+        }
+        if let Some(location) = span_to_location(project, span, position_encoding)? {
+            tracepoints.push(DiagnosticRelatedInformation {
+                location,
+                message: tracepoint.v.to_string(),
+            });
         }
     }
 
     Ok(tracepoints)
 }
 
+/// Gets the span to report a diagnostic at, preferring the spans in the
+/// user's files to the ones in packages.
 fn diagnostic_span_id(typst_diagnostic: &TypstDiagnostic) -> Option<(TypstFileId, TypstSpan)> {
-    iter::once(typst_diagnostic.span)
+    let spans = iter::once(typst_diagnostic.span)
         .chain(typst_diagnostic.trace.iter().map(|trace| trace.span))
-        .find_map(|span| Some((span.id()?, span)))
+        .filter_map(|span| Some((span.id()?, span)));
+
+    spans
+        .clone()
+        .find(|(id, _)| id.package().is_none())
+        .or_else(|| spans.clone().next())
 }
 
 fn diagnostic_range(
@@ -139,3 +242,106 @@ fn diagnostic_hints(typst_hints: &[EcoString]) -> Format<impl Iterator<Item = Ec
         .interleave(typst_hints.iter().cloned())
         .format("")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_diagnostics() {
+        let uri = Url::parse("file:///main.typ").unwrap();
+        let range = LspRange::new(LspPosition::new(0, 1), LspPosition::new(0, 2));
+        let error = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::ERROR),
+            message: "unknown variable: x".to_owned(),
+            ..Default::default()
+        };
+        let lint = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::WARNING),
+            message: "`x` shadows a binding".to_owned(),
+            ..Default::default()
+        };
+
+        let mut diagnostics = DiagnosticsMap::from_iter([(uri.clone(), vec![error.clone()])]);
+        let lints = DiagnosticsMap::from_iter([(uri.clone(), vec![lint, error])]);
+        merge_diagnostics(&mut diagnostics, lints);
+
+        let merged = &diagnostics[&uri];
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].severity, Some(LspSeverity::ERROR));
+        assert_eq!(
+            merged[0].message,
+            "unknown variable: x\n\n`x` shadows a binding"
+        );
+    }
+
+    #[test]
+    fn test_merge_same_message() {
+        let uri = Url::parse("file:///main.typ").unwrap();
+        let range = LspRange::new(LspPosition::new(0, 12), LspPosition::new(0, 22));
+        let warning = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::WARNING),
+            source: Some("typst".to_owned()),
+            message: "unknown font family: qzx sans".to_owned(),
+            ..Default::default()
+        };
+        let lint = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::HINT),
+            source: Some("tinymist".to_owned()),
+            message: "unknown font family: Qzx Sans".to_owned(),
+            ..Default::default()
+        };
+
+        let mut diagnostics = DiagnosticsMap::from_iter([(uri.clone(), vec![warning])]);
+        let lints = DiagnosticsMap::from_iter([(uri.clone(), vec![lint])]);
+        merge_diagnostics(&mut diagnostics, lints);
+
+        let merged = &diagnostics[&uri];
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].severity, Some(LspSeverity::WARNING));
+        assert_eq!(merged[0].message, "unknown font family: qzx sans");
+    }
+
+    #[test]
+    fn test_merge_unnecessary_tags() {
+        let uri = Url::parse("file:///main.typ").unwrap();
+        let range = LspRange::new(LspPosition::new(0, 5), LspPosition::new(0, 6));
+        let error = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::ERROR),
+            source: Some("typst".to_owned()),
+            message: "unknown variable: x".to_owned(),
+            ..Default::default()
+        };
+        let lint = LspDiagnostic {
+            range,
+            severity: Some(LspSeverity::HINT),
+            source: Some("tinymist".to_owned()),
+            message: "unused variable: `x`".to_owned(),
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            ..Default::default()
+        };
+
+        let mut diagnostics = DiagnosticsMap::from_iter([(uri.clone(), vec![error])]);
+        let lints = DiagnosticsMap::from_iter([(uri.clone(), vec![lint.clone()])]);
+        merge_diagnostics(&mut diagnostics, lints);
+        assert_eq!(diagnostics[&uri][0].tags, Some(vec![]));
+
+        let other = LspDiagnostic {
+            message: "`x` shadows a binding".to_owned(),
+            tags: None,
+            ..lint.clone()
+        };
+        let mut diagnostics = DiagnosticsMap::from_iter([(uri.clone(), vec![other])]);
+        let lints = DiagnosticsMap::from_iter([(uri.clone(), vec![lint])]);
+        merge_diagnostics(&mut diagnostics, lints);
+        assert_eq!(
+            diagnostics[&uri][0].tags,
+            Some(vec![DiagnosticTag::UNNECESSARY])
+        );
+    }
+}
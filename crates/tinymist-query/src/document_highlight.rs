@@ -0,0 +1,171 @@
+use lsp_types::DocumentHighlightKind;
+
+use crate::{
+    prelude::*,
+    syntax::{get_deref_target, DerefTarget, IdentRef},
+    SemanticRequest,
+};
+
+/// The [`textDocument/documentHighlight`] request is sent from the client to
+/// the server to resolve the document highlights for a given text document
+/// position.
+///
+/// [`textDocument/documentHighlight`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_documentHighlight
+///
+/// The highlights are resolved by the def-use analysis of the document, so
+/// that only the definition the identifier refers to and the references to
+/// that definition are highlighted, while the shadowed or shadowing bindings
+/// of the same name are not. The definition and the assigned references are
+/// highlighted as writes, and the other references as reads.
+#[derive(Debug, Clone)]
+pub struct DocumentHighlightRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The source code position to request for.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for DocumentHighlightRequest {
+    type Response = Vec<DocumentHighlight>;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let offset = ctx.to_typst_pos(self.position, &source)?;
+        let cursor = offset + 1;
+
+        let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
+        let deref_target = get_deref_target(ast_node, cursor)?;
+        let ident = match deref_target {
+            DerefTarget::VarAccess(node) | DerefTarget::Callee(node) => find_ident(node)?,
+            _ => return None,
+        };
+
+        let def_use = ctx.def_use(source.clone())?;
+        let ident_ref = IdentRef {
+            name: ident.text().to_string(),
+            range: ident.range(),
+        };
+        let def_id = def_use.get_ref(&ident_ref);
+        let def_id = def_id.or_else(|| Some(def_use.get_def(source.id(), &ident_ref)?.0))?;
+        let (def_fid, def) = def_use.get_def_by_id(def_id)?;
+
+        let mut highlights = vec![];
+        // The definition is imported from another file if it is not in this one.
+        if def_fid == source.id() {
+            highlights.push((def.range.clone(), DocumentHighlightKind::WRITE));
+        }
+        let root = LinkedNode::new(source.root());
+        highlights.extend(def_use.get_refs(def_id).map(|r| {
+            let kind = if is_assigned(&root, r) {
+                DocumentHighlightKind::WRITE
+            } else {
+                DocumentHighlightKind::READ
+            };
+            (r.range.clone(), kind)
+        }));
+
+        let position_encoding = ctx.position_encoding();
+        Some(
+            highlights
+                .into_iter()
+                .map(|(range, kind)| DocumentHighlight {
+                    range: typst_to_lsp::range(range, &source, position_encoding),
+                    kind: Some(kind),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Finds the identifier at the root of an accessed expression, e.g. `a` in
+/// `a.b`.
+fn find_ident(node: LinkedNode) -> Option<LinkedNode> {
+    let mut may_ident = node.cast::<ast::Expr>()?;
+    loop {
+        match may_ident {
+            ast::Expr::Parenthesized(e) => may_ident = e.expr(),
+            ast::Expr::FieldAccess(e) => may_ident = e.target(),
+            ast::Expr::Ident(..) => break,
+            _ => return None,
+        }
+    }
+
+    node.find(may_ident.span())
+}
+
+/// Whether a reference is the left-hand side of an assignment, e.g. `a` in
+/// `a += 1` or `(a, b) = (b, a)`.
+fn is_assigned(root: &LinkedNode, r: &IdentRef) -> bool {
+    let Some(mut node) = root.leaf_at(r.range.start + 1) else {
+        return false;
+    };
+    while let Some(parent) = node.parent().cloned() {
+        match parent.kind() {
+            SyntaxKind::Binary => {
+                let is_assign = parent.cast::<ast::Binary>().is_some_and(|binary| {
+                    matches!(
+                        binary.op(),
+                        ast::BinOp::Assign
+                            | ast::BinOp::AddAssign
+                            | ast::BinOp::SubAssign
+                            | ast::BinOp::MulAssign
+                            | ast::BinOp::DivAssign
+                    )
+                });
+                return is_assign && node.prev_sibling().is_none();
+            }
+            SyntaxKind::DestructAssignment => return node.prev_sibling().is_none(),
+            SyntaxKind::FieldAccess
+            | SyntaxKind::FuncCall
+            | SyntaxKind::Parenthesized
+            | SyntaxKind::Destructuring
+            | SyntaxKind::Named
+            | SyntaxKind::Spread => {}
+            _ => return false,
+        }
+        node = parent;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("document_highlight", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+
+            let request = DocumentHighlightRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(world).map(|mut highlights| {
+                highlights.sort_by_key(|h| (h.range.start, h.range.end));
+                highlights
+                    .into_iter()
+                    .map(|h| {
+                        let kind = if h.kind == Some(DocumentHighlightKind::WRITE) {
+                            "write"
+                        } else {
+                            "read"
+                        };
+                        format!(
+                            "{kind}@{}:{}:{}:{}",
+                            h.range.start.line,
+                            h.range.start.character,
+                            h.range.end.line,
+                            h.range.end.character
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
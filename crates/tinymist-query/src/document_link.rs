@@ -0,0 +1,153 @@
+use std::ops::Range;
+
+use crate::{prelude::*, syntax::resolve_id_by_path, SemanticRequest};
+
+/// The [`textDocument/documentLink`] request is sent from the client to the
+/// server to request the location of links in a document.
+///
+/// [`textDocument/documentLink`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_documentLink
+///
+/// The links are:
+/// + the paths of `#import` and `#include`.
+/// + the paths loaded by functions, e.g. `image("cat.png")` and
+///   `read("data.txt")`.
+/// + the URLs in markup, e.g. `https://typst.app`.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.0.0.
+#[derive(Debug, Clone)]
+pub struct DocumentLinkRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+}
+
+impl SemanticRequest for DocumentLinkRequest {
+    type Response = Vec<DocumentLink>;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+
+        let mut targets = vec![];
+        collect_link_targets(&LinkedNode::new(source.root()), &mut targets);
+
+        let links = targets.into_iter().filter_map(|(range, target)| {
+            let target = match target {
+                LinkTarget::Path(path) => {
+                    let id = resolve_id_by_path(ctx.world(), source.id(), &path)?;
+                    path_to_url(&ctx.path_for_id(id).ok()?).ok()?
+                }
+                LinkTarget::Url(url) => Url::parse(&url).ok()?,
+            };
+
+            Some(DocumentLink {
+                range: ctx.to_lsp_range(range, &source),
+                target: Some(target),
+                tooltip: None,
+                data: None,
+            })
+        });
+
+        Some(links.collect())
+    }
+}
+
+/// The functions taking a path to load as their first argument.
+const PATH_LOADERS: &[&str] = &[
+    "image",
+    "read",
+    "json",
+    "csv",
+    "yaml",
+    "toml",
+    "xml",
+    "cbor",
+    "bibliography",
+];
+
+pub(crate) enum LinkTarget {
+    Path(String),
+    Url(String),
+}
+
+pub(crate) fn collect_link_targets(
+    node: &LinkedNode,
+    targets: &mut Vec<(Range<usize>, LinkTarget)>,
+) {
+    let path = match node.kind() {
+        SyntaxKind::ModuleImport => node.cast::<ast::ModuleImport>().map(|e| e.source()),
+        SyntaxKind::ModuleInclude => node.cast::<ast::ModuleInclude>().map(|e| e.source()),
+        SyntaxKind::FuncCall => node.cast::<ast::FuncCall>().and_then(|call| {
+            let ast::Expr::Ident(callee) = call.callee() else {
+                return None;
+            };
+            if !PATH_LOADERS.contains(&callee.get().as_str()) {
+                return None;
+            }
+            call.args().items().find_map(|arg| match arg {
+                ast::Arg::Pos(arg) => Some(arg),
+                _ => None,
+            })
+        }),
+        SyntaxKind::Link => {
+            let url = node.text().to_string();
+            targets.push((node.range(), LinkTarget::Url(url)));
+            None
+        }
+        _ => None,
+    };
+
+    if let Some(ast::Expr::Str(path)) = path {
+        if let Some(path_node) = node.find(path.span()) {
+            // The link covers the path without the quotes.
+            let range = path_node.range();
+            let range = range.start + 1..range.end - 1;
+            targets.push((range, LinkTarget::Path(path.get().to_string())));
+        }
+    }
+
+    for child in node.children() {
+        collect_link_targets(&child, targets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_core::path::unix_slash;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("document_link", &|ctx, path| {
+            let request = DocumentLinkRequest { path: path.clone() };
+
+            let result = request.request(ctx).map(|links| {
+                links
+                    .into_iter()
+                    .map(|link| {
+                        let range = link.range;
+                        let target = link.target.map(|target| match target.scheme() {
+                            "file" => {
+                                let fp = unix_slash(&crate::url_to_path(target));
+                                fp.strip_prefix("C:").unwrap_or(&fp).to_owned()
+                            }
+                            _ => target.to_string(),
+                        });
+                        format!(
+                            "{}:{}:{}:{} -> {}",
+                            range.start.line,
+                            range.start.character,
+                            range.end.line,
+                            range.end.character,
+                            target.unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
@@ -0,0 +1,364 @@
+use std::ops::Range;
+
+use serde::Serialize;
+use typst::{
+    foundations::StyleChain,
+    model::{Document, FigureElem, HeadingElem},
+};
+
+use crate::{prelude::*, StatefulRequest};
+
+/// Where an outline is computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutlineSource {
+    /// The compiled document, which includes the headings and figures of the
+    /// included files and generated by code.
+    Document,
+    /// The syntax tree of the requested file, which is used when the
+    /// document is not compiled yet.
+    Syntax,
+}
+
+/// The kind of an outline item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutlineItemKind {
+    /// A heading.
+    Heading,
+    /// A figure.
+    Figure,
+    /// Another labelled element.
+    Label,
+}
+
+/// An item in the outline of a document.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineItem {
+    /// The kind of the item.
+    pub kind: OutlineItemKind,
+    /// The text of the heading, the caption of the figure, or the name of the
+    /// label.
+    pub title: String,
+    /// The level of a heading, starting from 1.
+    pub level: Option<usize>,
+    /// The label of the item, without the angle brackets.
+    pub label: Option<String>,
+    /// The page of the item in the compiled document, starting from 1.
+    pub page: Option<usize>,
+    /// The location of the item in the source files.
+    pub location: Option<LspLocation>,
+    /// The items in the section of a heading.
+    pub children: Vec<OutlineItem>,
+}
+
+/// The response to a [`DocumentOutlineRequest`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentOutline {
+    /// Where the outline is computed from.
+    pub source: OutlineSource,
+    /// The items at the top level, i.e. the items before the first heading
+    /// and the headings which are not nested in another heading.
+    pub items: Vec<OutlineItem>,
+    /// The outline rendered as a nested Markdown list, if requested.
+    pub markdown: Option<String>,
+}
+
+/// A request to get the outline of a document, i.e. its headings, figures,
+/// and labelled elements nested in the sections of the headings.
+///
+/// The outline is computed from the compiled document when it is available,
+/// and from the syntax tree of the requested file otherwise.
+///
+/// This is not part of the LSP protocol.
+#[derive(Debug, Clone)]
+pub struct DocumentOutlineRequest {
+    /// The path of the document to get the outline of.
+    pub path: PathBuf,
+    /// Whether to render the outline as Markdown as well.
+    pub markdown: bool,
+}
+
+impl StatefulRequest for DocumentOutlineRequest {
+    type Response = DocumentOutline;
+
+    fn request(
+        self,
+        ctx: &mut AnalysisContext,
+        doc: Option<VersionedDocument>,
+    ) -> Option<Self::Response> {
+        let (source, entries) = match doc {
+            Some(doc) => (
+                OutlineSource::Document,
+                document_outline(ctx, &doc.document),
+            ),
+            None => {
+                let source = ctx.source_by_path(&self.path).ok()?;
+                let uri = path_to_url(&self.path).ok()?;
+                let entries = syntax_outline(&source).into_iter().map(|(range, item)| {
+                    let location = LspLocation {
+                        uri: uri.clone(),
+                        range: ctx.to_lsp_range(range, &source),
+                    };
+                    OutlineItem {
+                        location: Some(location),
+                        ..item
+                    }
+                });
+                (OutlineSource::Syntax, entries.collect())
+            }
+        };
+
+        let items = nest_items(entries);
+        let markdown = self.markdown.then(|| render_markdown(&items));
+        Some(DocumentOutline {
+            source,
+            items,
+            markdown,
+        })
+    }
+}
+
+/// Creates an item without children.
+fn outline_item(kind: OutlineItemKind, title: String, level: Option<usize>) -> OutlineItem {
+    OutlineItem {
+        kind,
+        title,
+        level,
+        label: None,
+        page: None,
+        location: None,
+        children: vec![],
+    }
+}
+
+/// Collects the outline items of a compiled document in order.
+fn document_outline(ctx: &mut AnalysisContext, doc: &Document) -> Vec<OutlineItem> {
+    let mut items = vec![];
+    for elem in doc.introspector.all() {
+        let label = elem.label().map(|label| label.as_str().to_owned());
+        let mut item = if let Some(heading) = elem.to_packed::<HeadingElem>() {
+            // The headings excluded from the outline, e.g. the title of the
+            // outline itself, are skipped.
+            if !heading.outlined(StyleChain::default()) {
+                continue;
+            }
+            let level = heading.resolve_level(StyleChain::default()).get();
+            let title = heading.body().plain_text().trim().to_owned();
+            outline_item(OutlineItemKind::Heading, title, Some(level))
+        } else if elem.is::<FigureElem>() {
+            let title = match elem.get_by_name("caption") {
+                Some(Value::Content(caption)) => caption.plain_text().trim().to_owned(),
+                _ => String::new(),
+            };
+            outline_item(OutlineItemKind::Figure, title, None)
+        } else if let Some(label) = &label {
+            outline_item(OutlineItemKind::Label, label.clone(), None)
+        } else {
+            continue;
+        };
+
+        item.label = label;
+        item.page = elem.location().map(|loc| doc.introspector.page(loc).get());
+        item.location = span_location(ctx, elem.span());
+        items.push(item);
+    }
+    items
+}
+
+/// Gets the location of a span in its source file.
+fn span_location(ctx: &mut AnalysisContext, span: TypstSpan) -> Option<LspLocation> {
+    let id = span.id()?;
+    let source = ctx.source_by_id(id).ok()?;
+    let range = source.range(span)?;
+    let path = ctx.path_for_id(id).ok()?;
+    Some(LspLocation {
+        uri: path_to_url(&path).ok()?,
+        range: ctx.to_lsp_range(range, &source),
+    })
+}
+
+/// Collects the outline items of a source file in order, with the ranges of
+/// their syntax nodes.
+pub(crate) fn syntax_outline(source: &Source) -> Vec<(Range<usize>, OutlineItem)> {
+    let mut items = vec![];
+    collect_syntax_items(&LinkedNode::new(source.root()), &mut items);
+    items
+}
+
+fn collect_syntax_items(node: &LinkedNode, items: &mut Vec<(Range<usize>, OutlineItem)>) {
+    if let Some(heading) = node.cast::<ast::Heading>() {
+        // The label of a heading is the last node of its body.
+        let mut title = String::new();
+        let mut label = None;
+        for child in heading.body().to_untyped().children() {
+            match child.kind() {
+                SyntaxKind::Label => label = Some(label_name(child.text())),
+                _ => title.push_str(&child.clone().into_text()),
+            }
+        }
+        let level = heading.depth().get();
+        let mut item = outline_item(
+            OutlineItemKind::Heading,
+            title.trim().to_owned(),
+            Some(level),
+        );
+        item.label = label;
+        items.push((node.range(), item));
+        return;
+    }
+
+    if let Some(figure) = node.cast::<ast::FuncCall>().filter(is_figure_call) {
+        let caption = figure.args().items().find_map(|arg| match arg {
+            ast::Arg::Named(named) if named.name().as_str() == "caption" => Some(named.expr()),
+            _ => None,
+        });
+        let title = match caption {
+            Some(ast::Expr::Content(content)) => content.body().to_untyped().clone().into_text(),
+            Some(ast::Expr::Str(caption)) => caption.get(),
+            _ => EcoString::new(),
+        };
+        let title = title.trim().to_owned();
+        items.push((
+            node.range(),
+            outline_item(OutlineItemKind::Figure, title, None),
+        ));
+        return;
+    }
+
+    if node.kind() == SyntaxKind::Label {
+        let name = label_name(node.text());
+        // A label after a figure labels the figure.
+        let prev = node.prev_sibling();
+        if let Some((range, item)) = items.last_mut() {
+            if item.kind == OutlineItemKind::Figure && prev.is_some_and(|p| p.range() == *range) {
+                item.label = Some(name);
+                return;
+            }
+        }
+        let mut item = outline_item(OutlineItemKind::Label, name.clone(), None);
+        item.label = Some(name);
+        items.push((node.range(), item));
+        return;
+    }
+
+    for child in node.children() {
+        collect_syntax_items(&child, items);
+    }
+}
+
+/// Checks whether a call is of the `figure` function.
+fn is_figure_call(call: &ast::FuncCall) -> bool {
+    matches!(call.callee(), ast::Expr::Ident(ident) if ident.as_str() == "figure")
+}
+
+/// Gets the name of a label from its text, e.g. `intro` from `<intro>`.
+fn label_name(text: &str) -> String {
+    text.trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_owned()
+}
+
+/// Nests the items in the sections of the headings before them.
+fn nest_items(items: impl IntoIterator<Item = OutlineItem>) -> Vec<OutlineItem> {
+    fn close(stack: &mut Vec<OutlineItem>, roots: &mut Vec<OutlineItem>) {
+        let item = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(item),
+            None => roots.push(item),
+        }
+    }
+
+    let mut roots = vec![];
+    // The headings whose sections are open, from the outermost.
+    let mut stack: Vec<OutlineItem> = vec![];
+    for item in items {
+        let Some(level) = item.level else {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(item),
+                None => roots.push(item),
+            }
+            continue;
+        };
+        while stack.last().is_some_and(|open| open.level >= Some(level)) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(item);
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+/// Renders the items as a nested Markdown list.
+fn render_markdown(items: &[OutlineItem]) -> String {
+    fn render(items: &[OutlineItem], depth: usize, out: &mut String) {
+        for item in items {
+            out.push_str(&"  ".repeat(depth));
+            out.push_str("- ");
+            match item.kind {
+                OutlineItemKind::Heading => out.push_str(&item.title),
+                OutlineItemKind::Figure if item.title.is_empty() => out.push_str("Figure"),
+                OutlineItemKind::Figure => out.push_str(&format!("Figure: {}", item.title)),
+                OutlineItemKind::Label => {}
+            }
+            if let Some(label) = &item.label {
+                if item.kind != OutlineItemKind::Label {
+                    out.push(' ');
+                }
+                out.push_str(&format!("`<{label}>`"));
+            }
+            if let Some(page) = item.page {
+                out.push_str(&format!(" (p. {page})"));
+            }
+            out.push('\n');
+            render(&item.children, depth + 1, out);
+        }
+    }
+
+    let mut out = String::new();
+    render(items, 0, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_outline() {
+        let source = Source::detached(
+            "Intro text.\n= First <first>\n#figure([a], caption: [A *cat*.]) <fig>\n== Nested\nSee $x$ <eq>.\n= Second\n#figure(image(\"b.png\"))",
+        );
+        let items = nest_items(syntax_outline(&source).into_iter().map(|(_, item)| item));
+        assert_eq!(
+            render_markdown(&items),
+            "- First `<first>`\n  - Figure: A *cat*. `<fig>`\n  - Nested\n    - `<eq>`\n- Second\n  - Figure\n"
+        );
+    }
+
+    #[test]
+    fn test_nest_items() {
+        let heading =
+            |level| outline_item(OutlineItemKind::Heading, level.to_string(), Some(level));
+        let figure = || outline_item(OutlineItemKind::Figure, String::new(), None);
+        let items = nest_items([
+            figure(),
+            heading(2),
+            heading(3),
+            figure(),
+            heading(1),
+            heading(2),
+        ]);
+
+        let shape = |item: &OutlineItem| (item.title.clone(), item.children.len());
+        assert_eq!(
+            items.iter().map(shape).collect::<Vec<_>>(),
+            vec![("".into(), 0), ("2".into(), 1), ("1".into(), 1)]
+        );
+        assert_eq!(shape(&items[1].children[0]), ("3".into(), 1));
+    }
+}
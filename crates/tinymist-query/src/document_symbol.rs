@@ -1,6 +1,11 @@
+use std::ops::Range;
+
 use crate::{
     prelude::*,
-    syntax::{get_lexical_hierarchy, LexicalHierarchy, LexicalScopeKind},
+    syntax::{
+        get_lexical_hierarchy, LexicalHierarchy, LexicalInfo, LexicalKind, LexicalScopeKind,
+        LexicalVarKind,
+    },
     SyntaxRequest,
 };
 
@@ -16,6 +21,11 @@ use crate::{
 ///   symbol’s container name should be used to infer a hierarchy.
 /// * [`DocumentSymbolResponse::Nested`] which is a hierarchy of symbols found
 ///   in a given text document.
+///
+/// The symbols are the headings nested by their levels, the `let` bindings
+/// nested under their lexical parents, the labels, and the captions of
+/// figures. The range of a symbol covers the whole heading, binding, or
+/// figure, while the selection range points at its name.
 #[derive(Debug, Clone)]
 pub struct DocumentSymbolRequest {
     /// The path of the document to retrieve symbols from.
@@ -46,7 +56,10 @@ fn filter_document_symbols(
     symbols
         .iter()
         .map(|e| {
-            let rng = typst_to_lsp::range(e.info.range.clone(), source, position_encoding);
+            let range = symbol_range(source, &e.info);
+            let rng = typst_to_lsp::range(range, source, position_encoding);
+            let selection_rng =
+                typst_to_lsp::range(e.info.range.clone(), source, position_encoding);
 
             DocumentSymbol {
                 name: e.info.name.clone(),
@@ -55,8 +68,7 @@ fn filter_document_symbols(
                 tags: None,
                 deprecated: None,
                 range: rng,
-                selection_range: rng,
-                //             .raw_range,
+                selection_range: selection_rng,
                 children: e
                     .children
                     .as_ref()
@@ -66,6 +78,29 @@ fn filter_document_symbols(
         .collect()
 }
 
+/// Gets the range of the node enclosing a symbol, e.g. the heading of a
+/// heading's body and the `let` binding of a variable's name.
+fn symbol_range(source: &Source, info: &LexicalInfo) -> Range<usize> {
+    let is_enclosing = |kind: SyntaxKind| match &info.kind {
+        LexicalKind::Heading(..) => kind == SyntaxKind::Heading,
+        LexicalKind::Var(LexicalVarKind::Function | LexicalVarKind::Variable) => {
+            matches!(kind, SyntaxKind::LetBinding | SyntaxKind::ForLoop)
+        }
+        LexicalKind::Figure => kind == SyntaxKind::FuncCall,
+        _ => false,
+    };
+
+    let mut node = LinkedNode::new(source.root()).leaf_at(info.range.start + 1);
+    while let Some(n) = node {
+        if is_enclosing(n.kind()) {
+            return n.range();
+        }
+        node = n.parent().cloned();
+    }
+
+    info.range.clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
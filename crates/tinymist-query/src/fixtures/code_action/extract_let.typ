@@ -0,0 +1,2 @@
+#let a = (1 + 2) * 3 /* range -11..-6 */
+#a
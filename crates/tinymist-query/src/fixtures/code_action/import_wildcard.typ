@@ -0,0 +1,6 @@
+// path: base.typ
+#let a = 1;
+#let b = 2;
+-----
+#import "base.typ": * /* range -2..-1 */
+#a
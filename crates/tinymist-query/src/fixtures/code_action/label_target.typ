@@ -0,0 +1,7 @@
+= Introduction
+Some text.
+
+= Methods
+#figure(rect(), caption: [Setup])
+
+See @results /* range -3..-3 */
@@ -0,0 +1 @@
+#image("missing.png") /* range -5..-4 */
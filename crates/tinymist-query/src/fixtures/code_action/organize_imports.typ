@@ -0,0 +1,3 @@
+#import "b.typ": y, x
+#import "@preview/pkg:0.1.0": z
+#x #y #z /* range -8..-7 */
@@ -0,0 +1,3 @@
+Some text.
+
+= Intro /* range -3..-2 */
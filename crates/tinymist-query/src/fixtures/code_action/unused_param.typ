@@ -0,0 +1,2 @@
+#let f(x, y) = x /* range -7..-6 */
+#f(1, 2)
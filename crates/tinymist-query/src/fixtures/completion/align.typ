@@ -0,0 +1,2 @@
+// contains: bottom + left, center, center + horizon, left, top + left
+#align(/* range 0..1 */)
@@ -0,0 +1,2 @@
+// contains: it => ..
+#(1, 2).map(/* range 0..1 */)
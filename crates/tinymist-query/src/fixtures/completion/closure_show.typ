@@ -0,0 +1,2 @@
+// contains: it => ..
+#show heading: /* range 0..1 */
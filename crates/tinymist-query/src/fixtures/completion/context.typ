@@ -0,0 +1,2 @@
+// contains: counter, counter at, counter display, counter final, counter value
+#context cou/* range 0..1 */
@@ -0,0 +1,5 @@
+// path: utils.typ
+#let qzxfunc(x) = x
+#let qzxvalue = 1
+-----
+#qzx(/* range -1..0 */)
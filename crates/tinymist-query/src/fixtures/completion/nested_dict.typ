@@ -0,0 +1,2 @@
+// contains: array, phase
+#rect(stroke: (dash: (/* range 0..1 */)))
@@ -0,0 +1,4 @@
+// contains: Typst, mermaid
+#show raw.where(lang: "mermaid"): it => it
+
+```/* range 0..1 */ ```
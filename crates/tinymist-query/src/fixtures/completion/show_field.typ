@@ -0,0 +1,2 @@
+// contains: body, caption, func, kind, numbering, supplement
+#show figure: it => it./* range 0..1 */
@@ -0,0 +1,2 @@
+// contains: heading, label selector, regex selector, text selector, where selector
+#show /* range 0..1 */
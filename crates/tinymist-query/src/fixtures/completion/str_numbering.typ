@@ -0,0 +1,2 @@
+// contains: 1., 1.1, a)
+#set heading(numbering: "1"/* range -2..-1 */)
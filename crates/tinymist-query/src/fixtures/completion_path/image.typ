@@ -0,0 +1 @@
+#image(""/* range -1..0 */)
@@ -0,0 +1 @@
+#read(""/* range -1..0 */)
@@ -0,0 +1,9 @@
+// path: intro.typ
+#block[
+  The quick brown fox jumps over the lazy dog and runs far away from the hunters.
+]
+-----
+// path: outro.typ
+#block[
+  The quick brown fox jumps over the lazy dog and runs far away from the hunters.
+]
@@ -0,0 +1,3 @@
+#let t = red;
+#let red = 1;
+#let t = red;
\ No newline at end of file
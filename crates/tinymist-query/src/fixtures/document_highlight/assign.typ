@@ -0,0 +1,6 @@
+#let /* ident after */ x = (1, 2);
+#{
+  x.at(0) = 3
+  x += (4,)
+  x.len()
+}
@@ -0,0 +1,10 @@
+#let /* ident after */ x = 1;
+
+#let y = {
+  x + x;
+}
+
+#let f(y) = x + y;
+
+#let x = x;
+#let f = x;
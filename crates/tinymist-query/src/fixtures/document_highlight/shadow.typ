@@ -0,0 +1,3 @@
+#let x = 1;
+#let /* ident after */ x = x + 1;
+#x
@@ -0,0 +1,9 @@
+// path: base.typ
+#let x = 1;
+-----
+// path: sub/main.typ
+#import "../base.typ": x
+#include "/base.typ"
+#image("cat.png")
+#read("/data.txt")
+See https://typst.app for more.
@@ -0,0 +1,5 @@
+= Intro
+#figure(
+  rect(),
+  caption: [A cat],
+) <fig>
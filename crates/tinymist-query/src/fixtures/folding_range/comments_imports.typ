@@ -0,0 +1,10 @@
+// License notes
+// more notes
+#import "a.typ": x
+#import "b.typ": y
+
+/* block
+comment */
+- first item
+  continued
+- second
@@ -0,0 +1,6 @@
+// path: refs.bib
+@article{knuth, title = {The Art of Computer Programming}}
+@book{lamport, title = {LaTeX}}
+-----
+#bibliography("refs.bib")
+/* position after */ @knuth and @knuth.
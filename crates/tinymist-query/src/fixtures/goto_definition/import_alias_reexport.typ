@@ -0,0 +1,8 @@
+// path: lib.typ
+#let f() = 1;
+-----
+// path: base.typ
+#import "lib.typ": f as g
+-----
+#import "base.typ": g as h
+#(/* position after */ h);
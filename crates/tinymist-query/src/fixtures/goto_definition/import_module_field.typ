@@ -0,0 +1,5 @@
+// path: base.typ
+#let f() = 1;
+-----
+#import "base.typ"
+#(base./* position after */ f);
@@ -0,0 +1,3 @@
+#let f = (x) => x;
+#let g = f
+#(/* position after */ g);
@@ -0,0 +1,6 @@
+// path: base.typ
+#let f(x) = x;
+-----
+#import "base.typ": f
+#let g = f.with(1)
+#(/* position after */ g);
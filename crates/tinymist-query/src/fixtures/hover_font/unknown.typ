@@ -0,0 +1 @@
+#text(font: /* position after */ "Qzx Sans")[Hello]
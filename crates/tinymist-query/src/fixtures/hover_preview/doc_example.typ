@@ -0,0 +1,8 @@
+/// Doubles a number.
+///
+/// ```example
+/// #double(2)
+/// ```
+#let double(x) = 2 * x;
+
+#(/* ident after */ double(1));
@@ -0,0 +1 @@
+/* position after */ $ E = m c^2 $
@@ -0,0 +1 @@
+#(/* ident after */ sym.arrow);
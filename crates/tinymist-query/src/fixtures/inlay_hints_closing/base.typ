@@ -0,0 +1,11 @@
+#show heading: it => [
+  #set text(red)
+  #it.body
+]
+#figure[
+  A
+  B
+]
+#let f(x) = {
+  x
+}
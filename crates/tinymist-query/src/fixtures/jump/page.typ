@@ -0,0 +1,4 @@
+#set page(width: 120pt, height: 60pt, margin: 10pt)
+Hello
+#pagebreak()
+Next /* position after */page
@@ -0,0 +1,2 @@
+#set page(width: 120pt, height: 60pt, margin: 10pt)
+Hello /* position after */World
@@ -0,0 +1,5 @@
+= Introduction <intro>
+
+See /* position after */ @intro and @intro[the intro].
+
+#ref(<intro>) and @other
@@ -0,0 +1,3 @@
+/* position after */ ```typ
+#let x = 1;
+```
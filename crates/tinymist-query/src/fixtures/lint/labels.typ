@@ -0,0 +1,8 @@
+// path: /chapter.typ
+= Intro <intro>
+= Methods <methods>
+-----
+= Results <results>
+See @intro and @methds here.
+#show <results>: set text(red)
+Never referenced <lonely>
@@ -0,0 +1,2 @@
+#image("missing.png")
+#json("/data/missing.json")
@@ -0,0 +1,4 @@
+#import "@preview/example:0.1.0": add
+#import "@preview/example:0.2.0": sub
+#import "@preview/unknown:1.0.0": mul
+#add #sub #mul
@@ -0,0 +1,7 @@
+#let x = 1
+#let f() = {
+  let x = 2
+  x
+}
+#let text = "a"
+#text(f()) #x
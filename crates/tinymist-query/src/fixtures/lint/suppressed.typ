@@ -0,0 +1,4 @@
+// tinymist-ignore(unused-variable)
+#let a = 1
+#let b = 2 // tinymist-ignore
+#let c = 3
@@ -0,0 +1,9 @@
+#let f() = {
+  return 1
+  let x = 2
+  x
+}
+#if false [a] else [b]
+#while false { }
+#if 1 < 2 [c]
+#f()
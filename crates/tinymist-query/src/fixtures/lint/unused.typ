@@ -0,0 +1,5 @@
+#let _ignored = 1
+#let unused = 1
+#let used = 2
+#let f(x, y, _z) = x
+#f(used, 3, 4)
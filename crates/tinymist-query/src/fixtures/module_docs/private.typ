@@ -0,0 +1,4 @@
+/// Adds numbers.
+#let add(x, y) = _check(x) + y
+#let _check(x) = x
+#let value = 1
@@ -0,0 +1,2 @@
+#let x = 1;
+/* position after */#x
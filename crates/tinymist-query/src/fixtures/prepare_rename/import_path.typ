@@ -0,0 +1 @@
+#import /* position after */"base.typ"
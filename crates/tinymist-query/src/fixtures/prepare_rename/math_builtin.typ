@@ -0,0 +1 @@
+$/* position after */pi$
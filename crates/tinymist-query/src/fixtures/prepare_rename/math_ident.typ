@@ -0,0 +1,2 @@
+#let alpha = 1;
+$/* ident after */alpha + 1$
@@ -0,0 +1,10 @@
+// path: /lib.typ
+#let check(x) = assert(x > 0, message: "not positive")
+-----
+// path: /tests/fail.typ
+#import "/lib.typ": check
+#check(-1)
+-----
+// path: /tests/pass.typ
+#import "/lib.typ": check
+#check(1)
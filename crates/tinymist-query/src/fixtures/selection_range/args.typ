@@ -0,0 +1 @@
+#f(a, /* position after */b)
\ No newline at end of file
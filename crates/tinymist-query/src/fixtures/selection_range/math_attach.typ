@@ -0,0 +1 @@
+$x_(i + /* position after */1)^2$
@@ -0,0 +1,5 @@
+= Title
+Some *bo/* position after */ld* text
+more text
+
+Next para
\ No newline at end of file
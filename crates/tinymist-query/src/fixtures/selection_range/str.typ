@@ -0,0 +1 @@
+#let s = /* position after */"hello world"
\ No newline at end of file
@@ -0,0 +1,6 @@
+#let f() = 1
+#let x = 1
+#let y = 2
+#(x += y)
+#f() #heading[H] #calc.max(x, y)
+$alpha$ #sym.arrow
@@ -0,0 +1,2 @@
+#let f(x, y, size: 1) = x
+#f(1, size: /* position after */3)
@@ -0,0 +1,3 @@
+#let f(x, y, size: 1) = x
+#let g = f.with(1)
+#g(/* position after */2)
@@ -0,0 +1,4 @@
+#let add(x, y) = x + y
+-----
+#import "s0.typ": add
+#add(1, 2)
@@ -0,0 +1,11 @@
+// path: base.typ
+#import "other.typ": y
+#let x = 1;
+-----
+// path: other.typ
+#let y = 2;
+-----
+// path: sub/main.typ
+#import "../base.typ": x
+#include "/base.typ"
+#import "../other.typ": y
@@ -0,0 +1,14 @@
+// path: lib/base.typ
+#import "util.typ": y
+#import "../other.typ": z
+#let x = 1;
+-----
+// path: lib/util.typ
+#let y = 2;
+-----
+// path: other.typ
+#let z = 3;
+-----
+// path: main.typ
+#import "lib/base.typ": x
+#include "/lib/util.typ"
@@ -1,3 +1,7 @@
+use std::ops::Range;
+
+use lsp_types::FoldingRangeKind;
+
 use crate::{
     prelude::*,
     syntax::{get_lexical_hierarchy, LexicalHierarchy, LexicalKind, LexicalScopeKind},
@@ -12,6 +16,12 @@ use crate::{
 /// # Compatibility
 ///
 /// This request was introduced in specification version 3.10.0.
+///
+/// The folding ranges are the heading sections, which end before the next
+/// heading of the same or a higher level, the blocks, the argument lists, the
+/// list items, and the runs of comments and imports. The comments and the
+/// imports are marked with their kinds so that editors can collapse them
+/// separately.
 #[derive(Debug, Clone)]
 pub struct FoldingRangeRequest {
     /// The path of the document to get folding ranges for.
@@ -48,6 +58,25 @@ impl SyntaxRequest for FoldingRangeRequest {
             true,
             &mut results,
         );
+
+        let mut syntax_ranges = vec![];
+        calc_syntax_folding(&LinkedNode::new(source.root()), &mut syntax_ranges);
+        for (range, kind) in syntax_ranges {
+            let rng = typst_to_lsp::range(range, source, position_encoding);
+            if rng.start.line == rng.end.line {
+                continue;
+            }
+
+            results.push(FoldingRange {
+                start_line: rng.start.line,
+                start_character: Some(rng.start.character),
+                end_line: rng.end.line,
+                end_character: line_folding_only.then_some(rng.end.character),
+                kind,
+                collapsed_text: None,
+            });
+        }
+
         if false {
             trace!("FoldingRangeRequest(line_folding_only={line_folding_only}) symbols: {symbols:#?} results: {results:#?}");
         }
@@ -103,10 +132,11 @@ fn calc_folding_range(
         }
 
         if let Some(ch) = &e.children {
-            let parent_last_loc = if is_not_last_range {
-                (rng.end.line, Some(rng.end.character))
+            // The children end with the section of a heading, or the block.
+            let parent_last_loc = if matches!(e.info.kind, LexicalKind::Heading(..)) {
+                (range.end_line, None)
             } else {
-                parent_last_loc
+                (rng.end.line, Some(rng.end.character))
             };
 
             calc_folding_range(
@@ -116,7 +146,7 @@ fn calc_folding_range(
                 line_folding_only,
                 parent_last_loc,
                 last_loc,
-                !is_not_final_last_range,
+                false,
                 ranges,
             );
         }
@@ -125,6 +155,65 @@ fn calc_folding_range(
     }
 }
 
+/// A folding range found by [`calc_syntax_folding`].
+type SyntaxFolding = (Range<usize>, Option<FoldingRangeKind>);
+
+/// Collects the comments, the imports, and the list items, which are not
+/// blocks in the lexical hierarchy.
+fn calc_syntax_folding(node: &LinkedNode, ranges: &mut Vec<SyntaxFolding>) {
+    let mut comments: Option<Range<usize>> = None;
+    let mut imports: Option<Range<usize>> = None;
+
+    for child in node.children() {
+        match child.kind() {
+            SyntaxKind::LineComment => {
+                flush_run(&mut imports, FoldingRangeKind::Imports, ranges);
+                extend_run(&mut comments, &child);
+            }
+            SyntaxKind::ModuleImport => {
+                flush_run(&mut comments, FoldingRangeKind::Comment, ranges);
+                extend_run(&mut imports, &child);
+            }
+            // A single line break or a hash keeps the run.
+            SyntaxKind::Space if child.text().matches('\n').count() <= 1 => {}
+            SyntaxKind::Hash | SyntaxKind::Semicolon => {}
+            kind => {
+                flush_run(&mut comments, FoldingRangeKind::Comment, ranges);
+                flush_run(&mut imports, FoldingRangeKind::Imports, ranges);
+                match kind {
+                    SyntaxKind::BlockComment => {
+                        ranges.push((child.range(), Some(FoldingRangeKind::Comment)));
+                    }
+                    SyntaxKind::ListItem | SyntaxKind::EnumItem | SyntaxKind::TermItem => {
+                        ranges.push((child.range(), None));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        calc_syntax_folding(&child, ranges);
+    }
+
+    flush_run(&mut comments, FoldingRangeKind::Comment, ranges);
+    flush_run(&mut imports, FoldingRangeKind::Imports, ranges);
+}
+
+fn extend_run(run: &mut Option<Range<usize>>, node: &LinkedNode) {
+    let start = run.as_ref().map_or(node.offset(), |run| run.start);
+    *run = Some(start..node.range().end);
+}
+
+fn flush_run(
+    run: &mut Option<Range<usize>>,
+    kind: FoldingRangeKind,
+    ranges: &mut Vec<SyntaxFolding>,
+) {
+    if let Some(range) = run.take() {
+        ranges.push((range, Some(kind)));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
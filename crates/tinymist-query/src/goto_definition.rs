@@ -1,6 +1,11 @@
 use log::debug;
 
-use crate::{analysis::find_definition, prelude::*, syntax::get_deref_target, SemanticRequest};
+use crate::{
+    analysis::{find_bibliography_keys, find_definition, label_name_at, resolve_import_alias},
+    prelude::*,
+    syntax::get_deref_target,
+    SemanticRequest,
+};
 
 /// The [`textDocument/definition`] request asks the server for the definition
 /// location of a symbol at a given text document position.
@@ -17,6 +22,12 @@ use crate::{analysis::find_definition, prelude::*, syntax::get_deref_target, Sem
 /// ```text
 /// InitializeParams::capabilities::text_document::definition::link_support
 /// ```
+///
+/// The definitions of imported items are resolved through renamed imports and
+/// re-exports, and the fields of imported modules, e.g. `add` in `pkg.add`,
+/// are resolved into the source files of the modules, including the ones of
+/// packages in the package cache. The citations, e.g. `@key`, are resolved
+/// to the entries of the bibliography files.
 #[derive(Debug, Clone)]
 pub struct GotoDefinitionRequest {
     /// The path of the document to request for.
@@ -36,11 +47,32 @@ impl SemanticRequest for GotoDefinitionRequest {
         let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
         debug!("ast_node: {ast_node:?}", ast_node = ast_node);
 
+        // The citation keys are defined in the bibliography files.
+        if let Some((name, range)) = label_name_at(&ast_node) {
+            let keys = find_bibliography_keys(ctx, &name);
+            if !keys.is_empty() {
+                let origin_selection_range = ctx.to_lsp_range(range, &source);
+                let links = keys.into_iter().filter_map(|(fid, bib, range)| {
+                    let uri = path_to_url(&ctx.path_for_id(fid).ok()?).ok()?;
+                    let range = ctx.to_lsp_range(range, &bib);
+                    Some(LocationLink {
+                        origin_selection_range: Some(origin_selection_range),
+                        target_uri: uri,
+                        target_range: range,
+                        target_selection_range: range,
+                    })
+                });
+                return Some(GotoDefinitionResponse::Link(links.collect()));
+            }
+        }
+
         let deref_target = get_deref_target(ast_node, cursor)?;
         let use_site = deref_target.node().clone();
         let origin_selection_range = ctx.to_lsp_range(use_site.range(), &source);
 
         let def = find_definition(ctx, source.clone(), deref_target)?;
+        // Jumps to the renamed items rather than the import statements.
+        let def = resolve_import_alias(ctx, def);
 
         let (fid, def_range) = def.def_at?;
 
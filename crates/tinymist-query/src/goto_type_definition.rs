@@ -0,0 +1,195 @@
+use std::ops::Range;
+
+use log::debug;
+use typst::foundations::func::Repr;
+use typst::syntax::Span;
+
+use crate::{
+    analysis::FlowType,
+    prelude::*,
+    syntax::{find_document_before, get_deref_target, DerefTarget},
+    SemanticRequest,
+};
+
+/// The [`textDocument/typeDefinition`] request asks the server for the type
+/// definition location of a symbol at a given text document position.
+///
+/// [`textDocument/typeDefinition`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_typeDefinition
+///
+/// The type of a variable is inferred by type checking, or by evaluating the
+/// document if the type is unknown. The type definitions are:
+/// + the `let` binding of a user function, for a variable bound to the
+///   function, e.g. `g` after `#let g = f.with(1)`.
+/// + the `let` binding of a dictionary documented by comments, for a variable
+///   bound to the dictionary, i.e. the comments give the type a name.
+///
+/// The builtin functions and elements have no source to jump to.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.6.0.
+#[derive(Debug, Clone)]
+pub struct GotoTypeDefinitionRequest {
+    /// The path of the document to request for.
+    pub path: PathBuf,
+    /// The source code position to request for.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for GotoTypeDefinitionRequest {
+    type Response = GotoDefinitionResponse;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let offset = ctx.to_typst_pos(self.position, &source)?;
+        let cursor = offset + 1;
+
+        let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
+        let use_site = match get_deref_target(ast_node, cursor)? {
+            DerefTarget::VarAccess(node) | DerefTarget::Callee(node) => node,
+            _ => return None,
+        };
+        let origin_selection_range = ctx.to_lsp_range(use_site.range(), &source);
+
+        let (fid, target_range, selection_range) = find_type_definition(ctx, &source, &use_site)?;
+
+        let span_path = ctx.path_for_id(fid).ok()?;
+        let uri = path_to_url(&span_path).ok()?;
+        let span_source = ctx.source_by_id(fid).ok()?;
+
+        let res = Some(GotoDefinitionResponse::Link(vec![LocationLink {
+            origin_selection_range: Some(origin_selection_range),
+            target_uri: uri,
+            target_range: ctx.to_lsp_range(target_range, &span_source),
+            target_selection_range: ctx.to_lsp_range(selection_range, &span_source),
+        }]));
+
+        debug!("goto_type_definition: {fid:?} {res:?}");
+        res
+    }
+}
+
+/// The location of a type definition, i.e. the range of the `let` binding and
+/// the range of its name.
+type TypeDefinition = (TypstFileId, Range<usize>, Range<usize>);
+
+fn find_type_definition(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    use_site: &LinkedNode,
+) -> Option<TypeDefinition> {
+    let ty = ctx.type_of_span(use_site.span());
+    let ty_chk = ctx.type_check(source.clone());
+    if let Some((ty, ty_chk)) = ty.zip(ty_chk) {
+        let ty = ty_chk.simplify(ty, true);
+        if let Some(def) = type_def_of_type(ctx, &ty) {
+            return Some(def);
+        }
+    }
+
+    analyze_expr(ctx.world(), use_site)
+        .into_iter()
+        .find_map(|(value, _)| match value {
+            Value::Func(func) => type_def_of_func(ctx, &func),
+            _ => None,
+        })
+}
+
+fn type_def_of_type(ctx: &mut AnalysisContext, ty: &FlowType) -> Option<TypeDefinition> {
+    match ty {
+        FlowType::Value(v) => match &v.0 {
+            Value::Func(func) => type_def_of_func(ctx, func),
+            _ => None,
+        },
+        FlowType::With(w) => type_def_of_type(ctx, &w.0),
+        FlowType::Dict(record) => {
+            let (_, _, span) = record.fields.first()?;
+            type_def_of_dict(ctx, *span)
+        }
+        FlowType::Union(types) => types.iter().find_map(|ty| type_def_of_type(ctx, ty)),
+        _ => None,
+    }
+}
+
+/// Finds the `let` binding of a user function, through the functions created
+/// by `with`.
+fn type_def_of_func(ctx: &mut AnalysisContext, func: &Func) -> Option<TypeDefinition> {
+    let mut func = func;
+    while let Repr::With(w) = func.inner() {
+        func = &w.0;
+    }
+    if !matches!(func.inner(), Repr::Closure(..)) {
+        return None;
+    }
+
+    let fid = func.span().id()?;
+    let source = ctx.source_by_id(fid).ok()?;
+    let mut node = LinkedNode::new(source.root()).find(func.span())?;
+    while node.kind() != SyntaxKind::Closure {
+        node = node.parent()?.clone();
+    }
+
+    let closure = node.cast::<ast::Closure>()?;
+    let name_range = closure
+        .name()
+        .and_then(|name| node.find(name.span()))
+        .map(|name| name.range());
+    let binding = node.parent().filter(|p| p.kind() == SyntaxKind::LetBinding);
+    let (range, selection_range) = match (binding, name_range) {
+        (Some(binding), Some(name_range)) => (binding.range(), name_range),
+        (Some(binding), None) => (binding.range(), binding_name_range(binding)?),
+        (None, _) => (node.range(), name_range.unwrap_or_else(|| node.range())),
+    };
+
+    Some((fid, range, selection_range))
+}
+
+/// Finds the `let` binding of a dictionary documented by comments, given the
+/// span of a key of the dictionary.
+fn type_def_of_dict(ctx: &mut AnalysisContext, key_span: Span) -> Option<TypeDefinition> {
+    let fid = key_span.id()?;
+    let source = ctx.source_by_id(fid).ok()?;
+    let mut node = LinkedNode::new(source.root()).find(key_span)?;
+    while node.kind() != SyntaxKind::Dict {
+        node = node.parent()?.clone();
+    }
+
+    let binding = node
+        .parent()
+        .filter(|p| p.kind() == SyntaxKind::LetBinding)?;
+    let name_range = binding_name_range(binding)?;
+    find_document_before(&source, name_range.start)?;
+
+    Some((fid, binding.range(), name_range))
+}
+
+fn binding_name_range(binding: &LinkedNode) -> Option<Range<usize>> {
+    let name = binding
+        .cast::<ast::LetBinding>()?
+        .kind()
+        .bindings()
+        .into_iter()
+        .next()?;
+    Some(binding.find(name.span())?.range())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("goto_type_definition", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+
+            let request = GotoTypeDefinitionRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(world);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+}
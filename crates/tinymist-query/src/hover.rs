@@ -1,12 +1,22 @@
 use core::fmt;
 
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reflexo::debug_loc::DataSource;
+use reflexo::path::unix_slash;
+use typst::visualize::Color;
+
 use crate::{
-    analysis::{analyze_dyn_signature, find_definition, DefinitionLink, Signature},
+    analysis::{
+        analyze_dyn_signature, compile_preview, doc_example, find_color_expr_at, find_definition,
+        find_labelled_equation, font_family_at, label_name_at, lang_sample, select_fallback,
+        text_lang, DefinitionLink, ProviderQuery, Signature,
+    },
+    i18n::{tr, tr_args},
     jump_from_cursor,
     prelude::*,
     syntax::{find_document_before, get_deref_target, LexicalKind, LexicalVarKind},
     upstream::{expr_tooltip, tooltip, Tooltip},
-    LspHoverContents, StatefulRequest,
+    LspHoverContents, SemanticRequest, StatefulRequest,
 };
 
 /// The [`textDocument/hover`] request asks the server for hover information at
@@ -39,21 +49,31 @@ impl StatefulRequest for HoverRequest {
         // the typst's cursor is 1-based, so we need to add 1 to the offset
         let cursor = offset + 1;
 
-        let contents = def_tooltip(ctx, &source, cursor).or_else(|| {
+        let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
+
+        let mut expr_type = None;
+        let contents = font_family_tooltip(ctx, &source, &ast_node);
+        let contents = contents.or_else(|| def_tooltip(ctx, &source, cursor));
+        let contents = contents.or_else(|| {
+            expr_type = expr_type_tooltip(ctx, &ast_node);
             Some(typst_to_lsp::tooltip(&tooltip(
                 ctx.world(),
                 doc_ref,
                 &source,
                 cursor,
             )?))
-        })?;
+        });
+        let provided = provider_hover(ctx, &source, offset);
+        let preview = hover_preview(ctx, &source, &ast_node, cursor);
+        if contents.is_none() && expr_type.is_none() && provided.is_empty() && preview.is_none() {
+            return None;
+        }
 
-        let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
         let range = ctx.to_lsp_range(ast_node.range(), &source);
 
         // Neovim shows ugly hover if the hover content is in array, so we join them
         // manually with divider bars.
-        let mut contents = match contents {
+        let contents = contents.map(|contents| match contents {
             LspHoverContents::Array(contents) => contents
                 .into_iter()
                 .map(|e| match e {
@@ -74,7 +94,20 @@ impl StatefulRequest for HoverRequest {
                     MarkupKind::PlainText => e.value,
                 }
             }
-        };
+        });
+        let mut contents = contents
+            .into_iter()
+            .chain(expr_type)
+            .chain(provided)
+            .join("\n---\n");
+
+        if let Some(preview) = preview {
+            contents = if contents.is_empty() {
+                preview
+            } else {
+                format!("{preview}\n---\n{contents}")
+            };
+        }
 
         if ctx.analysis.enable_periscope {
             if let Some(doc) = doc.clone() {
@@ -115,6 +148,186 @@ impl StatefulRequest for HoverRequest {
     }
 }
 
+fn provider_hover(ctx: &mut AnalysisContext, source: &Source, offset: usize) -> Vec<String> {
+    if ctx.analysis.providers.is_empty() {
+        return vec![];
+    }
+    let path = unix_slash(source.id().vpath().as_rootless_path());
+    let Some(query) = ProviderQuery::new(source, path, offset) else {
+        return vec![];
+    };
+    ctx.analysis.providers.hover(&query)
+}
+
+/// Renders a swatch of the hovered color, or a preview of the hovered
+/// equation, symbol, or of the example in the docs of the hovered user
+/// function.
+fn hover_preview(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    leaf: &LinkedNode,
+    cursor: usize,
+) -> Option<String> {
+    if !ctx.analysis.enable_hover_preview {
+        return None;
+    }
+
+    if let Some((_, color)) = find_color_expr_at(ctx, source, cursor) {
+        return Some(color_swatch(color));
+    }
+
+    let code = preview_code(ctx, source, leaf, cursor)?;
+    let document = compile_preview(ctx.world(), &code)?;
+    let doc = VersionedDocument {
+        version: 0,
+        document,
+    };
+    ctx.resources.preview_image(ctx, doc)
+}
+
+/// Renders a color as a markdown image.
+fn color_swatch(color: Color) -> String {
+    let hex = color.to_hex();
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="16"><rect width="48" height="16" rx="2" fill="{hex}"/></svg>"#
+    );
+    let svg = utf8_percent_encode(&svg, NON_ALPHANUMERIC);
+    format!("![{hex}](data:image/svg+xml,{svg}) `{hex}`")
+}
+
+fn preview_code(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    leaf: &LinkedNode,
+    cursor: usize,
+) -> Option<String> {
+    // The references to equations preview the referenced equations.
+    if matches!(leaf.kind(), SyntaxKind::Ref | SyntaxKind::RefMarker) {
+        let (name, _) = label_name_at(leaf)?;
+        return find_labelled_equation(ctx, &name);
+    }
+
+    // Equations are previewed as a whole.
+    let mut node = Some(leaf);
+    while let Some(n) = node {
+        if n.kind() == SyntaxKind::Equation {
+            return Some(n.get().clone().into_text().to_string());
+        }
+        node = n.parent();
+    }
+
+    if leaf.kind() == SyntaxKind::Ident {
+        let mut expr = leaf.clone();
+        while let Some(parent) = expr
+            .parent()
+            .filter(|p| p.kind() == SyntaxKind::FieldAccess)
+        {
+            expr = parent.clone();
+        }
+        let values = analyze_expr(ctx.world(), &expr);
+        if let Some((Value::Symbol(sym), _)) = values.first() {
+            return Some(format!("#text(2em)[\\u{{{:x}}}]", sym.get() as u32));
+        }
+    }
+
+    // Only the docs of user functions are previewed, as the examples of the
+    // builtin ones refer to assets of the documentation.
+    let deref_target = get_deref_target(leaf.clone(), cursor)?;
+    let lnk = find_definition(ctx, source.clone(), deref_target)?;
+    if !matches!(lnk.kind, LexicalKind::Var(LexicalVarKind::Function)) {
+        return None;
+    }
+    let (fid, def_range) = lnk.def_at?;
+    let src = ctx.source_by_id(fid).ok()?;
+    let example = doc_example(&find_document_before(&src, def_range.start)?)?;
+
+    // Files of packages cannot be imported by path.
+    if fid.package().is_some() {
+        return Some(example);
+    }
+    let path = unix_slash(fid.vpath().as_rootless_path());
+    Some(format!("#import \"/{path}\": *\n{example}"))
+}
+
+/// Shows whether a font family passed to the `text` function is available,
+/// the variants of the family and the files providing them, and the font
+/// that the glyphs missing in the family fall back to in the document
+/// language.
+fn font_family_tooltip(
+    ctx: &AnalysisContext,
+    source: &Source,
+    leaf: &LinkedNode,
+) -> Option<LspHoverContents> {
+    let family = font_family_at(leaf)?;
+    let lang = text_lang(source, leaf).unwrap_or_else(|| "en".to_owned());
+    let book = ctx.world().book();
+
+    let fonts = book
+        .select_family(&family.to_lowercase())
+        .collect::<Vec<_>>();
+    let mut results = vec![];
+    if fonts.is_empty() {
+        results.push(tr_args(
+            "hover.font.unavailable",
+            "Font family `{family}` is not available.",
+            &[("family", &family)],
+        ));
+    } else {
+        let mut variants = vec![tr_args(
+            "hover.font.available",
+            "Font family `{family}` is available with {count} variant(s):",
+            &[("family", &family), ("count", &fonts.len())],
+        )];
+        for &idx in &fonts {
+            let Some(info) = book.info(idx) else {
+                continue;
+            };
+            let variant = info.variant;
+            let source = ctx.world().font(idx);
+            let source = source.and_then(|font| ctx.resources.font_info(font));
+            let source = match source.as_deref() {
+                Some(DataSource::Fs(fs)) => format!("`{}`", fs.path),
+                Some(DataSource::Memory(memory)) => {
+                    let embedded = tr("hover.font.embedded", " (embedded)");
+                    format!("`{}`{embedded}", memory.name)
+                }
+                None => tr("hover.font.unknown-source", "unknown source").to_owned(),
+            };
+            variants.push(format!(
+                "- {:?}, weight {}, stretch {}: {source}",
+                variant.style,
+                variant.weight.to_number(),
+                variant.stretch.to_ratio(),
+            ));
+        }
+        results.push(variants.join("\n"));
+    }
+
+    let fallback = select_fallback(book, &family, lang_sample(&lang));
+    let fallback = fallback.and_then(|idx| book.info(idx));
+    results.push(match fallback {
+        Some(info) => tr_args(
+            "hover.font.fallback",
+            "The glyphs of `{lang}` missing in the family fall back to `{font}`.",
+            &[("lang", &lang), ("font", &info.family)],
+        ),
+        None if fonts.is_empty() => tr_args(
+            "hover.font.no-cover",
+            "No font covers the glyphs of `{lang}`.",
+            &[("lang", &lang)],
+        ),
+        None => tr_args(
+            "hover.font.covered",
+            "The family covers the glyphs of `{lang}`.",
+            &[("lang", &lang)],
+        ),
+    });
+
+    Some(LspHoverContents::Scalar(MarkedString::String(
+        results.join("\n\n"),
+    )))
+}
+
 fn def_tooltip(
     ctx: &mut AnalysisContext,
     source: &Source,
@@ -134,7 +347,8 @@ fn def_tooltip(
         | LexicalKind::Var(LexicalVarKind::LabelRef)
         | LexicalKind::Var(LexicalVarKind::ValRef)
         | LexicalKind::Block
-        | LexicalKind::Heading(..) => None,
+        | LexicalKind::Heading(..)
+        | LexicalKind::Figure => None,
         LexicalKind::Var(LexicalVarKind::Function) => {
             let sig = if let Some(Value::Func(func)) = &lnk.value {
                 Some(analyze_dyn_signature(ctx, func.clone()))
@@ -173,9 +387,13 @@ fn def_tooltip(
                 }
             }
 
+            let value = match def_type(ctx, &lnk) {
+                Some(ty) => format!("let {name}: {ty};", name = lnk.name),
+                None => format!("let {name};", name = lnk.name),
+            };
             results.push(MarkedString::LanguageString(LanguageString {
                 language: "typc".to_owned(),
-                value: format!("let {name};", name = lnk.name),
+                value,
             }));
 
             if let Some(doc) = DocTooltip::get(ctx, &lnk) {
@@ -187,8 +405,67 @@ fn def_tooltip(
     }
 }
 
+/// Describes the type of a definition inferred by type checking.
+pub(crate) fn def_type(ctx: &mut AnalysisContext, lnk: &DefinitionLink) -> Option<String> {
+    let (fid, def_range) = lnk.def_at.clone()?;
+    let name_range = lnk.name_range.clone().unwrap_or(def_range);
+
+    let src = ctx.source_by_id(fid).ok()?;
+    let def = LinkedNode::new(src.root()).leaf_at(name_range.start + 1)?;
+    let ty = ctx.type_of_span(def.span())?;
+    let ty_chk = ctx.type_check(src.clone())?;
+    ty_chk.simplify(ty, true).describe()
+}
+
+/// A request to describe the type of the expression at a position inferred by
+/// type checking, which is also shown in the hover.
+#[derive(Debug, Clone)]
+pub struct ExprTypeRequest {
+    /// The path of the document containing the expression.
+    pub path: PathBuf,
+    /// The position of the expression.
+    pub position: LspPosition,
+}
+
+impl SemanticRequest for ExprTypeRequest {
+    type Response = String;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let offset = ctx.to_typst_pos(self.position, &source)?;
+        let leaf = LinkedNode::new(source.root()).leaf_at(offset + 1)?;
+        expr_type(ctx, &leaf)
+    }
+}
+
+/// Describes the type of the hovered expression inferred by type checking.
+fn expr_type_tooltip(ctx: &mut AnalysisContext, leaf: &LinkedNode) -> Option<String> {
+    let ty = expr_type(ctx, leaf)?;
+    Some(format!("```typc\n// Type\n{ty}\n```"))
+}
+
+/// Describes the type of an expression inferred by type checking.
+///
+/// The type of a function call is described at its closing parenthesis.
+fn expr_type(ctx: &mut AnalysisContext, leaf: &LinkedNode) -> Option<String> {
+    let node = match leaf.kind() {
+        SyntaxKind::RightParen => {
+            let args = leaf.parent().filter(|p| p.kind() == SyntaxKind::Args)?;
+            args.parent()
+                .filter(|p| p.kind() == SyntaxKind::FuncCall)?
+                .clone()
+        }
+        _ => leaf.clone(),
+    };
+
+    let ty = ctx.type_of_span(node.span())?;
+    let source = ctx.source_by_id(node.span().id()?).ok()?;
+    let ty_chk = ctx.type_check(source)?;
+    ty_chk.simplify(ty, true).describe()
+}
+
 // todo: hover with `with_stack`
-struct ParamTooltip(Option<Signature>);
+pub(crate) struct ParamTooltip(pub Option<Signature>);
 
 impl fmt::Display for ParamTooltip {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -249,14 +526,16 @@ impl fmt::Display for ParamTooltip {
     }
 }
 
-struct DocTooltip;
+pub(crate) struct DocTooltip;
 
 impl DocTooltip {
     fn get(ctx: &mut AnalysisContext, lnk: &DefinitionLink) -> Option<String> {
         self::DocTooltip::get_inner(ctx, lnk).map(|s| "\n\n".to_owned() + &s)
     }
 
-    fn get_inner(ctx: &mut AnalysisContext, lnk: &DefinitionLink) -> Option<String> {
+    /// Gets the docs of a builtin function or the doc comment before a
+    /// definition.
+    pub(crate) fn get_inner(ctx: &mut AnalysisContext, lnk: &DefinitionLink) -> Option<String> {
         if matches!(lnk.value, Some(Value::Func(..))) {
             if let Some(builtin) = Self::builtin_func_tooltip(lnk) {
                 return Some(builtin.to_owned());
@@ -314,4 +593,38 @@ mod tests {
             assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
         });
     }
+    #[test]
+    fn test_font_family() {
+        snapshot_testing("hover_font", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+
+            let request = HoverRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(world, None);
+            let contents = match result.map(|hover| hover.contents) {
+                Some(LspHoverContents::Scalar(MarkedString::String(contents))) => contents,
+                _ => String::new(),
+            };
+            // The fallback of the family depends on the fonts installed, so only
+            // the availability is snapshotted.
+            let availability = contents.split("\n\n").next().unwrap_or_default();
+            assert_snapshot!(availability);
+        });
+    }
+
+    #[test]
+    fn test_preview() {
+        snapshot_testing("hover_preview", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+            let offset = world.to_typst_pos(find_test_position(&source), &source);
+            let cursor = offset.unwrap() + 1;
+            let leaf = LinkedNode::new(source.root()).leaf_at(cursor).unwrap();
+
+            let code = preview_code(world, &source, &leaf, cursor);
+            assert_snapshot!(code.unwrap_or_default());
+        });
+    }
 }
@@ -0,0 +1,225 @@
+//! Localization of the messages produced by tinymist itself, i.e. the
+//! diagnostics, the hover texts, and the details of the completions. The
+//! titles of the code actions and code lenses, the messages shown by the
+//! commands, and the messages produced by typst are not localized here.
+//!
+//! A message is looked up by its key, falling back to its English text given
+//! at the call site, so that a missing translation never hides a message. The
+//! translation tables are compiled into the binary.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// A locale of the messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Locale {
+    /// English, the language of the messages at the call sites.
+    #[default]
+    En = 0,
+    /// Simplified Chinese.
+    ZhCn = 1,
+}
+
+impl Locale {
+    /// Parses a BCP 47 tag of a locale, e.g. `zh-CN` or `zh_cn`. The tags of
+    /// unsupported locales fall back to English.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.trim().replace('_', "-").to_lowercase();
+        match tag.as_str() {
+            "zh" | "zh-cn" | "zh-hans" | "zh-hans-cn" | "zh-sg" => Self::ZhCn,
+            _ => Self::En,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => &[],
+            Self::ZhCn => ZH_CN,
+        }
+    }
+}
+
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+/// Sets the locale of the messages produced afterwards.
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// Gets the locale of the messages.
+pub fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::ZhCn,
+        _ => Locale::En,
+    }
+}
+
+/// Translates a message by its key to the current locale, falling back to the
+/// English text.
+pub fn tr(key: &str, default: &'static str) -> &'static str {
+    translate(locale(), key, default)
+}
+
+/// Translates a message with `{name}` placeholders, substituting the
+/// arguments after translating.
+pub fn tr_args(key: &str, default: &'static str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    substitute(tr(key, default), args)
+}
+
+fn translate(locale: Locale, key: &str, default: &'static str) -> &'static str {
+    let table = locale.table();
+    let found = table.iter().find(|(k, _)| *k == key);
+    found.map_or(default, |(_, text)| text)
+}
+
+/// Substitutes the `{name}` placeholders of a text. Unknown placeholders are
+/// kept as is.
+fn substitute(text: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            let arg = args.iter().find(|(n, _)| *n == name)?;
+            Some((arg.1, end))
+        });
+        match arg {
+            Some((value, end)) => {
+                out.push_str(&value.to_string());
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The Simplified Chinese translations.
+const ZH_CN: &[(&str, &str)] = &[
+    // Lints
+    ("lint.unused.variable", "未使用的变量：`{name}`"),
+    ("lint.unused.function", "未使用的函数：`{name}`"),
+    ("lint.unused.parameter", "未使用的参数：`{name}`"),
+    ("lint.unused.import", "未使用的导入：`{name}`"),
+    ("lint.unused.fix.variable", "移除未使用的变量 `{name}`"),
+    ("lint.unused.fix.function", "移除未使用的函数 `{name}`"),
+    ("lint.unused.fix.parameter", "移除未使用的参数 `{name}`"),
+    ("lint.unused.fix.import", "移除未使用的导入 `{name}`"),
+    ("lint.unused.defined-here", "`{name}` 在此处定义"),
+    (
+        "lint.shadowed.binding",
+        "`{name}` 遮蔽了外层作用域中的绑定，如需重新赋值请使用 `{name} = ..`",
+    ),
+    ("lint.shadowed.declared-here", "`{name}` 在此处声明"),
+    (
+        "lint.shadowed.builtin",
+        "`{name}` 遮蔽了此文件中使用的内置函数 `{name}`",
+    ),
+    ("lint.unreachable.return", "`return` 之后的代码不可达"),
+    ("lint.unreachable.condition", "条件始终为 `{value}`"),
+    (
+        "lint.unreachable.condition-body",
+        "代码不可达：条件始终为 `{value}`",
+    ),
+    ("lint.package.unavailable", "包 `{spec}` 在本地不可用"),
+    ("lint.package.download", "下载包 `{spec}`"),
+    ("lint.package.newer", "`{spec}` 有更新的版本可用：`{newer}`"),
+    ("lint.package.update", "更新到 `{newer}`"),
+    ("lint.labels.undefined", "工作区中不存在标签 `<{name}>`"),
+    (
+        "lint.labels.undefined-candidates",
+        "工作区中不存在标签 `<{name}>`，您是否想使用 {candidates}？",
+    ),
+    ("lint.labels.unused", "标签 `<{name}>` 从未被引用"),
+    ("lint.labels.fix", "改为 `{name}`"),
+    ("lint.labels.heading", "标题"),
+    ("lint.labels.figure", "图表"),
+    (
+        "lint.labels.create",
+        "在{kind} `{title}` 处创建标签 `<{name}>`",
+    ),
+    (
+        "lint.labels.pick",
+        "在附近的标题或图表处创建标签 `<{name}>`……",
+    ),
+    ("lint.file.missing", "找不到文件：`{path}`"),
+    ("lint.file.fix", "改为 `{path}`"),
+    ("lint.file.create", "创建文件 `{path}`"),
+    ("lint.font.unknown", "未知字体族：{family}"),
+    ("lint.bibliography.duplicate", "重复的键 `{key}`"),
+    ("lint.bibliography.first-defined", "首次在此处定义"),
+    ("lint.spelling.unknown", "未知单词：`{word}`"),
+    ("lint.spelling.fix", "改为 `{suggestion}`"),
+    // Hover
+    ("hover.font.unavailable", "字体族 `{family}` 不可用。"),
+    (
+        "hover.font.available",
+        "字体族 `{family}` 可用，共有 {count} 个变体：",
+    ),
+    ("hover.font.embedded", "（内嵌）"),
+    ("hover.font.unknown-source", "未知来源"),
+    (
+        "hover.font.fallback",
+        "该字体族中缺失的 `{lang}` 字形将回退到 `{font}`。",
+    ),
+    ("hover.font.no-cover", "没有字体覆盖 `{lang}` 的字形。"),
+    ("hover.font.covered", "该字体族覆盖了 `{lang}` 的字形。"),
+    // Completion
+    ("completion.raw-lang", "带有 raw show 规则的语言。"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("zh-CN"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("zh_cn"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("zh-Hans"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("fr"), Locale::En);
+    }
+
+    #[test]
+    fn test_translate() {
+        let text = translate(
+            Locale::ZhCn,
+            "lint.spelling.unknown",
+            "unknown word: `{word}`",
+        );
+        assert_eq!(substitute(text, &[("word", &"wrold")]), "未知单词：`wrold`");
+        let text = translate(
+            Locale::En,
+            "lint.spelling.unknown",
+            "unknown word: `{word}`",
+        );
+        assert_eq!(
+            substitute(text, &[("word", &"wrold")]),
+            "unknown word: `wrold`"
+        );
+        assert_eq!(translate(Locale::ZhCn, "missing", "fallback"), "fallback");
+    }
+
+    #[test]
+    fn test_substitute() {
+        let args: &[(&str, &dyn fmt::Display)] = &[("a", &1), ("b", &"x")];
+        assert_eq!(substitute("{a} + {a} = {b}", args), "1 + 1 = x");
+        assert_eq!(substitute("{c} {a", args), "{c} {a");
+    }
+
+    #[test]
+    fn test_translation_keys() {
+        for (key, text) in ZH_CN {
+            assert!(!text.is_empty(), "empty translation of {key}");
+            assert!(ZH_CN.iter().filter(|(k, _)| k == key).count() == 1, "{key}");
+        }
+    }
+}
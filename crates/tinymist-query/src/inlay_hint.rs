@@ -1,6 +1,7 @@
 use std::ops::Range;
 
-use lsp_types::{InlayHintKind, InlayHintLabel};
+use lsp_types::{InlayHintKind, InlayHintLabel, InlayHintTooltip};
+use typst::syntax::SyntaxNode;
 
 use crate::{
     analysis::{analyze_call, ParamKind},
@@ -26,6 +27,18 @@ pub struct InlayHintConfig {
     // The typst sugar grammar
     /// Show inlay hints for content block arguments.
     pub on_content_block_args: bool,
+
+    // type hints group
+    /// Show inlay hints for the inferred types of variables bound by `let`.
+    pub on_let_types: bool,
+    /// The maximum number of characters of a type inlay hint, beyond which
+    /// the type is truncated.
+    pub max_type_length: usize,
+
+    // closing hints group
+    /// Show inlay hints after the blocks spanning at least the given number of
+    /// lines, indicating what they close.
+    pub closing_min_lines: Option<usize>,
 }
 
 impl InlayHintConfig {
@@ -39,6 +52,11 @@ impl InlayHintConfig {
             only_first_variadic_args: true,
 
             on_content_block_args: false,
+
+            on_let_types: false,
+            max_type_length: 32,
+
+            closing_min_lines: None,
         }
     }
 }
@@ -88,13 +106,12 @@ fn inlay_hint(
     range: Range<usize>,
     encoding: PositionEncoding,
 ) -> FileResult<Vec<InlayHint>> {
-    const SMART: InlayHintConfig = InlayHintConfig::smart();
-
     struct InlayHintWorker<'a, 'w> {
         ctx: &'a mut AnalysisContext<'w>,
         source: &'a Source,
         range: Range<usize>,
         encoding: PositionEncoding,
+        config: InlayHintConfig,
         hints: Vec<InlayHint>,
     }
 
@@ -123,6 +140,9 @@ fn inlay_hint(
                 // Type inlay hints
                 SyntaxKind::LetBinding => {
                     trace!("let binding found: {:?}", node);
+                    if self.config.on_let_types {
+                        self.analyze_let_type(node);
+                    }
                 }
                 // Assignment inlay hints
                 SyntaxKind::Eq => {
@@ -176,13 +196,13 @@ fn inlay_hint(
                     };
 
                     let (disable_by_single_pos_arg, disable_by_single_content_pos_arg) =
-                        if SMART.on_pos_args && SMART.off_single_pos_arg {
+                        if self.config.on_pos_args && self.config.off_single_pos_arg {
                             check_single_pos_arg()
                         } else {
                             (false, false)
                         };
 
-                    let disable_by_single_line_content_block = !SMART.on_content_block_args
+                    let disable_by_single_line_content_block = !self.config.on_content_block_args
                         || 'one_line: {
                             for arg in args.items() {
                                 let Some(arg_node) = args_node.find(arg.span()) else {
@@ -229,7 +249,7 @@ fn inlay_hint(
                                 continue
                             }
                             ParamKind::Positional
-                                if !SMART.on_pos_args
+                                if !self.config.on_pos_args
                                     || (info.is_content_block
                                         && (disable_by_single_content_pos_arg
                                             || disable_by_single_line_content_block))
@@ -238,10 +258,10 @@ fn inlay_hint(
                                 continue
                             }
                             ParamKind::Rest
-                                if (!SMART.on_variadic_args
+                                if (!self.config.on_variadic_args
                                     || disable_by_single_pos_arg
                                     || (!is_first_variadic_arg
-                                        && SMART.only_first_variadic_args)) =>
+                                        && self.config.only_first_variadic_args)) =>
                             {
                                 is_first_variadic_arg = false;
                                 continue;
@@ -279,18 +299,112 @@ fn inlay_hint(
                 SyntaxKind::Set => {
                     trace!("set rule found: {:?}", node);
                 }
+                // Closing inlay hints
+                SyntaxKind::ContentBlock | SyntaxKind::CodeBlock => {
+                    if let Some(min_lines) = self.config.closing_min_lines {
+                        self.analyze_closing(node, min_lines);
+                    }
+                }
                 _ => {}
             }
 
             None
         }
+
+        /// Shows the inferred type after a variable bound by `let`, unless the
+        /// type is obvious from the initializer.
+        fn analyze_let_type(&mut self, node: &LinkedNode) -> Option<()> {
+            let binding = node.cast::<ast::LetBinding>()?;
+            let ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(name))) =
+                binding.kind()
+            else {
+                return None;
+            };
+            if is_obvious_init(binding.init()?) {
+                return None;
+            }
+
+            let name_node = node.find(name.span())?;
+            let ty = self.ctx.type_of_span(name.span())?;
+            let ty_chk = self.ctx.type_check(self.source.clone())?;
+            let ty = ty_chk.simplify(ty, true).describe()?;
+
+            let max_len = self.config.max_type_length;
+            let (label, tooltip) = if ty.chars().count() > max_len {
+                let truncated = ty
+                    .chars()
+                    .take(max_len.saturating_sub(1))
+                    .collect::<String>();
+                (
+                    format!(": {truncated}…"),
+                    Some(InlayHintTooltip::String(ty)),
+                )
+            } else {
+                (format!(": {ty}"), None)
+            };
+
+            let pos = name_node.range().end;
+            self.hints.push(InlayHint {
+                position: typst_to_lsp::offset_to_position(pos, self.encoding, self.source),
+                label: InlayHintLabel::String(label),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            });
+
+            Some(())
+        }
+
+        /// Shows what a long block closes after its closing delimiter, i.e. the
+        /// construct owning the block and the show and set rules scoped to it.
+        fn analyze_closing(&mut self, node: &LinkedNode, min_lines: usize) -> Option<()> {
+            let range = node.range();
+            let start = self.source.byte_to_line(range.start)?;
+            let end = self.source.byte_to_line(range.end)?;
+            if end - start + 1 < min_lines {
+                return None;
+            }
+
+            let mut closed = vec![];
+            closed.extend(block_owner(node));
+            for rule in scoped_rules(node) {
+                if !closed.contains(&rule) {
+                    closed.push(rule);
+                }
+            }
+            if closed.is_empty() {
+                return None;
+            }
+
+            self.hints.push(InlayHint {
+                position: typst_to_lsp::offset_to_position(range.end, self.encoding, self.source),
+                label: InlayHintLabel::String(format!("// end of {}", closed.join(", "))),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+
+            Some(())
+        }
     }
 
+    let config = InlayHintConfig {
+        on_let_types: ctx.analysis.enable_type_inlay_hints,
+        closing_min_lines: ctx.analysis.closing_hint_min_lines,
+        ..InlayHintConfig::smart()
+    };
     let mut worker = InlayHintWorker {
         ctx,
         source,
         range,
         encoding,
+        config,
         hints: vec![],
     };
 
@@ -300,6 +414,88 @@ fn inlay_hint(
     Ok(worker.hints)
 }
 
+/// Whether the type of an initializer is obvious enough to omit its type
+/// inlay hint, e.g. a literal or a closure.
+fn is_obvious_init(init: ast::Expr) -> bool {
+    match init {
+        ast::Expr::Parenthesized(p) => is_obvious_init(p.expr()),
+        ast::Expr::None(_)
+        | ast::Expr::Auto(_)
+        | ast::Expr::Bool(_)
+        | ast::Expr::Int(_)
+        | ast::Expr::Float(_)
+        | ast::Expr::Numeric(_)
+        | ast::Expr::Str(_)
+        | ast::Expr::Content(_)
+        | ast::Expr::Closure(_) => true,
+        _ => false,
+    }
+}
+
+/// Describes the construct owning a block, e.g. `show heading` for the body of
+/// a show rule.
+fn block_owner(node: &LinkedNode) -> Option<String> {
+    let parent = node.parent()?;
+    match parent.kind() {
+        SyntaxKind::Args => {
+            let call = parent.parent()?.cast::<ast::FuncCall>()?;
+            Some(hint_snippet(call.callee().to_untyped()))
+        }
+        SyntaxKind::Closure => block_owner(parent),
+        SyntaxKind::ShowRule => Some(describe_show(parent.cast()?)),
+        SyntaxKind::LetBinding => {
+            let binding = parent.cast::<ast::LetBinding>()?;
+            let name = binding.kind().bindings().into_iter().next()?;
+            Some(format!("let {}", name.as_str()))
+        }
+        SyntaxKind::Conditional => Some("if".into()),
+        SyntaxKind::ForLoop => Some("for".into()),
+        SyntaxKind::WhileLoop => Some("while".into()),
+        SyntaxKind::Contextual => Some("context".into()),
+        _ => None,
+    }
+}
+
+/// Describes the show and set rules at the top level of a block, whose scopes
+/// end with the block.
+fn scoped_rules(node: &LinkedNode) -> Vec<String> {
+    let Some(body) = node
+        .children()
+        .find(|child| matches!(child.kind(), SyntaxKind::Markup | SyntaxKind::Code))
+    else {
+        return vec![];
+    };
+
+    body.children()
+        .filter_map(|child| match child.cast::<ast::Expr>()? {
+            ast::Expr::Set(set) => Some(format!("set {}", hint_snippet(set.target().to_untyped()))),
+            ast::Expr::Show(show) => Some(describe_show(show)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn describe_show(show: ast::ShowRule) -> String {
+    match show.selector() {
+        Some(selector) => format!("show {}", hint_snippet(selector.to_untyped())),
+        None => "show".into(),
+    }
+}
+
+/// Shortens the code of a node to fit in an inlay hint.
+fn hint_snippet(node: &SyntaxNode) -> String {
+    const MAX_LEN: usize = 24;
+
+    let text = node.clone().into_text();
+    let text = text.lines().next().unwrap_or_default().trim();
+    if text.chars().count() > MAX_LEN {
+        let truncated = text.chars().take(MAX_LEN - 1).collect::<String>();
+        format!("{truncated}…")
+    } else {
+        text.to_owned()
+    }
+}
+
 fn is_one_line(src: &Source, arg_node: &LinkedNode<'_>) -> bool {
     is_one_line_(src, arg_node).unwrap_or(true)
 }
@@ -335,4 +531,39 @@ mod tests {
             assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
         });
     }
+
+    #[test]
+    fn closing() {
+        snapshot_testing("inlay_hints_closing", &|ctx, path| {
+            ctx.analysis.closing_hint_min_lines = Some(3);
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = InlayHintRequest {
+                path: path.clone(),
+                range: typst_to_lsp::range(
+                    0..source.text().len(),
+                    &source,
+                    PositionEncoding::Utf16,
+                ),
+            };
+
+            let result = request.request(ctx);
+            assert_snapshot!(JsonRepr::new_redacted(result, &REDACT_LOC));
+        });
+    }
+
+    #[test]
+    fn obvious_init() {
+        let is_obvious = |code: &str| {
+            let root = typst::syntax::parse_code(code);
+            let init = root.cast::<ast::Code>().unwrap().exprs().next().unwrap();
+            is_obvious_init(init)
+        };
+
+        assert!(is_obvious("(1pt)"));
+        assert!(is_obvious("[content]"));
+        assert!(is_obvious("(x) => x"));
+        assert!(!is_obvious("json(\"x.json\")"));
+        assert!(!is_obvious("(1, 2)"));
+    }
 }
@@ -6,8 +6,9 @@ use std::num::NonZeroUsize;
 
 use typst::model::Document;
 use typst::{
-    layout::{Frame, FrameItem, Point, Position},
+    layout::{Frame, FrameItem, Point, Position, Size},
     syntax::{LinkedNode, Source, Span, SyntaxKind},
+    visualize::Geometry,
 };
 
 /// Find the output location in the document for a cursor position.
@@ -74,3 +75,92 @@ fn find_in_frame(frame: &Frame, span: Span, min_dis: &mut u64, p: &mut Point) ->
 
     None
 }
+
+/// Find the source span and the offset in it for a click position in the
+/// document, i.e. of the glyph, the shape, or the image clicked.
+pub fn jump_from_click(document: &Document, position: &Position) -> Option<(Span, usize)> {
+    let page = document.pages.get(position.page.get() - 1)?;
+    find_click_in_frame(&page.frame, position.point)
+}
+
+/// Find the source span and the offset in it for a click position in a frame.
+fn find_click_in_frame(frame: &Frame, click: Point) -> Option<(Span, usize)> {
+    // The items painted later are on top of the earlier ones.
+    for (pos, item) in frame.items().rev() {
+        let mut pos = *pos;
+        match item {
+            FrameItem::Group(group) => {
+                let Some(inv) = group.transform.invert() else {
+                    continue;
+                };
+                let click = (click - pos).transform(inv);
+                if let Some(res) = find_click_in_frame(&group.frame, click) {
+                    return Some(res);
+                }
+            }
+            FrameItem::Text(text) => {
+                for glyph in &text.glyphs {
+                    let width = glyph.x_advance.at(text.size);
+                    let top_left = Point::new(pos.x, pos.y - text.size);
+                    if is_in_rect(top_left, Size::new(width, text.size), click) {
+                        let (span, offset) = glyph.span;
+                        return Some((span, offset as usize));
+                    }
+                    pos.x += width;
+                }
+            }
+            FrameItem::Shape(shape, span) => {
+                if let Geometry::Rect(size) = shape.geometry {
+                    if is_in_rect(pos, size, click) {
+                        return Some((*span, 0));
+                    }
+                }
+            }
+            FrameItem::Image(_, size, span) if is_in_rect(pos, *size, click) => {
+                return Some((*span, 0));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whether a point is in a rectangle.
+fn is_in_rect(pos: Point, size: Size, click: Point) -> bool {
+    pos.x <= click.x && pos.x + size.x >= click.x && pos.y <= click.y && pos.y + size.y >= click.y
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::layout::Abs;
+
+    use super::*;
+    use crate::analysis::compile_main;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("jump", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+            let document = compile_main(ctx.world(), source.clone()).unwrap();
+
+            // Clicks the glyph at the cursor, i.e. a bit right of and above the
+            // left end of its baseline.
+            let position = find_test_position(&source);
+            let cursor = ctx.to_typst_pos(position, &source).unwrap() + 1;
+            let mut click = jump_from_cursor(&document, &source, cursor).unwrap();
+            click.point += Point::new(Abs::pt(1.), Abs::pt(-1.));
+
+            let result = jump_from_click(&document, &click).map(|(span, offset)| {
+                let range = source.range(span).unwrap();
+                serde_json::json!({
+                    "page": click.page.get(),
+                    "text": &source.text()[range],
+                    "offset": offset,
+                })
+            });
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
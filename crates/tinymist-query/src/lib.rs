@@ -9,6 +9,8 @@
 
 mod adt;
 pub mod analysis;
+pub mod i18n;
+pub mod profile;
 pub mod syntax;
 mod upstream;
 
@@ -20,34 +22,58 @@ pub use analysis::AnalysisContext;
 use typst::{model::Document as TypstDocument, syntax::Source};
 
 pub use diagnostics::*;
+pub(crate) mod code_action;
+pub use code_action::*;
 pub(crate) mod code_context;
 pub use code_context::*;
 pub(crate) mod code_lens;
 pub use code_lens::*;
 pub(crate) mod completion;
 pub use completion::*;
+pub(crate) mod content_reuse;
+pub use content_reuse::*;
 pub(crate) mod color_presentation;
 pub use color_presentation::*;
+pub(crate) mod diagnostic_fix;
+pub use diagnostic_fix::*;
 pub(crate) mod document_color;
 pub use document_color::*;
+pub(crate) mod document_highlight;
+pub use document_highlight::*;
 pub(crate) mod document_symbol;
 pub use document_symbol::*;
+pub(crate) mod document_link;
+pub use document_link::*;
 pub(crate) mod document_metrics;
 pub use document_metrics::*;
+pub(crate) mod document_outline;
+pub use document_outline::*;
 pub(crate) mod folding_range;
 pub use folding_range::*;
 pub(crate) mod goto_declaration;
 pub use goto_declaration::*;
 pub(crate) mod goto_definition;
 pub use goto_definition::*;
+pub(crate) mod goto_type_definition;
+pub use goto_type_definition::*;
 pub(crate) mod hover;
 pub use hover::*;
 pub(crate) mod inlay_hint;
 pub use inlay_hint::*;
 pub(crate) mod jump;
 pub use jump::*;
+pub(crate) mod lint;
+pub use lint::*;
+pub(crate) mod linked_editing_range;
+pub use linked_editing_range::*;
+pub(crate) mod on_type_formatting;
+pub use on_type_formatting::*;
+pub(crate) mod reflow_paragraph;
+pub use reflow_paragraph::*;
 pub(crate) mod rename;
 pub use rename::*;
+pub(crate) mod run_tests;
+pub use run_tests::*;
 pub(crate) mod selection_range;
 pub use selection_range::*;
 pub(crate) mod semantic_tokens;
@@ -56,12 +82,18 @@ pub(crate) mod semantic_tokens_full;
 pub use semantic_tokens_full::*;
 pub(crate) mod semantic_tokens_delta;
 pub use semantic_tokens_delta::*;
+pub(crate) mod semantic_tokens_range;
+pub use semantic_tokens_range::*;
 pub(crate) mod signature_help;
 pub use signature_help::*;
 pub(crate) mod symbol;
 pub use symbol::*;
+pub(crate) mod syntax_tree;
+pub use syntax_tree::*;
 pub(crate) mod prepare_rename;
 pub use prepare_rename::*;
+pub(crate) mod will_rename_files;
+pub use will_rename_files::*;
 pub(crate) mod references;
 pub use references::*;
 
@@ -122,27 +154,115 @@ pub trait StatefulRequest {
 
 #[allow(missing_docs)]
 mod polymorphic {
-    use lsp_types::TextEdit;
+    use lsp_types::{CodeActionOrCommand, TextEdit};
     use serde::{Deserialize, Serialize};
     use typst::foundations::Dict;
 
     use super::prelude::*;
     use super::*;
 
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum PageSelection {
         #[serde(rename = "first")]
         First,
         #[serde(rename = "merged")]
         Merged,
+        /// The pages in the ranges, each exported to its own file.
+        #[serde(rename = "ranges")]
+        Ranges(PageRanges),
+    }
+
+    /// The ranges of pages, e.g. `1-3,5,8-`, in which the pages are numbered
+    /// from 1.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(try_from = "String", into = "String")]
+    pub struct PageRanges(Vec<(usize, Option<usize>)>);
+
+    impl PageRanges {
+        /// Checks whether the ranges contain a page, numbered from 1.
+        pub fn contains(&self, page: usize) -> bool {
+            self.0
+                .iter()
+                .any(|&(start, end)| start <= page && end.map_or(true, |end| page <= end))
+        }
+    }
+
+    impl std::str::FromStr for PageRanges {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let parse_page = |page: &str| match page.trim().parse::<usize>() {
+                Ok(page) if page > 0 => Ok(page),
+                _ => Err(format!("invalid page number: {page:?}")),
+            };
+
+            let mut ranges = vec![];
+            for range in s.split(',') {
+                let range = match range.split_once('-') {
+                    Some((start, end)) => {
+                        let start = if start.trim().is_empty() {
+                            1
+                        } else {
+                            parse_page(start)?
+                        };
+                        let end = if end.trim().is_empty() {
+                            None
+                        } else {
+                            Some(parse_page(end)?)
+                        };
+                        if end.is_some_and(|end| end < start) {
+                            return Err(format!("invalid page range: {range:?}"));
+                        }
+                        (start, end)
+                    }
+                    None => {
+                        let page = parse_page(range)?;
+                        (page, Some(page))
+                    }
+                };
+                ranges.push(range);
+            }
+            Ok(Self(ranges))
+        }
+    }
+
+    impl TryFrom<String> for PageRanges {
+        type Error = String;
+
+        fn try_from(s: String) -> Result<Self, Self::Error> {
+            s.parse()
+        }
+    }
+
+    impl From<PageRanges> for String {
+        fn from(ranges: PageRanges) -> Self {
+            let ranges = ranges.0.iter().map(|&(start, end)| match end {
+                Some(end) if end == start => start.to_string(),
+                Some(end) => format!("{start}-{end}"),
+                None => format!("{start}-"),
+            });
+            ranges.collect::<Vec<_>>().join(",")
+        }
     }
 
     #[derive(Debug, Clone)]
     pub enum ExportKind {
         Pdf,
         WordCount,
-        Svg { page: PageSelection },
-        Png { page: PageSelection },
+        Svg {
+            page: PageSelection,
+            /// The template of the output path, in which `{page}` is replaced
+            /// by the page number.
+            output: Option<String>,
+        },
+        Png {
+            page: PageSelection,
+            /// The pixels per inch of the images.
+            ppi: Option<f32>,
+            /// The template of the output path, in which `{page}` is replaced
+            /// by the page number.
+            output: Option<String>,
+        },
     }
 
     impl ExportKind {
@@ -201,25 +321,36 @@ mod polymorphic {
         Hover(HoverRequest),
         GotoDefinition(GotoDefinitionRequest),
         GotoDeclaration(GotoDeclarationRequest),
+        GotoTypeDefinition(GotoTypeDefinitionRequest),
         References(ReferencesRequest),
+        DocumentHighlight(DocumentHighlightRequest),
         InlayHint(InlayHintRequest),
         DocumentColor(DocumentColorRequest),
         ColorPresentation(ColorPresentationRequest),
         CodeLens(CodeLensRequest),
+        CodeAction(CodeActionRequest),
         Completion(CompletionRequest),
         SignatureHelp(SignatureHelpRequest),
         Rename(RenameRequest),
         PrepareRename(PrepareRenameRequest),
+        WillRenameFiles(WillRenameFilesRequest),
         DocumentSymbol(DocumentSymbolRequest),
+        DocumentLink(DocumentLinkRequest),
         Symbol(SymbolRequest),
         SemanticTokensFull(SemanticTokensFullRequest),
         SemanticTokensDelta(SemanticTokensDeltaRequest),
+        SemanticTokensRange(SemanticTokensRangeRequest),
         Formatting(FormattingRequest),
         FoldingRange(FoldingRangeRequest),
         SelectionRange(SelectionRangeRequest),
+        LinkedEditingRange(LinkedEditingRangeRequest),
+        OnTypeFormatting(OnTypeFormattingRequest),
         InteractCodeContext(InteractCodeContextRequest),
 
         DocumentMetrics(DocumentMetricsRequest),
+        DocumentOutline(DocumentOutlineRequest),
+        ContentReuse(ContentReuseRequest),
+        ReflowParagraph(ReflowParagraphRequest),
         ServerInfo(ServerInfoRequest),
     }
 
@@ -232,25 +363,36 @@ mod polymorphic {
                 CompilerQueryRequest::Hover(..) => PinnedFirst,
                 CompilerQueryRequest::GotoDefinition(..) => PinnedFirst,
                 CompilerQueryRequest::GotoDeclaration(..) => PinnedFirst,
+                CompilerQueryRequest::GotoTypeDefinition(..) => PinnedFirst,
                 CompilerQueryRequest::References(..) => PinnedFirst,
+                CompilerQueryRequest::DocumentHighlight(..) => PinnedFirst,
                 CompilerQueryRequest::InlayHint(..) => Unique,
                 CompilerQueryRequest::DocumentColor(..) => PinnedFirst,
                 CompilerQueryRequest::ColorPresentation(..) => ContextFreeUnique,
-                CompilerQueryRequest::CodeLens(..) => Unique,
+                CompilerQueryRequest::CodeLens(..) => ContextFreeUnique,
+                CompilerQueryRequest::CodeAction(..) => PinnedFirst,
                 CompilerQueryRequest::Completion(..) => Mergeable,
                 CompilerQueryRequest::SignatureHelp(..) => PinnedFirst,
                 CompilerQueryRequest::Rename(..) => Mergeable,
                 CompilerQueryRequest::PrepareRename(..) => Mergeable,
+                CompilerQueryRequest::WillRenameFiles(..) => Mergeable,
                 CompilerQueryRequest::DocumentSymbol(..) => ContextFreeUnique,
+                CompilerQueryRequest::DocumentLink(..) => PinnedFirst,
                 CompilerQueryRequest::Symbol(..) => Mergeable,
                 CompilerQueryRequest::SemanticTokensFull(..) => ContextFreeUnique,
                 CompilerQueryRequest::SemanticTokensDelta(..) => ContextFreeUnique,
+                CompilerQueryRequest::SemanticTokensRange(..) => ContextFreeUnique,
                 CompilerQueryRequest::Formatting(..) => ContextFreeUnique,
                 CompilerQueryRequest::FoldingRange(..) => ContextFreeUnique,
                 CompilerQueryRequest::SelectionRange(..) => ContextFreeUnique,
+                CompilerQueryRequest::LinkedEditingRange(..) => ContextFreeUnique,
+                CompilerQueryRequest::OnTypeFormatting(..) => ContextFreeUnique,
                 CompilerQueryRequest::InteractCodeContext(..) => PinnedFirst,
 
                 CompilerQueryRequest::DocumentMetrics(..) => PinnedFirst,
+                CompilerQueryRequest::DocumentOutline(..) => PinnedFirst,
+                CompilerQueryRequest::ContentReuse(..) => Mergeable,
+                CompilerQueryRequest::ReflowParagraph(..) => ContextFreeUnique,
                 CompilerQueryRequest::ServerInfo(..) => Mergeable,
             }
         }
@@ -262,25 +404,36 @@ mod polymorphic {
                 CompilerQueryRequest::Hover(req) => &req.path,
                 CompilerQueryRequest::GotoDefinition(req) => &req.path,
                 CompilerQueryRequest::GotoDeclaration(req) => &req.path,
+                CompilerQueryRequest::GotoTypeDefinition(req) => &req.path,
                 CompilerQueryRequest::References(req) => &req.path,
+                CompilerQueryRequest::DocumentHighlight(req) => &req.path,
                 CompilerQueryRequest::InlayHint(req) => &req.path,
                 CompilerQueryRequest::DocumentColor(req) => &req.path,
                 CompilerQueryRequest::ColorPresentation(req) => &req.path,
                 CompilerQueryRequest::CodeLens(req) => &req.path,
+                CompilerQueryRequest::CodeAction(req) => &req.path,
                 CompilerQueryRequest::Completion(req) => &req.path,
                 CompilerQueryRequest::SignatureHelp(req) => &req.path,
                 CompilerQueryRequest::Rename(req) => &req.path,
                 CompilerQueryRequest::PrepareRename(req) => &req.path,
+                CompilerQueryRequest::WillRenameFiles(..) => return None,
                 CompilerQueryRequest::DocumentSymbol(req) => &req.path,
+                CompilerQueryRequest::DocumentLink(req) => &req.path,
                 CompilerQueryRequest::Symbol(..) => return None,
                 CompilerQueryRequest::SemanticTokensFull(req) => &req.path,
                 CompilerQueryRequest::SemanticTokensDelta(req) => &req.path,
+                CompilerQueryRequest::SemanticTokensRange(req) => &req.path,
                 CompilerQueryRequest::Formatting(req) => &req.path,
                 CompilerQueryRequest::FoldingRange(req) => &req.path,
                 CompilerQueryRequest::SelectionRange(req) => &req.path,
+                CompilerQueryRequest::LinkedEditingRange(req) => &req.path,
+                CompilerQueryRequest::OnTypeFormatting(req) => &req.path,
                 CompilerQueryRequest::InteractCodeContext(req) => &req.path,
 
                 CompilerQueryRequest::DocumentMetrics(req) => &req.path,
+                CompilerQueryRequest::DocumentOutline(req) => &req.path,
+                CompilerQueryRequest::ContentReuse(req) => &req.path,
+                CompilerQueryRequest::ReflowParagraph(req) => &req.path,
                 CompilerQueryRequest::ServerInfo(..) => return None,
             })
         }
@@ -293,25 +446,36 @@ mod polymorphic {
         Hover(Option<Hover>),
         GotoDefinition(Option<GotoDefinitionResponse>),
         GotoDeclaration(Option<GotoDeclarationResponse>),
+        GotoTypeDefinition(Option<GotoDefinitionResponse>),
         References(Option<Vec<LspLocation>>),
+        DocumentHighlight(Option<Vec<DocumentHighlight>>),
         InlayHint(Option<Vec<InlayHint>>),
         DocumentColor(Option<Vec<ColorInformation>>),
         ColorPresentation(Option<Vec<ColorPresentation>>),
         CodeLens(Option<Vec<CodeLens>>),
+        CodeAction(Option<Vec<CodeActionOrCommand>>),
         Completion(Option<CompletionResponse>),
         SignatureHelp(Option<SignatureHelp>),
         PrepareRename(Option<PrepareRenameResponse>),
         Rename(Option<WorkspaceEdit>),
+        WillRenameFiles(Option<WorkspaceEdit>),
         DocumentSymbol(Option<DocumentSymbolResponse>),
+        DocumentLink(Option<Vec<DocumentLink>>),
         Symbol(Option<Vec<SymbolInformation>>),
         SemanticTokensFull(Option<SemanticTokensResult>),
         SemanticTokensDelta(Option<SemanticTokensFullDeltaResult>),
+        SemanticTokensRange(Option<SemanticTokensRangeResult>),
         Formatting(Option<Vec<TextEdit>>),
         FoldingRange(Option<Vec<FoldingRange>>),
         SelectionRange(Option<Vec<SelectionRange>>),
+        LinkedEditingRange(Option<LinkedEditingRanges>),
+        OnTypeFormatting(Option<Vec<TextEdit>>),
         InteractCodeContext(Option<Vec<InteractCodeContextResponse>>),
 
         DocumentMetrics(Option<DocumentMetricsResponse>),
+        DocumentOutline(Option<DocumentOutline>),
+        ContentReuse(Option<Vec<ContentReuse>>),
+        ReflowParagraph(Option<Vec<TextEdit>>),
         ServerInfo(Option<HashMap<String, ServerInfoResponse>>),
     }
 }
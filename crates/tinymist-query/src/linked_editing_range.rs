@@ -0,0 +1,133 @@
+use std::ops::Range;
+
+use crate::{prelude::*, SyntaxRequest};
+
+/// The [`textDocument/linkedEditingRange`] request is sent from the client to
+/// the server to return for a given position in a document the range of the
+/// symbol at the position and all ranges that have the same content.
+///
+/// [`textDocument/linkedEditingRange`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_linkedEditingRange
+///
+/// The linked ranges are:
+/// + the names of a label and the references to it in the same file, e.g.
+///   `intro` in `<intro>` and `@intro`.
+/// + the opening and closing delimiters of a raw block, e.g. the fences of
+///   ```` ```typ ... ``` ````.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.16.0.
+#[derive(Debug, Clone)]
+pub struct LinkedEditingRangeRequest {
+    /// The path of the document to get linked editing ranges for.
+    pub path: PathBuf,
+    /// The position to get linked editing ranges for.
+    pub position: LspPosition,
+}
+
+impl SyntaxRequest for LinkedEditingRangeRequest {
+    type Response = LinkedEditingRanges;
+
+    fn request(
+        self,
+        source: &Source,
+        position_encoding: PositionEncoding,
+    ) -> Option<Self::Response> {
+        let offset = lsp_to_typst::position(self.position, position_encoding, source)?;
+        let root = LinkedNode::new(source.root());
+        let leaf = root.leaf_at(offset + 1)?;
+
+        let ranges = match leaf.kind() {
+            SyntaxKind::Label | SyntaxKind::RefMarker => {
+                let name = label_name(&leaf)?;
+                let mut ranges = vec![];
+                collect_label_names(&root, name, &mut ranges);
+                ranges
+            }
+            SyntaxKind::RawDelim => {
+                let raw = leaf.parent()?;
+                let delims = raw
+                    .children()
+                    .filter(|child| child.kind() == SyntaxKind::RawDelim);
+                delims.map(|delim| delim.range()).collect()
+            }
+            _ => return None,
+        };
+        if ranges.len() < 2 {
+            return None;
+        }
+
+        Some(LinkedEditingRanges {
+            ranges: ranges
+                .into_iter()
+                .map(|range| typst_to_lsp::range(range, source, position_encoding))
+                .collect(),
+            word_pattern: None,
+        })
+    }
+}
+
+/// Gets the range of the name of a label or a reference marker, i.e. the
+/// text without the `<`, `>`, or `@` markers.
+fn label_name_range(node: &LinkedNode) -> Option<Range<usize>> {
+    let range = node.range();
+    match node.kind() {
+        SyntaxKind::Label => Some(range.start + 1..range.end - 1),
+        SyntaxKind::RefMarker => Some(range.start + 1..range.end),
+        _ => None,
+    }
+}
+
+fn label_name<'a>(node: &'a LinkedNode) -> Option<&'a str> {
+    let range = label_name_range(node)?;
+    let offset = node.offset();
+    node.text().get(range.start - offset..range.end - offset)
+}
+
+fn collect_label_names(node: &LinkedNode, name: &str, ranges: &mut Vec<Range<usize>>) {
+    if label_name(node) == Some(name) {
+        ranges.extend(label_name_range(node));
+        return;
+    }
+
+    for child in node.children() {
+        collect_label_names(&child, name, ranges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("linked_editing_range", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+
+            let request = LinkedEditingRangeRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request.request(&source, PositionEncoding::Utf16);
+            let result = result.map(|ranges| {
+                ranges
+                    .ranges
+                    .into_iter()
+                    .map(|range| {
+                        format!(
+                            "{}:{}:{}:{}",
+                            range.start.line,
+                            range.start.character,
+                            range.end.line,
+                            range.end.character
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
@@ -0,0 +1,631 @@
+//! Lints of the source files, e.g. the unused definitions and imports, the
+//! shadowed bindings, the unreachable code, the paths to missing files and
+//! packages, the unknown font families, and the misspelled words. The
+//! bibliography files loaded by the source files are checked for syntax errors
+//! and duplicate keys as well.
+//!
+//! The lints are reported by the rules registered in the lint passes. The
+//! severity of each rule can be configured, and the lints can be suppressed
+//! by the `// tinymist-ignore(rule)` comments.
+
+mod font;
+mod labels;
+mod missing_file;
+mod package;
+mod shadowed;
+mod spelling;
+mod unreachable;
+mod unused;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+use lsp_types::{Command, DiagnosticTag, NumberOrString};
+use reflexo::{hash::hash128, ImmutPath};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::{bibliography_source, source_bibliography_files, BibFile, BibKind},
+    i18n::{tr, tr_args},
+    prelude::*,
+    DiagnosticsMap,
+};
+
+/// A lint rule, identified by its code in the configuration and the
+/// suppression comments.
+#[derive(Debug, Clone, Copy)]
+pub struct LintRule {
+    /// The code of the rule, e.g. `unused-variable`.
+    pub code: &'static str,
+    /// The severity of the lints if it is not configured.
+    pub severity: LspSeverity,
+    /// Whether the linted code is unnecessary, so that editors gray it out.
+    pub unnecessary: bool,
+    /// Whether the rule is enabled if it is not configured.
+    pub enabled: bool,
+}
+
+impl LintRule {
+    const fn new(code: &'static str, severity: LspSeverity, unnecessary: bool) -> Self {
+        Self {
+            code,
+            severity,
+            unnecessary,
+            enabled: true,
+        }
+    }
+
+    /// Disables the rule unless it is configured, e.g. if it is costly or
+    /// noisy.
+    const fn off_by_default(self) -> Self {
+        Self {
+            enabled: false,
+            ..self
+        }
+    }
+
+    /// Creates a lint of the rule.
+    fn lint(&self, range: Range<usize>, message: String) -> Lint {
+        Lint {
+            code: self.code,
+            range,
+            message,
+            severity: self.severity,
+            tags: if self.unnecessary {
+                vec![DiagnosticTag::UNNECESSARY]
+            } else {
+                vec![]
+            },
+            related: vec![],
+            fix: None,
+        }
+    }
+}
+
+const UNUSED_VARIABLE: LintRule =
+    LintRule::new("unused-variable", LspSeverity::HINT, true).off_by_default();
+const UNUSED_FUNCTION: LintRule =
+    LintRule::new("unused-function", LspSeverity::HINT, true).off_by_default();
+const UNUSED_PARAMETER: LintRule =
+    LintRule::new("unused-parameter", LspSeverity::HINT, true).off_by_default();
+const UNUSED_IMPORT: LintRule =
+    LintRule::new("unused-import", LspSeverity::HINT, true).off_by_default();
+const SHADOWED_BINDING: LintRule = LintRule::new("shadowed-binding", LspSeverity::WARNING, false);
+const SHADOWED_BUILTIN: LintRule = LintRule::new("shadowed-builtin", LspSeverity::WARNING, false);
+const UNREACHABLE_CODE: LintRule = LintRule::new("unreachable-code", LspSeverity::WARNING, true);
+const CONSTANT_CONDITION: LintRule =
+    LintRule::new("constant-condition", LspSeverity::WARNING, false);
+const MISSING_FILE: LintRule = LintRule::new("missing-file", LspSeverity::HINT, false);
+const MISSING_PACKAGE: LintRule = LintRule::new("missing-package", LspSeverity::WARNING, false);
+const OUTDATED_PACKAGE: LintRule =
+    LintRule::new("outdated-package", LspSeverity::HINT, false).off_by_default();
+// A hint, as the fonts of the text laid out are reported by the compiler as
+// warnings already.
+const UNKNOWN_FONT: LintRule = LintRule::new("unknown-font", LspSeverity::HINT, false);
+// A warning, as the references in the compiled document are reported by the
+// compiler as errors already.
+const UNDEFINED_REFERENCE: LintRule =
+    LintRule::new("undefined-reference", LspSeverity::WARNING, false);
+const UNUSED_LABEL: LintRule = LintRule::new("unused-label", LspSeverity::HINT, false);
+const SPELLING: LintRule = LintRule::new("spelling", LspSeverity::HINT, false);
+const BIBLIOGRAPHY_SYNTAX: LintRule =
+    LintRule::new("bibliography-syntax", LspSeverity::WARNING, false);
+const DUPLICATE_BIBLIOGRAPHY_KEY: LintRule =
+    LintRule::new("duplicate-bibliography-key", LspSeverity::WARNING, false);
+
+/// The rules of the bibliography files, which are not checked by the lint
+/// passes of the source files.
+const BIBLIOGRAPHY_RULES: &[LintRule] = &[BIBLIOGRAPHY_SYNTAX, DUPLICATE_BIBLIOGRAPHY_KEY];
+
+/// A pass checking a source file for some of the rules.
+struct LintPass {
+    /// The rules reported by the pass.
+    rules: &'static [LintRule],
+    /// Checks the source file.
+    run: fn(&mut AnalysisContext, &Source, &mut Vec<Lint>),
+    /// Computes the quick fixes of a lint lazily, if they are too costly to
+    /// compute for every lint.
+    suggest: Option<fn(&mut AnalysisContext, &Source, &Lint) -> Vec<LintFix>>,
+}
+
+/// The registry of the lint passes.
+const LINT_PASSES: &[LintPass] = &[
+    LintPass {
+        rules: &[
+            UNUSED_VARIABLE,
+            UNUSED_FUNCTION,
+            UNUSED_PARAMETER,
+            UNUSED_IMPORT,
+        ],
+        run: unused::lint_unused,
+        suggest: None,
+    },
+    LintPass {
+        rules: &[SHADOWED_BINDING, SHADOWED_BUILTIN],
+        run: shadowed::lint_shadowed,
+        suggest: None,
+    },
+    LintPass {
+        rules: &[UNREACHABLE_CODE, CONSTANT_CONDITION],
+        run: unreachable::lint_unreachable,
+        suggest: None,
+    },
+    LintPass {
+        rules: &[MISSING_FILE],
+        run: missing_file::lint_missing_file,
+        suggest: Some(missing_file::suggest_missing_file),
+    },
+    LintPass {
+        rules: &[MISSING_PACKAGE],
+        run: package::lint_missing_package,
+        suggest: None,
+    },
+    // The registry index is downloaded, so that the pass is separated to be off
+    // by default.
+    LintPass {
+        rules: &[OUTDATED_PACKAGE],
+        run: package::lint_outdated_package,
+        suggest: None,
+    },
+    LintPass {
+        rules: &[UNKNOWN_FONT],
+        run: font::lint_unknown_font,
+        suggest: Some(font::suggest_unknown_font),
+    },
+    LintPass {
+        rules: &[UNDEFINED_REFERENCE, UNUSED_LABEL],
+        run: labels::lint_labels,
+        suggest: Some(labels::suggest_labels),
+    },
+    LintPass {
+        rules: &[SPELLING],
+        run: spelling::lint_spelling,
+        suggest: Some(spelling::suggest_spelling),
+    },
+];
+
+/// Gets all the registered lint rules.
+pub fn lint_rules() -> impl Iterator<Item = &'static LintRule> {
+    let rules = LINT_PASSES.iter().flat_map(|pass| pass.rules);
+    rules.chain(BIBLIOGRAPHY_RULES)
+}
+
+/// The level of a lint rule configured by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintLevel {
+    /// The rule is disabled.
+    Off,
+    /// The lints are reported as hints.
+    Hint,
+    /// The lints are reported as information.
+    Info,
+    /// The lints are reported as warnings.
+    Warning,
+    /// The lints are reported as errors.
+    Error,
+}
+
+/// The user configuration of the lints, i.e. the levels of the rules by their
+/// codes, e.g. `{ "unused-variable": "hint" }`. The `*` key sets the level of
+/// the rules not listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LintConfig {
+    /// The levels of the rules by their codes.
+    pub rules: HashMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    /// Gets the configured severity of a rule, or `None` if it is disabled.
+    pub fn severity_of(&self, rule: &LintRule) -> Option<LspSeverity> {
+        let level = self.rules.get(rule.code).or_else(|| self.rules.get("*"));
+        match level {
+            None => rule.enabled.then_some(rule.severity),
+            Some(LintLevel::Off) => None,
+            Some(LintLevel::Hint) => Some(LspSeverity::HINT),
+            Some(LintLevel::Info) => Some(LspSeverity::INFORMATION),
+            Some(LintLevel::Warning) => Some(LspSeverity::WARNING),
+            Some(LintLevel::Error) => Some(LspSeverity::ERROR),
+        }
+    }
+}
+
+/// A lint found in a source file.
+#[derive(Debug, Clone)]
+pub struct Lint {
+    /// The code of the lint rule, e.g. `unused-variable`.
+    pub code: &'static str,
+    /// The range of the linted node in the source.
+    pub range: Range<usize>,
+    /// The message of the lint.
+    pub message: String,
+    /// The severity of the lint.
+    pub severity: LspSeverity,
+    /// The tags of the lint, e.g. [`DiagnosticTag::UNNECESSARY`] to gray out
+    /// the node in editors.
+    pub tags: Vec<DiagnosticTag>,
+    /// The related locations of the lint, e.g. the definition site of the
+    /// linted identifier.
+    pub related: Vec<(TypstFileId, Range<usize>, String)>,
+    /// The quick fix of the lint.
+    pub fix: Option<LintFix>,
+}
+
+/// A quick fix of a lint.
+#[derive(Debug, Clone)]
+pub struct LintFix {
+    /// The title of the quick fix.
+    pub title: String,
+    /// The edits to apply to the source.
+    pub edits: Vec<(Range<usize>, String)>,
+    /// The file to create, e.g. the missing file referenced by a path.
+    pub create: Option<PathBuf>,
+    /// The command to execute after the edits, e.g. downloading a package.
+    pub command: Option<Command>,
+}
+
+impl Lint {
+    /// Converts the lint to a LSP diagnostic.
+    pub fn to_diagnostic(&self, ctx: &AnalysisContext, source: &Source) -> LspDiagnostic {
+        let related = self.related.iter().filter_map(|(id, range, message)| {
+            let source = ctx.world().source(*id).ok()?;
+            let uri = path_to_url(&ctx.path_for_id(*id).ok()?).ok()?;
+            Some(DiagnosticRelatedInformation {
+                location: LspLocation {
+                    uri,
+                    range: ctx.to_lsp_range(range.clone(), &source),
+                },
+                message: message.clone(),
+            })
+        });
+        let related = related.collect::<Vec<_>>();
+
+        LspDiagnostic {
+            range: ctx.to_lsp_range(self.range.clone(), source),
+            severity: Some(self.severity),
+            code: Some(NumberOrString::String(self.code.to_owned())),
+            source: Some("tinymist".to_owned()),
+            message: self.message.clone(),
+            tags: (!self.tags.is_empty()).then(|| self.tags.clone()),
+            related_information: (!related.is_empty()).then_some(related),
+            ..Default::default()
+        }
+    }
+}
+
+/// The lints of the opened files, reused until the files are changed.
+///
+/// The lints of a file depending on the other files, e.g. of the definitions
+/// imported by them, are updated when the file itself is changed.
+#[derive(Default)]
+pub struct LintCache {
+    files: HashMap<PathBuf, (u128, Vec<LspDiagnostic>)>,
+}
+
+impl LintCache {
+    /// Clears the lints, e.g. when the configuration of the lints is changed.
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    /// Gets the lints of a file from the cache if it is unchanged, otherwise
+    /// lints it with `f`.
+    fn lint(
+        &mut self,
+        files: &mut HashMap<PathBuf, (u128, Vec<LspDiagnostic>)>,
+        path: PathBuf,
+        source: &Source,
+        f: impl FnOnce() -> Vec<LspDiagnostic>,
+    ) {
+        let version = hash128(source.text());
+        let lints = match self.files.remove(&path) {
+            Some((cached, lints)) if cached == version => lints,
+            _ => f(),
+        };
+        files.insert(path, (version, lints));
+    }
+}
+
+/// Lints the source files opened in the editor among the dependencies of the
+/// compilation, excluding the files in packages, and the bibliography files
+/// loaded by them. The files unchanged since they were last linted are not
+/// linted again.
+pub fn lint_diagnostics(
+    ctx: &mut AnalysisContext,
+    opened: &HashSet<ImmutPath>,
+    cache: &mut LintCache,
+) -> DiagnosticsMap {
+    let mut paths = vec![];
+    ctx.resources.iter_dependencies(&mut |path, _| {
+        if path.extension().is_some_and(|ext| ext == "typ") && opened.contains(path) {
+            paths.push(path.to_path_buf());
+        }
+    });
+
+    let mut files = HashMap::new();
+    let mut bibliographies = vec![];
+    for path in paths {
+        let Ok(source) = ctx.source_by_path(&path) else {
+            continue;
+        };
+        if source.id().package().is_some() {
+            continue;
+        }
+        for file in source_bibliography_files(ctx, &source) {
+            if !bibliographies.contains(&file) {
+                bibliographies.push(file);
+            }
+        }
+
+        cache.lint(&mut files, path, &source, || {
+            let lints = lint_source(ctx, &source);
+            let lints = lints.iter().map(|lint| lint.to_diagnostic(ctx, &source));
+            lints.collect()
+        });
+    }
+
+    for id in bibliographies {
+        let Some((kind, source)) = bibliography_source(ctx, id) else {
+            continue;
+        };
+        let Ok(path) = ctx.path_for_id(id) else {
+            continue;
+        };
+
+        cache.lint(&mut files, path, &source, || {
+            let lints = lint_bibliography(ctx, kind, &source);
+            let lints = lints.iter().map(|lint| lint.to_diagnostic(ctx, &source));
+            lints.collect()
+        });
+    }
+    // The files closed or no longer compiled are dropped.
+    cache.files = files;
+
+    let mut diagnostics = DiagnosticsMap::new();
+    for (path, (_, lints)) in &cache.files {
+        let Ok(uri) = path_to_url(path) else {
+            continue;
+        };
+        if !lints.is_empty() {
+            diagnostics.insert(uri, lints.clone());
+        }
+    }
+    diagnostics
+}
+
+/// Lints a bibliography file with the configured rules, i.e. reports its
+/// syntax errors and duplicate keys.
+pub fn lint_bibliography(ctx: &AnalysisContext, kind: BibKind, source: &Source) -> Vec<Lint> {
+    let file = BibFile::parse(kind, source.text());
+
+    let mut lints = vec![];
+    for error in &file.errors {
+        let lint = BIBLIOGRAPHY_SYNTAX.lint(error.range.clone(), error.message.clone());
+        lints.push(lint);
+    }
+    for (key, first) in file.duplicate_keys() {
+        let message = tr_args(
+            "lint.bibliography.duplicate",
+            "duplicate key `{key}`",
+            &[("key", &key.name)],
+        );
+        let mut lint = DUPLICATE_BIBLIOGRAPHY_KEY.lint(key.range.clone(), message);
+        let related = tr("lint.bibliography.first-defined", "first defined here").to_owned();
+        lint.related
+            .push((source.id(), first.range.clone(), related));
+        lints.push(lint);
+    }
+
+    let config = &ctx.analysis.lint;
+    lints.retain_mut(|lint| {
+        let rule = BIBLIOGRAPHY_RULES
+            .iter()
+            .find(|rule| rule.code == lint.code);
+        let Some(severity) = rule.and_then(|rule| config.severity_of(rule)) else {
+            return false;
+        };
+        lint.severity = severity;
+        true
+    });
+
+    lints.sort_by_key(|lint| lint.range.start);
+    lints
+}
+
+/// Lints a source file with the configured rules, excluding the lints
+/// suppressed by comments.
+pub fn lint_source(ctx: &mut AnalysisContext, source: &Source) -> Vec<Lint> {
+    let config = ctx.analysis.lint.clone();
+
+    let mut lints = vec![];
+    for pass in LINT_PASSES {
+        let enabled = pass
+            .rules
+            .iter()
+            .any(|rule| config.severity_of(rule).is_some());
+        if enabled {
+            (pass.run)(ctx, source, &mut lints);
+        }
+    }
+
+    let suppressions = collect_suppressions(source);
+    lints.retain_mut(|lint| {
+        let Some(rule) = lint_rules().find(|rule| rule.code == lint.code) else {
+            return false;
+        };
+        let Some(severity) = config.severity_of(rule) else {
+            return false;
+        };
+        lint.severity = severity;
+
+        let line = source.byte_to_line(lint.range.start);
+        match line.and_then(|line| suppressions.get(&line)) {
+            Some(None) => false,
+            Some(Some(codes)) => !codes.iter().any(|code| code == lint.code),
+            None => true,
+        }
+    });
+
+    lints.sort_by_key(|lint| lint.range.start);
+    lints
+}
+
+/// Gets the ranges of the unused imports in a source, regardless of the
+/// configured rules, e.g. to remove them when organizing the imports.
+pub(crate) fn unused_imports(ctx: &mut AnalysisContext, source: &Source) -> Vec<Range<usize>> {
+    let mut lints = vec![];
+    unused::lint_unused(ctx, source, &mut lints);
+    let lints = lints
+        .into_iter()
+        .filter(|lint| lint.code == UNUSED_IMPORT.code);
+    lints.map(|lint| lint.range).collect()
+}
+
+/// Gets the quick fixes of a lint, including the ones computed lazily by its
+/// lint pass.
+pub fn lint_fixes(ctx: &mut AnalysisContext, source: &Source, lint: &Lint) -> Vec<LintFix> {
+    let mut fixes = lint.fix.iter().cloned().collect::<Vec<_>>();
+    let pass = LINT_PASSES
+        .iter()
+        .find(|pass| pass.rules.iter().any(|rule| rule.code == lint.code));
+    if let Some(suggest) = pass.and_then(|pass| pass.suggest) {
+        fixes.extend(suggest(ctx, source, lint));
+    }
+    fixes
+}
+
+/// Collects the rules suppressed on the lines by the
+/// `// tinymist-ignore(rule, ..)` comments. A comment on its own line
+/// suppresses the next line, and a trailing comment suppresses its line. A
+/// comment without rules, i.e. `// tinymist-ignore`, suppresses all the rules.
+fn collect_suppressions(source: &Source) -> HashMap<usize, Option<Vec<String>>> {
+    fn collect(
+        source: &Source,
+        node: &LinkedNode,
+        lines: &mut HashMap<usize, Option<Vec<String>>>,
+    ) {
+        let text = match node.kind() {
+            SyntaxKind::LineComment => node.text().strip_prefix("//"),
+            SyntaxKind::BlockComment => node
+                .text()
+                .strip_prefix("/*")
+                .and_then(|text| text.strip_suffix("*/")),
+            _ => {
+                for child in node.children() {
+                    collect(source, &child, lines);
+                }
+                return;
+            }
+        };
+        let Some(rest) = text.and_then(|text| text.trim().strip_prefix("tinymist-ignore")) else {
+            return;
+        };
+        let codes = match rest.trim() {
+            "" => None,
+            rest => {
+                let Some(codes) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) else {
+                    return;
+                };
+                Some(
+                    codes
+                        .split(',')
+                        .map(|code| code.trim().to_owned())
+                        .collect(),
+                )
+            }
+        };
+
+        let Some(line) = source.byte_to_line(node.offset()) else {
+            return;
+        };
+        let line_start = source.line_to_byte(line).unwrap_or_default();
+        let own_line = source.text()[line_start..node.offset()].trim().is_empty();
+        let line = if own_line { line + 1 } else { line };
+
+        let entry = lines.entry(line).or_insert_with(|| Some(vec![]));
+        match codes {
+            Some(codes) => {
+                if let Some(suppressed) = entry {
+                    suppressed.extend(codes);
+                }
+            }
+            None => *entry = None,
+        }
+    }
+
+    let mut lines = HashMap::new();
+    collect(source, &LinkedNode::new(source.root()), &mut lines);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("lint", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let lints = lint_source(ctx, &source);
+            let result = lints
+                .iter()
+                .map(|lint| {
+                    let range = ctx.to_lsp_range(lint.range.clone(), &source);
+                    let fix = lint.fix.iter().flat_map(|fix| &fix.edits).map(|(edit, _)| {
+                        let edit = ctx.to_lsp_range(edit.clone(), &source);
+                        format!(
+                            " (remove {}:{}:{}:{})",
+                            edit.start.line,
+                            edit.start.character,
+                            edit.end.line,
+                            edit.end.character
+                        )
+                    });
+                    format!(
+                        "{}@{}:{}:{}:{}: {}{}",
+                        lint.code,
+                        range.start.line,
+                        range.start.character,
+                        range.end.line,
+                        range.end.character,
+                        lint.message,
+                        fix.collect::<String>()
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+
+    #[test]
+    fn test_config() {
+        let config: LintConfig = serde_json::from_value(serde_json::json!({
+            "unused-variable": "off",
+            "*": "error",
+        }))
+        .unwrap();
+        assert_eq!(config.severity_of(&UNUSED_VARIABLE), None);
+        assert_eq!(
+            config.severity_of(&SHADOWED_BINDING),
+            Some(LspSeverity::ERROR)
+        );
+
+        let config = LintConfig::default();
+        assert_eq!(config.severity_of(&UNUSED_IMPORT), None);
+        assert_eq!(
+            config.severity_of(&SHADOWED_BINDING),
+            Some(LspSeverity::WARNING)
+        );
+
+        let config: LintConfig = serde_json::from_value(serde_json::json!({
+            "unused-import": "hint",
+        }))
+        .unwrap();
+        assert_eq!(config.severity_of(&UNUSED_IMPORT), Some(LspSeverity::HINT));
+    }
+}
@@ -0,0 +1,38 @@
+//! Lints of the font families that are not available.
+
+use super::*;
+use crate::{analysis::collect_font_families, diagnostic_fix::fix_font_family};
+
+/// Reports the font families passed to the `text` function that are not
+/// available, even if the text is not laid out by the compiler.
+pub(super) fn lint_unknown_font(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    let book = ctx.world().book();
+    for (range, family) in collect_font_families(source) {
+        if book.select_family(&family.to_lowercase()).next().is_some() {
+            continue;
+        }
+
+        // The English message is the compiler warning up to the case of the
+        // family, to be merged with it.
+        let message = tr_args(
+            "lint.font.unknown",
+            "unknown font family: {family}",
+            &[("family", &family)],
+        );
+        lints.push(UNKNOWN_FONT.lint(range, message));
+    }
+}
+
+/// Suggests the closest available font families.
+pub(super) fn suggest_unknown_font(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lint: &Lint,
+) -> Vec<LintFix> {
+    let mut families = collect_font_families(source).into_iter();
+    let Some((_, family)) = families.find(|(range, _)| *range == lint.range) else {
+        return vec![];
+    };
+    let family = family.to_lowercase();
+    fix_font_family(ctx, source, lint.range.clone(), &[&family])
+}
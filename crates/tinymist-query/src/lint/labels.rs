@@ -0,0 +1,232 @@
+//! Lints of the references to undefined labels and the unused labels.
+
+use serde::Serialize;
+
+use super::*;
+use crate::analysis::{collect_labels, edit_distance, LabelIndex, LabelOccurrence};
+use crate::document_outline::{syntax_outline, OutlineItemKind};
+
+/// The maximum number of the suggested labels of an undefined reference.
+const MAX_CANDIDATES: usize = 3;
+/// The maximum number of the headings and figures offered to be labelled by
+/// an undefined reference.
+const MAX_TARGETS: usize = 8;
+/// The client command picking the heading or figure to be labelled, taking
+/// the [`LabelTargets`].
+const PICK_LABEL_TARGET_COMMAND: &str = "tinymist.pickLabelTarget";
+
+/// Reports the references to the labels that are neither defined in the
+/// workspace nor keys of the bibliography files, and the labels that are never
+/// referenced or used in the workspace.
+pub(super) fn lint_labels(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    let labels = collect_labels(source);
+    if labels.is_empty() {
+        return;
+    }
+
+    let index = ctx.label_index();
+    for label in labels {
+        match label {
+            // The labels created from non-literal strings may be referenced,
+            // so that no reference is undefined for sure.
+            LabelOccurrence::Reference(name, range) if !index.dynamic => {
+                if index.is_defined(&name) {
+                    continue;
+                }
+                lints.push(undefined_reference(&index, name, range));
+            }
+            LabelOccurrence::Definition(name, range) => {
+                if index.referenced.contains(&name) {
+                    continue;
+                }
+                let message = tr_args(
+                    "lint.labels.unused",
+                    "label `<{name}>` is never referenced",
+                    &[("name", &name)],
+                );
+                lints.push(UNUSED_LABEL.lint(range, message));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn undefined_reference(index: &LabelIndex, name: String, range: Range<usize>) -> Lint {
+    let candidates = label_candidates(index, &name);
+    let message = match candidates.as_slice() {
+        [] => tr_args(
+            "lint.labels.undefined",
+            "label `<{name}>` does not exist in the workspace",
+            &[("name", &name)],
+        ),
+        candidates => {
+            let candidates = candidates.iter().map(|candidate| format!("`{candidate}`"));
+            let candidates = candidates.collect::<Vec<_>>().join(", ");
+            tr_args(
+                "lint.labels.undefined-candidates",
+                "label `<{name}>` does not exist in the workspace, did you mean {candidates}?",
+                &[("name", &name), ("candidates", &candidates)],
+            )
+        }
+    };
+
+    let mut lint = UNDEFINED_REFERENCE.lint(range.clone(), message);
+    // Only the closest candidate is offered as the quick fix.
+    lint.fix = candidates.into_iter().next().map(|candidate| LintFix {
+        title: tr_args(
+            "lint.labels.fix",
+            "Change to `{name}`",
+            &[("name", &candidate)],
+        ),
+        edits: vec![(range, candidate)],
+        create: None,
+        command: None,
+    });
+    lint
+}
+
+/// A heading or a figure that can be labelled by an undefined reference.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelTarget {
+    /// The text of the heading or the caption of the figure.
+    title: String,
+    /// Either `heading` or `figure`.
+    kind: &'static str,
+    /// Where the label is inserted, i.e. the end of the heading or the figure.
+    position: LspPosition,
+}
+
+/// The arguments of the command picking the heading or figure to be labelled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelTargets {
+    /// The document of the targets.
+    uri: Url,
+    /// The inserted label, e.g. ` <fig:results>`.
+    label: String,
+    /// The targets, the nearest to the reference first.
+    targets: Vec<LabelTarget>,
+}
+
+/// Suggests creating the label of an undefined reference at a nearby heading
+/// or figure without a label, either the nearest one or one picked by the
+/// client.
+pub(super) fn suggest_labels(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lint: &Lint,
+) -> Vec<LintFix> {
+    if lint.code != UNDEFINED_REFERENCE.code {
+        return vec![];
+    }
+    let Some(name) = source.text().get(lint.range.clone()) else {
+        return vec![];
+    };
+    let label = format!(" <{name}>");
+
+    let mut targets = syntax_outline(source);
+    targets.retain(|(_, item)| {
+        let kind = matches!(
+            item.kind,
+            OutlineItemKind::Heading | OutlineItemKind::Figure
+        );
+        kind && item.label.is_none()
+    });
+    targets.sort_by_key(|(range, _)| range.end.abs_diff(lint.range.start));
+    targets.truncate(MAX_TARGETS);
+
+    let mut fixes = vec![];
+    let describe = |kind| match kind {
+        OutlineItemKind::Heading => "heading",
+        _ => "figure",
+    };
+    if let Some((range, item)) = targets.first() {
+        let kind = match item.kind {
+            OutlineItemKind::Heading => tr("lint.labels.heading", "heading"),
+            _ => tr("lint.labels.figure", "figure"),
+        };
+        let title = tr_args(
+            "lint.labels.create",
+            "Create label `<{name}>` at {kind} `{title}`",
+            &[("name", &name), ("kind", &kind), ("title", &item.title)],
+        );
+        fixes.push(LintFix {
+            title,
+            edits: vec![(range.end..range.end, label.clone())],
+            create: None,
+            command: None,
+        });
+    }
+
+    let uri = ctx.path_for_id(source.id()).ok();
+    let uri = uri.and_then(|path| path_to_url(&path).ok());
+    if let (Some(uri), true) = (uri, targets.len() > 1) {
+        let targets = targets.iter().map(|(range, item)| LabelTarget {
+            title: item.title.clone(),
+            kind: describe(item.kind),
+            position: ctx.to_lsp_pos(range.end, source),
+        });
+        let arguments = LabelTargets {
+            uri,
+            label,
+            targets: targets.collect(),
+        };
+        let title = tr_args(
+            "lint.labels.pick",
+            "Create label `<{name}>` at a nearby heading or figure...",
+            &[("name", &name)],
+        );
+        fixes.push(LintFix {
+            title: title.clone(),
+            edits: vec![],
+            create: None,
+            command: Some(Command {
+                title,
+                command: PICK_LABEL_TARGET_COMMAND.to_owned(),
+                arguments: serde_json::to_value(arguments).ok().map(|args| vec![args]),
+            }),
+        });
+    }
+
+    fixes
+}
+
+/// Gets the defined labels and the bibliography keys closest to an undefined
+/// name, by their edit distances.
+fn label_candidates(index: &LabelIndex, name: &str) -> Vec<String> {
+    let name = name.chars().collect::<Vec<_>>();
+    let threshold = (name.len() / 3).max(2);
+
+    let defined = index.defined.iter().chain(&index.bib_keys);
+    let mut candidates = defined
+        .map(|label| {
+            (
+                edit_distance(&name, &label.chars().collect::<Vec<_>>()),
+                label,
+            )
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+
+    let candidates = candidates.into_iter().take(MAX_CANDIDATES);
+    candidates.map(|(_, label)| label.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_candidates() {
+        let index = LabelIndex {
+            defined: ["methods", "results", "intro"].map(String::from).into(),
+            bib_keys: ["method"].map(String::from).into(),
+            ..LabelIndex::default()
+        };
+        assert_eq!(label_candidates(&index, "methds"), ["methods", "method"]);
+        assert!(label_candidates(&index, "conclusion").is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Lints of the paths to missing files.
+
+use reflexo::path::unix_slash;
+use typst::diag::FileError;
+
+use super::*;
+use crate::{
+    analysis::edit_distance,
+    document_link::{collect_link_targets, LinkTarget},
+    syntax::resolve_id_by_path,
+};
+
+/// Reports the paths of `#import`, `#include`, and the functions loading files,
+/// e.g. `image("cat.png")`, that are not found in the workspace.
+pub(super) fn lint_missing_file(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    for (range, path) in collect_paths(source) {
+        let Some(id) = resolve_id_by_path(ctx.world(), source.id(), &path) else {
+            continue;
+        };
+        if !matches!(ctx.world().file(id), Err(FileError::NotFound(..))) {
+            continue;
+        }
+
+        // The lint covers the quotes to be merged with the compiler errors.
+        let range = range.start - 1..range.end + 1;
+        let message = tr_args(
+            "lint.file.missing",
+            "file not found: `{path}`",
+            &[("path", &path)],
+        );
+        lints.push(MISSING_FILE.lint(range, message));
+    }
+}
+
+/// Suggests fixing the path to the closest existing file, or creating the
+/// missing file.
+pub(super) fn suggest_missing_file(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lint: &Lint,
+) -> Vec<LintFix> {
+    let range = lint.range.start + 1..lint.range.end - 1;
+    let mut paths = collect_paths(source).into_iter();
+    let Some((_, path)) = paths.find(|(r, _)| *r == range) else {
+        return vec![];
+    };
+    let Some(id) = resolve_id_by_path(ctx.world(), source.id(), &path) else {
+        return vec![];
+    };
+
+    let mut fixes = vec![];
+    if let Some(closest) = closest_path(ctx, source.id(), id, &path) {
+        fixes.push(LintFix {
+            title: tr_args("lint.file.fix", "Change to `{path}`", &[("path", &closest)]),
+            edits: vec![(range, closest)],
+            create: None,
+            command: None,
+        });
+    }
+    if let Ok(file) = ctx.path_for_id(id) {
+        fixes.push(LintFix {
+            title: tr_args(
+                "lint.file.create",
+                "Create file `{path}`",
+                &[("path", &path)],
+            ),
+            edits: vec![],
+            create: Some(file),
+            command: None,
+        });
+    }
+    fixes
+}
+
+/// Collects the paths in a source, excluding the package imports.
+fn collect_paths(source: &Source) -> Vec<(Range<usize>, String)> {
+    let mut targets = vec![];
+    collect_link_targets(&LinkedNode::new(source.root()), &mut targets);
+
+    let paths = targets
+        .into_iter()
+        .filter_map(|(range, target)| match target {
+            LinkTarget::Path(path) if !path.starts_with('@') => Some((range, path)),
+            _ => None,
+        });
+    paths.collect()
+}
+
+/// Finds the existing file in the workspace closest to a missing file, with
+/// the same extension. The path is written in the same way as the missing
+/// one, i.e. either relative to the current file or to the root.
+fn closest_path(
+    ctx: &AnalysisContext,
+    current: TypstFileId,
+    missing: TypstFileId,
+    path: &str,
+) -> Option<String> {
+    let missing = missing.vpath().as_rootless_path();
+    let chars = |path: &Path| unix_slash(path).chars().collect::<Vec<_>>();
+    let missing_chars = chars(missing);
+
+    let candidates = ctx.workspace_files().iter();
+    let candidates = candidates.filter(|file| file.extension() == missing.extension());
+    let (distance, closest) = candidates
+        .map(|file| (edit_distance(&missing_chars, &chars(file)), file))
+        .min()?;
+    if distance > (missing_chars.len() / 3).max(2) {
+        return None;
+    }
+
+    if path.starts_with('/') {
+        return Some(format!("/{}", unix_slash(closest)));
+    }
+    let base = current.vpath().as_rootless_path().parent()?;
+    let relative = pathdiff::diff_paths(closest, base)?;
+    Some(unix_slash(&relative))
+}
@@ -0,0 +1,110 @@
+//! Lints of the imported packages.
+
+use super::*;
+use crate::analysis::newer_compatible_version;
+
+/// The command downloading a package, taking the package spec.
+const DOWNLOAD_PACKAGE_COMMAND: &str = "tinymist.doDownloadPackage";
+
+/// Reports the imported packages that are not available locally, e.g. because
+/// they could not be downloaded when offline.
+pub(super) fn lint_missing_package(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lints: &mut Vec<Lint>,
+) {
+    let imports = collect_package_imports(source);
+    if imports.is_empty() {
+        return;
+    }
+
+    let local = ctx.resources.local_packages();
+    for (range, spec) in imports {
+        if local.contains(&spec) {
+            continue;
+        }
+
+        let args: &[(&str, &dyn fmt::Display)] = &[("spec", &spec)];
+        let message = tr_args(
+            "lint.package.unavailable",
+            "package `{spec}` is not available locally",
+            args,
+        );
+        let mut lint = MISSING_PACKAGE.lint(range, message);
+        let title = tr_args("lint.package.download", "Download package `{spec}`", args);
+        lint.fix = Some(LintFix {
+            title: title.clone(),
+            edits: vec![],
+            create: None,
+            command: Some(Command {
+                title,
+                command: DOWNLOAD_PACKAGE_COMMAND.to_owned(),
+                arguments: Some(vec![JsonValue::String(spec.to_string())]),
+            }),
+        });
+        lints.push(lint);
+    }
+}
+
+/// Reports the imported packages of which newer compatible versions exist in
+/// the registry.
+pub(super) fn lint_outdated_package(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lints: &mut Vec<Lint>,
+) {
+    let imports = collect_package_imports(source);
+    if imports.is_empty() {
+        return;
+    }
+
+    let packages = ctx.resources.registry_packages();
+    for (range, spec) in imports {
+        let Some(version) = newer_compatible_version(&spec, &packages) else {
+            continue;
+        };
+
+        let newer = PackageSpec {
+            version,
+            ..spec.clone()
+        };
+        let args: &[(&str, &dyn fmt::Display)] = &[("spec", &spec), ("newer", &newer)];
+        let message = tr_args(
+            "lint.package.newer",
+            "a newer version of `{spec}` is available: `{newer}`",
+            args,
+        );
+        let mut lint = OUTDATED_PACKAGE.lint(range.clone(), message);
+        lint.fix = Some(LintFix {
+            title: tr_args("lint.package.update", "Update to `{newer}`", args),
+            edits: vec![(range.start + 1..range.end - 1, newer.to_string())],
+            create: None,
+            command: None,
+        });
+        lints.push(lint);
+    }
+}
+
+/// Collects the packages imported by a source, with the ranges of their
+/// quoted specs.
+fn collect_package_imports(source: &Source) -> Vec<(Range<usize>, PackageSpec)> {
+    fn collect(node: &LinkedNode, imports: &mut Vec<(Range<usize>, PackageSpec)>) {
+        if let Some(import) = node.cast::<ast::ModuleImport>() {
+            if let ast::Expr::Str(path) = import.source() {
+                let spec = path.get().parse::<PackageSpec>();
+                let path_node = node.find(path.span());
+                if let (Ok(spec), Some(path_node)) = (spec, path_node) {
+                    imports.push((path_node.range(), spec));
+                }
+            }
+        }
+
+        for child in node.children() {
+            collect(&child, imports);
+        }
+    }
+
+    let mut imports = vec![];
+    collect(&LinkedNode::new(source.root()), &mut imports);
+    imports
+}
@@ -0,0 +1,177 @@
+//! Lints of the bindings shadowing other bindings or builtin functions.
+
+use std::collections::HashSet;
+
+use super::*;
+use crate::{
+    analysis::DefUseInfo,
+    syntax::{
+        get_lexical_hierarchy, LexicalHierarchy, LexicalKind, LexicalModKind, LexicalScopeKind,
+        LexicalVarKind,
+    },
+};
+
+/// Reports the `let` bindings shadowing a binding in an enclosing scope, which
+/// are usually meant to reassign it, and the `let` bindings shadowing a
+/// builtin function the file uses, e.g. `let text = ..` before calling
+/// `text(..)`.
+pub(super) fn lint_shadowed(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    let Some(def_use) = ctx.def_use(source.clone()) else {
+        return;
+    };
+    let Some(hierarchy) = get_lexical_hierarchy(source.clone(), LexicalScopeKind::DefUse) else {
+        return;
+    };
+
+    let root = LinkedNode::new(source.root());
+    let mut called = HashSet::new();
+    collect_called_names(&root, &mut called);
+
+    let mut worker = ShadowWorker {
+        ctx,
+        root,
+        def_use: &def_use,
+        called,
+        scopes: vec![],
+        lints,
+    };
+    worker.enter(&hierarchy);
+}
+
+struct ShadowWorker<'a, 'b, 'w> {
+    ctx: &'a mut AnalysisContext<'w>,
+    root: LinkedNode<'b>,
+    def_use: &'a DefUseInfo,
+    /// The names of the functions called in the file.
+    called: HashSet<String>,
+    /// The names and ranges bound in the scopes, from the outermost to the
+    /// innermost.
+    scopes: Vec<Vec<(String, Range<usize>)>>,
+    lints: &'a mut Vec<Lint>,
+}
+
+impl<'a, 'b, 'w> ShadowWorker<'a, 'b, 'w> {
+    fn enter(&mut self, items: &[LexicalHierarchy]) {
+        self.scopes.push(vec![]);
+        self.scan(items);
+        self.scopes.pop();
+    }
+
+    fn scan(&mut self, items: &[LexicalHierarchy]) {
+        for item in items {
+            match &item.info.kind {
+                LexicalKind::Block => {
+                    if let Some(children) = &item.children {
+                        self.enter(children);
+                    }
+                }
+                LexicalKind::Mod(LexicalModKind::Module(..)) => {
+                    if let Some(children) = &item.children {
+                        self.scan(children);
+                    }
+                }
+                LexicalKind::Var(LexicalVarKind::Variable | LexicalVarKind::Function) => {
+                    self.check(item);
+                    self.bind(item);
+                }
+                LexicalKind::Mod(
+                    LexicalModKind::Ident
+                    | LexicalModKind::Alias { .. }
+                    | LexicalModKind::PathVar
+                    | LexicalModKind::ModuleAlias,
+                ) => self.bind(item),
+                _ => {}
+            }
+        }
+    }
+
+    fn bind(&mut self, item: &LexicalHierarchy) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((item.info.name.clone(), item.info.range.clone()));
+        }
+    }
+
+    fn check(&mut self, item: &LexicalHierarchy) {
+        let name = &item.info.name;
+        if name.starts_with('_') {
+            return;
+        }
+        let Some(node) = self.root.leaf_at(item.info.range.start + 1) else {
+            return;
+        };
+        if !is_let_binding(&node) {
+            return;
+        }
+
+        let (outer, _) = self.scopes.split_at(self.scopes.len().saturating_sub(1));
+        let shadowed = outer.iter().flatten().filter(|(bound, _)| bound == name);
+        if let Some((_, range)) = shadowed.last() {
+            let message = tr_args(
+                "lint.shadowed.binding",
+                "`{name}` shadows a binding in an enclosing scope, \
+                 use `{name} = ..` to reassign it instead",
+                &[("name", name)],
+            );
+            let mut lint = SHADOWED_BINDING.lint(item.info.range.clone(), message);
+            let related = tr_args(
+                "lint.shadowed.declared-here",
+                "`{name}` is declared here",
+                &[("name", name)],
+            );
+            let id = self.root.span().id();
+            lint.related = id
+                .map(|id| (id, range.clone(), related))
+                .into_iter()
+                .collect();
+            self.lints.push(lint);
+            return;
+        }
+
+        let scope = self.ctx.world().library().global.scope();
+        if !matches!(scope.get(name), Some(Value::Func(..))) {
+            return;
+        }
+        // The builtin is used if it is referenced before the shadowing, or
+        // called after the shadowing by a binding that is not a function.
+        let used = self.def_use.undefined_refs.iter().any(|r| r.name == *name)
+            || (matches!(item.info.kind, LexicalKind::Var(LexicalVarKind::Variable))
+                && self.called.contains(name));
+        if used {
+            let message = tr_args(
+                "lint.shadowed.builtin",
+                "`{name}` shadows the builtin function `{name}` used in this file",
+                &[("name", name)],
+            );
+            let lint = SHADOWED_BUILTIN.lint(item.info.range.clone(), message);
+            self.lints.push(lint);
+        }
+    }
+}
+
+/// Checks whether an identifier is bound by a `let`, rather than a parameter,
+/// a loop variable, or an import.
+fn is_let_binding(node: &LinkedNode) -> bool {
+    let mut node = node.clone();
+    while let Some(parent) = node.parent() {
+        match parent.kind() {
+            SyntaxKind::LetBinding => return true,
+            SyntaxKind::Params | SyntaxKind::ForLoop | SyntaxKind::ModuleImport => return false,
+            _ => {}
+        }
+        node = parent.clone();
+    }
+
+    false
+}
+
+fn collect_called_names(node: &LinkedNode, names: &mut HashSet<String>) {
+    if let Some(call) = node.cast::<ast::FuncCall>() {
+        if let ast::Expr::Ident(callee) = call.callee() {
+            names.insert(callee.get().to_string());
+        }
+    }
+
+    for child in node.children() {
+        collect_called_names(&child, names);
+    }
+}
@@ -0,0 +1,222 @@
+//! Lints of the misspelled words in prose.
+
+use ecow::EcoString;
+
+use super::*;
+use crate::analysis::{Dictionary, SpellChecker};
+
+/// Reports the words in markup that are not in the dictionary of their
+/// language. The code, math, raw text, links, labels, and references are not
+/// checked, and neither are the languages without dictionaries.
+pub(super) fn lint_spelling(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    if !ctx.analysis.spell_check.config().enabled {
+        return;
+    }
+    let root = ctx.analysis.root.clone();
+    ctx.analysis.spell_check.refresh(&root);
+
+    let checker = &ctx.analysis.spell_check;
+    for (range, lang) in collect_words(source, &checker.config().language) {
+        let Some(dict) = checker.dictionary(&lang) else {
+            continue;
+        };
+        let word = &source.text()[range.clone()];
+        if !is_known(checker, &dict, word) {
+            let message = tr_args(
+                "lint.spelling.unknown",
+                "unknown word: `{word}`",
+                &[("word", &word)],
+            );
+            lints.push(SPELLING.lint(range, message));
+        }
+    }
+}
+
+/// Suggests the corrections of a misspelled word.
+pub(super) fn suggest_spelling(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    lint: &Lint,
+) -> Vec<LintFix> {
+    let checker = &ctx.analysis.spell_check;
+    let words = collect_words(source, &checker.config().language);
+    let Some((range, lang)) = words.into_iter().find(|(range, _)| *range == lint.range) else {
+        return vec![];
+    };
+    let Some(dict) = checker.dictionary(&lang) else {
+        return vec![];
+    };
+
+    let word = &source.text()[range.clone()];
+    let suggestions = dict.suggest(word, 5).into_iter();
+    let fixes = suggestions.map(|suggestion| LintFix {
+        title: tr_args(
+            "lint.spelling.fix",
+            "Change to `{suggestion}`",
+            &[("suggestion", &suggestion)],
+        ),
+        edits: vec![(range.clone(), suggestion)],
+        create: None,
+        command: None,
+    });
+    fixes.collect()
+}
+
+fn is_known(checker: &SpellChecker, dict: &Dictionary, word: &str) -> bool {
+    // The possessive forms are checked by their stems.
+    let stem = ["'s", "’s"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    [word, stem]
+        .iter()
+        .any(|word| dict.contains(word) || checker.is_user_word(word))
+}
+
+/// Collects the words in the prose of a source and their languages, i.e. the
+/// languages set by the last `set text(lang: ..)` in the enclosing blocks.
+fn collect_words(source: &Source, lang: &EcoString) -> Vec<(Range<usize>, EcoString)> {
+    let mut words = vec![];
+    let mut lang = lang.clone();
+    let root = LinkedNode::new(source.root());
+    collect_words_in(source.text(), &root, &mut lang, &mut words);
+    words
+}
+
+fn collect_words_in(
+    text: &str,
+    node: &LinkedNode,
+    lang: &mut EcoString,
+    words: &mut Vec<(Range<usize>, EcoString)>,
+) {
+    match node.kind() {
+        SyntaxKind::Raw
+        | SyntaxKind::Equation
+        | SyntaxKind::Link
+        | SyntaxKind::Label
+        | SyntaxKind::Ref => return,
+        SyntaxKind::SetRule => {
+            if let Some(set_lang) = text_lang(node) {
+                *lang = set_lang;
+            }
+            return;
+        }
+        SyntaxKind::ContentBlock | SyntaxKind::CodeBlock => {
+            // The set rules are scoped in the blocks.
+            let mut lang = lang.clone();
+            for child in node.children() {
+                collect_words_in(text, &child, &mut lang, words);
+            }
+            return;
+        }
+        SyntaxKind::Markup => {
+            // The words are split into texts by spaces and apostrophes.
+            let mut run: Option<Range<usize>> = None;
+            for child in node.children() {
+                let is_prose = matches!(
+                    child.kind(),
+                    SyntaxKind::Text | SyntaxKind::Space | SyntaxKind::SmartQuote
+                );
+                if is_prose {
+                    let range = child.range();
+                    run = Some(run.map_or(range.clone(), |run| run.start..range.end));
+                    continue;
+                }
+                if let Some(run) = run.take() {
+                    split_words(text, run, lang, words);
+                }
+                collect_words_in(text, &child, lang, words);
+            }
+            if let Some(run) = run {
+                split_words(text, run, lang, words);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    for child in node.children() {
+        collect_words_in(text, &child, lang, words);
+    }
+}
+
+/// Splits the words in a range of prose. The words with digits, and the
+/// acronyms and camel-cased words with inner capitals are skipped.
+fn split_words(
+    text: &str,
+    run: Range<usize>,
+    lang: &EcoString,
+    words: &mut Vec<(Range<usize>, EcoString)>,
+) {
+    let is_apostrophe = |c: char| c == '\'' || c == '’';
+
+    let prose = &text[run.clone()];
+    let mut start = None;
+    let ends = prose
+        .char_indices()
+        .chain(std::iter::once((prose.len(), ' ')));
+    for (i, c) in ends {
+        let in_word = c.is_alphanumeric() || (is_apostrophe(c) && start.is_some());
+        match start {
+            None if in_word => start = Some(i),
+            Some(word_start) if !in_word => {
+                start = None;
+                let word = prose[word_start..i].trim_end_matches(is_apostrophe);
+                let has_digit = word.chars().any(|c| c.is_numeric());
+                let inner_capital = word.chars().skip(1).any(char::is_uppercase);
+                if word.chars().count() < 2 || has_digit || inner_capital {
+                    continue;
+                }
+                let word_start = run.start + word_start;
+                words.push((word_start..word_start + word.len(), lang.clone()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gets the language set by `set text(lang: ..)`.
+fn text_lang(node: &LinkedNode) -> Option<EcoString> {
+    let rule = node.cast::<ast::SetRule>()?;
+    match rule.target() {
+        ast::Expr::Ident(target) if target.get() == "text" => {}
+        _ => return None,
+    }
+
+    rule.args().items().find_map(|arg| match arg {
+        ast::Arg::Named(named) if named.name().get() == "lang" => match named.expr() {
+            ast::Expr::Str(lang) => Some(lang.get()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_words() {
+        let source = Source::detached(
+            "Hello wörld, don't `raw` $x$ 2nd HTML #set text(lang: \"de\")\n\
+             Hallo [#set text(lang: \"fr\") Bonjour] Welt",
+        );
+
+        let words = collect_words(&source, &"en".into())
+            .into_iter()
+            .map(|(range, lang)| format!("{}:{lang}", &source.text()[range]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            [
+                "Hello:en",
+                "wörld:en",
+                "don't:en",
+                "Hallo:de",
+                "Bonjour:fr",
+                "Welt:de"
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Lints of the unreachable code.
+
+use super::*;
+
+/// Reports the code after a `return` in the same block, and the branches of
+/// `if` and `while` that are never taken as their conditions are constant,
+/// e.g. `while false { .. }`.
+pub(super) fn lint_unreachable(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    check_unreachable(ctx, &LinkedNode::new(source.root()), lints);
+}
+
+fn check_unreachable(ctx: &AnalysisContext, node: &LinkedNode, lints: &mut Vec<Lint>) {
+    let unreachable = |range, message| UNREACHABLE_CODE.lint(range, message);
+
+    match node.kind() {
+        SyntaxKind::Code | SyntaxKind::Markup => {
+            let mut rest = node
+                .children()
+                .skip_while(|child| child.kind() != SyntaxKind::FuncReturn);
+            if rest.next().is_some() {
+                let dead = rest
+                    .filter(|child| !is_separator(child.kind()))
+                    .map(|child| child.range())
+                    .reduce(|first, last| first.start..last.end);
+                if let Some(dead) = dead {
+                    let message =
+                        tr("lint.unreachable.return", "unreachable code after `return`").to_owned();
+                    lints.push(unreachable(dead, message));
+                }
+            }
+        }
+        SyntaxKind::Conditional => {
+            let cond = node.cast::<ast::Conditional>().unwrap();
+            if let Some(taken) = constant_condition(ctx, node, cond.condition()) {
+                let dead = if taken {
+                    cond.else_body()
+                } else {
+                    Some(cond.if_body())
+                };
+                let value: &dyn fmt::Display = &taken;
+                match dead.and_then(|dead| node.find(dead.span())) {
+                    Some(dead) => {
+                        let message = tr_args(
+                            "lint.unreachable.condition-body",
+                            "unreachable code: the condition is always `{value}`",
+                            &[("value", value)],
+                        );
+                        lints.push(unreachable(dead.range(), message));
+                    }
+                    None => {
+                        let range = node.find(cond.condition().span()).unwrap().range();
+                        let message = tr_args(
+                            "lint.unreachable.condition",
+                            "the condition is always `{value}`",
+                            &[("value", value)],
+                        );
+                        lints.push(CONSTANT_CONDITION.lint(range, message));
+                    }
+                }
+            }
+        }
+        SyntaxKind::WhileLoop => {
+            let cond = node.cast::<ast::WhileLoop>().unwrap();
+            if constant_condition(ctx, node, cond.condition()) == Some(false) {
+                if let Some(body) = node.find(cond.body().span()) {
+                    let message = tr_args(
+                        "lint.unreachable.condition-body",
+                        "unreachable code: the condition is always `{value}`",
+                        &[("value", &false)],
+                    );
+                    lints.push(unreachable(body.range(), message));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for child in node.children() {
+        check_unreachable(ctx, &child, lints);
+    }
+}
+
+fn is_separator(kind: SyntaxKind) -> bool {
+    kind.is_trivia() || matches!(kind, SyntaxKind::Semicolon | SyntaxKind::Parbreak)
+}
+
+/// Evaluates a condition without any identifier, which is a constant.
+fn constant_condition(ctx: &AnalysisContext, node: &LinkedNode, cond: ast::Expr) -> Option<bool> {
+    fn has_ident(node: &LinkedNode) -> bool {
+        matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent)
+            || node.children().any(|child| has_ident(&child))
+    }
+
+    if has_ident(&node.find(cond.span())?) {
+        return None;
+    }
+    match ctx.mini_eval(cond)? {
+        Value::Bool(taken) => Some(taken),
+        _ => None,
+    }
+}
@@ -0,0 +1,252 @@
+//! Lints of the unused definitions and imports.
+
+use std::ops::Range;
+
+use super::*;
+use crate::syntax::{IdentRef, LexicalKind, LexicalVarKind};
+
+/// The kinds of definitions checked by the unused lint.
+#[derive(Debug, Clone, Copy)]
+enum UnusedKind {
+    Variable,
+    Function,
+    Parameter,
+    Import,
+}
+
+impl UnusedKind {
+    fn rule(self) -> &'static LintRule {
+        match self {
+            Self::Variable => &UNUSED_VARIABLE,
+            Self::Function => &UNUSED_FUNCTION,
+            Self::Parameter => &UNUSED_PARAMETER,
+            Self::Import => &UNUSED_IMPORT,
+        }
+    }
+
+    /// The keys and the English texts of the message and the title of the fix.
+    fn messages(self) -> [(&'static str, &'static str); 2] {
+        match self {
+            Self::Variable => [
+                ("lint.unused.variable", "unused variable: `{name}`"),
+                (
+                    "lint.unused.fix.variable",
+                    "Remove unused variable `{name}`",
+                ),
+            ],
+            Self::Function => [
+                ("lint.unused.function", "unused function: `{name}`"),
+                (
+                    "lint.unused.fix.function",
+                    "Remove unused function `{name}`",
+                ),
+            ],
+            Self::Parameter => [
+                ("lint.unused.parameter", "unused parameter: `{name}`"),
+                (
+                    "lint.unused.fix.parameter",
+                    "Remove unused parameter `{name}`",
+                ),
+            ],
+            Self::Import => [
+                ("lint.unused.import", "unused import: `{name}`"),
+                ("lint.unused.fix.import", "Remove unused import `{name}`"),
+            ],
+        }
+    }
+}
+
+/// Reports the `let` bindings, the closure parameters, and the import items
+/// that are never referenced. The names starting with `_` are ignored.
+///
+/// The definitions exported by a file are used if other files in the
+/// workspace import them, or import the file as a module.
+pub(super) fn lint_unused(ctx: &mut AnalysisContext, source: &Source, lints: &mut Vec<Lint>) {
+    let Some(def_use) = ctx.def_use(source.clone()) else {
+        return;
+    };
+    let fid = source.id();
+    let root = LinkedNode::new(source.root());
+    let mut import_index = None;
+
+    let mut refs_by_def = HashMap::<DefId, Vec<&IdentRef>>::new();
+    for (ident, id) in &def_use.ident_refs {
+        refs_by_def.entry(*id).or_default().push(ident);
+    }
+
+    for (idx, ((def_fid, ident), def)) in def_use.ident_defs.iter().enumerate() {
+        let is_value = matches!(
+            def.kind,
+            LexicalKind::Var(LexicalVarKind::Variable | LexicalVarKind::Function)
+                | LexicalKind::Mod(..)
+        );
+        if !is_value || def.name.starts_with('_') {
+            continue;
+        }
+        let id = DefId(idx as u64);
+
+        // The definitions imported from other files are checked at the import
+        // items referring to them.
+        let refs = refs_by_def.get(&id).map_or(&[][..], Vec::as_slice);
+        let site = match refs {
+            [] if *def_fid == fid => def.range.clone(),
+            [site] if *def_fid != fid => site.range.clone(),
+            _ => continue,
+        };
+
+        let Some(node) = root.leaf_at(site.start + 1) else {
+            continue;
+        };
+        let Some((kind, owner)) = classify_unused(&node, &def.kind) else {
+            continue;
+        };
+        // A reference outside of an import is a use, and the renamed imports
+        // are checked at their new names.
+        let renamed = node.parent_kind() == Some(SyntaxKind::RenamedImportItem);
+        if *def_fid != fid && (!matches!(kind, UnusedKind::Import) || renamed) {
+            continue;
+        }
+
+        if def_use.is_exported(id) {
+            let import_index = import_index.get_or_insert_with(|| ctx.import_index());
+            if import_index.is_imported(*def_fid, ident) {
+                continue;
+            }
+        }
+
+        let name: &dyn fmt::Display = &def.name;
+        let [(key, message), (fix_key, fix_title)] = kind.messages();
+        let fix = removal_edit(source, &owner, kind).map(|edit| LintFix {
+            title: tr_args(fix_key, fix_title, &[("name", name)]),
+            edits: vec![(edit, String::new())],
+            create: None,
+            command: None,
+        });
+        let message = tr_args(key, message, &[("name", name)]);
+        let mut lint = kind.rule().lint(site, message);
+        lint.fix = fix;
+        if *def_fid != fid {
+            let related = tr_args(
+                "lint.unused.defined-here",
+                "`{name}` is defined here",
+                &[("name", name)],
+            );
+            lint.related = vec![(*def_fid, def.range.clone(), related)];
+        }
+        lints.push(lint);
+    }
+}
+
+/// Classifies an unused definition by its syntax, and gets the node to remove
+/// to fix it.
+fn classify_unused<'a>(
+    node: &LinkedNode<'a>,
+    kind: &LexicalKind,
+) -> Option<(UnusedKind, LinkedNode<'a>)> {
+    let mut child = node.clone();
+    while let Some(parent) = child.parent() {
+        match parent.kind() {
+            SyntaxKind::Params => return Some((UnusedKind::Parameter, child)),
+            SyntaxKind::LetBinding => {
+                let kind = match kind {
+                    LexicalKind::Var(LexicalVarKind::Function) => UnusedKind::Function,
+                    LexicalKind::Var(LexicalVarKind::Variable) => UnusedKind::Variable,
+                    _ => return None,
+                };
+                return Some((kind, parent.clone()));
+            }
+            SyntaxKind::ImportItems => return Some((UnusedKind::Import, child)),
+            SyntaxKind::ModuleImport => return Some((UnusedKind::Import, parent.clone())),
+            SyntaxKind::ForLoop => return None,
+            _ => {}
+        }
+        child = parent.clone();
+    }
+
+    None
+}
+
+/// Gets the range to remove for an unused definition.
+fn removal_edit(source: &Source, owner: &LinkedNode, kind: UnusedKind) -> Option<Range<usize>> {
+    match owner.kind() {
+        SyntaxKind::LetBinding => {
+            // Only removes the bindings of a single name.
+            let binding = owner.cast::<ast::LetBinding>()?;
+            if binding.kind().bindings().len() != 1 {
+                return None;
+            }
+            Some(statement_range(source, owner))
+        }
+        SyntaxKind::ModuleImport => {
+            let import = owner.cast::<ast::ModuleImport>()?;
+            if import.imports().is_some() {
+                return None;
+            }
+            Some(statement_range(source, owner))
+        }
+        _ if matches!(kind, UnusedKind::Import) => {
+            let items = owner.parent()?;
+            let count = items.children().filter(|c| is_list_item(c.kind())).count();
+            if count == 1 {
+                let import = items.parent()?;
+                return Some(statement_range(source, import));
+            }
+            Some(list_item_range(source, owner))
+        }
+        // Destructured parameters are kept as they may be positional.
+        SyntaxKind::Ident | SyntaxKind::Named | SyntaxKind::Spread => {
+            Some(list_item_range(source, owner))
+        }
+        _ => None,
+    }
+}
+
+fn is_list_item(kind: SyntaxKind) -> bool {
+    !kind.is_trivia() && kind != SyntaxKind::Comma
+}
+
+/// Gets the range of a statement with its hash, semicolon, and line if the
+/// statement is the only content on the line.
+fn statement_range(source: &Source, node: &LinkedNode) -> Range<usize> {
+    let mut range = node.range();
+    if let Some(hash) = node.prev_leaf().filter(|n| n.kind() == SyntaxKind::Hash) {
+        range.start = hash.offset();
+    }
+    if let Some(semi) = node
+        .next_leaf()
+        .filter(|n| n.kind() == SyntaxKind::Semicolon)
+    {
+        range.end = semi.range().end;
+    }
+
+    let text = source.text();
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = &text[range.end..];
+    let line_end = rest.find('\n');
+    let after = &rest[..line_end.unwrap_or(rest.len())];
+    if text[line_start..range.start].trim().is_empty() && after.trim().is_empty() {
+        range.start = line_start;
+        range.end += line_end.map_or(rest.len(), |i| i + 1);
+    }
+
+    range
+}
+
+/// Gets the range of an item in a comma-separated list with its comma.
+fn list_item_range(source: &Source, node: &LinkedNode) -> Range<usize> {
+    let mut range = node.range();
+    if let Some(comma) = node
+        .next_sibling()
+        .filter(|n| n.kind() == SyntaxKind::Comma)
+    {
+        let rest = &source.text()[comma.range().end..];
+        range.end = comma.range().end + rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    } else if let Some(comma) = node
+        .prev_sibling()
+        .filter(|n| n.kind() == SyntaxKind::Comma)
+    {
+        range.start = comma.offset();
+    }
+
+    range
+}
@@ -43,6 +43,7 @@ pub fn get_semantic_tokens_unregistration() -> Unregistration {
 pub fn get_semantic_tokens_options() -> SemanticTokensOptions {
     SemanticTokensOptions {
         legend: get_legend(),
+        range: Some(true),
         full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
         ..Default::default()
     }
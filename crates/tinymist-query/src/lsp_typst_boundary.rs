@@ -69,8 +69,21 @@ pub type TypstCompletion = crate::upstream::Completion;
 pub type TypstCompletionKind = crate::upstream::CompletionKind;
 
 const UNTITLED_ROOT: &str = "/untitled";
+/// The root of the paths of the documents which are not on the file system,
+/// e.g. of the remote workspaces, which are mapped to
+/// `/remote/<scheme>/<authority>/<path>`.
+const REMOTE_ROOT: &str = "/remote";
+/// The authority of a remote path whose URI has no authority, which is not a
+/// valid host name.
+const EMPTY_AUTHORITY: &str = "-";
 static EMPTY_URL: Lazy<Url> = Lazy::new(|| Url::parse("file://").unwrap());
 
+/// Whether a path is mapped from a document which is not on the file system,
+/// i.e. an untitled or remote document, which only exists in memory.
+pub fn is_virtual_path(path: &Path) -> bool {
+    path.starts_with(UNTITLED_ROOT) || path.starts_with(REMOTE_ROOT)
+}
+
 pub fn path_to_url(path: &Path) -> anyhow::Result<Url> {
     if let Ok(untitled) = path.strip_prefix(UNTITLED_ROOT) {
         // rust-url will panic on converting an empty path.
@@ -81,6 +94,25 @@ pub fn path_to_url(path: &Path) -> anyhow::Result<Url> {
         return Ok(Url::parse(&format!("untitled:{}", untitled.display()))?);
     }
 
+    if let Ok(remote) = path.strip_prefix(REMOTE_ROOT) {
+        let mut segs = remote.iter().map(|seg| seg.to_string_lossy());
+        let (Some(scheme), Some(authority)) = (segs.next(), segs.next()) else {
+            anyhow::bail!("could not convert remote path to URI: path: {path:?}");
+        };
+        let authority = if authority == EMPTY_AUTHORITY {
+            ""
+        } else {
+            authority.as_ref()
+        };
+
+        let mut uri = Url::parse(&format!("{scheme}://{authority}/"))?;
+        uri.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("could not convert remote path to URI: {path:?}"))?
+            .pop_if_empty()
+            .extend(segs);
+        return Ok(uri);
+    }
+
     Url::from_file_path(path).or_else(|e| {
         let _: () = e;
 
@@ -114,7 +146,21 @@ pub fn url_to_path(uri: Url) -> PathBuf {
         return Path::new(String::from_utf8_lossy(&bytes).as_ref()).clean();
     }
 
-    uri.to_file_path().unwrap()
+    // The documents of the other schemes, e.g. `vscode-vfs`, are not on the file
+    // system.
+    let authority = match uri.host_str() {
+        Some(host) if !host.is_empty() => match uri.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_owned(),
+        },
+        _ => EMPTY_AUTHORITY.to_owned(),
+    };
+    let mut path = Path::new(REMOTE_ROOT).join(uri.scheme()).join(authority);
+    for segment in uri.path().split('/').filter(|seg| !seg.is_empty()) {
+        let segment = percent_encoding::percent_decode(segment.as_bytes());
+        path.push(segment.decode_utf8_lossy().as_ref());
+    }
+    path.clean()
 }
 
 pub mod lsp_to_typst {
@@ -211,15 +257,15 @@ pub mod typst_to_lsp {
     use itertools::Itertools;
     use lazy_static::lazy_static;
     use lsp_types::{
-        Command, CompletionItemLabelDetails, CompletionTextEdit, Documentation, InsertTextFormat,
-        LanguageString, MarkedString, MarkupContent, MarkupKind, TextEdit,
+        Command, CompletionItemLabelDetails, CompletionItemTag, CompletionTextEdit, Documentation,
+        InsertTextFormat, LanguageString, MarkedString, MarkupContent, MarkupKind, TextEdit,
     };
     use regex::{Captures, Regex};
-    use typst::diag::EcoString;
     use typst::foundations::{CastInfo, Repr};
     use typst::syntax::Source;
 
     use super::*;
+    use crate::CompletionFeat;
 
     pub fn offset_to_position(
         typst_offset: TypstOffset,
@@ -275,6 +321,7 @@ pub mod typst_to_lsp {
             TypstCompletionKind::Field => LspCompletionKind::FIELD,
             TypstCompletionKind::Variable => LspCompletionKind::VARIABLE,
             TypstCompletionKind::Constant => LspCompletionKind::CONSTANT,
+            TypstCompletionKind::Color => LspCompletionKind::COLOR,
             TypstCompletionKind::Symbol(_) => LspCompletionKind::FIELD,
             TypstCompletionKind::Type => LspCompletionKind::CLASS,
             TypstCompletionKind::Module => LspCompletionKind::MODULE,
@@ -288,25 +335,40 @@ pub mod typst_to_lsp {
     }
 
     /// Adds numbering to placeholders in snippets
-    fn snippet(typst_snippet: &EcoString) -> String {
+    fn snippet(typst_snippet: &str) -> String {
         let mut counter = 1;
-        let result =
-            TYPST_SNIPPET_PLACEHOLDER_RE.replace_all(typst_snippet.as_str(), |cap: &Captures| {
-                let substitution = format!("${{{}:{}}}", counter, &cap[1]);
-                counter += 1;
-                substitution
-            });
+        let result = TYPST_SNIPPET_PLACEHOLDER_RE.replace_all(typst_snippet, |cap: &Captures| {
+            let substitution = format!("${{{}:{}}}", counter, &cap[1]);
+            counter += 1;
+            substitution
+        });
 
         result.to_string()
     }
 
-    pub fn completion(typst_completion: &TypstCompletion, lsp_replace: LspRange) -> LspCompletion {
+    /// Converts a snippet to the text inserted by the client, which is plain
+    /// text keeping the placeholder contents if the client doesn't support
+    /// snippets.
+    pub fn snippet_text(typst_snippet: &str, feat: CompletionFeat) -> (String, InsertTextFormat) {
+        if feat.snippets {
+            return (snippet(typst_snippet), InsertTextFormat::SNIPPET);
+        }
+
+        let text = TYPST_SNIPPET_PLACEHOLDER_RE.replace_all(typst_snippet, "$1");
+        (text.to_string(), InsertTextFormat::PLAIN_TEXT)
+    }
+
+    pub fn completion(
+        typst_completion: &TypstCompletion,
+        lsp_replace: LspRange,
+        feat: CompletionFeat,
+    ) -> LspCompletion {
         let typst_snippet = typst_completion
             .apply
             .as_ref()
             .unwrap_or(&typst_completion.label);
-        let lsp_snippet = snippet(typst_snippet);
-        let text_edit = CompletionTextEdit::Edit(TextEdit::new(lsp_replace, lsp_snippet));
+        let (text, insert_text_format) = snippet_text(typst_snippet, feat);
+        let text_edit = CompletionTextEdit::Edit(TextEdit::new(lsp_replace, text));
 
         LspCompletion {
             label: typst_completion.label.to_string(),
@@ -320,7 +382,10 @@ pub mod typst_to_lsp {
                 }
             }),
             text_edit: Some(text_edit),
-            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            insert_text_format: Some(insert_text_format),
+            tags: typst_completion
+                .deprecated
+                .then(|| vec![CompletionItemTag::DEPRECATED]),
             command: typst_completion.command.as_ref().map(|c| Command {
                 command: c.to_string(),
                 ..Default::default()
@@ -394,6 +459,23 @@ mod test {
         assert_eq!(path, Path::new("/untitled/test").clean());
     }
 
+    #[test]
+    fn test_remote() {
+        let uri = Url::parse("vscode-vfs://github/typst/book/ch%201.typ").unwrap();
+        let path = url_to_path(uri.clone());
+        assert_eq!(
+            path,
+            Path::new("/remote/vscode-vfs/github/typst/book/ch 1.typ")
+        );
+        assert!(is_virtual_path(&path));
+        assert_eq!(path_to_url(&path).unwrap(), uri);
+
+        let uri = Url::parse("vscode-notebook-cell:/main.typ").unwrap();
+        let path = url_to_path(uri.clone());
+        assert_eq!(path, Path::new("/remote/vscode-notebook-cell/-/main.typ"));
+        assert_eq!(path_to_url(&path).unwrap().path(), "/main.typ");
+    }
+
     #[test]
     fn unnamed_buffer() {
         // https://github.com/neovim/nvim-lspconfig/pull/2226
@@ -553,4 +635,26 @@ mod test {
         assert_eq!(post_emoji_position, post_emoji_actual);
         assert_eq!(end_position, end_actual);
     }
+
+    #[test]
+    fn snippet_plain_text_fallback() {
+        use crate::CompletionFeat;
+        use lsp_types::InsertTextFormat;
+
+        let snippet = "rgb(${r}, ${g}, ${b})${}";
+
+        let feat = CompletionFeat::default();
+        let (text, format) = typst_to_lsp::snippet_text(snippet, feat);
+        assert_eq!(text, "rgb(${1:r}, ${2:g}, ${3:b})${4:}");
+        assert_eq!(format, InsertTextFormat::SNIPPET);
+
+        let feat = CompletionFeat {
+            snippets: false,
+            trigger_suggest: false,
+        };
+        let (text, format) = typst_to_lsp::snippet_text(snippet, feat);
+        assert_eq!(text, "rgb(r, g, b)");
+        assert_eq!(format, InsertTextFormat::PLAIN_TEXT);
+        assert_eq!(feat.trigger_suggest_command(), None);
+    }
 }
@@ -0,0 +1,216 @@
+use std::ops::Range;
+
+use lsp_types::TextEdit;
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, SyntaxRequest};
+
+/// The user configuration of the formatting on typing, i.e. which of the edits
+/// are made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OnTypeFormattingConfig {
+    /// Whether to continue the list and enum items on a new line.
+    pub list_continuation: bool,
+    /// Whether to close the dollar of an equation.
+    pub math_delimiters: bool,
+    /// Whether to insert a space after the marker of a heading.
+    pub heading_spacing: bool,
+}
+
+impl Default for OnTypeFormattingConfig {
+    fn default() -> Self {
+        Self {
+            list_continuation: true,
+            math_delimiters: true,
+            heading_spacing: true,
+        }
+    }
+}
+
+/// The [`textDocument/onTypeFormatting`] request is sent from the client to
+/// the server to format parts of the document during typing.
+///
+/// [`textDocument/onTypeFormatting`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_onTypeFormatting
+///
+/// The edits made on typing are:
+/// + a newline in a list or enum item continues the item with the same
+///   indentation and marker, or ends the list if the item is empty.
+/// + a dollar opening an equation in markup is closed.
+/// + a `=` before text at the start of a line is followed by a space, which
+///   turns the line into a heading.
+#[derive(Debug, Clone)]
+pub struct OnTypeFormattingRequest {
+    /// The path of the document to format.
+    pub path: PathBuf,
+    /// The position after the typed character.
+    pub position: LspPosition,
+    /// The typed character.
+    pub ch: String,
+    /// The user configuration of the formatting.
+    pub config: OnTypeFormattingConfig,
+}
+
+impl SyntaxRequest for OnTypeFormattingRequest {
+    type Response = Vec<TextEdit>;
+
+    fn request(
+        self,
+        source: &Source,
+        position_encoding: PositionEncoding,
+    ) -> Option<Self::Response> {
+        let offset = lsp_to_typst::position(self.position, position_encoding, source)?;
+        let edits = match self.ch.as_str() {
+            "\n" if self.config.list_continuation => continue_list(source, offset),
+            "$" if self.config.math_delimiters => close_equation(source, offset),
+            "=" if self.config.heading_spacing => space_heading(source, offset),
+            _ => None,
+        }?;
+
+        let edits = edits.into_iter().map(|(range, new_text)| TextEdit {
+            range: typst_to_lsp::range(range, source, position_encoding),
+            new_text,
+        });
+        Some(edits.collect())
+    }
+}
+
+/// Continues the list or enum item on the line before a newline.
+fn continue_list(source: &Source, offset: usize) -> Option<Vec<(Range<usize>, String)>> {
+    let text = source.text();
+    let line_start = text[..offset].rfind('\n')? + 1;
+    // The whitespace may be inserted by the editor to indent the new line.
+    if !text[line_start..offset].trim().is_empty() {
+        return None;
+    }
+
+    let prev_end = line_start - 1;
+    let prev_end = prev_end - usize::from(text[..prev_end].ends_with('\r'));
+    let prev_start = text[..prev_end].rfind('\n').map_or(0, |i| i + 1);
+    let prev_line = &text[prev_start..prev_end];
+    let indent = &prev_line[..prev_line.len() - prev_line.trim_start().len()];
+    let marker_start = prev_start + indent.len();
+
+    let root = LinkedNode::new(source.root());
+    let marker = root.leaf_at(marker_start + 1)?;
+    if marker.offset() != marker_start {
+        return None;
+    }
+    let next_marker = match marker.kind() {
+        SyntaxKind::ListMarker => "-".to_owned(),
+        SyntaxKind::EnumMarker => match marker.text().strip_suffix('.') {
+            Some(number) => format!("{}.", number.parse::<usize>().ok()? + 1),
+            None => marker.text().to_string(),
+        },
+        _ => return None,
+    };
+
+    // An empty item ends the list instead.
+    let body = &text[marker.range().end..prev_end];
+    if body.trim().is_empty() {
+        return Some(vec![(marker_start..prev_end, String::new())]);
+    }
+
+    Some(vec![(
+        line_start..offset,
+        format!("{indent}{next_marker} "),
+    )])
+}
+
+/// Closes the equation opened by a dollar in markup.
+fn close_equation(source: &Source, offset: usize) -> Option<Vec<(Range<usize>, String)>> {
+    let root = LinkedNode::new(source.root());
+    let dollar = root.leaf_at(offset)?;
+    // The opening dollar of an unclosed equation is turned into an error.
+    let is_unclosed = dollar.kind() == SyntaxKind::Error
+        && dollar.text() == "$"
+        && dollar.range() == (offset - 1..offset)
+        && dollar.parent_kind() == Some(SyntaxKind::Equation);
+    is_unclosed.then(|| vec![(offset..offset, "$".to_owned())])
+}
+
+/// Inserts a space after the heading marker typed before text at the start
+/// of a line.
+fn space_heading(source: &Source, offset: usize) -> Option<Vec<(Range<usize>, String)>> {
+    let text = source.text();
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let marker = text[line_start..offset].trim_start();
+    if marker.is_empty() || marker.chars().any(|c| c != '=') {
+        return None;
+    }
+    let next = text[offset..].chars().next()?;
+    if next.is_whitespace() || next == '=' {
+        return None;
+    }
+
+    let root = LinkedNode::new(source.root());
+    let leaf = root.leaf_at(offset)?;
+    let in_markup = leaf.kind() == SyntaxKind::Text
+        && leaf.parent_kind() == Some(SyntaxKind::Markup)
+        && leaf.range().start == offset - marker.len();
+    in_markup.then(|| vec![(offset..offset, " ".to_owned())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies the edits made after typing at the `|` in a text.
+    fn type_at(
+        text: &str,
+        edits: fn(&Source, usize) -> Option<Vec<(Range<usize>, String)>>,
+    ) -> Option<String> {
+        let offset = text.find('|').unwrap();
+        let text = text.replacen('|', "", 1);
+        let source = Source::detached(&text);
+        let mut edits = edits(&source, offset)?;
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+        let mut text = text;
+        for (range, new_text) in edits {
+            text.replace_range(range, &new_text);
+        }
+        Some(text)
+    }
+
+    #[test]
+    fn test_continue_list() {
+        assert_eq!(type_at("- a\n|", continue_list).as_deref(), Some("- a\n- "));
+        assert_eq!(
+            type_at("- a\n  - b\n  |", continue_list).as_deref(),
+            Some("- a\n  - b\n  - ")
+        );
+        assert_eq!(
+            type_at("1. a\n9. b\n|", continue_list).as_deref(),
+            Some("1. a\n9. b\n10. ")
+        );
+        assert_eq!(type_at("+ a\n|", continue_list).as_deref(), Some("+ a\n+ "));
+        assert_eq!(
+            type_at("- a\n- \n|", continue_list).as_deref(),
+            Some("- a\n\n")
+        );
+        assert_eq!(type_at("a - b\n|", continue_list), None);
+        assert_eq!(type_at("```\n- a\n|```", continue_list), None);
+    }
+
+    #[test]
+    fn test_close_equation() {
+        assert_eq!(
+            type_at("Let $| be", close_equation).as_deref(),
+            Some("Let $$ be")
+        );
+        assert_eq!(type_at("Let $x$|", close_equation), None);
+        assert_eq!(type_at("#\"$|\"", close_equation), None);
+    }
+
+    #[test]
+    fn test_space_heading() {
+        assert_eq!(
+            type_at("==|Intro", space_heading).as_deref(),
+            Some("== Intro")
+        );
+        assert_eq!(type_at("=| Intro", space_heading), None);
+        assert_eq!(type_at("a =|b", space_heading), None);
+        assert_eq!(type_at("#let a =|b", space_heading), None);
+    }
+}
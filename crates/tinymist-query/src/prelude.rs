@@ -10,12 +10,13 @@ pub use itertools::{Format, Itertools};
 pub use log::{error, trace};
 pub use lsp_types::{
     request::GotoDeclarationResponse, CodeLens, ColorInformation, ColorPresentation,
-    CompletionResponse, DiagnosticRelatedInformation, DocumentSymbol, DocumentSymbolResponse,
-    Documentation, FoldingRange, GotoDefinitionResponse, Hover, InlayHint, LanguageString,
-    Location as LspLocation, LocationLink, MarkedString, MarkupContent, MarkupKind,
-    Position as LspPosition, PrepareRenameResponse, SelectionRange, SemanticTokens,
-    SemanticTokensDelta, SemanticTokensFullDeltaResult, SemanticTokensResult, SignatureHelp,
-    SignatureInformation, SymbolInformation, Url, WorkspaceEdit,
+    CompletionResponse, DiagnosticRelatedInformation, DocumentHighlight, DocumentLink,
+    DocumentSymbol, DocumentSymbolResponse, Documentation, FoldingRange, GotoDefinitionResponse,
+    Hover, InlayHint, LanguageString, LinkedEditingRanges, Location as LspLocation, LocationLink,
+    MarkedString, MarkupContent, MarkupKind, Position as LspPosition, PrepareRenameResponse,
+    SelectionRange, SemanticTokens, SemanticTokensDelta, SemanticTokensFullDeltaResult,
+    SemanticTokensRangeResult, SemanticTokensResult, SignatureHelp, SignatureInformation,
+    SymbolInformation, Url, WorkspaceEdit,
 };
 pub use reflexo::vector::ir::DefId;
 pub use serde_json::Value as JsonValue;
@@ -1,7 +1,9 @@
+use std::ops::Range;
+
 use crate::{
-    analysis::{find_definition, DefinitionLink},
+    analysis::{find_definition, label_name_at, DefinitionLink},
     prelude::*,
-    syntax::get_deref_target,
+    syntax::{get_deref_target, DerefTarget},
     SemanticRequest,
 };
 use log::debug;
@@ -21,6 +23,11 @@ use log::debug;
 /// is trying to rename a symbol that should not be renamed (inside a
 /// string or comment, on a builtin identifier, etc.), VSCode won't even
 /// show the rename pop-up.
+///
+/// The rename is rejected on keywords, builtin symbols, definitions in
+/// packages, and paths, which are renamed by renaming the files instead. The
+/// returned range covers only the identifier, e.g. `x` in `#x` and `b` in
+/// `a.b`.
 #[derive(Debug, Clone)]
 pub struct PrepareRenameRequest {
     /// The path of the document to request for.
@@ -30,7 +37,6 @@ pub struct PrepareRenameRequest {
 }
 
 // todo: rename alias
-// todo: rename import path?
 impl SemanticRequest for PrepareRenameRequest {
     type Response = PrepareRenameResponse;
 
@@ -38,14 +44,39 @@ impl SemanticRequest for PrepareRenameRequest {
         let source = ctx.source_by_path(&self.path).ok()?;
 
         let offset = ctx.to_typst_pos(self.position, &source)?;
-        let cursor = offset + 1;
+        let mut cursor = offset + 1;
 
-        let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
+        let mut ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
         debug!("ast_node: {ast_node:?}", ast_node = ast_node);
 
+        // The cursor on the hash of `#x` renames `x`.
+        if ast_node.kind() == SyntaxKind::Hash {
+            ast_node = ast_node.next_sibling()?.leftmost_leaf()?;
+            cursor = ast_node.offset() + 1;
+        }
+        if ast_node.kind().is_keyword() {
+            return None;
+        }
+
         let deref_target = get_deref_target(ast_node, cursor)?;
+        // Labels are renamed without their markers, e.g. `intro` in `@intro`.
+        if let DerefTarget::Label(node) | DerefTarget::Ref(node) = &deref_target {
+            let (name, range) = label_name_at(node)?;
+            return Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: ctx.to_lsp_range(range, &source),
+                placeholder: name,
+            });
+        }
+
+        if !matches!(
+            deref_target,
+            DerefTarget::VarAccess(..) | DerefTarget::Callee(..)
+        ) {
+            return None;
+        }
+
         let use_site = deref_target.node().clone();
-        let origin_selection_range = ctx.to_lsp_range(use_site.range(), &source);
+        let origin_selection_range = ctx.to_lsp_range(ident_range(&use_site)?, &source);
 
         let lnk = find_definition(ctx, source.clone(), deref_target)?;
         validate_renaming_definition(&lnk)?;
@@ -58,6 +89,15 @@ impl SemanticRequest for PrepareRenameRequest {
     }
 }
 
+/// Gets the range of the identifier to rename, i.e. the field of a field
+/// access.
+fn ident_range(use_site: &LinkedNode) -> Option<Range<usize>> {
+    match use_site.cast::<ast::FieldAccess>() {
+        Some(field_access) => Some(use_site.find(field_access.field().span())?.range()),
+        None => Some(use_site.range()),
+    }
+}
+
 pub(crate) fn validate_renaming_definition(lnk: &DefinitionLink) -> Option<()> {
     'check_func: {
         use typst::foundations::func::Repr;
@@ -0,0 +1,144 @@
+//! A lightweight profiler recording the scopes of a request, e.g. the analyses
+//! of the modules, in the [Chrome trace format], which can be viewed by
+//! `chrome://tracing` or [Perfetto](https://ui.perfetto.dev) as a flamegraph.
+//!
+//! The scopes are only recorded between [`start`] and [`finish`], so they are
+//! cheap to keep in the hot paths.
+//!
+//! [Chrome trace format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+static PROFILING: AtomicBool = AtomicBool::new(false);
+static EVENTS: Lazy<Mutex<Vec<TraceEvent>>> = Lazy::new(Default::default);
+static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+static THREADS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static THREAD_ID: u64 = THREADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A complete event of the Chrome trace format, i.e. a finished scope.
+#[derive(Debug, Clone, Serialize)]
+struct TraceEvent {
+    name: Cow<'static, str>,
+    cat: &'static str,
+    ph: &'static str,
+    /// The start of the scope in microseconds.
+    ts: u64,
+    /// The duration of the scope in microseconds.
+    dur: u64,
+    pid: u32,
+    tid: u64,
+}
+
+/// A scope being profiled, which is recorded when dropped.
+pub struct ProfileScope {
+    scope: Option<(Cow<'static, str>, Instant)>,
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let Some((name, start)) = self.scope.take() else {
+            return;
+        };
+        if !PROFILING.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let event = TraceEvent {
+            name,
+            cat: "tinymist",
+            ph: "X",
+            ts: start.saturating_duration_since(*EPOCH).as_micros() as u64,
+            dur: start.elapsed().as_micros() as u64,
+            pid: std::process::id(),
+            tid: THREAD_ID.with(|id| *id),
+        };
+        EVENTS.lock().push(event);
+    }
+}
+
+/// Whether the scopes are being recorded.
+pub fn is_profiling() -> bool {
+    PROFILING.load(Ordering::Relaxed)
+}
+
+/// Profiles a scope until the returned guard is dropped.
+pub fn scope(name: &'static str) -> ProfileScope {
+    scope_with(|| name)
+}
+
+/// Profiles a scope with a computed name, e.g. containing the path of a file,
+/// until the returned guard is dropped. The name is only computed when
+/// profiling.
+pub fn scope_with<N: Into<Cow<'static, str>>>(name: impl FnOnce() -> N) -> ProfileScope {
+    let scope = is_profiling().then(|| (name().into(), Instant::now()));
+    ProfileScope { scope }
+}
+
+/// Starts recording the scopes, dropping the recorded ones.
+pub fn start() {
+    Lazy::force(&EPOCH);
+    EVENTS.lock().clear();
+    PROFILING.store(true, Ordering::Relaxed);
+}
+
+/// Stops recording the scopes, returning the trace of the recorded ones in
+/// the Chrome trace format.
+pub fn finish() -> String {
+    PROFILING.store(false, Ordering::Relaxed);
+    let events = std::mem::take(&mut *EVENTS.lock());
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Trace {
+        trace_events: Vec<TraceEvent>,
+        display_time_unit: &'static str,
+    }
+
+    let trace = Trace {
+        trace_events: events,
+        display_time_unit: "ms",
+    };
+    serde_json::to_string(&trace).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        {
+            let _scope = scope("ignored");
+        }
+
+        start();
+        {
+            let _outer = scope("request");
+            let _inner = scope_with(|| format!("analyze {}", "main.typ"));
+        }
+        let trace = finish();
+        {
+            let _scope = scope("ignored");
+        }
+
+        // The scopes of the other tests running in parallel may be recorded.
+        let trace: serde_json::Value = serde_json::from_str(&trace).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        let names = events.iter().map(|e| e["name"].as_str().unwrap());
+        let names = names.filter(|name| *name == "request" || name.starts_with("analyze "));
+        assert_eq!(
+            names.collect::<Vec<_>>(),
+            vec!["analyze main.typ", "request"]
+        );
+        assert!(events.iter().all(|e| e["ph"] == "X"));
+    }
+}
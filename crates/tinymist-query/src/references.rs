@@ -1,6 +1,7 @@
 use log::debug;
 
 use crate::{
+    analysis::{find_bibliography_keys, find_label_occurrences, label_name_at, BibFile, BibKind},
     prelude::*,
     syntax::{get_deref_target, DerefTarget, IdentRef},
     SemanticRequest,
@@ -11,6 +12,11 @@ use crate::{
 /// given text document position.
 ///
 /// [`textDocument/references`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_references
+///
+/// The references to a label are its occurrences in the workspace, including
+/// the citations of the entries in the bibliography files. The request may be
+/// sent for a bibliography file as well, to find the citations of the entry
+/// at the position.
 #[derive(Debug, Clone)]
 pub struct ReferencesRequest {
     /// The path of the document to request for.
@@ -25,10 +31,20 @@ impl SemanticRequest for ReferencesRequest {
     fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
         let source = ctx.source_by_path(&self.path).ok()?;
         let offset = ctx.to_typst_pos(self.position, &source)?;
-        let cursor = offset + 1;
 
+        if let Some(kind) = BibKind::from_path(&self.path) {
+            let file = BibFile::parse(kind, source.text());
+            let key = file.key_at(offset)?;
+            return Some(find_label_references(ctx, &key.name));
+        }
+
+        let cursor = offset + 1;
         let ast_node = LinkedNode::new(source.root()).leaf_at(cursor)?;
         debug!("ast_node: {ast_node:?}", ast_node = ast_node);
+        if let Some((name, _)) = label_name_at(&ast_node) {
+            return Some(find_label_references(ctx, &name));
+        }
+
         let deref_target = get_deref_target(ast_node, cursor)?;
 
         let def_use = ctx.def_use(source.clone())?;
@@ -39,6 +55,33 @@ impl SemanticRequest for ReferencesRequest {
     }
 }
 
+/// Finds the occurrences of a label in the workspace, i.e. the labels, the
+/// references, and the citations, along with the entries of the same key in the
+/// bibliography files.
+fn find_label_references(ctx: &mut AnalysisContext, name: &str) -> Vec<LspLocation> {
+    let mut locations = vec![];
+    let mut push = |ctx: &AnalysisContext, fid, source: &Source, range| {
+        let uri = path_to_url(&ctx.path_for_id(fid).ok()?).ok()?;
+        locations.push(LspLocation {
+            uri,
+            range: ctx.to_lsp_range(range, source),
+        });
+        Some(())
+    };
+
+    for (fid, range) in find_label_occurrences(ctx, name) {
+        let Ok(source) = ctx.source_by_id(fid) else {
+            continue;
+        };
+        push(ctx, fid, &source, range);
+    }
+    for (fid, source, range) in find_bibliography_keys(ctx, name) {
+        push(ctx, fid, &source, range);
+    }
+
+    locations
+}
+
 pub(crate) fn find_references(
     ctx: &mut AnalysisContext<'_>,
     def_use: Arc<crate::analysis::DefUseInfo>,
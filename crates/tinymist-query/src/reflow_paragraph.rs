@@ -0,0 +1,283 @@
+use std::ops::Range;
+
+use lsp_types::TextEdit;
+use typst::syntax::SyntaxNode;
+
+use crate::{prelude::*, SyntaxRequest};
+
+/// A request to reflow the paragraph at a position, i.e. to hard-wrap its
+/// lines to a column, joining the lines which are too short.
+///
+/// The paragraph is reflowed on the syntax tree, so that the raw texts, the
+/// equations, the links, and the embedded code are never broken, the line
+/// comments and the line breaks still end their lines, and no line starts
+/// with a word that would be parsed as a list, enum, or heading marker.
+#[derive(Debug, Clone)]
+pub struct ReflowParagraphRequest {
+    /// The path of the document to reflow.
+    pub path: PathBuf,
+    /// The position in the paragraph to reflow.
+    pub position: LspPosition,
+    /// The maximum number of characters per line, which is exceeded only by
+    /// the unbreakable pieces.
+    pub width: u32,
+}
+
+impl SyntaxRequest for ReflowParagraphRequest {
+    type Response = Vec<TextEdit>;
+
+    fn request(
+        self,
+        source: &Source,
+        position_encoding: PositionEncoding,
+    ) -> Option<Self::Response> {
+        let offset = lsp_to_typst::position(self.position, position_encoding, source)?;
+        let (range, reflowed) = reflow_paragraph(source, offset, self.width as usize)?;
+        if source.text()[range.clone()] == reflowed {
+            return Some(vec![]);
+        }
+
+        Some(vec![TextEdit {
+            range: typst_to_lsp::range(range, source, position_encoding),
+            new_text: reflowed,
+        }])
+    }
+}
+
+/// A piece of a paragraph.
+#[derive(Debug, PartialEq, Eq)]
+enum Piece {
+    /// A word, which is never broken.
+    Word(String),
+    /// A space, where the line may be broken.
+    Space,
+    /// The end of a line, which is kept.
+    Newline,
+}
+
+/// Reflows the paragraph containing an offset, returning its range and the
+/// reflowed text.
+fn reflow_paragraph(
+    source: &Source,
+    offset: usize,
+    width: usize,
+) -> Option<(Range<usize>, String)> {
+    let root = LinkedNode::new(source.root());
+    let markup = paragraph_container(root.leaf_at(offset)?)?;
+    let children = markup.children().collect::<Vec<_>>();
+
+    // The paragraphs are separated by the blocks, e.g. headings and list items.
+    let text = source.text();
+    let mut start = 0;
+    let mut paragraph = None;
+    for i in 0..=children.len() {
+        if i < children.len() && !is_block(text, &children, i) {
+            continue;
+        }
+        let run = &children[start..i];
+        start = i + 1;
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            continue;
+        };
+        if first.offset() <= offset && offset <= last.range().end {
+            paragraph = Some(run);
+            break;
+        }
+    }
+
+    // The spaces around the paragraph are kept.
+    let mut paragraph = paragraph?;
+    while paragraph.first()?.kind() == SyntaxKind::Space {
+        paragraph = &paragraph[1..];
+    }
+    while paragraph.last()?.kind() == SyntaxKind::Space {
+        paragraph = &paragraph[..paragraph.len() - 1];
+    }
+    let range = paragraph.first()?.offset()..paragraph.last()?.range().end;
+
+    let mut pieces = vec![];
+    for node in paragraph {
+        collect_pieces(node.get(), &mut pieces);
+    }
+
+    // The lines after the first one are aligned with the item body, or
+    // indented as the first line.
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = if matches!(
+        markup.parent_kind(),
+        Some(SyntaxKind::ListItem | SyntaxKind::EnumItem)
+    ) {
+        let column = text[line_start..markup.offset()].chars().count();
+        " ".repeat(column)
+    } else {
+        let line = &text[line_start..];
+        line[..line.len() - line.trim_start().len()].to_owned()
+    };
+    let first_column = text[line_start..range.start].chars().count();
+
+    Some((range, fill_lines(&pieces, first_column, &indent, width)))
+}
+
+/// Finds the markup containing the paragraphs around a leaf, i.e. the markup
+/// of a document, a content block, or a list or enum item.
+fn paragraph_container(leaf: LinkedNode) -> Option<LinkedNode> {
+    let mut node = leaf;
+    loop {
+        if node.kind() == SyntaxKind::Markup {
+            match node.parent_kind() {
+                None
+                | Some(SyntaxKind::ContentBlock | SyntaxKind::ListItem | SyntaxKind::EnumItem) => {
+                    return Some(node);
+                }
+                Some(SyntaxKind::Heading | SyntaxKind::TermItem) => return None,
+                _ => {}
+            }
+        }
+        node = node.parent()?.clone();
+    }
+}
+
+/// Checks whether a child of markup is a block, which separates paragraphs.
+/// The embedded code at the start of a line is a block as well.
+fn is_block(text: &str, children: &[LinkedNode], i: usize) -> bool {
+    let at_line_start = |node: &LinkedNode| {
+        let line_start = text[..node.offset()].rfind('\n').map_or(0, |i| i + 1);
+        text[line_start..node.offset()].trim().is_empty()
+    };
+    let child = &children[i];
+    match child.kind() {
+        SyntaxKind::Parbreak
+        | SyntaxKind::Heading
+        | SyntaxKind::ListItem
+        | SyntaxKind::EnumItem
+        | SyntaxKind::TermItem => true,
+        SyntaxKind::Raw => child.cast::<ast::Raw>().is_some_and(|raw| raw.block()),
+        SyntaxKind::Hash => at_line_start(child),
+        _ => i > 0 && children[i - 1].kind() == SyntaxKind::Hash && at_line_start(&children[i - 1]),
+    }
+}
+
+/// Collects the pieces of a node in a paragraph.
+fn collect_pieces(node: &SyntaxNode, pieces: &mut Vec<Piece>) {
+    match node.kind() {
+        SyntaxKind::Space => {
+            if pieces.last() != Some(&Piece::Newline) {
+                pieces.push(Piece::Space);
+            }
+        }
+        SyntaxKind::Strong | SyntaxKind::Emph | SyntaxKind::Markup => {
+            for child in node.children() {
+                collect_pieces(child, pieces);
+            }
+        }
+        SyntaxKind::Linebreak | SyntaxKind::LineComment => {
+            push_word(pieces, node.text());
+            pieces.push(Piece::Newline);
+        }
+        _ => push_word(pieces, &node.clone().into_text()),
+    }
+}
+
+/// Appends text to the last word, or starts a new word after a space.
+fn push_word(pieces: &mut Vec<Piece>, text: &str) {
+    match pieces.last_mut() {
+        Some(Piece::Word(word)) => word.push_str(text),
+        _ => pieces.push(Piece::Word(text.to_owned())),
+    }
+}
+
+/// Fills the lines with the pieces greedily.
+fn fill_lines(pieces: &[Piece], first_column: usize, indent: &str, width: usize) -> String {
+    let indent_width = indent.chars().count();
+    let mut res = String::new();
+    let mut column = first_column;
+    let mut line_empty = true;
+    let mut pending_space = false;
+    for piece in pieces {
+        match piece {
+            Piece::Space => pending_space = !line_empty,
+            Piece::Newline => {
+                res.push('\n');
+                res.push_str(indent);
+                column = indent_width;
+                line_empty = true;
+                pending_space = false;
+            }
+            Piece::Word(word) => {
+                let word_width = word.chars().count();
+                let fits = column + usize::from(pending_space) + word_width <= width;
+                if !line_empty && !fits && !is_marker(word) {
+                    res.push('\n');
+                    res.push_str(indent);
+                    column = indent_width;
+                } else if pending_space {
+                    res.push(' ');
+                    column += 1;
+                }
+                res.push_str(word);
+                column += word_width;
+                line_empty = false;
+                pending_space = false;
+            }
+        }
+    }
+    res.trim_end().to_owned()
+}
+
+/// Checks whether a word would be parsed as a marker at the start of a line.
+fn is_marker(word: &str) -> bool {
+    matches!(word, "-" | "+" | "/")
+        || (!word.is_empty() && word.chars().all(|c| c == '='))
+        || word
+            .strip_suffix('.')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflow(text: &str, width: usize) -> Option<String> {
+        let offset = text.find('|').unwrap();
+        let text = text.replacen('|', "", 1);
+        let source = Source::detached(&text);
+        let (range, reflowed) = reflow_paragraph(&source, offset, width)?;
+
+        let mut text = text;
+        text.replace_range(range, &reflowed);
+        Some(text)
+    }
+
+    #[test]
+    fn test_reflow_paragraph() {
+        assert_eq!(
+            reflow("The |quick brown fox jumps over the lazy dog.", 16).as_deref(),
+            Some("The quick brown\nfox jumps over\nthe lazy dog.")
+        );
+        assert_eq!(
+            reflow("The |quick\nbrown\nfox.\n\nNext paragraph.", 80).as_deref(),
+            Some("The quick brown fox.\n\nNext paragraph.")
+        );
+        // The raw texts and the equations are not broken.
+        assert_eq!(
+            reflow("See |`a b c` and $x + y$ here.", 10).as_deref(),
+            Some("See\n`a b c`\nand\n$x + y$\nhere.")
+        );
+        // The line comments and the line breaks end their lines.
+        assert_eq!(
+            reflow("One |// note\ntwo \\\nthree four", 80).as_deref(),
+            Some("One // note\ntwo \\\nthree four")
+        );
+        // The list items are aligned with their bodies.
+        assert_eq!(
+            reflow("- one |two three\n- four", 9).as_deref(),
+            Some("- one two\n  three\n- four")
+        );
+        // The markers are not moved to the start of a line.
+        assert_eq!(
+            reflow("Cost |is a - b", 10).as_deref(),
+            Some("Cost is a -\nb")
+        );
+        assert_eq!(reflow("= Head|ing text", 5), None);
+    }
+}
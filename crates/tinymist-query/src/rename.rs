@@ -1,8 +1,13 @@
+use std::ops::Range;
+
 use log::debug;
 use lsp_types::TextEdit;
 
 use crate::{
-    analysis::find_definition, find_references, prelude::*, syntax::get_deref_target,
+    analysis::{find_bibliography_keys, find_definition, find_label_occurrences, label_name_at},
+    find_references,
+    prelude::*,
+    syntax::{get_deref_target, DerefTarget},
     validate_renaming_definition, SemanticRequest,
 };
 
@@ -11,6 +16,9 @@ use crate::{
 /// a workspace-wide rename of a symbol.
 ///
 /// [`textDocument/rename`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_rename
+///
+/// Renaming a label also renames the references to it in the workspace, and
+/// the key of the bibliography entry if the label is a citation key.
 #[derive(Debug, Clone)]
 pub struct RenameRequest {
     /// The path of the document to request for.
@@ -34,6 +42,10 @@ impl SemanticRequest for RenameRequest {
         debug!("ast_node: {ast_node:?}", ast_node = ast_node);
 
         let deref_target = get_deref_target(ast_node, cursor)?;
+        if let DerefTarget::Label(node) | DerefTarget::Ref(node) = &deref_target {
+            let (name, _) = label_name_at(node)?;
+            return rename_label(ctx, &name, &self.new_name);
+        }
 
         let lnk = find_definition(ctx, source.clone(), deref_target.clone())?;
 
@@ -80,3 +92,38 @@ impl SemanticRequest for RenameRequest {
         })
     }
 }
+
+/// Renames the label `name` and the references to it, as well as the entry of
+/// the same key in the bibliography files.
+fn rename_label(ctx: &mut AnalysisContext, name: &str, new_name: &str) -> Option<WorkspaceEdit> {
+    // The markers are not part of the name, e.g. `@intro` is renamed to `intro`.
+    let new_name = new_name
+        .trim_start_matches(['@', '<'])
+        .trim_end_matches('>');
+
+    let mut editions: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    let mut push_edit = |ctx: &mut AnalysisContext, fid, source: &Source, range: Range<usize>| {
+        let path = ctx.path_for_id(fid).ok()?;
+        let uri = path_to_url(&path).ok()?;
+        editions.entry(uri).or_default().push(TextEdit {
+            range: ctx.to_lsp_range(range, source),
+            new_text: new_name.to_owned(),
+        });
+        Some(())
+    };
+
+    for (fid, range) in find_label_occurrences(ctx, name) {
+        let Ok(source) = ctx.source_by_id(fid) else {
+            continue;
+        };
+        push_edit(ctx, fid, &source, range);
+    }
+    for (fid, source, range) in find_bibliography_keys(ctx, name) {
+        push_edit(ctx, fid, &source, range);
+    }
+
+    Some(WorkspaceEdit {
+        changes: Some(editions),
+        ..Default::default()
+    })
+}
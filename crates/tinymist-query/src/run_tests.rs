@@ -0,0 +1,201 @@
+use reflexo::path::unix_slash;
+use serde::Serialize;
+
+use crate::{analysis::compile_main, convert_diagnostics, prelude::*, DiagnosticsMap};
+
+/// The glob of the test files run by default.
+pub const DEFAULT_TEST_GLOB: &str = "tests/**/*.typ";
+
+/// A request to run the document tests of the workspace, i.e. to compile the
+/// files matching a glob, each as the main file, and to collect the failures
+/// of `assert` and `panic`.
+#[derive(Debug, Clone)]
+pub struct RunTestsRequest {
+    /// The glob of the test files, relative to the root, e.g.
+    /// `tests/**/*.typ`.
+    pub glob: String,
+}
+
+/// The result of a test file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    /// The path of the test file.
+    pub path: PathBuf,
+    /// The messages of the failures, e.g. `assertion failed`.
+    pub failures: Vec<String>,
+}
+
+/// The results of the test files and the diagnostics of their failures.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReport {
+    /// The number of the passed test files.
+    pub passed: usize,
+    /// The number of the failed test files.
+    pub failed: usize,
+    /// The results of the test files, in the order of their paths.
+    pub tests: Vec<TestResult>,
+    /// The diagnostics of the failures, by the files where they occur, which
+    /// may be the files imported by the tests.
+    #[serde(skip)]
+    pub diagnostics: DiagnosticsMap,
+}
+
+impl TestReport {
+    /// Adds the result of a test file and the diagnostics of its failures.
+    pub fn push(&mut self, result: TestResult, diagnostics: DiagnosticsMap) {
+        if result.failures.is_empty() {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        for (uri, diags) in diagnostics {
+            self.diagnostics.entry(uri).or_default().extend(diags);
+        }
+        self.tests.push(result);
+    }
+}
+
+impl RunTestsRequest {
+    /// Gets the test files matching the glob, in the order of their paths.
+    pub fn test_files(&self, ctx: &AnalysisContext) -> Vec<TypstFileId> {
+        let mut files = ctx.source_files().clone();
+        files.retain(|id| glob_match(&self.glob, &unix_slash(id.vpath().as_rootless_path())));
+        files.sort_by(|a, b| a.vpath().cmp(b.vpath()));
+        files
+    }
+}
+
+impl SemanticRequest for RunTestsRequest {
+    type Response = TestReport;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let mut report = TestReport::default();
+        for id in self.test_files(ctx) {
+            if let Some((result, diagnostics)) = run_test(ctx, id) {
+                report.push(result, diagnostics);
+            }
+        }
+
+        Some(report)
+    }
+}
+
+/// Runs a test file, i.e. compiles it as the main file, returning its result
+/// and the diagnostics of its failures.
+pub fn run_test(
+    ctx: &mut AnalysisContext,
+    id: TypstFileId,
+) -> Option<(TestResult, DiagnosticsMap)> {
+    let (Ok(source), Ok(path)) = (ctx.source_by_id(id), ctx.path_for_id(id)) else {
+        return None;
+    };
+    let _scope = crate::profile::scope_with(|| format!("test {}", path.display()));
+
+    let mut diagnostics = DiagnosticsMap::default();
+    let failures = match compile_main(ctx.world(), source) {
+        Ok(..) => vec![],
+        Err(errors) => {
+            for (uri, diags) in convert_diagnostics(ctx, errors.iter()) {
+                diagnostics.entry(uri).or_default().extend(diags);
+            }
+            errors.iter().map(|err| err.message.to_string()).collect()
+        }
+    };
+
+    Some((TestResult { path, failures }, diagnostics))
+}
+
+/// Matches a path separated by `/` against a glob, where `*` matches any
+/// characters in a component, `?` matches any character, and `**` matches any
+/// components.
+fn glob_match(glob: &str, path: &str) -> bool {
+    fn components(s: &str) -> Vec<&str> {
+        s.split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect()
+    }
+
+    fn match_components(glob: &[&str], path: &[&str]) -> bool {
+        match glob.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|idx| match_components(rest, &path[idx..])),
+            Some((pat, rest)) => path.split_first().is_some_and(|(name, path)| {
+                let pat = pat.chars().collect::<Vec<_>>();
+                let name = name.chars().collect::<Vec<_>>();
+                match_name(&pat, &name) && match_components(rest, path)
+            }),
+        }
+    }
+
+    fn match_name(pat: &[char], name: &[char]) -> bool {
+        match pat.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|idx| match_name(rest, &name[idx..])),
+            Some(('?', rest)) => !name.is_empty() && match_name(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && match_name(rest, &name[1..]),
+        }
+    }
+
+    match_components(&components(glob), &components(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("tests/**/*.typ", "tests/a.typ"));
+        assert!(glob_match("tests/**/*.typ", "tests/unit/b.typ"));
+        assert!(glob_match("./tests/*.typ", "tests/a.typ"));
+        assert!(glob_match("**/test-?.typ", "src/test-1.typ"));
+        assert!(!glob_match("tests/**/*.typ", "src/a.typ"));
+        assert!(!glob_match("tests/*.typ", "tests/unit/b.typ"));
+        assert!(!glob_match("tests/*.typ", "tests/a.bib"));
+    }
+
+    #[test]
+    fn test_glob_match_components() {
+        // `**` matches no component as well as many.
+        assert!(glob_match("**", "a/b/c.typ"));
+        assert!(glob_match("a/**/c.typ", "a/c.typ"));
+        assert!(glob_match("a/**/c.typ", "a/b/b/c.typ"));
+        // `*` and `?` don't match across the components.
+        assert!(!glob_match("a*c.typ", "a/c.typ"));
+        assert!(!glob_match("a?c.typ", "a/c.typ"));
+        assert!(glob_match("test-*-?.typ", "test-abc-1.typ"));
+        assert!(!glob_match("test-?.typ", "test-.typ"));
+    }
+
+    #[test]
+    fn test() {
+        snapshot_testing("run_tests", &|ctx, _| {
+            let request = RunTestsRequest {
+                glob: DEFAULT_TEST_GLOB.to_owned(),
+            };
+            let report = request.request(ctx).unwrap();
+
+            let name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+            let mut lines = vec![format!(
+                "passed: {}, failed: {}",
+                report.passed, report.failed
+            )];
+            for test in &report.tests {
+                let result = match test.failures.as_slice() {
+                    [] => "passed".to_owned(),
+                    failures => failures.join("; "),
+                };
+                lines.push(format!("{}: {result}", name(&test.path)));
+            }
+            let files = report.diagnostics.keys();
+            let files = files
+                .map(|uri| name(Path::new(uri.path())))
+                .collect::<Vec<_>>();
+            lines.push(format!("diagnostics: {}", files.join(", ")));
+            assert_snapshot!(lines.join("\n"));
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use crate::{prelude::*, SyntaxRequest};
 
 /// The [`textDocument/selectionRange`] request is sent from the client to the
@@ -11,6 +13,16 @@ use crate::{prelude::*, SyntaxRequest};
 /// parameters at the same index. Therefore `params.positions[i]` must be
 /// contained in `result[i].range`.
 ///
+/// The selection ranges are the ranges of the syntax nodes enclosing the
+/// position, skipping the nodes with the same range as their children. Besides
+/// the nodes, a range is expanded to:
+/// - the content between the delimiters of strings, arguments, parameters,
+///   arrays, dictionaries, and parenthesized expressions, e.g. `a, b` in `f(a,
+///   b)`,
+/// - a script of a math attachment with its marker, e.g. `_(i)` in `x_(i)^2`,
+/// - the paragraph in markup, i.e. the content between the paragraph breaks,
+///   headings, and list items.
+///
 /// # Compatibility
 ///
 /// This request was introduced in specification version 3.15.0.
@@ -35,7 +47,7 @@ impl SyntaxRequest for SelectionRangeRequest {
             let typst_offset = lsp_to_typst::position(position, position_encoding, source)?;
             let tree = LinkedNode::new(source.root());
             let leaf = tree.leaf_at(typst_offset + 1)?;
-            ranges.push(range_for_node(source, position_encoding, &leaf));
+            ranges.push(range_for_node(source, position_encoding, &leaf)?);
         }
 
         Some(ranges)
@@ -46,12 +58,147 @@ fn range_for_node(
     source: &Source,
     position_encoding: PositionEncoding,
     node: &LinkedNode,
-) -> SelectionRange {
-    let range = typst_to_lsp::range(node.range(), source, position_encoding);
-    SelectionRange {
-        range,
-        parent: node
-            .parent()
-            .map(|node| Box::new(range_for_node(source, position_encoding, node))),
+) -> Option<SelectionRange> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut push_range = |range: Range<usize>| {
+        // Each range must be strictly larger than the previous one.
+        let expands = ranges.last().map_or(true, |last| {
+            range != *last && range.start <= last.start && last.end <= range.end
+        });
+        if expands {
+            ranges.push(range);
+        }
+    };
+
+    let mut node = Some(node.clone());
+    while let Some(n) = node {
+        if let Some(inner) = inner_range(&n) {
+            push_range(inner);
+        }
+        push_range(n.range());
+        if let Some(script) = attach_script_range(&n) {
+            push_range(script);
+        }
+        if let Some(paragraph) = paragraph_range(&n) {
+            push_range(paragraph);
+        }
+        node = n.parent().cloned();
+    }
+
+    ranges.into_iter().rev().fold(None, |parent, range| {
+        Some(SelectionRange {
+            range: typst_to_lsp::range(range, source, position_encoding),
+            parent: parent.map(Box::new),
+        })
+    })
+}
+
+/// Gets the range between the delimiters of a node, e.g. `a, b` in `(a, b)`.
+fn inner_range(node: &LinkedNode) -> Option<Range<usize>> {
+    match node.kind() {
+        SyntaxKind::Str => {
+            let range = node.range();
+            (range.len() >= 2).then(|| range.start + 1..range.end - 1)
+        }
+        SyntaxKind::Args
+        | SyntaxKind::Params
+        | SyntaxKind::Array
+        | SyntaxKind::Dict
+        | SyntaxKind::Parenthesized
+        | SyntaxKind::Destructuring => {
+            // The trailing content blocks of arguments are not in the parens.
+            let mut children = node.children();
+            let open = children.find(|n| n.kind() == SyntaxKind::LeftParen)?;
+            let close = children.find(|n| n.kind() == SyntaxKind::RightParen)?;
+            Some(open.range().end..close.offset())
+        }
+        _ => None,
+    }
+}
+
+/// Gets the range of a script in a math attachment with its marker, e.g.
+/// `^2` in `x^2`.
+fn attach_script_range(node: &LinkedNode) -> Option<Range<usize>> {
+    if node.parent_kind() != Some(SyntaxKind::MathAttach) {
+        return None;
+    }
+
+    let marker = node.prev_sibling()?;
+    if !matches!(marker.kind(), SyntaxKind::Underscore | SyntaxKind::Hat) {
+        return None;
+    }
+
+    Some(marker.offset()..node.range().end)
+}
+
+/// Gets the range of the paragraph containing a node in markup, i.e. the
+/// siblings between the paragraph breaks, headings, and list items.
+fn paragraph_range(node: &LinkedNode) -> Option<Range<usize>> {
+    let is_break = |n: &LinkedNode| {
+        matches!(
+            n.kind(),
+            SyntaxKind::Parbreak
+                | SyntaxKind::Heading
+                | SyntaxKind::ListItem
+                | SyntaxKind::EnumItem
+                | SyntaxKind::TermItem
+        )
+    };
+
+    let parent = node.parent()?;
+    if parent.kind() != SyntaxKind::Markup || is_break(node) {
+        return None;
+    }
+
+    let siblings = parent.children().collect::<Vec<_>>();
+    let index = node.index();
+    let start = siblings[..index]
+        .iter()
+        .rposition(is_break)
+        .map_or(0, |i| i + 1);
+    let end = siblings[index..]
+        .iter()
+        .position(is_break)
+        .map_or(siblings.len(), |i| index + i);
+
+    let paragraph = &siblings[start..end];
+    let is_content = |n: &&LinkedNode| n.kind() != SyntaxKind::Space;
+    let first = paragraph.iter().find(is_content)?;
+    let last = paragraph.iter().rfind(is_content)?;
+    Some(first.offset()..last.range().end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test() {
+        snapshot_testing("selection_range", &|world, path| {
+            let source = world.source_by_path(&path).unwrap();
+
+            let request = SelectionRangeRequest {
+                path: path.clone(),
+                positions: vec![find_test_position(&source)],
+            };
+
+            let result = request.request(&source, PositionEncoding::Utf16);
+            let result = result.map(|ranges| {
+                let mut chain = vec![];
+                let mut range = ranges.into_iter().next();
+                while let Some(r) = range {
+                    let rng = r.range;
+                    chain.push(format!(
+                        "{}:{}:{}:{}",
+                        rng.start.line, rng.start.character, rng.end.line, rng.end.character
+                    ));
+                    range = r.parent.map(|parent| *parent);
+                }
+                chain
+            });
+
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
     }
 }
@@ -1,38 +1,82 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use lsp_types::{SemanticToken, SemanticTokensEdit};
 
+use crate::LspPosition;
+
+/// The tokens last sent for a document.
 #[derive(Debug)]
-struct CachedTokens {
-    tokens: Vec<SemanticToken>,
+pub struct CachedTokens {
+    /// The tokens, encoded relative to each other.
+    pub tokens: Vec<SemanticToken>,
+    /// The absolute starting positions of the tokens, indexing the tokens for
+    /// range requests.
+    pub positions: Vec<LspPosition>,
+    /// The hash of the source text the tokens are computed from.
+    pub version: u128,
     id: u64,
 }
 
+/// The tokens last sent for each document, identified by result ids.
 #[derive(Default, Debug)]
 pub struct CacheInner {
-    last_sent: Option<CachedTokens>,
+    docs: HashMap<PathBuf, CachedTokens>,
     next_id: u64,
 }
 
 impl CacheInner {
-    pub fn try_take_result(&mut self, id: &str) -> Option<Vec<SemanticToken>> {
+    /// Gets the tokens of a document computed from the given version of its
+    /// source.
+    pub fn get_by_version(&self, path: &Path, version: u128) -> Option<&CachedTokens> {
+        self.docs
+            .get(path)
+            .filter(|cached| cached.version == version)
+    }
+
+    /// Gets the tokens of a document sent with the given result id.
+    pub fn try_get_result(&self, path: &Path, id: &str) -> Option<Vec<SemanticToken>> {
         let id = id.parse::<u64>().ok()?;
-        match self.last_sent.take() {
-            Some(cached) if cached.id == id => Some(cached.tokens),
-            Some(cached) => {
-                // replace after taking
-                self.last_sent = Some(cached);
-                None
+        let cached = self.docs.get(path)?;
+        (cached.id == id).then(|| cached.tokens.clone())
+    }
+
+    /// Caches the tokens of a document, returning their result id.
+    ///
+    /// The result id is kept if the tokens are unchanged, so that it stays
+    /// stable across requests.
+    pub fn cache_result(
+        &mut self,
+        path: &Path,
+        version: u128,
+        tokens: Vec<SemanticToken>,
+    ) -> String {
+        if let Some(cached) = self.docs.get_mut(path) {
+            if cached.tokens == tokens {
+                cached.version = version;
+                return cached.id.to_string();
             }
-            None => None,
         }
-    }
 
-    pub fn cache_result(&mut self, tokens: Vec<SemanticToken>) -> String {
         let id = self.get_next_id();
-        let cached = CachedTokens { tokens, id };
-        self.last_sent = Some(cached);
+        let positions = token_positions(&tokens);
+        self.docs.insert(
+            path.to_owned(),
+            CachedTokens {
+                tokens,
+                positions,
+                version,
+                id,
+            },
+        );
         id.to_string()
     }
 
+    /// Removes the tokens of a document.
+    pub fn remove(&mut self, path: &Path) {
+        self.docs.remove(path);
+    }
+
     fn get_next_id(&mut self) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
@@ -40,6 +84,42 @@ impl CacheInner {
     }
 }
 
+/// Decodes the absolute starting positions of relatively encoded tokens.
+fn token_positions(tokens: &[SemanticToken]) -> Vec<LspPosition> {
+    let mut pos = LspPosition::new(0, 0);
+    tokens
+        .iter()
+        .map(|token| {
+            if token.delta_line == 0 {
+                pos.character += token.delta_start;
+            } else {
+                pos.line += token.delta_line;
+                pos.character = token.delta_start;
+            }
+            pos
+        })
+        .collect()
+}
+
+/// Selects the tokens starting in a range, re-encoding the first token
+/// relative to the start of the document.
+pub fn token_range(
+    cached: &CachedTokens,
+    start: LspPosition,
+    end: LspPosition,
+) -> Vec<SemanticToken> {
+    let first = cached.positions.partition_point(|pos| *pos < start);
+    let last = cached.positions.partition_point(|pos| *pos < end);
+
+    let mut tokens = cached.tokens[first..last].to_vec();
+    if let Some(token) = tokens.first_mut() {
+        let pos = cached.positions[first];
+        token.delta_line = pos.line;
+        token.delta_start = pos.character;
+    }
+    tokens
+}
+
 pub fn token_delta(from: &[SemanticToken], to: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
     // Taken from `rust-analyzer`'s algorithm
     // https://github.com/rust-lang/rust-analyzer/blob/master/crates/rust-analyzer/src/semantic_tokens.rs#L219
@@ -73,3 +153,40 @@ pub fn token_delta(from: &[SemanticToken], to: &[SemanticToken]) -> Vec<Semantic
         }]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(delta_line: u32, delta_start: u32, length: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    #[test]
+    fn test_token_range() {
+        let tokens = vec![
+            token(0, 0, 1),
+            token(0, 2, 3),
+            token(1, 4, 1),
+            token(2, 0, 2),
+        ];
+        let cached = CachedTokens {
+            positions: token_positions(&tokens),
+            tokens,
+            version: 0,
+            id: 0,
+        };
+
+        let range = token_range(&cached, LspPosition::new(0, 1), LspPosition::new(1, 5));
+        assert_eq!(range, vec![token(0, 2, 3), token(1, 4, 1)]);
+
+        let range = token_range(&cached, LspPosition::new(1, 0), LspPosition::new(9, 0));
+        assert_eq!(range, vec![token(1, 4, 1), token(2, 0, 2)]);
+    }
+}
@@ -0,0 +1,337 @@
+//! Classification of identifiers by the kind of the items they resolve to.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+use reflexo::vector::ir::DefId;
+use typst::foundations::Value;
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+
+use super::is_function_ident;
+use super::modifier_set::ModifierSet;
+use super::typst_tokens::{Modifier, TokenType};
+use crate::analysis::{find_deprecation, AnalysisContext, DefUseInfo, FlowType};
+use crate::syntax::{IdentRef, LexicalKind, LexicalModKind, LexicalVarKind};
+
+/// The methods mutating their receiver in place.
+const MUTATING_METHODS: &[&str] = &["push", "pop", "insert", "remove"];
+
+/// The token of a resolved identifier.
+#[derive(Clone, Copy)]
+pub(super) struct IdentToken {
+    pub token_type: TokenType,
+    pub modifiers: ModifierSet,
+}
+
+/// The tokens of the identifiers in a source, keyed by their ranges.
+pub(super) type IdentTokens = HashMap<Range<usize>, IdentToken>;
+
+/// Resolves the identifiers in a source to their kinds, i.e. function,
+/// variable, module, element, or symbol.
+///
+/// Identifiers that cannot be resolved are left out, and tokenized by their
+/// syntax only.
+pub(super) fn resolve_idents(ctx: &mut AnalysisContext, source: &Source) -> IdentTokens {
+    let def_use = ctx.def_use(source.clone());
+    let undefined = def_use
+        .iter()
+        .flat_map(|def_use| def_use.undefined_refs.iter().cloned())
+        .collect();
+    let local_names = def_use
+        .iter()
+        .flat_map(|def_use| def_use.ident_defs.values().map(|def| def.name.clone()))
+        .collect();
+
+    let mut resolver = IdentResolver {
+        version: ctx.typst_version(),
+        source,
+        ctx,
+        def_use,
+        undefined,
+        local_names,
+        mutated: HashSet::new(),
+        tokens: HashMap::new(),
+    };
+
+    let root = LinkedNode::new(source.root());
+    resolver.collect_mutated(&root);
+    resolver.resolve(&root);
+    resolver.tokens
+}
+
+struct IdentResolver<'a, 'w> {
+    ctx: &'a mut AnalysisContext<'w>,
+    source: &'a Source,
+    def_use: Option<Arc<DefUseInfo>>,
+    /// The references to no local definition.
+    undefined: HashSet<IdentRef>,
+    /// The names of the local definitions, which may shadow the items of the
+    /// library in math.
+    local_names: HashSet<String>,
+    version: (u32, u32),
+    /// The definitions assigned or mutated by a method after their
+    /// declarations.
+    mutated: HashSet<DefId>,
+    tokens: IdentTokens,
+}
+
+impl IdentResolver<'_, '_> {
+    fn collect_mutated(&mut self, node: &LinkedNode) {
+        match node.kind() {
+            SyntaxKind::Binary => {
+                let assigned = node.cast::<ast::Binary>().is_some_and(|binary| {
+                    matches!(
+                        binary.op(),
+                        ast::BinOp::Assign
+                            | ast::BinOp::AddAssign
+                            | ast::BinOp::SubAssign
+                            | ast::BinOp::MulAssign
+                            | ast::BinOp::DivAssign
+                    )
+                });
+                if assigned {
+                    if let Some(lhs) = node.children().next() {
+                        self.mark_mutated(&lhs);
+                    }
+                }
+            }
+            SyntaxKind::DestructAssignment => {
+                if let Some(pattern) = node.children().next() {
+                    self.mark_bindings_mutated(&pattern);
+                }
+            }
+            SyntaxKind::FuncCall => {
+                let callee = node.children().next();
+                let method = callee
+                    .as_ref()
+                    .and_then(|callee| callee.cast::<ast::FieldAccess>());
+                if let (Some(callee), Some(method)) = (&callee, method) {
+                    if MUTATING_METHODS.contains(&method.field().as_str()) {
+                        if let Some(target) = callee.children().next() {
+                            self.mark_mutated(&target);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children() {
+            self.collect_mutated(&child);
+        }
+    }
+
+    /// Marks the variable at the root of an assigned expression, e.g. `a` in
+    /// `a.b.at(0) = 1`.
+    fn mark_mutated(&mut self, node: &LinkedNode) {
+        match node.kind() {
+            SyntaxKind::Ident => {
+                if let Some(id) = self.def_id(node) {
+                    self.mutated.insert(id);
+                }
+            }
+            SyntaxKind::FieldAccess | SyntaxKind::FuncCall | SyntaxKind::Parenthesized => {
+                let target = node
+                    .children()
+                    .find(|child| child.cast::<ast::Expr>().is_some());
+                if let Some(target) = target {
+                    self.mark_mutated(&target);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn mark_bindings_mutated(&mut self, node: &LinkedNode) {
+        if node.kind() == SyntaxKind::Ident {
+            self.mark_mutated(node);
+            return;
+        }
+        for child in node.children() {
+            self.mark_bindings_mutated(&child);
+        }
+    }
+
+    fn resolve(&mut self, node: &LinkedNode) {
+        if matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent) {
+            if let Some(token) = self.resolve_ident(node) {
+                self.tokens.insert(node.range(), token);
+            }
+            return;
+        }
+
+        for child in node.children() {
+            self.resolve(&child);
+        }
+    }
+
+    fn resolve_ident(&mut self, node: &LinkedNode) -> Option<IdentToken> {
+        let parent = node.parent()?;
+        if parent.kind() == SyntaxKind::FieldAccess && node.prev_sibling().is_some() {
+            return self.resolve_global_field(parent);
+        }
+        if !self.is_undefined(node) {
+            return self.resolve_local(node);
+        }
+        self.resolve_global(node)
+    }
+
+    fn resolve_local(&mut self, node: &LinkedNode) -> Option<IdentToken> {
+        let def_use = self.def_use.clone()?;
+        let ident = self.ident_ref(node);
+        let (id, fid, def) = match def_use.get_def(self.source.id(), &ident) {
+            Some((id, def)) => (id, self.source.id(), def),
+            None => {
+                let id = def_use.get_ref(&ident)?;
+                let (fid, def) = def_use.get_def_by_id(id)?;
+                (id, fid, def)
+            }
+        };
+
+        let token_type = match &def.kind {
+            LexicalKind::Var(LexicalVarKind::Function) => TokenType::Function,
+            LexicalKind::Var(LexicalVarKind::Variable) => {
+                let def_node = (fid == self.source.id())
+                    .then(|| LinkedNode::new(self.source.root()).leaf_at(def.range.start + 1))
+                    .flatten();
+                match def_node.and_then(|def_node| self.def_value_type(&def_node)) {
+                    Some(token_type) => token_type,
+                    None => TokenType::Variable,
+                }
+            }
+            LexicalKind::Mod(
+                LexicalModKind::Module(..) | LexicalModKind::ModuleAlias | LexicalModKind::PathVar,
+            ) => TokenType::Namespace,
+            LexicalKind::Mod(LexicalModKind::Ident | LexicalModKind::Alias { .. }) => {
+                if is_function_ident(node) {
+                    TokenType::Function
+                } else {
+                    TokenType::Variable
+                }
+            }
+            _ => return None,
+        };
+
+        let modifiers = if token_type == TokenType::Variable && !self.mutated.contains(&id) {
+            ModifierSet::new(&[Modifier::Readonly])
+        } else {
+            ModifierSet::empty()
+        };
+        Some(IdentToken {
+            token_type,
+            modifiers,
+        })
+    }
+
+    /// Distinguishes variables bound to functions by their inferred types.
+    fn def_value_type(&mut self, def_node: &LinkedNode) -> Option<TokenType> {
+        let ty = self.ctx.type_of_span(def_node.span())?;
+        let ty_chk = self.ctx.type_check(self.source.clone())?;
+        match ty_chk.simplify(ty, true) {
+            FlowType::Func(..) | FlowType::With(..) => Some(TokenType::Function),
+            FlowType::Value(v) => match &v.0 {
+                Value::Func(..) | Value::Type(..) => Some(value_token_type(&v.0)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn resolve_global(&self, node: &LinkedNode) -> Option<IdentToken> {
+        let is_math = node.kind() == SyntaxKind::MathIdent;
+        let lib = self.ctx.world().library();
+        let scope = if is_math {
+            lib.math.scope()
+        } else {
+            lib.global.scope()
+        };
+        let value = scope.get(node.text())?;
+
+        let mut token = library_token(value);
+        if !is_math && find_deprecation(node.text(), self.version).is_some() {
+            token.modifiers = token.modifiers | ModifierSet::new(&[Modifier::Deprecated]);
+        }
+        Some(token)
+    }
+
+    /// Resolves the field of an item of the library, e.g. `max` in `calc.max`.
+    fn resolve_global_field(&self, access_node: &LinkedNode) -> Option<IdentToken> {
+        let access = access_node.cast::<ast::FieldAccess>()?;
+        let (target, is_math) = match access.target() {
+            ast::Expr::Ident(target) => (target.get().clone(), false),
+            ast::Expr::MathIdent(target) => (target.get().clone(), true),
+            _ => return None,
+        };
+
+        let target_node = access_node.children().next()?;
+        if !self.is_undefined(&target_node) {
+            return None;
+        }
+
+        let lib = self.ctx.world().library();
+        let scope = if is_math {
+            lib.math.scope()
+        } else {
+            lib.global.scope()
+        };
+        let field = access.field();
+        let value = match scope.get(&target)? {
+            Value::Module(module) => module.field(&field).ok()?.clone(),
+            Value::Func(func) => func.field(&field).ok()?.clone(),
+            Value::Symbol(symbol) => Value::Symbol(symbol.clone().modified(&field).ok()?),
+            _ => return None,
+        };
+        Some(library_token(&value))
+    }
+
+    /// Whether an identifier refers to no local definition.
+    ///
+    /// Identifiers in math are not tracked by the def-use analysis, so they
+    /// are assumed to be undefined unless a local definition has the same
+    /// name.
+    fn is_undefined(&self, node: &LinkedNode) -> bool {
+        if self.def_use.is_none() {
+            return true;
+        }
+        if node.kind() == SyntaxKind::MathIdent {
+            return !self.local_names.contains(node.text().as_str());
+        }
+        self.undefined.contains(&self.ident_ref(node))
+    }
+
+    fn def_id(&self, node: &LinkedNode) -> Option<DefId> {
+        self.def_use.as_ref()?.get_ref(&self.ident_ref(node))
+    }
+
+    fn ident_ref(&self, node: &LinkedNode) -> IdentRef {
+        IdentRef {
+            name: node.text().to_string(),
+            range: node.range(),
+        }
+    }
+}
+
+/// The token of an item of the library, which cannot be reassigned.
+fn library_token(value: &Value) -> IdentToken {
+    let token_type = value_token_type(value);
+    let modifiers = if token_type == TokenType::Variable {
+        ModifierSet::new(&[Modifier::DefaultLibrary, Modifier::Readonly])
+    } else {
+        ModifierSet::new(&[Modifier::DefaultLibrary])
+    };
+    IdentToken {
+        token_type,
+        modifiers,
+    }
+}
+
+fn value_token_type(value: &Value) -> TokenType {
+    match value {
+        Value::Func(func) if func.element().is_some() => TokenType::Element,
+        Value::Func(..) | Value::Type(..) => TokenType::Function,
+        Value::Module(..) => TokenType::Namespace,
+        Value::Symbol(..) => TokenType::Symbol,
+        _ => TokenType::Variable,
+    }
+}
@@ -1,25 +1,53 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use lsp_types::{SemanticToken, SemanticTokensEdit};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use reflexo::hash::hash128;
 use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
 
+use crate::analysis::AnalysisContext;
 use crate::{LspPosition, PositionEncoding};
 
-use self::delta::token_delta;
+use self::delta::{token_delta, token_range};
+use self::idents::{resolve_idents, IdentTokens};
 use self::modifier_set::ModifierSet;
 
 use self::delta::CacheInner as TokenCacheInner;
 
 mod delta;
+mod idents;
 mod modifier_set;
 mod typst_tokens;
 pub use self::typst_tokens::{Modifier, TokenType};
 
+/// The identifiers of a source classified by the items they resolve to.
+struct ResolvedIdents {
+    /// The source the identifiers are resolved in.
+    source: Source,
+    /// The hash of the text of the source.
+    version: u128,
+    /// Increased whenever the identifiers are resolved again.
+    revision: u64,
+    tokens: Arc<IdentTokens>,
+}
+
 /// A semantic token context providing incremental semantic tokens rendering.
+///
+/// The tokens are computed from the syntax of a source, so that they are
+/// available without waiting for the compiler. The identifiers are classified
+/// separately by [`SemanticTokenContext::resolve_idents`], which needs the
+/// analysis of the source, and the classification is reused until the source
+/// is resolved again. Both are kept until the source is evicted by
+/// [`SemanticTokenContext::evict`], e.g. when the document is closed.
 #[derive(Default)]
 pub struct SemanticTokenContext {
     cache: RwLock<TokenCacheInner>,
+    idents: RwLock<HashMap<PathBuf, ResolvedIdents>>,
+    /// The paths of the sources whose identifiers are being resolved.
+    resolving: Mutex<HashSet<PathBuf>>,
     position_encoding: PositionEncoding,
     /// Whether to allow overlapping tokens.
     pub allow_overlapping_token: bool,
@@ -36,38 +64,164 @@ impl SemanticTokenContext {
     ) -> Self {
         Self {
             cache: RwLock::new(TokenCacheInner::default()),
+            idents: RwLock::new(HashMap::new()),
+            resolving: Mutex::new(HashSet::new()),
             position_encoding,
             allow_overlapping_token,
             allow_multiline_token,
         }
     }
 
+    /// Classifies the identifiers of the source at a path by the items they
+    /// resolve to, which needs the def-use and type information of the
+    /// source.
+    ///
+    /// Returns whether the identifiers are resolved again, i.e. the source is
+    /// changed since it was last resolved.
+    pub fn resolve_idents(&self, ctx: &mut AnalysisContext, path: &Path) -> bool {
+        self.resolving.lock().remove(path);
+        let Ok(source) = ctx.source_by_path(path) else {
+            return false;
+        };
+        let version = hash128(source.text());
+        let revision = match self.idents.read().get(path) {
+            Some(resolved) if resolved.version == version => return false,
+            Some(resolved) => resolved.revision + 1,
+            None => 0,
+        };
+
+        let tokens = Arc::new(resolve_idents(ctx, &source));
+        let resolved = ResolvedIdents {
+            source,
+            version,
+            revision,
+            tokens,
+        };
+        self.idents.write().insert(path.to_owned(), resolved);
+        true
+    }
+
+    /// Classifies the identifiers of the source at a path like
+    /// [`Self::resolve_idents`], if they are scheduled by
+    /// [`Self::schedule_resolving`] and not evicted since then.
+    pub fn resolve_scheduled_idents(&self, ctx: &mut AnalysisContext, path: &Path) -> bool {
+        if !self.resolving.lock().contains(path) {
+            return false;
+        }
+        self.resolve_idents(ctx, path)
+    }
+
+    /// Evicts the tokens and the classified identifiers of a source, e.g.
+    /// when the document is closed.
+    pub fn evict(&self, path: &Path) {
+        self.cache.write().remove(path);
+        self.idents.write().remove(path);
+        self.resolving.lock().remove(path);
+    }
+
+    /// Checks whether the identifiers of a source are resolved in another
+    /// version of the source, or not resolved at all.
+    pub fn idents_outdated(&self, path: &Path, source: &Source) -> bool {
+        self.idents_revision(path, source).is_none()
+    }
+
+    /// Checks whether the identifiers of a source should be resolved, i.e.
+    /// they are outdated and not being resolved yet, marking them as being
+    /// resolved if so.
+    pub fn schedule_resolving(&self, path: &Path, source: &Source) -> bool {
+        self.idents_outdated(path, source) && self.resolving.lock().insert(path.to_owned())
+    }
+
+    /// Gets the revision of the classified identifiers of a source, if they
+    /// are resolved in the same version of the source.
+    fn idents_revision(&self, path: &Path, source: &Source) -> Option<u64> {
+        let idents = self.idents.read();
+        let resolved = idents.get(path)?;
+        (resolved.version == hash128(source.text())).then_some(resolved.revision)
+    }
+
+    /// Gets the classified identifiers of a source and the revision of the
+    /// classification.
+    ///
+    /// If the source is edited since it was resolved, the identifiers out of
+    /// the edited range are reused at their shifted ranges.
+    fn idents(&self, path: &Path, source: &Source) -> (Arc<IdentTokens>, Option<u64>) {
+        let idents = self.idents.read();
+        let Some(resolved) = idents.get(path) else {
+            return (Arc::default(), None);
+        };
+        if resolved.version == hash128(source.text()) {
+            return (resolved.tokens.clone(), Some(resolved.revision));
+        }
+
+        let tokens = shift_idents(&resolved.tokens, resolved.source.text(), source.text());
+        (Arc::new(tokens), None)
+    }
+
     /// Get the semantic tokens for a source.
-    pub fn get_semantic_tokens_full(&self, source: &Source) -> (Vec<SemanticToken>, String) {
+    pub fn get_semantic_tokens_full(
+        &self,
+        path: &Path,
+        source: &Source,
+    ) -> (Vec<SemanticToken>, String) {
         let root = LinkedNode::new(source.root());
 
+        let (idents, revision) = self.idents(path, source);
         let mut tokenizer = Tokenizer::new(
             source.clone(),
+            idents,
             self.allow_multiline_token,
             self.position_encoding,
         );
         tokenizer.tokenize_tree(&root, ModifierSet::empty());
         let output = tokenizer.output;
 
-        let result_id = self.cache.write().cache_result(output.clone());
+        let version = hash128(&(source.text(), revision));
+        let result_id = self
+            .cache
+            .write()
+            .cache_result(path, version, output.clone());
         (output, result_id)
     }
 
+    /// Get the semantic tokens starting in a range of a source.
+    ///
+    /// The tokens of the full source are reused if they are computed from the
+    /// same version of the source and of the classified identifiers.
+    pub fn get_semantic_tokens_range(
+        &self,
+        path: &Path,
+        source: &Source,
+        range: lsp_types::Range,
+    ) -> Vec<SemanticToken> {
+        let revision = self.idents_revision(path, source);
+        let version = hash128(&(source.text(), revision));
+        if revision.is_some() {
+            let cache = self.cache.read();
+            if let Some(cached) = cache.get_by_version(path, version) {
+                return token_range(cached, range.start, range.end);
+            }
+        }
+
+        self.get_semantic_tokens_full(path, source);
+        let cache = self.cache.read();
+        match cache.get_by_version(path, version) {
+            Some(cached) => token_range(cached, range.start, range.end),
+            None => vec![],
+        }
+    }
+
     /// Get the semantic tokens delta for a source.
     pub fn try_semantic_tokens_delta_from_result_id(
         &self,
+        path: &Path,
         source: &Source,
         result_id: &str,
     ) -> (Result<Vec<SemanticTokensEdit>, Vec<SemanticToken>>, String) {
-        let cached = self.cache.write().try_take_result(result_id);
+        let cached = self.cache.read().try_get_result(path, result_id);
 
         // this call will overwrite the cache, so need to read from cache first
-        let (tokens, result_id) = self.get_semantic_tokens_full(source);
+        let (tokens, result_id) = self.get_semantic_tokens_full(path, source);
 
         match cached {
             Some(cached) => (Ok(token_delta(&cached, &tokens)), result_id),
@@ -76,11 +230,44 @@ impl SemanticTokenContext {
     }
 }
 
+/// Shifts the identifiers resolved in an old text to a new text. The
+/// identifiers overlapping the edited range are dropped.
+fn shift_idents(tokens: &IdentTokens, old: &str, new: &str) -> IdentTokens {
+    let prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(old.len().min(new.len()) - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    tokens
+        .iter()
+        .filter_map(|(range, token)| {
+            if range.end <= prefix {
+                Some((range.clone(), *token))
+            } else if range.start >= old_end {
+                let range = range.start - old_end + new_end..range.end - old_end + new_end;
+                Some((range, *token))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
 struct Tokenizer {
     curr_pos: LspPosition,
     pos_offset: usize,
     output: Vec<SemanticToken>,
     source: Source,
+    idents: Arc<IdentTokens>,
     encoding: PositionEncoding,
 
     allow_multiline_token: bool,
@@ -89,12 +276,18 @@ struct Tokenizer {
 }
 
 impl Tokenizer {
-    fn new(source: Source, allow_multiline_token: bool, encoding: PositionEncoding) -> Self {
+    fn new(
+        source: Source,
+        idents: Arc<IdentTokens>,
+        allow_multiline_token: bool,
+        encoding: PositionEncoding,
+    ) -> Self {
         Self {
             curr_pos: LspPosition::new(0, 0),
             pos_offset: 0,
             output: Vec::new(),
             source,
+            idents,
             allow_multiline_token,
             encoding,
 
@@ -105,10 +298,14 @@ impl Tokenizer {
     /// Tokenize a node and its children
     fn tokenize_tree(&mut self, root: &LinkedNode, modifiers: ModifierSet) {
         let is_leaf = root.get().children().len() == 0;
+        let range = root.range();
         let modifiers = modifiers | modifiers_from_node(root);
+        let modifiers = match self.idents.get(&range) {
+            Some(ident) if is_leaf => modifiers | ident.modifiers,
+            _ => modifiers,
+        };
 
-        let range = root.range();
-        let mut token = token_from_node(root)
+        let mut token = token_from_node(root, &self.idents)
             .or_else(|| is_leaf.then_some(TokenType::Text))
             .map(|token_type| Token::new(token_type, modifiers, range.clone()));
 
@@ -289,7 +486,7 @@ fn modifiers_from_node(node: &LinkedNode) -> ModifierSet {
 /// In tokenization, returning `Some` stops recursion, while returning `None`
 /// continues and attempts to tokenize each of `node`'s children. If there are
 /// no children, `Text` is taken as the default.
-fn token_from_node(node: &LinkedNode) -> Option<TokenType> {
+fn token_from_node(node: &LinkedNode, idents: &IdentTokens) -> Option<TokenType> {
     use SyntaxKind::*;
 
     match node.kind() {
@@ -299,8 +496,8 @@ fn token_from_node(node: &LinkedNode) -> Option<TokenType> {
         Underscore if node.parent_kind() == Some(Emph) => Some(TokenType::Punctuation),
         Underscore if node.parent_kind() == Some(MathAttach) => Some(TokenType::Operator),
 
-        MathIdent | Ident => Some(token_from_ident(node)),
-        Hash => token_from_hashtag(node),
+        MathIdent | Ident => Some(token_from_ident(node, idents)),
+        Hash => token_from_hashtag(node, idents),
 
         LeftBrace | RightBrace | LeftBracket | RightBracket | LeftParen | RightParen | Comma
         | Semicolon | Colon => Some(TokenType::Punctuation),
@@ -343,8 +540,12 @@ fn is_function_ident(ident: &LinkedNode) -> bool {
     function_call || function_content
 }
 
-fn token_from_ident(ident: &LinkedNode) -> TokenType {
-    if is_function_ident(ident) {
+/// Determines the [`TokenType`] of an identifier by the item it resolves to,
+/// falling back to its syntax if it cannot be resolved.
+fn token_from_ident(ident: &LinkedNode, idents: &IdentTokens) -> TokenType {
+    if let Some(resolved) = idents.get(&ident.range()) {
+        resolved.token_type
+    } else if is_function_ident(ident) {
         TokenType::Function
     } else {
         TokenType::Interpolated
@@ -358,8 +559,8 @@ fn get_expr_following_hashtag<'a>(hashtag: &LinkedNode<'a>) -> Option<LinkedNode
         .and_then(|node| node.leftmost_leaf())
 }
 
-fn token_from_hashtag(hashtag: &LinkedNode) -> Option<TokenType> {
+fn token_from_hashtag(hashtag: &LinkedNode, idents: &IdentTokens) -> Option<TokenType> {
     get_expr_following_hashtag(hashtag)
         .as_ref()
-        .and_then(token_from_node)
+        .and_then(|node| token_from_node(node, idents))
 }
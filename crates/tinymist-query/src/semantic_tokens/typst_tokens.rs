@@ -18,6 +18,8 @@ const LIST_MARKER: SemanticTokenType = SemanticTokenType::new("marker");
 const LIST_TERM: SemanticTokenType = SemanticTokenType::new("term");
 const DELIMITER: SemanticTokenType = SemanticTokenType::new("delim");
 const INTERPOLATED: SemanticTokenType = SemanticTokenType::new("pol");
+const ELEMENT: SemanticTokenType = SemanticTokenType::new("element");
+const SYMBOL: SemanticTokenType = SemanticTokenType::new("symbol");
 const ERROR: SemanticTokenType = SemanticTokenType::new("error");
 const TEXT: SemanticTokenType = SemanticTokenType::new("text");
 
@@ -34,6 +36,8 @@ pub enum TokenType {
     Number,
     Function,
     Decorator,
+    Variable,
+    Namespace,
     // Custom types
     Bool,
     Punctuation,
@@ -47,6 +51,10 @@ pub enum TokenType {
     ListTerm,
     Delimiter,
     Interpolated,
+    /// An element function, e.g. `heading`.
+    Element,
+    /// A symbol, e.g. `alpha` in math.
+    Symbol,
     Error,
     /// Any text in markup without a more specific token type, possible styled.
     ///
@@ -72,6 +80,8 @@ impl From<TokenType> for SemanticTokenType {
             Number => Self::NUMBER,
             Function => Self::FUNCTION,
             Decorator => Self::DECORATOR,
+            Variable => Self::VARIABLE,
+            Namespace => Self::NAMESPACE,
             Bool => BOOL,
             Punctuation => PUNCTUATION,
             Escape => ESCAPE,
@@ -84,6 +94,8 @@ impl From<TokenType> for SemanticTokenType {
             ListTerm => LIST_TERM,
             Delimiter => DELIMITER,
             Interpolated => INTERPOLATED,
+            Element => ELEMENT,
+            Symbol => SYMBOL,
             Error => ERROR,
             Text => TEXT,
             None => unreachable!(),
@@ -101,6 +113,9 @@ pub enum Modifier {
     Strong,
     Emph,
     Math,
+    Readonly,
+    DefaultLibrary,
+    Deprecated,
 }
 
 impl Modifier {
@@ -121,6 +136,9 @@ impl From<Modifier> for SemanticTokenModifier {
             Strong => STRONG,
             Emph => EMPH,
             Math => MATH,
+            Readonly => SemanticTokenModifier::READONLY,
+            DefaultLibrary => SemanticTokenModifier::DEFAULT_LIBRARY,
+            Deprecated => SemanticTokenModifier::DEPRECATED,
         }
     }
 }
@@ -29,8 +29,11 @@ impl SemanticTokensDeltaRequest {
         ctx: &SemanticTokenContext,
         source: Source,
     ) -> Option<SemanticTokensFullDeltaResult> {
-        let (tokens, result_id) =
-            ctx.try_semantic_tokens_delta_from_result_id(&source, &self.previous_result_id);
+        let (tokens, result_id) = ctx.try_semantic_tokens_delta_from_result_id(
+            &self.path,
+            &source,
+            &self.previous_result_id,
+        );
 
         match tokens {
             Ok(edits) => Some(
@@ -29,7 +29,7 @@ impl SemanticTokensFullRequest {
         ctx: &SemanticTokenContext,
         source: Source,
     ) -> Option<SemanticTokensResult> {
-        let (tokens, result_id) = ctx.get_semantic_tokens_full(&source);
+        let (tokens, result_id) = ctx.get_semantic_tokens_full(&self.path, &source);
 
         Some(
             SemanticTokens {
@@ -43,8 +43,12 @@ impl SemanticTokensFullRequest {
 
 #[cfg(test)]
 mod tests {
+    use lsp_types::{SemanticTokenModifier, SemanticTokenType};
+    use strum::IntoEnumIterator;
+
     use super::*;
     use crate::tests::*;
+    use crate::{Modifier, TokenType};
 
     #[test]
     fn test() {
@@ -54,6 +58,7 @@ mod tests {
             let request = SemanticTokensFullRequest { path: path.clone() };
 
             let cache = SemanticTokenContext::default();
+            cache.resolve_idents(ctx, &path);
 
             let mut result = request.request(&cache, source).unwrap();
             if let SemanticTokensResult::Tokens(tokens) = &mut result {
@@ -63,4 +68,114 @@ mod tests {
             assert_snapshot!(serde_json::to_string(&result).unwrap());
         });
     }
+
+    /// Describes the tokens of the identifiers, one per line.
+    fn describe_idents(source: &Source, tokens: &SemanticTokens) -> String {
+        let types = TokenType::iter().collect::<Vec<_>>();
+        let mut pos = LspPosition::new(0, 0);
+        let mut lines = vec![];
+        for token in &tokens.data {
+            if token.delta_line > 0 {
+                pos = LspPosition::new(pos.line + token.delta_line, token.delta_start);
+            } else {
+                pos.character += token.delta_start;
+            }
+
+            let token_type = types[token.token_type as usize];
+            if !matches!(
+                token_type,
+                TokenType::Function
+                    | TokenType::Variable
+                    | TokenType::Namespace
+                    | TokenType::Element
+                    | TokenType::Symbol
+                    | TokenType::Interpolated
+            ) {
+                continue;
+            }
+            let start = lsp_to_typst::position(pos, PositionEncoding::Utf16, source).unwrap();
+            let text = &source.text()[start..start + token.length as usize];
+            if text == "#" {
+                continue;
+            }
+
+            let token_type = SemanticTokenType::from(token_type);
+            let modifiers = Modifier::iter()
+                .filter(|m| token.token_modifiers_bitset & m.bitmask() != 0)
+                .map(|m| SemanticTokenModifier::from(m).as_str().to_owned())
+                .collect::<Vec<_>>();
+            if modifiers.is_empty() {
+                lines.push(format!("{text}: {}", token_type.as_str()));
+            } else {
+                let modifiers = modifiers.join(", ");
+                lines.push(format!("{text}: {} [{modifiers}]", token_type.as_str()));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn full_tokens(cache: &SemanticTokenContext, path: &Path, source: &Source) -> SemanticTokens {
+        let request = SemanticTokensFullRequest {
+            path: path.to_owned(),
+        };
+        match request.request(cache, source.clone()).unwrap() {
+            SemanticTokensResult::Tokens(tokens) => tokens,
+            SemanticTokensResult::Partial(..) => panic!("expected tokens"),
+        }
+    }
+
+    #[test]
+    fn test_idents() {
+        snapshot_testing("semantic_tokens_idents", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let cache = SemanticTokenContext::default();
+            assert!(cache.idents_outdated(&path, &source));
+            assert!(cache.resolve_idents(ctx, &path));
+            assert!(!cache.resolve_idents(ctx, &path));
+
+            let tokens = full_tokens(&cache, &path, &source);
+            assert_snapshot!(describe_idents(&source, &tokens));
+        });
+    }
+
+    #[test]
+    fn test_shifted_idents() {
+        snapshot_testing("semantic_tokens_idents", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let cache = SemanticTokenContext::default();
+            cache.resolve_idents(ctx, &path);
+            let resolved = describe_idents(&source, &full_tokens(&cache, &path, &source));
+
+            // The identifiers resolved before an edit are reused until the edited
+            // source is resolved again.
+            let mut edited = source.clone();
+            edited.edit(0..0, "\n");
+            assert!(cache.idents_outdated(&path, &edited));
+            let shifted = describe_idents(&edited, &full_tokens(&cache, &path, &edited));
+            assert_eq!(shifted, resolved);
+        });
+    }
+
+    #[test]
+    fn test_evict() {
+        snapshot_testing("semantic_tokens_idents", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let cache = SemanticTokenContext::default();
+            assert!(cache.schedule_resolving(&path, &source));
+            assert!(cache.resolve_scheduled_idents(ctx, &path));
+            let result_id = full_tokens(&cache, &path, &source).result_id.unwrap();
+
+            // The evicted identifiers are neither kept nor resolved in the
+            // background, and the tokens are sent in full again.
+            cache.evict(&path);
+            assert!(cache.idents_outdated(&path, &source));
+            assert!(!cache.resolve_scheduled_idents(ctx, &path));
+            let (delta, _) =
+                cache.try_semantic_tokens_delta_from_result_id(&path, &source, &result_id);
+            assert!(delta.is_err());
+        });
+    }
 }
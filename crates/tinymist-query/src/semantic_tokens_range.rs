@@ -0,0 +1,81 @@
+use crate::{prelude::*, SemanticTokenContext};
+
+/// The [`textDocument/semanticTokens/range`] request is sent from the client
+/// to the server to resolve the semantic tokens of a visible range of a file.
+///
+/// [`textDocument/semanticTokens/range`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_semanticTokens
+///
+/// Clients may request the tokens of the visible range first to show them
+/// faster in large files. The tokens of the full file are cached per version
+/// of the file, so that subsequent requests on an unchanged file are answered
+/// from the cache.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.16.0.
+#[derive(Debug, Clone)]
+pub struct SemanticTokensRangeRequest {
+    /// The path of the document to get semantic tokens for.
+    pub path: PathBuf,
+    /// The range of the document to get semantic tokens for.
+    pub range: LspRange,
+}
+
+impl SemanticTokensRangeRequest {
+    /// Handles the request to compute the semantic tokens in a range of a
+    /// given document.
+    pub fn request(
+        self,
+        ctx: &SemanticTokenContext,
+        source: Source,
+    ) -> Option<SemanticTokensRangeResult> {
+        let tokens = ctx.get_semantic_tokens_range(&self.path, &source, self.range);
+
+        Some(
+            SemanticTokens {
+                result_id: None,
+                data: tokens,
+            }
+            .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use crate::SemanticTokensFullRequest;
+
+    #[test]
+    fn test() {
+        snapshot_testing("semantic_tokens", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let cache = SemanticTokenContext::default();
+            cache.resolve_idents(ctx, &path);
+
+            let request = SemanticTokensRangeRequest {
+                path: path.clone(),
+                range: typst_to_lsp::range(
+                    0..source.text().len(),
+                    &source,
+                    PositionEncoding::Utf16,
+                ),
+            };
+            let SemanticTokensRangeResult::Tokens(range) =
+                request.request(&cache, source.clone()).unwrap()
+            else {
+                panic!("expected tokens");
+            };
+
+            let request = SemanticTokensFullRequest { path: path.clone() };
+            let SemanticTokensResult::Tokens(full) = request.request(&cache, source).unwrap()
+            else {
+                panic!("expected tokens");
+            };
+
+            assert_eq!(range.data, full.data);
+        });
+    }
+}
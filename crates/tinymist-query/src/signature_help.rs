@@ -1,8 +1,22 @@
-use crate::{prelude::*, syntax::param_index_at_leaf, SemanticRequest};
+use lsp_types::{ParameterInformation, ParameterLabel};
+
+use crate::{
+    analysis::{
+        analyze_signature, analyze_type_signatures, resolve_call_target, FlowSignature, FlowType,
+        ParamSpec, PrimarySignature, Signature, SignatureTarget,
+    },
+    prelude::*,
+    syntax::{arg_target_at_leaf, ArgTarget},
+    SemanticRequest,
+};
 
 /// The [`textDocument/signatureHelp`] request is sent from the client to the
 /// server to request signature information at a given cursor position.
 ///
+/// The parameters are those of the function analyzed at runtime, refined by
+/// the types inferred by type checking. A closure bound to a union type
+/// results in one signature per member of the union.
+///
 /// [`textDocument/signatureHelp`]: https://microsoft.github.io/language-server-protocol/specification#textDocument_signatureHelp
 #[derive(Debug, Clone)]
 pub struct SignatureHelpRequest {
@@ -20,62 +34,198 @@ impl SemanticRequest for SignatureHelpRequest {
         let typst_offset = ctx.to_typst_pos(self.position, &source)?;
 
         let ast_node = LinkedNode::new(source.root()).leaf_at(typst_offset + 1)?;
-        let (callee, callee_node, args) = surrounding_function_syntax(&ast_node)?;
+        let (callee, callee_node, _args) = surrounding_function_syntax(&ast_node)?;
 
         if !callee.hash() && !matches!(callee, ast::Expr::MathIdent(_)) {
             return None;
         }
 
-        let values = analyze_expr(ctx.world(), &callee_node);
-
-        let function = values.into_iter().find_map(|v| match v.0 {
-            Value::Func(f) => Some(f),
-            _ => None,
-        })?;
+        let cc = resolve_call_target(ctx, callee_node)?;
+        let this = cc.method_this().cloned();
+        let function = cc.callee();
         trace!("got function {function:?}");
 
-        let param_index = param_index_at_leaf(&ast_node, &function, args);
-
-        let label = format!(
-            "{}({}){}",
-            function.name().unwrap_or("<anonymous closure>"),
-            match function.params() {
-                Some(params) => params
-                    .iter()
-                    .map(typst_to_lsp::param_info_to_label)
-                    .join(", "),
-                None => "".to_owned(),
-            },
-            match function.returns() {
-                Some(returns) => format!("-> {}", typst_to_lsp::cast_info_to_label(returns)),
-                None => "".to_owned(),
-            }
-        );
-        let params = function
-            .params()
-            .unwrap_or_default()
+        let signature = analyze_signature(
+            ctx,
+            source.clone(),
+            SignatureTarget::Runtime(function.clone()),
+        )?;
+        let type_sigs = analyze_type_signatures(ctx, &function);
+
+        // Positional arguments bound by `with` or by the receiver of a method
+        // are hidden from the signature.
+        let bound = signature
+            .bindings()
             .iter()
-            .map(typst_to_lsp::param_info)
-            .collect();
-        trace!("got signature info {label} {params:?}");
+            .flat_map(|args| args.items.iter())
+            .filter(|arg| arg.name.is_none())
+            .count()
+            + usize::from(this.is_some());
 
+        let name = function.name().unwrap_or("<anonymous closure>");
         let documentation = function.docs().map(markdown_docs);
+        let arg = arg_target_at_leaf(&ast_node);
+
+        let signatures = match type_sigs {
+            Some(type_sigs) if !type_sigs.is_empty() => type_sigs
+                .iter()
+                .map(|ty| signature_info(name, &function, &signature, bound, Some(ty)))
+                .collect(),
+            _ => vec![signature_info(name, &function, &signature, bound, None)],
+        };
+        let signatures = signatures
+            .into_iter()
+            .map(|(label, params)| {
+                let active_parameter = arg
+                    .as_ref()
+                    .and_then(|arg| active_param(&params, arg))
+                    .map(|i| i as u32);
+                trace!("got signature info {label} {active_parameter:?}");
 
-        let active_parameter = param_index.map(|i| i as u32);
+                SignatureInformation {
+                    label,
+                    documentation: documentation.clone(),
+                    parameters: Some(params.into_iter().map(|p| p.info).collect()),
+                    active_parameter,
+                }
+            })
+            .collect();
 
         Some(SignatureHelp {
-            signatures: vec![SignatureInformation {
-                label,
-                documentation,
-                parameters: Some(params),
-                active_parameter,
-            }],
+            signatures,
             active_signature: Some(0),
             active_parameter: None,
         })
     }
 }
 
+/// A parameter shown in a signature.
+struct SignatureParam {
+    spec: Arc<ParamSpec>,
+    info: ParameterInformation,
+}
+
+/// Renders the label and the parameters of a signature, optionally refined by
+/// a signature inferred by type checking.
+fn signature_info(
+    name: &str,
+    function: &Func,
+    signature: &Signature,
+    bound: usize,
+    ty: Option<&FlowSignature>,
+) -> (String, Vec<SignatureParam>) {
+    let primary = signature.primary();
+
+    let mut label = format!("{name}(");
+    let mut params = vec![];
+    for (spec, param_ty) in signature_params(function, primary, bound, ty) {
+        if !params.is_empty() {
+            label.push_str(", ");
+        }
+
+        let start = label.encode_utf16().count() as u32;
+        if spec.variadic {
+            label.push_str("..");
+        }
+        label.push_str(&spec.name);
+        let end = label.encode_utf16().count() as u32;
+        let param_ty = param_ty.or_else(|| spec.type_repr.as_ref().map(|t| t.to_string()));
+        if let Some(param_ty) = param_ty {
+            label.push_str(": ");
+            label.push_str(&param_ty);
+        }
+
+        let documentation = (!spec.docs.is_empty()).then(|| markdown_docs(&spec.docs));
+        params.push(SignatureParam {
+            info: ParameterInformation {
+                label: ParameterLabel::LabelOffsets([start, end]),
+                documentation,
+            },
+            spec,
+        });
+    }
+    label.push(')');
+
+    let ret = match function.returns() {
+        Some(returns) => Some(typst_to_lsp::cast_info_to_label(returns)),
+        None => ty.and_then(|ty| ty.ret.describe()),
+    };
+    if let Some(ret) = ret {
+        label.push_str(" -> ");
+        label.push_str(&ret);
+    }
+
+    (label, params)
+}
+
+/// Lists the parameters of a signature that are not bound yet, with their
+/// types inferred by type checking if any.
+fn signature_params(
+    function: &Func,
+    primary: &PrimarySignature,
+    bound: usize,
+    ty: Option<&FlowSignature>,
+) -> Vec<(Arc<ParamSpec>, Option<String>)> {
+    let mut params = vec![];
+
+    for (i, spec) in primary.pos.iter().enumerate().skip(bound) {
+        let param_ty = ty.and_then(|ty| ty.pos.get(i)).and_then(FlowType::describe);
+        params.push((spec.clone(), param_ty));
+    }
+
+    if let Some(rest) = &primary.rest {
+        let param_ty = ty
+            .and_then(|ty| ty.rest.as_ref())
+            .and_then(FlowType::describe);
+        params.push((rest.clone(), param_ty));
+    }
+
+    // Named parameters keep the order of declaration if it is known.
+    let order = |name: &str| {
+        function
+            .params()
+            .and_then(|params| params.iter().position(|p| p.name == name))
+    };
+    let mut named = primary
+        .named
+        .values()
+        .filter(|spec| !spec.positional)
+        .collect::<Vec<_>>();
+    named.sort_by(|x, y| (order(&x.name), &x.name).cmp(&(order(&y.name), &y.name)));
+    for spec in named {
+        let param_ty = ty
+            .and_then(|ty| ty.named.iter().find(|(name, _)| name == &*spec.name))
+            .and_then(|(_, ty)| ty.describe());
+        params.push((spec.clone(), param_ty));
+    }
+
+    params
+}
+
+/// Finds the index of the parameter corresponding to the argument at the
+/// cursor.
+fn active_param(params: &[SignatureParam], arg: &ArgTarget) -> Option<usize> {
+    let positional = |n: usize| {
+        let mut pos = params.iter().enumerate().filter(|(_, p)| p.spec.positional);
+        pos.clone()
+            .filter(|(_, p)| !p.spec.variadic)
+            .nth(n)
+            .or_else(|| pos.find(|(_, p)| p.spec.variadic))
+            .map(|(i, _)| i)
+    };
+
+    match arg {
+        ArgTarget::Positional(n) => positional(*n),
+        ArgTarget::Named(name) => params
+            .iter()
+            .position(|p| p.spec.named && p.spec.name == name.as_str()),
+        ArgTarget::Ident(prefix, n) => params
+            .iter()
+            .position(|p| p.spec.named && p.spec.name.starts_with(prefix.as_str()))
+            .or_else(|| positional(*n)),
+    }
+}
+
 fn surrounding_function_syntax<'b>(
     leaf: &'b LinkedNode,
 ) -> Option<(ast::Expr<'b>, LinkedNode<'b>, ast::Args<'b>)> {
@@ -101,3 +251,47 @@ fn markdown_docs(docs: &str) -> Documentation {
         value: docs.to_owned(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    /// Describes a signature by its name, the names of its parameters, and the
+    /// active parameter, e.g. `f(x, y) active: y`.
+    fn describe(signature: &SignatureInformation) -> String {
+        let label = signature.label.encode_utf16().collect::<Vec<_>>();
+        let param_name = |param: &ParameterInformation| match param.label {
+            ParameterLabel::LabelOffsets([start, end]) => {
+                String::from_utf16_lossy(&label[start as usize..end as usize])
+            }
+            ParameterLabel::Simple(ref name) => name.clone(),
+        };
+
+        let params = signature.parameters.iter().flatten();
+        let names = params.map(param_name).collect::<Vec<_>>();
+        let name = signature.label.split('(').next().unwrap_or_default();
+        let active = signature
+            .active_parameter
+            .and_then(|i| names.get(i as usize))
+            .map_or("none", |name| name.as_str());
+        format!("{name}({}) active: {active}", names.join(", "))
+    }
+
+    #[test]
+    fn test() {
+        snapshot_testing("signature_help", &|ctx, path| {
+            let source = ctx.source_by_path(&path).unwrap();
+
+            let request = SignatureHelpRequest {
+                path: path.clone(),
+                position: find_test_position(&source),
+            };
+
+            let result = request
+                .request(ctx)
+                .map(|help| help.signatures.iter().map(describe).collect::<Vec<_>>());
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
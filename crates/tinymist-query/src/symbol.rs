@@ -72,12 +72,18 @@ fn filter_document_symbols(
     uri: &Url,
     position_encoding: PositionEncoding,
 ) -> Vec<SymbolInformation> {
-    symbols
-        .iter()
-        .flat_map(|e| {
-            [e].into_iter()
-                .chain(e.children.as_deref().into_iter().flatten())
-        })
+    fn flatten(symbols: &[LexicalHierarchy]) -> Vec<&LexicalHierarchy> {
+        symbols
+            .iter()
+            .flat_map(|e| {
+                let children = e.children.as_deref().map(|c| flatten(c));
+                iter::once(e).chain(children.into_iter().flatten())
+            })
+            .collect()
+    }
+
+    flatten(symbols)
+        .into_iter()
         .filter(|e| e.info.name.contains(query_string))
         .map(|e| {
             let rng = typst_to_lsp::range(e.info.range.clone(), source, position_encoding);
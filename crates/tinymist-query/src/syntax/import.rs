@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
-fn resolve_id_by_path(
+/// Resolves the file id of an import path relative to the `current` file.
+pub fn resolve_id_by_path(
     world: &dyn World,
     current: TypstFileId,
     import_path: &str,
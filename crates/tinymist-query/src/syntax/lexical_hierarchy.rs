@@ -114,6 +114,9 @@ pub enum LexicalKind {
     Var(LexicalVarKind),
     Mod(LexicalModKind),
     Block,
+    /// `figure(.., caption: [A cat])`
+    ///                       ^^^^^
+    Figure,
 }
 
 impl LexicalKind {
@@ -177,6 +180,7 @@ impl TryFrom<LexicalKind> for SymbolKind {
             LexicalKind::Var(LexicalVarKind::Variable) => Ok(SymbolKind::VARIABLE),
             LexicalKind::Var(LexicalVarKind::Function) => Ok(SymbolKind::FUNCTION),
             LexicalKind::Var(LexicalVarKind::Label) => Ok(SymbolKind::CONSTANT),
+            LexicalKind::Figure => Ok(SymbolKind::OBJECT),
             LexicalKind::Var(..) | LexicalKind::Mod(..) | LexicalKind::Block => Err(()),
         }
     }
@@ -364,7 +368,9 @@ impl LexicalHierarchyWorker {
                 'heading_break: while let Some((w, _)) = self.stack.last() {
                     match w.kind {
                         LexicalKind::Heading(l) if l < level => break 'heading_break,
-                        LexicalKind::Block => break 'heading_break,
+                        LexicalKind::Block | LexicalKind::Var(..) | LexicalKind::Figure => {
+                            break 'heading_break
+                        }
                         _ if self.stack.len() <= 1 => break 'heading_break,
                         _ => {}
                     }
@@ -414,7 +420,17 @@ impl LexicalHierarchyWorker {
 
                     // reverse order for correct symbol affection
                     let name_offset = pattern.as_ref().map(|e| e.offset());
-                    if self.g == LexicalScopeKind::DefUse {
+                    let is_ident = |p: &LinkedNode| p.kind() == SyntaxKind::Ident;
+                    if self.g == LexicalScopeKind::Symbol && pattern.as_ref().is_some_and(is_ident)
+                    {
+                        // The symbols in the initializer are nested under the variable.
+                        let body = node
+                            .children()
+                            .rev()
+                            .find(|n| n.is::<ast::Expr>())
+                            .filter(|body| name_offset.is_some_and(|e| e < body.offset()));
+                        self.get_symbols_under(pattern, IdentContext::Var, body)?;
+                    } else if self.g == LexicalScopeKind::DefUse {
                         self.get_symbols_in_first_expr(node.children().rev(), name_offset)?;
                         self.get_symbols_in_opt_with(pattern, IdentContext::Var)?;
                     } else {
@@ -435,17 +451,19 @@ impl LexicalHierarchyWorker {
                     self.get_symbols_in_first_expr(node.children().rev(), iterable_offset)?;
                 }
                 SyntaxKind::Closure => {
-                    let n = node.children().next();
-                    if let Some(n) = n {
-                        if n.kind() == SyntaxKind::Ident {
-                            self.get_symbols_with(n, IdentContext::Func)?;
-                        }
-                    }
+                    let name = node
+                        .children()
+                        .next()
+                        .filter(|n| n.kind() == SyntaxKind::Ident);
                     let body = node
                         .children()
                         .rev()
                         .find(|n| n.cast::<ast::Expr>().is_some());
-                    if let Some(body) = body {
+                    if self.g == LexicalScopeKind::Symbol {
+                        // The symbols in the body are nested under the function.
+                        self.get_symbols_under(name, IdentContext::Func, body)?;
+                    } else if let Some(body) = body {
+                        self.get_symbols_in_opt_with(name, IdentContext::Func)?;
                         if self.g == LexicalScopeKind::DefUse {
                             let symbol = LexicalInfo {
                                 name: String::new(),
@@ -515,6 +533,33 @@ impl LexicalHierarchyWorker {
         Ok(())
     }
 
+    /// Pushes the symbol of a name, and collects the symbols in the body as
+    /// its children.
+    fn get_symbols_under(
+        &mut self,
+        name: Option<LinkedNode>,
+        context: IdentContext,
+        body: Option<LinkedNode>,
+    ) -> anyhow::Result<()> {
+        let c = self.ident_context;
+        self.ident_context = context;
+        let symbol = name.map(|name| self.get_ident(&name)).transpose();
+        self.ident_context = c;
+
+        let Some(symbol) = symbol?.flatten() else {
+            return self.get_symbols_in_opt_with(body, IdentContext::Ref);
+        };
+
+        self.stack.push((symbol, eco_vec![]));
+        let stack_height = self.stack.len();
+        self.get_symbols_in_opt_with(body, IdentContext::Ref)?;
+        while stack_height <= self.stack.len() {
+            self.symbreak();
+        }
+
+        Ok(())
+    }
+
     #[inline(always)]
     fn get_symbols_in_opt_with(
         &mut self,
@@ -586,9 +631,16 @@ impl LexicalHierarchyWorker {
 
                 (name, kind)
             }
-            SyntaxKind::Equation | SyntaxKind::Raw | SyntaxKind::BlockComment
-                if self.g.affect_markup() =>
+            // Identifiers in math are always references, e.g. `x` in `$x^2$`.
+            SyntaxKind::MathIdent
+                if self.g.affect_ref() && self.ident_context == IdentContext::Ref =>
             {
+                let ast_node = node
+                    .cast::<ast::MathIdent>()
+                    .ok_or_else(|| anyhow!("cast to ast node failed: {:?}", node))?;
+                (ast_node.get().to_string(), LexicalKind::val_ref())
+            }
+            SyntaxKind::Equation | SyntaxKind::Raw if self.g.affect_markup() => {
                 (String::new(), LexicalKind::Block)
             }
             SyntaxKind::CodeBlock | SyntaxKind::ContentBlock if self.g.affect_block() => {
@@ -639,6 +691,11 @@ impl LexicalHierarchyWorker {
                     SyntaxKind::Heading if self.g.affect_heading() => LexicalKind::Heading(
                         parent.cast::<ast::Heading>().unwrap().depth().get() as i16,
                     ),
+                    SyntaxKind::ContentBlock
+                        if self.g == LexicalScopeKind::Symbol && is_figure_caption(parent) =>
+                    {
+                        LexicalKind::Figure
+                    }
                     _ => return Ok(None),
                 };
 
@@ -755,3 +812,22 @@ fn symbreak(sym: LexicalInfo, curr: EcoVec<LexicalHierarchy>) -> LexicalHierarch
         },
     }
 }
+
+/// Checks whether a content block is the caption of a figure, i.e.
+/// `figure(.., caption: [..])`.
+fn is_figure_caption(block: &LinkedNode) -> bool {
+    let Some(named) = block.parent().and_then(|p| p.cast::<ast::Named>()) else {
+        return false;
+    };
+    if named.name().get() != "caption" {
+        return false;
+    }
+
+    let call = block
+        .parent()
+        .and_then(|p| p.parent()?.parent()?.cast::<ast::FuncCall>());
+    matches!(
+        call.map(|call| call.callee()),
+        Some(ast::Expr::Ident(callee)) if callee.get() == "figure"
+    )
+}
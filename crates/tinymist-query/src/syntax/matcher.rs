@@ -1,4 +1,4 @@
-use ecow::EcoVec;
+use ecow::{EcoString, EcoVec};
 use typst::{
     foundations::{Func, ParamInfo},
     syntax::{
@@ -337,6 +337,47 @@ pub fn param_index_at_leaf(leaf: &LinkedNode, function: &Func, args: ast::Args)
     Some(param_index)
 }
 
+/// The argument at the cursor, independent of the parameters of the callee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgTarget {
+    /// The positional argument following the given number of positional
+    /// arguments.
+    Positional(usize),
+    /// The value of a named argument.
+    Named(EcoString),
+    /// An identifier starting an argument, which is either the name of a named
+    /// argument or a positional argument.
+    Ident(EcoString, usize),
+}
+
+/// Find the argument at the leaf in the argument list.
+pub fn arg_target_at_leaf(leaf: &LinkedNode) -> Option<ArgTarget> {
+    let deciding = deciding_syntax(leaf);
+    match deciding.kind() {
+        // After colon: "func(param:|)", "func(param: |)".
+        SyntaxKind::Colon => {
+            let prev = deciding.prev_leaf()?;
+            let param_ident = prev.cast::<ast::Ident>()?;
+            Some(ArgTarget::Named(param_ident.get().clone()))
+        }
+        // Before: "func(|)", "func(hi|)", "func(12,|)".
+        SyntaxKind::Comma | SyntaxKind::LeftParen => {
+            let positional_args_so_far = deciding
+                .parent()?
+                .children()
+                .take_while(|arg| arg.offset() < deciding.offset())
+                .filter(|arg| matches!(arg.cast::<ast::Arg>(), Some(ast::Arg::Pos(_))))
+                .count();
+            let next = deciding.next_leaf();
+            match next.as_ref().and_then(|next| next.cast::<ast::Ident>()) {
+                Some(next) => Some(ArgTarget::Ident(next.get().clone(), positional_args_so_far)),
+                None => Some(ArgTarget::Positional(positional_args_so_far)),
+            }
+        }
+        _ => None,
+    }
+}
+
 /// Find the piece of syntax that decides what we're completing.
 fn deciding_syntax<'b>(leaf: &'b LinkedNode) -> LinkedNode<'b> {
     let mut deciding = leaf.clone();
@@ -76,16 +76,41 @@ pub(crate) fn scan_workspace_files<T>(
     root: &Path,
     ext: &RegexSet,
     f: impl Fn(&Path) -> T,
+) -> Vec<T> {
+    scan_workspace_files_with(root, ext, false, None, f)
+}
+
+/// Scan the files in the workspace, optionally including hidden files, and
+/// stop after `max_entries` files are found.
+///
+/// The files are visited in the order of their names, so that the result is
+/// deterministic even if the scan is cut.
+///
+/// Note: this function will touch the physical file system.
+pub(crate) fn scan_workspace_files_with<T>(
+    root: &Path,
+    ext: &RegexSet,
+    show_hidden: bool,
+    max_entries: Option<usize>,
+    f: impl Fn(&Path) -> T,
 ) -> Vec<T> {
     let mut res = vec![];
-    let mut it = walkdir::WalkDir::new(root).follow_links(false).into_iter();
+    let mut it = walkdir::WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter();
     loop {
+        if max_entries.is_some_and(|max| res.len() >= max) {
+            log::info!("stop scanning {root:?} at {} files", res.len());
+            break;
+        }
+
         let de = match it.next() {
             None => break,
             Some(Err(_err)) => continue,
             Some(Ok(entry)) => entry,
         };
-        if is_hidden(&de) {
+        if !show_hidden && is_hidden(&de) {
             if de.file_type().is_dir() {
                 it.skip_current_dir();
             }
@@ -146,3 +171,36 @@ pub(crate) fn scan_workspace_files<T>(
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use reflexo::path::unix_slash;
+
+    use super::*;
+    use crate::analysis::PathPreference;
+
+    #[test]
+    fn test_scan_workspace_files_with() {
+        let dir = std::env::temp_dir().join(format!("tinymist-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for path in ["b.png", "a.typ", "c/d.typ", ".hidden/e.typ", ".f.typ"] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let scan = |show_hidden, max_entries| {
+            let ext = PathPreference::None.ext_matcher();
+            scan_workspace_files_with(&dir, ext, show_hidden, max_entries, unix_slash)
+        };
+
+        assert_eq!(scan(false, None), ["a.typ", "b.png", "c/d.typ"]);
+        assert_eq!(
+            scan(true, None),
+            [".f.typ", ".hidden/e.typ", "a.typ", "b.png", "c/d.typ"]
+        );
+        assert_eq!(scan(false, Some(2)), ["a.typ", "b.png"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use core::fmt;
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::{prelude::*, SemanticRequest};
+
+/// A request to get the syntax tree of a document, e.g. to debug why a rule or
+/// a completion doesn't trigger at a position.
+///
+/// If a range is given, the tree of the smallest node enclosing it is
+/// returned, otherwise the tree of the whole document.
+#[derive(Debug, Clone)]
+pub struct SyntaxTreeRequest {
+    /// The path of the document.
+    pub path: PathBuf,
+    /// The range of the selection.
+    pub range: Option<LspRange>,
+    /// Whether to describe the types of the nodes inferred by type checking.
+    pub with_types: bool,
+}
+
+/// A node of a syntax tree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTreeNode {
+    /// The kind of the node, e.g. `FuncCall`.
+    pub kind: String,
+    /// The range of the node.
+    pub range: LspRange,
+    /// The text of the node, if it is a leaf.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The type of the node inferred by type checking, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    /// The children of the node.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SyntaxTreeNode>,
+}
+
+impl SemanticRequest for SyntaxTreeRequest {
+    type Response = SyntaxTreeNode;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let source = ctx.source_by_path(&self.path).ok()?;
+        let root = LinkedNode::new(source.root());
+        let node = match self.range {
+            Some(range) => {
+                let range = ctx.to_typst_range(range, &source)?;
+                enclosing_node(root, &range)
+            }
+            None => root,
+        };
+
+        let encoding = ctx.analysis.position_encoding;
+        let ty_chk = if self.with_types {
+            ctx.type_check(source.clone())
+        } else {
+            None
+        };
+        let mut ty_of = |node: &LinkedNode| {
+            let ty_chk = ty_chk.as_ref()?;
+            let ty = ctx.type_of_span(node.span())?;
+            ty_chk.simplify(ty, true).describe()
+        };
+
+        Some(convert_node(&source, encoding, &node, &mut ty_of))
+    }
+}
+
+/// Gets the smallest node enclosing a range.
+fn enclosing_node<'a>(node: LinkedNode<'a>, range: &Range<usize>) -> LinkedNode<'a> {
+    let child = node.children().find(|child| {
+        let child_range = child.range();
+        child_range.start <= range.start && range.end <= child_range.end
+    });
+    match child {
+        Some(child) => enclosing_node(child, range),
+        None => node,
+    }
+}
+
+/// Converts a node and its descendants, describing their types by `ty_of`.
+fn convert_node(
+    source: &Source,
+    encoding: PositionEncoding,
+    node: &LinkedNode,
+    ty_of: &mut impl FnMut(&LinkedNode) -> Option<String>,
+) -> SyntaxTreeNode {
+    let children = node.children();
+    let children = children.map(|child| convert_node(source, encoding, &child, ty_of));
+    let children = children.collect::<Vec<_>>();
+    SyntaxTreeNode {
+        kind: format!("{:?}", node.kind()),
+        range: typst_to_lsp::range(node.range(), source, encoding),
+        text: children.is_empty().then(|| node.text().to_string()),
+        ty: ty_of(node),
+        children,
+    }
+}
+
+/// Pretty prints the tree, one node per line indented by its depth, with the
+/// zero-based range, the text of a leaf, and the type, e.g.
+/// `Int 0:13-0:14 "1": 1`.
+impl fmt::Display for SyntaxTreeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn print(node: &SyntaxTreeNode, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let (start, end) = (node.range.start, node.range.end);
+            write!(
+                f,
+                "{:indent$}{} {}:{}-{}:{}",
+                "",
+                node.kind,
+                start.line,
+                start.character,
+                end.line,
+                end.character,
+                indent = depth * 2
+            )?;
+            if let Some(text) = &node.text {
+                write!(f, " {text:?}")?;
+            }
+            if let Some(ty) = &node.ty {
+                write!(f, ": {ty}")?;
+            }
+            writeln!(f)?;
+            for child in &node.children {
+                print(child, depth + 1, f)?;
+            }
+            Ok(())
+        }
+
+        print(self, 0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_tree() {
+        let source = Source::detached("#let x = add(1, y)");
+        let root = LinkedNode::new(source.root());
+        let node = enclosing_node(root, &(13..14));
+        assert_eq!(node.kind(), SyntaxKind::Int);
+
+        let call = enclosing_node(LinkedNode::new(source.root()), &(9..15));
+        assert_eq!(call.kind(), SyntaxKind::FuncCall);
+        let mut ty_of = |node: &LinkedNode| (node.kind() == SyntaxKind::Int).then(|| "1".into());
+        let tree = convert_node(&source, PositionEncoding::Utf16, &call, &mut ty_of);
+        assert_eq!(
+            tree.to_string(),
+            "FuncCall 0:9-0:18\n  \
+             Ident 0:9-0:12 \"add\"\n  \
+             Args 0:12-0:18\n    \
+             LeftParen 0:12-0:13 \"(\"\n    \
+             Int 0:13-0:14 \"1\": 1\n    \
+             Comma 0:14-0:15 \",\"\n    \
+             Space 0:15-0:16 \" \"\n    \
+             Ident 0:16-0:17 \"y\"\n    \
+             RightParen 0:17-0:18 \")\"\n"
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use core::fmt;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ops::Range,
     path::{Path, PathBuf},
 };
 
+use ecow::{eco_vec, EcoVec};
 use once_cell::sync::Lazy;
 pub use serde::Serialize;
 use serde_json::{ser::PrettyFormatter, Serializer, Value};
@@ -29,7 +30,7 @@ pub use typst_ts_compiler::TypstSystemWorld;
 use crate::{
     analysis::{Analysis, AnalysisResources},
     prelude::AnalysisContext,
-    typst_to_lsp, LspPosition, PositionEncoding,
+    typst_to_lsp, LintConfig, LintLevel, LspPosition, PositionEncoding,
 };
 
 struct WrapWorld<'a>(&'a mut TypstSystemWorld);
@@ -46,6 +47,17 @@ impl<'a> AnalysisResources for WrapWorld<'a> {
     fn iter_dependencies(&self, f: &mut dyn FnMut(&reflexo::ImmutPath, typst_ts_compiler::Time)) {
         self.0.iter_dependencies(f)
     }
+
+    fn local_packages(&self) -> EcoVec<PackageSpec> {
+        eco_vec!["@preview/example:0.1.0".parse().unwrap()]
+    }
+
+    fn registry_packages(&self) -> EcoVec<PackageSpec> {
+        ["0.1.0", "0.1.2", "0.2.0", "1.0.0"]
+            .iter()
+            .map(|version| format!("@preview/example:{version}").parse().unwrap())
+            .collect()
+    }
 }
 
 pub fn snapshot_testing(name: &str, f: &impl Fn(&mut AnalysisContext, PathBuf)) {
@@ -75,6 +87,19 @@ pub fn snapshot_testing(name: &str, f: &impl Fn(&mut AnalysisContext, PathBuf))
                         root,
                         position_encoding: PositionEncoding::Utf16,
                         enable_periscope: false,
+                        enable_hover_preview: false,
+                        enable_type_inlay_hints: false,
+                        closing_hint_min_lines: None,
+                        snippets: Default::default(),
+                        providers: Default::default(),
+                        path_completion: Default::default(),
+                        // The rules off by default are checked as well.
+                        lint: LintConfig {
+                            rules: HashMap::from([("*".to_owned(), LintLevel::Hint)]),
+                        },
+                        spell_check: Default::default(),
+                        completion_feat: Default::default(),
+                        memory_budget: None,
                         caches: Default::default(),
                     },
                 );
@@ -1,8 +1,10 @@
 use std::cmp::Reverse;
 use std::collections::HashSet;
+use std::ops::Range;
 
 use ecow::{eco_format, EcoString};
 use if_chain::if_chain;
+use reflexo::path::unix_slash;
 use serde::{Deserialize, Serialize};
 use typst::foundations::{
     fields_on, format_str, mutable_methods_on, repr, AutoValue, CastInfo, Func, Label, NoneValue,
@@ -16,7 +18,10 @@ use typst::visualize::Color;
 use unscanny::Scanner;
 
 use super::{plain_docs_sentence, summarize_font_family};
-use crate::analysis::{analyze_expr, analyze_import, analyze_labels};
+use crate::analysis::{
+    analyze_expr, analyze_import, analyze_labels, FlowSignature, FlowType, ProviderQuery,
+    SnippetScope,
+};
 use crate::AnalysisContext;
 
 mod ext;
@@ -34,23 +39,41 @@ use ext::*;
 /// Passing a `document` (from a previous compilation) is optional, but enhances
 /// the autocompletions. Label completions, for instance, are only generated
 /// when the document is available.
+///
+/// The labels of the completions whose values fit the type expected at the
+/// cursor are returned as well, to rank them first.
+#[allow(clippy::type_complexity)]
 pub fn autocomplete(
     mut ctx: CompletionContext,
-) -> Option<(usize, bool, Vec<Completion>, Vec<lsp_types::CompletionItem>)> {
+) -> Option<(
+    usize,
+    bool,
+    Vec<Completion>,
+    Vec<lsp_types::CompletionItem>,
+    HashSet<EcoString>,
+)> {
     let _ = complete_comments(&mut ctx)
         || complete_literal(&mut ctx).is_none() && {
             log::info!("continue after completing literal");
             complete_field_accesses(&mut ctx)
                 || complete_open_labels(&mut ctx)
                 || complete_imports(&mut ctx)
+                || complete_show_selector(&mut ctx)
                 || complete_rules(&mut ctx)
                 || complete_params(&mut ctx)
                 || complete_markup(&mut ctx)
                 || complete_math(&mut ctx)
                 || complete_code(&mut ctx)
         };
-
-    Some((ctx.from, ctx.incomplete, ctx.completions, ctx.completions2))
+    ctx.provider_completions();
+
+    Some((
+        ctx.from,
+        ctx.incomplete,
+        ctx.completions,
+        ctx.completions2,
+        ctx.fitting,
+    ))
 }
 
 /// An autocompletion option.
@@ -73,6 +96,9 @@ pub struct Completion {
     pub detail: Option<EcoString>,
     /// An optional command to run when the completion is selected.
     pub command: Option<&'static str>,
+    /// Whether the completed item is deprecated.
+    #[serde(default)]
+    pub deprecated: bool,
 }
 
 /// A kind of item that can be completed.
@@ -92,6 +118,8 @@ pub enum CompletionKind {
     /// A constant.
     #[default]
     Constant,
+    /// A color.
+    Color,
     /// A symbol.
     Symbol(char),
     /// A variable.
@@ -114,6 +142,26 @@ fn complete_comments(ctx: &mut CompletionContext) -> bool {
 
 /// Complete in markup mode.
 fn complete_markup(ctx: &mut CompletionContext) -> bool {
+    // The language of a raw block: "```|" or "```py|".
+    if ctx.leaf.parent_kind() == Some(SyntaxKind::Raw) {
+        let opening = ctx.leaf.kind() == SyntaxKind::RawDelim
+            && ctx.leaf.index() == 0
+            && ctx.leaf.len() >= 3
+            && ctx.leaf.range().end == ctx.cursor;
+        if ctx.leaf.kind() == SyntaxKind::RawLang || opening {
+            ctx.from = if opening {
+                ctx.cursor
+            } else {
+                ctx.leaf.offset()
+            };
+            ctx.raw_completions();
+            ctx.raw_show_rule_completions();
+            return true;
+        }
+
+        return false;
+    }
+
     // Bail if we aren't even in markup.
     if !matches!(
         ctx.leaf.parent_kind(),
@@ -143,6 +191,15 @@ fn complete_markup(ctx: &mut CompletionContext) -> bool {
         return true;
     }
 
+    // An emoji shorthand: ":smi|".
+    if ctx.leaf.kind() == SyntaxKind::Text {
+        if let Some((colon, name)) = emoji_shorthand(ctx.before, ctx.leaf.offset()) {
+            ctx.from = colon;
+            ctx.emoji_shorthand_completions(name);
+            return true;
+        }
+    }
+
     // Behind a half-completed binding: "#let x = |".
     if_chain! {
         if let Some(prev) = ctx.leaf.prev_leaf();
@@ -290,6 +347,8 @@ fn markup_completions(ctx: &mut CompletionContext) {
         "$ ${sum_x^2} $",
         "Inserts a block-level mathematical equation.",
     );
+
+    ctx.user_snippet_completions(SnippetScope::Markup);
 }
 
 /// Complete in math mode.
@@ -350,6 +409,8 @@ fn math_completions(ctx: &mut CompletionContext) {
         "${x}/${y}",
         "Inserts a fraction.",
     );
+
+    ctx.user_snippet_completions(SnippetScope::Math);
 }
 
 /// Complete field accesses.
@@ -364,10 +425,9 @@ fn complete_field_accesses(ctx: &mut CompletionContext) -> bool {
         if prev.is::<ast::Expr>();
         if prev.parent_kind() != Some(SyntaxKind::Markup) ||
            prev.prev_sibling_kind() == Some(SyntaxKind::Hash);
-        if let Some((value, styles)) = analyze_expr(ctx.world(), &prev).into_iter().next();
+        if target_field_completions(ctx, &prev);
         then {
             ctx.from = ctx.cursor;
-            field_access_completions(ctx, &value, &styles);
             return true;
         }
     }
@@ -379,14 +439,41 @@ fn complete_field_accesses(ctx: &mut CompletionContext) -> bool {
         if prev.kind() == SyntaxKind::Dot;
         if let Some(prev_prev) = prev.prev_sibling();
         if prev_prev.is::<ast::Expr>();
-        if let Some((value, styles)) = analyze_expr(ctx.world(), &prev_prev).into_iter().next();
+        if target_field_completions(ctx, &prev_prev);
         then {
             ctx.from = ctx.leaf.offset();
-            field_access_completions(ctx, &value, &styles);
             return true;
         }
     }
 
+    // Behind the name of a package not imported yet: "cetz.|".
+    if_chain! {
+        if ctx.leaf.kind() == SyntaxKind::Dot;
+        if ctx.leaf.range().end == ctx.cursor;
+        if ctx.package_import_completions();
+        then {
+            ctx.from = ctx.cursor;
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Add completions for the fields of the target of a field access, by its
+/// value, or by its type if it can't be evaluated, e.g. the element shown by
+/// `it` in `show figure: it => it.|`.
+fn target_field_completions(ctx: &mut CompletionContext, target: &LinkedNode) -> bool {
+    if let Some((value, styles)) = analyze_expr(ctx.world(), target).into_iter().next() {
+        field_access_completions(ctx, &value, &styles);
+        return true;
+    }
+
+    if let Some(elem) = shown_element(ctx, target) {
+        element_field_completions(ctx, elem);
+        return true;
+    }
+
     false
 }
 
@@ -436,6 +523,7 @@ fn field_access_completions(ctx: &mut CompletionContext, value: &Value, styles:
                     ctx.completions.push(Completion {
                         kind: CompletionKind::Symbol(modified.get()),
                         label: modifier.into(),
+                        label_detail: Some(modified.get().into()),
                         ..Completion::default()
                     });
                 }
@@ -587,13 +675,6 @@ fn complete_rules(ctx: &mut CompletionContext) -> bool {
         return true;
     }
 
-    // Behind the show keyword: "show |".
-    if matches!(prev.kind(), SyntaxKind::Show) {
-        ctx.from = ctx.cursor;
-        show_rule_selector_completions(ctx);
-        return true;
-    }
-
     // Behind a half-completed show rule: "show strong: |".
     if_chain! {
         if let Some(prev) = ctx.leaf.prev_leaf();
@@ -622,26 +703,81 @@ fn set_rule_completions(ctx: &mut CompletionContext) {
     });
 }
 
-/// Add completions for selectors.
-fn show_rule_selector_completions(ctx: &mut CompletionContext) {
+/// Complete the selector of a show rule: "show |", "show hea|", or
+/// "show hea|: it => it".
+fn complete_show_selector(ctx: &mut CompletionContext) -> bool {
+    let Some(colon) = show_selector_position(&ctx.leaf) else {
+        return false;
+    };
+
+    ctx.from = match ctx.leaf.kind() {
+        SyntaxKind::Ident => ctx.leaf.offset(),
+        _ => ctx.cursor,
+    };
+    show_rule_selector_completions(ctx, colon);
+    true
+}
+
+/// Add completions for selectors, followed by a colon unless it is already
+/// typed.
+fn show_rule_selector_completions(ctx: &mut CompletionContext, colon: bool) {
     ctx.scope_completions(
         false,
         |value| matches!(value, Value::Func(func) if func.element().is_some()),
     );
-
-    ctx.enrich("", ": ");
+    ctx.label_completions();
 
     ctx.snippet_completion(
         "text selector",
-        "\"${text}\": ${}",
+        "\"${text}\"",
         "Replace occurrences of specific text.",
     );
 
     ctx.snippet_completion(
         "regex selector",
-        "regex(\"${regex}\"): ${}",
+        "regex(\"${regex}\")",
         "Replace matches of a regular expression.",
     );
+
+    ctx.snippet_completion(
+        "label selector",
+        "<${label}>",
+        "Transform the elements with a label.",
+    );
+
+    ctx.snippet_completion(
+        "where selector",
+        "${element}.where(${field}: ${value})",
+        "Transform the elements whose fields have specific values.",
+    );
+
+    ctx.snippet_completion(
+        "or selector",
+        "selector(${element}).or(${other})",
+        "Transform the elements matching either of the selectors.",
+    );
+
+    ctx.snippet_completion(
+        "and selector",
+        "selector(${element}).and(${other})",
+        "Transform the elements matching both of the selectors.",
+    );
+
+    ctx.snippet_completion(
+        "before selector",
+        "selector(${element}).before(${end})",
+        "Transform the elements before the end, e.g. a label.",
+    );
+
+    ctx.snippet_completion(
+        "after selector",
+        "selector(${element}).after(${start})",
+        "Transform the elements after the start, e.g. a label.",
+    );
+
+    if !colon {
+        ctx.enrich("", ": ");
+    }
 }
 
 /// Add completions for recipes.
@@ -664,6 +800,14 @@ fn show_rule_recipe_completions(ctx: &mut CompletionContext) {
         "Transform the element with a function.",
     );
 
+    // A show rule expects a function from the selected element to content.
+    ctx.closure_completion(&FlowSignature {
+        pos: vec![FlowType::Content],
+        named: vec![],
+        rest: None,
+        ret: FlowType::Content,
+    });
+
     ctx.scope_completions(false, |value| matches!(value, Value::Func(_)));
 }
 
@@ -943,6 +1087,12 @@ fn code_completions(ctx: &mut CompletionContext, hash: bool) {
             "Creates an unnamed function.",
         );
     }
+
+    ctx.user_snippet_completions(SnippetScope::Code);
+
+    if in_context(&ctx.leaf) {
+        ctx.context_completions();
+    }
 }
 
 /// Context for autocompletion.
@@ -961,6 +1111,8 @@ pub struct CompletionContext<'a, 'w> {
     pub completions2: Vec<lsp_types::CompletionItem>,
     pub incomplete: bool,
     pub seen_casts: HashSet<u128>,
+    /// The labels of the completions whose values fit the expected type.
+    pub fitting: HashSet<EcoString>,
 }
 
 impl<'a, 'w> CompletionContext<'a, 'w> {
@@ -990,6 +1142,7 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
             completions: vec![],
             completions2: vec![],
             seen_casts: HashSet::new(),
+            fitting: HashSet::new(),
         })
     }
 
@@ -1026,9 +1179,114 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
             // VS Code doesn't do that... Auto triggering suggestion only happens on typing (word
             // starts or trigger characters). However, you can use editor.action.triggerSuggest as
             // command on a suggestion to "manually" retrigger suggest after inserting one
-            //
-            // todo: only vscode and neovim (0.9.1) support this
-            command: Some("editor.action.triggerSuggest"),
+            command: self.trigger_suggest(),
+            ..Completion::default()
+        });
+    }
+
+    /// Add completions for the user-defined snippets in the given scope.
+    fn user_snippet_completions(&mut self, scope: SnippetScope) {
+        let analysis = &mut self.ctx.analysis;
+        let root = analysis.root.clone();
+        analysis.snippets.refresh(&root);
+
+        let snippets = analysis
+            .snippets
+            .in_scope(scope)
+            .cloned()
+            .collect::<Vec<_>>();
+        let command = self.trigger_suggest();
+        for snippet in snippets {
+            self.completions.push(Completion {
+                kind: CompletionKind::Syntax,
+                label: snippet.label,
+                apply: Some(snippet.body),
+                detail: snippet.description,
+                command,
+                ..Completion::default()
+            });
+        }
+    }
+
+    /// Add completions from the third-party providers.
+    fn provider_completions(&mut self) {
+        if self.ctx.analysis.providers.is_empty() {
+            return;
+        }
+        let Some(id) = self.root.span().id() else {
+            return;
+        };
+        let Ok(source) = self.ctx.source_by_id(id) else {
+            return;
+        };
+        let path = unix_slash(id.vpath().as_rootless_path());
+        let Some(query) = ProviderQuery::new(&source, path, self.cursor) else {
+            return;
+        };
+
+        for completion in self.ctx.analysis.providers.complete(&query) {
+            self.completions.push(Completion {
+                kind: CompletionKind::Syntax,
+                label: completion.label,
+                apply: completion.apply,
+                detail: completion.detail,
+                ..Completion::default()
+            });
+        }
+    }
+
+    /// The range of the string literal at the cursor, if the cursor is
+    /// between its quotes.
+    fn str_literal_range(&self) -> Option<Range<usize>> {
+        if self.leaf.kind() != SyntaxKind::Str {
+            return None;
+        }
+
+        let range = self.leaf.range();
+        let end = if self.str_literal_closed() {
+            range.end - 1
+        } else {
+            range.end
+        };
+        (range.start < self.cursor && self.cursor <= end).then_some(range)
+    }
+
+    fn str_literal_closed(&self) -> bool {
+        let text = self.leaf.text();
+        text.len() > 1 && text.ends_with('"')
+    }
+
+    /// Add a completion for a string value.
+    ///
+    /// If the cursor is inside a string literal, the value is completed
+    /// between the quotes. Otherwise, a quoted string is inserted.
+    fn str_completion(
+        &mut self,
+        value: &str,
+        detail: Option<EcoString>,
+        label_detail: Option<EcoString>,
+    ) {
+        let quoted = value.repr();
+        let (label, apply) = match self.str_literal_range() {
+            Some(range) => {
+                self.from = range.start + 1;
+                let escaped = &quoted[1..quoted.len() - 1];
+                let apply = if self.str_literal_closed() {
+                    escaped.into()
+                } else {
+                    eco_format!("{escaped}\"")
+                };
+                (value.into(), apply)
+            }
+            None => (quoted.clone(), quoted),
+        };
+
+        self.completions.push(Completion {
+            kind: CompletionKind::Constant,
+            label,
+            apply: Some(apply),
+            detail,
+            label_detail,
             ..Completion::default()
         });
     }
@@ -1136,6 +1394,11 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
         parens: bool,
         docs: Option<&str>,
     ) {
+        if let (None, Value::Str(value)) = (&label, value) {
+            self.str_completion(value, docs.map(Into::into), None);
+            return;
+        }
+
         let at = label.as_deref().is_some_and(|field| !is_ident(field));
         let label = label.unwrap_or_else(|| value.repr());
 
@@ -1153,7 +1416,7 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
         let mut command = None;
         if parens && matches!(value, Value::Func(_)) {
             if let Value::Func(func) = value {
-                command = Some("editor.action.triggerSuggest");
+                command = self.trigger_suggest();
                 if func
                     .params()
                     .is_some_and(|params| params.iter().all(|param| param.name == "self"))
@@ -1165,10 +1428,6 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
             }
         } else if at {
             apply = Some(eco_format!("at(\"{label}\")"));
-        } else if label.starts_with('"') && self.after.starts_with('"') {
-            if let Some(trimmed) = label.strip_suffix('"') {
-                apply = Some(trimmed.into());
-            }
         }
 
         self.completions.push(Completion {
@@ -1176,12 +1435,18 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
                 Value::Func(_) => CompletionKind::Func,
                 Value::Type(_) => CompletionKind::Type,
                 Value::Symbol(s) => CompletionKind::Symbol(s.get()),
+                Value::Color(_) => CompletionKind::Color,
                 _ => CompletionKind::Constant,
             },
             label,
             apply,
             detail,
-            label_detail: None,
+            // Clients may render the hex as a swatch.
+            label_detail: match value {
+                Value::Color(color) => Some(color.to_hex()),
+                Value::Symbol(symbol) => Some(symbol.get().into()),
+                _ => None,
+            },
             command,
             ..Completion::default()
         });
@@ -1194,6 +1459,16 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
             return;
         }
 
+        // Inside a string literal, only string values are completed.
+        if self.str_literal_range().is_some()
+            && !matches!(
+                cast,
+                CastInfo::Value(Value::Str(..), _) | CastInfo::Union(..)
+            )
+        {
+            return;
+        }
+
         match cast {
             CastInfo::Any => {}
             CastInfo::Value(value, docs) => {
@@ -1,26 +1,33 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Range;
 
 use ecow::{eco_format, EcoString};
 use lsp_types::{CompletionItem, CompletionTextEdit, InsertTextFormat, TextEdit};
 use once_cell::sync::OnceCell;
 use reflexo::path::{unix_slash, PathClean};
-use typst::foundations::{AutoValue, Func, Label, NoneValue, Type, Value};
-use typst::layout::{Dir, Length};
+use typst::foundations::{
+    Array, AutoValue, CastInfo, Content, Dict, Element, Func, Label, NoneValue, Str, Type, Value,
+};
+use typst::layout::{Dir, Length, Ratio, Rel};
 use typst::syntax::ast::AstNode;
-use typst::syntax::{ast, Span, SyntaxKind};
-use typst::visualize::Color;
+use typst::syntax::{ast, is_ident, Span, SyntaxKind};
+use typst::visualize::{Color, Stroke};
 
 use super::{Completion, CompletionContext, CompletionKind};
 use crate::analysis::{
-    analyze_dyn_signature, analyze_import, resolve_call_target, FlowBuiltinType, FlowRecord,
-    FlowType, PathPreference, FLOW_INSET_DICT, FLOW_MARGIN_DICT, FLOW_OUTSET_DICT,
+    analyze_dyn_signature, analyze_import, analyze_type_signatures, find_definition,
+    find_deprecation, resolve_call_target, CallConvention, Export, FlowBuiltinType, FlowRecord,
+    FlowSignature, FlowType, PathPreference, FLOW_INSET_DICT, FLOW_MARGIN_DICT, FLOW_OUTSET_DICT,
     FLOW_RADIUS_DICT, FLOW_STROKE_DICT,
 };
-use crate::syntax::{get_non_strict_def_target, param_index_at_leaf, DefTarget};
+use crate::completion::fuzzy::{cmp_sort_text, fuzzy_score};
+use crate::i18n::tr;
+use crate::syntax::{param_index_at_leaf, scan_workspace_files_with, DerefTarget};
 use crate::upstream::complete::complete_code;
 use crate::upstream::plain_docs_sentence;
 
-use crate::{prelude::*, typst_to_lsp::completion_kind, LspCompletion};
+use crate::typst_to_lsp::{completion_kind, snippet_text};
+use crate::{prelude::*, LspCompletion};
 
 impl<'a, 'w> CompletionContext<'a, 'w> {
     pub fn world(&self) -> &'w dyn typst::World {
@@ -31,10 +38,96 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
         self.scope_completions_(parens, |v| v.map_or(true, &filter));
     }
 
+    /// The command to trigger suggestions again after inserting a completion,
+    /// if the client supports it.
+    pub fn trigger_suggest(&self) -> Option<&'static str> {
+        self.ctx.analysis.completion_feat.trigger_suggest_command()
+    }
+
     pub fn strict_scope_completions(&mut self, parens: bool, filter: impl Fn(&Value) -> bool) {
         self.scope_completions_(parens, |v| v.map_or(false, &filter));
     }
 
+    /// Add completions for the raw languages registered by the show rules of
+    /// the source, e.g. `mermaid` in `show raw.where(lang: "mermaid")`.
+    pub fn raw_show_rule_completions(&mut self) {
+        let mut langs = BTreeSet::new();
+        collect_raw_show_rule_langs(&self.root, &mut langs);
+
+        for lang in langs {
+            if !is_ident(&lang)
+                || self
+                    .completions
+                    .iter()
+                    .any(|c| c.label == lang || c.apply.as_ref() == Some(&lang))
+            {
+                continue;
+            }
+
+            self.completions.push(Completion {
+                kind: CompletionKind::Constant,
+                label: lang,
+                detail: Some(tr("completion.raw-lang", "A language with a raw show rule.").into()),
+                ..Completion::default()
+            });
+        }
+    }
+
+    /// Add completions for the functions depending on the context, e.g.
+    /// `counter(..).get()`, and rank them before the other completions.
+    pub fn context_completions(&mut self) {
+        let start = self.completions.len();
+        for &(label, snippet, docs) in CONTEXT_SNIPPETS {
+            self.snippet_completion(label, snippet, docs);
+        }
+
+        for (i, c) in self.completions.iter_mut().enumerate() {
+            let boosted = if i >= start {
+                i - start
+            } else if c.kind == CompletionKind::Func && CONTEXT_FUNCS.contains(&c.label.as_str()) {
+                CONTEXT_SNIPPETS.len()
+            } else {
+                continue;
+            };
+            c.sort_text = Some(eco_format!("{boosted:03}"));
+        }
+    }
+
+    /// Add completions for the emojis fuzzily matching the `name` typed in a
+    /// shorthand like `:smi`, inserting the symbol access `#emoji.smile`.
+    pub fn emoji_shorthand_completions(&mut self, name: &str) {
+        let scope = self.world().library().global.scope();
+        let Some(Value::Module(emoji)) = scope.get("emoji") else {
+            return;
+        };
+
+        for (base, value) in emoji.scope().iter() {
+            let Value::Symbol(symbol) = value else {
+                continue;
+            };
+
+            for (modifiers, c) in symbol.variants() {
+                let path = if modifiers.is_empty() {
+                    base.clone()
+                } else {
+                    eco_format!("{base}.{modifiers}")
+                };
+                if fuzzy_score(name, &path).is_none() {
+                    continue;
+                }
+
+                self.completions.push(Completion {
+                    kind: CompletionKind::Symbol(c),
+                    label: eco_format!(":{path}:"),
+                    label_detail: Some(c.into()),
+                    apply: Some(eco_format!("#emoji.{path}")),
+                    detail: Some(eco_format!("The emoji {c}.")),
+                    ..Completion::default()
+                });
+            }
+        }
+    }
+
     /// Add completions for definitions that are available at the cursor.
     ///
     /// Filters the global/math scope with the given filter.
@@ -144,12 +237,27 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
         let scope = if in_math { &lib.math } else { &lib.global }
             .scope()
             .clone();
+        let version = self.ctx.typst_version();
         for (name, value) in scope.iter() {
             if filter(Some(value)) && !defined.contains_key(name) {
                 self.value_completion(Some(name.clone()), value, parens, None);
+
+                // Points to the replacement of the deprecated items.
+                if let Some(deprecation) = find_deprecation(name, version) {
+                    if let Some(compl) = self.completions.last_mut() {
+                        compl.deprecated = true;
+                        compl.detail = Some(deprecation.detail());
+                    }
+                }
             }
         }
 
+        if filter(None) {
+            let shadowed = |name: &str| defined.contains_key(name) || scope.get(name).is_some();
+            self.auto_import_completions(shadowed);
+        }
+
+        let trigger_suggest = self.trigger_suggest();
         for (name, kind) in defined {
             if filter(None) && !name.is_empty() {
                 if kind == CompletionKind::Func {
@@ -158,8 +266,7 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
                         kind: kind.clone(),
                         label: eco_format!("{}.with", name),
                         apply: Some(apply),
-                        // todo: only vscode and neovim (0.9.1) support this
-                        command: Some("editor.action.triggerSuggest"),
+                        command: trigger_suggest,
                         ..Default::default()
                     });
                     let apply = eco_format!("{}.where(${{}})", name);
@@ -167,8 +274,7 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
                         kind: kind.clone(),
                         label: eco_format!("{}.where", name),
                         apply: Some(apply),
-                        // todo: only vscode and neovim (0.9.1) support this
-                        command: Some("editor.action.triggerSuggest"),
+                        command: trigger_suggest,
                         ..Default::default()
                     });
                     // todo: check arguments, if empty, jump to after the parens
@@ -177,8 +283,7 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
                         kind: kind.clone(),
                         label: name,
                         apply: Some(apply),
-                        // todo: only vscode and neovim (0.9.1) support this
-                        command: Some("editor.action.triggerSuggest"),
+                        command: trigger_suggest,
                         ..Completion::default()
                     });
                 } else {
@@ -191,9 +296,422 @@ impl<'a, 'w> CompletionContext<'a, 'w> {
             }
         }
     }
+
+    /// Add a completion for a closure matching the expected signature, e.g.
+    /// `it => ..` or `(acc, x) => ..`.
+    pub fn closure_completion(&mut self, sig: &FlowSignature) {
+        let names = closure_param_names(sig);
+        let params = names
+            .iter()
+            .map(|name| eco_format!("${{{name}}}"))
+            .join(", ");
+        let (label, apply) = match names.as_slice() {
+            [name] => (
+                eco_format!("{name} => .."),
+                eco_format!("{params} => ${{}}"),
+            ),
+            _ => (
+                eco_format!("({}) => ..", names.iter().join(", ")),
+                eco_format!("({params}) => ${{}}"),
+            ),
+        };
+
+        self.completions.push(Completion {
+            kind: CompletionKind::Syntax,
+            label,
+            apply: Some(apply),
+            detail: Some(eco_format!(
+                "A closure taking {} argument{}.",
+                names.len(),
+                if names.len() == 1 { "" } else { "s" }
+            )),
+            ..Completion::default()
+        });
+    }
+}
+
+/// Names the positional parameters of a closure with the given signature.
+///
+/// Names are taken from the variables of the signature if any. Otherwise, a
+/// sole parameter is named `it`, and a first parameter of the same type as the
+/// result, as in `array.fold`, is named `acc`.
+fn closure_param_names(sig: &FlowSignature) -> Vec<EcoString> {
+    const FALLBACK: [&str; 3] = ["x", "y", "z"];
+
+    let mut fallback = FALLBACK.iter();
+    let mut names = Vec::with_capacity(sig.pos.len());
+    for (i, ty) in sig.pos.iter().enumerate() {
+        let name = match ty {
+            FlowType::Var(v) => v.1.clone(),
+            _ if sig.pos.len() == 1 => "it".into(),
+            _ if i == 0 && typst::util::hash128(ty) == typst::util::hash128(&sig.ret) => {
+                "acc".into()
+            }
+            _ => match fallback.next() {
+                Some(name) => (*name).into(),
+                None => eco_format!("arg{i}"),
+            },
+        };
+        names.push(name);
+    }
+
+    names
+}
+
+impl<'a, 'w> CompletionContext<'a, 'w> {
+    /// Add completions for definitions exported by other files in the
+    /// workspace and by the locally available packages, which additionally
+    /// insert an import of the definition at the top of the current file.
+    ///
+    /// Only offered when completing an existing identifier, to avoid flooding
+    /// the list with the exports of the whole workspace.
+    fn auto_import_completions(&mut self, shadowed: impl Fn(&str) -> bool) {
+        if self.leaf.kind() != SyntaxKind::Ident && self.leaf.kind() != SyntaxKind::MathIdent {
+            return;
+        }
+        let Some(current) = self.root.span().id() else {
+            return;
+        };
+        let Ok(source) = self.ctx.source_by_id(current) else {
+            return;
+        };
+
+        // The definitions of the workspace are not accessed on a target, e.g.
+        // `cetz.ca|`.
+        let in_access = self.leaf.prev_sibling_kind() == Some(SyntaxKind::Dot);
+        if !in_access {
+            let replace_range = self.ctx.to_lsp_range(self.leaf.range(), &source);
+            for (id, name, kind) in workspace_exports(self.ctx, current) {
+                if shadowed(&name) {
+                    continue;
+                }
+                let Some(path) = relative_import_path(current, id) else {
+                    continue;
+                };
+
+                let import = format!("#import \"{path}\": {name}\n");
+                let detail = format!("import from \"{path}\"");
+                self.import_completion(name.clone(), name, kind, detail, import, replace_range);
+            }
+        }
+
+        self.package_import_completions();
+    }
+
+    /// Add completions for definitions exported by the locally available
+    /// packages, accessed on the package name, e.g. `cetz.canvas`, which
+    /// additionally insert an import of the package at the top of the current
+    /// file. Returns whether any completion is added.
+    ///
+    /// Only offered for the packages whose names match the typed text, e.g.
+    /// `ce|`, `cetz.|`, or `cetz.ca|`.
+    pub fn package_import_completions(&mut self) -> bool {
+        let Some((typed, range)) = self.typed_access() else {
+            return false;
+        };
+        let Some(current) = self.root.span().id() else {
+            return false;
+        };
+        let Ok(source) = self.ctx.source_by_id(current) else {
+            return false;
+        };
+        let replace_range = self.ctx.to_lsp_range(range, &source);
+
+        let mut added = false;
+        let index = self.ctx.package_export_index();
+        for (spec, exports) in index.packages.iter() {
+            let matches = match typed.split_once('.') {
+                Some((target, _)) => target == spec.name.as_str(),
+                None => spec.name.starts_with(typed.as_str()),
+            };
+            let prefix = eco_format!("\"@{}/{}:", spec.namespace, spec.name);
+            if !matches || self.text.contains(prefix.as_str()) {
+                continue;
+            }
+
+            let import = format!("#import \"{spec}\"\n");
+            let detail = format!("import \"{spec}\"");
+            for export in exports.iter() {
+                let (name, kind) = (&export.name, export_kind(export));
+                let access = eco_format!("{}.{name}", spec.name);
+                let (label, import, detail) = (access.clone(), import.clone(), detail.clone());
+                self.import_completion(label, access, kind, detail, import, replace_range);
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// Gets the text typed for a package-qualified name and the range to
+    /// replace by it, which spans the package name if it is typed, e.g.
+    /// `ce|`, `cetz.|`, or `cetz.ca|`.
+    fn typed_access(&self) -> Option<(EcoString, Range<usize>)> {
+        let target_of = |dot: &LinkedNode| {
+            let target = dot.prev_sibling()?;
+            matches!(target.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent).then_some(target)
+        };
+
+        match self.leaf.kind() {
+            SyntaxKind::Ident | SyntaxKind::MathIdent => {
+                let typed = self
+                    .leaf
+                    .text()
+                    .get(..self.cursor.checked_sub(self.leaf.offset())?)?;
+                match self.leaf.prev_sibling() {
+                    Some(dot) if dot.kind() == SyntaxKind::Dot => {
+                        let target = target_of(&dot)?;
+                        let typed = eco_format!("{}.{typed}", target.text());
+                        Some((typed, target.offset()..self.leaf.range().end))
+                    }
+                    _ if typed.is_empty() => None,
+                    _ => Some((typed.into(), self.leaf.range())),
+                }
+            }
+            SyntaxKind::Dot => {
+                let target = target_of(&self.leaf)?;
+                let typed = eco_format!("{}.", target.text());
+                Some((typed, target.offset()..self.leaf.range().end))
+            }
+            _ => None,
+        }
+    }
+
+    /// Add a completion which additionally inserts an import at the top of
+    /// the current file.
+    fn import_completion(
+        &mut self,
+        label: EcoString,
+        access: EcoString,
+        kind: CompletionKind,
+        detail: String,
+        import: String,
+        replace_range: LspRange,
+    ) {
+        let apply = if kind == CompletionKind::Func {
+            eco_format!("{access}(${{}})")
+        } else {
+            access
+        };
+        let import_at = LspRange::new(LspPosition::new(0, 0), LspPosition::new(0, 0));
+        let (apply, format) = snippet_text(&apply, self.ctx.analysis.completion_feat);
+
+        self.completions2.push(LspCompletion {
+            label: label.to_string(),
+            kind: Some(completion_kind(kind)),
+            detail: Some(detail),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                replace_range,
+                apply,
+            ))),
+            additional_text_edits: Some(vec![TextEdit::new(import_at, import)]),
+            insert_text_format: Some(format),
+            ..Default::default()
+        });
+    }
+}
+
+/// The functions whose results depend on the context.
+const CONTEXT_FUNCS: &[&str] = &["counter", "here", "locate", "measure", "query", "state"];
+
+/// The snippets of the functions depending on the context, with the methods
+/// commonly chained to them.
+const CONTEXT_SNIPPETS: &[(&str, &str, &str)] = &[
+    (
+        "counter value",
+        "counter(${heading}).get()",
+        "Gets the value of a counter at the current location.",
+    ),
+    (
+        "counter display",
+        "counter(${page}).display()",
+        "Displays the value of a counter at the current location with its numbering.",
+    ),
+    (
+        "counter final",
+        "counter(${heading}).final()",
+        "Gets the value of a counter at the end of the document.",
+    ),
+    (
+        "counter at",
+        "counter(${heading}).at(${label})",
+        "Gets the value of a counter at a location, e.g. a label.",
+    ),
+    (
+        "state value",
+        "state(${key}).get()",
+        "Gets the value of a state at the current location.",
+    ),
+    (
+        "query",
+        "query(${selector})",
+        "Finds the elements matching a selector in the document.",
+    ),
+    (
+        "query before",
+        "query(selector(${heading}).before(here()))",
+        "Finds the elements matching a selector before the current location.",
+    ),
+    (
+        "query after",
+        "query(selector(${heading}).after(here()))",
+        "Finds the elements matching a selector after the current location.",
+    ),
+    (
+        "here page",
+        "here().page()",
+        "Gets the page number of the current location.",
+    ),
+    (
+        "here position",
+        "here().position()",
+        "Gets the position of the current location on its page.",
+    ),
+    (
+        "measure",
+        "measure(${content})",
+        "Measures the size of content in the current styles.",
+    ),
+];
+
+/// Checks whether a leaf is in a context expression, e.g. `context { .. }`,
+/// where the functions depending on the context can be called.
+pub(super) fn in_context(leaf: &LinkedNode) -> bool {
+    if leaf
+        .prev_leaf()
+        .is_some_and(|prev| prev.kind() == SyntaxKind::Context)
+    {
+        return true;
+    }
+
+    let mut node = leaf.parent();
+    while let Some(parent) = node {
+        if parent.kind() == SyntaxKind::Contextual {
+            return true;
+        }
+        node = parent.parent();
+    }
+    false
+}
+
+/// Finds an emoji shorthand like `:smi` ending at the cursor, returning the
+/// offset of the colon and the typed name.
+///
+/// The colon must start a word no earlier than the `leaf_offset`, and the name
+/// must be nonempty.
+pub(super) fn emoji_shorthand(before: &str, leaf_offset: usize) -> Option<(usize, &str)> {
+    let colon = before.rfind(':')?;
+    let name = &before[colon + 1..];
+    if colon < leaf_offset
+        || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return None;
+    }
+
+    let word_start = before[..colon]
+        .chars()
+        .next_back()
+        .map_or(true, char::is_whitespace);
+    word_start.then_some((colon, name))
+}
+
+/// Checks whether a leaf is at the selector of a show rule, i.e. behind the
+/// `show` keyword and before the colon, returning whether the colon is already
+/// typed.
+pub(super) fn show_selector_position(leaf: &LinkedNode) -> Option<bool> {
+    let show_rule = match leaf.kind() {
+        SyntaxKind::Ident if leaf.parent_kind() == Some(SyntaxKind::ShowRule) => leaf.parent()?,
+        kind if kind.is_trivia() => {
+            let prev = leaf.prev_leaf()?;
+            if prev.kind() != SyntaxKind::Show {
+                return None;
+            }
+            prev.parent()?
+        }
+        _ => return None,
+    };
+
+    let colon = show_rule.children().find(|c| c.kind() == SyntaxKind::Colon);
+    match colon {
+        Some(colon) if colon.offset() < leaf.range().end => None,
+        colon => Some(colon.is_some()),
+    }
+}
+
+/// Collects the `lang` constraints of `raw.where(..)` selectors in show rules.
+fn collect_raw_show_rule_langs(node: &LinkedNode, langs: &mut BTreeSet<EcoString>) {
+    if let Some(show) = node.cast::<ast::ShowRule>() {
+        if let Some(ast::Expr::FuncCall(call)) = show.selector() {
+            let is_raw_where = match call.callee() {
+                ast::Expr::FieldAccess(access) => {
+                    let target = access.target();
+                    access.field().as_str() == "where"
+                        && matches!(target, ast::Expr::Ident(ident) if ident.as_str() == "raw")
+                }
+                _ => false,
+            };
+            if is_raw_where {
+                for arg in call.args().items() {
+                    if let ast::Arg::Named(named) = arg {
+                        if let ("lang", ast::Expr::Str(lang)) =
+                            (named.name().as_str(), named.expr())
+                        {
+                            langs.insert(lang.get());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for child in node.children() {
+        collect_raw_show_rule_langs(&child, langs);
+    }
 }
 
-fn sort_and_explicit_code_completion(ctx: &mut CompletionContext) {
+/// Collects the top-level definitions of the workspace files other than the
+/// `current` one.
+fn workspace_exports(
+    ctx: &mut AnalysisContext,
+    current: TypstFileId,
+) -> Vec<(TypstFileId, EcoString, CompletionKind)> {
+    let index = ctx.export_index();
+    let files = index.files.iter().filter(|(id, _)| *id != current);
+    let exports = files.flat_map(|(id, exports)| {
+        let exports = exports.iter();
+        exports.map(|export| (*id, export.name.clone(), export_kind(export)))
+    });
+    exports.collect()
+}
+
+/// The kind of the completion of an exported definition.
+fn export_kind(export: &Export) -> CompletionKind {
+    if export.is_func {
+        CompletionKind::Func
+    } else {
+        CompletionKind::Variable
+    }
+}
+
+/// Computes the path to import the file `target` from the file `current`. The
+/// files in a package are imported by paths only from the same package.
+fn relative_import_path(current: TypstFileId, target: TypstFileId) -> Option<EcoString> {
+    if current.package() != target.package() {
+        return None;
+    }
+    let current = current.vpath().as_rooted_path();
+    let target = target.vpath().as_rooted_path();
+    let path = pathdiff::diff_paths(target, current.parent()?)?;
+    Some(unix_slash(&path).into())
+}
+
+/// Sorts the completions, then appends the rest code completions.
+///
+/// The rest completions whose values fit the `expected` type at the cursor are
+/// ranked before the others.
+fn sort_and_explicit_code_completion(ctx: &mut CompletionContext, expected: Option<&FlowType>) {
     let mut completions = std::mem::take(&mut ctx.completions);
     let explict = ctx.explicit;
     ctx.explicit = true;
@@ -206,6 +724,13 @@ fn sort_and_explicit_code_completion(ctx: &mut CompletionContext) {
         ctx.completions
     );
 
+    let fitting = expected
+        .map(|expected| fitting_labels(ctx, expected))
+        .unwrap_or_default();
+    // The completions are ranked by the fuzzy scores again if an identifier is
+    // being completed, where the fitting ones are boosted as well.
+    ctx.fitting.extend(fitting.iter().cloned());
+
     completions.sort_by(|a, b| {
         a.sort_text
             .as_ref()
@@ -213,9 +738,10 @@ fn sort_and_explicit_code_completion(ctx: &mut CompletionContext) {
             .then_with(|| a.label.cmp(&b.label))
     });
     ctx.completions.sort_by(|a, b| {
-        a.sort_text
-            .as_ref()
-            .cmp(&b.sort_text.as_ref())
+        let fits = |c: &Completion| fitting.contains(&c.label);
+        fits(b)
+            .cmp(&fits(a))
+            .then_with(|| cmp_sort_text(&a.sort_text, &b.sort_text))
             .then_with(|| a.label.cmp(&b.label))
     });
 
@@ -245,6 +771,141 @@ fn sort_and_explicit_code_completion(ctx: &mut CompletionContext) {
     log::debug!("sort_and_explicit_code_completion: {:?}", ctx.completions);
 }
 
+/// Collects the labels of the completions whose values fit the `expected`
+/// type.
+///
+/// A label is resolved to the local variables by the type checking of the
+/// current source, and then to the global scope. A function fits if its
+/// return type does.
+fn fitting_labels(ctx: &mut CompletionContext, expected: &FlowType) -> HashSet<EcoString> {
+    let mut expected_tys = vec![];
+    flow_value_types(expected, &mut expected_tys);
+    if expected_tys.is_empty() {
+        return HashSet::new();
+    }
+
+    let mut local_tys: HashMap<EcoString, Vec<Type>> = HashMap::new();
+    let source = ctx
+        .root
+        .span()
+        .id()
+        .and_then(|id| ctx.ctx.source_by_id(id).ok());
+    if let Some(ty_chk) = source.and_then(|source| ctx.ctx.type_check(source)) {
+        for var in ty_chk.vars.values() {
+            let ty = ty_chk.simplify(var.get_ref(), false);
+            let tys = local_tys.entry(var.name()).or_default();
+            match &ty {
+                FlowType::Func(sig) => {
+                    tys.push(Type::of::<Func>());
+                    flow_value_types(&sig.ret, tys);
+                }
+                ty => flow_value_types(ty, tys),
+            }
+        }
+    }
+
+    let scope = ctx.world().library().global.scope();
+    let fits = |actual: Type| {
+        expected_tys.contains(&actual)
+            || (actual == Type::of::<i64>() && expected_tys.contains(&Type::of::<f64>()))
+            || ((actual == Type::of::<Length>() || actual == Type::of::<Ratio>())
+                && expected_tys.contains(&Type::of::<Rel>()))
+    };
+
+    let mut fitting = HashSet::new();
+    for compl in &ctx.completions {
+        let mut actual_tys = vec![];
+        if let Some(tys) = local_tys.get(&compl.label) {
+            actual_tys.extend(tys.iter().copied());
+        } else if let Some(value) = scope.get(&compl.label) {
+            actual_tys.push(value.ty());
+            if let Value::Func(func) = value {
+                if let Some(returns) = func.returns() {
+                    cast_value_types(returns, &mut actual_tys);
+                }
+            }
+        }
+
+        if actual_tys.into_iter().any(fits) {
+            fitting.insert(compl.label.clone());
+        }
+    }
+
+    fitting
+}
+
+/// Collects the types of the values described by a flow type.
+fn flow_value_types(ty: &FlowType, out: &mut Vec<Type>) {
+    match ty {
+        FlowType::Content | FlowType::Element(..) => out.push(Type::of::<Content>()),
+        FlowType::None => out.push(Type::of::<NoneValue>()),
+        FlowType::Auto => out.push(Type::of::<AutoValue>()),
+        FlowType::Boolean(..) => out.push(Type::of::<bool>()),
+        FlowType::Value(v) => value_type(&v.0, out),
+        FlowType::ValueDoc(v) => value_type(&v.0, out),
+        FlowType::Func(..) => out.push(Type::of::<Func>()),
+        FlowType::Dict(..) => out.push(Type::of::<Dict>()),
+        FlowType::Array(..) | FlowType::Tuple(..) => out.push(Type::of::<Array>()),
+        FlowType::Union(u) => {
+            for ty in u.iter() {
+                flow_value_types(ty, out);
+            }
+        }
+        FlowType::Let(store) => {
+            for ty in &store.lbs {
+                flow_value_types(ty, out);
+            }
+        }
+        FlowType::Builtin(b) => match b {
+            FlowBuiltinType::Color => out.push(Type::of::<Color>()),
+            FlowBuiltinType::Length | FlowBuiltinType::TextSize => out.push(Type::of::<Length>()),
+            FlowBuiltinType::Float => out.push(Type::of::<f64>()),
+            FlowBuiltinType::Dir => out.push(Type::of::<Dir>()),
+            FlowBuiltinType::Stroke => out.extend([
+                Type::of::<Stroke>(),
+                Type::of::<Color>(),
+                Type::of::<Length>(),
+            ]),
+            FlowBuiltinType::Margin
+            | FlowBuiltinType::Inset
+            | FlowBuiltinType::Outset
+            | FlowBuiltinType::Radius => {
+                out.extend([Type::of::<Rel>(), Type::of::<Length>(), Type::of::<Dict>()])
+            }
+            FlowBuiltinType::TextFont
+            | FlowBuiltinType::TextLang
+            | FlowBuiltinType::TextRegion
+            | FlowBuiltinType::Numbering
+            | FlowBuiltinType::DatePattern
+            | FlowBuiltinType::Path(..) => out.push(Type::of::<Str>()),
+            FlowBuiltinType::Args => {}
+        },
+        _ => {}
+    }
+}
+
+/// Collects the types of the values described by a cast info.
+fn cast_value_types(cast: &CastInfo, out: &mut Vec<Type>) {
+    match cast {
+        CastInfo::Any => {}
+        CastInfo::Value(v, _) => value_type(v, out),
+        CastInfo::Type(ty) => out.push(*ty),
+        CastInfo::Union(u) => {
+            for cast in u {
+                cast_value_types(cast, out);
+            }
+        }
+    }
+}
+
+/// Collects the type of a value, or the type itself if the value is a type.
+fn value_type(v: &Value, out: &mut Vec<Type>) {
+    match v {
+        Value::Type(ty) => out.push(*ty),
+        v => out.push(v.ty()),
+    }
+}
+
 /// Add completions for the parameters of a function.
 pub fn param_completions<'a>(
     ctx: &mut CompletionContext<'a, '_>,
@@ -259,6 +920,15 @@ pub fn param_completions<'a>(
     else {
         return;
     };
+
+    // Selectors constrain the fields of the element instead.
+    if let CallConvention::Where(func) = &cc {
+        if let Some(elem) = func.element() {
+            selector_field_completions(ctx, elem, args);
+        }
+        return;
+    }
+
     // todo: regards call convention
     let this = cc.method_this().cloned();
     let func = cc.callee();
@@ -276,25 +946,7 @@ pub fn param_completions<'a>(
 
     let signature = analyze_dyn_signature(ctx.ctx, func.clone());
 
-    let def = func.span();
-    let type_sig = def.id().and_then(|id| {
-        let source = ctx.ctx.source_by_id(id).ok()?;
-        let def = get_non_strict_def_target(source.find(def)?)?;
-        let DefTarget::Let(l) = def else {
-            return None;
-        };
-
-        let lb = l.cast::<ast::LetBinding>()?;
-        let ast::LetBindingKind::Closure(c) = lb.kind() else {
-            return None;
-        };
-
-        let fn_ty = ctx.ctx.type_of_span(c.span());
-        let info = ctx.ctx.type_check(source)?;
-
-        log::info!("function sig by type checking: {:?}", fn_ty);
-        fn_ty.and_then(|ty| ty.signatures(&info, false))
-    });
+    let type_sig = analyze_type_signatures(ctx.ctx, &func);
 
     // Exclude named arguments which are already present.
     let exclude: Vec<_> = args
@@ -347,20 +999,18 @@ pub fn param_completions<'a>(
             continue;
         }
 
-        if param.named {
+        if param.named && ctx.str_literal_range().is_none() {
             let compl = Completion {
                 kind: CompletionKind::Param,
                 label: param.name.clone().into(),
                 apply: Some(eco_format!("{}: ${{}}", param.name)),
                 detail: Some(plain_docs_sentence(&param.docs)),
                 label_detail: None,
-                // todo: only vscode and neovim (0.9.1) support this
-                //
                 // VS Code doesn't do that... Auto triggering suggestion only happens on typing
                 // (word starts or trigger characters). However, you can use
                 // editor.action.triggerSuggest as command on a suggestion to
                 // "manually" retrigger suggest after inserting one
-                command: Some("editor.action.triggerSuggest"),
+                command: ctx.trigger_suggest(),
                 ..Completion::default()
             };
             match param.infer_type {
@@ -377,17 +1027,18 @@ pub fn param_completions<'a>(
                         });
                     }
                 }
-                Some(FlowType::Builtin(FlowBuiltinType::Dir)) => {
-                    for dir_template in &["ltr", "rtl", "ttb", "btt"] {
+                Some(ref ty) => {
+                    let constants = expected_constants(ty).map_or(&[][..], |c| c.constants);
+                    for (constant, _) in constants {
                         let compl = compl.clone();
                         ctx.completions.push(Completion {
-                            label: eco_format!("{}: {}", param.name, dir_template),
+                            label: eco_format!("{}: {}", param.name, constant),
                             apply: None,
                             ..compl
                         });
                     }
                 }
-                _ => {}
+                None => {}
             }
             ctx.completions.push(compl);
         }
@@ -404,7 +1055,10 @@ pub fn param_completions<'a>(
         }
     }
 
-    sort_and_explicit_code_completion(ctx);
+    let expected = pos_index
+        .and_then(|i| primary_sig.pos.get(i))
+        .and_then(|pos| pos.infer_type.as_ref());
+    sort_and_explicit_code_completion(ctx, expected);
     if ctx.before.ends_with(',') {
         ctx.enrich(" ", "");
     }
@@ -415,6 +1069,11 @@ fn type_completion(
     infer_type: Option<&FlowType>,
     docs: Option<&str>,
 ) -> Option<()> {
+    // Inside a string literal, only values written as strings are completed.
+    if ctx.str_literal_range().is_some() && !is_str_like(infer_type?) {
+        return None;
+    }
+
     // Prevent duplicate completions from appearing.
     if !ctx.seen_casts.insert(typst::util::hash128(&infer_type)) {
         return Some(());
@@ -497,39 +1156,60 @@ fn type_completion(
                     "color.hsl(${h}, ${s}, ${l}, ${a})",
                     "A custom HSLA color.",
                 );
+                ctx.snippet_completion(
+                    "rgb(\"#..\")",
+                    "rgb(\"#${}\")",
+                    "A custom RGB color from a hex string.",
+                );
+                ctx.snippet_completion(
+                    "darken()",
+                    "${blue}.darken(${20%})",
+                    "A named color, darkened by a factor.",
+                );
+                ctx.snippet_completion(
+                    "lighten()",
+                    "${blue}.lighten(${20%})",
+                    "A named color, lightened by a factor.",
+                );
                 let color_ty = Type::of::<Color>();
                 ctx.strict_scope_completions(false, |value| value.ty() == color_ty);
+                for (name, hex) in CSS_COLORS {
+                    ctx.completions.push(Completion {
+                        kind: CompletionKind::Color,
+                        label: (*name).into(),
+                        apply: Some(eco_format!("rgb(\"{hex}\")")),
+                        detail: Some(eco_format!("The CSS color {name}.")),
+                        label_detail: Some((*hex).into()),
+                        ..Completion::default()
+                    });
+                }
             }
             FlowBuiltinType::TextSize => return None,
             FlowBuiltinType::TextLang => {
                 for (&key, desc) in rust_iso639::ALL_MAP.entries() {
                     let detail = eco_format!("An ISO 639-1/2/3 language code, {}.", desc.name);
-                    ctx.completions.push(Completion {
-                        kind: CompletionKind::Syntax,
-                        label: key.to_lowercase().into(),
-                        apply: Some(eco_format!("\"{}\"", key.to_lowercase())),
-                        detail: Some(detail),
-                        label_detail: Some(desc.name.into()),
-                        ..Completion::default()
-                    });
+                    ctx.str_completion(&key.to_lowercase(), Some(detail), Some(desc.name.into()));
                 }
             }
             FlowBuiltinType::TextRegion => {
                 for (&key, desc) in rust_iso3166::ALPHA2_MAP.entries() {
                     let detail = eco_format!("An ISO 3166-1 alpha-2 region code, {}.", desc.name);
-                    ctx.completions.push(Completion {
-                        kind: CompletionKind::Syntax,
-                        label: key.to_lowercase().into(),
-                        apply: Some(eco_format!("\"{}\"", key.to_lowercase())),
-                        detail: Some(detail),
-                        label_detail: Some(desc.name.into()),
-                        ..Completion::default()
-                    });
+                    ctx.str_completion(&key.to_lowercase(), Some(detail), Some(desc.name.into()));
+                }
+            }
+            FlowBuiltinType::Numbering => {
+                for (pattern, desc) in NUMBERING_PATTERNS {
+                    ctx.str_completion(pattern, Some((*desc).into()), None);
+                }
+            }
+            FlowBuiltinType::DatePattern => {
+                for (pattern, desc) in DATE_PATTERNS {
+                    ctx.str_completion(pattern, Some((*desc).into()), None);
                 }
             }
             FlowBuiltinType::Dir => {
-                let ty = Type::of::<Dir>();
-                ctx.strict_scope_completions(false, |value| value.ty() == ty);
+                let constants = expected_constants(&FlowType::Builtin(FlowBuiltinType::Dir))?;
+                builtin_constant_completions(ctx, constants);
             }
             FlowBuiltinType::TextFont => {
                 ctx.font_completions();
@@ -565,7 +1245,7 @@ fn type_completion(
             }
         },
         FlowType::Args(_) => return None,
-        FlowType::Func(_) => return None,
+        FlowType::Func(sig) => ctx.closure_completion(sig),
         FlowType::With(_) => return None,
         FlowType::At(_) => return None,
         FlowType::Union(u) => {
@@ -609,6 +1289,8 @@ fn type_completion(
                         "(${params}) => ${output}",
                         "A custom function.",
                     );
+                } else if let Some(constants) = expected_constants(infer_type?) {
+                    builtin_constant_completions(ctx, constants);
                 } else {
                     ctx.completions.push(Completion {
                         kind: CompletionKind::Syntax,
@@ -646,6 +1328,362 @@ fn type_completion(
     Some(())
 }
 
+/// Add completions for the fields of an element usable as the constraints of
+/// a selector, e.g. `level` in `heading.where(level: 1)`.
+fn selector_field_completions(ctx: &mut CompletionContext, elem: Element, args: ast::Args) {
+    // Exclude fields which are already constrained.
+    let exclude: Vec<_> = args
+        .items()
+        .filter_map(|arg| match arg {
+            ast::Arg::Named(named) => Some(named.name()),
+            _ => None,
+        })
+        .collect();
+
+    for param in elem.params() {
+        if elem.field_id(param.name).is_none()
+            || exclude.iter().any(|ident| ident.as_str() == param.name)
+        {
+            continue;
+        }
+
+        ctx.completions.push(Completion {
+            kind: CompletionKind::Param,
+            label: param.name.into(),
+            apply: Some(eco_format!("{}: ${{}}", param.name)),
+            detail: Some(plain_docs_sentence(param.docs)),
+            command: ctx.trigger_suggest(),
+            ..Completion::default()
+        });
+    }
+
+    sort_and_explicit_code_completion(ctx, None);
+    if ctx.before.ends_with(',') {
+        ctx.enrich(" ", "");
+    }
+}
+
+/// Gets the element bound to a variable by the type checking of show rules,
+/// e.g. the figure bound to `it` in `show figure: it => ..`.
+pub(super) fn shown_element(ctx: &mut CompletionContext, node: &LinkedNode) -> Option<Element> {
+    if node.kind() != SyntaxKind::Ident {
+        return None;
+    }
+
+    let source = ctx.ctx.source_by_id(node.span().id()?).ok()?;
+    let lnk = find_definition(ctx.ctx, source, DerefTarget::VarAccess(node.clone()))?;
+    let (fid, def_range) = lnk.def_at?;
+    let name_range = lnk.name_range.unwrap_or(def_range);
+
+    let def_source = ctx.ctx.source_by_id(fid).ok()?;
+    let def = LinkedNode::new(def_source.root()).leaf_at(name_range.start + 1)?;
+    let ty = ctx.ctx.type_of_span(def.span())?;
+    let ty_chk = ctx.ctx.type_check(def_source)?;
+    match ty_chk.simplify(ty, true) {
+        FlowType::Element(elem) => Some(elem),
+        _ => None,
+    }
+}
+
+/// Add completions for the fields of an element, e.g. `caption` of a figure,
+/// and the methods of content.
+pub(super) fn element_field_completions(ctx: &mut CompletionContext, elem: Element) {
+    for param in elem.params() {
+        if elem.field_id(param.name).is_none() {
+            continue;
+        }
+
+        ctx.completions.push(Completion {
+            kind: CompletionKind::Field,
+            label: param.name.into(),
+            detail: Some(plain_docs_sentence(param.docs)),
+            ..Completion::default()
+        });
+    }
+
+    for (name, value) in Type::of::<Content>().scope().iter() {
+        ctx.value_completion(Some(name.clone()), value, true, None);
+    }
+}
+
+/// The CSS named colors, except those already defined by Typst.
+const CSS_COLORS: &[(&str, &str)] = &[
+    ("aliceblue", "#f0f8ff"),
+    ("antiquewhite", "#faebd7"),
+    ("aquamarine", "#7fffd4"),
+    ("azure", "#f0ffff"),
+    ("beige", "#f5f5dc"),
+    ("bisque", "#ffe4c4"),
+    ("blanchedalmond", "#ffebcd"),
+    ("blueviolet", "#8a2be2"),
+    ("brown", "#a52a2a"),
+    ("burlywood", "#deb887"),
+    ("cadetblue", "#5f9ea0"),
+    ("chartreuse", "#7fff00"),
+    ("chocolate", "#d2691e"),
+    ("coral", "#ff7f50"),
+    ("cornflowerblue", "#6495ed"),
+    ("cornsilk", "#fff8dc"),
+    ("crimson", "#dc143c"),
+    ("cyan", "#00ffff"),
+    ("darkblue", "#00008b"),
+    ("darkcyan", "#008b8b"),
+    ("darkgoldenrod", "#b8860b"),
+    ("darkgray", "#a9a9a9"),
+    ("darkgreen", "#006400"),
+    ("darkgrey", "#a9a9a9"),
+    ("darkkhaki", "#bdb76b"),
+    ("darkmagenta", "#8b008b"),
+    ("darkolivegreen", "#556b2f"),
+    ("darkorange", "#ff8c00"),
+    ("darkorchid", "#9932cc"),
+    ("darkred", "#8b0000"),
+    ("darksalmon", "#e9967a"),
+    ("darkseagreen", "#8fbc8f"),
+    ("darkslateblue", "#483d8b"),
+    ("darkslategray", "#2f4f4f"),
+    ("darkslategrey", "#2f4f4f"),
+    ("darkturquoise", "#00ced1"),
+    ("darkviolet", "#9400d3"),
+    ("deeppink", "#ff1493"),
+    ("deepskyblue", "#00bfff"),
+    ("dimgray", "#696969"),
+    ("dimgrey", "#696969"),
+    ("dodgerblue", "#1e90ff"),
+    ("firebrick", "#b22222"),
+    ("floralwhite", "#fffaf0"),
+    ("forestgreen", "#228b22"),
+    ("gainsboro", "#dcdcdc"),
+    ("ghostwhite", "#f8f8ff"),
+    ("gold", "#ffd700"),
+    ("goldenrod", "#daa520"),
+    ("greenyellow", "#adff2f"),
+    ("grey", "#808080"),
+    ("honeydew", "#f0fff0"),
+    ("hotpink", "#ff69b4"),
+    ("indianred", "#cd5c5c"),
+    ("indigo", "#4b0082"),
+    ("ivory", "#fffff0"),
+    ("khaki", "#f0e68c"),
+    ("lavender", "#e6e6fa"),
+    ("lavenderblush", "#fff0f5"),
+    ("lawngreen", "#7cfc00"),
+    ("lemonchiffon", "#fffacd"),
+    ("lightblue", "#add8e6"),
+    ("lightcoral", "#f08080"),
+    ("lightcyan", "#e0ffff"),
+    ("lightgoldenrodyellow", "#fafad2"),
+    ("lightgray", "#d3d3d3"),
+    ("lightgreen", "#90ee90"),
+    ("lightgrey", "#d3d3d3"),
+    ("lightpink", "#ffb6c1"),
+    ("lightsalmon", "#ffa07a"),
+    ("lightseagreen", "#20b2aa"),
+    ("lightskyblue", "#87cefa"),
+    ("lightslategray", "#778899"),
+    ("lightslategrey", "#778899"),
+    ("lightsteelblue", "#b0c4de"),
+    ("lightyellow", "#ffffe0"),
+    ("limegreen", "#32cd32"),
+    ("linen", "#faf0e6"),
+    ("magenta", "#ff00ff"),
+    ("mediumaquamarine", "#66cdaa"),
+    ("mediumblue", "#0000cd"),
+    ("mediumorchid", "#ba55d3"),
+    ("mediumpurple", "#9370db"),
+    ("mediumseagreen", "#3cb371"),
+    ("mediumslateblue", "#7b68ee"),
+    ("mediumspringgreen", "#00fa9a"),
+    ("mediumturquoise", "#48d1cc"),
+    ("mediumvioletred", "#c71585"),
+    ("midnightblue", "#191970"),
+    ("mintcream", "#f5fffa"),
+    ("mistyrose", "#ffe4e1"),
+    ("moccasin", "#ffe4b5"),
+    ("navajowhite", "#ffdead"),
+    ("oldlace", "#fdf5e6"),
+    ("olivedrab", "#6b8e23"),
+    ("orangered", "#ff4500"),
+    ("orchid", "#da70d6"),
+    ("palegoldenrod", "#eee8aa"),
+    ("palegreen", "#98fb98"),
+    ("paleturquoise", "#afeeee"),
+    ("palevioletred", "#db7093"),
+    ("papayawhip", "#ffefd5"),
+    ("peachpuff", "#ffdab9"),
+    ("peru", "#cd853f"),
+    ("pink", "#ffc0cb"),
+    ("plum", "#dda0dd"),
+    ("powderblue", "#b0e0e6"),
+    ("rebeccapurple", "#663399"),
+    ("rosybrown", "#bc8f8f"),
+    ("royalblue", "#4169e1"),
+    ("saddlebrown", "#8b4513"),
+    ("salmon", "#fa8072"),
+    ("sandybrown", "#f4a460"),
+    ("seagreen", "#2e8b57"),
+    ("seashell", "#fff5ee"),
+    ("sienna", "#a0522d"),
+    ("skyblue", "#87ceeb"),
+    ("slateblue", "#6a5acd"),
+    ("slategray", "#708090"),
+    ("slategrey", "#708090"),
+    ("snow", "#fffafa"),
+    ("springgreen", "#00ff7f"),
+    ("steelblue", "#4682b4"),
+    ("tan", "#d2b48c"),
+    ("thistle", "#d8bfd8"),
+    ("tomato", "#ff6347"),
+    ("turquoise", "#40e0d0"),
+    ("violet", "#ee82ee"),
+    ("wheat", "#f5deb3"),
+    ("whitesmoke", "#f5f5f5"),
+    ("yellowgreen", "#9acd32"),
+];
+
+/// Common numbering patterns.
+const NUMBERING_PATTERNS: &[(&str, &str)] = &[
+    ("1.", "Arabic numbers followed by a period."),
+    ("1.1", "Nested arabic numbers, separated by periods."),
+    (
+        "1.a",
+        "Arabic numbers, then lowercase letters for nested levels.",
+    ),
+    ("(1)", "Parenthesized arabic numbers."),
+    ("a)", "Lowercase letters followed by a parenthesis."),
+    ("A.", "Uppercase letters followed by a period."),
+    ("i.", "Lowercase roman numerals followed by a period."),
+    ("I.", "Uppercase roman numerals followed by a period."),
+    ("*", "Symbols, starting with an asterisk."),
+    ("①", "Circled numbers."),
+];
+
+/// Common date and time patterns.
+const DATE_PATTERNS: &[(&str, &str)] = &[
+    ("[year]-[month]-[day]", "An ISO 8601 date, e.g. 2024-03-14."),
+    ("[hour]:[minute]:[second]", "A time, e.g. 13:37:00."),
+    (
+        "[year]-[month]-[day] [hour]:[minute]:[second]",
+        "An ISO 8601 date and time, e.g. 2024-03-14 13:37:00.",
+    ),
+    ("[day].[month].[year]", "A day-first date, e.g. 14.03.2024."),
+    (
+        "[month]/[day]/[year]",
+        "A month-first date, e.g. 03/14/2024.",
+    ),
+    (
+        "[month repr:long] [day padding:none], [year]",
+        "A long date, e.g. March 14, 2024.",
+    ),
+    (
+        "[day padding:none] [month repr:long] [year]",
+        "A long day-first date, e.g. 14 March 2024.",
+    ),
+];
+
+/// The constants of a builtin type, completed where a value of the type is
+/// expected.
+struct BuiltinConstants {
+    /// The name of the type, e.g. `alignment`.
+    ty: &'static str,
+    /// The constants of the type and their docs.
+    constants: &'static [(&'static str, &'static str)],
+    /// The common combinations of the constants and their docs.
+    combinations: &'static [(&'static str, &'static str)],
+}
+
+/// The builtin types whose values are usually written as constants.
+const BUILTIN_CONSTANTS: &[BuiltinConstants] = &[
+    BuiltinConstants {
+        ty: "alignment",
+        constants: &[
+            ("start", "Align at the start of the text direction."),
+            ("end", "Align at the end of the text direction."),
+            ("left", "Align at the left edge."),
+            ("center", "Align at the horizontal middle."),
+            ("right", "Align at the right edge."),
+            ("top", "Align at the top edge."),
+            ("horizon", "Align at the vertical middle."),
+            ("bottom", "Align at the bottom edge."),
+        ],
+        combinations: &[
+            ("top + left", "Align at the top left corner."),
+            ("top + center", "Align at the middle of the top edge."),
+            ("top + right", "Align at the top right corner."),
+            ("horizon + left", "Align at the middle of the left edge."),
+            ("center + horizon", "Align at the center."),
+            ("horizon + right", "Align at the middle of the right edge."),
+            ("bottom + left", "Align at the bottom left corner."),
+            ("bottom + center", "Align at the middle of the bottom edge."),
+            ("bottom + right", "Align at the bottom right corner."),
+        ],
+    },
+    BuiltinConstants {
+        ty: "direction",
+        constants: &[
+            ("ltr", "Left to right."),
+            ("rtl", "Right to left."),
+            ("ttb", "Top to bottom."),
+            ("btt", "Bottom to top."),
+        ],
+        combinations: &[],
+    },
+];
+
+/// Gets the constants of the type expected at a position, e.g. the directions
+/// of `dir: ltr`.
+fn expected_constants(ty: &FlowType) -> Option<&'static BuiltinConstants> {
+    let ty = match ty {
+        FlowType::Builtin(FlowBuiltinType::Dir) => Type::of::<Dir>(),
+        FlowType::Value(v) => match &v.0 {
+            Value::Type(ty) => *ty,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    BUILTIN_CONSTANTS.iter().find(|c| c.ty == ty.short_name())
+}
+
+/// Add completions for the constants of a builtin type and their common
+/// combinations.
+fn builtin_constant_completions(ctx: &mut CompletionContext, constants: &BuiltinConstants) {
+    for (name, docs) in constants.constants {
+        ctx.completions.push(Completion {
+            kind: CompletionKind::Constant,
+            label: (*name).into(),
+            detail: Some((*docs).into()),
+            ..Completion::default()
+        });
+    }
+    for (combination, docs) in constants.combinations {
+        ctx.completions.push(Completion {
+            kind: CompletionKind::Syntax,
+            label: (*combination).into(),
+            detail: Some((*docs).into()),
+            ..Completion::default()
+        });
+    }
+}
+
+/// Whether values of the type are written as string literals, so that they
+/// can be completed inside the quotes.
+fn is_str_like(ty: &FlowType) -> bool {
+    match ty {
+        FlowType::Builtin(ty) => matches!(
+            ty,
+            FlowBuiltinType::TextLang
+                | FlowBuiltinType::TextRegion
+                | FlowBuiltinType::TextFont
+                | FlowBuiltinType::Numbering
+                | FlowBuiltinType::DatePattern
+        ),
+        FlowType::Value(v) => matches!(v.0, Value::Str(..)),
+        FlowType::ValueDoc(v) => matches!(v.0, Value::Str(..)),
+        FlowType::Union(..) | FlowType::Let(..) => true,
+        _ => false,
+    }
+}
+
 /// Add completions for the values of a named function parameter.
 pub fn named_param_value_completions<'a>(
     ctx: &mut CompletionContext<'a, '_>,
@@ -665,28 +1703,12 @@ pub fn named_param_value_completions<'a>(
 
         return;
     };
+    // Selectors may constrain positional fields by name.
+    let is_where = matches!(cc, CallConvention::Where(..));
     // todo: regards call convention
     let func = cc.callee();
 
-    let def = func.span();
-    let type_sig = def.id().and_then(|id| {
-        let source = ctx.ctx.source_by_id(id).ok()?;
-        let def = get_non_strict_def_target(source.find(def)?)?;
-        let DefTarget::Let(l) = def else {
-            return None;
-        };
-
-        let lb = l.cast::<ast::LetBinding>()?;
-        let ast::LetBindingKind::Closure(c) = lb.kind() else {
-            return None;
-        };
-
-        let fn_ty = ctx.ctx.type_of_span(c.span());
-        let info = ctx.ctx.type_check(source)?;
-
-        log::info!("function sig by type checking: {:?}", fn_ty);
-        fn_ty.and_then(|ty| ty.signatures(&info, false))
-    });
+    let type_sig = analyze_type_signatures(ctx.ctx, &func);
 
     use typst::foundations::func::Repr;
     let mut func = func;
@@ -700,10 +1722,15 @@ pub fn named_param_value_completions<'a>(
 
     let primary_sig = signature.primary();
 
-    let Some(param) = primary_sig.named.get(name) else {
-        return;
+    let param = match primary_sig.named.get(name) {
+        Some(param) => param,
+        None if is_where => match primary_sig.pos.iter().find(|p| p.name == name) {
+            Some(param) => param,
+            None => return,
+        },
+        None => return,
     };
-    if !param.named {
+    if !param.named && !is_where {
         return;
     }
 
@@ -724,7 +1751,7 @@ pub fn named_param_value_completions<'a>(
         }
     }
 
-    if !completed {
+    if !completed && ctx.str_literal_range().is_none() {
         if let Some(expr) = &param.expr {
             ctx.completions.push(Completion {
                 kind: CompletionKind::Constant,
@@ -746,7 +1773,8 @@ pub fn named_param_value_completions<'a>(
         ctx.cast_completions(&param.input);
     }
 
-    sort_and_explicit_code_completion(ctx);
+    let expected = ty.or(param.infer_type.as_ref());
+    sort_and_explicit_code_completion(ctx, expected);
     if ctx.before.ends_with(':') {
         ctx.enrich(" ", "");
     }
@@ -849,8 +1877,7 @@ pub fn complete_literal(ctx: &mut CompletionContext) -> Option<()> {
                             kind: CompletionKind::Field,
                             label: key.clone(),
                             apply: Some(eco_format!("{}: ${{}}", key)),
-                            // todo: only vscode and neovim (0.9.1) support this
-                            command: Some("editor.action.triggerSuggest"),
+                            command: self.ctx.trigger_suggest(),
                             ..Completion::default()
                         });
                     }
@@ -896,6 +1923,7 @@ pub fn complete_literal(ctx: &mut CompletionContext) -> Option<()> {
         }
     }
 
+    let expected = named_ty.clone();
     ctx.work(named_ty, lit_ty);
 
     let ctx = ctx.ctx;
@@ -905,7 +1933,7 @@ pub fn complete_literal(ctx: &mut CompletionContext) -> Option<()> {
     }
     ctx.incomplete = false;
 
-    sort_and_explicit_code_completion(ctx);
+    sort_and_explicit_code_completion(ctx, expected.as_ref());
     Some(())
 }
 
@@ -917,34 +1945,28 @@ pub fn complete_path(
     p: &PathPreference,
 ) -> Option<Vec<CompletionItem>> {
     let id = source.id();
-    if id.package().is_some() {
-        return None;
-    }
+
+    // Paths in a package are resolved relative to the package root.
+    let root = match id.package() {
+        Some(spec) => ctx.resources.resolve(spec).ok()?,
+        None => ctx.analysis.root.clone(),
+    };
 
     let is_in_text;
     let text;
     let rng;
     if let Some(v) = v {
-        let vp = v.cast::<ast::Str>()?;
-        // todo: path escape
-        let real_content = vp.get();
-        let str_content = v.text();
-        let unquoted = &str_content[1..str_content.len() - 1];
-        if unquoted != real_content {
-            return None;
-        }
-
         let vr = v.range();
         let offset = vr.start + 1;
         if cursor < offset || vr.end <= cursor || vr.len() < 2 {
             return None;
         }
 
-        text = &source.text()[offset..cursor];
+        text = unescape_str(&source.text()[offset..cursor]);
         rng = offset..vr.end - 1;
         is_in_text = true;
     } else {
-        text = "";
+        text = String::new();
         rng = cursor..cursor;
         is_in_text = false;
     }
@@ -952,7 +1974,7 @@ pub fn complete_path(
     let has_root = path.has_root();
 
     let src_path = id.vpath();
-    let base = src_path.resolve(&ctx.analysis.root)?;
+    let base = src_path.resolve(&root)?;
     let dst_path = src_path.join(path);
     let mut compl_path = dst_path.as_rootless_path();
     if !compl_path.is_dir() {
@@ -965,17 +1987,35 @@ pub fn complete_path(
         return None;
     }
 
-    let dirs = ctx.analysis.root.clone();
-    log::debug!("compl_dirs: {dirs:?}");
+    let files = match id.package() {
+        Some(..) => {
+            let config = &ctx.analysis.path_completion;
+            let filter = ctx.completion_filter(p);
+            let files = scan_workspace_files_with(
+                &root,
+                PathPreference::None.ext_matcher(),
+                config.show_hidden,
+                config.max_entries,
+                |path| path.to_owned(),
+            );
+            files
+                .into_iter()
+                .filter(|path| filter(path.as_path()))
+                .collect()
+        }
+        None => ctx.completion_files(p).cloned().collect(),
+    };
+
+    log::debug!("compl_dirs: {root:?}");
     // find directory or files in the path
     let mut folder_completions = vec![];
     let mut module_completions = vec![];
     // todo: test it correctly
-    for path in ctx.completion_files(p) {
+    for path in files {
         log::debug!("compl_check_path: {path:?}");
 
         // diff with root
-        let path = dirs.join(path);
+        let path = root.join(path);
 
         // Skip self smartly
         if path.clean() == base.clean() {
@@ -984,7 +2024,7 @@ pub fn complete_path(
 
         let label = if has_root {
             // diff with root
-            let w = path.strip_prefix(&ctx.analysis.root).ok()?;
+            let w = path.strip_prefix(&root).ok()?;
             eco_format!("/{}", unix_slash(w))
         } else {
             let base = base.parent()?;
@@ -1026,13 +2066,13 @@ pub fn complete_path(
     Some(
         completions
             .map(|typst_completion| {
-                let lsp_snippet = &typst_completion.0;
+                let quoted = typst::foundations::Repr::repr(typst_completion.0.as_str());
                 let text_edit = CompletionTextEdit::Edit(TextEdit::new(
                     replace_range,
                     if is_in_text {
-                        lsp_snippet.to_string()
+                        quoted[1..quoted.len() - 1].to_string()
                     } else {
-                        format!(r#""{lsp_snippet}""#)
+                        quoted.to_string()
                     },
                 ));
 
@@ -1060,11 +2100,156 @@ pub fn complete_path(
     )
 }
 
+/// Decodes the escape sequences of the content of a string literal.
+///
+/// The content may be cut at the cursor, so an incomplete escape sequence at
+/// the end is dropped.
+fn unescape_str(raw: &str) -> String {
+    let mut s = unscanny::Scanner::new(raw);
+    let mut out = String::with_capacity(raw.len());
+    while let Some(c) = s.eat() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match s.eat() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('u') if s.eat_if('{') => {
+                let hex = s.eat_while(|c: char| c.is_ascii_hexdigit());
+                if !s.eat_if('}') {
+                    break;
+                }
+                out.extend(u32::from_str_radix(hex, 16).ok().and_then(char::from_u32));
+            }
+            Some(c) => out.push(c),
+            None => break,
+        }
+    }
+
+    out
+}
+
 #[cfg(test)]
 
 mod tests {
+    use typst::syntax::{LinkedNode, Source};
+
+    use super::{
+        closure_param_names, emoji_shorthand, expected_constants, in_context, relative_import_path,
+        show_selector_position, unescape_str, BUILTIN_CONSTANTS,
+    };
+    use crate::analysis::{FlowBuiltinType, FlowSignature, FlowType};
     use crate::upstream::complete::safe_str_slice;
 
+    #[test]
+    fn test_emoji_shorthand() {
+        assert_eq!(emoji_shorthand("Hi :smi", 0), Some((3, "smi")));
+        assert_eq!(emoji_shorthand(":face.ha", 0), Some((0, "face.ha")));
+        assert_eq!(emoji_shorthand("Note: ", 0), None);
+        assert_eq!(emoji_shorthand("Note:", 0), None);
+        assert_eq!(emoji_shorthand("a:b", 0), None);
+        assert_eq!(emoji_shorthand("Hi :smi", 4), None);
+    }
+
+    #[test]
+    fn test_relative_import_path() {
+        use typst::syntax::{package::PackageSpec, FileId, VirtualPath};
+
+        let workspace = |path: &str| FileId::new(None, VirtualPath::new(path));
+        let spec: PackageSpec = "@preview/example:0.1.0".parse().unwrap();
+        let package = |path: &str| FileId::new(Some(spec.clone()), VirtualPath::new(path));
+
+        let path = relative_import_path(workspace("/chapters/a.typ"), workspace("/utils.typ"));
+        assert_eq!(path.as_deref(), Some("../utils.typ"));
+        let path = relative_import_path(package("/src/lib.typ"), package("/src/utils.typ"));
+        assert_eq!(path.as_deref(), Some("utils.typ"));
+        assert_eq!(
+            relative_import_path(workspace("/main.typ"), package("/lib.typ")),
+            None
+        );
+        assert_eq!(
+            relative_import_path(package("/lib.typ"), workspace("/main.typ")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_show_selector_position() {
+        let position = |text: &str, cursor: usize| {
+            let source = Source::detached(text);
+            let leaf = LinkedNode::new(source.root()).leaf_at(cursor).unwrap();
+            show_selector_position(&leaf)
+        };
+        assert_eq!(position("#show ", 6), Some(false));
+        assert_eq!(position("#show hea", 9), Some(false));
+        assert_eq!(position("#show hea: it => it", 9), Some(true));
+        assert_eq!(position("#show heading: it", 17), None);
+        assert_eq!(position("#show heading.where(level: 1)", 21), None);
+        assert_eq!(position("#set text(red)", 6), None);
+    }
+
+    #[test]
+    fn test_in_context() {
+        let in_context_at = |text: &str, cursor: usize| {
+            let source = Source::detached(text);
+            let leaf = LinkedNode::new(source.root()).leaf_at(cursor).unwrap();
+            in_context(&leaf)
+        };
+        assert!(in_context_at("#context { cou }", 13));
+        assert!(in_context_at("#context cou", 12));
+        assert!(in_context_at("#context ", 9));
+        assert!(!in_context_at("#{ cou }", 6));
+    }
+
+    #[test]
+    fn test_builtin_constants() {
+        let library = typst::Library::builder().build();
+        for constants in BUILTIN_CONSTANTS {
+            for (name, _) in constants.constants {
+                let value = library.global.scope().get(name).unwrap();
+                assert_eq!(value.ty().short_name(), constants.ty);
+            }
+        }
+
+        let dir = expected_constants(&FlowType::Builtin(FlowBuiltinType::Dir));
+        assert_eq!(dir.map(|c| c.ty), Some("direction"));
+    }
+
+    #[test]
+    fn test_unescape_str() {
+        assert_eq!(unescape_str(r"figures/a b.png"), "figures/a b.png");
+        assert_eq!(unescape_str(r#"a\\b\"c"#), r#"a\b"c"#);
+        assert_eq!(unescape_str(r"\u{41}\t"), "A\t");
+        assert_eq!(unescape_str(r"dir\"), "dir");
+        assert_eq!(unescape_str(r"\u{4"), "");
+    }
+
+    #[test]
+    fn test_closure_param_names() {
+        let names = |pos: Vec<FlowType>, ret: FlowType| {
+            let sig = FlowSignature {
+                pos,
+                named: vec![],
+                rest: None,
+                ret,
+            };
+            closure_param_names(&sig)
+        };
+
+        assert_eq!(names(vec![FlowType::Any], FlowType::Any), ["it"]);
+        assert_eq!(
+            names(vec![FlowType::Any, FlowType::Any], FlowType::Any),
+            ["acc", "x"]
+        );
+        assert_eq!(
+            names(vec![FlowType::Content, FlowType::None], FlowType::Any),
+            ["x", "y"]
+        );
+    }
+
     #[test]
     fn test_before() {
         const TEST_UTF8_STR: &str = "我们";
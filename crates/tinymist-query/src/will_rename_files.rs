@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use lsp_types::TextEdit;
+use reflexo::path::unix_slash;
+
+use crate::{prelude::*, syntax::resolve_id_by_path, SemanticRequest};
+
+/// The [`workspace/willRenameFiles`] request is sent from the client to the
+/// server before files are actually renamed as long as the rename is
+/// triggered from within the client.
+///
+/// [`workspace/willRenameFiles`]: https://microsoft.github.io/language-server-protocol/specification#workspace_willRenameFiles
+///
+/// The returned edits rewrite the paths of `#import` and `#include` that
+/// refer to the renamed files, as well as the relative paths in the renamed
+/// files themselves. The edits are applied before the files are renamed.
+///
+/// # Compatibility
+///
+/// This request was introduced in specification version 3.16.0.
+#[derive(Debug, Clone)]
+pub struct WillRenameFilesRequest {
+    /// The old and new paths of the renamed files or folders.
+    pub paths: Vec<(PathBuf, PathBuf)>,
+}
+
+impl SemanticRequest for WillRenameFilesRequest {
+    type Response = WorkspaceEdit;
+
+    fn request(self, ctx: &mut AnalysisContext) -> Option<Self::Response> {
+        let root = ctx.analysis.root.clone();
+        let to_vpath = |path: &Path| Some(VirtualPath::new(path.strip_prefix(&root).ok()?));
+        let moves = self
+            .paths
+            .iter()
+            .filter_map(|(old, new)| Some((to_vpath(old)?, to_vpath(new)?)))
+            .collect::<Vec<_>>();
+        if moves.is_empty() {
+            return None;
+        }
+
+        let mut editions = HashMap::new();
+        for id in ctx.source_files().clone() {
+            let Ok(source) = ctx.source_by_id(id) else {
+                continue;
+            };
+
+            let mut edits = vec![];
+            let root = LinkedNode::new(source.root());
+            collect_path_edits(ctx.world(), &root, &moves, &mut edits);
+            if edits.is_empty() {
+                continue;
+            }
+
+            let Ok(path) = ctx.path_for_id(id) else {
+                continue;
+            };
+            let Ok(uri) = path_to_url(&path) else {
+                continue;
+            };
+            let edits = edits.into_iter().map(|(range, new_text)| TextEdit {
+                range: ctx.to_lsp_range(range, &source),
+                new_text,
+            });
+            editions.insert(uri, edits.collect());
+        }
+
+        Some(WorkspaceEdit {
+            changes: Some(editions),
+            ..Default::default()
+        })
+    }
+}
+
+/// Gets the new location of a file, if it is renamed or in a renamed folder.
+fn moved_id(moves: &[(VirtualPath, VirtualPath)], id: TypstFileId) -> Option<TypstFileId> {
+    if id.package().is_some() {
+        return None;
+    }
+
+    let path = id.vpath().as_rooted_path();
+    moves.iter().find_map(|(old, new)| {
+        let rest = path.strip_prefix(old.as_rooted_path()).ok()?;
+        let new_path = if rest.as_os_str().is_empty() {
+            new.clone()
+        } else {
+            new.join(rest)
+        };
+        Some(TypstFileId::new(None, new_path))
+    })
+}
+
+fn collect_path_edits(
+    world: &dyn World,
+    node: &LinkedNode,
+    moves: &[(VirtualPath, VirtualPath)],
+    edits: &mut Vec<(Range<usize>, String)>,
+) {
+    let path_expr = match node.kind() {
+        SyntaxKind::ModuleImport => node.cast::<ast::ModuleImport>().map(|e| e.source()),
+        SyntaxKind::ModuleInclude => node.cast::<ast::ModuleInclude>().map(|e| e.source()),
+        _ => None,
+    };
+    if let Some(ast::Expr::Str(path)) = path_expr {
+        let path_node = node.find(path.span());
+        let new_path = path_node.and_then(|path_node| {
+            let new_path = renamed_import_path(world, &path_node, path.get().as_str(), moves)?;
+            Some((path_node.range(), new_path))
+        });
+        edits.extend(new_path);
+    }
+
+    for child in node.children() {
+        collect_path_edits(world, &child, moves, edits);
+    }
+}
+
+/// Computes the new path string of an import, if either the importing file or
+/// the imported file is renamed.
+fn renamed_import_path(
+    world: &dyn World,
+    path_node: &LinkedNode,
+    path: &str,
+    moves: &[(VirtualPath, VirtualPath)],
+) -> Option<String> {
+    if path.starts_with('@') {
+        return None;
+    }
+
+    let current = path_node.span().id()?;
+    let target = resolve_id_by_path(world, current, path)?;
+    let new_current = moved_id(moves, current);
+    let new_target = moved_id(moves, target);
+
+    let new_path = if path.starts_with('/') {
+        unix_slash(new_target?.vpath().as_rooted_path())
+    } else {
+        if new_current.is_none() && new_target.is_none() {
+            return None;
+        }
+        let new_current = new_current.unwrap_or(current);
+        let new_target = new_target.unwrap_or(target);
+        let dir = new_current.vpath().as_rooted_path().parent()?;
+        let new_path = pathdiff::diff_paths(new_target.vpath().as_rooted_path(), dir)?;
+        unix_slash(&new_path)
+    };
+
+    (new_path != path).then(|| format!("{new_path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    fn describe_edits(edit: WorkspaceEdit) -> Vec<String> {
+        let mut edits = edit
+            .changes
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|(uri, edits)| {
+                let fp = unix_slash(&crate::url_to_path(uri));
+                let fp = fp.strip_prefix("C:").unwrap_or(&fp).to_owned();
+                edits.into_iter().map(move |edit| {
+                    let range = edit.range;
+                    format!(
+                        "{fp}@{}:{}:{}:{} -> {}",
+                        range.start.line,
+                        range.start.character,
+                        range.end.line,
+                        range.end.character,
+                        edit.new_text
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        edits.sort();
+        edits
+    }
+
+    #[test]
+    fn test() {
+        snapshot_testing("will_rename_files", &|ctx, _path| {
+            let root = ctx.analysis.root.clone();
+            let request = WillRenameFilesRequest {
+                paths: vec![(root.join("base.typ"), root.join("lib/renamed.typ"))],
+            };
+
+            let result = request.request(ctx).map(describe_edits);
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+
+    #[test]
+    fn test_folder() {
+        snapshot_testing("will_rename_folder", &|ctx, _path| {
+            let root = ctx.analysis.root.clone();
+            let request = WillRenameFilesRequest {
+                paths: vec![(root.join("lib"), root.join("src/lib"))],
+            };
+
+            let result = request.request(ctx).map(describe_edits);
+            assert_snapshot!(JsonRepr::new_pure(result));
+        });
+    }
+}
@@ -85,11 +85,32 @@ impl PeriscopeRenderer {
 
         log::debug!("periscope image: {sw}x{sh}, {svg_payload}");
 
-        // encode as markdown dataurl image
-        let base64 = base64::engine::general_purpose::STANDARD.encode(svg_payload);
-        Some(enlarge_image(format_args!(
-            "![Periscope Mode](data:image/svg+xml;base64,{base64}|width={sw}|height={sh})"
-        )))
+        Some(marked_image("Periscope Mode", svg_payload, sw, sh))
+    }
+
+    /// Render the first page of a preview document into markdown format.
+    pub fn render_preview_marked(&self, doc: VersionedDocument) -> Option<String> {
+        type UsingExporter = SvgExporter<PeriscopeExportFeature>;
+        let mut doc = UsingExporter::svg_doc(&doc.document);
+        doc.module.prepare_glyphs();
+        let page0 = doc.pages.first()?.clone();
+        let mut svg_text = UsingExporter::render(&doc.module, &[page0.clone()], None);
+
+        let width = page0.size.x.0;
+        let height = page0.size.y.0;
+
+        let svg_header = svg_text.get_mut(0)?;
+        *svg_header = SvgText::Plain(header_inner(
+            width,
+            0.,
+            height,
+            self.p.scale,
+            self.p.invert_color == "always",
+        ));
+
+        let sw = width * self.p.scale;
+        let sh = height * self.p.scale;
+        Some(marked_image("Preview", SvgText::join(svg_text), sw, sh))
     }
 
     /// Render the periscope image for the given document.
@@ -128,6 +149,14 @@ impl PeriscopeRenderer {
     }
 }
 
+/// Encode the SVG as a markdown data-URI image.
+fn marked_image(alt: &str, svg_payload: String, w: f32, h: f32) -> String {
+    let base64 = base64::engine::general_purpose::STANDARD.encode(svg_payload);
+    enlarge_image(format_args!(
+        "![{alt}](data:image/svg+xml;base64,{base64}|width={w}|height={h})"
+    ))
+}
+
 fn enlarge_image(md: fmt::Arguments) -> String {
     format!("```\n```\n{md}\n```\n```")
 }
@@ -7,9 +7,9 @@ pub mod typ_client;
 pub mod typ_server;
 mod user_action;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use tinymist_query::analysis::Analysis;
+use tinymist_query::analysis::{Analysis, AnalysisProviders, SpellChecker, UserSnippets};
 use tinymist_query::ExportKind;
 use tinymist_render::PeriscopeRenderer;
 use tokio::sync::{broadcast, watch};
@@ -24,7 +24,7 @@ use self::{
     formatting::run_format_thread,
     render::{ExportActor, ExportConfig},
     typ_client::{CompileClientActor, CompileDriver, CompileHandler},
-    typ_server::CompileServerActor,
+    typ_server::{CompileServerActor, MemoryRevisions},
     user_action::run_user_action_thread,
 };
 use crate::{
@@ -48,11 +48,14 @@ impl CompileServer {
     ) -> CompileClientActor {
         let (doc_tx, doc_rx) = watch::channel(None);
         let (render_tx, _) = broadcast::channel(10);
+        let summary = std::sync::Arc::new(parking_lot::Mutex::new(None));
 
         let config = ExportConfig {
             substitute_pattern: self.config.output_path.clone(),
             entry: entry.clone(),
             mode: self.config.export_pdf,
+            debounce: self.config.export_debounce,
+            creation_timestamp: self.config.determine_creation_timestamp(),
         };
 
         // Run Export actors before preparing cluster to avoid loss of events
@@ -86,6 +89,8 @@ impl CompileServer {
         // Create the server
         let inner = Deferred::new({
             let current_runtime = self.handle.clone();
+            let revisions = std::sync::Arc::new(MemoryRevisions::default());
+            let summary = summary.clone();
             let handler = CompileHandler {
                 #[cfg(feature = "preview")]
                 inner: std::sync::Arc::new(parking_lot::Mutex::new(None)),
@@ -93,11 +98,26 @@ impl CompileServer {
                 doc_tx,
                 render_tx: render_tx.clone(),
                 editor_tx: self.diag_tx.clone(),
+                revisions: revisions.clone(),
+                summary,
             };
 
             let position_encoding = self.const_config().position_encoding;
+            let completion_feat = self.const_config().completion_feat;
             let enable_periscope = self.config.periscope_args.is_some();
+            let enable_hover_preview =
+                self.config.hover_preview && self.const_config().hover_markdown;
+            let enable_type_inlay_hints = self.config.type_inlay_hints;
+            let closing_hint_min_lines = self.config.closing_hints_min_lines;
+            let memory_budget = self.config.memory_budget;
+            let creation_timestamp = self.config.determine_creation_timestamp();
             let periscope_args = self.config.periscope_args.clone();
+            let completion_snippets = self.config.completion_snippets.clone();
+            let analysis_plugins = self.config.analysis_plugins.clone();
+            let path_completion = self.config.path_completion.clone();
+            let lint = self.config.lint.clone();
+            let spell_check_config = self.config.spell_check.clone();
+            let package_overrides = self.config.determine_package_overrides();
             let diag_group = editor_group.clone();
             let entry = entry.clone();
             let font_resolver = self.font.clone();
@@ -106,11 +126,17 @@ impl CompileServer {
 
                 // Create the world
                 let font_resolver = font_resolver.wait().clone();
-                let world = LspWorldBuilder::build(entry.clone(), font_resolver, inputs)
-                    .expect("incorrect options");
+                let world =
+                    LspWorldBuilder::build(entry.clone(), font_resolver, inputs, package_overrides)
+                        .expect("incorrect options");
 
                 // Create the compiler
                 let driver = CompileDriverInner::new(world);
+                let mut snippets = UserSnippets::default();
+                snippets.set_configured(completion_snippets);
+                let providers = load_analysis_providers(&analysis_plugins);
+                let mut spell_check = SpellChecker::default();
+                spell_check.set_config(spell_check_config);
                 let driver = CompileDriver {
                     inner: driver,
                     handler,
@@ -118,13 +144,28 @@ impl CompileServer {
                         position_encoding,
                         root: Path::new("").into(),
                         enable_periscope,
+                        enable_hover_preview,
+                        enable_type_inlay_hints,
+                        closing_hint_min_lines,
+                        snippets,
+                        providers,
+                        path_completion,
+                        lint,
+                        spell_check,
+                        completion_feat,
+                        memory_budget,
                         caches: Default::default(),
                     },
                     periscope: PeriscopeRenderer::new(periscope_args.unwrap_or_default()),
+                    creation_timestamp,
+                    lints: Default::default(),
+                    registry_revision: 0,
                 };
 
                 // Create the actor
-                let server = CompileServerActor::new(driver, entry).with_watch(true);
+                let server = CompileServerActor::new(driver, entry)
+                    .with_watch(true)
+                    .with_memory_revisions(revisions);
                 let client = server.client();
 
                 // We do send memory changes instead of initializing compiler with them.
@@ -138,7 +179,16 @@ impl CompileServer {
             }
         });
 
-        CompileClientActor::new(editor_group, self.config.clone(), entry, inner, render_tx)
+        let hover_markdown = self.const_config().hover_markdown;
+        CompileClientActor::new(
+            editor_group,
+            self.config.clone(),
+            hover_markdown,
+            entry,
+            inner,
+            render_tx,
+            summary,
+        )
     }
 }
 
@@ -164,11 +214,9 @@ impl TypstLanguageServer {
         self.format_thread = Some(tx_req.clone());
 
         let client = self.client.clone();
-        let mode = self.config.formatter;
+        let config = self.config.formatting_config();
         let enc = self.const_config.position_encoding;
-        std::thread::spawn(move || {
-            run_format_thread(FormattingConfig { mode, width: 120 }, rx_req, client, enc)
-        });
+        std::thread::spawn(move || run_format_thread(config, rx_req, client, enc));
     }
 
     pub fn run_user_action_thread(&mut self) {
@@ -184,3 +232,23 @@ impl TypstLanguageServer {
         std::thread::spawn(move || run_user_action_thread(rx_req, client));
     }
 }
+
+/// Loads the WebAssembly plugins providing completions and hovers.
+pub(crate) fn load_analysis_providers(plugins: &[PathBuf]) -> AnalysisProviders {
+    let mut providers = AnalysisProviders::default();
+
+    #[cfg(feature = "wasm-plugin")]
+    for path in plugins {
+        match tinymist_query::analysis::WasmProvider::load(path) {
+            Ok(provider) => providers.register(std::sync::Arc::new(provider)),
+            Err(err) => log::error!("failed to load analysis plugin {path:?}: {err}"),
+        }
+    }
+
+    #[cfg(not(feature = "wasm-plugin"))]
+    if !plugins.is_empty() {
+        log::warn!("analysis plugins are configured but the `wasm-plugin` feature is disabled");
+    }
+
+    providers
+}
@@ -1,18 +1,34 @@
 //! The cluster actor running in background
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
 
 use log::info;
-use lsp_types::{Diagnostic, Url};
+use lsp_types::{
+    notification::{Progress, ShowMessage},
+    request::{CodeLensRefresh, WorkDoneProgressCreate},
+    Diagnostic, MessageType, ProgressParams, ProgressParamsValue, ProgressToken, ShowMessageParams,
+    Url, WorkDoneProgress, WorkDoneProgressCreateParams,
+};
 use tinymist_query::{DiagnosticsMap, LspDiagnostic};
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time::Instant};
 
 use crate::{tools::word_count::WordsCount, LspHost, TypstLanguageServer};
 
+/// The delay of notifying the compiling status, so that the status doesn't
+/// flash when the document is compiled quickly, e.g. while the user is typing.
+const COMPILING_STATUS_DELAY: Duration = Duration::from_millis(200);
+
 pub enum CompileClusterRequest {
     Diag(String, Option<DiagnosticsMap>),
     Status(String, TinymistCompileStatusEnum),
     WordCount(String, Option<WordsCount>),
+    Progress(ProgressToken, WorkDoneProgress),
+    /// The client responds to the creation of the pending progress, with
+    /// whether it succeeded.
+    ProgressCreated(bool),
+    RefreshCodeLens(String),
+    ShowMessage(MessageType, String),
 }
 
 pub struct EditorActor {
@@ -23,13 +39,39 @@ pub struct EditorActor {
     pub affect_map: HashMap<String, Vec<Url>>,
     pub published_primary: bool,
     pub notify_compile_status: bool,
+    pub refresh_code_lens: bool,
+    pub work_done_progress: bool,
 }
 
 impl EditorActor {
     pub async fn run(mut self) {
         let mut compile_status = TinymistCompileStatusEnum::Compiling;
+        let mut status_debounce = CompileStatusDebounce::default();
+        let mut progress_queue = ProgressQueue::default();
         let mut words_count = None;
-        while let Some(req) = self.diag_rx.recv().await {
+        loop {
+            let req = match status_debounce.deadline() {
+                Some(deadline) => tokio::select! {
+                    req = self.diag_rx.recv() => req,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        if let Some(status) = status_debounce.expire(Instant::now()) {
+                            compile_status = status;
+                            self.host.send_notification::<TinymistCompileStatus>(
+                                TinymistCompileStatus {
+                                    status: compile_status.clone(),
+                                    words_count: words_count.clone(),
+                                },
+                            );
+                        }
+                        continue;
+                    }
+                },
+                None => self.diag_rx.recv().await,
+            };
+            let Some(req) = req else {
+                break;
+            };
+
             match req {
                 CompileClusterRequest::Diag(group, diagnostics) => {
                     info!(
@@ -55,6 +97,9 @@ impl EditorActor {
                 CompileClusterRequest::Status(group, status) => {
                     log::debug!("received status request");
                     if self.notify_compile_status && group == "primary" {
+                        let Some(status) = status_debounce.update(status, Instant::now()) else {
+                            continue;
+                        };
                         compile_status = status;
                         self.host.send_notification::<TinymistCompileStatus>(
                             TinymistCompileStatus {
@@ -64,6 +109,32 @@ impl EditorActor {
                         );
                     }
                 }
+                CompileClusterRequest::Progress(token, progress) => {
+                    log::debug!("received progress request");
+                    if self.work_done_progress {
+                        let events = progress_queue.report(token, progress);
+                        self.send_progress(events);
+                    }
+                }
+                CompileClusterRequest::ProgressCreated(ok) => {
+                    let events = progress_queue.created(ok);
+                    self.send_progress(events);
+                }
+                CompileClusterRequest::RefreshCodeLens(group) => {
+                    log::debug!("received code lens refresh request");
+                    // The code lenses show the summary of the primary document.
+                    if self.refresh_code_lens && group == "primary" {
+                        self.host.send_request::<CodeLensRefresh>((), |_, resp| {
+                            if let Some(err) = resp.error {
+                                log::error!("failed to refresh code lenses: {err:?}");
+                            }
+                        });
+                    }
+                }
+                CompileClusterRequest::ShowMessage(typ, message) => {
+                    self.host
+                        .send_notification::<ShowMessage>(ShowMessageParams { typ, message });
+                }
                 CompileClusterRequest::WordCount(group, wc) => {
                     log::debug!("received word count request");
                     if self.notify_compile_status && group == "primary" {
@@ -81,6 +152,32 @@ impl EditorActor {
         info!("compile cluster actor is stopped");
     }
 
+    fn send_progress(&self, events: Vec<ProgressEvent>) {
+        for event in events {
+            match event {
+                ProgressEvent::Create(token) => {
+                    self.host.send_request::<WorkDoneProgressCreate>(
+                        WorkDoneProgressCreateParams { token },
+                        |server, resp| {
+                            if let Some(err) = &resp.error {
+                                log::error!("failed to create progress: {err:?}");
+                            }
+                            let created =
+                                CompileClusterRequest::ProgressCreated(resp.error.is_none());
+                            let _ = server.primary.diag_tx.send(created);
+                        },
+                    );
+                }
+                ProgressEvent::Notify(token, progress) => {
+                    self.host.send_notification::<Progress>(ProgressParams {
+                        token,
+                        value: ProgressParamsValue::WorkDone(progress),
+                    });
+                }
+            }
+        }
+    }
+
     async fn flush_primary_diagnostics(&mut self, enable: bool) {
         let affected = self.affect_map.get("primary");
 
@@ -159,9 +256,123 @@ impl EditorActor {
         }
     }
 }
+
+/// A message to send for a progress.
+#[derive(Debug)]
+enum ProgressEvent {
+    /// Creates the token by `window/workDoneProgress/create`.
+    Create(ProgressToken),
+    /// Reports the progress by `$/progress`.
+    Notify(ProgressToken, WorkDoneProgress),
+}
+
+/// Holds the progresses until the client creates their tokens, since the
+/// client rejects the `$/progress` of a token that it hasn't created. The
+/// tokens are created one at a time, since the response to the creation
+/// doesn't tell the token.
+#[derive(Debug, Default)]
+struct ProgressQueue {
+    /// The tokens to create, with their progresses reported so far. The first
+    /// one is being created.
+    pending: VecDeque<(ProgressToken, Vec<WorkDoneProgress>)>,
+    /// The created tokens, which have not ended yet.
+    created: HashSet<ProgressToken>,
+}
+
+impl ProgressQueue {
+    /// Receives a progress, returning the messages to send now.
+    fn report(&mut self, token: ProgressToken, progress: WorkDoneProgress) -> Vec<ProgressEvent> {
+        if self.created.contains(&token) {
+            if matches!(progress, WorkDoneProgress::End(..)) {
+                self.created.remove(&token);
+            }
+            return vec![ProgressEvent::Notify(token, progress)];
+        }
+        if let Some((_, queued)) = self.pending.iter_mut().find(|(t, _)| *t == token) {
+            queued.push(progress);
+            return vec![];
+        }
+        // The progresses of the tokens failed to create are dropped.
+        if !matches!(progress, WorkDoneProgress::Begin(..)) {
+            return vec![];
+        }
+        self.pending.push_back((token.clone(), vec![progress]));
+        if self.pending.len() == 1 {
+            vec![ProgressEvent::Create(token)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Receives the response to the creation of the first pending token,
+    /// returning the messages to send now.
+    fn created(&mut self, ok: bool) -> Vec<ProgressEvent> {
+        let Some((token, queued)) = self.pending.pop_front() else {
+            return vec![];
+        };
+        let mut events = vec![];
+        if ok {
+            if !matches!(queued.last(), Some(WorkDoneProgress::End(..))) {
+                self.created.insert(token.clone());
+            }
+            let queued = queued.into_iter();
+            events.extend(queued.map(|progress| ProgressEvent::Notify(token.clone(), progress)));
+        }
+        if let Some((next, _)) = self.pending.front() {
+            events.push(ProgressEvent::Create(next.clone()));
+        }
+        events
+    }
+}
+
+/// Debounces the compiling status, which is notified only if the compilation
+/// takes longer than [`COMPILING_STATUS_DELAY`]. The terminal statuses, i.e.
+/// the success and the error, are always notified.
+#[derive(Debug, Default)]
+struct CompileStatusDebounce {
+    /// The time when the pending compiling status is received, if any.
+    compiling_since: Option<Instant>,
+}
+
+impl CompileStatusDebounce {
+    /// Receives a status, returning it if it should be notified now.
+    fn update(
+        &mut self,
+        status: TinymistCompileStatusEnum,
+        now: Instant,
+    ) -> Option<TinymistCompileStatusEnum> {
+        match status {
+            TinymistCompileStatusEnum::Compiling => {
+                self.compiling_since.get_or_insert(now);
+                None
+            }
+            status => {
+                self.compiling_since = None;
+                Some(status)
+            }
+        }
+    }
+
+    /// The time when the pending compiling status should be notified, if any.
+    fn deadline(&self) -> Option<Instant> {
+        self.compiling_since
+            .map(|since| since + COMPILING_STATUS_DELAY)
+    }
+
+    /// Returns the pending compiling status if it is delayed long enough.
+    fn expire(&mut self, now: Instant) -> Option<TinymistCompileStatusEnum> {
+        let deadline = self.deadline()?;
+        if now < deadline {
+            return None;
+        }
+        self.compiling_since = None;
+        Some(TinymistCompileStatusEnum::Compiling)
+    }
+}
+
 // Notification
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum TinymistCompileStatusEnum {
     Compiling,
@@ -180,3 +391,110 @@ impl lsp_types::notification::Notification for TinymistCompileStatus {
     type Params = Self;
     const METHOD: &'static str = "tinymist/compileStatus";
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TinymistCompileStatusEnum::*;
+
+    fn begin() -> WorkDoneProgress {
+        WorkDoneProgress::Begin(lsp_types::WorkDoneProgressBegin::default())
+    }
+
+    fn end() -> WorkDoneProgress {
+        WorkDoneProgress::End(lsp_types::WorkDoneProgressEnd { message: None })
+    }
+
+    fn token(id: &str) -> ProgressToken {
+        ProgressToken::String(id.to_owned())
+    }
+
+    fn summary(events: Vec<ProgressEvent>) -> Vec<String> {
+        let token_id = |token: ProgressToken| match token {
+            ProgressToken::String(id) => id,
+            ProgressToken::Number(id) => id.to_string(),
+        };
+        events
+            .into_iter()
+            .map(|event| match event {
+                ProgressEvent::Create(token) => format!("create {}", token_id(token)),
+                ProgressEvent::Notify(token, WorkDoneProgress::Begin(..)) => {
+                    format!("begin {}", token_id(token))
+                }
+                ProgressEvent::Notify(token, WorkDoneProgress::Report(..)) => {
+                    format!("report {}", token_id(token))
+                }
+                ProgressEvent::Notify(token, WorkDoneProgress::End(..)) => {
+                    format!("end {}", token_id(token))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_progress_after_creation() {
+        let mut queue = ProgressQueue::default();
+        assert_eq!(summary(queue.report(token("a"), begin())), ["create a"]);
+        // The progresses wait for the response to the creation.
+        assert!(queue.report(token("a"), end()).is_empty());
+        assert_eq!(summary(queue.created(true)), ["begin a", "end a"]);
+        assert!(queue.created.is_empty());
+    }
+
+    #[test]
+    fn test_progress_created_one_at_a_time() {
+        let mut queue = ProgressQueue::default();
+        assert_eq!(summary(queue.report(token("a"), begin())), ["create a"]);
+        assert!(queue.report(token("b"), begin()).is_empty());
+        assert_eq!(summary(queue.created(true)), ["begin a", "create b"]);
+        assert_eq!(summary(queue.report(token("a"), end())), ["end a"]);
+        assert_eq!(summary(queue.created(true)), ["begin b"]);
+        assert_eq!(summary(queue.report(token("b"), end())), ["end b"]);
+        assert!(queue.created.is_empty());
+    }
+
+    #[test]
+    fn test_progress_creation_failed() {
+        let mut queue = ProgressQueue::default();
+        assert_eq!(summary(queue.report(token("a"), begin())), ["create a"]);
+        assert!(queue.created(false).is_empty());
+        assert!(queue.report(token("a"), end()).is_empty());
+    }
+
+    #[test]
+    fn test_debounce_quick_compilation() {
+        let mut debounce = CompileStatusDebounce::default();
+        let start = Instant::now();
+        assert_eq!(debounce.update(Compiling, start), None);
+        assert_eq!(debounce.deadline(), Some(start + COMPILING_STATUS_DELAY));
+
+        let now = start + COMPILING_STATUS_DELAY / 2;
+        assert_eq!(debounce.expire(now), None);
+        assert_eq!(debounce.update(CompileSuccess, now), Some(CompileSuccess));
+        assert_eq!(debounce.deadline(), None);
+    }
+
+    #[test]
+    fn test_debounce_slow_compilation() {
+        let mut debounce = CompileStatusDebounce::default();
+        let start = Instant::now();
+        assert_eq!(debounce.update(Compiling, start), None);
+        // The compilations following a burst of edits don't postpone the status.
+        let now = start + COMPILING_STATUS_DELAY / 2;
+        assert_eq!(debounce.update(Compiling, now), None);
+
+        let now = start + COMPILING_STATUS_DELAY;
+        assert_eq!(debounce.expire(now), Some(Compiling));
+        assert_eq!(debounce.deadline(), None);
+        assert_eq!(debounce.update(CompileError, now), Some(CompileError));
+    }
+
+    #[test]
+    fn test_debounce_terminal_status() {
+        let mut debounce = CompileStatusDebounce::default();
+        let now = Instant::now();
+        assert_eq!(debounce.update(CompileSuccess, now), Some(CompileSuccess));
+        assert_eq!(debounce.update(CompileSuccess, now), Some(CompileSuccess));
+        assert_eq!(debounce.expire(now + COMPILING_STATUS_DELAY), None);
+    }
+}
@@ -5,10 +5,15 @@ use typst::syntax::Source;
 
 use crate::{result_to_response_, FormatterMode, LspHost, LspResult, TypstLanguageServer};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FormattingConfig {
     pub mode: FormatterMode,
     pub width: u32,
+    /// The number of spaces per indentation level, or the default of the
+    /// formatter.
+    pub indent: Option<u32>,
+    /// Whether to keep the line breaks in markup instead of wrapping lines.
+    pub keep_hard_breaks: bool,
 }
 
 pub enum FormattingRequest {
@@ -27,6 +32,9 @@ pub fn run_format_thread(
         log::info!("formatting thread with config: {c:#?}");
         match c.mode {
             FormatterMode::Typstyle => {
+                if c.indent.is_some_and(|indent| indent != 2) || c.keep_hard_breaks {
+                    log::warn!("typstyle only supports the print width, other options are ignored");
+                }
                 let cw = c.width as usize;
                 let f: FmtFn = Box::new(move |e: Source| {
                     let res = typstyle_core::Typstyle::new_with_src(e.clone(), cw).pretty_print();
@@ -35,9 +43,14 @@ pub fn run_format_thread(
                 f
             }
             FormatterMode::Typstfmt => {
+                let default = typstfmt_lib::Config::default();
                 let config = typstfmt_lib::Config {
                     max_line_length: c.width as usize,
-                    ..typstfmt_lib::Config::default()
+                    indent_space: c
+                        .indent
+                        .map_or(default.indent_space, |indent| indent as usize),
+                    line_wrap: !c.keep_hard_breaks,
+                    ..default
                 };
                 let f: FmtFn = Box::new(move |e: Source| {
                     let res = typstfmt_lib::format(e.text(), config);
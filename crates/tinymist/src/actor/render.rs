@@ -2,19 +2,31 @@
 
 use std::{
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, Timelike, Utc};
 use log::{error, info};
+use lsp_types::{
+    NumberOrString, ProgressToken, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd,
+    WorkDoneProgressReport,
+};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use tinymist_query::{ExportKind, PageSelection};
+use tinymist_query::{is_virtual_path, ExportKind, PageRanges, PageSelection};
 use tokio::sync::{
     broadcast::{self, error::RecvError},
     mpsc, oneshot, watch,
 };
-use typst::{foundations::Smart, layout::Frame};
+use typst::{
+    foundations::{Datetime, Smart},
+    layout::Frame,
+};
 use typst_ts_core::{config::compiler::EntryState, path::PathClean, ImmutPath, TypstDocument};
 
 use crate::{tools::word_count, ExportMode};
@@ -47,6 +59,10 @@ pub struct ExportConfig {
     pub substitute_pattern: String,
     pub entry: EntryState,
     pub mode: ExportMode,
+    /// The delay of the exports on typing, which is restarted by each edit.
+    pub debounce: Duration,
+    /// The time fixing the creation date of the exports, if any.
+    pub creation_timestamp: Option<DateTime<Utc>>,
 }
 
 pub struct ExportActor {
@@ -58,6 +74,8 @@ pub struct ExportActor {
     pub substitute_pattern: String,
     pub entry: EntryState,
     pub mode: ExportMode,
+    pub debounce: Duration,
+    pub creation_timestamp: Option<DateTime<Utc>>,
     pub kind: ExportKind,
 }
 
@@ -78,14 +96,29 @@ impl ExportActor {
             substitute_pattern: config.substitute_pattern,
             entry: config.entry,
             mode: config.mode,
+            debounce: config.debounce,
+            creation_timestamp: config.creation_timestamp,
             kind,
         }
     }
 
     pub async fn run(mut self) {
         let kind = &self.kind;
+        // The deadline of the pending export on typing, if any.
+        let mut typed_deadline = None;
         loop {
-            let req = match self.render_rx.recv().await {
+            let req = match typed_deadline {
+                Some(deadline) => tokio::select! {
+                    req = self.render_rx.recv() => req,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        typed_deadline = None;
+                        self.check_mode_and_export(RenderActorRequest::OnTyped).await;
+                        continue;
+                    }
+                },
+                None => self.render_rx.recv().await,
+            };
+            let req = match req {
                 Ok(req) => req,
                 Err(RecvError::Closed) => {
                     info!("RenderActor(@{kind:?}): channel closed");
@@ -103,10 +136,20 @@ impl ExportActor {
                     self.substitute_pattern = cfg.substitute_pattern;
                     self.entry = cfg.entry;
                     self.mode = cfg.mode;
+                    self.debounce = cfg.debounce;
+                    self.creation_timestamp = cfg.creation_timestamp;
                 }
                 RenderActorRequest::ChangeExportPath(cfg) => {
                     self.entry = cfg.entry;
                 }
+                // The oneshot exports are left to the actor exporting files, so
+                // that they are not exported and reported twice.
+                RenderActorRequest::Oneshot(..) if matches!(kind, ExportKind::WordCount) => {}
+                // The exports on typing are delayed until the typing pauses, so
+                // that a document is exported once for a burst of edits.
+                RenderActorRequest::OnTyped if !self.debounce.is_zero() => {
+                    typed_deadline = Some(tokio::time::Instant::now() + self.debounce);
+                }
                 _ => {
                     let cb = match &req {
                         RenderActorRequest::Oneshot(oneshot) => Some(oneshot.callback.clone()),
@@ -177,7 +220,21 @@ impl ExportActor {
                 && matches!(req, RenderActorRequest::OnSaved(..))
         };
         if should_do {
-            return match self.export(kind, &document, &root, &path).await {
+            // The progress of the exports requested by the user is reported.
+            let progress = matches!(req, RenderActorRequest::Oneshot(..)).then(|| {
+                let title = format!("Exporting {}", kind.extension().to_uppercase());
+                ExportProgress::begin(&self.editor_tx, title)
+            });
+            let res = self
+                .export(kind, &document, &root, &path, progress.as_ref())
+                .await;
+            if let Some(progress) = progress {
+                progress.end(match &res {
+                    Ok(to) => format!("Exported to {}", to.display()),
+                    Err(..) => "Failed to export".to_owned(),
+                });
+            }
+            return match res {
                 Ok(pdf) => Some(pdf),
                 Err(err) => {
                     error!("RenderActor({kind:?}): failed to export {err}");
@@ -195,82 +252,91 @@ impl ExportActor {
         doc: &TypstDocument,
         root: &Path,
         path: &Path,
+        progress: Option<&ExportProgress<'_>>,
     ) -> anyhow::Result<PathBuf> {
-        let Some(to) = substitute_path(&self.substitute_pattern, root, path) else {
-            return Err(anyhow::anyhow!(
-                "RenderActor({kind:?}): failed to substitute path"
-            ));
+        let output = match kind {
+            ExportKind::Svg { output, .. } | ExportKind::Png { output, .. } => output.as_deref(),
+            _ => None,
+        };
+        let to = match output {
+            // The output template is relative to the directory of the document.
+            Some(output) => path.parent().unwrap_or(root).join(output).clean(),
+            None => {
+                let now = match self.creation_timestamp {
+                    Some(now) => now.with_timezone(&Local),
+                    None => Local::now(),
+                };
+                let pattern = substitute_date(&self.substitute_pattern, &now.naive_local());
+                let Some(to) = substitute_path(&pattern, root, path) else {
+                    bail!("RenderActor({kind:?}): failed to substitute path");
+                };
+                to.as_ref().to_owned()
+            }
         };
         if to.is_relative() {
-            return Err(anyhow::anyhow!(
-                "RenderActor({kind:?}): path is relative: {to:?}"
-            ));
+            bail!("RenderActor({kind:?}): path is relative: {to:?}");
         }
         if to.is_dir() {
-            return Err(anyhow::anyhow!(
-                "RenderActor({kind:?}): path is a directory: {to:?}"
-            ));
+            bail!("RenderActor({kind:?}): path is a directory: {to:?}");
         }
 
-        let to = to.with_extension(kind.extension());
+        let to = if output.is_some() && to.extension().is_some() {
+            to
+        } else {
+            to.with_extension(kind.extension())
+        };
         info!("RenderActor({kind:?}): exporting {path:?} to {to:?}");
 
-        if let Some(e) = to.parent() {
-            if !e.exists() {
-                std::fs::create_dir_all(e).with_context(|| {
-                    format!("RenderActor({kind:?}): failed to create directory")
-                })?;
-            }
-        }
-
         static DEFAULT_FRAME: Lazy<Frame> = Lazy::new(Frame::default);
-        let data = match kind {
-            ExportKind::Pdf => {
-                // todo: Some(pdf_uri.as_str())
-                // todo: timestamp world.now()
-                typst_pdf::pdf(doc, Smart::Auto, None)
-            }
+        let first_frame = doc.pages.first().map(|f| &f.frame);
+        let first_frame = first_frame.unwrap_or(&*DEFAULT_FRAME);
+        let merged = format!("1-{}", doc.pages.len());
+        let (data, to) = match kind {
+            ExportKind::Pdf => (export_pdf(doc, self.creation_timestamp)?, to),
             ExportKind::Svg {
                 page: PageSelection::First,
-            } => typst_svg::svg(
-                doc.pages
-                    .first()
-                    .map(|f| &f.frame)
-                    .unwrap_or(&*DEFAULT_FRAME),
-            )
-            .into_bytes(),
+                ..
+            } => (
+                typst_svg::svg(first_frame).into_bytes(),
+                page_path(&to, "1", false),
+            ),
             ExportKind::Svg {
                 page: PageSelection::Merged,
-            } => typst_svg::svg_merged(doc, typst::layout::Abs::zero()).into_bytes(),
+                ..
+            } => (
+                typst_svg::svg_merged(doc, typst::layout::Abs::zero()).into_bytes(),
+                page_path(&to, &merged, false),
+            ),
             ExportKind::Png {
                 page: PageSelection::First,
-            } => {
-                let pixmap = typst_render::render(
-                    doc.pages
-                        .first()
-                        .map(|f| &f.frame)
-                        .unwrap_or(&*DEFAULT_FRAME),
-                    3.,
-                    typst::visualize::Color::WHITE,
-                );
-                pixmap
-                    .encode_png()
-                    .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?
-            }
+                ppi,
+                ..
+            } => (render_png(first_frame, *ppi)?, page_path(&to, "1", false)),
             ExportKind::Png {
                 page: PageSelection::Merged,
+                ppi,
+                ..
             } => {
                 let pixmap = typst_render::render_merged(
                     doc,
-                    3.,
+                    pixel_per_pt(*ppi),
                     typst::visualize::Color::WHITE,
                     typst::layout::Abs::zero(),
                     typst::visualize::Color::WHITE,
                 );
-                pixmap
+                let data = pixmap
                     .encode_png()
-                    .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?
+                    .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))?;
+                (data, page_path(&to, &merged, false))
+            }
+            ExportKind::Svg {
+                page: PageSelection::Ranges(ranges),
+                ..
             }
+            | ExportKind::Png {
+                page: PageSelection::Ranges(ranges),
+                ..
+            } => return export_pages(kind, doc, ranges, &to, progress),
             ExportKind::WordCount => {
                 let wc = word_count::word_count(doc);
                 log::debug!("word count: {wc:?}");
@@ -282,19 +348,185 @@ impl ExportActor {
             }
         };
 
-        std::fs::write(&to, data)
-            .with_context(|| format!("RenderActor({kind:?}): failed to export"))?;
+        write_export(kind, &to, data)?;
 
         info!("RenderActor({kind:?}): export complete");
         Ok(to)
     }
 }
 
+/// Exports each page in the ranges to its own file, returning the path of the
+/// first page.
+fn export_pages(
+    kind: &ExportKind,
+    doc: &TypstDocument,
+    ranges: &PageRanges,
+    to: &Path,
+    progress: Option<&ExportProgress<'_>>,
+) -> anyhow::Result<PathBuf> {
+    let pages = doc.pages.iter().enumerate();
+    let pages = pages
+        .map(|(idx, page)| (idx + 1, page))
+        .filter(|(number, _)| ranges.contains(*number))
+        .collect::<Vec<_>>();
+    if pages.is_empty() {
+        bail!("RenderActor({kind:?}): no pages in the ranges");
+    }
+
+    let mut first = None;
+    for (idx, (number, page)) in pages.iter().enumerate() {
+        if let Some(progress) = progress {
+            let message = format!("Page {number} ({}/{})", idx + 1, pages.len());
+            progress.report(message, (idx * 100 / pages.len()) as u32);
+        }
+
+        let data = match kind {
+            ExportKind::Svg { .. } => typst_svg::svg(&page.frame).into_bytes(),
+            ExportKind::Png { ppi, .. } => render_png(&page.frame, *ppi)?,
+            _ => bail!("RenderActor({kind:?}): cannot export pages"),
+        };
+        let to = page_path(to, &number.to_string(), true);
+        write_export(kind, &to, data)?;
+        first.get_or_insert(to);
+    }
+
+    info!("RenderActor({kind:?}): export complete");
+    Ok(first.unwrap())
+}
+
+/// Exports a document as PDF. The creation date is set if it is fixed.
+fn export_pdf(
+    doc: &TypstDocument,
+    creation_timestamp: Option<DateTime<Utc>>,
+) -> anyhow::Result<Vec<u8>> {
+    // todo: Some(pdf_uri.as_str())
+    let timestamp = creation_timestamp.and_then(to_datetime);
+    Ok(typst_pdf::pdf(doc, Smart::Auto, timestamp))
+}
+
+/// Writes the exported data, creating the parent directories.
+fn write_export(kind: &ExportKind, to: &Path, data: Vec<u8>) -> anyhow::Result<()> {
+    if let Some(e) = to.parent() {
+        if !e.exists() {
+            std::fs::create_dir_all(e)
+                .with_context(|| format!("RenderActor({kind:?}): failed to create directory"))?;
+        }
+    }
+
+    std::fs::write(to, data).with_context(|| format!("RenderActor({kind:?}): failed to export"))
+}
+
+/// The pixels per inch of the PNG images by default, i.e. 3 pixels per point.
+const DEFAULT_PPI: f32 = 216.;
+
+fn pixel_per_pt(ppi: Option<f32>) -> f32 {
+    ppi.unwrap_or(DEFAULT_PPI) / 72.
+}
+
+fn render_png(frame: &Frame, ppi: Option<f32>) -> anyhow::Result<Vec<u8>> {
+    let pixmap = typst_render::render(frame, pixel_per_pt(ppi), typst::visualize::Color::WHITE);
+    pixmap
+        .encode_png()
+        .map_err(|err| anyhow::anyhow!("failed to encode PNG ({err})"))
+}
+
+/// The placeholder of the page number in an output path.
+const PAGE_PLACEHOLDER: &str = "{page}";
+
+/// Substitutes a page number, or a range of pages, for the placeholder in an
+/// output path. If there is no placeholder, the number is appended to the file
+/// stem when `append` is set, so that the pages are exported to different
+/// files.
+fn page_path(to: &Path, page: &str, append: bool) -> PathBuf {
+    let path = to.to_string_lossy();
+    if path.contains(PAGE_PLACEHOLDER) {
+        return PathBuf::from(path.replace(PAGE_PLACEHOLDER, page));
+    }
+    if !append {
+        return to.to_owned();
+    }
+
+    let stem = to.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match to.extension() {
+        Some(ext) => format!("{stem}-{page}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{page}"),
+    };
+    to.with_file_name(file_name)
+}
+
+/// Reports the progress of an export to the client.
+struct ExportProgress<'a> {
+    editor_tx: &'a mpsc::UnboundedSender<CompileClusterRequest>,
+    token: ProgressToken,
+}
+
+impl<'a> ExportProgress<'a> {
+    fn begin(editor_tx: &'a mpsc::UnboundedSender<CompileClusterRequest>, title: String) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let progress = Self {
+            editor_tx,
+            token: NumberOrString::String(format!("tinymist/export/{id}")),
+        };
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title,
+            percentage: Some(0),
+            ..Default::default()
+        }));
+        progress
+    }
+
+    fn report(&self, message: String, percentage: u32) {
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            message: Some(message),
+            percentage: Some(percentage),
+            ..Default::default()
+        }));
+    }
+
+    fn end(self, message: String) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(message),
+        }));
+    }
+
+    fn send(&self, progress: WorkDoneProgress) {
+        let req = CompileClusterRequest::Progress(self.token.clone(), progress);
+        let _ = self.editor_tx.send(req);
+    }
+}
+
+/// Converts a time to a datetime of typst in UTC.
+fn to_datetime(time: DateTime<Utc>) -> Option<Datetime> {
+    Datetime::from_ymd_hms(
+        time.year(),
+        time.month().try_into().ok()?,
+        time.day().try_into().ok()?,
+        time.hour().try_into().ok()?,
+        time.minute().try_into().ok()?,
+        time.second().try_into().ok()?,
+    )
+}
+
+/// Substitutes the date and the time of an export for the `{date}` and
+/// `{time}` placeholders in an output path pattern.
+fn substitute_date(substitute_pattern: &str, now: &NaiveDateTime) -> String {
+    substitute_pattern
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H-%M-%S").to_string())
+}
+
+/// Substitutes the paths of a document for the `$root` or `{root}`, `$dir` or
+/// `{dir}`, and `$name` placeholders in an output path pattern, and the stem
+/// of its file name for the `{name}` placeholder.
 #[comemo::memoize]
 fn substitute_path(substitute_pattern: &str, root: &Path, path: &Path) -> Option<ImmutPath> {
-    if let Ok(path) = path.strip_prefix("/untitled") {
-        let tmp = std::env::temp_dir();
-        let path = tmp.join("typst").join(path);
+    // The documents not on the file system are exported to the temporary
+    // directory.
+    if is_virtual_path(path) {
+        let path = path.strip_prefix("/untitled").unwrap_or(path);
+        let path = path.strip_prefix("/").unwrap_or(path);
+        let path = std::env::temp_dir().join("typst").join(path);
         return Some(path.as_path().into());
     }
 
@@ -310,18 +542,26 @@ fn substitute_path(substitute_pattern: &str, root: &Path, path: &Path) -> Option
     let f = file_name.to_string_lossy();
 
     // replace all $root
-    let mut path = substitute_pattern.replace("$root", &w);
+    let mut path = substitute_pattern
+        .replace("$root", &w)
+        .replace("{root}", &w);
     if let Some(dir) = dir {
         let d = dir.to_string_lossy();
-        path = path.replace("$dir", &d);
+        path = path.replace("$dir", &d).replace("{dir}", &d);
     }
     path = path.replace("$name", &f);
+    // The file stem is substituted, so that the placeholder can be followed by
+    // a suffix.
+    let stem = Path::new(file_name).file_stem().unwrap_or_default();
+    path = path.replace("{name}", &stem.to_string_lossy());
 
     Some(PathBuf::from(path).clean().into())
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::NaiveDate;
+
     use super::*;
 
     #[test]
@@ -346,4 +586,49 @@ mod tests {
             Some(PathBuf::from("/substitute/target/dir1/dir2/file.txt").into())
         );
     }
+
+    #[test]
+    fn test_substitute_braced_path() {
+        let root = Path::new("/root");
+        let path = Path::new("/root/dir1/file.typ");
+        let now = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .and_then(|date| date.and_hms_opt(8, 5, 30))
+            .unwrap();
+
+        let pattern = substitute_date("{root}/out/{date}/{dir}/{name}-{time}", &now);
+        assert_eq!(pattern, "{root}/out/2024-03-09/{dir}/{name}-08-05-30");
+        assert_eq!(
+            substitute_path(&pattern, root, path),
+            Some(PathBuf::from("/root/out/2024-03-09/dir1/file-08-05-30").into())
+        );
+    }
+
+    #[test]
+    fn test_page_path() {
+        let to = Path::new("/out/doc.png");
+        assert_eq!(page_path(to, "2", true), PathBuf::from("/out/doc-2.png"));
+        assert_eq!(page_path(to, "2", false), PathBuf::from("/out/doc.png"));
+
+        let to = Path::new("/out/{page}/doc-{page}.svg");
+        assert_eq!(
+            page_path(to, "1-3", false),
+            PathBuf::from("/out/1-3/doc-1-3.svg")
+        );
+    }
+
+    #[test]
+    fn test_page_ranges() {
+        let ranges: PageRanges = "1-3, 5,8-".parse().unwrap();
+        let pages = (1..=10).filter(|page| ranges.contains(*page));
+        assert_eq!(pages.collect::<Vec<_>>(), vec![1, 2, 3, 5, 8, 9, 10]);
+        assert_eq!(String::from(ranges), "1-3,5,8-");
+
+        let ranges: PageRanges = "-2".parse().unwrap();
+        assert!(ranges.contains(1) && !ranges.contains(3));
+
+        assert!("0".parse::<PageRanges>().is_err());
+        assert!("3-1".parse::<PageRanges>().is_err());
+        assert!("a".parse::<PageRanges>().is_err());
+        assert!("".parse::<PageRanges>().is_err());
+    }
 }
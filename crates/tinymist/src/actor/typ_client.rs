@@ -26,18 +26,23 @@
 //! information to other actors.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::Deref,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
 use log::{error, info, trace};
 use parking_lot::Mutex;
 use tinymist_query::{
-    analysis::{Analysis, AnalysisContext, AnalysisResources},
-    DiagnosticsMap, ExportKind, ServerInfoResponse, VersionedDocument,
+    analysis::{
+        warm_up_file, warm_up_files, warm_up_library, Analysis, AnalysisContext, AnalysisResources,
+    },
+    is_virtual_path, run_test, DiagnosticsMap, ExportKind, LintCache, RunTestsRequest,
+    SemanticTokenContext, ServerInfoResponse, TestReport, VersionedDocument,
 };
 use tinymist_render::PeriscopeRenderer;
 use tokio::sync::{broadcast, mpsc, oneshot, watch};
@@ -52,7 +57,7 @@ use typst::{
 use typst_ts_compiler::{
     service::{CompileDriverImpl, CompileEnv, CompileMiddleware, Compiler, EntryManager, EnvWorld},
     vfs::notify::{FileChangeSet, MemoryEvent},
-    Time,
+    ShadowApi, Time,
 };
 use typst_ts_core::{
     config::compiler::EntryState, debug_loc::DataSource, error::prelude::*, typst::prelude::EcoVec,
@@ -62,15 +67,18 @@ use typst_ts_core::{
 use super::{
     cluster::{CompileClusterRequest, TinymistCompileStatusEnum},
     render::ExportConfig,
-    typ_server::{CompileClient as TsCompileClient, CompileServerActor},
+    typ_server::{CompileClient as TsCompileClient, CompileServerActor, MemoryRevisions},
 };
 use crate::{
+    actor::load_analysis_providers,
     actor::render::{OneshotRendering, PathVars, RenderActorRequest},
     actor::typ_server::EntryStateExt,
     compiler_init::CompileConfig,
+    tools::package::{registry_packages, registry_revision},
     tools::preview::{CompilationHandle, CompileStatus},
+    tools::stats::CompilerStats,
     utils,
-    world::LspWorld,
+    world::{FixedTimeWorld, LspWorld, SharedFontResolver},
 };
 
 type CompileDriverInner = CompileDriverImpl<LspWorld>;
@@ -88,6 +96,10 @@ pub struct CompileHandler {
     pub(super) doc_tx: watch::Sender<Option<Arc<TypstDocument>>>,
     pub(super) render_tx: broadcast::Sender<RenderActorRequest>,
     pub(super) editor_tx: EditorSender,
+    pub(super) revisions: Arc<MemoryRevisions>,
+    /// The main file and the summary of the last compiled document, which are
+    /// shown in the code lenses.
+    pub(super) summary: Arc<Mutex<Option<(PathBuf, String)>>>,
 }
 
 impl CompilationHandle for CompileHandler {
@@ -105,16 +117,11 @@ impl CompilationHandle for CompileHandler {
             let _ = self.render_tx.send(RenderActorRequest::OnTyped);
         }
 
-        self.editor_tx
-            .send(CompileClusterRequest::Status(
-                self.diag_group.clone(),
-                if res.is_ok() {
-                    TinymistCompileStatusEnum::CompileSuccess
-                } else {
-                    TinymistCompileStatusEnum::CompileError
-                },
-            ))
-            .unwrap();
+        self.notify_status(if res.is_ok() {
+            TinymistCompileStatusEnum::CompileSuccess
+        } else {
+            TinymistCompileStatusEnum::CompileError
+        });
 
         #[cfg(feature = "preview")]
         if let Some(inner) = self.inner.lock().as_ref() {
@@ -124,6 +131,11 @@ impl CompilationHandle for CompileHandler {
 }
 
 impl CompileHandler {
+    fn notify_status(&self, status: TinymistCompileStatusEnum) {
+        let status = CompileClusterRequest::Status(self.diag_group.clone(), status);
+        self.editor_tx.send(status).unwrap();
+    }
+
     fn push_diagnostics(&mut self, diagnostics: Option<DiagnosticsMap>) {
         let res = self.editor_tx.send(CompileClusterRequest::Diag(
             self.diag_group.clone(),
@@ -141,6 +153,12 @@ pub struct CompileDriver {
     pub(super) handler: CompileHandler,
     pub(super) analysis: Analysis,
     pub(super) periscope: PeriscopeRenderer,
+    /// The time fixing the current date of the compilations, if any.
+    pub(super) creation_timestamp: Option<DateTime<Utc>>,
+    /// The lints of the opened files.
+    pub(super) lints: LintCache,
+    /// The revision of the registry index which the lints are checked with.
+    pub(super) registry_revision: usize,
 }
 
 impl CompileMiddleware for CompileDriver {
@@ -156,16 +174,34 @@ impl CompileMiddleware for CompileDriver {
 
     fn wrap_compile(&mut self, env: &mut CompileEnv) -> SourceResult<Arc<typst::model::Document>> {
         self.handler
-            .editor_tx
-            .send(CompileClusterRequest::Status(
-                self.handler.diag_group.clone(),
-                TinymistCompileStatusEnum::Compiling,
-            ))
-            .unwrap();
+            .notify_status(TinymistCompileStatusEnum::Compiling);
         self.handler.status(CompileStatus::Compiling);
-        match self.inner_mut().compile(env) {
+        let res = {
+            let _scope = tinymist_query::profile::scope("compile");
+            match self.creation_timestamp {
+                Some(now) => self.compile_at(env, now),
+                None => self.inner_mut().compile(env),
+            }
+        };
+
+        // The memory files are changed during the compilation, e.g. when the user is
+        // typing. The results are dropped instead of flashing the outdated
+        // diagnostics. The status is still notified, since the changes may not
+        // trigger another compilation, e.g. if they are reverted.
+        if self.handler.revisions.is_stale() {
+            log::debug!("TypstActor: dropping stale compilation results");
+            self.handler.notify_status(if res.is_ok() {
+                TinymistCompileStatusEnum::CompileSuccess
+            } else {
+                TinymistCompileStatusEnum::CompileError
+            });
+            return res.map_err(|_| EcoVec::new());
+        }
+
+        match res {
             Ok(doc) => {
                 self.handler.notify_compile(Ok(doc.clone()));
+                self.notify_summary(&doc);
                 self.notify_diagnostics(
                     EcoVec::new(),
                     env.tracer.as_ref().map(|e| e.clone().warnings()),
@@ -183,6 +219,39 @@ impl CompileMiddleware for CompileDriver {
 }
 
 impl CompileDriver {
+    /// Compiles the document with the current date fixed at a time, after
+    /// resetting the world the same as the inner compiler does.
+    fn compile_at(
+        &mut self,
+        env: &mut CompileEnv,
+        now: DateTime<Utc>,
+    ) -> SourceResult<Arc<typst::model::Document>> {
+        self.inner.reset()?;
+        let world = FixedTimeWorld::new(self.inner.world(), now);
+        let mut tracer = env.tracer.take().unwrap_or_default();
+        let res = typst::compile(&world, &mut tracer).map(Arc::new);
+        env.tracer = Some(tracer);
+        res
+    }
+
+    /// Updates the summary of the compiled document, asking the editor to
+    /// refresh the code lenses only if the summary is changed.
+    fn notify_summary(&self, doc: &TypstDocument) {
+        let entry = &self.inner.world().entry;
+        let main = entry.root().zip(entry.main());
+        let main = main.filter(|(_, main)| main.package().is_none());
+        let main = main.and_then(|(root, main)| main.vpath().resolve(&root));
+        let summary = main.map(|main| (main, tinymist_query::document_summary(doc)));
+
+        let mut last = self.handler.summary.lock();
+        if *last == summary {
+            return;
+        }
+        *last = summary;
+        let group = self.handler.diag_group.clone();
+        let _ = (self.handler.editor_tx).send(CompileClusterRequest::RefreshCodeLens(group));
+    }
+
     fn notify_diagnostics(
         &mut self,
         errors: EcoVec<SourceDiagnostic>,
@@ -190,9 +259,26 @@ impl CompileDriver {
     ) {
         trace!("notify diagnostics: {errors:#?} {warnings:#?}");
 
+        // The packages are checked again once the registry index is fetched.
+        let revision = registry_revision();
+        if revision != self.registry_revision {
+            self.registry_revision = revision;
+            self.lints.clear();
+        }
+
+        // Only the files opened in the editor are linted.
+        let opened: HashSet<ImmutPath> = self.inner.world().shadow_paths().into_iter().collect();
+        let mut lints = std::mem::take(&mut self.lints);
         let diagnostics = self.run_analysis(|ctx| {
-            tinymist_query::convert_diagnostics(ctx, errors.iter().chain(warnings.iter().flatten()))
+            let mut diagnostics = tinymist_query::convert_diagnostics(
+                ctx,
+                errors.iter().chain(warnings.iter().flatten()),
+            );
+            let lints = tinymist_query::lint_diagnostics(ctx, &opened, &mut lints);
+            tinymist_query::merge_diagnostics(&mut diagnostics, lints);
+            diagnostics
         });
+        self.lints = lints;
 
         match diagnostics {
             Ok(diagnostics) => {
@@ -232,11 +318,18 @@ impl CompileDriver {
             anyhow!("failed to prepare env")
         })?;
 
-        struct WrapWorld<'a>(&'a mut LspWorld, &'a PeriscopeRenderer);
+        /// The world, and the world fixing its current date if the creation
+        /// timestamp is set, so that the previews compiled in analysis show
+        /// the same date as the document.
+        struct WrapWorld<'a>(
+            &'a LspWorld,
+            &'a PeriscopeRenderer,
+            Option<&'a dyn typst::World>,
+        );
 
         impl<'a> AnalysisResources for WrapWorld<'a> {
             fn world(&self) -> &dyn typst::World {
-                self.0
+                self.2.unwrap_or(self.0)
             }
 
             fn resolve(&self, spec: &PackageSpec) -> Result<Arc<Path>, PackageError> {
@@ -249,6 +342,12 @@ impl CompileDriver {
                 self.0.iter_dependencies(f)
             }
 
+            /// Get the packages in the registry index, which is fetched in the
+            /// background.
+            fn registry_packages(&self) -> EcoVec<PackageSpec> {
+                registry_packages()
+            }
+
             /// Resolve extra font information.
             fn font_info(&self, font: TypstFont) -> Option<Arc<DataSource>> {
                 self.0.font_resolver.inner.describe_font(&font)
@@ -263,9 +362,21 @@ impl CompileDriver {
             ) -> Option<String> {
                 self.1.render_marked(ctx, doc, pos)
             }
+
+            /// Render the first page of a preview document.
+            fn preview_image(
+                &self,
+                _ctx: &mut AnalysisContext,
+                doc: VersionedDocument,
+            ) -> Option<String> {
+                self.1.render_preview_marked(doc)
+            }
         }
 
-        let w = WrapWorld(w, &self.periscope);
+        let w = &*w;
+        let fixed = (self.creation_timestamp).map(|now| FixedTimeWorld::new(w, now));
+        let fixed = fixed.as_ref().map(|w| w as &dyn typst::World);
+        let w = WrapWorld(w, &self.periscope, fixed);
 
         self.analysis.root = root;
         Ok(f(&mut AnalysisContext::new_borrow(&w, &mut self.analysis)))
@@ -275,28 +386,44 @@ impl CompileDriver {
 pub struct CompileClientActor {
     pub diag_group: String,
     pub config: CompileConfig,
+    /// Whether the client renders markdown in hover, which the previews in
+    /// hover require.
+    hover_markdown: bool,
     entry: EntryState,
     inner: Deferred<CompileClient>,
     render_tx: broadcast::Sender<RenderActorRequest>,
+    summary: Arc<Mutex<Option<(PathBuf, String)>>>,
 }
 
 impl CompileClientActor {
     pub(crate) fn new(
         diag_group: String,
         config: CompileConfig,
+        hover_markdown: bool,
         entry: EntryState,
         inner: Deferred<CompileClient>,
         render_tx: broadcast::Sender<RenderActorRequest>,
+        summary: Arc<Mutex<Option<(PathBuf, String)>>>,
     ) -> Self {
         Self {
             diag_group,
             config,
+            hover_markdown,
             entry,
             inner,
             render_tx,
+            summary,
         }
     }
 
+    /// Gets the summary of the last compiled document if a path is its main
+    /// file, without waiting for the compiler.
+    pub fn document_summary(&self, path: &Path) -> Option<String> {
+        let summary = self.summary.lock();
+        let (main, summary) = summary.as_ref()?;
+        (main == path).then(|| summary.clone())
+    }
+
     pub fn inner(&self) -> &CompileClient {
         self.inner.wait()
     }
@@ -317,6 +444,102 @@ impl CompileClientActor {
         self.inner().steal_async(f).await
     }
 
+    /// Warms up the analysis caches of the workspace in a background task, i.e.
+    /// analyzes at most `budget` source files and the signatures of the
+    /// standard library. The compiler thread is stolen for one file at a time,
+    /// so that the other requests are not blocked for long.
+    pub fn warm_up(&self, handle: &tokio::runtime::Handle, budget: usize) {
+        let client = self.inner().clone();
+        let diag_group = self.diag_group.clone();
+        handle.spawn(async move {
+            let files = client
+                .steal_async(move |c| {
+                    c.compiler
+                        .compiler
+                        .run_analysis(|ctx| warm_up_files(ctx, budget))
+                })
+                .await;
+            let Some(files) = files.ok().and_then(Result::ok) else {
+                error!("TypstActor({diag_group}): failed to warm up the workspace");
+                return;
+            };
+
+            let mut analyzed = 0;
+            for id in files {
+                let res = client
+                    .steal_async(move |c| {
+                        c.compiler
+                            .compiler
+                            .run_analysis(|ctx| warm_up_file(ctx, id))
+                    })
+                    .await;
+                if matches!(res, Ok(Ok(true))) {
+                    analyzed += 1;
+                }
+            }
+            let funcs = client
+                .steal_async(|c| c.compiler.compiler.run_analysis(warm_up_library))
+                .await;
+            let funcs = funcs.ok().and_then(Result::ok).unwrap_or_default();
+
+            info!("TypstActor({diag_group}): warmed up {analyzed} files and {funcs} functions");
+        });
+    }
+
+    /// Runs the document tests in the background, stealing the compiler thread
+    /// for one test file at a time, so that the other requests are not blocked
+    /// by the long tests.
+    pub fn run_tests(
+        &self,
+        req: RunTestsRequest,
+    ) -> BoxFuture<'static, anyhow::Result<TestReport>> {
+        let client = self.inner().clone();
+        Box::pin(async move {
+            let files = client
+                .steal_async(move |c| c.compiler.compiler.run_analysis(|ctx| req.test_files(ctx)))
+                .await??;
+
+            let mut report = TestReport::default();
+            for id in files {
+                let res = client
+                    .steal_async(move |c| c.compiler.compiler.run_analysis(|ctx| run_test(ctx, id)))
+                    .await??;
+                if let Some((result, diagnostics)) = res {
+                    report.push(result, diagnostics);
+                }
+            }
+            Ok(report)
+        })
+    }
+
+    /// Classifies the identifiers of the source at a path for the semantic
+    /// tokens in a background task, calling `on_resolved` if they are resolved
+    /// again.
+    pub fn resolve_sema_idents(
+        &self,
+        handle: &tokio::runtime::Handle,
+        tokens_ctx: Arc<SemanticTokenContext>,
+        path: ImmutPath,
+        on_resolved: impl FnOnce() + Send + 'static,
+    ) {
+        let client = self.inner().clone();
+        let diag_group = self.diag_group.clone();
+        handle.spawn(async move {
+            let resolved = client
+                .steal_async(move |c| {
+                    c.compiler
+                        .compiler
+                        .run_analysis(|ctx| tokens_ctx.resolve_scheduled_idents(ctx, &path))
+                })
+                .await;
+            match resolved {
+                Ok(Ok(true)) => on_resolved(),
+                Ok(Ok(false)) => {}
+                _ => error!("TypstActor({diag_group}): failed to resolve the identifiers"),
+            }
+        });
+    }
+
     pub fn settle(&mut self) {
         let _ = self.change_entry(None);
         info!("TypstActor({}): settle requested", self.diag_group);
@@ -327,13 +550,111 @@ impl CompileClientActor {
     }
 
     pub fn sync_config(&mut self, config: CompileConfig) {
+        if config.completion_snippets != self.config.completion_snippets {
+            let snippets = config.completion_snippets.clone();
+            let _ = self.steal(move |c| {
+                c.compiler
+                    .compiler
+                    .analysis
+                    .snippets
+                    .set_configured(snippets);
+            });
+        }
+
+        if config.path_completion != self.config.path_completion {
+            let path_completion = config.path_completion.clone();
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.path_completion = path_completion;
+            });
+        }
+
+        if config.analysis_plugins != self.config.analysis_plugins {
+            let analysis_plugins = config.analysis_plugins.clone();
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.providers = load_analysis_providers(&analysis_plugins);
+            });
+        }
+
+        let relint =
+            config.lint != self.config.lint || config.spell_check != self.config.spell_check;
+        if config.lint != self.config.lint {
+            let lint = config.lint.clone();
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.lint = lint;
+            });
+        }
+
+        if config.spell_check != self.config.spell_check {
+            let spell_check = config.spell_check.clone();
+            let _ = self.steal(move |c| {
+                c.compiler
+                    .compiler
+                    .analysis
+                    .spell_check
+                    .set_config(spell_check);
+            });
+        }
+
+        if relint {
+            self.relint();
+        }
+
+        if config.hover_preview != self.config.hover_preview {
+            let hover_preview = config.hover_preview && self.hover_markdown;
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.enable_hover_preview = hover_preview;
+            });
+        }
+
+        if config.type_inlay_hints != self.config.type_inlay_hints {
+            let type_inlay_hints = config.type_inlay_hints;
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.enable_type_inlay_hints = type_inlay_hints;
+            });
+        }
+
+        if config.closing_hints_min_lines != self.config.closing_hints_min_lines {
+            let min_lines = config.closing_hints_min_lines;
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.closing_hint_min_lines = min_lines;
+            });
+        }
+
+        if config.memory_budget != self.config.memory_budget {
+            let memory_budget = config.memory_budget;
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.analysis.memory_budget = memory_budget;
+            });
+        }
+
+        let creation_timestamp = config.determine_creation_timestamp();
+        if creation_timestamp != self.config.determine_creation_timestamp() {
+            let _ = self.steal(move |c| {
+                c.compiler.compiler.creation_timestamp = creation_timestamp;
+            });
+            // The documents are compiled again at the new time.
+            let files = FileChangeSet::new_inserts(vec![]);
+            self.add_memory_changes(MemoryEvent::Update(files));
+        }
+
+        let package_overrides = config.determine_package_overrides();
+        if package_overrides != self.config.determine_package_overrides() {
+            let _ = self.steal(move |c| {
+                c.compiler.world_mut().registry.overrides = package_overrides;
+            });
+            // The packages imported are resolved to other directories.
+            self.clear_cache();
+            let files = FileChangeSet::new_inserts(vec![]);
+            self.add_memory_changes(MemoryEvent::Update(files));
+        }
+
         self.config = config;
     }
 
     pub fn change_entry(&mut self, path: Option<ImmutPath>) -> Result<(), Error> {
         if path
             .as_deref()
-            .is_some_and(|p| !p.is_absolute() && !p.starts_with("/untitled"))
+            .is_some_and(|p| !p.is_absolute() && !is_virtual_path(p))
         {
             return Err(error_once!("entry file must be absolute", path: path.unwrap().display()));
         }
@@ -380,6 +701,10 @@ impl CompileClientActor {
         self.inner.wait().add_memory_changes(event);
     }
 
+    pub fn add_fs_changes(&self, files: FileChangeSet) {
+        self.inner.wait().add_fs_changes(files);
+    }
+
     pub(crate) fn change_export_pdf(&mut self, config: ExportConfig) {
         let _ = self
             .render_tx
@@ -387,16 +712,51 @@ impl CompileClientActor {
                 substitute_pattern: config.substitute_pattern,
                 entry: self.entry.clone(),
                 mode: config.mode,
+                pdf: config.pdf,
+                debounce: config.debounce,
+                creation_timestamp: config.creation_timestamp,
             }))
             .unwrap();
     }
 
+    /// Changes the fonts of the world, recompiling the document with them.
+    pub fn change_fonts(&self, font: Deferred<SharedFontResolver>) {
+        let _ = self.steal(move |c| {
+            c.compiler.world_mut().font_resolver = font.wait().clone();
+            c.compiler.compiler.analysis.caches = Default::default();
+        });
+
+        let files = FileChangeSet::new_inserts(vec![]);
+        self.add_memory_changes(MemoryEvent::Update(files));
+    }
+
     pub fn clear_cache(&self) {
         let _ = self.steal(|c| {
             c.compiler.compiler.analysis.caches = Default::default();
         });
     }
 
+    /// Clears the cached lints and compiles the documents again to lint them.
+    pub fn relint(&self) {
+        let _ = self.steal(|c| c.compiler.compiler.lints.clear());
+        let files = FileChangeSet::new_inserts(vec![]);
+        self.add_memory_changes(MemoryEvent::Update(files));
+    }
+
+    /// Gets a function clearing the caches and recompiling the document, which
+    /// can be called on other threads, e.g. after a package is downloaded.
+    pub fn recompiler(&self) -> impl FnOnce() + Send + 'static {
+        let client = self.inner().clone();
+        move || {
+            let _ = client.steal(|c| {
+                c.compiler.compiler.analysis.caches = Default::default();
+            });
+
+            let files = FileChangeSet::new_inserts(vec![]);
+            client.add_memory_changes(MemoryEvent::Update(files));
+        }
+    }
+
     pub fn collect_server_info(&self) -> anyhow::Result<HashMap<String, ServerInfoResponse>> {
         let dg = self.diag_group.clone();
         self.steal(move |c| {
@@ -417,6 +777,31 @@ impl CompileClientActor {
         .map_err(|e| e.into())
     }
 
+    /// Collects the statistics of the compiler, which is awaited without
+    /// blocking the caller until the compiler thread is stolen.
+    pub fn collect_server_stats(&self) -> BoxFuture<'static, anyhow::Result<CompilerStats>> {
+        let client = self.inner().clone();
+        Box::pin(async move {
+            let stats = client.steal_async(move |c| {
+                use typst_ts_compiler::NotifyApi;
+                let cc = &c.compiler.compiler;
+
+                let mut tracked_files = 0;
+                cc.world().iter_dependencies(&mut |_, _| tracked_files += 1);
+
+                CompilerStats {
+                    tracked_files,
+                    estimated_memory_usage: HashMap::from_iter([
+                        ("vfs".to_owned(), cc.world().vfs.memory_usage()),
+                        ("analysis".to_owned(), cc.analysis.estimated_memory()),
+                    ]),
+                    caches: cc.analysis.caches.stats(),
+                }
+            });
+            stats.await.map_err(|e| e.into())
+        })
+    }
+
     pub fn on_export(&self, kind: ExportKind, path: PathBuf) -> anyhow::Result<Option<PathBuf>> {
         // todo: we currently doesn't respect the path argument...
         info!("CompileActor: on export: {}", path.display());
@@ -6,7 +6,10 @@ use std::{
     collections::HashSet,
     num::NonZeroUsize,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     thread::JoinHandle,
 };
 
@@ -24,7 +27,7 @@ use typst_ts_compiler::{
         features::{FeatureSet, WITH_COMPILING_STATUS_FEATURE},
         watch_deps, CompileEnv, CompileReporter, Compiler, ConsoleDiagReporter, EntryManager,
     },
-    vfs::notify::{FilesystemEvent, MemoryEvent, NotifyMessage},
+    vfs::notify::{FileChangeSet, FilesystemEvent, MemoryEvent, NotifyMessage},
     world::{CompilerFeat, CompilerWorld},
     ShadowApi,
 };
@@ -57,8 +60,8 @@ enum Interrupt<Ctx> {
     ///
     /// See [`CompileClient<Ctx>::steal`] for more information.
     Task(BorrowTask<Ctx>),
-    /// Memory file changes.
-    Memory(MemoryEvent),
+    /// Memory file changes, tagged with their revision.
+    Memory(usize, MemoryEvent),
     /// File system event.
     Fs(FilesystemEvent),
     /// Request compiler to stop.
@@ -75,10 +78,34 @@ enum CompilerResponse {
 struct TaggedMemoryEvent {
     /// The logical tick when the event is received.
     logical_tick: usize,
+    /// The revision of the memory event.
+    revision: usize,
     /// The memory event happened.
     event: MemoryEvent,
 }
 
+/// The revisions of the memory changes sent to and applied by the compiler
+/// thread.
+///
+/// When the user is typing, the compilation may finish after newer memory
+/// changes are sent. The results of such a compilation are stale, and should
+/// be dropped instead of being published, since another compilation is going
+/// to follow.
+#[derive(Debug, Default)]
+pub struct MemoryRevisions {
+    /// The latest revision sent by the clients.
+    sent: AtomicUsize,
+    /// The latest revision applied to the compiler.
+    applied: AtomicUsize,
+}
+
+impl MemoryRevisions {
+    /// Whether some memory changes sent are not applied yet.
+    pub fn is_stale(&self) -> bool {
+        self.applied.load(Ordering::SeqCst) < self.sent.load(Ordering::SeqCst)
+    }
+}
+
 struct SuspendState {
     suspended: bool,
     dirty: bool,
@@ -110,6 +137,8 @@ pub struct CompileServerActor<C: Compiler> {
     /// Internal channel for stealing the compiler thread.
     steal_tx: mpsc::UnboundedSender<Interrupt<Self>>,
     steal_rx: mpsc::UnboundedReceiver<Interrupt<Self>>,
+    /// The revisions of the memory changes.
+    revisions: Arc<MemoryRevisions>,
 
     suspend_state: SuspendState,
 }
@@ -139,6 +168,7 @@ where
 
             steal_tx,
             steal_rx,
+            revisions: Default::default(),
 
             suspend_state: SuspendState {
                 suspended: entry.is_inactive(),
@@ -330,7 +360,7 @@ where
                 task(self);
                 false
             }
-            Interrupt::Memory(event) => {
+            Interrupt::Memory(revision, event) => {
                 log::debug!("CompileServerActor: memory event incoming");
 
                 // Emulate memory changes.
@@ -351,7 +381,7 @@ where
 
                 // If there is no invalidation happening, apply memory changes directly.
                 if files.is_empty() && self.dirty_shadow_logical_tick == 0 {
-                    self.apply_memory_changes(event);
+                    self.apply_memory_changes(revision, event);
                     return true;
                 }
 
@@ -363,6 +393,7 @@ where
                         invalidates: files.into_iter().collect(),
                         opaque: Box::new(TaggedMemoryEvent {
                             logical_tick: self.logical_tick,
+                            revision,
                             event,
                         }),
                     },
@@ -394,6 +425,7 @@ where
             let event = upstream_event.take()?.opaque;
             let TaggedMemoryEvent {
                 logical_tick,
+                revision,
                 event,
             } = *event.downcast().ok()?;
 
@@ -402,14 +434,15 @@ where
                 self.dirty_shadow_logical_tick = 0;
             }
 
-            self.apply_memory_changes(event);
+            self.apply_memory_changes(revision, event);
         }
 
         Some(())
     }
 
     /// Apply memory changes to underlying compiler.
-    fn apply_memory_changes(&mut self, event: MemoryEvent) {
+    fn apply_memory_changes(&mut self, revision: usize, event: MemoryEvent) {
+        self.revisions.applied.fetch_max(revision, Ordering::SeqCst);
         if matches!(event, MemoryEvent::Sync(..)) {
             self.compiler.reset_shadow();
         }
@@ -444,9 +477,17 @@ impl<C: Compiler> CompileServerActor<C> {
         self
     }
 
+    /// Shares the revisions of the memory changes, e.g. with the compiler to
+    /// check whether its results are stale.
+    pub fn with_memory_revisions(mut self, revisions: Arc<MemoryRevisions>) -> Self {
+        self.revisions = revisions;
+        self
+    }
+
     pub fn client(&self) -> CompileClient<Self> {
         let intr_tx = self.steal_tx.clone();
-        CompileClient { intr_tx }
+        let revisions = self.revisions.clone();
+        CompileClient { intr_tx, revisions }
     }
 
     pub fn document(&self) -> Option<Arc<TypstDocument>> {
@@ -457,12 +498,14 @@ impl<C: Compiler> CompileServerActor<C> {
 #[derive(Debug, Clone)]
 pub struct CompileClient<Ctx> {
     intr_tx: mpsc::UnboundedSender<Interrupt<Ctx>>,
+    revisions: Arc<MemoryRevisions>,
 }
 
 impl<Ctx> CompileClient<Ctx> {
     pub fn faked() -> Self {
         let (intr_tx, _) = mpsc::unbounded_channel();
-        Self { intr_tx }
+        let revisions = Default::default();
+        Self { intr_tx, revisions }
     }
 
     fn steal_inner<Ret: Send + 'static>(
@@ -512,7 +555,16 @@ impl<Ctx> CompileClient<Ctx> {
     }
 
     pub fn add_memory_changes(&self, event: MemoryEvent) {
-        log_send_error("mem_event", self.intr_tx.send(Interrupt::Memory(event)));
+        let revision = self.revisions.sent.fetch_add(1, Ordering::SeqCst) + 1;
+        let intr = Interrupt::Memory(revision, event);
+        log_send_error("mem_event", self.intr_tx.send(intr));
+    }
+
+    /// Invalidates the files changed on the disk, which are reported by the
+    /// editor instead of the watcher, and compiles again.
+    pub fn add_fs_changes(&self, files: FileChangeSet) {
+        let intr = Interrupt::Fs(FilesystemEvent::Update(files));
+        log_send_error("fs_event", self.intr_tx.send(intr));
     }
 }
 
@@ -49,7 +49,7 @@ fn run_trace_program(req: UserActionTraceRequest) -> anyhow::Result<TraceReport>
     let mut cmd = std::process::Command::new(&req.compiler_program);
     let mut cmd = &mut cmd;
 
-    cmd = cmd.arg("compile");
+    cmd = cmd.arg("compile-server");
 
     cmd = cmd
         .arg("--root")
@@ -1,7 +1,11 @@
+use std::path::PathBuf;
+
 use once_cell::sync::Lazy;
 use tinymist::transport::MirrorArgs;
 
 use tinymist::compiler_init::{CompileOnceArgs, FontArgs};
+use tinymist::headless::{HeadlessArgs, MarkupFormat, QueryKind};
+use tinymist_query::LspPosition;
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
@@ -17,8 +21,17 @@ pub struct CliArguments {
 pub enum Commands {
     /// Run Language Server
     Lsp(LspArgs),
-    /// Run Compile Server
-    Compile(CompileArgs),
+    /// Compile a document and export it to PDF, like the language server does
+    Compile(CompileCliArgs),
+    /// Watch a document, compiling and exporting it to PDF on changes
+    Watch(CompileCliArgs),
+    /// Run an analysis on a document and print its result in JSON
+    Query(QueryArgs),
+    /// Generate the API reference of a package from the symbols exported by
+    /// its entry
+    Doc(DocArgs),
+    /// Run Compile Server, which is also run by `compile` without an input
+    CompileServer(CompileArgs),
     /// Probe
     Probe,
 }
@@ -40,6 +53,85 @@ pub struct CompileArgs {
     pub compile: CompileOnceArgs,
 }
 
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Parser))]
+pub struct CompileCliArgs {
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub compile: CompileOnceArgs,
+    /// The path pattern of the exported PDF, which supports the same variables
+    /// as the `outputPath` configuration, e.g. `$root/target/$dir/$name`
+    #[cfg_attr(feature = "clap", clap(long, short, value_name = "PATTERN"))]
+    pub output: Option<String>,
+    /// The arguments of the compile server, which is run by `compile` without
+    /// an input
+    #[cfg_attr(feature = "clap", clap(long, default_value = "false", hide = true))]
+    pub persist: bool,
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub mirror: MirrorArgs,
+}
+
+impl CompileCliArgs {
+    /// Converts the arguments to the ones of the compile server.
+    pub fn into_compile_server(self) -> CompileArgs {
+        CompileArgs {
+            persist: self.persist,
+            mirror: self.mirror,
+            compile: self.compile,
+        }
+    }
+
+    /// Converts the arguments to the ones of a headless compilation.
+    pub fn into_headless(self, watch: bool) -> HeadlessArgs {
+        HeadlessArgs {
+            compile: self.compile,
+            output: self.output,
+            watch,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Parser))]
+pub struct QueryArgs {
+    /// The analysis to run
+    #[cfg_attr(feature = "clap", clap(value_enum))]
+    pub kind: QueryKind,
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub compile: CompileOnceArgs,
+    /// The zero-based line and character in UTF-16 code units, e.g. `3:10`,
+    /// which is required by the analyses at a position
+    #[cfg_attr(feature = "clap", clap(long, value_name = "LINE:CHARACTER", value_parser = parse_position))]
+    pub position: Option<LspPosition>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Parser))]
+pub struct DocArgs {
+    /// The input is the entry of the package, e.g. `lib.typ`, and the root is
+    /// the directory of its `typst.toml`
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub compile: CompileOnceArgs,
+    /// The format of the generated reference
+    #[cfg_attr(feature = "clap", clap(long, value_enum, default_value = "markdown"))]
+    pub format: MarkupFormat,
+    /// The path of the generated reference, which is printed to stdout if not
+    /// specified
+    #[cfg_attr(feature = "clap", clap(long, short, value_name = "PATH"))]
+    pub output: Option<PathBuf>,
+}
+
+/// Parses a position in the form of `line:character`.
+fn parse_position(raw: &str) -> Result<LspPosition, String> {
+    let (line, character) = raw
+        .split_once(':')
+        .ok_or("position must be a line and a character separated by a colon")?;
+    let line = line.trim().parse();
+    let line = line.map_err(|e| format!("invalid line: {e}"))?;
+    let character = character.trim().parse();
+    let character = character.map_err(|e| format!("invalid character: {e}"))?;
+    Ok(LspPosition::new(line, character))
+}
+
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
 pub struct LspArgs {
@@ -69,3 +161,21 @@ Typst Version:       {}
         env!("TYPST_VERSION"),
     )
 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("3:10"), Ok(LspPosition::new(3, 10)));
+        assert_eq!(parse_position(" 0 : 0 "), Ok(LspPosition::new(0, 0)));
+        assert!(parse_position("3").is_err());
+        assert!(parse_position("a:1")
+            .unwrap_err()
+            .starts_with("invalid line"));
+        assert!(parse_position("1:-1")
+            .unwrap_err()
+            .starts_with("invalid character"));
+    }
+}
@@ -40,6 +40,8 @@ mod world;
 pub use crate::harness::LspHost;
 pub use server::compiler;
 pub use server::compiler_init;
+pub use server::config;
+pub use server::headless;
 pub use server::lsp::*;
 pub use server::lsp_init::*;
 pub use world::{CompileFontOpts, CompileOnceOpts, CompileOpts, LspWorld, LspWorldBuilder};
@@ -13,6 +13,7 @@ use parking_lot::RwLock;
 use tinymist::{
     compiler_init::{CompileInit, CompileInitializeParams},
     harness::{lsp_harness, InitializedLspDriver, LspDriver, LspHost},
+    headless,
     transport::with_stdio_transport,
     CompileFontOpts, Init, LspWorld, TypstLanguageServer,
 };
@@ -49,11 +50,31 @@ fn main() -> anyhow::Result<()> {
     #[cfg(feature = "dhat-heap")]
     let _profiler = dhat::Profiler::new_heap();
 
+    // Parse command line arguments
+    let args = CliArguments::parse();
+    let command = match args.command.unwrap_or_default() {
+        // The compile server was run by `compile` before, which is kept for the
+        // clients starting it without an input.
+        Commands::Compile(args) if args.compile.input.is_none() => {
+            Commands::CompileServer(args.into_compile_server())
+        }
+        command => command,
+    };
+
     // Start logging
     let _ = {
         use log::LevelFilter::*;
+        // The headless commands only log the problems, leaving the terminal to
+        // the diagnostics.
+        let level = match &command {
+            Commands::Compile(..)
+            | Commands::Watch(..)
+            | Commands::Query(..)
+            | Commands::Doc(..) => Warn,
+            _ => Info,
+        };
         env_logger::builder()
-            .filter_module("tinymist", Info)
+            .filter_module("tinymist", level)
             .filter_module("typst_preview", Debug)
             .filter_module("typst_ts", Info)
             .filter_module("typst_ts_compiler::service::compile", Info)
@@ -61,12 +82,32 @@ fn main() -> anyhow::Result<()> {
             .try_init()
     };
 
-    // Parse command line arguments
-    let args = CliArguments::parse();
-
-    match args.command.unwrap_or_default() {
+    match command {
         Commands::Lsp(args) => lsp_main(args),
-        Commands::Compile(args) => compiler_main(args),
+        Commands::Compile(args) => headless::compile(
+            RUNTIMES.tokio_runtime.handle().clone(),
+            args.into_headless(false),
+        ),
+        Commands::Watch(args) => headless::compile(
+            RUNTIMES.tokio_runtime.handle().clone(),
+            args.into_headless(true),
+        ),
+        Commands::Query(args) => {
+            let handle = RUNTIMES.tokio_runtime.handle().clone();
+            let res = headless::query(handle, &args.compile, args.kind, args.position)?;
+            println!("{}", serde_json::to_string_pretty(&res)?);
+            Ok(())
+        }
+        Commands::Doc(args) => {
+            let handle = RUNTIMES.tokio_runtime.handle().clone();
+            let docs = headless::docs(handle, &args.compile, args.format)?;
+            match &args.output {
+                Some(output) => std::fs::write(output, docs)?,
+                None => print!("{docs}"),
+            }
+            Ok(())
+        }
+        Commands::CompileServer(args) => compiler_main(args),
         Commands::Probe => Ok(()),
     }
 }
@@ -4,7 +4,7 @@ use std::{collections::HashMap, path::Path, sync::Arc, time::Instant};
 use crossbeam_channel::{select, Receiver};
 use log::{error, info, warn};
 use lsp_server::{Notification, Request, ResponseError};
-use lsp_types::{notification::Notification as _, ExecuteCommandParams};
+use lsp_types::{notification::Notification as _, ExecuteCommandParams, MessageType};
 use paste::paste;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
@@ -20,7 +20,7 @@ use crate::{
     harness::InitializedLspDriver,
     internal_error, invalid_params, method_not_found, run_query,
     state::MemoryFileMeta,
-    world::SharedFontResolver,
+    world::{CompileFontOpts, SharedFontResolver},
     LspHost, LspResult,
 };
 
@@ -75,6 +75,7 @@ pub struct CompileServerArgs {
     pub const_config: CompilerConstConfig,
     pub diag_tx: mpsc::UnboundedSender<CompileClusterRequest>,
     pub font: Deferred<SharedFontResolver>,
+    pub font_opts: CompileFontOpts,
     pub handle: tokio::runtime::Handle,
 }
 
@@ -107,12 +108,17 @@ pub struct CompileServer {
     pub handle: tokio::runtime::Handle,
     /// The font resolver to use.
     pub font: Deferred<SharedFontResolver>,
+    /// The font options passed in the command line, which are overridden by
+    /// the configured ones.
+    pub font_opts: CompileFontOpts,
     /// Source synchronized with client
     pub memory_changes: HashMap<Arc<Path>, MemoryFileMeta>,
     /// The diagnostics sender to send diagnostics to `crate::actor::cluster`.
     pub diag_tx: mpsc::UnboundedSender<CompileClusterRequest>,
     /// The compiler actor.
     pub compiler: Option<CompileClientActor>,
+    /// Whether the analysis caches of the workspace are warmed up.
+    pub warmed_up: bool,
 }
 
 impl CompileServer {
@@ -123,6 +129,7 @@ impl CompileServer {
             const_config,
             diag_tx,
             font,
+            font_opts,
             handle,
         } = args;
 
@@ -133,7 +140,9 @@ impl CompileServer {
             config: compile_config,
             const_config,
             font,
+            font_opts,
             compiler: None,
+            warmed_up: false,
             handle,
             memory_changes: HashMap::new(),
 
@@ -344,41 +353,70 @@ impl CompileServer {
 impl CompileServer {
     pub fn on_changed_configuration(&mut self, values: Map<String, JsonValue>) -> LspResult<()> {
         let config = self.config.clone();
-        match self.config.update_by_map(&values) {
-            Ok(()) => {}
-            Err(err) => {
-                self.config = config;
-                error!("error applying new settings: {err}");
-                return Err(invalid_params(format!(
-                    "error applying new settings: {err}"
-                )));
-            }
+        if let Err(err) = self.config.update_by_map(&values) {
+            warn!("invalid settings are reset to their defaults: {err}");
         }
 
         if let Some(e) = self.compiler.as_mut() {
             e.sync_config(self.config.clone());
         }
 
+        let font_opts = self.config.determine_font_opts(self.font_opts.clone());
+        if font_opts != config.determine_font_opts(self.font_opts.clone()) {
+            // The previous fonts are kept if the new ones cannot be loaded.
+            let (prev, diag_tx) = (self.font.clone(), self.diag_tx.clone());
+            self.font = Deferred::new(move || match SharedFontResolver::new(font_opts) {
+                Ok(font) => font,
+                Err(err) => {
+                    error!("failed to load the fonts, keeping the previous ones: {err}");
+                    let message = format!("Failed to load the fonts: {err}");
+                    let req = CompileClusterRequest::ShowMessage(MessageType::ERROR, message);
+                    let _ = diag_tx.send(req);
+                    prev.wait().clone()
+                }
+            });
+            if let Some(e) = self.compiler.as_ref() {
+                e.change_fonts(self.font.clone());
+            }
+        }
+
         // todo: watch changes of the root path
 
         if config.output_path != self.config.output_path
             || config.export_pdf != self.config.export_pdf
+            || config.export_debounce != self.config.export_debounce
+            || config.determine_creation_timestamp() != self.config.determine_creation_timestamp()
         {
-            let config = ExportConfig {
-                substitute_pattern: self.config.output_path.clone(),
-                mode: self.config.export_pdf,
-                ..ExportConfig::default()
-            };
-
-            self.compiler
-                .as_mut()
-                .unwrap()
-                .change_export_pdf(config.clone());
+            let config = self.export_config();
+            self.compiler.as_mut().unwrap().change_export_pdf(config);
         }
 
         info!("new settings applied");
         Ok(())
     }
+
+    /// Fixes the current date of the compilations at a UNIX timestamp, or
+    /// unfixes it by `None`, until the settings are changed.
+    pub fn change_creation_timestamp(&mut self, timestamp: Option<i64>) {
+        self.config.creation_timestamp = timestamp;
+        let config = self.config.clone();
+        let export_config = self.export_config();
+        if let Some(e) = self.compiler.as_mut() {
+            e.sync_config(config);
+            e.change_export_pdf(export_config);
+        }
+    }
+
+    /// Gets the configuration of the exports.
+    fn export_config(&self) -> ExportConfig {
+        ExportConfig {
+            substitute_pattern: self.config.output_path.clone(),
+            mode: self.config.export_pdf,
+            debounce: self.config.export_debounce,
+            creation_timestamp: self.config.determine_creation_timestamp(),
+            ..ExportConfig::default()
+        }
+    }
 }
 
 struct Cancelled;
@@ -426,13 +464,22 @@ impl CompileServer {
     /// Export the current document as a Svg file.
     pub fn export_svg(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let opts = parse_opts(arguments.get(1))?;
-        self.export(ExportKind::Svg { page: opts.page }, arguments)
+        let kind = ExportKind::Svg {
+            page: opts.page,
+            output: opts.output,
+        };
+        self.export(kind, arguments)
     }
 
     /// Export the current document as a Png file.
     pub fn export_png(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let opts = parse_opts(arguments.get(1))?;
-        self.export(ExportKind::Png { page: opts.page }, arguments)
+        let kind = ExportKind::Png {
+            page: opts.page,
+            ppi: opts.ppi,
+            output: opts.output,
+        };
+        self.export(kind, arguments)
     }
 
     /// Export the current document as some format. The client is responsible
@@ -468,18 +515,31 @@ impl CompileServer {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ExportOpts {
-    page: PageSelection,
+pub(crate) struct ExportOpts {
+    pub page: PageSelection,
+    /// The pixels per inch of the PNG images.
+    pub ppi: Option<f32>,
+    /// The template of the output path, in which `{page}` is replaced by the
+    /// page number.
+    pub output: Option<String>,
 }
 
-fn parse_opts(v: Option<&JsonValue>) -> LspResult<ExportOpts> {
-    Ok(match v {
+/// Parses the options of the SVG and PNG exports, shared by the commands of
+/// the language server and the compile server.
+pub(crate) fn parse_opts(v: Option<&JsonValue>) -> LspResult<ExportOpts> {
+    let opts = match v {
         Some(opts) => serde_json::from_value::<ExportOpts>(opts.clone())
             .map_err(|_| invalid_params("The third argument is not a valid object"))?,
         _ => ExportOpts {
             page: PageSelection::First,
+            ppi: None,
+            output: None,
         },
-    })
+    };
+    if opts.ppi.is_some_and(|ppi| !ppi.is_finite() || ppi <= 0.) {
+        return Err(invalid_params("The pixels per inch must be positive"));
+    }
+    Ok(opts)
 }
 
 fn parse_path(v: Option<&JsonValue>) -> LspResult<ImmutPath> {
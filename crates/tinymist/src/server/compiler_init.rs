@@ -1,14 +1,20 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::bail;
+use chrono::{DateTime, Utc};
 use clap::builder::ValueParser;
 use clap::{ArgAction, Parser};
 use comemo::Prehashed;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde_json::{Map, Value as JsonValue};
-use tinymist_query::PositionEncoding;
+use tinymist_query::{
+    analysis::{PathCompletionConfig, SpellCheckConfig, UserSnippet},
+    CompletionFeat, LintConfig, PositionEncoding,
+};
 use tinymist_render::PeriscopeArgs;
 use tokio::sync::mpsc;
 use typst::foundations::IntoValue;
@@ -16,12 +22,13 @@ use typst::syntax::FileId;
 use typst::syntax::VirtualPath;
 use typst::util::Deferred;
 use typst_ts_core::config::compiler::EntryState;
-use typst_ts_core::{ImmutPath, TypstDict};
+use typst_ts_core::{path::PathClean, ImmutPath, TypstDict};
 
 use crate::actor::cluster::CompileClusterRequest;
 use crate::compiler::{CompileServer, CompileServerArgs};
 use crate::harness::LspDriver;
-use crate::world::{ImmutDict, SharedFontResolver};
+use crate::server::config::{ConfigErrors, ConfigReader};
+use crate::world::{ImmutDict, PackageOverrides, SharedFontResolver};
 use crate::{CompileExtraOpts, CompileFontOpts, ExportMode, LspHost};
 
 #[cfg(feature = "clap")]
@@ -66,6 +73,15 @@ pub struct CompileOnceArgs {
 
     #[cfg_attr(feature = "clap", clap(flatten))]
     pub font: FontArgs,
+
+    /// The creation date of the document as a UNIX timestamp, which fixes the
+    /// current date of `datetime.today()` and the creation date of the PDF
+    #[clap(
+        long = "creation-timestamp",
+        env = "SOURCE_DATE_EPOCH",
+        value_name = "UNIX_TIMESTAMP"
+    )]
+    pub creation_timestamp: Option<i64>,
 }
 
 /// Parses key/value pairs split by the first equal sign.
@@ -93,16 +109,61 @@ pub struct CompileConfig {
     pub output_path: String,
     /// The mode of PDF export.
     pub export_pdf: ExportMode,
+    /// The delay of the exports on typing.
+    pub export_debounce: Duration,
+    /// The maximum number of source files to analyze in advance, or `None` to
+    /// disable the warm-up.
+    pub warm_up_budget: Option<usize>,
     /// Specifies the root path of the project manually.
     pub root_path: Option<PathBuf>,
+    /// The entries of the project, to which the files imported or included by
+    /// them are resolved.
+    pub entries: Vec<PathBuf>,
+    /// Whether to detect the entry of the focusing file when no entry is
+    /// pinned or configured.
+    pub entry_detection: bool,
+    /// The entries of the folders, overriding the configured or detected ones
+    /// unless the main file is pinned.
+    pub entry_overrides: BTreeMap<PathBuf, PathBuf>,
     /// Notify the compile status to the editor.
     pub notify_compile_status: bool,
     /// Enable periscope document in hover.
     pub periscope_args: Option<PeriscopeArgs>,
+    /// Enable rendered previews of equations, symbols, and examples in hover.
+    pub hover_preview: bool,
+    /// Enable inlay hints for the inferred types of variables.
+    pub type_inlay_hints: bool,
+    /// The minimum number of lines of a block to show a closing inlay hint
+    /// after it.
+    pub closing_hints_min_lines: Option<usize>,
+    /// The cap of the estimated memory usage of the analysis caches in bytes.
+    pub memory_budget: Option<usize>,
     /// Typst extra arguments.
     pub typst_extra_args: Option<CompileExtraOpts>,
+    /// The UNIX timestamp fixing the current date of the compilations,
+    /// overriding the one passed in the extra arguments.
+    pub creation_timestamp: Option<i64>,
+    /// The font paths, overriding the ones passed in the command line.
+    pub font_paths: Option<Vec<PathBuf>>,
+    /// Whether to load the system fonts.
+    pub system_fonts: Option<bool>,
+    /// Whether to load the fonts embedded in the binary.
+    pub embedded_fonts: Option<bool>,
+    /// The local directories of the packages being developed, by their specs
+    /// with or without versions.
+    pub package_overrides: BTreeMap<String, PathBuf>,
     /// The preferred theme for the document.
     pub preferred_theme: Option<String>,
+    /// The user-defined completion snippets.
+    pub completion_snippets: Vec<UserSnippet>,
+    /// The paths to the WebAssembly plugins providing completions and hovers.
+    pub analysis_plugins: Vec<PathBuf>,
+    /// The user preferences of path completion.
+    pub path_completion: PathCompletionConfig,
+    /// The user configuration of the lints.
+    pub lint: LintConfig,
+    /// The user configuration of spell checking.
+    pub spell_check: SpellCheckConfig,
     pub has_default_entry_path: bool,
 }
 
@@ -121,117 +182,102 @@ impl CompileConfig {
 
     /// Updates the configuration with a map.
     ///
+    /// The invalid entries are reset to their defaults, while the valid ones
+    /// are still applied.
+    ///
     /// # Errors
-    /// Errors if the update is invalid.
-    pub fn update_by_map(&mut self, update: &Map<String, JsonValue>) -> anyhow::Result<()> {
-        if let Some(JsonValue::String(output_path)) = update.get("outputPath") {
-            self.output_path = output_path.to_owned();
-        } else {
-            self.output_path = String::new();
-        }
-
-        let export_pdf = update
-            .get("exportPdf")
-            .map(ExportMode::deserialize)
-            .and_then(Result::ok);
-        if let Some(export_pdf) = export_pdf {
-            self.export_pdf = export_pdf;
-        } else {
-            self.export_pdf = ExportMode::default();
-        }
-
-        let root_path = update.get("rootPath");
-        if let Some(root_path) = root_path {
-            if root_path.is_null() {
-                self.root_path = None;
-            }
-            if let Some(root_path) = root_path.as_str().map(PathBuf::from) {
-                self.root_path = Some(root_path);
-            }
-        } else {
-            self.root_path = None;
-        }
+    /// Errors with the invalid entries if there are any.
+    pub fn update_by_map(&mut self, update: &Map<String, JsonValue>) -> Result<(), ConfigErrors> {
+        let mut reader = ConfigReader::new(update);
+        self.read(&mut reader);
+        reader.finish()
+    }
 
-        let compile_status = update.get("compileStatus").and_then(|x| x.as_str());
-        if let Some(word_count) = compile_status {
-            if !matches!(word_count, "enable" | "disable") {
-                bail!("compileStatus must be either 'enable' or 'disable'");
-            }
-        }
-        self.notify_compile_status = compile_status.map_or(false, |e| e != "disable");
-
-        let preferred_theme = update.get("preferredTheme").and_then(|x| x.as_str());
-        self.preferred_theme = preferred_theme.map(str::to_owned);
-
-        // periscope_args
-        let periscope_args = update.get("hoverPeriscope");
-        let periscope_args: Option<PeriscopeArgs> = match periscope_args {
-            Some(serde_json::Value::String(e)) if e == "enable" => Some(PeriscopeArgs::default()),
-            Some(serde_json::Value::Null | serde_json::Value::String(..)) | None => None,
-            Some(periscope_args) => match serde_json::from_value(periscope_args.clone()) {
-                Ok(e) => Some(e),
-                Err(e) => {
-                    log::error!("failed to parse hoverPeriscope: {e}");
-                    return Ok(());
-                }
-            },
+    /// Reads the entries of the configuration, resetting the missing or
+    /// invalid ones to their defaults.
+    pub(crate) fn read(&mut self, r: &mut ConfigReader) {
+        self.output_path = r.get("outputPath").unwrap_or_default();
+        self.export_pdf = r.get("exportPdf").unwrap_or_default();
+        self.export_debounce = r
+            .get("exportDebounce")
+            .map_or(Duration::ZERO, Duration::from_millis);
+        self.warm_up_budget = r.get("warmUpBudget");
+
+        self.root_path = r.get("rootPath");
+        self.entries = r.get("entries").unwrap_or_default();
+        self.entry_detection = r.toggle("entryDetection").unwrap_or(false);
+        self.entry_overrides = r.get("entryOverrides").unwrap_or_default();
+
+        self.font_paths = r.get("fontPaths");
+        self.system_fonts = r.get("systemFonts");
+        self.embedded_fonts = r.get("embeddedFonts");
+
+        self.notify_compile_status = r.toggle("compileStatus").unwrap_or(false);
+        self.preferred_theme = r.get("preferredTheme");
+
+        let periscope_args = match r.raw("hoverPeriscope") {
+            Some(JsonValue::String(e)) if e == "enable" => Some(PeriscopeArgs::default()),
+            Some(JsonValue::String(e)) if e == "disable" => None,
+            Some(..) => r.get::<PeriscopeArgs>("hoverPeriscope"),
+            None => None,
         };
-        if let Some(mut periscope_args) = periscope_args {
+        self.periscope_args = periscope_args.map(|mut periscope_args| {
             if periscope_args.invert_color == "auto"
                 && self.preferred_theme.as_ref().is_some_and(|t| t == "dark")
             {
                 periscope_args.invert_color = "always".to_owned();
             }
-
-            self.periscope_args = Some(periscope_args);
-        } else {
-            self.periscope_args = None;
-        }
-
-        'parse_extra_args: {
-            if let Some(typst_extra_args) = update.get("typstExtraArgs") {
-                let typst_args: Vec<String> = match serde_json::from_value(typst_extra_args.clone())
-                {
-                    Ok(e) => e,
-                    Err(e) => {
-                        log::error!("failed to parse typstExtraArgs: {e}");
-                        return Ok(());
-                    }
-                };
-
-                let command = match CompileOnceArgs::try_parse_from(
-                    Some("typst-cli".to_owned()).into_iter().chain(typst_args),
-                ) {
-                    Ok(e) => e,
-                    Err(e) => {
-                        log::error!("failed to parse typstExtraArgs: {e}");
-                        break 'parse_extra_args;
-                    }
-                };
-
-                // Convert the input pairs to a dictionary.
-                let inputs: TypstDict = if command.inputs.is_empty() {
-                    TypstDict::default()
-                } else {
-                    let pairs = command.inputs.iter();
-                    let pairs = pairs.map(|(k, v)| (k.as_str().into(), v.as_str().into_value()));
-                    pairs.collect()
-                };
-
-                // todo: the command.root may be not absolute
-                self.typst_extra_args = Some(CompileExtraOpts {
-                    entry: command.input.map(|e| Path::new(&e).into()),
-                    root_dir: command.root,
-                    inputs: Arc::new(Prehashed::new(inputs)),
-                    font_paths: command.font.font_paths,
-                });
-            }
-        }
-
+            periscope_args
+        });
+
+        self.hover_preview = r.toggle("hoverPreview").unwrap_or(false);
+        self.type_inlay_hints = r.toggle("typeInlayHints").unwrap_or(false);
+        self.closing_hints_min_lines = r.get("closingHintsMinLines");
+        self.memory_budget = r
+            .get::<usize>("analysisMemoryBudget")
+            .map(|budget| budget * 1024 * 1024);
+
+        self.package_overrides = r.get("packageOverrides").unwrap_or_default();
+        self.completion_snippets = r.get("completionSnippets").unwrap_or_default();
+        self.analysis_plugins = r.get("analysisPlugins").unwrap_or_default();
+        self.path_completion = r
+            .get::<PathCompletionConfig>("completionPaths")
+            .map(PathCompletionConfig::normalized)
+            .unwrap_or_default();
+        self.lint = r.get("lint").unwrap_or_default();
+        self.spell_check = r.get("spellCheck").unwrap_or_default();
+
+        self.creation_timestamp = r.get("creationTimestamp");
+        self.typst_extra_args = r.get::<Vec<String>>("typstExtraArgs").and_then(|args| {
+            let args = Some("typst-cli".to_owned()).into_iter().chain(args);
+            let command = match CompileOnceArgs::try_parse_from(args) {
+                Ok(command) => command,
+                Err(err) => {
+                    r.invalid("typstExtraArgs", err.to_string().trim());
+                    return None;
+                }
+            };
+
+            // Convert the input pairs to a dictionary.
+            let inputs: TypstDict = if command.inputs.is_empty() {
+                TypstDict::default()
+            } else {
+                let pairs = command.inputs.iter();
+                let pairs = pairs.map(|(k, v)| (k.as_str().into(), v.as_str().into_value()));
+                pairs.collect()
+            };
+
+            Some(CompileExtraOpts {
+                entry: command.input.map(|e| Path::new(&e).into()),
+                root_dir: command.root,
+                inputs: Arc::new(Prehashed::new(inputs)),
+                font_paths: command.font.font_paths,
+                creation_timestamp: command.creation_timestamp,
+            })
+        });
+
+        self.validate(r);
         self.has_default_entry_path = self.determine_default_entry_path().is_some();
-        self.validate()?;
-
-        Ok(())
     }
 
     pub fn determine_root(&self, entry: Option<&ImmutPath>) -> Option<ImmutPath> {
@@ -288,6 +334,86 @@ impl CompileConfig {
         })
     }
 
+    /// Determines the font options, where the configured ones override the
+    /// ones passed in the command line, and the relative font paths are
+    /// resolved against the root.
+    pub fn determine_font_opts(&self, mut opts: CompileFontOpts) -> CompileFontOpts {
+        if let Some(font_paths) = &self.font_paths {
+            let root = self.determine_root(None);
+            let font_paths = font_paths.iter().map(|path| match root.as_ref() {
+                Some(root) if path.is_relative() => root.join(path).clean(),
+                _ => path.clone(),
+            });
+            opts.font_paths = font_paths.collect();
+        } else if opts.font_paths.is_empty() {
+            if let Some(extras) = &self.typst_extra_args {
+                opts.font_paths = extras.font_paths.clone();
+            }
+        }
+        if let Some(system_fonts) = self.system_fonts {
+            opts.no_system_fonts = !system_fonts;
+        }
+        if let Some(embedded_fonts) = self.embedded_fonts {
+            opts.no_embedded_fonts = !embedded_fonts;
+        }
+        opts
+    }
+
+    /// Determines the time fixing the current date of the compilations, if
+    /// any.
+    pub fn determine_creation_timestamp(&self) -> Option<DateTime<Utc>> {
+        let extras = self.typst_extra_args.as_ref();
+        let timestamp = self
+            .creation_timestamp
+            .or_else(|| extras.and_then(|extras| extras.creation_timestamp))?;
+        DateTime::from_timestamp(timestamp, 0)
+    }
+
+    /// Determines the directories of the overridden packages, where the
+    /// relative paths are resolved against the root.
+    pub fn determine_package_overrides(&self) -> PackageOverrides {
+        let root = self.determine_root(None);
+        let overrides = self.package_overrides.iter().map(|(spec, dir)| {
+            let dir = match root.as_ref() {
+                Some(root) if dir.is_relative() => root.join(dir),
+                _ => dir.clone(),
+            };
+            (spec.clone(), dir.clean().as_path().into())
+        });
+        PackageOverrides::new(overrides)
+    }
+
+    /// Determines the entries of the project, where the relative paths are
+    /// resolved against the root.
+    pub fn determine_entries(&self) -> Vec<ImmutPath> {
+        let root = self.determine_root(None);
+        let entries = self.entries.iter().filter_map(|entry| {
+            if entry.is_relative() {
+                return Some(root.as_ref()?.join(entry).clean().as_path().into());
+            }
+            Some(entry.clean().as_path().into())
+        });
+        entries.collect()
+    }
+
+    /// Determines the entry overridden for the innermost folder containing a
+    /// file, where the relative paths are resolved against the root.
+    pub fn determine_entry_override(&self, path: &Path) -> Option<ImmutPath> {
+        let root = self.determine_root(None);
+        let resolve = |path: &PathBuf| {
+            if path.is_relative() {
+                return Some(root.as_ref()?.join(path).clean());
+            }
+            Some(path.clean())
+        };
+        let overrides = self.entry_overrides.iter();
+        let overrides = overrides.filter_map(|(dir, entry)| Some((resolve(dir)?, resolve(entry)?)));
+        let (_, entry) = overrides
+            .filter(|(dir, _)| path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())?;
+        Some(entry.as_path().into())
+    }
+
     pub fn determine_entry(&self, entry: Option<ImmutPath>) -> EntryState {
         // todo: formalize untitled path
         // let is_untitled = entry.as_ref().is_some_and(|p| p.starts_with("/untitled"));
@@ -331,22 +457,33 @@ impl CompileConfig {
         EMPTY.clone()
     }
 
-    pub fn validate(&self) -> anyhow::Result<()> {
+    /// Validates the entries that can't be checked by their types, resetting
+    /// the invalid ones.
+    pub(crate) fn validate(&mut self, r: &mut ConfigReader) {
+        if let Some(timestamp) = self.creation_timestamp {
+            if DateTime::from_timestamp(timestamp, 0).is_none() {
+                let msg = format!("must be a valid UNIX timestamp: {timestamp}");
+                r.invalid("creationTimestamp", msg);
+                self.creation_timestamp = None;
+            }
+        }
+
         if let Some(root) = &self.root_path {
             if !root.is_absolute() {
-                bail!("rootPath must be an absolute path: {root:?}");
+                r.invalid("rootPath", format!("must be an absolute path: {root:?}"));
+                self.root_path = None;
             }
         }
 
         if let Some(extra_args) = &self.typst_extra_args {
             if let Some(root) = &extra_args.root_dir {
                 if !root.is_absolute() {
-                    bail!("typstExtraArgs.root must be an absolute path: {root:?}");
+                    let msg = format!("root must be an absolute path: {root:?}");
+                    r.invalid("typstExtraArgs", msg);
+                    self.typst_extra_args = None;
                 }
             }
         }
-
-        Ok(())
     }
 }
 
@@ -357,12 +494,18 @@ pub struct CompilerConstConfig {
     /// Determined position encoding, either UTF-8 or UTF-16.
     /// Defaults to UTF-16 if not specified.
     pub position_encoding: PositionEncoding,
+    /// The completion features supported by the client.
+    pub completion_feat: CompletionFeat,
+    /// Allow markdown contents in hover.
+    pub hover_markdown: bool,
 }
 
 impl Default for CompilerConstConfig {
     fn default() -> Self {
         Self {
             position_encoding: PositionEncoding::Utf16,
+            completion_feat: CompletionFeat::default(),
+            hover_markdown: false,
         }
     }
 }
@@ -393,20 +536,14 @@ impl LspDriver for CompileInit {
         Result<Self::InitResult, lsp_server::ResponseError>,
     ) {
         let mut compile_config = CompileConfig::default();
-        compile_config.update(&params.config).unwrap();
+        if let Err(err) = compile_config.update(&params.config) {
+            log::warn!("invalid settings are reset to their defaults: {err}");
+        }
 
         // prepare fonts
         // todo: on font resolving failure, downgrade to a fake font book
         let font = {
-            let mut opts = self.font;
-            if let Some(font_paths) = compile_config
-                .typst_extra_args
-                .as_ref()
-                .map(|x| &x.font_paths)
-            {
-                opts.font_paths = font_paths.clone();
-            }
-
+            let opts = compile_config.determine_font_opts(self.font.clone());
             Deferred::new(|| SharedFontResolver::new(opts).expect("failed to create font book"))
         };
 
@@ -421,10 +558,13 @@ impl LspDriver for CompileInit {
                         _ => PositionEncoding::Utf8,
                     })
                     .unwrap_or_default(),
+                completion_feat: CompletionFeat::default(),
+                hover_markdown: false,
             },
             diag_tx: self.diag_tx,
             handle: self.handle,
             font,
+            font_opts: self.font,
         };
 
         let mut service = CompileServer::new(args);
@@ -0,0 +1,138 @@
+//! Reading of the configuration sent by the client, which validates each entry
+//! by its type and collects the invalid entries, so that the valid ones are
+//! still applied and the invalid ones are reported back to the client.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as JsonValue};
+
+/// An invalid entry of the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// The key of the entry, e.g. `formatterPrintWidth`.
+    pub key: String,
+    /// Why the entry is invalid.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// The invalid entries of a configuration, which are reset to their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, err) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Reads the entries of a configuration, collecting the invalid ones.
+///
+/// A missing entry and a `null` entry are the same, both falling back to the
+/// default of the entry.
+pub struct ConfigReader<'a> {
+    map: &'a Map<String, JsonValue>,
+    errors: Vec<ConfigError>,
+}
+
+impl<'a> ConfigReader<'a> {
+    /// Creates a reader of the entries of a configuration.
+    pub fn new(map: &'a Map<String, JsonValue>) -> Self {
+        Self {
+            map,
+            errors: vec![],
+        }
+    }
+
+    /// Gets the raw value of an entry, if it is set.
+    pub fn raw(&self, key: &str) -> Option<&'a JsonValue> {
+        self.map.get(key).filter(|value| !value.is_null())
+    }
+
+    /// Deserializes an entry, if it is set and valid.
+    pub fn get<T: DeserializeOwned>(&mut self, key: &str) -> Option<T> {
+        let value = self.raw(key)?;
+        match T::deserialize(value) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.invalid(key, err);
+                None
+            }
+        }
+    }
+
+    /// Reads an entry toggled by `"enable"` or `"disable"`.
+    pub fn toggle(&mut self, key: &str) -> Option<bool> {
+        match self.raw(key)?.as_str() {
+            Some("enable") => Some(true),
+            Some("disable") => Some(false),
+            _ => {
+                self.invalid(key, "must be either 'enable' or 'disable'");
+                None
+            }
+        }
+    }
+
+    /// Reports an invalid entry, e.g. one failing a check beyond its type.
+    pub fn invalid(&mut self, key: &str, message: impl fmt::Display) {
+        self.errors.push(ConfigError {
+            key: key.to_owned(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Finishes reading, returning the invalid entries if there are any.
+    pub fn finish(self) -> Result<(), ConfigErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_config_reader() {
+        let JsonValue::Object(map) = json!({
+            "width": 80,
+            "height": "tall",
+            "depth": null,
+            "preview": "enable",
+            "hints": "sometimes",
+        }) else {
+            unreachable!()
+        };
+
+        let mut reader = ConfigReader::new(&map);
+        assert_eq!(reader.get::<u32>("width"), Some(80));
+        assert_eq!(reader.get::<u32>("height"), None);
+        assert_eq!(reader.get::<u32>("depth"), None);
+        assert_eq!(reader.get::<u32>("missing"), None);
+        assert_eq!(reader.toggle("preview"), Some(true));
+        assert_eq!(reader.toggle("hints"), None);
+
+        let errors = reader.finish().unwrap_err();
+        let keys = errors.0.iter().map(|err| err.key.as_str());
+        assert_eq!(keys.collect::<Vec<_>>(), ["height", "hints"]);
+        assert!(errors.to_string().contains("hints: must be either"));
+    }
+}
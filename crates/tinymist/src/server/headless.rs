@@ -0,0 +1,432 @@
+//! The headless compilation and analysis of a document from the command line,
+//! which share the compiler, the configuration, and the export of the language
+//! server, so that a document is built identically in the editor and in CI.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context};
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use codespan_reporting::files::{Files, SimpleFiles};
+use codespan_reporting::term::{self, termcolor};
+use lsp_types::DiagnosticSeverity;
+use parking_lot::RwLock;
+use serde_json::Value as JsonValue;
+use tinymist_query::{
+    url_to_path, CompilerQueryRequest, CompilerQueryResponse, DiagnosticsMap,
+    DocumentSymbolRequest, ExportKind, ExprTypeRequest, HoverRequest, LspDiagnostic, LspPosition,
+    PositionEncoding, SemanticRequest, SyntaxRequest,
+};
+use tokio::sync::mpsc;
+use typst::syntax::Source;
+use typst_ts_core::path::PathClean;
+
+use crate::actor::cluster::{CompileClusterRequest, TinymistCompileStatusEnum};
+use crate::compiler::CompileServer;
+use crate::compiler_init::{CompileInit, CompileInitializeParams, CompileOnceArgs};
+use crate::harness::LspDriver;
+use crate::tools::package::package_docs;
+use crate::{CompileFontOpts, LspHost};
+
+pub use crate::tools::markup_export::MarkupFormat;
+
+/// The options of a headless compilation.
+#[derive(Debug, Clone)]
+pub struct HeadlessArgs {
+    /// The input, the root, the `sys.inputs`, and the fonts of the document.
+    pub compile: CompileOnceArgs,
+    /// The path pattern of the exported PDF, i.e. the `outputPath`
+    /// configuration.
+    pub output: Option<String>,
+    /// Whether to keep compiling the document on changes.
+    pub watch: bool,
+}
+
+/// The analyses which can be queried from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum QueryKind {
+    /// The symbols of the input, as `textDocument/documentSymbol`.
+    DocumentSymbols,
+    /// The diagnostics of the compilation by the paths, as
+    /// `textDocument/publishDiagnostics`.
+    Diagnostics,
+    /// The type of the expression at the position, as shown in the hover.
+    Type,
+    /// The hover at the position, as `textDocument/hover`.
+    Hover,
+}
+
+/// Compiles a document by a compile server, printing the diagnostics to
+/// stderr.
+///
+/// The document is exported to PDF after it is compiled successfully. Without
+/// watching, it returns after the first compilation, failing on errors.
+/// Otherwise, it keeps compiling and exporting the document on changes.
+pub fn compile(handle: tokio::runtime::Handle, args: HeadlessArgs) -> anyhow::Result<()> {
+    let config = serde_json::json!({
+        "outputPath": args.output.clone().unwrap_or_default(),
+        "exportPdf": if args.watch { "onType" } else { "never" },
+    });
+    let mut server = HeadlessServer::start(handle, &args.compile, config, PositionEncoding::Utf8)?;
+    let input = server.input.clone();
+
+    let on_compiling = || {
+        if args.watch {
+            eprintln!("compiling {}", input.display());
+        }
+    };
+    while let Some((compiled, diagnostics)) = server.next_compilation(on_compiling) {
+        print_diagnostics(&diagnostics)?;
+        if args.watch {
+            let status = if compiled {
+                "compiled"
+            } else {
+                "failed to compile"
+            };
+            eprintln!("{status} {}", input.display());
+            continue;
+        }
+
+        if !compiled {
+            bail!("failed to compile {}", input.display());
+        }
+        let kind = ExportKind::Pdf;
+        let Some(to) = server.service.compiler().on_export(kind, input.clone())? else {
+            bail!("failed to export {}", input.display());
+        };
+        eprintln!("exported to {}", to.display());
+        return Ok(());
+    }
+
+    Ok(())
+}
+
+/// Runs an analysis of the language server on a document, returning its
+/// result in JSON. The positions are zero-based and encoded in UTF-16, the
+/// same as the ones of LSP.
+///
+/// The document is compiled before the analysis, so the analysis sees the
+/// compiled document, e.g. to hover the labels.
+pub fn query(
+    handle: tokio::runtime::Handle,
+    args: &CompileOnceArgs,
+    kind: QueryKind,
+    position: Option<LspPosition>,
+) -> anyhow::Result<JsonValue> {
+    let mut server =
+        HeadlessServer::start(handle, args, serde_json::json!({}), PositionEncoding::Utf16)?;
+    let path = server.input.clone();
+    let (_, diagnostics) = server
+        .next_compilation(|| {})
+        .context("the compiler stopped before compiling")?;
+    let position = || position.context("the position is required for the query");
+
+    let res = match kind {
+        QueryKind::DocumentSymbols => {
+            let text = std::fs::read_to_string(&path)?;
+            let source = Source::detached(text);
+            let req = DocumentSymbolRequest { path };
+            serde_json::to_value(req.request(&source, PositionEncoding::Utf16))?
+        }
+        QueryKind::Diagnostics => {
+            let diagnostics = diagnostics.into_iter();
+            let diagnostics = diagnostics.map(|(uri, diags)| (url_to_path(uri), diags));
+            serde_json::to_value(diagnostics.collect::<BTreeMap<_, _>>())?
+        }
+        QueryKind::Type => {
+            let req = ExprTypeRequest {
+                path,
+                position: position()?,
+            };
+            let res = server
+                .service
+                .compiler()
+                .steal_world(move |ctx| req.request(ctx))?;
+            serde_json::to_value(res)?
+        }
+        QueryKind::Hover => {
+            let req = HoverRequest {
+                path,
+                position: position()?,
+            };
+            let res = server.service.query(CompilerQueryRequest::Hover(req))?;
+            let CompilerQueryResponse::Hover(res) = res else {
+                unreachable!()
+            };
+            serde_json::to_value(res)?
+        }
+    };
+
+    Ok(res)
+}
+
+/// Generates the API reference of the symbols exported by a package entry,
+/// e.g. the `lib.typ` of a package whose `typst.toml` is at the root.
+pub fn docs(
+    handle: tokio::runtime::Handle,
+    args: &CompileOnceArgs,
+    format: MarkupFormat,
+) -> anyhow::Result<String> {
+    let mut server =
+        HeadlessServer::start(handle, args, serde_json::json!({}), PositionEncoding::Utf16)?;
+    let path = server.input.clone();
+    server
+        .next_compilation(|| {})
+        .context("the compiler stopped before compiling")?;
+
+    let docs = server.service.compiler().steal_world(move |ctx| {
+        let source = ctx.source_by_path(&path)?;
+        package_docs(ctx, &source, format).map_err(|err| anyhow!("{err}"))
+    })??;
+    Ok(docs)
+}
+
+/// A compile server started for a document from the command line.
+struct HeadlessServer {
+    service: CompileServer,
+    diag_rx: mpsc::UnboundedReceiver<CompileClusterRequest>,
+    /// The absolute path of the document.
+    input: PathBuf,
+}
+
+impl HeadlessServer {
+    /// Starts a compile server compiling the input of the arguments, with the
+    /// other configuration in `config`.
+    fn start(
+        handle: tokio::runtime::Handle,
+        args: &CompileOnceArgs,
+        mut config: JsonValue,
+        position_encoding: PositionEncoding,
+    ) -> anyhow::Result<Self> {
+        let cwd = std::env::current_dir()?;
+        let Some(input) = &args.input else {
+            bail!("the input file is required");
+        };
+        let input = cwd.join(input).clean();
+        if !input.is_file() {
+            bail!("the input file does not exist: {}", input.display());
+        }
+
+        let (diag_tx, diag_rx) = mpsc::unbounded_channel();
+        // The font paths are passed in `typstExtraArgs` below, resolved against
+        // the current directory.
+        let init = CompileInit {
+            handle,
+            font: CompileFontOpts {
+                no_system_fonts: args.font.no_system_fonts,
+                ..Default::default()
+            },
+            diag_tx,
+        };
+
+        // The arguments are passed as `typstExtraArgs`, so they are resolved the
+        // same as the ones configured in the editor.
+        let mut extra_args = vec![input.to_string_lossy().into_owned()];
+        if let Some(root) = &args.root {
+            extra_args.push(format!("--root={}", cwd.join(root).clean().display()));
+        }
+        for (key, value) in &args.inputs {
+            extra_args.push(format!("--input={key}={value}"));
+        }
+        for path in &args.font.font_paths {
+            extra_args.push(format!("--font-path={}", cwd.join(path).clean().display()));
+        }
+        if let Some(timestamp) = args.creation_timestamp {
+            extra_args.push(format!("--creation-timestamp={timestamp}"));
+        }
+        config["typstExtraArgs"] = extra_args.into();
+
+        // The messages to the client are dropped.
+        let (sender, _) = crossbeam_channel::unbounded();
+        let host = LspHost::new(Arc::new(RwLock::new(Some(sender))));
+        let (service, res) = init.initialize(
+            host,
+            CompileInitializeParams {
+                config,
+                position_encoding: Some(position_encoding.into()),
+            },
+        );
+        res.map_err(|e| anyhow!("cannot start the compiler: {}", e.message))?;
+
+        Ok(Self {
+            service,
+            diag_rx,
+            input,
+        })
+    }
+
+    /// Waits for the next compilation, returning whether it succeeded and its
+    /// diagnostics. `on_compiling` is called when the compilation starts.
+    ///
+    /// The compile server compiles the entry once it starts, and notifies the
+    /// status before the diagnostics of each compilation.
+    fn next_compilation(
+        &mut self,
+        mut on_compiling: impl FnMut(),
+    ) -> Option<(bool, DiagnosticsMap)> {
+        let mut compiled = false;
+        while let Some(req) = self.diag_rx.blocking_recv() {
+            match req {
+                CompileClusterRequest::Status(_, TinymistCompileStatusEnum::Compiling) => {
+                    on_compiling();
+                }
+                CompileClusterRequest::Status(_, status) => {
+                    compiled = matches!(status, TinymistCompileStatusEnum::CompileSuccess);
+                }
+                CompileClusterRequest::Diag(_, diagnostics) => {
+                    return Some((compiled, diagnostics.unwrap_or_default()));
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+/// Prints the diagnostics of the files to stderr, in the order of the paths.
+fn print_diagnostics(diagnostics: &DiagnosticsMap) -> anyhow::Result<()> {
+    let mut diagnostics = diagnostics.iter().collect::<Vec<_>>();
+    diagnostics.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+    let mut files = SimpleFiles::new();
+    let writer = termcolor::StandardStream::stderr(termcolor::ColorChoice::Auto);
+    let config = term::Config::default();
+    for (uri, diagnostics) in diagnostics {
+        let path = url_to_path(uri.clone());
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        let file = files.add(path.display().to_string(), text);
+        for diag in diagnostics {
+            let diag = to_codespan(&files, file, diag);
+            term::emit(&mut writer.lock(), &config, &files, &diag)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a diagnostic whose positions are encoded in UTF-8 to the one of
+/// `codespan_reporting`, with the related information as notes.
+fn to_codespan(
+    files: &SimpleFiles<String, String>,
+    file: usize,
+    diag: &LspDiagnostic,
+) -> Diagnostic<usize> {
+    let severity = match diag.severity {
+        Some(DiagnosticSeverity::WARNING) => Severity::Warning,
+        Some(DiagnosticSeverity::INFORMATION) => Severity::Note,
+        Some(DiagnosticSeverity::HINT) => Severity::Help,
+        _ => Severity::Error,
+    };
+    let offset = |line: u32, character: u32| {
+        let line = files.line_range(file, line as usize).ok()?;
+        Some((line.start + character as usize).min(line.end))
+    };
+    let start = offset(diag.range.start.line, diag.range.start.character);
+    let end = offset(diag.range.end.line, diag.range.end.character);
+
+    let mut notes = vec![];
+    for info in diag.related_information.iter().flatten() {
+        let related = url_to_path(info.location.uri.clone());
+        let position = info.location.range.start;
+        notes.push(format!(
+            "{}:{}:{}: {}",
+            related.display(),
+            position.line + 1,
+            position.character + 1,
+            info.message
+        ));
+    }
+
+    let diagnostic = Diagnostic::new(severity)
+        .with_message(&diag.message)
+        .with_notes(notes);
+    match start.zip(end) {
+        Some((start, end)) => diagnostic.with_labels(vec![Label::primary(file, start..end)]),
+        None => diagnostic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lsp_types::{DiagnosticRelatedInformation, Location, Position, Range, Url};
+
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let tmp = std::env::temp_dir().join(format!("tinymist-query-{}", std::process::id()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        let input = tmp.join("main.typ");
+        std::fs::write(&input, "#let x = 1\n#x\n#y\n").unwrap();
+        let args = CompileOnceArgs {
+            input: Some(input.to_string_lossy().into_owned()),
+            root: Some(tmp.clone()),
+            ..Default::default()
+        };
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let query = |kind, position| query(runtime.handle().clone(), &args, kind, position);
+
+        let symbols = query(QueryKind::DocumentSymbols, None).unwrap();
+        assert_eq!(symbols[0]["name"], "x");
+
+        let diagnostics = query(QueryKind::Diagnostics, None).unwrap();
+        let diagnostics = diagnostics.as_object().unwrap().values();
+        let mut messages = diagnostics.flat_map(|diags| diags.as_array().unwrap());
+        assert!(messages.any(|diag| diag["message"] == "unknown variable: y"));
+
+        let ty = query(QueryKind::Type, Some(LspPosition::new(1, 1))).unwrap();
+        assert!(ty.is_string());
+
+        // The analyses at a position require the position.
+        assert!(query(QueryKind::Hover, None).is_err());
+        // The input is required.
+        let res = super::query(
+            runtime.handle().clone(),
+            &CompileOnceArgs::default(),
+            QueryKind::Diagnostics,
+            None,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_to_codespan() {
+        let mut files = SimpleFiles::new();
+        let file = files.add("main.typ".to_owned(), "#let x = 1\n#ä(x)\n".to_owned());
+        let path = Path::new("/root/main.typ");
+        let uri = Url::from_file_path(path).unwrap();
+
+        let diag = LspDiagnostic {
+            range: Range::new(Position::new(1, 1), Position::new(1, 3)),
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: "unknown variable: ä".to_owned(),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location::new(uri, Range::new(Position::new(0, 5), Position::new(0, 6))),
+                message: "error occurred here".to_owned(),
+            }]),
+            ..Default::default()
+        };
+        let diag = to_codespan(&files, file, &diag);
+        assert_eq!(diag.severity, Severity::Error);
+        assert_eq!(diag.labels[0].range, 12..14);
+        assert_eq!(diag.notes, vec!["/root/main.typ:1:6: error occurred here"]);
+
+        // The positions out of the file are not labeled.
+        let diag = LspDiagnostic {
+            range: Range::new(Position::new(5, 0), Position::new(5, 1)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            ..Default::default()
+        };
+        let diag = to_codespan(&files, file, &diag);
+        assert_eq!(diag.severity, Severity::Warning);
+        assert!(diag.labels.is_empty());
+    }
+}
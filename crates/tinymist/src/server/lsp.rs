@@ -4,7 +4,7 @@ use core::fmt;
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::{bail, Context};
@@ -14,7 +14,10 @@ use futures::future::BoxFuture;
 use log::{error, info, trace, warn};
 use lsp_server::{ErrorCode, Message, Notification, Request, RequestId, ResponseError};
 use lsp_types::notification::Notification as NotificationTrait;
-use lsp_types::request::{GotoDeclarationParams, GotoDeclarationResponse, WorkspaceConfiguration};
+use lsp_types::request::{
+    GotoDeclarationParams, GotoDeclarationResponse, GotoTypeDefinitionParams,
+    GotoTypeDefinitionResponse, WorkspaceConfiguration,
+};
 use lsp_types::*;
 use parking_lot::lock_api::RwLock;
 use paste::paste;
@@ -22,11 +25,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
 use tinymist_query::{
     get_semantic_tokens_options, get_semantic_tokens_registration,
-    get_semantic_tokens_unregistration, ExportKind, PageSelection, SemanticTokenContext,
+    get_semantic_tokens_unregistration,
+    i18n::{self, Locale},
+    ExportKind, RunTestsRequest, SemanticRequest, SemanticTokenContext, SyntaxTreeRequest,
+    DEFAULT_TEST_GLOB,
 };
 use tokio::sync::mpsc;
 use typst::diag::StrResult;
-use typst::syntax::package::{PackageSpec, VersionlessPackageSpec};
+use typst::syntax::package::{PackageManifest, PackageSpec, VersionlessPackageSpec};
 use typst::util::Deferred;
 use typst_ts_compiler::service::Compiler;
 use typst_ts_core::path::PathClean;
@@ -38,11 +44,18 @@ use crate::actor::typ_client::CompileClientActor;
 use crate::actor::{
     FormattingConfig, FormattingRequest, UserActionRequest, UserActionTraceRequest,
 };
-use crate::compiler::{CompileServer, CompileServerArgs};
+use crate::compiler::{parse_opts, CompileServer, CompileServerArgs};
 use crate::compiler_init::CompilerConstConfig;
 use crate::harness::{InitializedLspDriver, LspHost};
-use crate::tools::package::InitTask;
-use crate::world::SharedFontResolver;
+use crate::tools::fonts::list_fonts;
+use crate::tools::image_drop::{drop_image, DEFAULT_ASSETS_DIR};
+use crate::tools::markup_export::{export_markup, AssetMode, MarkupFormat};
+use crate::tools::package::{package_docs, InitTask};
+use crate::tools::paste::{convert_paste, PasteFormat};
+use crate::tools::project::{DependencyGraph, EntryStatus};
+use crate::tools::stats::{RequestTimings, ServerStats, TinymistServerStats};
+use crate::tools::word_count::{word_count, WordsCount};
+use crate::world::{CompileFontOpts, SharedFontResolver};
 use crate::{run_query, LspResult};
 
 pub type MaySyncResult<'a> = Result<JsonValue, BoxFuture<'a, JsonValue>>;
@@ -50,12 +63,14 @@ pub type MaySyncResult<'a> = Result<JsonValue, BoxFuture<'a, JsonValue>>;
 #[derive(Debug)]
 enum Event {
     Lsp(lsp_server::Message),
+    ReportStats,
 }
 
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Event::Lsp(_) => write!(f, "Event::Lsp"),
+            Event::ReportStats => write!(f, "Event::ReportStats"),
         }
     }
 }
@@ -187,6 +202,7 @@ pub struct TypstLanguageServerArgs {
     pub const_config: ConstConfig,
     pub diag_tx: mpsc::UnboundedSender<CompileClusterRequest>,
     pub font: Deferred<SharedFontResolver>,
+    pub font_opts: CompileFontOpts,
 }
 
 /// The object providing the language server functionality.
@@ -205,6 +221,20 @@ pub struct TypstLanguageServer {
     pub pinning: bool,
     /// The client focusing file.
     pub focusing: Option<ImmutPath>,
+    /// The dependency graph of the configured entries, built lazily.
+    pub dependency_graph: Option<DependencyGraph>,
+    /// The dependency graphs of the candidates of the detected entries, by the
+    /// folders of the focused files, built lazily.
+    pub entry_detection: HashMap<ImmutPath, DependencyGraph>,
+    /// The entry last reported to the client.
+    pub entry_status: Option<EntryStatus>,
+    /// The path to write the trace of the next request to, if it is profiled.
+    pub profile_next: Option<PathBuf>,
+    /// The durations of the latest requests.
+    pub timings: RequestTimings,
+    /// The ticks to report the statistics of the server periodically, which
+    /// never come if the reports are disabled.
+    pub stats_ticker: Receiver<Instant>,
 
     // Configurations
     /// User configuration from the editor.
@@ -225,7 +255,7 @@ pub struct TypstLanguageServer {
 
     // Resources
     /// The semantic token context.
-    pub tokens_ctx: SemanticTokenContext,
+    pub tokens_ctx: Arc<SemanticTokenContext>,
     /// The compiler for general purpose.
     pub primary: CompileServer,
     /// The compilers for tasks
@@ -254,9 +284,12 @@ impl TypstLanguageServer {
                 compile_config: Default::default(),
                 const_config: CompilerConstConfig {
                     position_encoding: args.const_config.position_encoding,
+                    completion_feat: args.const_config.completion_feat,
+                    hover_markdown: args.const_config.hover_markdown,
                 },
                 diag_tx: args.diag_tx,
                 font: args.font,
+                font_opts: args.font_opts,
                 handle: args.handle,
             }),
             dedicates: Vec::new(),
@@ -273,7 +306,13 @@ impl TypstLanguageServer {
 
             pinning: false,
             focusing: None,
-            tokens_ctx,
+            tokens_ctx: Arc::new(tokens_ctx),
+            dependency_graph: None,
+            entry_detection: HashMap::new(),
+            entry_status: None,
+            profile_next: None,
+            timings: RequestTimings::default(),
+            stats_ticker: crossbeam_channel::never(),
             format_thread: None,
             user_action_threads: None,
         }
@@ -298,23 +337,31 @@ impl TypstLanguageServer {
             request_fn!(Completion, Self::completion),
             request_fn!(SemanticTokensFullRequest, Self::semantic_tokens_full),
             request_fn!(SemanticTokensFullDeltaRequest, Self::semantic_tokens_full_delta),
+            request_fn!(SemanticTokensRangeRequest, Self::semantic_tokens_range),
             request_fn!(DocumentSymbolRequest, Self::document_symbol),
             // Sync for low latency
             request_fn_!(Formatting, Self::formatting),
             request_fn!(SelectionRangeRequest, Self::selection_range),
+            request_fn!(LinkedEditingRange, Self::linked_editing_range),
+            request_fn!(OnTypeFormatting, Self::on_type_formatting),
             // latency insensitive
             request_fn!(InlayHintRequest, Self::inlay_hint),
             request_fn!(DocumentColor, Self::document_color),
             request_fn!(ColorPresentationRequest, Self::color_presentation),
             request_fn!(HoverRequest, Self::hover),
             request_fn!(CodeLensRequest, Self::code_lens),
+            request_fn!(CodeActionRequest, Self::code_action),
             request_fn!(FoldingRangeRequest, Self::folding_range),
             request_fn!(SignatureHelpRequest, Self::signature_help),
             request_fn!(PrepareRenameRequest, Self::prepare_rename),
             request_fn!(Rename, Self::rename),
+            request_fn!(WillRenameFiles, Self::will_rename_files),
             request_fn!(GotoDefinition, Self::goto_definition),
             request_fn!(GotoDeclaration, Self::goto_declaration),
+            request_fn!(GotoTypeDefinition, Self::goto_type_definition),
             request_fn!(References, Self::references),
+            request_fn!(DocumentHighlightRequest, Self::document_highlight),
+            request_fn!(DocumentLinkRequest, Self::document_link),
             request_fn!(WorkspaceSymbolRequest, Self::symbol),
             request_fn_!(ExecuteCommand, Self::on_execute_command),
         ])
@@ -329,6 +376,7 @@ impl TypstLanguageServer {
             notify_fn!(DidChangeTextDocument, Self::did_change),
             notify_fn!(DidSaveTextDocument, Self::did_save),
             notify_fn!(DidChangeConfiguration, Self::did_change_configuration),
+            notify_fn!(DidChangeWatchedFiles, Self::did_change_watched_files),
         ])
     }
 }
@@ -380,6 +428,37 @@ impl InitializedLspDriver for TypstLanguageServer {
             }
         }
 
+        if self.const_config().watched_files_registration {
+            trace!("setting up to request watched files notifications");
+
+            const WATCHED_FILES_REGISTRATION_ID: &str = "watched_files";
+            const WATCHED_FILES_METHOD_ID: &str = "workspace/didChangeWatchedFiles";
+
+            // The sources and the resources read by them, e.g. the images, the
+            // bibliographies, and the data files.
+            const WATCHED_FILES_GLOB: &str =
+                "**/*.{typ,bib,yml,yaml,json,csv,toml,xml,cbor,txt,png,jpg,jpeg,gif,svg,wasm}";
+
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(WATCHED_FILES_GLOB.to_owned()),
+                    kind: None,
+                }],
+            };
+            let err = self
+                .client
+                .register_capability(vec![Registration {
+                    id: WATCHED_FILES_REGISTRATION_ID.to_owned(),
+                    method: WATCHED_FILES_METHOD_ID.to_owned(),
+                    register_options: serde_json::to_value(options).ok(),
+                }])
+                .err();
+            if let Some(err) = err {
+                error!("could not register to watch file changes: {err}");
+            }
+        }
+
+        self.reset_stats_ticker();
         self.primary.initialized(params);
         info!("server initialized");
     }
@@ -428,6 +507,7 @@ impl TypstLanguageServer {
         select! {
             recv(inbox) -> msg =>
                 msg.ok().map(Event::Lsp),
+            recv(self.stats_ticker) -> _ => Some(Event::ReportStats),
         }
     }
 
@@ -444,6 +524,7 @@ impl TypstLanguageServer {
                     self.client.clone().complete_request(self, resp)
                 }
             },
+            Event::ReportStats => self.report_server_stats(),
         }
         Ok(())
     }
@@ -471,7 +552,31 @@ impl TypstLanguageServer {
             return;
         };
 
-        let res = handler(self, (req.id.clone(), req.params));
+        // The commands are timed separately, rather than all as
+        // `workspace/executeCommand`.
+        let command = match req.method.as_str() {
+            "workspace/executeCommand" => req.params.get("command").and_then(JsonValue::as_str),
+            _ => None,
+        };
+        let label = command.unwrap_or(&req.method).to_owned();
+
+        let profile = self.profile_next.take();
+        if profile.is_some() {
+            tinymist_query::profile::start();
+        }
+        let started = Instant::now();
+        let res = {
+            let _scope = tinymist_query::profile::scope_with(|| req.method.clone());
+            handler(self, (req.id.clone(), req.params))
+        };
+        self.timings.record(&label, started.elapsed());
+        if let Some(path) = profile {
+            let trace = tinymist_query::profile::finish();
+            match std::fs::write(&path, trace) {
+                Ok(()) => info!("request {} profiled to {path:?}", req.method),
+                Err(err) => error!("could not write the profile to {path:?}: {err}"),
+            }
+        }
         if matches!(res, Ok(Some(()))) {
             return;
         }
@@ -645,15 +750,38 @@ impl TypstLanguageServer {
             exec_fn!("tinymist.exportPdf", Self::export_pdf),
             exec_fn!("tinymist.exportSvg", Self::export_svg),
             exec_fn!("tinymist.exportPng", Self::export_png),
+            exec_fn!("tinymist.exportHtml", Self::export_html),
+            exec_fn!("tinymist.exportMarkdown", Self::export_markdown),
             exec_fn!("tinymist.doClearCache", Self::clear_cache),
+            exec_fn!("tinymist.setCreationTimestamp", Self::set_timestamp),
             exec_fn!("tinymist.pinMain", Self::pin_document),
+            exec_fn!("tinymist.unpinMain", Self::unpin_document),
             exec_fn!("tinymist.focusMain", Self::focus_document),
             exec_fn!("tinymist.doInitTemplate", Self::init_template),
             exec_fn!("tinymist.doGetTemplateEntry", Self::do_get_template_entry),
+            exec_fn!("tinymist.doDownloadPackage", Self::download_package),
+            exec_fn!("tinymist.listPackages", Self::list_packages),
+            exec_fn!("tinymist.openPackage", Self::open_package),
+            exec_fn!("tinymist.createLocalPackage", Self::create_local_package),
             exec_fn!("tinymist.interactCodeContext", Self::interact_code_context),
+            exec_fn!("tinymist.evaluateBinding", Self::evaluate_binding),
+            exec_fn!("tinymist.generatePackageDocs", Self::generate_package_docs),
+            exec_fn_!("tinymist.runTests", Self::run_tests),
+            exec_fn!("tinymist.reflowParagraph", Self::reflow_paragraph),
             exec_fn_!("tinymist.getDocumentTrace", Self::get_document_trace),
             exec_fn!("tinymist.getDocumentMetrics", Self::get_document_metrics),
+            exec_fn!("tinymist.getDependencyGraph", Self::get_dependency_graph),
+            exec_fn!("tinymist.profileRequest", Self::profile_request),
+            exec_fn!("tinymist.previewScrollTo", Self::preview_scroll_to),
+            exec_fn!("tinymist.previewJumpToSource", Self::preview_jump_to_source),
+            exec_fn!("tinymist.getDocumentOutline", Self::get_document_outline),
+            exec_fn!("tinymist.findContentReuse", Self::find_content_reuse),
             exec_fn!("tinymist.getServerInfo", Self::get_server_info),
+            exec_fn_!("tinymist.serverStats", Self::server_stats),
+            exec_fn!("tinymist.listFonts", Self::list_fonts),
+            exec_fn!("tinymist.convertPaste", Self::convert_paste),
+            exec_fn!("tinymist.dropImage", Self::drop_image),
+            exec_fn!("tinymist.getAst", Self::get_ast),
             // For Documentations
             exec_fn!("tinymist.getResources", Self::get_resources),
         ])
@@ -667,13 +795,84 @@ impl TypstLanguageServer {
     /// Export the current document as a Svg file.
     pub fn export_svg(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let opts = parse_opts(arguments.get(1))?;
-        self.export(ExportKind::Svg { page: opts.page }, arguments)
+        let kind = ExportKind::Svg {
+            page: opts.page,
+            output: opts.output,
+        };
+        self.export(kind, arguments)
     }
 
     /// Export the current document as a Png file.
     pub fn export_png(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let opts = parse_opts(arguments.get(1))?;
-        self.export(ExportKind::Png { page: opts.page }, arguments)
+        let kind = ExportKind::Png {
+            page: opts.page,
+            ppi: opts.ppi,
+            output: opts.output,
+        };
+        self.export(kind, arguments)
+    }
+
+    /// Export the current document as a HTML file.
+    pub fn export_html(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        self.export_markup(MarkupFormat::Html, arguments)
+    }
+
+    /// Export the current document as a Markdown file.
+    pub fn export_markdown(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        self.export_markup(MarkupFormat::Markdown, arguments)
+    }
+
+    /// Export the current document as a markup format, next to the document.
+    /// The external assets are written to the `<name>_assets` directory.
+    pub fn export_markup(
+        &mut self,
+        format: MarkupFormat,
+        arguments: Vec<JsonValue>,
+    ) -> LspResult<JsonValue> {
+        #[derive(Debug, Clone, Default, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct MarkupExportOpts {
+            assets: Option<AssetMode>,
+        }
+
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let opts = match arguments.get(1) {
+            None | Some(JsonValue::Null) => MarkupExportOpts::default(),
+            Some(opts) => serde_json::from_value::<MarkupExportOpts>(opts.clone())
+                .map_err(|_| invalid_params("The second argument is not a valid object"))?,
+        };
+        let assets = opts.assets.unwrap_or(self.config.export_assets);
+
+        let to = path.with_extension(format.extension());
+        let stem = to.file_stem().unwrap_or_default().to_string_lossy();
+        let asset_dir = format!("{stem}_assets");
+
+        let export = self
+            .primary()
+            .steal_world(move |ctx| {
+                let source = ctx.source_by_path(&path).map_err(|err| err.to_string())?;
+                let export = export_markup(ctx.world(), &source, format, assets, &asset_dir);
+                Ok::<_, String>(export)
+            })
+            .map_err(|err| err.to_string())
+            .and_then(|res| res)
+            .map_err(internal_error)?;
+
+        let write = || -> std::io::Result<()> {
+            let dir = to.parent().unwrap_or(Path::new("."));
+            for (asset, data) in &export.assets {
+                let asset = dir.join(asset);
+                if let Some(parent) = asset.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(asset, data.as_slice())?;
+            }
+            std::fs::write(&to, export.text.as_bytes())
+        };
+        write().map_err(|err| internal_error(format!("Cannot write the export: {err}")))?;
+
+        serde_json::to_value(&to).map_err(|_| internal_error("Cannot serialize path"))
     }
 
     /// Export the current document as some format. The client is responsible
@@ -712,6 +911,162 @@ impl TypstLanguageServer {
         Ok(res)
     }
 
+    /// Evaluate a top-level binding of a document, and show its value to the
+    /// user.
+    pub fn evaluate_binding(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        use typst::foundations::Repr;
+
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let name = arguments
+            .get(1)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_owned())
+            .ok_or_else(|| invalid_params("The second parameter is not a valid name"))?;
+
+        let res = self
+            .primary()
+            .steal_world(move |ctx| {
+                let source = ctx.source_by_path(&path).map_err(|err| err.to_string())?;
+                let value = tinymist_query::analysis::evaluate_binding(ctx.world(), &source, &name)
+                    .map_err(|err| err.to_string())?;
+                Ok::<_, String>(format!("{name} = {}", value.repr()))
+            })
+            .map_err(|err| err.to_string())
+            .and_then(|res| res);
+
+        let (typ, message) = match &res {
+            Ok(value) => (MessageType::INFO, value.clone()),
+            Err(err) => (MessageType::ERROR, format!("failed to evaluate: {err}")),
+        };
+        self.client
+            .send_notification::<notification::ShowMessage>(ShowMessageParams { typ, message });
+
+        let value = res.map_err(internal_error)?;
+        Ok(JsonValue::String(value))
+    }
+
+    /// Generate the API reference of the symbols exported by a package entry,
+    /// as Markdown by default or as HTML.
+    pub fn generate_package_docs(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let format = match arguments.get(1).and_then(|v| v.as_str()) {
+            None | Some("markdown") => MarkupFormat::Markdown,
+            Some("html") => MarkupFormat::Html,
+            Some(_) => return Err(invalid_params("The second parameter is not a valid format")),
+        };
+
+        let docs = self
+            .primary()
+            .steal_world(move |ctx| {
+                let source = ctx.source_by_path(&path).map_err(|err| err.to_string())?;
+                package_docs(ctx, &source, format).map_err(|err| err.to_string())
+            })
+            .map_err(|err| err.to_string())
+            .and_then(|res| res)
+            .map_err(internal_error)?;
+
+        Ok(JsonValue::String(docs))
+    }
+
+    /// Run the document tests of the workspace, i.e. compile the files
+    /// matching the glob in the first argument or the `testGlob`
+    /// configuration, publish the failures of `assert` and `panic` as
+    /// diagnostics, and return the results.
+    pub fn run_tests(
+        &mut self,
+        req_id: RequestId,
+        arguments: Vec<JsonValue>,
+    ) -> LspResult<Option<()>> {
+        let glob = match arguments.first() {
+            None | Some(JsonValue::Null) => self.config.test_glob.clone(),
+            Some(JsonValue::String(glob)) => Some(glob.clone()),
+            Some(_) => return Err(invalid_params("The first parameter is not a valid glob")),
+        };
+        let glob = glob.unwrap_or_else(|| DEFAULT_TEST_GLOB.to_owned());
+
+        // The failures of the last run are cleared while the tests run again.
+        let diag_tx = self.primary.diag_tx.clone();
+        let _ = diag_tx.send(CompileClusterRequest::Diag("tests".to_owned(), None));
+
+        let report = self.primary().run_tests(RunTestsRequest { glob });
+        let client = self.client.clone();
+        self.handle.spawn(async move {
+            let res = report.await.map_err(|err| {
+                error!("could not run the tests: {err}");
+                internal_error("Cannot run the tests")
+            });
+            let res = res.and_then(|mut report| {
+                let diagnostics = std::mem::take(&mut report.diagnostics);
+                let _ = diag_tx.send(CompileClusterRequest::Diag(
+                    "tests".to_owned(),
+                    Some(diagnostics),
+                ));
+
+                let (typ, message) = match report.failed {
+                    0 => (MessageType::INFO, format!("{} tests passed", report.passed)),
+                    failed => (
+                        MessageType::ERROR,
+                        format!("{failed} tests failed, {} tests passed", report.passed),
+                    ),
+                };
+                client.send_notification::<notification::ShowMessage>(ShowMessageParams {
+                    typ,
+                    message,
+                });
+
+                serde_json::to_value(report)
+                    .map_err(|e| internal_error(format!("Cannot serialize response {e}")))
+            });
+            if let Ok(response) = result_to_response_(req_id, res) {
+                client.respond(response);
+            }
+        });
+
+        Ok(Some(()))
+    }
+
+    /// Reflow the paragraph at a position of a document to a column, which
+    /// defaults to the print width of the formatter.
+    pub fn reflow_paragraph(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let position = arguments
+            .get(1)
+            .and_then(|v| serde_json::from_value::<Position>(v.clone()).ok())
+            .ok_or_else(|| invalid_params("The second parameter is not a valid position"))?;
+        let width = match arguments.get(2) {
+            None | Some(JsonValue::Null) => self.config.formatting_config().width,
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v > 0)
+                .ok_or_else(|| invalid_params("The third parameter is not a valid width"))?,
+        };
+
+        let uri = tinymist_query::path_to_url(&path)
+            .map_err(|e| invalid_params(format!("Cannot convert path to url: {e}")))?;
+        let edits = run_query!(self.ReflowParagraph(path, position, width))?;
+        let Some(edits) = edits.filter(|edits| !edits.is_empty()) else {
+            return Ok(JsonValue::Null);
+        };
+
+        self.client.send_request::<request::ApplyWorkspaceEdit>(
+            ApplyWorkspaceEditParams {
+                label: Some("Reflow paragraph".to_owned()),
+                edit: WorkspaceEdit {
+                    changes: Some(HashMap::from_iter([(uri, edits)])),
+                    ..Default::default()
+                },
+            },
+            |_, resp| {
+                if let Some(err) = resp.error {
+                    log::error!("failed to reflow paragraph: {err:?}");
+                }
+            },
+        );
+
+        Ok(JsonValue::Null)
+    }
+
     /// Get the trace data of the document.
     pub fn get_document_trace(
         &mut self,
@@ -770,11 +1125,195 @@ impl TypstLanguageServer {
         res.map_err(|e| internal_error(format!("could not get document trace: {e}")))
     }
 
-    /// Get the metrics of the document.
+    /// Get the metrics of the document, i.e. the spans and fonts used by it,
+    /// and the counts of its words, characters, and pages from the last
+    /// compilation in the additional `wordsCount` field. Returns null if the
+    /// document is not compiled.
     pub fn get_document_metrics(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        #[derive(Debug, Clone, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct DocumentMetrics {
+            #[serde(flatten)]
+            metrics: tinymist_query::DocumentMetricsResponse,
+            words_count: Option<WordsCount>,
+        }
+
         let path = parse_path(arguments.first())?.as_ref().to_owned();
 
-        let res = run_query!(self.DocumentMetrics(path))?;
+        let Some(metrics) = run_query!(self.DocumentMetrics(path))? else {
+            return Ok(JsonValue::Null);
+        };
+        let words_count = self
+            .primary()
+            .steal_state(|_, doc| doc.map(|doc| word_count(&doc.document)))
+            .map_err(|e| internal_error(format!("could not count words: {e}")))?;
+        let res = DocumentMetrics {
+            metrics,
+            words_count,
+        };
+        let res = serde_json::to_value(res)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Profile the next request, e.g. a completion or a hover, writing its
+    /// trace in the Chrome trace format to the given path, or to a file in the
+    /// temporary directory if not specified. Returns the path of the trace.
+    ///
+    /// The requests responded asynchronously are only profiled until they are
+    /// scheduled.
+    pub fn profile_request(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let path = match arguments.first() {
+            Some(JsonValue::Null) | None => {
+                let now = chrono::Local::now().format("%Y-%m-%d-%H-%M-%S");
+                std::env::temp_dir().join(format!("tinymist-profile-{now}.json"))
+            }
+            path => parse_path(path)?.as_ref().to_owned(),
+        };
+
+        info!("profiling the next request to {path:?}");
+        self.profile_next = Some(path.clone());
+        Ok(JsonValue::String(path.to_string_lossy().into_owned()))
+    }
+
+    /// Get the dependency graph of the given entry, or of the configured
+    /// entries if not specified, i.e. the files imported, included, or read by
+    /// them.
+    pub fn get_dependency_graph(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let entries = match parse_path_or_null(arguments.first())? {
+            Some(entry) => vec![entry],
+            None => {
+                let entries = self.config.compile.determine_entries();
+                let focusing = self.focusing.clone();
+                let entries = (!entries.is_empty()).then_some(entries);
+                entries.unwrap_or_else(|| focusing.into_iter().collect())
+            }
+        };
+
+        let root = self.config.compile.determine_root(entries.first());
+        let graph =
+            DependencyGraph::build(&entries, root.as_deref(), |file| self.load_source(file));
+        let res = serde_json::to_value(graph)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Get the outline of the document, i.e. its headings, figures, and
+    /// labelled elements.
+    pub fn get_document_outline(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        #[derive(Debug, Clone, Default, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct DocumentOutlineOpts {
+            #[serde(default)]
+            markdown: bool,
+        }
+
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let opts = match arguments.get(1) {
+            Some(opts) => serde_json::from_value::<DocumentOutlineOpts>(opts.clone())
+                .map_err(|_| invalid_params("The second argument is not a valid object"))?,
+            None => DocumentOutlineOpts::default(),
+        };
+        let markdown = opts.markdown;
+
+        let res = run_query!(self.DocumentOutline(path, markdown))?;
+        let res = serde_json::to_value(res)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Get the position in the compiled document of a cursor in the source, so
+    /// that a preview can be scrolled to it.
+    pub fn preview_scroll_to(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let path = parse_path(arguments.first())?;
+        let position = arguments
+            .get(1)
+            .and_then(|v| serde_json::from_value::<Position>(v.clone()).ok())
+            .ok_or_else(|| invalid_params("The second parameter is not a valid position"))?;
+
+        let res = self
+            .primary()
+            .steal_state(move |ctx, doc| {
+                let source = ctx.source_by_path(&path).ok()?;
+                let cursor = ctx.to_typst_pos(position, &source)?;
+                let pos = tinymist_query::jump_from_cursor(&doc?.document, &source, cursor)?;
+                Some(DocumentPosition {
+                    page: pos.page.get(),
+                    x: pos.point.x.to_pt(),
+                    y: pos.point.y.to_pt(),
+                })
+            })
+            .map_err(|e| internal_error(format!("could not resolve document position: {e}")))?;
+        let res = serde_json::to_value(res)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Get the source location of a position clicked in the compiled document,
+    /// e.g. in a preview, and show it in the editor.
+    pub fn preview_jump_to_source(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        use typst::layout::{Abs, Point};
+
+        let pos = arguments
+            .first()
+            .and_then(|v| serde_json::from_value::<DocumentPosition>(v.clone()).ok())
+            .ok_or_else(|| {
+                invalid_params("The first parameter is not a valid document position")
+            })?;
+        let page = std::num::NonZeroUsize::new(pos.page)
+            .ok_or_else(|| invalid_params("The page number must be positive"))?;
+        let position = typst::layout::Position {
+            page,
+            point: Point::new(Abs::pt(pos.x), Abs::pt(pos.y)),
+        };
+
+        let location = self
+            .primary()
+            .steal_state(move |ctx, doc| {
+                let (span, offset) = tinymist_query::jump_from_click(&doc?.document, &position)?;
+                let id = span.id()?;
+                let source = ctx.source_by_id(id).ok()?;
+                let range = source.find(span)?.range();
+                let cursor = range.start + offset.min(range.len());
+                let uri = tinymist_query::path_to_url(&ctx.path_for_id(id).ok()?).ok()?;
+                Some(Location {
+                    uri,
+                    range: ctx.to_lsp_range(cursor..cursor, &source),
+                })
+            })
+            .map_err(|e| internal_error(format!("could not resolve source location: {e}")))?;
+
+        if let Some(location) = &location {
+            self.client.send_request::<request::ShowDocument>(
+                ShowDocumentParams {
+                    uri: location.uri.clone(),
+                    external: Some(false),
+                    take_focus: Some(true),
+                    selection: Some(location.range),
+                },
+                |_, resp| {
+                    if let Some(err) = resp.error {
+                        log::error!("failed to show the source location: {err:?}");
+                    }
+                },
+            );
+        }
+
+        let res = serde_json::to_value(location)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Find near-duplicate markup blocks in the workspace of the document.
+    pub fn find_content_reuse(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+
+        let res = run_query!(self.ContentReuse(path))?;
         let res = serde_json::to_value(res)
             .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
 
@@ -791,6 +1330,190 @@ impl TypstLanguageServer {
         Ok(res)
     }
 
+    /// Get the statistics of the server, e.g. the sizes of the caches and the
+    /// timings of the requests.
+    pub fn server_stats(
+        &mut self,
+        req_id: RequestId,
+        _arguments: Vec<JsonValue>,
+    ) -> LspResult<Option<()>> {
+        let stats = self.collect_server_stats();
+        let client = self.client.clone();
+        self.handle.spawn(async move {
+            let res = stats.await.map_err(|err| {
+                error!("could not collect the server stats: {err}");
+                internal_error("Internal error")
+            });
+            if let Ok(response) = result_to_response_(req_id, res) {
+                client.respond(response);
+            }
+        });
+
+        Ok(Some(()))
+    }
+
+    /// Collects the statistics of the server in the background, so that the
+    /// main loop is not blocked by the busy compilers.
+    fn collect_server_stats(&self) -> BoxFuture<'static, anyhow::Result<ServerStats>> {
+        let compilers = std::iter::once(&self.primary).chain(&self.dedicates);
+        let compilers = compilers.flat_map(|server| server.compiler.as_ref());
+        let compilers = compilers
+            .map(|compiler| (compiler.diag_group.clone(), compiler.collect_server_stats()))
+            .collect::<Vec<_>>();
+        let timings = self.timings.report();
+
+        Box::pin(async move {
+            let mut stats = HashMap::new();
+            for (diag_group, compiler) in compilers {
+                stats.insert(diag_group, compiler.await?);
+            }
+
+            Ok(ServerStats {
+                compilers: stats,
+                timings,
+            })
+        })
+    }
+
+    /// Notifies the client of the statistics of the server.
+    fn report_server_stats(&mut self) {
+        let stats = self.collect_server_stats();
+        let client = self.client.clone();
+        self.handle.spawn(async move {
+            match stats.await {
+                Ok(stats) => client.send_notification::<TinymistServerStats>(stats),
+                Err(err) => error!("could not collect the server stats: {err}"),
+            }
+        });
+    }
+
+    /// Resets the ticks of the periodic reports of the statistics by the
+    /// configured interval in seconds.
+    fn reset_stats_ticker(&mut self) {
+        self.stats_ticker = match self.config.server_stats_interval {
+            Some(secs) => crossbeam_channel::tick(Duration::from_secs(secs)),
+            None => crossbeam_channel::never(),
+        };
+    }
+
+    /// Sets the locale of the messages produced by tinymist, preferring the
+    /// configured one to the one of the client.
+    pub(crate) fn apply_locale(&self) {
+        let locale = self.config.locale.as_deref();
+        let locale = locale.or(self.const_config.locale.as_deref());
+        let locale = Locale::from_tag(locale.unwrap_or("en"));
+        info!("messages are localized to {locale:?}");
+        i18n::set_locale(locale);
+    }
+
+    /// List the fonts seen by the compiler, with their variants and the paths
+    /// of the loaded ones.
+    pub fn list_fonts(&self, _arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let fonts = list_fonts(self.primary.font.wait());
+        let res = serde_json::to_value(fonts)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+
+        Ok(res)
+    }
+
+    /// Convert pasted Markdown, LaTeX math, or CSV to Typst markup, returning
+    /// `null` if the text is in none of the formats. The format is detected
+    /// unless it is given in the second argument.
+    pub fn convert_paste(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let text = arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| invalid_params("The first parameter is not a valid text"))?;
+        let format = match arguments.get(1) {
+            None | Some(JsonValue::Null) => None,
+            Some(v) => Some(
+                serde_json::from_value::<PasteFormat>(v.clone())
+                    .map_err(|e| invalid_params(format!("The second parameter is invalid: {e}")))?,
+            ),
+        };
+
+        let res = serde_json::to_value(convert_paste(text, format))
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+        Ok(res)
+    }
+
+    /// Generate a figure for an image dropped into the document in the first
+    /// argument. The image in the second argument is copied into the
+    /// `assetsDirectory` if it lies outside the root.
+    pub fn drop_image(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let document = parse_path(arguments.first())?;
+        let image = match arguments.get(1) {
+            Some(JsonValue::String(s)) => Path::new(s).clean(),
+            _ => return Err(invalid_params("The second parameter is not a valid path")),
+        };
+
+        let root = self.config.compile.determine_root(Some(&document));
+        let root = root
+            .or_else(|| document.parent().map(From::from))
+            .ok_or_else(|| invalid_params("Cannot determine the root of the document"))?;
+        let assets_dir = self.config.assets_directory.as_deref();
+        let assets_dir = Path::new(assets_dir.unwrap_or(DEFAULT_ASSETS_DIR));
+
+        let dropped = drop_image(&document, &image, &root, assets_dir)
+            .map_err(|e| internal_error(format!("Cannot drop the image: {e}")))?;
+        let res = serde_json::to_value(dropped)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))?;
+        Ok(res)
+    }
+
+    /// Get the syntax tree of a document, or of the node enclosing the range
+    /// in the second argument, pretty printed or in JSON. The types of the
+    /// nodes inferred by type checking are included if `types` is set.
+    pub fn get_ast(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        #[derive(Debug, Clone, Copy, Default, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        enum AstFormat {
+            #[default]
+            Text,
+            Json,
+        }
+
+        #[derive(Debug, Clone, Default, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct AstOpts {
+            #[serde(default)]
+            format: AstFormat,
+            #[serde(default)]
+            types: bool,
+        }
+
+        let path = parse_path(arguments.first())?.as_ref().to_owned();
+        let range = match arguments.get(1) {
+            None | Some(JsonValue::Null) => None,
+            Some(range) => Some(
+                serde_json::from_value::<Range>(range.clone())
+                    .map_err(|_| invalid_params("The second parameter is not a valid range"))?,
+            ),
+        };
+        let opts = match arguments.get(2) {
+            None | Some(JsonValue::Null) => AstOpts::default(),
+            Some(opts) => serde_json::from_value::<AstOpts>(opts.clone())
+                .map_err(|_| invalid_params("The third parameter is not a valid object"))?,
+        };
+
+        let req = SyntaxTreeRequest {
+            path,
+            range,
+            with_types: opts.types,
+        };
+        let tree = self
+            .primary()
+            .steal_world(move |ctx| req.request(ctx))
+            .map_err(|err| internal_error(err.to_string()))?
+            .ok_or_else(|| internal_error("Cannot get the syntax tree"))?;
+
+        match opts.format {
+            AstFormat::Text => Ok(JsonValue::String(tree.to_string())),
+            AstFormat::Json => serde_json::to_value(tree)
+                .map_err(|e| internal_error(format!("Cannot serialize response {e}"))),
+        }
+    }
+
     /// Clear all cached resources.
     ///
     /// # Errors
@@ -806,6 +1529,28 @@ impl TypstLanguageServer {
         Ok(JsonValue::Null)
     }
 
+    /// Fix the current date of the compilations at a UNIX timestamp, or unfix
+    /// it by `null`, until the settings are changed. The previews, the exports,
+    /// and the word counts are then reproducible.
+    pub fn set_timestamp(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let timestamp = match arguments.first() {
+            Some(JsonValue::Null) | None => None,
+            Some(timestamp) => match timestamp.as_i64() {
+                Some(t) if chrono::DateTime::from_timestamp(t, 0).is_some() => Some(t),
+                _ => return Err(invalid_params("The parameter is not a valid timestamp")),
+            },
+        };
+
+        self.config.compile.creation_timestamp = timestamp;
+        self.primary.change_creation_timestamp(timestamp);
+        for dedicate in &mut self.dedicates {
+            dedicate.change_creation_timestamp(timestamp);
+        }
+
+        info!("creation timestamp changed: {timestamp:?}");
+        Ok(JsonValue::Null)
+    }
+
     /// Pin main file to some path.
     pub fn pin_document(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let new_entry = parse_path_or_null(arguments.first())?;
@@ -817,6 +1562,15 @@ impl TypstLanguageServer {
         Ok(JsonValue::Null)
     }
 
+    /// Unpin the main file, so that the focusing file is compiled again.
+    pub fn unpin_document(&mut self, _arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let update_result = self.pin_entry(None);
+        update_result.map_err(|err| internal_error(format!("could not unpin file: {err}")))?;
+
+        info!("file unpinned");
+        Ok(JsonValue::Null)
+    }
+
     /// Focus main file to some path.
     pub fn focus_document(&mut self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         let new_entry = parse_path_or_null(arguments.first())?;
@@ -828,7 +1582,11 @@ impl TypstLanguageServer {
         Ok(JsonValue::Null)
     }
 
-    /// Initialize a new template.
+    /// Initialize a project from a template package into a directory, returning
+    /// the absolute path of the created entry file as `entryPath`.
+    ///
+    /// The command is named `doInitTemplate` since `tinymist.initTemplate` is
+    /// the command of the VS Code extension prompting for the arguments.
     pub fn init_template(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
         use crate::tools::package::{self, determine_latest_version, TemplateSource};
 
@@ -924,6 +1682,130 @@ impl TypstLanguageServer {
 
         Ok(JsonValue::String(entry))
     }
+
+    /// Download a package into the cache directory, and recompile the
+    /// documents with it. The cached package is downloaded again if `force` is
+    /// set in the options.
+    ///
+    /// The package is downloaded in the background, so that neither the server
+    /// nor the compilers are blocked by the network.
+    pub fn download_package(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        use typst_ts_compiler::package::{http::HttpRegistry, Registry};
+
+        #[derive(Debug, Default, Deserialize)]
+        #[serde(default)]
+        struct DownloadOpts {
+            force: bool,
+        }
+
+        let spec = parse_package_spec(arguments.first())?;
+        let opts = match arguments.get(1) {
+            Some(JsonValue::Null) | None => DownloadOpts::default(),
+            Some(opts) => serde_json::from_value::<DownloadOpts>(opts.clone())
+                .map_err(|_| invalid_params("The second argument is not a valid object"))?,
+        };
+
+        let recompilers = Some(self.primary())
+            .into_iter()
+            .chain(self.dedicates.iter().map(|v| v.compiler()))
+            .map(|v| v.recompiler())
+            .collect::<Vec<_>>();
+        let client = self.client.clone();
+        self.handle.spawn_blocking(move || {
+            let download = || -> Result<(), String> {
+                if opts.force {
+                    let dir = tinymist_query::analysis::cached_package_dir(&spec);
+                    if let Some(dir) = dir.filter(|dir| dir.exists()) {
+                        std::fs::remove_dir_all(&dir)
+                            .map_err(|e| format!("failed to remove package: {e}"))?;
+                    }
+                }
+                let registry = HttpRegistry::default();
+                registry.resolve(&spec).map_err(|err| err.to_string())?;
+                Ok(())
+            };
+
+            if let Err(err) = download() {
+                error!("failed to download package {spec}: {err}");
+                client.send_notification::<notification::ShowMessage>(ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: format!("Failed to download package {spec}: {err}"),
+                });
+                return;
+            }
+
+            for recompile in recompilers {
+                recompile();
+            }
+        });
+
+        Ok(JsonValue::Null)
+    }
+
+    /// List the packages available locally, i.e. the downloaded packages and
+    /// the `@local` packages, with their versions and directories.
+    pub fn list_packages(&self, _arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let packages = tinymist_query::analysis::list_local_packages();
+        serde_json::to_value(packages)
+            .map_err(|e| internal_error(format!("Cannot serialize response {e}")))
+    }
+
+    /// Open the entrypoint of a package available locally in the editor,
+    /// returning the directory of the package.
+    pub fn open_package(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let spec = parse_package_spec(arguments.first())?;
+        let dir = tinymist_query::analysis::local_package_dir(&spec)
+            .ok_or_else(|| invalid_params(format!("package {spec} is not available locally")))?;
+
+        let manifest = std::fs::read_to_string(dir.join("typst.toml"))
+            .map_err(|e| internal_error(format!("failed to read package manifest: {e}")))?;
+        let manifest: PackageManifest = toml::from_str(&manifest)
+            .map_err(|e| internal_error(format!("failed to parse package manifest: {e}")))?;
+        self.show_document(&dir.join(manifest.package.entrypoint.as_str()));
+
+        Ok(JsonValue::String(dir.to_string_lossy().into_owned()))
+    }
+
+    /// Create a package `@local/{name}:{version}` with a manifest and an empty
+    /// entrypoint, and open the entrypoint in the editor. The version defaults
+    /// to `0.1.0`.
+    pub fn create_local_package(&self, arguments: Vec<JsonValue>) -> LspResult<JsonValue> {
+        let name = arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| invalid_params("The first parameter is not a valid package name"))?;
+        let version = match arguments.get(1) {
+            Some(JsonValue::Null) | None => "0.1.0".parse(),
+            Some(version) => version.as_str().unwrap_or_default().parse(),
+        };
+        let version = version.map_err(|e| invalid_params(format!("invalid version: {e}")))?;
+
+        let entrypoint = tinymist_query::analysis::create_local_package(name, version)
+            .map_err(|e| internal_error(format!("failed to create package: {e}")))?;
+        self.show_document(&entrypoint);
+
+        Ok(JsonValue::String(entrypoint.to_string_lossy().into_owned()))
+    }
+
+    /// Shows a file in the editor.
+    fn show_document(&self, path: &Path) {
+        let Ok(uri) = Url::from_file_path(path) else {
+            return;
+        };
+        self.client.send_request::<request::ShowDocument>(
+            ShowDocumentParams {
+                uri,
+                external: Some(false),
+                take_focus: Some(true),
+                selection: None,
+            },
+            |_, resp| {
+                if let Some(err) = resp.error {
+                    log::error!("failed to show the document: {err:?}");
+                }
+            },
+        );
+    }
 }
 
 impl TypstLanguageServer {
@@ -982,6 +1864,7 @@ impl TypstLanguageServer {
     fn did_close(&mut self, params: DidCloseTextDocumentParams) -> LspResult<()> {
         let path = as_path_(params.text_document.uri);
 
+        self.tokens_ctx.evict(&path);
         self.remove_source(path.clone()).unwrap();
         Ok(())
     }
@@ -1002,19 +1885,38 @@ impl TypstLanguageServer {
         Ok(())
     }
 
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) -> LspResult<()> {
+        let paths = params.changes.into_iter().filter_map(|change| {
+            let path = ImmutPath::from(change.uri.to_file_path().ok()?);
+            // The opened files are updated by their edits.
+            (!self.primary.memory_changes.contains_key(&path)).then_some(path)
+        });
+        let paths = paths.collect::<Vec<_>>();
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let affected = self.reload_files(paths.clone());
+        let affected = affected.map_err(|err| internal_error(format!("{err}")))?;
+        info!("files changed: {paths:?}, affecting entries: {affected:?}");
+        Ok(())
+    }
+
     fn on_changed_configuration(&mut self, values: Map<String, JsonValue>) -> LspResult<()> {
         let config = self.config.clone();
-        match self.config.update_by_map(&values) {
-            Ok(()) => {}
-            Err(err) => {
-                self.config = config;
-                error!("error applying new settings: {err}");
-                return Err(invalid_params(format!(
-                    "error applying new settings: {err}"
-                )));
-            }
+        // The valid settings are still applied, while the invalid ones are reset to
+        // their defaults and reported to the client.
+        if let Err(err) = self.config.update_by_map(&values) {
+            warn!("invalid settings are reset to their defaults: {err}");
+            self.client
+                .send_notification::<notification::ShowMessage>(ShowMessageParams {
+                    typ: MessageType::WARNING,
+                    message: format!("Invalid settings are reset to their defaults: {err}"),
+                });
         }
         self.primary.on_changed_configuration(values)?;
+        self.dependency_graph = None;
+        self.entry_detection.clear();
 
         info!("new settings applied");
 
@@ -1026,16 +1928,31 @@ impl TypstLanguageServer {
             }
         }
 
+        if config.server_stats_interval != self.config.server_stats_interval {
+            self.reset_stats_ticker();
+        }
+
+        if config.locale != self.config.locale {
+            self.apply_locale();
+            // The lints are cached with the messages in the previous locale.
+            self.primary().relint();
+            for dedicate in &self.dedicates {
+                dedicate.compiler().relint();
+            }
+        }
+
         if config.formatter != self.config.formatter {
             let err = self.enable_formatter_caps(self.config.formatter != FormatterMode::Disable);
             if let Err(err) = err {
                 error!("could not change formatter config: {err}");
             }
+        }
+
+        // The options are applied to the formatter without restarting it.
+        let formatting_config = self.config.formatting_config();
+        if config.formatting_config() != formatting_config {
             if let Some(f) = &self.format_thread {
-                let err = f.send(FormattingRequest::ChangeConfig(FormattingConfig {
-                    mode: self.config.formatter,
-                    width: self.config.formatter_print_width,
-                }));
+                let err = f.send(FormattingRequest::ChangeConfig(formatting_config));
                 if let Err(err) = err {
                     error!("could not change formatter config: {err}");
                 }
@@ -1096,11 +2013,27 @@ impl TypstLanguageServer {
         run_query!(self.GotoDeclaration(path, position))
     }
 
+    fn goto_type_definition(
+        &mut self,
+        params: GotoTypeDefinitionParams,
+    ) -> LspResult<Option<GotoTypeDefinitionResponse>> {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(self.GotoTypeDefinition(path, position))
+    }
+
     fn references(&mut self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
         let (path, position) = as_path_pos(params.text_document_position);
         run_query!(self.References(path, position))
     }
 
+    fn document_highlight(
+        &mut self,
+        params: DocumentHighlightParams,
+    ) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(self.DocumentHighlight(path, position))
+    }
+
     fn hover(&mut self, params: HoverParams) -> LspResult<Option<Hover>> {
         let (path, position) = as_path_pos(params.text_document_position_params);
         run_query!(self.Hover(path, position))
@@ -1124,6 +2057,24 @@ impl TypstLanguageServer {
         run_query!(self.SelectionRange(path, positions))
     }
 
+    fn linked_editing_range(
+        &mut self,
+        params: LinkedEditingRangeParams,
+    ) -> LspResult<Option<LinkedEditingRanges>> {
+        let (path, position) = as_path_pos(params.text_document_position_params);
+        run_query!(self.LinkedEditingRange(path, position))
+    }
+
+    fn on_type_formatting(
+        &mut self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> LspResult<Option<Vec<TextEdit>>> {
+        let (path, position) = as_path_pos(params.text_document_position);
+        let ch = params.ch;
+        let config = self.config.on_type_formatting;
+        run_query!(self.OnTypeFormatting(path, position, ch, config))
+    }
+
     fn document_symbol(
         &mut self,
         params: DocumentSymbolParams,
@@ -1132,6 +2083,14 @@ impl TypstLanguageServer {
         run_query!(self.DocumentSymbol(path))
     }
 
+    fn document_link(
+        &mut self,
+        params: DocumentLinkParams,
+    ) -> LspResult<Option<Vec<DocumentLink>>> {
+        let path = as_path(params.text_document);
+        run_query!(self.DocumentLink(path))
+    }
+
     fn semantic_tokens_full(
         &mut self,
         params: SemanticTokensParams,
@@ -1149,6 +2108,15 @@ impl TypstLanguageServer {
         run_query!(self.SemanticTokensDelta(path, previous_result_id))
     }
 
+    fn semantic_tokens_range(
+        &mut self,
+        params: SemanticTokensRangeParams,
+    ) -> LspResult<Option<SemanticTokensRangeResult>> {
+        let path = as_path(params.text_document);
+        let range = params.range;
+        run_query!(self.SemanticTokensRange(path, range))
+    }
+
     fn formatting(
         &self,
         req_id: RequestId,
@@ -1197,7 +2165,21 @@ impl TypstLanguageServer {
 
     fn code_lens(&mut self, params: CodeLensParams) -> LspResult<Option<Vec<CodeLens>>> {
         let path = as_path(params.text_document);
-        run_query!(self.CodeLens(path))
+        let compiler = self.primary.compiler.as_ref();
+        let summary = compiler.and_then(|compiler| compiler.document_summary(&path));
+        run_query!(self.CodeLens(path, summary))
+    }
+
+    fn code_action(
+        &mut self,
+        params: CodeActionParams,
+    ) -> LspResult<Option<Vec<CodeActionOrCommand>>> {
+        let path = as_path(params.text_document);
+        let range = params.range;
+        let diagnostics = params.context.diagnostics;
+        let only = params.context.only;
+        let trigger_rename = self.const_config().code_action_rename;
+        run_query!(self.CodeAction(path, range, diagnostics, only, trigger_rename))
     }
 
     fn completion(&mut self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
@@ -1229,6 +2211,19 @@ impl TypstLanguageServer {
         run_query!(self.PrepareRename(path, position))
     }
 
+    fn will_rename_files(&mut self, params: RenameFilesParams) -> LspResult<Option<WorkspaceEdit>> {
+        let paths = params
+            .files
+            .into_iter()
+            .filter_map(|file| {
+                let old_uri = Url::parse(&file.old_uri).ok()?;
+                let new_uri = Url::parse(&file.new_uri).ok()?;
+                Some((as_path_(old_uri), as_path_(new_uri)))
+            })
+            .collect::<Vec<_>>();
+        run_query!(self.WillRenameFiles(paths))
+    }
+
     fn symbol(
         &mut self,
         params: WorkspaceSymbolParams,
@@ -1238,19 +2233,21 @@ impl TypstLanguageServer {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ExportOpts {
-    page: PageSelection,
+fn parse_package_spec(v: Option<&JsonValue>) -> LspResult<PackageSpec> {
+    v.and_then(|v| v.as_str())
+        .ok_or_else(|| invalid_params("The first parameter is not a valid package spec"))?
+        .parse()
+        .map_err(|e| invalid_params(format!("failed to parse package spec: {e}")))
 }
 
-fn parse_opts(v: Option<&JsonValue>) -> LspResult<ExportOpts> {
-    Ok(match v {
-        Some(opts) => serde_json::from_value::<ExportOpts>(opts.clone())
-            .map_err(|_| invalid_params("The third argument is not a valid object"))?,
-        _ => ExportOpts {
-            page: PageSelection::First,
-        },
-    })
+/// A position in the compiled document, where the coordinates are in points
+/// from the top left corner of the page.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct DocumentPosition {
+    /// The page number, starting from 1.
+    page: usize,
+    x: f64,
+    y: f64,
 }
 
 fn parse_path(v: Option<&JsonValue>) -> LspResult<ImmutPath> {
@@ -2,22 +2,26 @@ use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::bail;
 use itertools::Itertools;
-use log::info;
+use log::{info, warn};
 use lsp_types::*;
 use serde::Deserialize;
 use serde_json::{Map, Value as JsonValue};
-use tinymist_query::{get_semantic_tokens_options, PositionEncoding};
+use tinymist_query::{
+    get_semantic_tokens_options, url_to_path, CompletionFeat, OnTypeFormattingConfig,
+    PositionEncoding,
+};
 use tokio::sync::mpsc;
 use typst::util::Deferred;
 use typst_ts_core::ImmutPath;
 
 use crate::actor::cluster::EditorActor;
+use crate::actor::FormattingConfig;
 use crate::compiler_init::CompileConfig;
 use crate::harness::LspHost;
+use crate::server::config::{ConfigErrors, ConfigReader};
+use crate::tools::markup_export::AssetMode;
 use crate::world::{ImmutDict, SharedFontResolver};
-use crate::{
-    invalid_params, CompileFontOpts, LspResult, TypstLanguageServer, TypstLanguageServerArgs,
-};
+use crate::{CompileFontOpts, LspResult, TypstLanguageServer, TypstLanguageServerArgs};
 
 // todo: svelte-language-server responds to a Goto Definition request with
 // LocationLink[] even if the client does not report the
@@ -77,19 +81,49 @@ pub struct CompileExtraOpts {
 
     /// will remove later
     pub font_paths: Vec<PathBuf>,
+
+    /// The UNIX timestamp fixing the current date of the compilations.
+    pub creation_timestamp: Option<i64>,
 }
 
 const CONFIG_ITEMS: &[&str] = &[
     "outputPath",
     "exportPdf",
+    "exportDebounce",
+    "warmUpBudget",
     "rootPath",
+    "entries",
+    "entryDetection",
+    "entryOverrides",
     "semanticTokens",
     "formatterMode",
     "formatterPrintWidth",
+    "formatterIndentSize",
+    "formatterKeepHardBreaks",
     "typstExtraArgs",
+    "creationTimestamp",
     "compileStatus",
     "preferredTheme",
     "hoverPeriscope",
+    "hoverPreview",
+    "typeInlayHints",
+    "closingHintsMinLines",
+    "analysisMemoryBudget",
+    "fontPaths",
+    "systemFonts",
+    "embeddedFonts",
+    "packageOverrides",
+    "completionSnippets",
+    "analysisPlugins",
+    "completionPaths",
+    "lint",
+    "spellCheck",
+    "onTypeFormatting",
+    "exportAssets",
+    "testGlob",
+    "serverStatsInterval",
+    "locale",
+    "assetsDirectory",
 ];
 
 /// The user configuration read from the editor.
@@ -104,7 +138,27 @@ pub struct Config {
     /// Dynamic configuration for the experimental formatter.
     pub formatter: FormatterMode,
     /// Dynamic configuration for the experimental formatter.
-    pub formatter_print_width: u32,
+    pub formatter_print_width: Option<u32>,
+    /// Dynamic configuration for the indentation of the formatter.
+    pub formatter_indent_size: Option<u32>,
+    /// Dynamic configuration for keeping the line breaks in markup.
+    pub formatter_keep_hard_breaks: bool,
+    /// Dynamic configuration for the formatting on typing.
+    pub on_type_formatting: OnTypeFormattingConfig,
+    /// Dynamic configuration for the assets of the HTML and Markdown exports.
+    pub export_assets: AssetMode,
+    /// Dynamic configuration for the glob of the test files run by
+    /// `tinymist.runTests`.
+    pub test_glob: Option<String>,
+    /// Dynamic configuration for the interval in seconds of the periodic
+    /// reports of the statistics of the server, which are disabled if `None`.
+    pub server_stats_interval: Option<u64>,
+    /// Dynamic configuration for the locale of the messages produced by
+    /// tinymist, overriding the locale of the client.
+    pub locale: Option<String>,
+    /// Dynamic configuration for the directory, relative to the root, where
+    /// the dropped images out of the root are copied into.
+    pub assets_directory: Option<String>,
 }
 
 impl Config {
@@ -150,41 +204,57 @@ impl Config {
 
     /// Updates the configuration with a map.
     ///
+    /// The invalid entries are reset to their defaults, while the valid ones
+    /// are still applied.
+    ///
     /// # Errors
-    /// Errors if the update is invalid.
-    pub fn update_by_map(&mut self, update: &Map<String, JsonValue>) -> anyhow::Result<()> {
-        let semantic_tokens = update
-            .get("semanticTokens")
-            .map(SemanticTokensMode::deserialize)
-            .and_then(Result::ok);
-        if let Some(semantic_tokens) = semantic_tokens {
-            self.semantic_tokens = semantic_tokens;
-        }
-
-        let formatter = update
-            .get("formatterMode")
-            .map(FormatterMode::deserialize)
-            .and_then(Result::ok);
-        if let Some(formatter) = formatter {
-            self.formatter = formatter;
-        }
-
-        let print_width = update
-            .get("formatterPrintWidth")
-            .and_then(|e| serde_json::from_value::<u32>(e.clone()).ok());
-        if let Some(formatter) = print_width {
-            self.formatter_print_width = formatter;
+    /// Errors with the invalid entries if there are any.
+    pub fn update_by_map(&mut self, update: &Map<String, JsonValue>) -> Result<(), ConfigErrors> {
+        let mut r = ConfigReader::new(update);
+
+        self.semantic_tokens = r.get("semanticTokens").unwrap_or_default();
+        self.formatter = r.get("formatterMode").unwrap_or_default();
+
+        let mut formatter_size = |key: &str| {
+            let size = r.get::<u32>(key)?;
+            if size == 0 {
+                r.invalid(key, "must be positive");
+                return None;
+            }
+            Some(size)
+        };
+        self.formatter_print_width = formatter_size("formatterPrintWidth");
+        self.formatter_indent_size = formatter_size("formatterIndentSize");
+        self.formatter_keep_hard_breaks = r.get("formatterKeepHardBreaks").unwrap_or(false);
+        self.on_type_formatting = r.get("onTypeFormatting").unwrap_or_default();
+
+        self.export_assets = r.get("exportAssets").unwrap_or_default();
+
+        self.test_glob = r.get::<String>("testGlob").filter(|glob| !glob.is_empty());
+        self.server_stats_interval = r.get::<u64>("serverStatsInterval");
+        if self.server_stats_interval == Some(0) {
+            r.invalid("serverStatsInterval", "must be positive");
+            self.server_stats_interval = None;
         }
-
-        self.compile.update_by_map(update)?;
-        self.validate()?;
-        Ok(())
+        self.locale = r
+            .get::<String>("locale")
+            .filter(|locale| !locale.is_empty());
+        self.assets_directory = r
+            .get::<String>("assetsDirectory")
+            .filter(|dir| !dir.is_empty());
+
+        self.compile.read(&mut r);
+        r.finish()
     }
 
-    fn validate(&self) -> anyhow::Result<()> {
-        self.compile.validate()?;
-
-        Ok(())
+    /// Gets the configuration of the formatter.
+    pub fn formatting_config(&self) -> FormattingConfig {
+        FormattingConfig {
+            mode: self.formatter,
+            width: self.formatter_print_width.unwrap_or(120),
+            indent: self.formatter_indent_size,
+            keep_hard_breaks: self.formatter_keep_hard_breaks,
+        }
     }
 }
 
@@ -197,16 +267,33 @@ pub struct ConstConfig {
     pub position_encoding: PositionEncoding,
     /// Allow dynamic registration of configuration changes.
     pub cfg_change_registration: bool,
+    /// Allow dynamic registration of watched files changes.
+    pub watched_files_registration: bool,
     /// Allow dynamic registration of semantic tokens.
     pub sema_tokens_dynamic_registration: bool,
     /// Allow overlapping tokens.
     pub sema_tokens_overlapping_token_support: bool,
     /// Allow multiline tokens.
     pub sema_tokens_multiline_token_support: bool,
+    /// Allow refreshing the semantic tokens, e.g. after the identifiers are
+    /// classified.
+    pub sema_tokens_refresh: bool,
     /// Allow line folding on documents.
     pub doc_line_folding_only: bool,
     /// Allow dynamic registration of document formatting.
     pub doc_fmt_dynamic_registration: bool,
+    /// The completion features supported by the client.
+    pub completion_feat: CompletionFeat,
+    /// Allow markdown contents in hover.
+    pub hover_markdown: bool,
+    /// Allow running `editor.action.rename` after a code action.
+    pub code_action_rename: bool,
+    /// Allow refreshing the code lenses, e.g. after a compilation.
+    pub code_lens_refresh: bool,
+    /// Allow reporting the progress of the work initiated by the server.
+    pub work_done_progress: bool,
+    /// The locale of the client, e.g. `zh-CN`.
+    pub locale: Option<String>,
 }
 
 impl From<&InitializeParams> for ConstConfig {
@@ -233,6 +320,24 @@ impl From<&InitializeParams> for ConstConfig {
         let supports_config_change_registration = workspace_caps
             .and_then(|workspace| workspace.configuration)
             .unwrap_or(false);
+        let supports_watched_files_registration = workspace_caps
+            .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+            .and_then(|watched_files| watched_files.dynamic_registration)
+            .unwrap_or(false);
+        let supports_semantic_tokens_refresh = workspace_caps
+            .and_then(|workspace| workspace.semantic_tokens.as_ref())
+            .and_then(|semantic_tokens| semantic_tokens.refresh_support)
+            .unwrap_or(false);
+        let supports_code_lens_refresh = workspace_caps
+            .and_then(|workspace| workspace.code_lens.as_ref())
+            .and_then(|code_lens| code_lens.refresh_support)
+            .unwrap_or(false);
+        let supports_work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
 
         let doc_caps = params.capabilities.text_document.as_ref();
         let folding_caps = doc_caps.and_then(|doc| doc.folding_range.as_ref());
@@ -256,15 +361,49 @@ impl From<&InitializeParams> for ConstConfig {
             .and_then(|formatting| formatting.dynamic_registration)
             .unwrap_or(false);
 
+        let hover_caps = doc_caps.and_then(|doc| doc.hover.as_ref());
+        let supports_hover_markdown = hover_caps
+            .and_then(|hover| hover.content_format.as_ref())
+            .is_some_and(|formats| formats.contains(&MarkupKind::Markdown));
+
+        let completion_caps = doc_caps.and_then(|doc| doc.completion.as_ref());
+        let supports_completion_snippets = completion_caps
+            .and_then(|completion| completion.completion_item.as_ref())
+            .and_then(|item| item.snippet_support)
+            .unwrap_or(false);
+        // There is no capability for running commands after a completion, so the command is only
+        // sent to the clients known to support it.
+        let supports_trigger_suggest = params.client_info.as_ref().is_some_and(|info| {
+            let name = info.name.as_str();
+            name.starts_with("Visual Studio Code")
+                || matches!(name, "VSCodium" | "Code - OSS" | "Neovim")
+        });
+        // The rename command is specific to VS Code and its forks.
+        let supports_code_action_rename = params.client_info.as_ref().is_some_and(|info| {
+            let name = info.name.as_str();
+            name.starts_with("Visual Studio Code") || matches!(name, "VSCodium" | "Code - OSS")
+        });
+
         Self {
             position_encoding,
             sema_tokens_dynamic_registration: supports_semantic_tokens_dynamic_registration,
             sema_tokens_overlapping_token_support:
                 supports_semantic_tokens_overlapping_token_support,
             sema_tokens_multiline_token_support: supports_semantic_tokens_multiline_token_support,
+            sema_tokens_refresh: supports_semantic_tokens_refresh,
             doc_fmt_dynamic_registration: supports_document_formatting_dynamic_registration,
             cfg_change_registration: supports_config_change_registration,
+            watched_files_registration: supports_watched_files_registration,
             doc_line_folding_only: line_folding_only,
+            completion_feat: CompletionFeat {
+                snippets: supports_completion_snippets,
+                trigger_suggest: supports_trigger_suggest,
+            },
+            hover_markdown: supports_hover_markdown,
+            code_action_rename: supports_code_action_rename,
+            code_lens_refresh: supports_code_lens_refresh,
+            work_done_progress: supports_work_done_progress,
+            locale: params.locale.clone(),
         }
     }
 }
@@ -308,15 +447,13 @@ impl Init {
                 roots: match params.workspace_folders.as_ref() {
                     Some(roots) => roots
                         .iter()
-                        .map(|root| &root.uri)
-                        .map(Url::to_file_path)
-                        .collect::<Result<Vec<_>, _>>()
-                        .unwrap(),
+                        .map(|root| url_to_path(root.uri.clone()))
+                        .collect(),
                     #[allow(deprecated)] // `params.root_path` is marked as deprecated
                     None => params
                         .root_uri
                         .as_ref()
-                        .map(|uri| uri.to_file_path().unwrap())
+                        .map(|uri| url_to_path(uri.clone()))
                         .or_else(|| params.root_path.clone().map(PathBuf::from))
                         .into_iter()
                         .collect(),
@@ -325,29 +462,24 @@ impl Init {
             },
             ..Config::default()
         };
-        let res = match &params.initialization_options {
-            Some(init) => config
-                .update(init)
-                .map_err(|e| e.to_string())
-                .map_err(invalid_params),
-            None => Ok(()),
-        };
+        // The invalid settings are reset to their defaults and reported to the
+        // client, instead of failing the initialization.
+        if let Some(init) = &params.initialization_options {
+            if let Err(err) = config.update(init) {
+                warn!("invalid settings are reset to their defaults: {err}");
+                self.host
+                    .send_notification::<notification::ShowMessage>(ShowMessageParams {
+                        typ: MessageType::WARNING,
+                        message: format!("Invalid settings are reset to their defaults: {err}"),
+                    });
+            }
+        }
 
         // prepare fonts
         // todo: on font resolving failure, downgrade to a fake font book
+        let font_opts = std::mem::take(&mut self.compile_opts);
         let font = {
-            let mut opts = std::mem::take(&mut self.compile_opts);
-            if opts.font_paths.is_empty() {
-                if let Some(font_paths) = config
-                    .compile
-                    .typst_extra_args
-                    .as_ref()
-                    .map(|x| &x.font_paths)
-                {
-                    opts.font_paths = font_paths.clone();
-                }
-            }
-
+            let opts = config.compile.determine_font_opts(font_opts.clone());
             Deferred::new(|| SharedFontResolver::new(opts).expect("failed to create font book"))
         };
 
@@ -360,15 +492,13 @@ impl Init {
             diag_tx,
             handle: self.handle.clone(),
             font,
+            font_opts,
         });
 
-        if let Err(err) = res {
-            return (service, Err(err));
-        }
-
         info!("initialized with config {config:?}", config = config);
         service.primary.config = config.compile.clone();
         service.config = config;
+        service.apply_locale();
 
         service.run_format_thread();
         service.run_user_action_thread();
@@ -380,6 +510,8 @@ impl Init {
             affect_map: HashMap::new(),
             published_primary: false,
             notify_compile_status: service.config.compile.notify_compile_status,
+            refresh_code_lens: cc.code_lens_refresh,
+            work_done_progress: cc.work_done_progress,
         };
 
         let fallback = service.config.compile.determine_default_entry_path();
@@ -428,7 +560,9 @@ impl Init {
                     },
                 }),
                 definition_provider: Some(OneOf::Left(true)),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
                 references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
                 completion_provider: Some(CompletionOptions {
                     // Please update the language-configurations.json if you are changing this
                     // setting.
@@ -461,8 +595,21 @@ impl Init {
                 }),
                 color_provider: Some(ColorProviderCapability::Simple(true)),
                 document_symbol_provider: Some(OneOf::Left(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: None,
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: None,
+                    },
+                }),
                 workspace_symbol_provider: Some(OneOf::Left(true)),
                 selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(
+                    true,
+                )),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: "\n".to_owned(),
+                    more_trigger_character: Some(vec!["$".to_owned(), "=".to_owned()]),
+                }),
                 rename_provider: Some(OneOf::Right(RenameOptions {
                     prepare_provider: Some(true),
                     work_done_progress_options: WorkDoneProgressOptions {
@@ -475,6 +622,31 @@ impl Init {
                         supported: Some(true),
                         change_notifications: Some(OneOf::Left(true)),
                     }),
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(FileOperationRegistrationOptions {
+                            // The folders are registered as well, since the imports of
+                            // the files in a renamed folder are updated.
+                            filters: vec![
+                                FileOperationFilter {
+                                    scheme: Some("file".to_owned()),
+                                    pattern: FileOperationPattern {
+                                        glob: "**/*.typ".to_owned(),
+                                        matches: Some(FileOperationPatternKind::File),
+                                        options: None,
+                                    },
+                                },
+                                FileOperationFilter {
+                                    scheme: Some("file".to_owned()),
+                                    pattern: FileOperationPattern {
+                                        glob: "**/*".to_owned(),
+                                        matches: Some(FileOperationPatternKind::Folder),
+                                        options: None,
+                                    },
+                                },
+                            ],
+                        }),
+                        ..Default::default()
+                    }),
                     ..Default::default()
                 }),
                 document_formatting_provider,
@@ -482,6 +654,22 @@ impl Init {
                 code_lens_provider: Some(CodeLensOptions {
                     resolve_provider: Some(false),
                 }),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR,
+                            CodeActionKind::REFACTOR_EXTRACT,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                        ]),
+                        ..Default::default()
+                    },
+                )),
+                // The pasted text is converted by `tinymist.convertPaste`,
+                // which the clients may use to provide paste edits. The flag
+                // is documented in the README of the neovim support.
+                experimental: Some(serde_json::json!({ "convertPaste": true })),
                 ..Default::default()
             },
             ..Default::default()
@@ -527,6 +715,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_formatter_config() {
+        let mut config = Config::default();
+        assert_eq!(config.formatting_config().width, 120);
+
+        let update = json!({
+            "formatterMode": "typstfmt",
+            "formatterPrintWidth": 80,
+            "formatterIndentSize": 4,
+            "formatterKeepHardBreaks": true,
+        });
+        config.update(&update).unwrap();
+        assert_eq!(
+            config.formatting_config(),
+            FormattingConfig {
+                mode: FormatterMode::Typstfmt,
+                width: 80,
+                indent: Some(4),
+                keep_hard_breaks: true,
+            }
+        );
+
+        let err = config
+            .update(&json!({ "formatterIndentSize": 0 }))
+            .unwrap_err();
+        assert!(err.to_string().contains("formatterIndentSize"));
+        let err = config
+            .update(&json!({ "formatterPrintWidth": -1 }))
+            .unwrap_err();
+        assert!(err.to_string().contains("formatterPrintWidth"));
+    }
+
+    #[test]
+    fn test_partially_invalid_config() {
+        let mut config = Config::default();
+        config.semantic_tokens = SemanticTokensMode::Disable;
+        config.formatter = FormatterMode::Typstyle;
+        let update = json!({
+            "outputPath": "out",
+            "exportPdf": "sometimes",
+            "semanticTokens": "sometimes",
+            "formatterMode": "prettier",
+            "formatterPrintWidth": 0,
+            "compileStatus": "enable",
+        });
+
+        let err = config.update(&update).unwrap_err().to_string();
+        assert!(err.contains("exportPdf"), "unexpected error: {err}");
+        assert!(err.contains("semanticTokens"), "unexpected error: {err}");
+        assert!(err.contains("formatterMode"), "unexpected error: {err}");
+        assert!(
+            err.contains("formatterPrintWidth"),
+            "unexpected error: {err}"
+        );
+        assert_eq!(config.compile.output_path, "out");
+        assert_eq!(config.compile.export_pdf, ExportMode::default());
+        assert_eq!(config.semantic_tokens, SemanticTokensMode::default());
+        assert_eq!(config.formatter, FormatterMode::default());
+        assert_eq!(config.formatter_print_width, None);
+        assert!(config.compile.notify_compile_status);
+    }
+
     #[test]
     fn test_empty_extra_args() {
         let mut config = Config::default();
@@ -1,5 +1,8 @@
+pub mod config;
 pub mod lsp;
 pub mod lsp_init;
 
 pub mod compiler;
 pub mod compiler_init;
+
+pub mod headless;
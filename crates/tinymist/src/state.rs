@@ -1,10 +1,10 @@
 //! Bootstrap actors for Tinymist.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use ::typst::{diag::FileResult, syntax::Source};
 use anyhow::anyhow;
-use lsp_types::TextDocumentContentChangeEvent;
+use lsp_types::{request::SemanticTokensRefresh, TextDocumentContentChangeEvent};
 use tinymist_query::{
     lsp_to_typst, CompilerQueryRequest, CompilerQueryResponse, FoldRequestFeature, OnExportRequest,
     OnSaveExportRequest, PositionEncoding, SemanticRequest, StatefulRequest, SyntaxRequest,
@@ -15,6 +15,7 @@ use typst_ts_compiler::{
 };
 use typst_ts_core::{error::prelude::*, Bytes, Error, ImmutPath};
 
+use crate::tools::project::{entry_candidates, DependencyGraph, EntryReason, EntryStatus};
 use crate::{actor::typ_client::CompileClientActor, compiler::CompileServer, TypstLanguageServer};
 
 impl CompileServer {
@@ -25,20 +26,44 @@ impl CompileServer {
             .unwrap()
             .change_entry(new_entry.clone())?;
 
+        // The analysis requires a main file, so the workspace is warmed up once
+        // a file is focused for the first time.
+        if new_entry.is_some() && !self.warmed_up {
+            if let Some(budget) = self.config.warm_up_budget {
+                self.warmed_up = true;
+                self.compiler().warm_up(&self.handle, budget);
+            }
+        }
+
         Ok(())
     }
 }
 
+/// The notification of the entry resolved for the focusing file.
+pub struct TinymistEntryStatus;
+
+impl lsp_types::notification::Notification for TinymistEntryStatus {
+    type Params = EntryStatus;
+    const METHOD: &'static str = "tinymist/entryStatus";
+}
+
 impl TypstLanguageServer {
     /// Pin the entry to the given path
     pub fn pin_entry(&mut self, new_entry: Option<ImmutPath>) -> Result<(), Error> {
         let pinning = new_entry.is_some();
-        self.primary.do_change_entry(new_entry)?;
+        self.primary.do_change_entry(new_entry.clone())?;
         self.pinning = pinning;
 
-        if !pinning {
+        if let Some(entry) = new_entry {
+            let focusing = self.focusing.clone();
+            self.report_entry(focusing, entry, EntryReason::Pinned);
+        } else {
             let fallback = self.config.compile.determine_default_entry_path();
-            let fallback = fallback.or_else(|| self.focusing.clone());
+            let focusing = self.focusing.clone();
+            if let Some(e) = &fallback {
+                self.report_entry(focusing.clone(), e.clone(), EntryReason::Configured);
+            }
+            let fallback = fallback.or_else(|| Some(self.resolve_entry(focusing?)));
             if let Some(e) = fallback {
                 self.primary.do_change_entry(Some(e))?;
             }
@@ -49,12 +74,157 @@ impl TypstLanguageServer {
 
     /// Updates the primary (focusing) entry
     pub fn focus_entry(&mut self, new_entry: Option<ImmutPath>) -> Result<(), Error> {
+        self.focusing = new_entry.clone();
         if self.pinning || self.config.compile.has_default_entry_path {
-            self.focusing = new_entry;
             return Ok(());
         }
 
-        self.primary.do_change_entry(new_entry.clone())
+        let new_entry = new_entry.map(|entry| self.resolve_entry(entry));
+        self.primary.do_change_entry(new_entry)
+    }
+
+    /// Resolves a file to its entry and reports it to the client, i.e. the
+    /// entry overridden for its folder, the configured entry owning it, the
+    /// detected entry if the detection is enabled, or the file itself.
+    pub fn resolve_entry(&mut self, path: ImmutPath) -> ImmutPath {
+        let (entry, reason) = self.resolve_entry_with_reason(path.clone());
+        self.report_entry(Some(path), entry.clone(), reason);
+        entry
+    }
+
+    fn resolve_entry_with_reason(&mut self, path: ImmutPath) -> (ImmutPath, EntryReason) {
+        if let Some(entry) = self.config.compile.determine_entry_override(&path) {
+            return (entry, EntryReason::Overridden);
+        }
+
+        // The configured entries own the files they import or include.
+        let entries = self.config.compile.determine_entries();
+        if !entries.is_empty() {
+            let graph = match self.dependency_graph.take() {
+                Some(graph) => graph,
+                None => {
+                    let root = self.config.compile.determine_root(None);
+                    DependencyGraph::build(&entries, root.as_deref(), |file| self.load_source(file))
+                }
+            };
+            let owner = graph.owner(&path).map(ImmutPath::from);
+            self.dependency_graph = Some(graph);
+            return match owner {
+                Some(owner) => (owner, EntryReason::Configured),
+                None => (path, EntryReason::Focused),
+            };
+        }
+
+        if !self.config.compile.entry_detection {
+            return (path, EntryReason::Focused);
+        }
+
+        // The graph of the candidates is built once per folder and reused for
+        // the files in it, unless the file is new to the folder.
+        let folder = ImmutPath::from(path.parent().unwrap_or(path.as_ref()));
+        let graph = match self.entry_detection.remove(&folder) {
+            Some(graph) if graph.entries.iter().any(|entry| *entry == *path) => graph,
+            _ => {
+                let root = self.config.compile.determine_root(Some(&path));
+                let candidates = entry_candidates(&path, root.as_deref());
+                DependencyGraph::build(&candidates, root.as_deref(), |file| self.load_source(file))
+            }
+        };
+        let detected = graph.detect_entry(&path, |file| self.load_source(file));
+        let detected = detected.map(|(entry, reason)| (ImmutPath::from(entry), reason));
+        self.entry_detection.insert(folder, graph);
+        detected.unwrap_or((path, EntryReason::Focused))
+    }
+
+    /// Notifies the client of the entry compiled for a file, if it is changed.
+    fn report_entry(&mut self, focusing: Option<ImmutPath>, entry: ImmutPath, reason: EntryReason) {
+        let status = EntryStatus {
+            focusing: focusing.map(|path| path.to_path_buf()),
+            entry: entry.to_path_buf(),
+            reason,
+        };
+        if self.entry_status.as_ref() == Some(&status) {
+            return;
+        }
+
+        log::info!("entry resolved: {status:?}");
+        self.client
+            .send_notification::<TinymistEntryStatus>(status.clone());
+        self.entry_status = Some(status);
+    }
+
+    /// Updates the dependency graphs of the project for a changed file,
+    /// returning the configured entries affected by the change. The focusing
+    /// file is resolved again if the imports are changed.
+    ///
+    /// An opened file is not parsed again, since its source is kept parsed
+    /// incrementally.
+    pub fn update_dependencies(&mut self, path: &Path) -> Result<Vec<ImmutPath>, Error> {
+        let source = self.primary.memory_changes.get(path);
+        let source = source.map(|meta| meta.content.clone());
+        let syntax = source.as_ref().map(Source::root);
+
+        let mut changed = false;
+        let mut affected = vec![];
+        if let Some(mut graph) = self.dependency_graph.take() {
+            changed |= graph.update(path, syntax, |file| self.load_source(file));
+            let entries = graph.affected_entries(path);
+            affected = entries.into_iter().map(ImmutPath::from).collect();
+            self.dependency_graph = Some(graph);
+        }
+        let mut detection = std::mem::take(&mut self.entry_detection);
+        for graph in detection.values_mut() {
+            changed |= graph.update(path, syntax, |file| self.load_source(file));
+        }
+        self.entry_detection = detection;
+
+        if changed && self.focusing.is_some() {
+            self.focus_entry(self.focusing.clone())?;
+        }
+        Ok(affected)
+    }
+
+    /// Reads the files changed on the disk again, which are not opened, and
+    /// updates the compilers and the dependency graphs of the project,
+    /// returning the configured entries affected by the changes.
+    pub fn reload_files(&mut self, paths: Vec<ImmutPath>) -> Result<Vec<ImmutPath>, Error> {
+        let mut files = FileChangeSet::new_inserts(vec![]);
+        for path in &paths {
+            match std::fs::read(path) {
+                Ok(content) => {
+                    let mt = std::fs::metadata(path).and_then(|meta| meta.modified());
+                    let mt = mt.unwrap_or_else(|_| Time::now());
+                    let snapshot = FileResult::Ok((mt, Bytes::from(content))).into();
+                    files.inserts.push((path.clone(), snapshot));
+                }
+                Err(_) => files.removes.push(path.clone()),
+            }
+        }
+
+        let primary = Some(self.primary());
+        let clients_to_notify =
+            (primary.into_iter()).chain(self.dedicates.iter().map(CompileServer::compiler));
+        for client in clients_to_notify {
+            client.add_fs_changes(files.clone());
+        }
+
+        let mut affected = vec![];
+        for path in &paths {
+            for entry in self.update_dependencies(path)? {
+                if !affected.contains(&entry) {
+                    affected.push(entry);
+                }
+            }
+        }
+        Ok(affected)
+    }
+
+    /// Loads the text of a source file, preferring the unsaved changes.
+    pub fn load_source(&self, path: &Path) -> Option<String> {
+        match self.primary.memory_changes.get(path) {
+            Some(meta) => Some(meta.content.text().to_owned()),
+            None => std::fs::read_to_string(path).ok(),
+        }
     }
 }
 
@@ -65,7 +235,7 @@ pub struct MemoryFileMeta {
 }
 
 impl TypstLanguageServer {
-    fn update_source(&self, files: FileChangeSet) -> Result<(), Error> {
+    fn update_source(&mut self, files: FileChangeSet) -> Result<(), Error> {
         let primary = Some(self.primary());
         let clients_to_notify =
             (primary.into_iter()).chain(self.dedicates.iter().map(CompileServer::compiler));
@@ -93,9 +263,13 @@ impl TypstLanguageServer {
         log::info!("create source: {:?}", path);
 
         // todo: is it safe to believe that the path is normalized?
-        let files = FileChangeSet::new_inserts(vec![(path, FileResult::Ok((now, content)).into())]);
+        let files =
+            FileChangeSet::new_inserts(vec![(path.clone(), FileResult::Ok((now, content)).into())]);
 
-        self.update_source(files)
+        self.update_source(files)?;
+        // The imports may be changed, which changes the owners of the files.
+        self.update_dependencies(&path)?;
+        Ok(())
     }
 
     pub fn remove_source(&mut self, path: PathBuf) -> Result<(), Error> {
@@ -105,9 +279,11 @@ impl TypstLanguageServer {
         log::info!("remove source: {:?}", path);
 
         // todo: is it safe to believe that the path is normalized?
-        let files = FileChangeSet::new_removes(vec![path]);
+        let files = FileChangeSet::new_removes(vec![path.clone()]);
 
-        self.update_source(files)
+        self.update_source(files)?;
+        self.update_dependencies(&path)?;
+        Ok(())
     }
 
     pub fn edit_source(
@@ -145,7 +321,9 @@ impl TypstLanguageServer {
 
         let files = FileChangeSet::new_inserts(vec![(path.clone(), snapshot)]);
 
-        self.update_source(files)
+        self.update_source(files)?;
+        self.update_dependencies(&path)?;
+        Ok(())
     }
 }
 
@@ -185,7 +363,8 @@ macro_rules! query_tokens_cache {
     ($self:ident, $method:ident, $req:expr) => {{
         let path: ImmutPath = $req.path.clone().into();
 
-        $self.query_source(path, |source| {
+        $self.query_source(path.clone(), |source| {
+            $self.resolve_sema_idents(path, &source);
             let res = $req.request(&$self.tokens_ctx, source);
             Ok(CompilerQueryResponse::$method(res))
         })
@@ -218,6 +397,32 @@ impl TypstLanguageServer {
         f(source)
     }
 
+    /// Classifies the identifiers of a source for the semantic tokens on the
+    /// compiler thread in the background, if the classification is outdated,
+    /// so that the tokens are never blocked by the compiler. The client is
+    /// asked to request the tokens again after that.
+    fn resolve_sema_idents(&self, path: ImmutPath, source: &Source) {
+        if !self.tokens_ctx.schedule_resolving(&path, source) {
+            return;
+        }
+        let Some(compiler) = self.primary.compiler.as_ref() else {
+            return;
+        };
+
+        let client = self.client.clone();
+        let refresh = self.const_config.sema_tokens_refresh;
+        let tokens_ctx = self.tokens_ctx.clone();
+        compiler.resolve_sema_idents(&self.primary.handle, tokens_ctx, path, move || {
+            if refresh {
+                client.send_request::<SemanticTokensRefresh>((), |_, resp| {
+                    if let Some(err) = resp.error {
+                        log::error!("failed to refresh semantic tokens: {err:?}");
+                    }
+                });
+            }
+        });
+    }
+
     pub fn query(&mut self, query: CompilerQueryRequest) -> anyhow::Result<CompilerQueryResponse> {
         use CompilerQueryRequest::*;
 
@@ -225,19 +430,24 @@ impl TypstLanguageServer {
             InteractCodeContext(req) => query_source!(self, InteractCodeContext, req),
             SemanticTokensFull(req) => query_tokens_cache!(self, SemanticTokensFull, req),
             SemanticTokensDelta(req) => query_tokens_cache!(self, SemanticTokensDelta, req),
+            SemanticTokensRange(req) => query_tokens_cache!(self, SemanticTokensRange, req),
             FoldingRange(req) => query_source!(self, FoldingRange, req),
+            CodeLens(req) => query_source!(self, CodeLens, req),
             SelectionRange(req) => query_source!(self, SelectionRange, req),
+            LinkedEditingRange(req) => query_source!(self, LinkedEditingRange, req),
+            OnTypeFormatting(req) => query_source!(self, OnTypeFormatting, req),
             DocumentSymbol(req) => query_source!(self, DocumentSymbol, req),
+            ReflowParagraph(req) => query_source!(self, ReflowParagraph, req),
             ColorPresentation(req) => Ok(CompilerQueryResponse::ColorPresentation(req.request())),
             _ => {
-                let client = &mut self.primary;
                 if !self.pinning && !self.config.compile.has_default_entry_path {
                     // todo: race condition, we need atomic primary query
                     if let Some(path) = query.associated_path() {
-                        client.do_change_entry(Some(path.into()))?;
+                        let entry = self.resolve_entry(path.into());
+                        self.primary.do_change_entry(Some(entry))?;
                     }
                 }
-                Self::query_on(client.compiler(), query)
+                Self::query_on(self.primary.compiler(), query)
             }
         }
     }
@@ -260,30 +470,41 @@ impl TypstLanguageServer {
             Hover(req) => query_state!(client, Hover, req),
             GotoDefinition(req) => query_world!(client, GotoDefinition, req),
             GotoDeclaration(req) => query_world!(client, GotoDeclaration, req),
+            GotoTypeDefinition(req) => query_world!(client, GotoTypeDefinition, req),
             References(req) => query_world!(client, References, req),
+            DocumentHighlight(req) => query_world!(client, DocumentHighlight, req),
             InlayHint(req) => query_world!(client, InlayHint, req),
             DocumentColor(req) => query_world!(client, DocumentColor, req),
-            CodeLens(req) => query_world!(client, CodeLens, req),
+            CodeAction(req) => query_world!(client, CodeAction, req),
             Completion(req) => query_state!(client, Completion, req),
             SignatureHelp(req) => query_world!(client, SignatureHelp, req),
             Rename(req) => query_world!(client, Rename, req),
             PrepareRename(req) => query_world!(client, PrepareRename, req),
+            WillRenameFiles(req) => query_world!(client, WillRenameFiles, req),
             Symbol(req) => query_world!(client, Symbol, req),
+            DocumentLink(req) => query_world!(client, DocumentLink, req),
 
             DocumentMetrics(req) => query_state!(client, DocumentMetrics, req),
+            DocumentOutline(req) => query_state!(client, DocumentOutline, req),
+            ContentReuse(req) => query_world!(client, ContentReuse, req),
             ServerInfo(_) => {
                 let res = client.collect_server_info()?;
                 Ok(CompilerQueryResponse::ServerInfo(Some(res)))
             }
 
             InteractCodeContext(..)
+            | SemanticTokensFull(..)
+            | SemanticTokensDelta(..)
+            | SemanticTokensRange(..)
             | FoldingRange(..)
+            | CodeLens(..)
             | SelectionRange(..)
-            | SemanticTokensDelta(..)
+            | LinkedEditingRange(..)
+            | OnTypeFormatting(..)
+            | ReflowParagraph(..)
             | Formatting(..)
             | DocumentSymbol(..)
-            | ColorPresentation(..)
-            | SemanticTokensFull(..) => unreachable!(),
+            | ColorPresentation(..) => unreachable!(),
         }
     }
 }
@@ -0,0 +1,92 @@
+//! The fonts seen by the compiler, grouped by their families.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use typst::text::{FontStretch, FontStyle, FontWeight};
+use typst_ts_core::{debug_loc::DataSource, FontResolver};
+
+use crate::world::SharedFontResolver;
+
+/// A font family seen by the compiler.
+#[derive(Debug, Clone, Serialize)]
+pub struct FontFamily {
+    /// The name of the family.
+    pub name: String,
+    /// The variants of the family.
+    pub variants: Vec<FontVariantInfo>,
+}
+
+/// A variant of a font family, i.e. a font.
+#[derive(Debug, Clone, Serialize)]
+pub struct FontVariantInfo {
+    /// The style of the font.
+    pub style: FontStyle,
+    /// The weight of the font.
+    pub weight: FontWeight,
+    /// The stretch of the font.
+    pub stretch: FontStretch,
+    /// Where the font is loaded from, e.g. the path to the font file, if the
+    /// font is loaded by the compiler.
+    pub source: Option<DataSource>,
+}
+
+/// Lists the fonts of a resolver, sorted by their family names.
+///
+/// The fonts are listed by the metadata in the font book without being
+/// loaded, so the sources are only known for the fonts already loaded.
+pub fn list_fonts(resolver: &SharedFontResolver) -> Vec<FontFamily> {
+    let sources = resolver.inner.loaded_fonts();
+    let sources = sources.map(|(idx, font)| (idx, resolver.inner.describe_font(&font)));
+    let sources = sources.collect::<HashMap<_, _>>();
+
+    let book = resolver.font_book();
+    let mut families = BTreeMap::<_, Vec<_>>::new();
+    for (idx, info) in (0..).map_while(|idx| Some((idx, book.info(idx)?))) {
+        let source = sources.get(&idx).cloned().flatten();
+        families
+            .entry(info.family.clone())
+            .or_default()
+            .push(FontVariantInfo {
+                style: info.variant.style,
+                weight: info.variant.weight,
+                stretch: info.variant.stretch,
+                source: source.map(|source| source.as_ref().clone()),
+            });
+    }
+
+    let families = families.into_iter();
+    let families = families.map(|(name, variants)| FontFamily { name, variants });
+    families.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompileFontOpts;
+
+    #[test]
+    fn test_list_fonts() {
+        let opts = CompileFontOpts {
+            no_system_fonts: true,
+            ..Default::default()
+        };
+        let resolver = SharedFontResolver::new(opts).unwrap();
+        let families = list_fonts(&resolver);
+        let family = families
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case("new computer modern"));
+        assert!(
+            family.is_some_and(|f| !f.variants.is_empty()),
+            "{families:?}"
+        );
+
+        let opts = CompileFontOpts {
+            no_system_fonts: true,
+            no_embedded_fonts: true,
+            ..Default::default()
+        };
+        let resolver = SharedFontResolver::new(opts).unwrap();
+        assert!(list_fonts(&resolver).is_empty());
+    }
+}
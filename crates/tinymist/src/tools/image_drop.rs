@@ -0,0 +1,183 @@
+//! Insertion of the images dropped into a document, which generates a figure
+//! referring to the image by its path relative to the document.
+//!
+//! Typst cannot read the files outside the project root, so an image out of
+//! the root is copied into the assets directory of the project first.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The default directory, relative to the project root, where the images out
+/// of the root are copied into.
+pub const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// The figure generated for a dropped image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedImage {
+    /// The markup of the figure to insert.
+    pub snippet: String,
+    /// The path of the image in the figure, relative to the document.
+    pub path: String,
+    /// Where the image is copied to, if it lies outside the project root.
+    pub copied_to: Option<PathBuf>,
+}
+
+/// Generates the figure for an image dropped into a document, copying the
+/// image into `assets_dir` under the `root` if it lies outside the root.
+///
+/// All the paths must be absolute.
+pub fn drop_image(
+    document: &Path,
+    image: &Path,
+    root: &Path,
+    assets_dir: &Path,
+) -> anyhow::Result<DroppedImage> {
+    let Some(doc_dir) = document.parent() else {
+        anyhow::bail!("the document {document:?} has no parent directory");
+    };
+    if !image.is_file() {
+        anyhow::bail!("the image {image:?} is not a file");
+    }
+
+    let (image, copied_to) = if image.starts_with(root) {
+        (image.to_owned(), None)
+    } else {
+        let mut comps = assets_dir.components();
+        if !comps.all(|comp| matches!(comp, Component::Normal(_) | Component::CurDir)) {
+            anyhow::bail!("the assets directory {assets_dir:?} lies outside the project root");
+        }
+        let dir = root.join(assets_dir);
+        std::fs::create_dir_all(&dir)?;
+        let dest = copy_destination(image, &dir)?;
+        if !dest.exists() {
+            std::fs::copy(image, &dest)?;
+        }
+        (dest.clone(), Some(dest))
+    };
+
+    let path = relative_path(doc_dir, &image);
+    let snippet = figure_snippet(&path);
+    Ok(DroppedImage {
+        snippet,
+        path,
+        copied_to,
+    })
+}
+
+/// Finds where to copy an image into a directory. A file of the same name is
+/// reused if it has the same content, otherwise a suffix is appended to the
+/// name of the copy.
+fn copy_destination(image: &Path, dir: &Path) -> anyhow::Result<PathBuf> {
+    let Some(name) = image.file_name() else {
+        anyhow::bail!("the image {image:?} has no file name");
+    };
+    let dest = dir.join(name);
+    if !dest.exists() || same_content(image, &dest)? {
+        return Ok(dest);
+    }
+
+    let stem = image.file_stem().unwrap_or(name).to_string_lossy();
+    let ext = image.extension().map(|ext| ext.to_string_lossy());
+    for idx in 1.. {
+        let name = match &ext {
+            Some(ext) => format!("{stem}-{idx}.{ext}"),
+            None => format!("{stem}-{idx}"),
+        };
+        let dest = dir.join(name);
+        if !dest.exists() || same_content(image, &dest)? {
+            return Ok(dest);
+        }
+    }
+    unreachable!()
+}
+
+fn same_content(a: &Path, b: &Path) -> anyhow::Result<bool> {
+    let (meta_a, meta_b) = (std::fs::metadata(a)?, std::fs::metadata(b)?);
+    if meta_a.len() != meta_b.len() {
+        return Ok(false);
+    }
+    Ok(std::fs::read(a)? == std::fs::read(b)?)
+}
+
+/// Gets the path of a file relative to a directory, joined by slashes as in
+/// the Typst paths.
+fn relative_path(dir: &Path, file: &Path) -> String {
+    let dir = dir.components().collect::<Vec<_>>();
+    let file = file.components().collect::<Vec<_>>();
+    let common = dir.iter().zip(&file).take_while(|(a, b)| a == b).count();
+
+    let ups = (common..dir.len()).map(|_| "..".to_owned());
+    let downs = file[common..].iter().map(|comp| match comp {
+        Component::Normal(name) => name.to_string_lossy().into_owned(),
+        comp => comp.as_os_str().to_string_lossy().into_owned(),
+    });
+    ups.chain(downs).collect::<Vec<_>>().join("/")
+}
+
+/// Generates the markup of a figure showing the image at a path.
+fn figure_snippet(path: &str) -> String {
+    let path = path.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("#figure(image(\"{path}\"), caption: [])")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_path() {
+        let rel = |dir: &str, file: &str| relative_path(Path::new(dir), Path::new(file));
+        assert_eq!(rel("/proj", "/proj/a.png"), "a.png");
+        assert_eq!(rel("/proj/ch1", "/proj/img/a.png"), "../img/a.png");
+        assert_eq!(rel("/proj/ch1/sec", "/proj/a.png"), "../../a.png");
+    }
+
+    #[test]
+    fn test_figure_snippet() {
+        assert_eq!(
+            figure_snippet("img/a \"b\".png"),
+            r#"#figure(image("img/a \"b\".png"), caption: [])"#
+        );
+    }
+
+    #[test]
+    fn test_drop_image() {
+        let tmp = std::env::temp_dir().join(format!("tinymist-drop-{}", std::process::id()));
+        let (root, outside) = (tmp.join("proj"), tmp.join("outside"));
+        std::fs::create_dir_all(root.join("ch1")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        let document = root.join("ch1/main.typ");
+        let assets = Path::new(DEFAULT_ASSETS_DIR);
+
+        // An image in the root is referred to in place.
+        std::fs::write(root.join("a.png"), b"a").unwrap();
+        let dropped = drop_image(&document, &root.join("a.png"), &root, assets).unwrap();
+        assert_eq!(
+            dropped.snippet,
+            r#"#figure(image("../a.png"), caption: [])"#
+        );
+        assert_eq!(dropped.copied_to, None);
+
+        // An image out of the root is copied, reusing an identical copy.
+        std::fs::write(outside.join("b.png"), b"b").unwrap();
+        for _ in 0..2 {
+            let dropped = drop_image(&document, &outside.join("b.png"), &root, assets).unwrap();
+            assert_eq!(dropped.path, "../assets/b.png");
+            assert_eq!(dropped.copied_to, Some(root.join("assets/b.png")));
+        }
+
+        // A different image of the same name gets a suffix.
+        let other = tmp.join("b.png");
+        std::fs::write(&other, b"other").unwrap();
+        let dropped = drop_image(&document, &other, &root, assets).unwrap();
+        assert_eq!(dropped.path, "../assets/b-1.png");
+        assert_eq!(
+            std::fs::read(root.join("assets/b-1.png")).unwrap(),
+            b"other"
+        );
+
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+}
@@ -0,0 +1,913 @@
+//! Best-effort lowering of Typst markup to HTML and Markdown.
+//!
+//! The compiled document only keeps the laid out frames, so the markup is
+//! lowered from the syntax trees of the sources instead. The code is not
+//! evaluated, except that the included files are followed and a few functions
+//! are recognized by their names, e.g. `image`, `link`, and `strong`. The
+//! content blocks passed to other functions are lowered as they are.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use typst::foundations::Bytes;
+use typst::syntax::{
+    ast::{self, AstNode},
+    FileId, Source, SyntaxKind, SyntaxNode,
+};
+use typst::World;
+
+/// The format of a markup export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MarkupFormat {
+    /// A standalone HTML page.
+    Html,
+    /// A CommonMark document, with the equations in dollars.
+    Markdown,
+}
+
+impl MarkupFormat {
+    /// The extension of the exported file.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// How the assets, e.g. the images, are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetMode {
+    /// Write the assets to a directory next to the exported file.
+    #[default]
+    External,
+    /// Embed the assets into the exported file as data URLs.
+    Embed,
+}
+
+/// Loads the files referenced by the exported markup.
+pub trait MarkupLoader {
+    /// Loads a source file, e.g. an included file.
+    fn load_source(&self, id: FileId) -> Option<Source>;
+    /// Loads a binary file, e.g. an image.
+    fn load_file(&self, id: FileId) -> Option<Bytes>;
+}
+
+impl<W: World + ?Sized> MarkupLoader for W {
+    fn load_source(&self, id: FileId) -> Option<Source> {
+        self.source(id).ok()
+    }
+
+    fn load_file(&self, id: FileId) -> Option<Bytes> {
+        self.file(id).ok()
+    }
+}
+
+/// A markup export, i.e. the exported text and the external assets.
+#[derive(Debug, Clone)]
+pub struct MarkupExport {
+    /// The text of the exported file.
+    pub text: String,
+    /// The external assets, by their paths relative to the exported file.
+    pub assets: Vec<(String, Bytes)>,
+}
+
+/// Exports a source and the files included by it as HTML or Markdown.
+///
+/// The external assets are placed in `asset_dir`, which is relative to the
+/// exported file.
+pub fn export_markup<L: MarkupLoader + ?Sized>(
+    loader: &L,
+    source: &Source,
+    format: MarkupFormat,
+    assets: AssetMode,
+    asset_dir: &str,
+) -> MarkupExport {
+    let mut lowerer = Lowerer {
+        loader,
+        assets,
+        asset_dir,
+        external: vec![],
+        asset_paths: HashMap::new(),
+        includes: vec![source.id()],
+    };
+    let blocks = lowerer.markup(source.id(), source.root());
+
+    let text = match format {
+        MarkupFormat::Html => render_html(&blocks),
+        MarkupFormat::Markdown => render_markdown(&blocks),
+    };
+    MarkupExport {
+        text,
+        assets: lowerer.external,
+    }
+}
+
+/// A block of lowered markup.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading {
+        level: usize,
+        label: Option<String>,
+        body: Vec<Inline>,
+    },
+    Paragraph(Vec<Inline>),
+    List {
+        ordered: bool,
+        items: Vec<Vec<Block>>,
+    },
+    Code {
+        lang: Option<String>,
+        text: String,
+    },
+    Math(String),
+}
+
+/// An inline piece of lowered markup.
+#[derive(Debug, Clone, PartialEq)]
+enum Inline {
+    Text(String),
+    Strong(Vec<Inline>),
+    Emph(Vec<Inline>),
+    Code(String),
+    Math(String),
+    Link { url: String, body: Vec<Inline> },
+    Image { src: String, alt: String },
+    LineBreak,
+}
+
+struct Lowerer<'a, L: ?Sized> {
+    loader: &'a L,
+    assets: AssetMode,
+    asset_dir: &'a str,
+    external: Vec<(String, Bytes)>,
+    asset_paths: HashMap<FileId, String>,
+    /// The files being lowered, which are not included again.
+    includes: Vec<FileId>,
+}
+
+impl<L: MarkupLoader + ?Sized> Lowerer<'_, L> {
+    /// Lowers markup into blocks.
+    fn markup(&mut self, id: FileId, markup: &SyntaxNode) -> Vec<Block> {
+        let mut blocks = vec![];
+        let mut para = vec![];
+        for child in markup.children() {
+            match child.kind() {
+                SyntaxKind::Parbreak => flush(&mut para, &mut blocks),
+                SyntaxKind::Heading => {
+                    flush(&mut para, &mut blocks);
+                    let Some(heading) = child.cast::<ast::Heading>() else {
+                        continue;
+                    };
+                    let body = heading.body();
+                    let label = body
+                        .to_untyped()
+                        .children()
+                        .find(|node| node.kind() == SyntaxKind::Label)
+                        .map(|label| label_name(label.text()));
+                    let mut inlines = vec![];
+                    self.inlines(id, body.to_untyped(), &mut inlines, &mut vec![]);
+                    blocks.push(Block::Heading {
+                        level: heading.depth().get(),
+                        label,
+                        body: trim_inlines(inlines),
+                    });
+                }
+                SyntaxKind::ListItem | SyntaxKind::EnumItem => {
+                    flush(&mut para, &mut blocks);
+                    let ordered = child.kind() == SyntaxKind::EnumItem;
+                    let body = match child.cast::<ast::ListItem>() {
+                        Some(item) => item.body(),
+                        None => match child.cast::<ast::EnumItem>() {
+                            Some(item) => item.body(),
+                            None => continue,
+                        },
+                    };
+                    let item = self.markup(id, body.to_untyped());
+                    match blocks.last_mut() {
+                        Some(Block::List { ordered: o, items }) if *o == ordered => {
+                            items.push(item);
+                        }
+                        _ => blocks.push(Block::List {
+                            ordered,
+                            items: vec![item],
+                        }),
+                    }
+                }
+                SyntaxKind::TermItem => {
+                    flush(&mut para, &mut blocks);
+                    let Some(item) = child.cast::<ast::TermItem>() else {
+                        continue;
+                    };
+                    let mut term = vec![];
+                    self.inlines(id, item.term().to_untyped(), &mut term, &mut vec![]);
+                    let mut inlines = vec![Inline::Strong(trim_inlines(term))];
+                    inlines.push(Inline::Text(": ".to_owned()));
+                    self.inlines(
+                        id,
+                        item.description().to_untyped(),
+                        &mut inlines,
+                        &mut vec![],
+                    );
+                    blocks.push(Block::Paragraph(trim_inlines(inlines)));
+                }
+                SyntaxKind::Raw if child.cast::<ast::Raw>().is_some_and(|raw| raw.block()) => {
+                    flush(&mut para, &mut blocks);
+                    let (lang, text) = raw_text(&child.clone().into_text());
+                    blocks.push(Block::Code { lang, text });
+                }
+                SyntaxKind::Equation
+                    if child.cast::<ast::Equation>().is_some_and(|eq| eq.block()) =>
+                {
+                    flush(&mut para, &mut blocks);
+                    blocks.push(Block::Math(math_text(&child.clone().into_text())));
+                }
+                _ => self.inline(id, child, &mut para, &mut blocks),
+            }
+        }
+        flush(&mut para, &mut blocks);
+        blocks
+    }
+
+    /// Lowers the children of markup into inlines, flushing the paragraph to
+    /// the blocks if a child is lowered into blocks.
+    fn inlines(
+        &mut self,
+        id: FileId,
+        markup: &SyntaxNode,
+        para: &mut Vec<Inline>,
+        blocks: &mut Vec<Block>,
+    ) {
+        for child in markup.children() {
+            self.inline(id, child, para, blocks);
+        }
+    }
+
+    /// Lowers an inline node.
+    fn inline(
+        &mut self,
+        id: FileId,
+        node: &SyntaxNode,
+        para: &mut Vec<Inline>,
+        blocks: &mut Vec<Block>,
+    ) {
+        let inline = match node.kind() {
+            SyntaxKind::Text | SyntaxKind::SmartQuote => Inline::Text(node.text().to_string()),
+            SyntaxKind::Space => Inline::Text(" ".to_owned()),
+            SyntaxKind::Escape => match node.cast::<ast::Escape>() {
+                Some(escape) => Inline::Text(escape.get().to_string()),
+                None => return,
+            },
+            SyntaxKind::Shorthand => match node.cast::<ast::Shorthand>() {
+                Some(shorthand) => Inline::Text(shorthand.get().to_string()),
+                None => return,
+            },
+            SyntaxKind::Linebreak => Inline::LineBreak,
+            SyntaxKind::Strong | SyntaxKind::Emph => {
+                let Some(body) = node.children().find(|n| n.kind() == SyntaxKind::Markup) else {
+                    return;
+                };
+                let mut inlines = vec![];
+                self.inlines(id, body, &mut inlines, blocks);
+                match node.kind() {
+                    SyntaxKind::Strong => Inline::Strong(inlines),
+                    _ => Inline::Emph(inlines),
+                }
+            }
+            SyntaxKind::Raw => Inline::Code(raw_text(&node.clone().into_text()).1),
+            SyntaxKind::Equation => Inline::Math(math_text(&node.clone().into_text())),
+            SyntaxKind::Link => Inline::Link {
+                url: node.text().to_string(),
+                body: vec![Inline::Text(node.text().to_string())],
+            },
+            SyntaxKind::Ref => {
+                let target = node
+                    .cast::<ast::Ref>()
+                    .map_or_else(String::new, |r| r.target().to_owned());
+                Inline::Link {
+                    url: format!("#{target}"),
+                    body: vec![Inline::Text(target)],
+                }
+            }
+            SyntaxKind::Markup => return self.inlines(id, node, para, blocks),
+            kind if kind.is_trivia() || kind == SyntaxKind::Label || kind == SyntaxKind::Hash => {
+                return;
+            }
+            _ => return self.code(id, node, para, blocks),
+        };
+        para.push(inline);
+    }
+
+    /// Lowers the content produced by an embedded expression.
+    fn code(
+        &mut self,
+        id: FileId,
+        node: &SyntaxNode,
+        para: &mut Vec<Inline>,
+        blocks: &mut Vec<Block>,
+    ) {
+        if let Some(content) = node.cast::<ast::ContentBlock>() {
+            let lowered = self.markup(id, content.body().to_untyped());
+            return splice(lowered, para, blocks);
+        }
+
+        if let Some(include) = node.cast::<ast::ModuleInclude>() {
+            let ast::Expr::Str(path) = include.source() else {
+                return;
+            };
+            let included = id.join(&path.get());
+            if self.includes.contains(&included) {
+                return;
+            }
+            let Some(source) = self.loader.load_source(included) else {
+                return;
+            };
+            self.includes.push(included);
+            let lowered = self.markup(included, source.root());
+            self.includes.pop();
+            flush(para, blocks);
+            blocks.extend(lowered);
+            return;
+        }
+
+        let Some(call) = node.cast::<ast::FuncCall>() else {
+            return;
+        };
+        let ast::Expr::Ident(callee) = call.callee() else {
+            return;
+        };
+
+        let mut pos = vec![];
+        let mut named = HashMap::new();
+        for arg in call.args().items() {
+            match arg {
+                ast::Arg::Pos(expr) => pos.push(expr),
+                ast::Arg::Named(arg) => {
+                    named.insert(arg.name().as_str().to_owned(), arg.expr());
+                }
+                ast::Arg::Spread(..) => {}
+            }
+        }
+        let str_arg = |expr: Option<&ast::Expr>| match expr {
+            Some(ast::Expr::Str(s)) => Some(s.get().to_string()),
+            _ => None,
+        };
+
+        match callee.as_str() {
+            "image" => {
+                let Some(src) = str_arg(pos.first()).and_then(|path| self.asset(id, &path)) else {
+                    return;
+                };
+                let alt = str_arg(named.get("alt")).unwrap_or_default();
+                para.push(Inline::Image { src, alt });
+            }
+            "link" => {
+                let Some(url) = str_arg(pos.first()) else {
+                    return;
+                };
+                let mut body = vec![];
+                for expr in pos.iter().skip(1) {
+                    self.code(id, expr.to_untyped(), &mut body, blocks);
+                }
+                if body.is_empty() {
+                    body.push(Inline::Text(url.clone()));
+                }
+                para.push(Inline::Link { url, body });
+            }
+            "strong" | "emph" => {
+                let mut body = vec![];
+                for expr in &pos {
+                    self.code(id, expr.to_untyped(), &mut body, blocks);
+                }
+                para.push(match callee.as_str() {
+                    "strong" => Inline::Strong(body),
+                    _ => Inline::Emph(body),
+                });
+            }
+            "raw" => {
+                if let Some(text) = str_arg(pos.first()) {
+                    para.push(Inline::Code(text));
+                }
+            }
+            "figure" => {
+                // The body and the caption are separate paragraphs, e.g. an image
+                // and the text below it.
+                flush(para, blocks);
+                let body = pos.first().or(named.get("body"));
+                for expr in body.into_iter().chain(named.get("caption")) {
+                    let mut inlines = vec![];
+                    self.code(id, expr.to_untyped(), &mut inlines, blocks);
+                    flush(&mut inlines, blocks);
+                }
+            }
+            _ => {
+                // The content is lowered as it is, e.g. the body of a block.
+                let args = pos.iter().chain(named.get("body"));
+                for expr in args {
+                    self.code(id, expr.to_untyped(), para, blocks);
+                }
+            }
+        }
+    }
+
+    /// Gets the URL of an asset referenced by a file, exporting it if it is
+    /// external.
+    fn asset(&mut self, id: FileId, path: &str) -> Option<String> {
+        let asset = id.join(path);
+        if let Some(path) = self.asset_paths.get(&asset) {
+            return Some(path.clone());
+        }
+
+        let data = self.loader.load_file(asset)?;
+        let name = asset.vpath().as_rootless_path().file_name()?;
+        let name = name.to_string_lossy();
+        let url = match self.assets {
+            AssetMode::Embed => {
+                let data = base64::engine::general_purpose::STANDARD.encode(data.as_slice());
+                format!("data:{};base64,{data}", mime_type(&name))
+            }
+            AssetMode::External => {
+                // The assets of the same name are numbered.
+                let mut path = format!("{}/{name}", self.asset_dir);
+                let mut n = 1;
+                while self.external.iter().any(|(p, _)| *p == path) {
+                    path = format!("{}/{n}-{name}", self.asset_dir);
+                    n += 1;
+                }
+                self.external.push((path.clone(), data));
+                path
+            }
+        };
+        self.asset_paths.insert(asset, url.clone());
+        Some(url)
+    }
+}
+
+/// Ends the paragraph.
+fn flush(para: &mut Vec<Inline>, blocks: &mut Vec<Block>) {
+    let inlines = trim_inlines(std::mem::take(para));
+    if !inlines.is_empty() {
+        blocks.push(Block::Paragraph(inlines));
+    }
+}
+
+/// Adds lowered blocks, which are merged into the paragraph if they are a
+/// single paragraph.
+fn splice(mut lowered: Vec<Block>, para: &mut Vec<Inline>, blocks: &mut Vec<Block>) {
+    if let [Block::Paragraph(inlines)] = lowered.as_mut_slice() {
+        para.append(inlines);
+        return;
+    }
+    flush(para, blocks);
+    blocks.extend(lowered);
+}
+
+/// Removes the spaces around inlines, and merges the adjacent texts.
+fn trim_inlines(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut res: Vec<Inline> = vec![];
+    for inline in inlines {
+        if let (Some(Inline::Text(last)), Inline::Text(text)) = (res.last_mut(), &inline) {
+            // The spaces are collapsed as in Typst.
+            if !(last.ends_with(' ') && text == " ") {
+                last.push_str(text);
+            }
+            continue;
+        }
+        res.push(inline);
+    }
+
+    if let Some(Inline::Text(text)) = res.first_mut() {
+        *text = text.trim_start().to_owned();
+    }
+    if let Some(Inline::Text(text)) = res.last_mut() {
+        *text = text.trim_end().to_owned();
+    }
+    res.retain(|inline| !matches!(inline, Inline::Text(text) if text.is_empty()));
+    res
+}
+
+/// Gets the name of a label from its text, e.g. `intro` from `<intro>`.
+fn label_name(text: &str) -> String {
+    text.trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_owned()
+}
+
+/// Gets the language and the text of raw markup.
+fn raw_text(raw: &str) -> (Option<String>, String) {
+    let ticks = raw.len() - raw.trim_start_matches('`').len();
+    let inner = &raw[ticks..raw.len() - ticks.min(raw.len() - ticks)];
+    if ticks < 3 {
+        return (None, inner.to_owned());
+    }
+
+    // The language tag directly follows the opening backticks.
+    let lang_len = inner
+        .find(|c: char| c.is_whitespace() || c == '`')
+        .unwrap_or(inner.len());
+    let lang = (lang_len > 0).then(|| inner[..lang_len].to_owned());
+    let text = &inner[lang_len..];
+    let text = text
+        .strip_prefix('\n')
+        .unwrap_or(text.trim_start_matches(' '));
+    (lang, text.trim_end().to_owned())
+}
+
+/// Gets the text of an equation without the dollars.
+fn math_text(equation: &str) -> String {
+    let inner = equation.strip_prefix('$').unwrap_or(equation);
+    let inner = inner.strip_suffix('$').unwrap_or(inner);
+    inner.trim().to_owned()
+}
+
+/// Guesses the MIME type of an asset from its extension.
+fn mime_type(name: &str) -> &'static str {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+fn render_html(blocks: &[Block]) -> String {
+    let title = blocks.iter().find_map(|block| match block {
+        Block::Heading { body, .. } => Some(plain_text(body)),
+        _ => None,
+    });
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    if let Some(title) = title {
+        let _ = writeln!(out, "<title>{}</title>", escape_html(&title));
+    }
+    out.push_str("</head>\n<body>\n");
+    html_blocks(blocks, &mut out);
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
+        match block {
+            Block::Heading { level, label, body } => {
+                let level = (*level).min(6);
+                let id = match label {
+                    Some(label) => format!(" id=\"{}\"", escape_html(label)),
+                    None => String::new(),
+                };
+                let _ = writeln!(out, "<h{level}{id}>{}</h{level}>", html_inlines(body));
+            }
+            Block::Paragraph(inlines) => {
+                let _ = writeln!(out, "<p>{}</p>", html_inlines(inlines));
+            }
+            Block::List { ordered, items } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+                let _ = writeln!(out, "<{tag}>");
+                for item in items {
+                    match item.as_slice() {
+                        [Block::Paragraph(inlines)] => {
+                            let _ = writeln!(out, "<li>{}</li>", html_inlines(inlines));
+                        }
+                        _ => {
+                            out.push_str("<li>\n");
+                            html_blocks(item, out);
+                            out.push_str("</li>\n");
+                        }
+                    }
+                }
+                let _ = writeln!(out, "</{tag}>");
+            }
+            Block::Code { lang, text } => {
+                let class = match lang {
+                    Some(lang) => format!(" class=\"language-{}\"", escape_html(lang)),
+                    None => String::new(),
+                };
+                let _ = writeln!(out, "<pre><code{class}>{}</code></pre>", escape_html(text));
+            }
+            Block::Math(text) => {
+                let _ = writeln!(out, "<div class=\"math\">{}</div>", escape_html(text));
+            }
+        }
+    }
+}
+
+fn html_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape_html(text)),
+            Inline::Strong(body) => {
+                let _ = write!(out, "<strong>{}</strong>", html_inlines(body));
+            }
+            Inline::Emph(body) => {
+                let _ = write!(out, "<em>{}</em>", html_inlines(body));
+            }
+            Inline::Code(text) => {
+                let _ = write!(out, "<code>{}</code>", escape_html(text));
+            }
+            Inline::Math(text) => {
+                let _ = write!(out, "<span class=\"math\">{}</span>", escape_html(text));
+            }
+            Inline::Link { url, body } => {
+                let url = escape_html(url);
+                let _ = write!(out, "<a href=\"{url}\">{}</a>", html_inlines(body));
+            }
+            Inline::Image { src, alt } => {
+                let (src, alt) = (escape_html(src), escape_html(alt));
+                let _ = write!(out, "<img src=\"{src}\" alt=\"{alt}\">");
+            }
+            Inline::LineBreak => out.push_str("<br>\n"),
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut out = markdown_blocks(blocks).join("\n\n");
+    out.push('\n');
+    out
+}
+
+fn markdown_blocks(blocks: &[Block]) -> Vec<String> {
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading { level, body, .. } => {
+                format!("{} {}", "#".repeat((*level).min(6)), markdown_inlines(body))
+            }
+            Block::Paragraph(inlines) => markdown_inlines(inlines),
+            Block::List { ordered, items } => {
+                let mut lines = vec![];
+                for (idx, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}. ", idx + 1)
+                    } else {
+                        "- ".to_owned()
+                    };
+                    let indent = " ".repeat(marker.len());
+                    // The nested lists directly follow the text of the item.
+                    let mut body = String::new();
+                    for (block, text) in item.iter().zip(markdown_blocks(item)) {
+                        if !body.is_empty() {
+                            let tight = matches!(block, Block::List { .. });
+                            body.push_str(if tight { "\n" } else { "\n\n" });
+                        }
+                        body.push_str(&text);
+                    }
+                    let mut body_lines = body.lines();
+                    lines.push(format!("{marker}{}", body_lines.next().unwrap_or_default()));
+                    for line in body_lines {
+                        if line.is_empty() {
+                            lines.push(String::new());
+                        } else {
+                            lines.push(format!("{indent}{line}"));
+                        }
+                    }
+                }
+                lines.join("\n")
+            }
+            Block::Code { lang, text } => {
+                let fence = "`".repeat(longest_run(text, '`').max(2) + 1);
+                let lang = lang.as_deref().unwrap_or_default();
+                format!("{fence}{lang}\n{text}\n{fence}")
+            }
+            Block::Math(text) => format!("$$\n{text}\n$$"),
+        })
+        .collect()
+}
+
+fn markdown_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape_markdown(text)),
+            Inline::Strong(body) => {
+                let _ = write!(out, "**{}**", markdown_inlines(body));
+            }
+            Inline::Emph(body) => {
+                let _ = write!(out, "*{}*", markdown_inlines(body));
+            }
+            Inline::Code(text) => {
+                let fence = "`".repeat(longest_run(text, '`') + 1);
+                let pad = if text.starts_with('`') || text.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+                let _ = write!(out, "{fence}{pad}{text}{pad}{fence}");
+            }
+            Inline::Math(text) => {
+                let _ = write!(out, "${text}$");
+            }
+            Inline::Link { url, body } => match body.as_slice() {
+                [Inline::Text(text)] if text == url && !url.contains([' ', '<', '>']) => {
+                    let _ = write!(out, "<{url}>");
+                }
+                _ => {
+                    let url = escape_markdown_url(url);
+                    let _ = write!(out, "[{}]({url})", markdown_inlines(body));
+                }
+            },
+            Inline::Image { src, alt } => {
+                let src = escape_markdown_url(src);
+                let _ = write!(out, "![{}]({src})", escape_markdown(alt));
+            }
+            Inline::LineBreak => out.push_str("\\\n"),
+        }
+    }
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '$' | '#'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escapes a URL in the parentheses of a link or an image, where the spaces and
+/// the parentheses would end the destination.
+fn escape_markdown_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' => out.push_str("%20"),
+            '(' => out.push_str("%28"),
+            ')' => out.push_str("%29"),
+            '<' => out.push_str("%3C"),
+            '>' => out.push_str("%3E"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Gets the length of the longest run of a character in a text.
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in text.chars() {
+        run = if ch == c { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+fn plain_text(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) | Inline::Code(text) | Inline::Math(text) => out.push_str(text),
+            Inline::Strong(body) | Inline::Emph(body) | Inline::Link { body, .. } => {
+                out.push_str(&plain_text(body))
+            }
+            Inline::Image { alt, .. } => out.push_str(alt),
+            Inline::LineBreak => out.push(' '),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::VirtualPath;
+
+    use super::*;
+
+    struct TestLoader(Vec<Source>);
+
+    impl MarkupLoader for TestLoader {
+        fn load_source(&self, id: FileId) -> Option<Source> {
+            self.0.iter().find(|source| source.id() == id).cloned()
+        }
+
+        fn load_file(&self, id: FileId) -> Option<Bytes> {
+            let path = id.vpath().as_rootless_path();
+            (path.extension()? == "png").then(|| Bytes::from(b"png".to_vec()))
+        }
+    }
+
+    fn export(text: &str, format: MarkupFormat, assets: AssetMode) -> MarkupExport {
+        let id = |path: &str| FileId::new(None, VirtualPath::new(path));
+        let main = Source::new(id("/main.typ"), text.to_owned());
+        let chapter = Source::new(id("/chapter.typ"), "== Chapter\nIncluded.".to_owned());
+        let loader = TestLoader(vec![main.clone(), chapter]);
+        export_markup(&loader, &main, format, assets, "main_assets")
+    }
+
+    const DOC: &str = r#"= Intro <intro>
+Some *strong* and _emph_ text with `code`, $x^2$, and https://typst.app.
+See @intro.
+
+- one
+- two
+  + nested
+
+```rust
+fn main() {}
+```
+
+#figure(image("cat.png", alt: "A cat"), caption: [A *cat*.])
+#let x = 1
+#include "chapter.typ"
+"#;
+
+    #[test]
+    fn test_export_markdown() {
+        let res = export(DOC, MarkupFormat::Markdown, AssetMode::External);
+        assert_eq!(
+            res.text,
+            r#"# Intro
+
+Some **strong** and *emph* text with `code`, $x^2$, and <https://typst.app>. See [intro](#intro).
+
+- one
+- two
+  1. nested
+
+```rust
+fn main() {}
+```
+
+![A cat](main_assets/cat.png)
+
+A **cat**.
+
+## Chapter
+
+Included.
+"#
+        );
+        let assets = res.assets.iter().map(|(path, _)| path.as_str());
+        assert_eq!(assets.collect::<Vec<_>>(), vec!["main_assets/cat.png"]);
+    }
+
+    #[test]
+    fn test_export_html() {
+        let res = export(
+            "= A & B <ab>\n#image(\"cat.png\")",
+            MarkupFormat::Html,
+            AssetMode::Embed,
+        );
+        assert!(res.text.contains("<title>A &amp; B</title>"));
+        assert!(res.text.contains("<h1 id=\"ab\">A &amp; B</h1>"));
+        assert!(res
+            .text
+            .contains("<p><img src=\"data:image/png;base64,cG5n\" alt=\"\"></p>"));
+        assert!(res.assets.is_empty());
+    }
+
+    #[test]
+    fn test_export_markdown_urls() {
+        let res = export(
+            "#image(\"my cat (1).png\")\n#link(\"https://example.com/a b\")",
+            MarkupFormat::Markdown,
+            AssetMode::External,
+        );
+        assert_eq!(
+            res.text,
+            "![](main_assets/my%20cat%20%281%29.png) [https://example.com/a b](https://example.com/a%20b)\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_text() {
+        assert_eq!(raw_text("`a b`"), (None, "a b".to_owned()));
+        assert_eq!(
+            raw_text("```typ\n#let a = 1\n```"),
+            (Some("typ".to_owned()), "#let a = 1".to_owned())
+        );
+        assert_eq!(raw_text("``` a ```"), (None, "a".to_owned()));
+    }
+}
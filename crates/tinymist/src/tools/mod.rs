@@ -1,3 +1,9 @@
+pub mod fonts;
+pub mod image_drop;
+pub mod markup_export;
 pub mod package;
+pub mod paste;
 pub mod preview;
+pub mod project;
+pub mod stats;
 pub mod word_count;
@@ -0,0 +1,232 @@
+//! Generation of the API reference of a package from the symbols exported by
+//! its entry, as Markdown or HTML.
+
+use std::fmt::Write;
+
+use tinymist_query::analysis::{module_docs, DocSymbolKind, SymbolDocs};
+use tinymist_query::AnalysisContext;
+use typst::diag::StrResult;
+use typst::syntax::package::PackageManifest;
+use typst::syntax::{Source, VirtualPath};
+use typst::World;
+use typst_ts_core::TypstFileId;
+
+use crate::tools::markup_export::{escape_html, MarkupFormat};
+
+/// Generates the API reference of the module of a package entry.
+///
+/// The title and the description are read from the `typst.toml` at the root,
+/// falling back to the name of the entry.
+pub fn package_docs(
+    ctx: &mut AnalysisContext,
+    source: &Source,
+    format: MarkupFormat,
+) -> StrResult<String> {
+    let manifest = read_manifest(ctx.world());
+    let title = match &manifest {
+        Some(manifest) => format!("{}:{}", manifest.package.name, manifest.package.version),
+        None => {
+            let path = source.id().vpath().as_rootless_path();
+            let stem = path.file_stem().unwrap_or_default();
+            stem.to_string_lossy().into_owned()
+        }
+    };
+    let description = manifest.and_then(|manifest| manifest.package.description);
+
+    let symbols = module_docs(ctx, source)?;
+    Ok(render_docs(
+        &title,
+        description.as_deref(),
+        &symbols,
+        format,
+    ))
+}
+
+/// Reads the manifest of the package at the root.
+fn read_manifest(world: &dyn World) -> Option<PackageManifest> {
+    let id = TypstFileId::new(None, VirtualPath::new("typst.toml"));
+    let bytes = world.file(id).ok()?;
+    toml::from_str(std::str::from_utf8(&bytes).ok()?).ok()
+}
+
+/// Renders the documentation of the symbols as an API reference.
+pub fn render_docs(
+    title: &str,
+    description: Option<&str>,
+    symbols: &[SymbolDocs],
+    format: MarkupFormat,
+) -> String {
+    match format {
+        MarkupFormat::Markdown => render_markdown(title, description, symbols),
+        MarkupFormat::Html => render_html(title, description, symbols),
+    }
+}
+
+fn render_markdown(title: &str, description: Option<&str>, symbols: &[SymbolDocs]) -> String {
+    let mut out = format!("# {title}\n");
+    if let Some(description) = description {
+        let _ = write!(out, "\n{description}\n");
+    }
+    for symbol in symbols {
+        let _ = write!(out, "\n## `{}`\n", symbol.name);
+        if let Some(signature) = declaration(symbol) {
+            let _ = write!(out, "\n```typc\n{signature}\n```\n");
+        }
+        if let Some(docs) = &symbol.docs {
+            let _ = write!(out, "\n{}\n", display_docs(docs).trim());
+        }
+    }
+    out
+}
+
+fn render_html(title: &str, description: Option<&str>, symbols: &[SymbolDocs]) -> String {
+    let title = escape_html(title);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{title}</title>");
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{title}</h1>");
+    if let Some(description) = description {
+        let _ = writeln!(out, "<p>{}</p>", escape_html(description));
+    }
+    for symbol in symbols {
+        let name = escape_html(&symbol.name);
+        let _ = writeln!(out, "<h2 id=\"{name}\"><code>{name}</code></h2>");
+        if let Some(signature) = declaration(symbol) {
+            let signature = escape_html(&signature);
+            let _ = writeln!(
+                out,
+                "<pre><code class=\"language-typc\">{signature}</code></pre>"
+            );
+        }
+        if let Some(docs) = &symbol.docs {
+            html_docs(&display_docs(docs), &mut out);
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Gets the declaration of a symbol shown in the reference, the same as the
+/// one shown in the hover.
+fn declaration(symbol: &SymbolDocs) -> Option<String> {
+    match symbol.kind {
+        DocSymbolKind::Module => None,
+        _ => Some(format!("let {};", symbol.signature.as_ref()?)),
+    }
+}
+
+/// Rewrites the examples of the docs as the code shown in them, i.e. the lines
+/// starting with `>>>` are only compiled and dropped, and the lines starting
+/// with `<<<` are only shown and kept.
+fn display_docs(docs: &str) -> String {
+    let mut out = String::new();
+    let mut in_example = false;
+    for line in docs.lines() {
+        let trimmed = line.trim_start();
+        if in_example {
+            if trimmed.starts_with(">>>") {
+                continue;
+            }
+            if trimmed.starts_with("```") {
+                in_example = false;
+            }
+        } else if trimmed == "```example" {
+            in_example = true;
+            out.push_str("```typ\n");
+            continue;
+        }
+        let line = match trimmed.strip_prefix("<<<") {
+            Some(line) if in_example => line.strip_prefix(' ').unwrap_or(line),
+            _ => line,
+        };
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders the paragraphs and the fenced code blocks of Markdown docs as HTML.
+/// The other syntax is kept as text.
+fn html_docs(docs: &str, out: &mut String) {
+    let mut para = vec![];
+    let mut code: Option<(String, Vec<&str>)> = None;
+    let flush = |para: &mut Vec<&str>, out: &mut String| {
+        if !para.is_empty() {
+            let _ = writeln!(out, "<p>{}</p>", escape_html(&para.join("\n")));
+            para.clear();
+        }
+    };
+    for line in docs.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((lang, lines)), Some(_)) => {
+                let class = match lang.as_str() {
+                    "" => String::new(),
+                    lang => format!(" class=\"language-{}\"", escape_html(lang)),
+                };
+                let text = escape_html(&lines.join("\n"));
+                let _ = writeln!(out, "<pre><code{class}>{text}</code></pre>");
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(lang)) => {
+                flush(&mut para, out);
+                code = Some((lang.trim().to_owned(), vec![]));
+            }
+            (None, None) if line.trim().is_empty() => flush(&mut para, out),
+            (None, None) => para.push(line),
+        }
+    }
+    flush(&mut para, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols() -> Vec<SymbolDocs> {
+        let docs = "Adds numbers.\n\n```example\n>>> #set text(red)\n<<< #add(1, 2)\n```";
+        vec![
+            SymbolDocs {
+                name: "add".to_owned(),
+                kind: DocSymbolKind::Function,
+                signature: Some("add(x, y: 1)".to_owned()),
+                docs: Some(docs.to_owned()),
+            },
+            SymbolDocs {
+                name: "utils".to_owned(),
+                kind: DocSymbolKind::Module,
+                signature: None,
+                docs: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let res = render_docs(
+            "pkg:0.1.0",
+            Some("A package."),
+            &symbols(),
+            MarkupFormat::Markdown,
+        );
+        assert_eq!(
+            res,
+            "# pkg:0.1.0\n\nA package.\n\n\
+             ## `add`\n\n```typc\nlet add(x, y: 1);\n```\n\n\
+             Adds numbers.\n\n```typ\n#add(1, 2)\n```\n\n\
+             ## `utils`\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let res = render_docs("a & b", None, &symbols(), MarkupFormat::Html);
+        assert!(res.contains("<title>a &amp; b</title>"));
+        assert!(res.contains("<h2 id=\"add\"><code>add</code></h2>"));
+        assert!(res.contains("<pre><code class=\"language-typc\">let add(x, y: 1);</code></pre>"));
+        assert!(res.contains("<p>Adds numbers.</p>"));
+        assert!(res.contains("<pre><code class=\"language-typ\">#add(1, 2)</code></pre>"));
+    }
+}
@@ -0,0 +1,49 @@
+//! The package registry index, which is downloaded in the background so that
+//! the compiler is never blocked by the network.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use typst::syntax::package::PackageSpec;
+use typst_ts_compiler::package::{http::HttpRegistry, Registry};
+use typst_ts_core::typst::prelude::EcoVec;
+
+enum IndexState {
+    Unfetched,
+    Fetching,
+    Fetched(EcoVec<PackageSpec>),
+}
+
+/// The index shared by the compilers, which is fetched once per server.
+static INDEX: Lazy<Mutex<IndexState>> = Lazy::new(|| Mutex::new(IndexState::Unfetched));
+/// The revision of the index, which is bumped when the index is fetched.
+static REVISION: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the packages in the registry index if it is fetched. Otherwise, the
+/// index starts to be fetched in the background, and no package is returned
+/// until then.
+pub fn registry_packages() -> EcoVec<PackageSpec> {
+    let mut index = INDEX.lock();
+    match &*index {
+        IndexState::Fetched(packages) => return packages.clone(),
+        IndexState::Fetching => return EcoVec::new(),
+        IndexState::Unfetched => {}
+    }
+
+    *index = IndexState::Fetching;
+    std::thread::spawn(|| {
+        let registry = HttpRegistry::default();
+        let packages = registry.packages().iter().map(|(spec, _)| spec.clone());
+        *INDEX.lock() = IndexState::Fetched(packages.collect());
+        REVISION.fetch_add(1, Ordering::SeqCst);
+        log::info!("fetched the package registry index");
+    });
+    EcoVec::new()
+}
+
+/// Gets the revision of the registry index, which is changed once the index is
+/// fetched, e.g. to check the packages again.
+pub fn registry_revision() -> usize {
+    REVISION.load(Ordering::SeqCst)
+}
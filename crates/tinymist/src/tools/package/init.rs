@@ -41,7 +41,8 @@ pub fn get_entry(world: &LspWorld, tmpl: TemplateSource) -> StrResult<Bytes> {
     world.file(entry_point).map_err(|e| eco_format!("{e}"))
 }
 
-/// Execute an initialization command.
+/// Execute an initialization command, downloading the template package if
+/// needed, and returning the path of the created entry file.
 pub fn init(world: &LspWorld, task: InitTask) -> StrResult<PathBuf> {
     let TemplateSource::Package(spec) = task.tmpl;
     let project_dir = task
@@ -59,15 +60,19 @@ pub fn init(world: &LspWorld, task: InitTask) -> StrResult<PathBuf> {
         bail!("package {spec} is not a template");
     };
 
-    let entry_point = Path::new(template.entrypoint.as_str()).to_owned();
-
-    // Determine the directory at which we will create the project.
-    // let project_dir =
-    // Path::new(command.dir.as_deref().unwrap_or(&manifest.package.name));
-
     // Set up the project.
     scaffold_project(world, template, toml_id, &project_dir)?;
 
+    // The entrypoint of the template is relative to the template directory,
+    // which is the project directory after scaffolding.
+    let entry_point = project_dir.join(template.entrypoint.as_str());
+    if !entry_point.is_file() {
+        bail!(
+            "template entrypoint {} does not exist in package {spec}",
+            template.entrypoint
+        );
+    }
+
     Ok(entry_point)
 }
 
@@ -4,6 +4,10 @@ use typst_ts_compiler::package::Registry;
 
 use crate::world::LspWorld;
 
+mod docs;
+pub use docs::*;
+mod index;
+pub use index::*;
 mod init;
 pub use init::*;
 
@@ -0,0 +1,627 @@
+//! Conversion of pasted text to Typst markup, i.e. Markdown, LaTeX math, and
+//! CSV are detected and converted to the equivalent markup.
+//!
+//! The conversion is best-effort: the common syntax is converted and the
+//! rest is kept as text, with the characters special in markup escaped.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+/// The format of pasted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PasteFormat {
+    /// Markdown, e.g. headings, lists, links, and pipe tables.
+    Markdown,
+    /// LaTeX math, e.g. `$\frac{1}{2}$`.
+    Latex,
+    /// Comma-separated or tab-separated values.
+    Csv,
+}
+
+/// The pasted text converted to Typst markup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertedPaste {
+    /// The detected format of the pasted text.
+    pub format: PasteFormat,
+    /// The converted markup.
+    pub text: String,
+}
+
+/// Converts pasted text to Typst markup, detecting its format if it is not
+/// given. Returns `None` if the text is in none of the formats.
+pub fn convert_paste(text: &str, format: Option<PasteFormat>) -> Option<ConvertedPaste> {
+    let format = format.or_else(|| detect_format(text))?;
+    let converted = match format {
+        PasteFormat::Markdown => convert_markdown(text),
+        PasteFormat::Latex => convert_latex(text),
+        PasteFormat::Csv => convert_csv(text)?,
+    };
+    Some(ConvertedPaste {
+        format,
+        text: converted,
+    })
+}
+
+/// Detects the format of pasted text.
+pub fn detect_format(text: &str) -> Option<PasteFormat> {
+    let trimmed = text.trim();
+    if ["$", "\\[", "\\(", "\\begin{"]
+        .iter()
+        .any(|delim| trimmed.starts_with(delim))
+    {
+        return Some(PasteFormat::Latex);
+    }
+    // The Markdown lists and tables may contain commas, so that Markdown is
+    // checked first.
+    if text.lines().any(is_markdown_line) {
+        return Some(PasteFormat::Markdown);
+    }
+    csv_rows(text)
+        .filter(|rows| is_tabular(text, rows))
+        .map(|_| PasteFormat::Csv)
+}
+
+/// Checks whether the rows split by commas look like a table rather than
+/// prose, i.e. no field is a clause ending with a punctuation. The rows split
+/// by tabs are always tables.
+fn is_tabular(text: &str, rows: &[Vec<String>]) -> bool {
+    if text.lines().next().is_some_and(|line| line.contains('\t')) {
+        return true;
+    }
+    rows.iter().flatten().all(|field| {
+        let numeric = field.parse::<f64>().is_ok();
+        numeric || !field.ends_with(['.', '!', '?', ':', ';'])
+    })
+}
+
+fn is_markdown_line(line: &str) -> bool {
+    let line = line.trim_start();
+    let heading = line.trim_start_matches('#');
+    (heading.len() < line.len() && line.len() - heading.len() <= 6 && heading.starts_with(' '))
+        || ["- ", "* ", "+ ", "> ", "```", "|"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        || ordered_item(line).is_some()
+        || line.contains("](")
+        || line.contains("**")
+}
+
+/// Gets the text of an ordered list item, e.g. `item` of `1. item`.
+fn ordered_item(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == line.len() {
+        return None;
+    }
+    rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "))
+}
+
+/// Escapes the characters starting markup syntax in text, including the
+/// slashes starting comments, except those of the URLs, e.g. `https://`.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut prev = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let special = matches!(
+            c,
+            '#' | '$' | '@' | '<' | '\\' | '*' | '_' | '`' | '[' | ']' | '~'
+        );
+        let comment = c == '/' && matches!(chars.peek(), Some('/' | '*')) && prev != Some(':');
+        if special || comment {
+            out.push('\\');
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+    out
+}
+
+fn convert_markdown(text: &str) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut out = vec![];
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        // The fenced code blocks are raw blocks of the same syntax.
+        if trimmed.starts_with("```") {
+            out.push(line.to_owned());
+            idx += 1;
+            while idx < lines.len() {
+                out.push(lines[idx].to_owned());
+                idx += 1;
+                if lines[idx - 1].trim_start().starts_with("```") {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if let Some((table, rows)) = pipe_table(&lines[idx..]) {
+            out.push(table);
+            idx += rows;
+            continue;
+        }
+
+        let heading = trimmed.trim_start_matches('#');
+        let depth = trimmed.len() - heading.len();
+        let converted = if (1..=6).contains(&depth) && heading.starts_with(' ') {
+            format!("{} {}", "=".repeat(depth), markdown_inline(heading.trim()))
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            format!("{indent}- {}", markdown_inline(item))
+        } else if let Some(item) = ordered_item(trimmed) {
+            format!("{indent}+ {}", markdown_inline(item))
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            format!("#quote(block: true)[{}]", markdown_inline(quote.trim()))
+        } else if trimmed.len() >= 3
+            && ["-", "*", "_"]
+                .iter()
+                .any(|c| trimmed.replace(c, "").is_empty())
+        {
+            "#line(length: 100%)".to_owned()
+        } else {
+            format!("{indent}{}", markdown_inline(trimmed))
+        };
+        out.push(converted);
+        idx += 1;
+    }
+    out.join("\n")
+}
+
+/// Converts the inline syntax of Markdown, i.e. the emphasis, the code, the
+/// links, and the images.
+fn markdown_inline(text: &str) -> String {
+    let mut out = String::new();
+    // The plain text is escaped as a whole, e.g. to escape `//` but `://`.
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if !plain.is_empty() && is_inline_start(rest) {
+            out.push_str(&escape(&std::mem::take(&mut plain)));
+        }
+        // The code spans are raw text of the same syntax.
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                out.push_str(&rest[..end + 2]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        if let Some((alt, url, len)) = rest.strip_prefix('!').and_then(markdown_link) {
+            let _ = write!(out, "#image({url:?}, alt: {alt:?})");
+            rest = &rest[len + 1..];
+            continue;
+        }
+        if let Some((label, url, len)) = markdown_link(rest) {
+            let _ = write!(out, "#link({url:?})[{}]", markdown_inline(label));
+            rest = &rest[len..];
+            continue;
+        }
+        if let Some((inner, len)) = ["**", "__"].iter().find_map(|delim| delimited(rest, delim)) {
+            let _ = write!(out, "*{}*", markdown_inline(inner));
+            rest = &rest[len..];
+            continue;
+        }
+        if let Some((inner, len)) = ["*", "_"].iter().find_map(|delim| delimited(rest, delim)) {
+            let _ = write!(out, "_{}_", markdown_inline(inner));
+            rest = &rest[len..];
+            continue;
+        }
+
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out.push_str(&escape(&plain));
+    out
+}
+
+/// Checks whether the text starts with a character which may start inline
+/// syntax of Markdown.
+fn is_inline_start(text: &str) -> bool {
+    text.starts_with(['`', '!', '[', '*', '_'])
+}
+
+/// Parses a link, e.g. `[label](url)`, returning the label, the URL, and the
+/// length of the link.
+fn markdown_link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.strip_prefix('[')?.find("](")? + 1;
+    let url_end = text[label_end + 2..].find(')')? + label_end + 2;
+    Some((
+        &text[1..label_end],
+        &text[label_end + 2..url_end],
+        url_end + 1,
+    ))
+}
+
+/// Parses a span enclosed by a delimiter, returning the inner text and the
+/// length of the span. The inner text must not be empty or start with a
+/// space.
+fn delimited<'a>(text: &'a str, delim: &str) -> Option<(&'a str, usize)> {
+    let rest = text.strip_prefix(delim)?;
+    let end = rest.find(delim)?;
+    let inner = &rest[..end];
+    if inner.is_empty() || inner.starts_with(' ') || inner.ends_with(' ') {
+        return None;
+    }
+    Some((inner, end + 2 * delim.len()))
+}
+
+/// Converts a pipe table at the start of the lines, returning the table and
+/// the number of the consumed lines. The table must have a delimiter row.
+fn pipe_table(lines: &[&str]) -> Option<(String, usize)> {
+    fn cells(line: &str) -> Option<Vec<&str>> {
+        let line = line.trim().strip_prefix('|')?;
+        let line = line.strip_suffix('|').unwrap_or(line);
+        Some(line.split('|').map(str::trim).collect())
+    }
+
+    let header = cells(lines.first()?)?;
+    let delimiter = cells(lines.get(1)?)?;
+    let is_delimiter = delimiter
+        .iter()
+        .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':')));
+    if !is_delimiter || delimiter.len() != header.len() {
+        return None;
+    }
+
+    let mut rows = vec![];
+    for line in &lines[2..] {
+        let Some(row) = cells(line) else {
+            break;
+        };
+        rows.push(row);
+    }
+
+    let convert = |row: &[&str]| {
+        let row = row
+            .iter()
+            .map(|cell| format!("[{}]", markdown_inline(cell)));
+        row.collect::<Vec<_>>().join(", ")
+    };
+    let mut table = format!("#table(\n  columns: {},\n", header.len());
+    let _ = writeln!(table, "  table.header({}),", convert(&header));
+    for row in &rows {
+        let _ = writeln!(table, "  {},", convert(row));
+    }
+    table.push(')');
+    Some((table, rows.len() + 2))
+}
+
+/// Splits CSV or TSV into rows of the same number of fields, if there are at
+/// least two rows and two fields in each row.
+fn csv_rows(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines = text.lines().filter(|line| !line.trim().is_empty());
+    let lines = lines.collect::<Vec<_>>();
+    if lines.len() < 2 {
+        return None;
+    }
+    let sep = if lines[0].contains('\t') { '\t' } else { ',' };
+
+    let rows = lines.iter().map(|line| csv_fields(line, sep));
+    let rows = rows.collect::<Vec<_>>();
+    let cols = rows[0].len();
+    (cols >= 2 && rows.iter().all(|row| row.len() == cols)).then_some(rows)
+}
+
+/// Splits a line of CSV into its fields, which may be quoted.
+fn csv_fields(line: &str, sep: char) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.iter().map(|field| field.trim().to_owned()).collect()
+}
+
+fn convert_csv(text: &str) -> Option<String> {
+    let rows = csv_rows(text)?;
+    let convert = |row: &[String]| {
+        let row = row.iter().map(|cell| format!("[{}]", escape(cell)));
+        row.collect::<Vec<_>>().join(", ")
+    };
+
+    let mut table = format!("#table(\n  columns: {},\n", rows[0].len());
+    let _ = writeln!(table, "  table.header({}),", convert(&rows[0]));
+    for row in &rows[1..] {
+        let _ = writeln!(table, "  {},", convert(row));
+    }
+    table.push(')');
+    Some(table)
+}
+
+/// The LaTeX commands converted to Typst symbols or functions.
+const LATEX_COMMANDS: &[(&str, &str)] = &[
+    ("cdot", "dot"),
+    ("times", "times"),
+    ("div", "div"),
+    ("pm", "plus.minus"),
+    ("leq", "<="),
+    ("le", "<="),
+    ("geq", ">="),
+    ("ge", ">="),
+    ("neq", "!="),
+    ("ne", "!="),
+    ("approx", "approx"),
+    ("equiv", "equiv"),
+    ("infty", "infinity"),
+    ("to", "->"),
+    ("rightarrow", "->"),
+    ("leftarrow", "<-"),
+    ("Rightarrow", "=>"),
+    ("in", "in"),
+    ("subset", "subset"),
+    ("cup", "union"),
+    ("cap", "sect"),
+    ("sum", "sum"),
+    ("prod", "product"),
+    ("int", "integral"),
+    ("partial", "diff"),
+    ("nabla", "nabla"),
+    ("ldots", "dots"),
+    ("cdots", "dots.c"),
+    ("left", ""),
+    ("right", ""),
+    (",", "thin"),
+    (";", "med"),
+    ("quad", "quad"),
+];
+
+/// The LaTeX commands taking one argument, converted to Typst functions.
+const LATEX_FUNCS: &[(&str, &str)] = &[
+    ("sqrt", "sqrt"),
+    ("mathbb", "bb"),
+    ("mathbf", "bold"),
+    ("mathcal", "cal"),
+    ("mathrm", "upright"),
+    ("hat", "hat"),
+    ("bar", "overline"),
+    ("overline", "overline"),
+    ("vec", "arrow"),
+    ("tilde", "tilde"),
+];
+
+/// Converts LaTeX math, which is either inline, e.g. `$x$` and `\(x\)`, or
+/// display, e.g. `$$x$$`, `\[x\]`, and `\begin{equation}x\end{equation}`.
+fn convert_latex(text: &str) -> String {
+    let text = text.trim();
+    let delims = [
+        ("$$", "$$", true),
+        ("\\[", "\\]", true),
+        ("\\begin{equation*}", "\\end{equation*}", true),
+        ("\\begin{equation}", "\\end{equation}", true),
+        ("$", "$", false),
+        ("\\(", "\\)", false),
+    ];
+    let (body, block) = delims
+        .iter()
+        .find_map(|(open, close, block)| {
+            let body = text.strip_prefix(open)?.strip_suffix(close)?;
+            Some((body, *block))
+        })
+        .unwrap_or((text, false));
+
+    let body = latex_math(body.trim());
+    match block {
+        true => format!("$ {body} $"),
+        false => format!("${body}$"),
+    }
+}
+
+/// Converts the body of LaTeX math.
+fn latex_math(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(rest.len())
+                    .max(1.min(rest.len()));
+                let (name, after) = rest.split_at(len);
+                rest = after;
+                if name == "frac" {
+                    let (num, after) = latex_group(rest);
+                    let (den, after) = latex_group(after);
+                    rest = after;
+                    push_token(&mut out, &format!("({num})/({den})"));
+                } else if name == "text" {
+                    let (text, after) = latex_raw_group(rest);
+                    rest = after;
+                    push_token(&mut out, &format!("{text:?}"));
+                } else if let Some((_, func)) = LATEX_FUNCS.iter().find(|(n, _)| *n == name) {
+                    let (arg, after) = latex_group(rest);
+                    rest = after;
+                    push_token(&mut out, &format!("{func}({arg})"));
+                } else if let Some((_, sym)) = LATEX_COMMANDS.iter().find(|(n, _)| *n == name) {
+                    push_token(&mut out, sym);
+                } else {
+                    // The Greek letters and the most symbols share the names.
+                    push_token(&mut out, name);
+                }
+            }
+            '^' | '_' => {
+                let (arg, after) = latex_group(rest);
+                rest = after;
+                let is_ident = arg.chars().all(|c| c.is_ascii_alphabetic() || c == '.');
+                let arg = if arg.chars().count() > 1 && !is_ident {
+                    format!("({arg})")
+                } else {
+                    arg
+                };
+                out.push(c);
+                out.push_str(&arg);
+            }
+            '{' | '}' => {}
+            c if c.is_ascii_alphabetic() => push_token(&mut out, &c.to_string()),
+            c => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Pushes a token, separating it from the previous letters, e.g. `a b` for
+/// `ab`, which would be a single identifier in Typst.
+fn push_token(out: &mut String, token: &str) {
+    let ident = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == ')');
+    if ident(out.chars().last()) && token.starts_with(|c: char| c.is_alphanumeric()) {
+        out.push(' ');
+    }
+    out.push_str(token);
+}
+
+/// Gets the converted argument of a command, which is either a group in
+/// braces or a single character.
+fn latex_group(text: &str) -> (String, &str) {
+    let (raw, rest) = latex_raw_group(text);
+    (latex_math(raw), rest)
+}
+
+fn latex_raw_group(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    if let Some(inner) = text.strip_prefix('{') {
+        let mut depth = 1;
+        for (idx, c) in inner.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return (&inner[..idx], &inner[idx + 1..]);
+            }
+        }
+        return (inner, "");
+    }
+    // A command is an argument as a whole, e.g. `\alpha` in `x^\alpha`.
+    if let Some(name) = text.strip_prefix('\\') {
+        let len = name
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(name.len())
+            .max(1.min(name.len()));
+        return text.split_at(len + 1);
+    }
+    match text.chars().next() {
+        Some(c) => text.split_at(c.len_utf8()),
+        None => ("", ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(text: &str) -> (Option<PasteFormat>, String) {
+        match convert_paste(text, None) {
+            Some(converted) => (Some(converted.format), converted.text),
+            None => (None, String::new()),
+        }
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("# Title"), Some(PasteFormat::Markdown));
+        assert_eq!(
+            detect_format("See [docs](https://typst.app)."),
+            Some(PasteFormat::Markdown)
+        );
+        assert_eq!(detect_format("$x^2$"), Some(PasteFormat::Latex));
+        assert_eq!(detect_format("a,b\n1,2"), Some(PasteFormat::Csv));
+        assert_eq!(detect_format("a\tb\n1\t2\n"), Some(PasteFormat::Csv));
+        assert_eq!(detect_format("Hello, world.\nBye."), None);
+        assert_eq!(detect_format("#hashtag"), None);
+        assert_eq!(
+            detect_format("- apples, pears\n- milk, eggs"),
+            Some(PasteFormat::Markdown)
+        );
+        assert_eq!(detect_format("First, we mix it.\nThen, we bake it."), None);
+    }
+
+    #[test]
+    fn test_convert_markdown() {
+        let text =
+            "## Intro\n\nSome **bold** and *em* with `code`, a [link](https://x.org) and #1.\n\
+                    - item\n  - nested\n1. first\n\n```rust\nlet x = *y;\n```\n---";
+        assert_eq!(
+            convert(text),
+            (
+                Some(PasteFormat::Markdown),
+                "== Intro\n\nSome *bold* and _em_ with `code`, a #link(\"https://x.org\")[link] \
+                 and \\#1.\n- item\n  - nested\n+ first\n\n```rust\nlet x = *y;\n```\n\
+                 #line(length: 100%)"
+                    .to_owned()
+            )
+        );
+
+        assert_eq!(
+            convert_markdown("[x] ~1 // not a comment, see https://typst.app"),
+            "\\[x\\] \\~1 \\// not a comment, see https://typst.app"
+        );
+
+        let image = convert_markdown("![a cat](cat.png)");
+        assert_eq!(image, "#image(\"cat.png\", alt: \"a cat\")");
+    }
+
+    #[test]
+    fn test_convert_pipe_table() {
+        let text = "| a | b |\n|---|:-:|\n| 1 | **2** |\nafter";
+        assert_eq!(
+            convert_markdown(text),
+            "#table(\n  columns: 2,\n  table.header([a], [b]),\n  [1], [*2*],\n)\nafter"
+        );
+    }
+
+    #[test]
+    fn test_convert_csv() {
+        let text = "name,\"note, quoted\"\nalice,#1\n";
+        assert_eq!(
+            convert(text),
+            (
+                Some(PasteFormat::Csv),
+                "#table(\n  columns: 2,\n  table.header([name], [note, quoted]),\n  \
+                 [alice], [\\#1],\n)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_convert_latex() {
+        assert_eq!(
+            convert("$\\frac{a+1}{2} \\leq \\alpha^{2n}$").1,
+            "$(a+1)/(2) <= alpha^(2 n)$"
+        );
+        assert_eq!(
+            convert("\\[ \\sum_{i=1}^{n} x_i \\cdot \\sqrt{ab} \\]").1,
+            "$ sum_(i=1)^n x_i dot sqrt(a b) $"
+        );
+        assert_eq!(
+            convert("$\\mathbb{R} \\text{ if } x \\in A$").1,
+            "$bb(R) \" if \" x in A$"
+        );
+        assert_eq!(
+            convert("$x^\\alpha + \\sum_{i=1}^\\infty y_\\pm$").1,
+            "$x^alpha + sum_(i=1)^infinity y_plus.minus$"
+        );
+    }
+}
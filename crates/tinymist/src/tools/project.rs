@@ -0,0 +1,458 @@
+//! The dependency graph of a project, and the resolution of its files to the
+//! entries.
+//!
+//! A project may have multiple entries, e.g. the books of a series, and a file
+//! of the project is compiled in the context of the entry importing or
+//! including it, directly or transitively.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use typst::syntax::{ast, parse, SyntaxNode};
+use typst_ts_core::{path::PathClean, ImmutPath};
+
+/// The functions reading a resource from the path in their first argument.
+const RESOURCE_FUNCS: &[&str] = &[
+    "image",
+    "read",
+    "json",
+    "csv",
+    "yaml",
+    "toml",
+    "xml",
+    "cbor",
+    "bibliography",
+    "plugin",
+];
+
+/// How a file depends on another file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+    /// The file is imported by `import`.
+    Import,
+    /// The file is included by `include`.
+    Include,
+    /// The file is read as a resource, e.g. by `image` or `read`.
+    Resource,
+}
+
+/// A dependency of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    /// The path of the file depended on.
+    pub path: PathBuf,
+    /// How the file is depended on.
+    pub kind: DependencyKind,
+}
+
+/// Why a file is compiled as the entry of the focusing file.
+///
+/// The reasons to detect an entry are ordered by preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryReason {
+    /// The entry is detected since it prints a bibliography.
+    Bibliography,
+    /// The entry is detected since it prints an outline.
+    Outline,
+    /// The entry is detected since it isn't imported or included by any other
+    /// file.
+    NotImported,
+    /// The entry is pinned by the user.
+    Pinned,
+    /// The entry is configured by `entries` or `typstExtraArgs`.
+    Configured,
+    /// The entry is configured for the folder of the file by `entryOverrides`.
+    Overridden,
+    /// The file itself is compiled.
+    Focused,
+}
+
+/// The entry resolved for the focusing file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryStatus {
+    /// The focusing file.
+    pub focusing: Option<PathBuf>,
+    /// The entry compiled for the file.
+    pub entry: PathBuf,
+    /// Why the entry is compiled.
+    pub reason: EntryReason,
+}
+
+/// The dependency graph of the source files reachable from the entries of a
+/// project. The absolute paths in the sources are resolved against the root.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    /// The entries of the project.
+    pub entries: Vec<PathBuf>,
+    /// The root of the project.
+    pub root: Option<PathBuf>,
+    /// The dependencies of the source files, by their paths.
+    pub files: BTreeMap<PathBuf, Vec<Dependency>>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph of the files reachable from the entries, where `load`
+    /// reads the text of a source file.
+    pub fn build(
+        entries: &[ImmutPath],
+        root: Option<&Path>,
+        load: impl Fn(&Path) -> Option<String>,
+    ) -> Self {
+        let mut graph = Self {
+            entries: entries.iter().map(|entry| entry.to_path_buf()).collect(),
+            root: root.map(Path::to_owned),
+            files: BTreeMap::new(),
+        };
+        let entries = graph.entries.clone();
+        graph.visit(entries, &load);
+        graph
+    }
+
+    /// Updates the dependencies of a changed file in the graph, returning
+    /// whether they are changed. The file is parsed again unless its `syntax`
+    /// is given, e.g. by an opened document. The new source files depended on
+    /// are visited as well.
+    pub fn update(
+        &mut self,
+        path: &Path,
+        syntax: Option<&SyntaxNode>,
+        load: impl Fn(&Path) -> Option<String>,
+    ) -> bool {
+        let Some(deps) = self.files.get(path) else {
+            return false;
+        };
+        let new_deps = match syntax {
+            Some(syntax) => self.syntax_dependencies(path, syntax),
+            None => self.dependencies(path, &load),
+        };
+        if *deps == new_deps {
+            return false;
+        }
+
+        self.files.insert(path.to_owned(), new_deps);
+        // The files which are no longer reachable are kept, which doesn't
+        // change the owners of the reachable files.
+        let entries = self.entries.clone();
+        self.visit(entries, &load);
+        true
+    }
+
+    /// Gets the first entry whose dependencies contain a file, directly or
+    /// transitively, or the entry itself.
+    pub fn owner(&self, path: &Path) -> Option<&Path> {
+        if let Some(entry) = self.entries.iter().find(|entry| *entry == path) {
+            return Some(entry);
+        }
+        let entries = self.entries.iter();
+        let mut owners = entries.filter(|entry| self.reachable(entry).contains(path));
+        owners.next().map(PathBuf::as_path)
+    }
+
+    /// Gets the entries depending on a file, directly or transitively.
+    pub fn affected_entries(&self, path: &Path) -> Vec<&Path> {
+        let entries = self.entries.iter();
+        let entries =
+            entries.filter(|entry| *entry == path || self.reachable(entry).contains(path));
+        entries.map(PathBuf::as_path).collect()
+    }
+
+    /// Detects the likely entry of a file among the entries of the graph, i.e.
+    /// an entry depending on the file, or the file itself, which isn't
+    /// depended on by any other entry. The ones printing a bibliography or an
+    /// outline are preferred.
+    pub fn detect_entry(
+        &self,
+        path: &Path,
+        load: impl Fn(&Path) -> Option<String>,
+    ) -> Option<(&Path, EntryReason)> {
+        let depended = self.entries.iter().flat_map(|entry| self.reachable(entry));
+        let depended = depended.collect::<HashSet<_>>();
+
+        let entries = self.entries.iter().filter(|entry| {
+            !depended.contains(entry.as_path())
+                && (*entry == path || self.reachable(entry).contains(path))
+        });
+        let entries = entries.map(|entry| {
+            let reason = load(entry).map_or(EntryReason::NotImported, |text| entry_reason(&text));
+            (entry.as_path(), reason)
+        });
+        entries.min_by_key(|&(entry, reason)| (reason, entry))
+    }
+
+    /// Gets the files reachable from a file in the graph, excluding itself.
+    fn reachable(&self, from: &Path) -> HashSet<&Path> {
+        let mut reachable = HashSet::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(file) = queue.pop_front() {
+            for dep in self.files.get(file).into_iter().flatten() {
+                if reachable.insert(dep.path.as_path()) {
+                    queue.push_back(&dep.path);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Visits the source files reachable from some files which are not in the
+    /// graph yet.
+    fn visit(&mut self, from: Vec<PathBuf>, load: &impl Fn(&Path) -> Option<String>) {
+        let mut queue = VecDeque::from(from);
+        while let Some(file) = queue.pop_front() {
+            let deps = match self.files.get(&file) {
+                Some(deps) => deps.clone(),
+                None => {
+                    let deps = self.dependencies(&file, load);
+                    self.files.insert(file, deps.clone());
+                    deps
+                }
+            };
+            let sources = deps.into_iter().filter(|dep| {
+                dep.kind != DependencyKind::Resource && !self.files.contains_key(&dep.path)
+            });
+            queue.extend(sources.map(|dep| dep.path));
+        }
+    }
+
+    /// Reads the dependencies of a source file.
+    fn dependencies(
+        &self,
+        file: &Path,
+        load: &impl Fn(&Path) -> Option<String>,
+    ) -> Vec<Dependency> {
+        match load(file) {
+            Some(text) => self.syntax_dependencies(file, &parse(&text)),
+            None => vec![],
+        }
+    }
+
+    /// Gets the dependencies of a parsed source file.
+    fn syntax_dependencies(&self, file: &Path, syntax: &SyntaxNode) -> Vec<Dependency> {
+        let mut deps = vec![];
+        collect_deps(syntax, &mut deps);
+
+        let root = self.root.as_deref();
+        let deps = deps.into_iter().filter_map(|(dep, kind)| {
+            let path = resolve_dep(file, &dep, root)?;
+            Some(Dependency { path, kind })
+        });
+        let mut res: Vec<Dependency> = vec![];
+        for dep in deps {
+            if !res.contains(&dep) {
+                res.push(dep);
+            }
+        }
+        res
+    }
+}
+
+/// Lists the source files which may be the entry of a file, i.e. the file
+/// itself and the source files in its folder and the ancestor folders within
+/// the root.
+pub fn entry_candidates(path: &Path, root: Option<&Path>) -> Vec<ImmutPath> {
+    let mut candidates = vec![ImmutPath::from(path)];
+    for dir in path.ancestors().skip(1) {
+        if root.is_some_and(|root| !dir.starts_with(root)) {
+            break;
+        }
+
+        let files = std::fs::read_dir(dir).into_iter().flatten().flatten();
+        let files = files.map(|entry| entry.path()).filter(|file| {
+            file != path && file.extension().is_some_and(|ext| ext == "typ") && file.is_file()
+        });
+        let mut files = files.collect::<Vec<_>>();
+        files.sort();
+        candidates.extend(files.into_iter().map(ImmutPath::from));
+
+        if root.map_or(true, |root| dir == root) {
+            break;
+        }
+    }
+    candidates
+}
+
+/// Gets the reason to detect a source file as an entry by the functions it
+/// calls.
+fn entry_reason(text: &str) -> EntryReason {
+    fn calls(node: &SyntaxNode, name: &str) -> bool {
+        let callee = node.cast::<ast::FuncCall>().map(|call| call.callee());
+        if matches!(callee, Some(ast::Expr::Ident(ident)) if ident.get().as_str() == name) {
+            return true;
+        }
+        node.children().any(|child| calls(child, name))
+    }
+
+    let root = parse(text);
+    if calls(&root, "bibliography") {
+        EntryReason::Bibliography
+    } else if calls(&root, "outline") {
+        EntryReason::Outline
+    } else {
+        EntryReason::NotImported
+    }
+}
+
+/// Collects the paths imported, included, or read by a syntax tree.
+fn collect_deps(node: &SyntaxNode, deps: &mut Vec<(String, DependencyKind)>) {
+    let mut push = |expr: Option<ast::Expr>, kind| match expr {
+        Some(ast::Expr::Str(path)) => deps.push((path.get().to_string(), kind)),
+        // The bibliography may be read from multiple files.
+        Some(ast::Expr::Array(paths)) => {
+            for path in paths.items() {
+                if let ast::ArrayItem::Pos(ast::Expr::Str(path)) = path {
+                    deps.push((path.get().to_string(), kind));
+                }
+            }
+        }
+        _ => {}
+    };
+
+    if let Some(import) = node.cast::<ast::ModuleImport>() {
+        push(Some(import.source()), DependencyKind::Import);
+    } else if let Some(include) = node.cast::<ast::ModuleInclude>() {
+        push(Some(include.source()), DependencyKind::Include);
+    } else if let Some(call) = node.cast::<ast::FuncCall>() {
+        let callee = match call.callee() {
+            ast::Expr::Ident(ident) => Some(ident.get().clone()),
+            _ => None,
+        };
+        if callee.is_some_and(|callee| RESOURCE_FUNCS.contains(&callee.as_str())) {
+            let path = call.args().items().find_map(|arg| match arg {
+                ast::Arg::Pos(expr) => Some(expr),
+                _ => None,
+            });
+            push(path, DependencyKind::Resource);
+        }
+    }
+
+    for child in node.children() {
+        collect_deps(child, deps);
+    }
+}
+
+/// Resolves the path of a dependency of a file, skipping the packages.
+fn resolve_dep(file: &Path, dep: &str, root: Option<&Path>) -> Option<PathBuf> {
+    if dep.starts_with('@') {
+        return None;
+    }
+    let path = match dep.strip_prefix('/') {
+        Some(dep) => root?.join(dep),
+        None => file.parent()?.join(dep),
+    };
+    Some(path.clean())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_dependency_graph() {
+        let mut files = HashMap::from([
+            ("/book/a.typ", "#include \"chapters/one.typ\""),
+            (
+                "/book/b.typ",
+                "#import \"/shared.typ\": *\n#include \"chapters/two.typ\"",
+            ),
+            (
+                "/book/chapters/one.typ",
+                "#import \"../shared.typ\": *\n#image(\"fig.png\")",
+            ),
+            (
+                "/book/chapters/two.typ",
+                "#import \"@preview/pkg:0.1.0\": *\n#bibliography((\"a.bib\", \"b.bib\"))",
+            ),
+            ("/book/shared.typ", "#let x = 1"),
+        ]);
+        let entries: Vec<ImmutPath> = vec![
+            Path::new("/book/a.typ").into(),
+            Path::new("/book/b.typ").into(),
+        ];
+        let root = Some(Path::new("/book"));
+
+        let load = |files: &HashMap<&str, &str>, path: &Path| {
+            files.get(path.to_str()?).map(|text| text.to_string())
+        };
+        let mut graph = DependencyGraph::build(&entries, root, |path| load(&files, path));
+        let owner = |graph: &DependencyGraph, path: &str| {
+            let owner = graph.owner(Path::new(path));
+            owner.map(|owner| owner.to_string_lossy().into_owned())
+        };
+        assert_eq!(owner(&graph, "/book/b.typ").as_deref(), Some("/book/b.typ"));
+        assert_eq!(
+            owner(&graph, "/book/chapters/one.typ").as_deref(),
+            Some("/book/a.typ")
+        );
+        assert_eq!(
+            owner(&graph, "/book/chapters/b.bib").as_deref(),
+            Some("/book/b.typ")
+        );
+        assert_eq!(owner(&graph, "/book/other.typ"), None);
+        assert_eq!(
+            graph.affected_entries(Path::new("/book/shared.typ")),
+            vec![Path::new("/book/a.typ"), Path::new("/book/b.typ")]
+        );
+        assert_eq!(
+            graph.files[Path::new("/book/chapters/one.typ")][1],
+            Dependency {
+                path: PathBuf::from("/book/chapters/fig.png"),
+                kind: DependencyKind::Resource,
+            }
+        );
+
+        // A chapter moved to another book changes its owner.
+        files.insert("/book/a.typ", "");
+        files.insert(
+            "/book/b.typ",
+            "#include \"chapters/one.typ\"\n#include \"chapters/two.typ\"",
+        );
+        let update = |graph: &mut DependencyGraph, path: &str, syntax: Option<&SyntaxNode>| {
+            graph.update(Path::new(path), syntax, |path| load(&files, path))
+        };
+        assert!(update(&mut graph, "/book/a.typ", None));
+        // The syntax of an opened file is reused.
+        let syntax = parse(files["/book/b.typ"]);
+        assert!(update(&mut graph, "/book/b.typ", Some(&syntax)));
+        assert!(!update(&mut graph, "/book/shared.typ", None));
+        assert_eq!(
+            owner(&graph, "/book/chapters/one.typ").as_deref(),
+            Some("/book/b.typ")
+        );
+    }
+
+    #[test]
+    fn test_detect_entry() {
+        let files = HashMap::from([
+            ("/thesis/notes.typ", "#import \"macros.typ\": *"),
+            (
+                "/thesis/main.typ",
+                "#outline()\n#include \"chapters/intro.typ\"\n#bibliography(\"refs.bib\")",
+            ),
+            ("/thesis/slides.typ", "#include \"chapters/intro.typ\""),
+            ("/thesis/chapters/intro.typ", "#import \"../macros.typ\": *"),
+            ("/thesis/macros.typ", "#let x = 1"),
+        ]);
+        let load = |path: &Path| files.get(path.to_str()?).map(|text| text.to_string());
+        let candidates: Vec<ImmutPath> = files.keys().map(|path| Path::new(path).into()).collect();
+        let graph = DependencyGraph::build(&candidates, Some(Path::new("/thesis")), load);
+        let detect = |path: &str| {
+            let (entry, reason) = graph.detect_entry(Path::new(path), load)?;
+            Some((entry.to_string_lossy().into_owned(), reason))
+        };
+
+        let main = ("/thesis/main.typ".to_owned(), EntryReason::Bibliography);
+        assert_eq!(detect("/thesis/chapters/intro.typ"), Some(main.clone()));
+        assert_eq!(detect("/thesis/macros.typ"), Some(main));
+        assert_eq!(
+            detect("/thesis/slides.typ"),
+            Some(("/thesis/slides.typ".to_owned(), EntryReason::NotImported))
+        );
+        assert_eq!(entry_reason("#outline(title: none)"), EntryReason::Outline);
+    }
+}
@@ -0,0 +1,131 @@
+//! Statistics of the server, e.g. the sizes of the caches and the timings of
+//! the requests, which help to diagnose performance issues on large projects.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tinymist_query::analysis::AnalysisCacheStats;
+
+/// The number of the latest samples kept for each kind of request.
+const MAX_SAMPLES: usize = 1024;
+
+/// The durations of the latest requests, by their methods or commands.
+#[derive(Debug, Default)]
+pub struct RequestTimings {
+    samples: HashMap<String, VecDeque<Duration>>,
+}
+
+impl RequestTimings {
+    /// Records the duration of a request.
+    pub fn record(&mut self, method: &str, duration: Duration) {
+        let samples = match self.samples.get_mut(method) {
+            Some(samples) => samples,
+            None => self.samples.entry(method.to_owned()).or_default(),
+        };
+        if samples.len() >= MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+    }
+
+    /// Summarizes the recorded durations, by the methods or commands.
+    pub fn report(&self) -> BTreeMap<String, TimingStats> {
+        let samples = self.samples.iter();
+        let samples = samples.filter(|(_, samples)| !samples.is_empty());
+        samples
+            .map(|(method, samples)| (method.clone(), TimingStats::new(samples)))
+            .collect()
+    }
+}
+
+/// The percentiles of the durations of a kind of request, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingStats {
+    /// The number of the samples.
+    pub count: usize,
+    /// The median duration.
+    pub p50: f64,
+    /// The 90th percentile of the durations.
+    pub p90: f64,
+    /// The 99th percentile of the durations.
+    pub p99: f64,
+    /// The longest duration.
+    pub max: f64,
+}
+
+impl TimingStats {
+    /// Summarizes non-empty samples by the nearest-rank percentiles.
+    fn new(samples: &VecDeque<Duration>) -> Self {
+        let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.;
+        let percentile = |p: usize| {
+            let rank = (p * sorted.len()).div_ceil(100);
+            ms(sorted[rank.max(1) - 1])
+        };
+        Self {
+            count: sorted.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: ms(sorted[sorted.len() - 1]),
+        }
+    }
+}
+
+/// The statistics of a compiler, i.e. the primary one or a dedicated one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilerStats {
+    /// The number of the files tracked by the compiler, i.e. the dependencies
+    /// of the last compilation.
+    pub tracked_files: usize,
+    /// The estimated memory usage of the compiler in bytes, by its parts.
+    pub estimated_memory_usage: HashMap<String, usize>,
+    /// The sizes of the analysis caches.
+    pub caches: AnalysisCacheStats,
+}
+
+/// The statistics of the server, reported by `tinymist.serverStats` and the
+/// `tinymist/serverStats` notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStats {
+    /// The statistics of the compilers, by their names.
+    pub compilers: HashMap<String, CompilerStats>,
+    /// The timings of the requests, by their methods or commands.
+    pub timings: BTreeMap<String, TimingStats>,
+}
+
+/// The notification of the statistics of the server, sent periodically if
+/// `serverStatsInterval` is set.
+pub struct TinymistServerStats;
+
+impl lsp_types::notification::Notification for TinymistServerStats {
+    type Params = ServerStats;
+    const METHOD: &'static str = "tinymist/serverStats";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_timings() {
+        let mut timings = RequestTimings::default();
+        for ms in (1..=100).rev() {
+            timings.record("textDocument/hover", Duration::from_millis(ms));
+        }
+        for _ in 0..MAX_SAMPLES + 1 {
+            timings.record("tinymist.getServerInfo", Duration::from_millis(2));
+        }
+
+        let report = timings.report();
+        let hover = &report["textDocument/hover"];
+        assert_eq!((hover.count, hover.p50, hover.p90), (100, 50., 90.));
+        assert_eq!((hover.p99, hover.max), (99., 100.));
+        assert_eq!(report["tinymist.getServerInfo"].count, MAX_SAMPLES);
+    }
+}
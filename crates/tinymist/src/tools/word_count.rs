@@ -20,6 +20,23 @@ pub struct WordsCount {
     /// Number of CJK characters.
     #[serde(rename = "cjkChars")]
     pub cjk_chars: usize,
+    /// Number of pages.
+    pub pages: usize,
+    /// Estimated reading time in seconds.
+    #[serde(rename = "readingTime")]
+    pub reading_time: usize,
+}
+
+/// The reading speed of the words except CJK characters, in words per minute.
+const WORDS_PER_MINUTE: usize = 230;
+/// The reading speed of CJK characters, in characters per minute.
+const CJK_CHARS_PER_MINUTE: usize = 500;
+
+/// Estimates the reading time in seconds, rounded up.
+fn reading_time(words: usize, cjk_chars: usize) -> usize {
+    let other_words = words.saturating_sub(cjk_chars);
+    let secs = other_words * 60 * CJK_CHARS_PER_MINUTE + cjk_chars * 60 * WORDS_PER_MINUTE;
+    secs.div_ceil(WORDS_PER_MINUTE * CJK_CHARS_PER_MINUTE)
 }
 
 /// Count words in a document.
@@ -86,6 +103,8 @@ pub fn word_count(doc: &Document) -> WordsCount {
         chars,
         spaces,
         cjk_chars,
+        pages: doc.pages.len(),
+        reading_time: reading_time(words, cjk_chars),
     }
 }
 
@@ -300,3 +319,16 @@ impl SpanMapper {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_time() {
+        assert_eq!(reading_time(0, 0), 0);
+        assert_eq!(reading_time(230, 0), 60);
+        assert_eq!(reading_time(1000, 500), 191);
+        assert_eq!(reading_time(1, 0), 1);
+    }
+}
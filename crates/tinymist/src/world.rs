@@ -1,17 +1,31 @@
-use std::{borrow::Cow, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use chrono::{DateTime, Datelike, Local, Utc};
 use comemo::Prehashed;
 use serde::{Deserialize, Serialize};
+use typst::{
+    diag::{EcoString, FileResult, PackageError},
+    foundations::{Bytes, Datetime},
+    syntax::{package::PackageSpec, FileId, Source},
+    text::{Font, FontBook},
+    Library, World,
+};
 use typst_ts_core::{
     config::{compiler::EntryState, CompileFontOpts as FontOptsInner},
     error::prelude::*,
     font::FontResolverImpl,
-    FontResolver, TypstDict,
+    FontResolver, ImmutPath, TypstDict,
 };
 
 use typst_ts_compiler::{
     font::system::SystemFontSearcher,
-    package::http::HttpRegistry,
+    package::{http::HttpRegistry, Registry},
     vfs::{system::SystemAccessModel, Vfs},
     world::CompilerWorld,
 };
@@ -51,6 +65,10 @@ pub struct CompileFontOpts {
     /// Exclude system font paths
     #[serde(rename = "noSystemFonts")]
     pub no_system_fonts: bool,
+
+    /// Exclude the fonts embedded in the binary
+    #[serde(rename = "noEmbeddedFonts", default)]
+    pub no_embedded_fonts: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -110,8 +128,65 @@ impl typst_ts_compiler::world::CompilerFeat for SystemCompilerFeat {
     type FontResolver = SharedFontResolver;
     /// It accesses a physical file system.
     type AccessModel = SystemAccessModel;
-    /// It performs native HTTP requests for fetching package data.
-    type Registry = HttpRegistry;
+    /// It performs native HTTP requests for fetching package data, except the
+    /// overridden packages.
+    type Registry = LspRegistry;
+}
+
+/// The packages resolved to local directories instead of the registry, e.g. the
+/// packages being developed, by their specs with versions, e.g.
+/// `@preview/example:0.1.0`, or without versions, e.g. `@preview/example`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageOverrides(HashMap<String, ImmutPath>);
+
+impl PackageOverrides {
+    /// Creates the overrides from the specs and the directories.
+    pub fn new(overrides: impl IntoIterator<Item = (String, ImmutPath)>) -> Self {
+        Self(overrides.into_iter().collect())
+    }
+
+    /// Gets the directory overriding a package, preferring the override of
+    /// the exact version.
+    pub fn resolve(&self, spec: &PackageSpec) -> Option<&ImmutPath> {
+        let versionless = format!("@{}/{}", spec.namespace, spec.name);
+        self.0
+            .get(&spec.to_string())
+            .or_else(|| self.0.get(&versionless))
+    }
+}
+
+/// The registry of [`LspWorld`], which downloads the packages by HTTP, except
+/// the ones overridden by local directories.
+#[derive(Default)]
+pub struct LspRegistry {
+    inner: HttpRegistry,
+    /// The packages resolved to local directories.
+    pub overrides: PackageOverrides,
+}
+
+impl Deref for LspRegistry {
+    type Target = HttpRegistry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Registry for LspRegistry {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn resolve(&self, spec: &PackageSpec) -> Result<Arc<Path>, PackageError> {
+        match self.overrides.resolve(spec) {
+            Some(dir) => Ok(dir.clone()),
+            None => self.inner.resolve(spec),
+        }
+    }
+
+    fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
+        self.inner.packages()
+    }
 }
 
 /// The compiler world in system environment.
@@ -130,11 +205,16 @@ impl LspWorldBuilder {
         entry: EntryState,
         font_resolver: SharedFontResolver,
         inputs: ImmutDict,
+        package_overrides: PackageOverrides,
     ) -> ZResult<LspWorld> {
+        let registry = LspRegistry {
+            inner: HttpRegistry::default(),
+            overrides: package_overrides,
+        };
         let mut res = CompilerWorld::new_raw(
             entry,
             Vfs::new(SystemAccessModel {}),
-            HttpRegistry::default(),
+            registry,
             font_resolver,
         );
         res.inputs = inputs;
@@ -144,12 +224,119 @@ impl LspWorldBuilder {
     /// Resolve fonts from given options.
     pub(crate) fn resolve_fonts(opts: CompileFontOpts) -> ZResult<FontResolverImpl> {
         let mut searcher = SystemFontSearcher::new();
+        let with_embedded_fonts = if opts.no_embedded_fonts {
+            vec![]
+        } else {
+            typst_assets::fonts().map(Cow::Borrowed).collect()
+        };
         searcher.resolve_opts(FontOptsInner {
             font_profile_cache_path: opts.font_profile_cache_path,
             font_paths: opts.font_paths,
             no_system_fonts: opts.no_system_fonts,
-            with_embedded_fonts: typst_assets::fonts().map(Cow::Borrowed).collect(),
+            with_embedded_fonts,
         })?;
         Ok(searcher.into())
     }
 }
+
+/// A world whose current date is fixed at a time, e.g. the creation timestamp
+/// of a document, so that `datetime.today()` is reproducible.
+pub struct FixedTimeWorld<'a> {
+    base: &'a dyn World,
+    now: DateTime<Utc>,
+}
+
+impl<'a> FixedTimeWorld<'a> {
+    /// Creates a world fixing the current date of the base world at a time.
+    pub fn new(base: &'a dyn World, now: DateTime<Utc>) -> Self {
+        Self { base, now }
+    }
+}
+
+impl World for FixedTimeWorld<'_> {
+    fn library(&self) -> &Prehashed<Library> {
+        self.base.library()
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        self.base.book()
+    }
+
+    fn main(&self) -> Source {
+        self.base.main()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        self.base.source(id)
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.base.file(id)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.base.font(index)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        today_at(self.now, offset)
+    }
+
+    fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
+        self.base.packages()
+    }
+}
+
+/// Gets the date at a time, in the local time zone or in a UTC offset in
+/// hours, the same as `datetime.today()`.
+fn today_at(now: DateTime<Utc>, offset: Option<i64>) -> Option<Datetime> {
+    let date = match offset {
+        None => now.with_timezone(&Local).date_naive(),
+        Some(hours) => (now + chrono::Duration::try_hours(hours)?).date_naive(),
+    };
+    Datetime::from_ymd(
+        date.year(),
+        date.month().try_into().ok()?,
+        date.day().try_into().ok()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_overrides() {
+        let overrides = PackageOverrides::new([
+            (
+                "@preview/example".to_owned(),
+                Path::new("/dev/example").into(),
+            ),
+            (
+                "@preview/example:0.1.0".to_owned(),
+                Path::new("/dev/example-0.1").into(),
+            ),
+        ]);
+        let resolve = |spec: &str| {
+            let spec = spec.parse().unwrap();
+            overrides.resolve(&spec).map(|dir| dir.to_path_buf())
+        };
+
+        assert_eq!(
+            resolve("@preview/example:0.1.0"),
+            Some(PathBuf::from("/dev/example-0.1"))
+        );
+        assert_eq!(
+            resolve("@preview/example:0.2.0"),
+            Some(PathBuf::from("/dev/example"))
+        );
+        assert_eq!(resolve("@preview/other:0.1.0"), None);
+    }
+
+    #[test]
+    fn test_today_at() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(today_at(now, Some(0)), Datetime::from_ymd(2023, 11, 14));
+        assert_eq!(today_at(now, Some(10)), Datetime::from_ymd(2023, 11, 15));
+    }
+}
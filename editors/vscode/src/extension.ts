@@ -75,6 +75,7 @@ async function startClient(context: ExtensionContext): Promise<void> {
         documentSelector: [
             { scheme: "file", language: "typst" },
             { scheme: "untitled", language: "typst" },
+            { scheme: "vscode-vfs", language: "typst" },
         ],
         initializationOptions: config,
         middleware: {
@@ -135,6 +136,10 @@ async function startClient(context: ExtensionContext): Promise<void> {
 
     context.subscriptions.push(
         commands.registerCommand("tinymist.exportCurrentPdf", () => commandExport("Pdf")),
+        commands.registerCommand("tinymist.exportCurrentHtml", () => commandExport("Html")),
+        commands.registerCommand("tinymist.exportCurrentMarkdown", () =>
+            commandExport("Markdown")
+        ),
         commands.registerCommand("tinymist.getCurrentDocumentMetrics", () =>
             commandGetCurrentDocumentMetrics()
         ),
@@ -143,8 +148,12 @@ async function startClient(context: ExtensionContext): Promise<void> {
         commands.registerCommand("typst-lsp.pinMainToCurrent", () => commandPinMain(true)),
         commands.registerCommand("typst-lsp.unpinMain", () => commandPinMain(false)),
         commands.registerCommand("tinymist.showPdf", () => commandShow("Pdf")),
+        commands.registerCommand("tinymist.reflowCurrentParagraph", commandReflowParagraph),
+        commands.registerCommand("tinymist.pasteAsTypst", commandPasteAsTypst),
+        commands.registerCommand("tinymist.insertImage", commandInsertImage),
         commands.registerCommand("tinymist.clearCache", commandClearCache),
         commands.registerCommand("tinymist.runCodeLens", commandRunCodeLens),
+        commands.registerCommand("tinymist.pickLabelTarget", commandPickLabelTarget),
         commands.registerCommand("tinymist.initTemplate", (...args) =>
             commandInitTemplate(context, false, ...args)
         ),
@@ -361,6 +370,116 @@ async function commandPinMain(isPin: boolean): Promise<void> {
     });
 }
 
+async function commandReflowParagraph(): Promise<void> {
+    const activeEditor = window.activeTextEditor;
+    if (activeEditor === undefined || client === undefined) {
+        return;
+    }
+
+    const position = client.code2ProtocolConverter.asPosition(activeEditor.selection.active);
+    await client.sendRequest("workspace/executeCommand", {
+        command: "tinymist.reflowParagraph",
+        arguments: [activeEditor.document.uri.fsPath, position],
+    });
+}
+
+interface ConvertedPaste {
+    format: "markdown" | "latex" | "csv";
+    text: string;
+}
+
+/**
+ * Pastes the clipboard text, converting Markdown, LaTeX math, or CSV to Typst
+ * markup. Other text is pasted as is.
+ */
+async function commandPasteAsTypst(): Promise<void> {
+    const activeEditor = window.activeTextEditor;
+    if (activeEditor === undefined || client === undefined) {
+        return;
+    }
+
+    const text = await vscode.env.clipboard.readText();
+    const converted: ConvertedPaste | null = await client.sendRequest("workspace/executeCommand", {
+        command: "tinymist.convertPaste",
+        arguments: [text],
+    });
+    const pasted = converted?.text ?? text;
+    await activeEditor.edit((builder) => {
+        for (const selection of activeEditor.selections) {
+            builder.replace(selection, pasted);
+        }
+    });
+}
+
+interface DroppedImage {
+    snippet: string;
+    path: string;
+    copiedTo: string | null;
+}
+
+/**
+ * Inserts a figure showing an image picked from the file system. The image is
+ * copied into the assets directory of the project if it lies outside the root.
+ */
+async function commandInsertImage(): Promise<void> {
+    const activeEditor = window.activeTextEditor;
+    if (activeEditor === undefined || client === undefined) {
+        return;
+    }
+
+    const picked = await window.showOpenDialog({
+        canSelectMany: false,
+        filters: { Images: ["png", "jpg", "jpeg", "gif", "svg", "webp"] },
+    });
+    if (picked === undefined || picked.length === 0) {
+        return;
+    }
+
+    const dropped: DroppedImage = await client.sendRequest("workspace/executeCommand", {
+        command: "tinymist.dropImage",
+        arguments: [activeEditor.document.uri.fsPath, picked[0].fsPath],
+    });
+    if (dropped.copiedTo) {
+        window.showInformationMessage(`The image is copied to ${dropped.copiedTo}`);
+    }
+    await activeEditor.edit((builder) => {
+        builder.replace(activeEditor.selection, dropped.snippet);
+    });
+}
+
+interface LabelTargets {
+    uri: string;
+    label: string;
+    targets: {
+        title: string;
+        kind: "heading" | "figure";
+        position: { line: number; character: number };
+    }[];
+}
+
+/**
+ * Picks a heading or figure near an undefined reference and inserts the
+ * missing label after it.
+ */
+async function commandPickLabelTarget(args: LabelTargets): Promise<void> {
+    const items = args.targets.map((target) => ({
+        label: target.title || `(untitled ${target.kind})`,
+        description: `${target.kind}, line ${target.position.line + 1}`,
+        target,
+    }));
+    const picked = await window.showQuickPick(items, {
+        title: `Create label ${args.label.trim()} at`,
+    });
+    if (picked === undefined) {
+        return;
+    }
+
+    const { line, character } = picked.target.position;
+    const edit = new vscode.WorkspaceEdit();
+    edit.insert(Uri.parse(args.uri), new vscode.Position(line, character), args.label);
+    await workspace.applyEdit(edit);
+}
+
 async function commandShowTemplateGallery(context: vscode.ExtensionContext): Promise<void> {
     await activateEditorTool(context, "template-gallery");
 }
@@ -607,6 +726,14 @@ async function commandRunCodeLens(...args: string[]): Promise<void> {
 
             break;
         }
+        case "pin-main": {
+            void vscode.commands.executeCommand(`tinymist.pinMainToCurrent`);
+            break;
+        }
+        case "word-count": {
+            void vscode.commands.executeCommand(`tinymist.showSummary`);
+            break;
+        }
         default: {
             console.error("unknown code lens command", args[0]);
         }
@@ -13,12 +13,17 @@ let words = 0;
 let chars = 0;
 let spaces = 0;
 let cjkChars = 0;
+let pages = 0;
+let readingTime = 0;
 
 interface WordsCount {
     words: number;
     chars: number;
     spaces: number;
     cjkChars: number;
+    pages: number;
+    /** The estimated reading time in seconds. */
+    readingTime: number;
 }
 
 export interface TinymistStatus {
@@ -39,10 +44,13 @@ export function wordCountItemProcess(event: TinymistStatus) {
     statusBarItem = statusBarItem || initWordCountItem();
 
     const updateTooltip = () => {
+        const minutes = Math.ceil(readingTime / 60);
         statusBarItem.tooltip = `${words} ${plural("Word", words)}
 ${chars} ${plural("Character", chars)}
 ${spaces} ${plural("Space", spaces)}
 ${cjkChars} CJK ${plural("Character", cjkChars)}
+${pages} ${plural("Page", pages)}
+${minutes} ${plural("Minute", minutes)} to read
 [Click to show logs]`;
     };
 
@@ -50,6 +58,8 @@ ${cjkChars} CJK ${plural("Character", cjkChars)}
     chars = event.wordsCount?.chars || 0;
     spaces = event.wordsCount?.spaces || 0;
     cjkChars = event.wordsCount?.cjkChars || 0;
+    pages = event.wordsCount?.pages || 0;
+    readingTime = event.wordsCount?.readingTime || 0;
 
     const style: string = "errorStatus";
     if (statusBarItem) {